@@ -0,0 +1,168 @@
+//! Benchmarks of the decoding of tick- and depth-heavy message mixes
+//!
+//! Run with `cargo bench --bench decode`.
+use std::sync::{mpsc, Arc, Mutex};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use twsapi::core::client::ConnStatus;
+use twsapi::core::conflation::TickConflator;
+use twsapi::core::decoder::{parse_number, Decoder};
+use twsapi::core::managed_accounts::ManagedAccounts;
+use twsapi::core::market_rules::MarketRuleCache;
+use twsapi::core::messages::split_fields;
+use twsapi::core::order_tracker::OrderTracker;
+use twsapi::core::quote_cache::QuoteCache;
+use twsapi::core::server_versions::MAX_CLIENT_VER;
+use twsapi::core::smart_components::SmartComponentRegistry;
+use twsapi::core::streamer::RequestSender;
+use twsapi::core::subscription::StreamRegistry;
+
+#[allow(dead_code)]
+#[path = "../examples/test_wrapper/defaults.rs"]
+mod defaults;
+
+use defaults::DefaultWrapper;
+
+/// A mix of top of book ticks, sizes and tick-by-tick quotes as a busy
+/// market data subscription sends them
+const TICK_MIX: [&str; 6] = [
+    "1\u{0}6\u{0}1\u{0}1\u{0}150.25\u{0}100\u{0}3\u{0}",
+    "1\u{0}6\u{0}1\u{0}2\u{0}150.27\u{0}200\u{0}3\u{0}",
+    "2\u{0}6\u{0}1\u{0}8\u{0}1234567\u{0}",
+    "1\u{0}6\u{0}1\u{0}4\u{0}150.26\u{0}300\u{0}1\u{0}",
+    "99\u{0}1\u{0}3\u{0}1672531200\u{0}150.24\u{0}150.26\u{0}100\u{0}200\u{0}0\u{0}",
+    "99\u{0}1\u{0}2\u{0}1672531200\u{0}150.26\u{0}100\u{0}0\u{0}ISLAND\u{0}\u{0}",
+];
+
+/// Market depth updates of a SMART depth subscription, as a deep book sends
+/// them many times a second
+const DEPTH_MIX: [&str; 6] = [
+    "13\u{0}1\u{0}4\u{0}0\u{0}NSDQ\u{0}1\u{0}1\u{0}150.25\u{0}100\u{0}1\u{0}",
+    "13\u{0}1\u{0}4\u{0}1\u{0}ARCA\u{0}1\u{0}1\u{0}150.24\u{0}300\u{0}1\u{0}",
+    "13\u{0}1\u{0}4\u{0}0\u{0}BATS\u{0}1\u{0}0\u{0}150.27\u{0}200\u{0}1\u{0}",
+    "13\u{0}1\u{0}4\u{0}2\u{0}IEX\u{0}0\u{0}1\u{0}150.23\u{0}500\u{0}1\u{0}",
+    "12\u{0}1\u{0}5\u{0}0\u{0}1\u{0}0\u{0}4.5\u{0}12\u{0}",
+    "12\u{0}1\u{0}5\u{0}3\u{0}2\u{0}1\u{0}4.25\u{0}7\u{0}",
+];
+
+/// Number fields as TWS sends them, with an unset double and an empty field
+const NUMBERS: [&str; 10] = [
+    "150.25",
+    "100",
+    "1234567",
+    "1672531200",
+    "150.24",
+    "1.7976931348623157E308",
+    "0",
+    "-2.5",
+    "0.00001",
+    "",
+];
+
+//==================================================================================================
+fn decoder() -> Decoder<DefaultWrapper> {
+    let (_sender, receiver) = mpsc::channel();
+    Decoder::new(
+        Arc::new(Mutex::new(DefaultWrapper::new())),
+        receiver,
+        MAX_CLIENT_VER,
+        Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+        Arc::new(Mutex::new(TickConflator::new())),
+        Arc::new(Mutex::new(SmartComponentRegistry::new())),
+        Arc::new(Mutex::new(MarketRuleCache::new())),
+        RequestSender::new(None),
+        Arc::new(Mutex::new(StreamRegistry::new())),
+        Arc::new(Mutex::new(QuoteCache::new())),
+        Arc::new(Mutex::new(OrderTracker::new())),
+        Arc::new(Mutex::new(ManagedAccounts::new())),
+        Arc::new(Mutex::new(None)),
+        Arc::new(Mutex::new(None)),
+    )
+}
+
+//==================================================================================================
+fn bench_numbers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("numbers");
+    group.throughput(Throughput::Elements(NUMBERS.len() as u64));
+    group.bench_function("parse_number", |b| {
+        b.iter(|| {
+            for field in NUMBERS {
+                black_box(parse_number::<f64>(black_box(field)));
+            }
+        })
+    });
+    group.bench_function("str_parse", |b| {
+        b.iter(|| {
+            for field in NUMBERS {
+                black_box(black_box(field).parse::<f64>().ok());
+            }
+        })
+    });
+    group.finish();
+}
+
+//==================================================================================================
+fn bench_split_fields(c: &mut Criterion) {
+    let messages: Vec<&str> = TICK_MIX.iter().chain(DEPTH_MIX.iter()).copied().collect();
+    let bytes: usize = messages.iter().map(|msg| msg.len()).sum();
+    let mut group = c.benchmark_group("split_fields");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("memchr", |b| {
+        b.iter(|| {
+            for msg in &messages {
+                black_box(split_fields(black_box(msg)));
+            }
+        })
+    });
+    // The splitting split_fields() replaced, for comparison
+    group.bench_function("str_split", |b| {
+        b.iter(|| {
+            for msg in &messages {
+                let mut fields: Vec<&str> = black_box(msg).split('\0').collect();
+                fields.pop();
+                black_box(fields);
+            }
+        })
+    });
+    group.finish();
+}
+
+//==================================================================================================
+fn bench_tick_mix(c: &mut Criterion) {
+    let mut decoder = decoder();
+    let mut group = c.benchmark_group("tick_mix");
+    group.throughput(Throughput::Elements(TICK_MIX.len() as u64));
+    group.bench_function("interpret", |b| {
+        b.iter(|| {
+            for msg in TICK_MIX {
+                decoder.interpret(&split_fields(black_box(msg))).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+//==================================================================================================
+fn bench_depth_mix(c: &mut Criterion) {
+    let mut decoder = decoder();
+    let mut group = c.benchmark_group("depth_mix");
+    group.throughput(Throughput::Elements(DEPTH_MIX.len() as u64));
+    group.bench_function("interpret", |b| {
+        b.iter(|| {
+            for msg in DEPTH_MIX {
+                decoder.interpret(&split_fields(black_box(msg))).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_numbers,
+    bench_split_fields,
+    bench_tick_mix,
+    bench_depth_mix
+);
+criterion_main!(benches);
@@ -1,12 +1,12 @@
 //! Examples of populating fields that define various types of contacts
 
-use twsapi::core::contract::{ComboLeg, Contract, PositionType};
+use twsapi::core::contract::{ComboLeg, Contract, PositionType, Right, SecType};
 
 //==================================================================================================
 pub fn eur_gbp_fx() -> Contract {
     Contract {
         symbol: "EUR".to_string(),
-        sec_type: "CASH".to_string(),
+        sec_type: SecType::Forex,
         currency: "GBP".to_string(),
         exchange: "IDEALPRO".to_string(),
         ..Default::default()
@@ -17,7 +17,7 @@ pub fn eur_gbp_fx() -> Contract {
 pub fn index() -> Contract {
     Contract {
         symbol: "DAX".to_string(),
-        sec_type: "IND".to_string(),
+        sec_type: SecType::Index,
         currency: "EUR".to_string(),
         exchange: "DTB".to_string(),
         ..Default::default()
@@ -28,7 +28,7 @@ pub fn index() -> Contract {
 pub fn cfd() -> Contract {
     Contract {
         symbol: "IBDE30".to_string(),
-        sec_type: "cfd".to_string(),
+        sec_type: SecType::Cfd,
         currency: "EUR".to_string(),
         exchange: "SMART".to_string(),
         ..Default::default()
@@ -39,7 +39,7 @@ pub fn cfd() -> Contract {
 pub fn european_stock() -> Contract {
     Contract {
         symbol: "BMW".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         currency: "EUR".to_string(),
         exchange: "SMART".to_string(),
         primary_exchange: "IBIS".to_string(),
@@ -51,7 +51,7 @@ pub fn european_stock() -> Contract {
 pub fn european_stock2() -> Contract {
     Contract {
         symbol: "NOKIA".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         currency: "EUR".to_string(),
         exchange: "SMART".to_string(),
         primary_exchange: "HEX".to_string(),
@@ -63,11 +63,11 @@ pub fn european_stock2() -> Contract {
 pub fn option_at_ise() -> Contract {
     Contract {
         symbol: "COF".to_string(),
-        sec_type: "OPT".to_string(),
+        sec_type: SecType::Option,
         currency: "USD".to_string(),
         exchange: "ISE".to_string(),
         last_trade_date_or_contract_month: "20190315".to_string(),
-        right: "P".to_string(),
+        right: Right::Put,
         strike: 105.0,
         multiplier: "100".to_string(),
         ..Default::default()
@@ -79,7 +79,7 @@ pub fn bond_with_cusip() -> Contract {
     Contract {
         // enter CUSIP as symbol
         symbol: "912828C57".to_string(),
-        sec_type: "BOND".to_string(),
+        sec_type: SecType::Bond,
         exchange: "SMART".to_string(),
         currency: "USD".to_string(),
         ..Default::default()
@@ -99,7 +99,7 @@ pub fn bond() -> Contract {
 pub fn mutual_fund() -> Contract {
     Contract {
         symbol: "VINIX".to_string(),
-        sec_type: "FUND".to_string(),
+        sec_type: SecType::Fund,
         exchange: "FUNDSERV".to_string(),
         currency: "USD".to_string(),
         ..Default::default()
@@ -110,7 +110,7 @@ pub fn mutual_fund() -> Contract {
 pub fn commodity() -> Contract {
     Contract {
         symbol: "XAUUSD".to_string(),
-        sec_type: "CMDTY".to_string(),
+        sec_type: SecType::Commodity,
         exchange: "SMART".to_string(),
         currency: "USD".to_string(),
         ..Default::default()
@@ -121,7 +121,7 @@ pub fn commodity() -> Contract {
 pub fn usstock() -> Contract {
     Contract {
         symbol: "AMZN".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         currency: "USD".to_string(),
         //In the API side, NASDAQ is always defined as ISLAND in the exchange field
         exchange: "ISLAND".to_string(),
@@ -134,7 +134,7 @@ pub fn usstock() -> Contract {
 pub fn usstock_with_primary_exch() -> Contract {
     Contract {
         symbol: "MSFT".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         currency: "USD".to_string(),
         exchange: "SMART".to_string(),
         //Specify the Primary Exchange attribute to avoid contract ambiguity
@@ -149,7 +149,7 @@ pub fn usstock_with_primary_exch() -> Contract {
 pub fn us_stock_at_smart() -> Contract {
     Contract {
         symbol: "MSFT".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         currency: "USD".to_string(),
         exchange: "SMART".to_string(),
         ..Default::default()
@@ -160,12 +160,12 @@ pub fn us_stock_at_smart() -> Contract {
 pub fn us_option_contract() -> Contract {
     Contract {
         symbol: "GOOG".to_string(),
-        sec_type: "OPT".to_string(),
+        sec_type: SecType::Option,
         exchange: "SMART".to_string(),
         currency: "USD".to_string(),
         last_trade_date_or_contract_month: "20201218".to_string(),
         strike: 1180.0,
-        right: "C".to_string(),
+        right: Right::Call,
         multiplier: "100".to_string(),
         ..Default::default()
     }
@@ -175,12 +175,12 @@ pub fn us_option_contract() -> Contract {
 pub fn option_at_box() -> Contract {
     Contract {
         symbol: "GOOG".to_string(),
-        sec_type: "OPT".to_string(),
+        sec_type: SecType::Option,
         exchange: "BOX".to_string(),
         currency: "USD".to_string(),
         last_trade_date_or_contract_month: "20201218".to_string(),
         strike: 1180.0,
-        right: "C".to_string(),
+        right: Right::Call,
         multiplier: "100".to_string(),
         ..Default::default()
     }
@@ -194,12 +194,12 @@ pub fn option_at_box() -> Contract {
 pub fn option_with_trading_class() -> Contract {
     Contract {
         symbol: "SANT".to_string(),
-        sec_type: "OPT".to_string(),
+        sec_type: SecType::Option,
         exchange: "MEFFRV".to_string(),
         currency: "EUR".to_string(),
         last_trade_date_or_contract_month: "20190621".to_string(),
         strike: 7.5,
-        right: "C".to_string(),
+        right: Right::Call,
         multiplier: "100".to_string(),
         trading_class: "SANEU".to_string(),
         ..Default::default()
@@ -213,7 +213,7 @@ pub fn option_with_local_symbol() -> Contract {
     Contract {
         //Watch out for the spaces within the local symbol!
         local_symbol: "C DBK  DEC 20  1600".to_string(),
-        sec_type: "OPT".to_string(),
+        sec_type: SecType::Option,
         exchange: "DTB".to_string(),
         currency: "EUR".to_string(),
         ..Default::default()
@@ -225,7 +225,7 @@ pub fn option_with_local_symbol() -> Contract {
 pub fn dutch_warrant() -> Contract {
     Contract {
         local_symbol: "B881G".to_string(),
-        sec_type: "IOPT".to_string(),
+        sec_type: SecType::StructuredProduct,
         exchange: "SBF".to_string(),
         currency: "EUR".to_string(),
         ..Default::default()
@@ -238,7 +238,7 @@ pub fn dutch_warrant() -> Contract {
 pub fn simple_future() -> Contract {
     Contract {
         symbol: "ES".to_string(),
-        sec_type: "FUT".to_string(),
+        sec_type: SecType::Future,
         exchange: "GLOBEX".to_string(),
         currency: "USD".to_string(),
         last_trade_date_or_contract_month: "202009".to_string(),
@@ -251,7 +251,7 @@ pub fn simple_future() -> Contract {
 /// attributes such as symbol, currency, strike, etc.
 pub fn future_with_local_symbol() -> Contract {
     Contract {
-        sec_type: "FUT".to_string(),
+        sec_type: SecType::Future,
         exchange: "GLOBEX".to_string(),
         currency: "USD".to_string(),
         local_symbol: "ESU0".to_string(),
@@ -263,7 +263,7 @@ pub fn future_with_local_symbol() -> Contract {
 pub fn future_with_multiplier() -> Contract {
     Contract {
         symbol: "DAX".to_string(),
-        sec_type: "FUT".to_string(),
+        sec_type: SecType::Future,
         exchange: "DTB".to_string(),
         currency: "EUR".to_string(),
         last_trade_date_or_contract_month: "201903".to_string(),
@@ -278,7 +278,7 @@ pub fn wrong_contract() -> Contract {
     Contract {
         symbol: " IJR ".to_string(),
         con_id: 9579976,
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         exchange: "SMART".to_string(),
         currency: "USD".to_string(),
         ..Default::default()
@@ -289,12 +289,12 @@ pub fn wrong_contract() -> Contract {
 pub fn futures_on_options() -> Contract {
     Contract {
         symbol: "ES".to_string(),
-        sec_type: "FOP".to_string(),
+        sec_type: SecType::FuturesOption,
         exchange: "GLOBEX".to_string(),
         currency: "USD".to_string(),
         last_trade_date_or_contract_month: "20190315".to_string(),
         strike: 2900.0,
-        right: "C".to_string(),
+        right: Right::Call,
         multiplier: "50".to_string(),
         ..Default::default()
     }
@@ -309,7 +309,7 @@ pub fn by_isin() -> Contract {
         sec_id: "US45841N1072".to_string(),
         exchange: "SMART".to_string(),
         currency: "USD".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         ..Default::default()
     }
 }
@@ -324,7 +324,7 @@ pub fn by_isin() -> Contract {
 /// in the example below.
 pub fn by_con_id() -> Contract {
     Contract {
-        sec_type: "CASH".to_string(),
+        sec_type: SecType::Forex,
         con_id: 12087792,
         exchange: "IDEALPRO".to_string(),
         ..Default::default()
@@ -340,7 +340,7 @@ pub fn by_con_id() -> Contract {
 pub fn option_for_query() -> Contract {
     Contract {
         symbol: "FISV".to_string(),
-        sec_type: "OPT".to_string(),
+        sec_type: SecType::Option,
         exchange: "SMART".to_string(),
         currency: "USD".to_string(),
         ..Default::default()
@@ -351,7 +351,7 @@ pub fn option_for_query() -> Contract {
 pub fn option_combo_contract() -> Contract {
     Contract {
         symbol: "DBK".to_string(),
-        sec_type: "BAG".to_string(),
+        sec_type: SecType::Combo,
         currency: "EUR".to_string(),
         exchange: "DTB".to_string(),
         combo_legs: vec![
@@ -382,7 +382,7 @@ pub fn option_combo_contract() -> Contract {
 pub fn stock_combo_contract() -> Contract {
     Contract {
         symbol: "IBKR,MCD".to_string(),
-        sec_type: "BAG".to_string(),
+        sec_type: SecType::Combo,
         currency: "USD".to_string(),
         exchange: "SMART".to_string(),
         combo_legs: vec![
@@ -410,7 +410,7 @@ pub fn stock_combo_contract() -> Contract {
 pub fn future_combo_contract() -> Contract {
     Contract {
         symbol: "VIX".to_string(),
-        sec_type: "BAG".to_string(),
+        sec_type: SecType::Combo,
         currency: "USD".to_string(),
         exchange: "CFE".to_string(),
         combo_legs: vec![
@@ -442,7 +442,7 @@ pub fn smart_future_combo_contract() -> Contract {
     Contract {
         symbol: "WTI".to_string(), /* WTI,COIL spread. Symbol can be defined as first leg symbol
                                     * ("WTI") or currency ("USD") */
-        sec_type: "BAG".to_string(),
+        sec_type: SecType::Combo,
         currency: "USD".to_string(),
         exchange: "SMART".to_string(),
         combo_legs: vec![
@@ -469,7 +469,7 @@ pub fn smart_future_combo_contract() -> Contract {
 pub fn inter_cmdty_futures_contract() -> Contract {
     Contract {
         symbol: "CL.BZ".to_string(), //symbol is 'local symbol' of intercommodity spread.
-        sec_type: "BAG".to_string(),
+        sec_type: SecType::Combo,
         currency: "USD".to_string(),
         exchange: "NYMEX".to_string(),
         combo_legs: vec![
@@ -495,7 +495,7 @@ pub fn inter_cmdty_futures_contract() -> Contract {
 //==================================================================================================
 pub fn news_feed_for_query() -> Contract {
     Contract {
-        sec_type: "NEWS".to_string(),
+        sec_type: SecType::News,
         exchange: "BRFG".to_string(), //Briefing Trader
         ..Default::default()
     }
@@ -505,7 +505,7 @@ pub fn news_feed_for_query() -> Contract {
 pub fn brfgbroadtape_news_feed() -> Contract {
     Contract {
         symbol: "BRFG:BRFG_ALL".to_string(),
-        sec_type: "NEWS".to_string(),
+        sec_type: SecType::News,
         exchange: "BRFG".to_string(),
         ..Default::default()
     }
@@ -515,7 +515,7 @@ pub fn brfgbroadtape_news_feed() -> Contract {
 pub fn djnlbroadtape_news_feed() -> Contract {
     Contract {
         symbol: "DJNL:DJNL_ALL".to_string(),
-        sec_type: "NEWS".to_string(),
+        sec_type: SecType::News,
         exchange: "DJNL".to_string(),
         ..Default::default()
     }
@@ -525,7 +525,7 @@ pub fn djnlbroadtape_news_feed() -> Contract {
 pub fn djtopbroadtape_news_feed() -> Contract {
     Contract {
         symbol: "DJTOP:ASIAPAC".to_string(),
-        sec_type: "NEWS".to_string(),
+        sec_type: SecType::News,
         exchange: "DJTOP".to_string(),
         ..Default::default()
     }
@@ -535,7 +535,7 @@ pub fn djtopbroadtape_news_feed() -> Contract {
 pub fn brfupdnbroadtape_news_feed() -> Contract {
     Contract {
         symbol: "BRFUPDN:BRF_ALL".to_string(),
-        sec_type: "NEWS".to_string(),
+        sec_type: SecType::News,
         exchange: "BRFUPDN".to_string(),
         ..Default::default()
     }
@@ -545,7 +545,7 @@ pub fn brfupdnbroadtape_news_feed() -> Contract {
 pub fn cont_fut() -> Contract {
     Contract {
         symbol: "ES".to_string(),
-        sec_type: "CONTFUT".to_string(),
+        sec_type: SecType::ContinuousFuture,
         exchange: "GLOBEX".to_string(),
         ..Default::default()
     }
@@ -555,7 +555,7 @@ pub fn cont_fut() -> Contract {
 pub fn cont_and_expiring_fut() -> Contract {
     Contract {
         symbol: "ES".to_string(),
-        sec_type: "FUT+CONTFUT".to_string(),
+        sec_type: "FUT+CONTFUT".into(),
         exchange: "GLOBEX".to_string(),
         ..Default::default()
     }
@@ -565,7 +565,7 @@ pub fn cont_and_expiring_fut() -> Contract {
 pub fn jefferies_contract() -> Contract {
     Contract {
         symbol: "AAPL".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         exchange: "JEFFALGO".to_string(),
         currency: "USD".to_string(),
         ..Default::default()
@@ -576,7 +576,7 @@ pub fn jefferies_contract() -> Contract {
 pub fn csfbcontract() -> Contract {
     Contract {
         symbol: "IBKR".to_string(),
-        sec_type: "STK".to_string(),
+        sec_type: SecType::Stock,
         exchange: "CSFBALGO".to_string(),
         currency: "USD".to_string(),
         ..Default::default()
@@ -587,7 +587,7 @@ pub fn csfbcontract() -> Contract {
 pub fn usstock_cfd() -> Contract {
     Contract {
         symbol: "IBM".to_string(),
-        sec_type: "cfd".to_string(),
+        sec_type: SecType::Cfd,
         currency: "USD".to_string(),
         exchange: "SMART".to_string(),
         ..Default::default()
@@ -598,7 +598,7 @@ pub fn usstock_cfd() -> Contract {
 pub fn european_stock_cfd() -> Contract {
     Contract {
         symbol: "BMW".to_string(),
-        sec_type: "cfd".to_string(),
+        sec_type: SecType::Cfd,
         currency: "EUR".to_string(),
         exchange: "SMART".to_string(),
         ..Default::default()
@@ -609,7 +609,7 @@ pub fn european_stock_cfd() -> Contract {
 pub fn cash_cfd() -> Contract {
     Contract {
         symbol: "EUR".to_string(),
-        sec_type: "cfd".to_string(),
+        sec_type: SecType::Cfd,
         currency: "USD".to_string(),
         exchange: "SMART".to_string(),
         ..Default::default()
@@ -620,7 +620,7 @@ pub fn cash_cfd() -> Contract {
 pub fn qbalgo_contract() -> Contract {
     Contract {
         symbol: "ES".to_string(),
-        sec_type: "FUT".to_string(),
+        sec_type: SecType::Future,
         exchange: "QBALGO".to_string(),
         currency: "USD".to_string(),
         last_trade_date_or_contract_month: "202009".to_string(),
@@ -10,17 +10,25 @@ use chrono::Utc;
 use log::*;
 
 use twsapi::core::common::{
-    BarData, CommissionReport, DepthMktDataDescription, FaDataType, FamilyCode, HistogramData,
-    HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider, PriceIncrement,
-    RealTimeBar, SmartComponent, TickAttrib, TickAttribBidAsk, TickAttribLast, TickByTickType,
+    BarData, CommissionReport, DataFarmStatus, DepthMktDataDescription, FaDataType, FamilyCode,
+    HistogramData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider,
+    PriceIncrement, RealTimeBar, TickAttrib, TickAttribBidAsk, TickAttribLast, TickByTickType,
     TickType,
 };
 use twsapi::core::contract::{
     Contract, ContractDescription, ContractDetails, DeltaNeutralContract,
 };
+use twsapi::core::display_groups::DisplayGroupContract;
+use twsapi::core::errors::DecodeError;
 use twsapi::core::execution::Execution;
+use twsapi::core::historical::HistoricalSchedule;
+use twsapi::core::news::{NewsArticle, NewsBulletin, NewsHeadline};
 use twsapi::core::order::{Order, OrderState, SoftDollarTier};
+use twsapi::core::quote_cache::{AuctionState, TradingStatus};
+use twsapi::core::smart_components::SmartComponentMap;
 use twsapi::core::wrapper::Wrapper;
+#[cfg(feature = "wsh")]
+use twsapi::core::wsh::WshEvent;
 
 //==================================================================================================
 /// Example implementation of the Wrapper callback trait.  Just logs callback
@@ -40,13 +48,29 @@ impl Default for DefaultWrapper {
 }
 
 impl Wrapper for DefaultWrapper {
-    fn error(&mut self, request_id: i32, error_code: i32, error_string: &str) {
+    fn error(
+        &mut self,
+        request_id: i32,
+        error_code: i32,
+        error_string: &str,
+        advanced_order_reject_json: &str,
+    ) {
         error!(
-            "request_id: {} ,error_code: {} , error_string:{}",
-            request_id, error_code, error_string
+            "request_id: {} ,error_code: {} , error_string:{}, advanced_order_reject_json: {}",
+            request_id, error_code, error_string, advanced_order_reject_json
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn data_farm_status(&mut self, status: &DataFarmStatus) {
+        info!("data_farm_status -- status: {}", status);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn decode_error(&mut self, error: &DecodeError) {
+        error!("decode_error -- {}", error);
+    }
+
     //----------------------------------------------------------------------------------------------
     fn win_error(&mut self, text: &str, last_error: i32) {
         error!("text: {} , last_error:{}", text, last_error);
@@ -74,7 +98,7 @@ impl Wrapper for DefaultWrapper {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn tick_size(&mut self, request_id: i32, tick_type: TickType, size: i32) {
+    fn tick_size(&mut self, request_id: i32, tick_type: TickType, size: BigDecimal) {
         info!(
             "tick_size -- request_id: {}, tick_type: {}, size: {}",
             request_id, tick_type, size
@@ -94,6 +118,22 @@ impl Wrapper for DefaultWrapper {
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn trading_status(&mut self, request_id: i32, trading_status: TradingStatus) {
+        info!(
+            "trading_status -- request_id: {}, trading_status: {}",
+            request_id, trading_status
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn auction_state(&mut self, request_id: i32, auction_state: AuctionState) {
+        info!(
+            "auction_state -- request_id: {}, auction_state: {}",
+            request_id, auction_state
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn tick_string(&mut self, request_id: i32, tick_type: TickType, value: &str) {
         info!(
@@ -142,8 +182,8 @@ impl Wrapper for DefaultWrapper {
         &mut self,
         order_id: i32,
         status: &str,
-        filled: f64,
-        remaining: f64,
+        filled: BigDecimal,
+        remaining: BigDecimal,
         avg_fill_price: f64,
         perm_id: i32,
         parent_id: i32,
@@ -196,7 +236,7 @@ impl Wrapper for DefaultWrapper {
     fn update_portfolio(
         &mut self,
         contract: Contract,
-        position: f64,
+        position: BigDecimal,
         market_price: f64,
         market_value: f64,
         average_cost: f64,
@@ -275,7 +315,7 @@ impl Wrapper for DefaultWrapper {
         operation: i32,
         side: i32,
         price: f64,
-        size: i32,
+        size: BigDecimal,
     ) {
         info!(
             "update_market_depth -- request_id: {}, position: {}, operation: {}, side: {}, price: {}, size: {}",
@@ -292,7 +332,7 @@ impl Wrapper for DefaultWrapper {
         operation: i32,
         side: i32,
         price: f64,
-        size: i32,
+        size: BigDecimal,
         is_smart_depth: bool,
     ) {
         info!(
@@ -302,17 +342,8 @@ impl Wrapper for DefaultWrapper {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn update_news_bulletin(
-        &mut self,
-        msg_id: i32,
-        msg_type: i32,
-        news_message: &str,
-        origin_exch: &str,
-    ) {
-        info!(
-            "update_news_bulletin -- msg_id: {}, msg_type: {}, news_message: {}, origin_exch: {}",
-            msg_id, msg_type, news_message, origin_exch
-        );
+    fn update_news_bulletin(&mut self, bulletin: NewsBulletin) {
+        info!("update_news_bulletin -- bulletin: {}", bulletin);
     }
 
     //----------------------------------------------------------------------------------------------
@@ -325,6 +356,14 @@ impl Wrapper for DefaultWrapper {
         info!("receive_fa -- fa_data: {}, cxml: {}", fa_data, cxml);
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn replace_fa_end(&mut self, request_id: i32, text: &str) {
+        info!(
+            "replace_fa_end -- request_id: {}, text: {}",
+            request_id, text
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn historical_data(&mut self, request_id: i32, bar: BarData) {
         info!(
@@ -429,7 +468,7 @@ impl Wrapper for DefaultWrapper {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn position(&mut self, account: &str, contract: Contract, position: f64, avg_cost: f64) {
+    fn position(&mut self, account: &str, contract: Contract, position: BigDecimal, avg_cost: f64) {
         info!(
             "position -- account: {}, contract: [{}], position: {}, avg_cost: {}",
             account, contract, position, avg_cost
@@ -491,15 +530,15 @@ impl Wrapper for DefaultWrapper {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn display_group_list(&mut self, request_id: i32, groups: &str) {
+    fn display_group_list(&mut self, request_id: i32, groups: Vec<i32>) {
         info!(
-            "display_group_list -- request_id: {}, error_text: {}",
+            "display_group_list -- request_id: {}, groups: {:?}",
             request_id, groups
         );
     }
 
     //----------------------------------------------------------------------------------------------
-    fn display_group_updated(&mut self, request_id: i32, contract_info: &str) {
+    fn display_group_updated(&mut self, request_id: i32, contract_info: DisplayGroupContract) {
         info!(
             "display_group_updated -- request_id: {}, contract_info: {}",
             request_id, contract_info
@@ -513,7 +552,7 @@ impl Wrapper for DefaultWrapper {
         account: &str,
         model_code: &str,
         contract: Contract,
-        pos: f64,
+        pos: BigDecimal,
         avg_cost: f64,
     ) {
         info!(
@@ -554,6 +593,7 @@ impl Wrapper for DefaultWrapper {
         &mut self,
         request_id: i32,
         tick_type: TickType,
+        tick_attrib: i32,
         implied_vol: f64,
         delta: f64,
         opt_price: f64,
@@ -564,10 +604,12 @@ impl Wrapper for DefaultWrapper {
         und_price: f64,
     ) {
         info!(
-            "tick_option_computation -- request_id: {}, tick_type: {}, implied_vol: {}, delta: {}, \
-             opt_price: {}, pv_dividend: {},  gamma: {}, vega: {}, theta: {}, und_price: {}",
+            "tick_option_computation -- request_id: {}, tick_type: {}, tick_attrib: {}, \
+             implied_vol: {}, delta: {}, opt_price: {}, pv_dividend: {},  gamma: {}, vega: {}, \
+             theta: {}, und_price: {}",
             request_id,
             tick_type,
+            tick_attrib,
             implied_vol,
             delta,
             opt_price,
@@ -647,26 +689,17 @@ impl Wrapper for DefaultWrapper {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn tick_news(
-        &mut self,
-        ticker_id: i32,
-        time_stamp: i32,
-        provider_code: &str,
-        article_id: &str,
-        headline: &str,
-        extra_data: &str,
-    ) {
+    fn tick_news(&mut self, ticker_id: i32, headline: NewsHeadline) {
         info!(
-            "tick_news -- ticker_id: {}, time_stamp: {}, provider_code: {}, article_id: {}, \
-             headline: {}, extra_data: {},",
-            ticker_id, time_stamp, provider_code, article_id, headline, extra_data
+            "tick_news -- ticker_id: {}, headline: {}",
+            ticker_id, headline
         );
     }
 
     //----------------------------------------------------------------------------------------------
-    fn smart_components(&mut self, request_id: i32, smart_components: Vec<SmartComponent>) {
+    fn smart_components(&mut self, request_id: i32, smart_components: SmartComponentMap) {
         info!(
-            "smart_components -- request_id: {}, smart_components: {:?}",
+            "smart_components -- request_id: {}, smart_components: {}",
             request_id, smart_components
         );
     }
@@ -691,25 +724,18 @@ impl Wrapper for DefaultWrapper {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn news_article(&mut self, request_id: i32, article_type: i32, article_text: &str) {
+    fn news_article(&mut self, request_id: i32, article: NewsArticle) {
         info!(
-            "news_article -- request_id: {}, article_type: {}, article_text: {}",
-            request_id, article_type, article_text
+            "news_article -- request_id: {}, article: {}",
+            request_id, article
         );
     }
 
     //----------------------------------------------------------------------------------------------
-    fn historical_news(
-        &mut self,
-        request_id: i32,
-        time: &str,
-        provider_code: &str,
-        article_id: &str,
-        headline: &str,
-    ) {
+    fn historical_news(&mut self, request_id: i32, headline: NewsHeadline) {
         info!(
-            "historical_news -- request_id: {}, time: {}, provider_code: {}, article_id: {}, headline: {}",
-            request_id, time, provider_code, article_id, headline
+            "historical_news -- request_id: {}, headline: {}",
+            request_id, headline
         );
     }
 
@@ -745,6 +771,14 @@ impl Wrapper for DefaultWrapper {
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn historical_schedule(&mut self, request_id: i32, schedule: HistoricalSchedule) {
+        info!(
+            "historical_schedule -- request_id: {}, schedule: {}",
+            request_id, schedule
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn reroute_market_data_request(&mut self, request_id: i32, con_id: i32, exchange: &str) {
         info!(
@@ -787,7 +821,7 @@ impl Wrapper for DefaultWrapper {
     fn profit_and_loss_single(
         &mut self,
         request_id: i32,
-        pos: i32,
+        pos: BigDecimal,
         daily_pn_l: f64,
         unrealized_pn_l: f64,
         realized_pn_l: f64,
@@ -840,7 +874,7 @@ impl Wrapper for DefaultWrapper {
         tick_type: TickByTickType,
         time: i64,
         price: f64,
-        size: i32,
+        size: BigDecimal,
         tick_attrib_last: TickAttribLast,
         exchange: &str,
         special_conditions: &str,
@@ -859,8 +893,8 @@ impl Wrapper for DefaultWrapper {
         time: i64,
         bid_price: f64,
         ask_price: f64,
-        bid_size: i32,
-        ask_size: i32,
+        bid_size: BigDecimal,
+        ask_size: BigDecimal,
         tick_attrib_bid_ask: TickAttribBidAsk,
     ) {
         info!(
@@ -898,4 +932,32 @@ impl Wrapper for DefaultWrapper {
     fn completed_orders_end(&mut self) {
         info!("completed_orders_end -- (no parameters for this message)");
     }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "wsh")]
+    fn wsh_meta_data(&mut self, request_id: i32, meta_data: serde_json::Value) {
+        info!(
+            "wsh_meta_data -- request_id: {}, meta_data: {}",
+            request_id, meta_data
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "wsh")]
+    fn wsh_event_data(&mut self, request_id: i32, events: Vec<WshEvent>) {
+        for event in events {
+            info!(
+                "wsh_event_data -- request_id: {}, event: {}",
+                request_id, event
+            );
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn user_info(&mut self, request_id: i32, white_branding_id: &str) {
+        info!(
+            "user_info -- request_id: {}, white_branding_id: {}",
+            request_id, white_branding_id
+        );
+    }
 }
@@ -17,21 +17,29 @@ use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, UNIX_EPOCH};
+#[cfg(feature = "wsh")]
+use twsapi::core::wsh::WshEvent;
 use twsapi::{
     core::client::{DateTimeFormat, EClient},
     core::common::{
-        BarData, CommissionReport, DepthMktDataDescription, FaDataType, FamilyCode, HistogramData,
-        HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, MarketDataTypeEnum, NewsProvider,
-        PriceIncrement, RealTimeBar, SmartComponent, TagValue, TickAttrib, TickAttribBidAsk,
-        TickAttribLast, TickByTickType, TickType,
+        BarData, BarSize, CommissionReport, DataFarmStatus, DepthMktDataDescription, FaDataType,
+        FamilyCode, HistogramData, HistoricalDuration, HistoricalTick, HistoricalTickBidAsk,
+        HistoricalTickLast, MarketDataTypeEnum, NewsProvider, PriceIncrement, RealTimeBar,
+        TagValue, TickAttrib, TickAttribBidAsk, TickAttribLast, TickByTickType, TickType,
+        WhatToShow,
     },
     core::contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract},
-    core::errors::IBKRApiLibError,
+    core::errors::{DecodeError, IbkrError},
     core::execution::Execution,
+    core::historical::HistoricalSchedule,
     core::{
         account_summary_tags::AccountSummaryTags,
+        display_groups::DisplayGroupContract,
+        news::{NewsArticle, NewsBulletin, NewsHeadline},
         order::{Order, OrderState, SoftDollarTier},
         order_condition::{OrderConditionEnum, TriggerMethod},
+        quote_cache::{AuctionState, TradingStatus},
+        smart_components::SmartComponentMap,
         wrapper::Wrapper,
     },
     core::{algo_params::fill_arrival_price_params, streamer::Streamer},
@@ -68,7 +76,7 @@ impl<T: Streamer> TestWrapper<T> {
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn start_requests(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn start_requests(&mut self) -> Result<(), IbkrError> {
         self.place_futures_order()?;
         //self.order_operations_req()?; //tested ok
         //self.what_if_order_operations()?; //tested ok
@@ -99,7 +107,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn place_futures_order(&mut self) -> Result<(), IBKRApiLibError> {
+    fn place_futures_order(&mut self) -> Result<(), IbkrError> {
         {
             let next_id = self.next_order_id();
             info!("Placing order... {}", next_id);
@@ -111,7 +119,7 @@ impl<T: Streamer> TestWrapper<T> {
                 .expect(CLIENT_POISONED_MUTEX)
                 .place_order(
                     next_id,
-                    &Contract::future("NQ", "GLOBEX", "20211217"),
+                    &Contract::future("NQ", "20211217", "CME"),
                     &Order::limit_order("DF3987931", "BUY", 10.0, 16500.0),
                 )?;
         }
@@ -121,7 +129,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn account_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn account_operations_req(&self) -> Result<(), IbkrError> {
         // Requesting managed accounts
         let mut locked_client = self
             .client
@@ -160,7 +168,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    pub fn real_time_bars_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    pub fn real_time_bars_operations_req(&self) -> Result<(), IbkrError> {
         // Requesting real time bars
         self.client
             .as_ref()
@@ -171,7 +179,7 @@ impl<T: Streamer> TestWrapper<T> {
                 3001,
                 contract_samples::us_stock_at_smart().borrow(),
                 1,
-                "TRADES",
+                WhatToShow::Trades,
                 true,
                 &[],
             )?;
@@ -181,7 +189,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn order_operations_req(&mut self) -> Result<(), IBKRApiLibError> {
+    fn order_operations_req(&mut self) -> Result<(), IbkrError> {
         // Requesting the next valid id
         // The parameter is always ignored.
 
@@ -715,7 +723,7 @@ impl<T: Streamer> TestWrapper<T> {
         // .expect(CLIENT_IS_NONE)
         // .lock()
         // .expect(CLIENT_POISONED_MUTEX)
-        //     .request_executions(10001, ExecutionFilter::default().borrow())?;
+        //     .request_executions(10001, &ExecutionFilter::builder().today().build())?;
 
         // self.client
         // .as_ref()
@@ -731,7 +739,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn order_operations_cancel(&mut self) -> Result<(), IBKRApiLibError> {
+    fn order_operations_cancel(&mut self) -> Result<(), IbkrError> {
         if self.next_order_id != -1 {
             self.client
                 .as_ref()
@@ -748,7 +756,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn bracket_sample(&mut self) -> Result<(), IBKRApiLibError> {
+    fn bracket_sample(&mut self) -> Result<(), IbkrError> {
         // BRACKET ORDER
         let bracket =
             order_samples::bracket_order(self.next_order_id(), "BUY", 100.0, 30.0, 40.0, 20.0);
@@ -792,12 +800,12 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn condition_samples(&mut self) -> Result<(), IBKRApiLibError> {
+    fn condition_samples(&mut self) -> Result<(), IbkrError> {
         let mut mkt = order_samples::market_order("BUY", 100.0);
         // Order will become active if conditioning criteria is met
         mkt.conditions
             .push(OrderConditionEnum::Price(order_samples::price_condition(
-                TriggerMethod::Default as i32,
+                TriggerMethod::Default,
                 208813720,
                 "SMART",
                 600.0,
@@ -843,7 +851,7 @@ impl<T: Streamer> TestWrapper<T> {
         lmt.conditions_cancel_order = true;
         lmt.conditions
             .push(OrderConditionEnum::Price(order_samples::price_condition(
-                TriggerMethod::Last as i32,
+                TriggerMethod::Last,
                 208813720,
                 "SMART",
                 600.0,
@@ -867,7 +875,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn hedge_sample(&mut self) -> Result<(), IBKRApiLibError> {
+    fn hedge_sample(&mut self) -> Result<(), IbkrError> {
         let mut parent = order_samples::limit_order("BUY", 100.0, 10.0);
         parent.order_id = self.next_order_id();
         parent.transmit = false;
@@ -1004,7 +1012,7 @@ impl<T: Streamer> TestWrapper<T> {
     */
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn algo_samples(&mut self) -> Result<(), IBKRApiLibError> {
+    fn algo_samples(&mut self) -> Result<(), IbkrError> {
         // let next_id = self.next_order_id();
         // let mut scale_order =
         //     order_samples::relative_pegged_to_primary("BUY", 70000.0, 189.0, 0.01);
@@ -1356,7 +1364,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn oca_sample(&mut self) -> Result<(), IBKRApiLibError> {
+    fn oca_sample(&mut self) -> Result<(), IbkrError> {
         let oca_orders = vec![
             order_samples::limit_order("BUY", 1.0, 10.0),
             order_samples::limit_order("BUY", 1.0, 11.0),
@@ -1386,7 +1394,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn what_if_order_operations(&mut self) -> Result<(), IBKRApiLibError> {
+    fn what_if_order_operations(&mut self) -> Result<(), IbkrError> {
         let mut what_if_order = order_samples::limit_order("SELL", 5.0, 70.0);
         what_if_order.what_if = true;
         let next_id = self.next_order_id();
@@ -1408,7 +1416,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn request_global_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn request_global_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -1426,7 +1434,7 @@ impl<T: Streamer> TestWrapper<T> {
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn historical_data_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    pub fn historical_data_operations_req(&self) -> Result<(), IbkrError> {
         //Requesting historical data
         self.client
             .as_ref()
@@ -1436,7 +1444,7 @@ impl<T: Streamer> TestWrapper<T> {
             .request_head_time_stamp(
                 4101,
                 contract_samples::simple_future().borrow(),
-                "TRADES",
+                WhatToShow::Trades,
                 Some(false),
                 DateTimeFormat::from_u64(1),
             )?;
@@ -1453,9 +1461,9 @@ impl<T: Streamer> TestWrapper<T> {
                 4102,
                 contract_samples::simple_future().borrow(),
                 query_time.as_str(),
-                "1 M",
-                "1 day",
-                "MIDPOINT",
+                HistoricalDuration::Months(1),
+                BarSize::_1Day,
+                WhatToShow::Midpoint,
                 1,
                 1,
                 false,
@@ -1470,9 +1478,9 @@ impl<T: Streamer> TestWrapper<T> {
                 4103,
                 contract_samples::simple_future().borrow(),
                 query_time.as_str(),
-                "10 D",
-                "1 min",
-                "TRADES",
+                HistoricalDuration::Days(10),
+                BarSize::_1Min,
+                WhatToShow::Trades,
                 1,
                 1,
                 false,
@@ -1487,9 +1495,9 @@ impl<T: Streamer> TestWrapper<T> {
                 4104,
                 contract_samples::eur_gbp_fx().borrow(),
                 "",
-                "1 M",
-                "1 day",
-                "MIDPOINT",
+                HistoricalDuration::Months(1),
+                BarSize::_1Day,
+                WhatToShow::Midpoint,
                 1,
                 1,
                 true,
@@ -1501,7 +1509,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn market_data_type_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn market_data_type_operations(&self) -> Result<(), IbkrError> {
         // Switch to live (1) frozen (2) delayed (3) delayed frozen (4).
         self.client
             .as_ref()
@@ -1515,7 +1523,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn tick_data_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn tick_data_operations_req(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -1789,7 +1797,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn market_depth_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn market_depth_operations_req(&self) -> Result<(), IbkrError> {
         // Requesting the Deep Book
         self.client
             .as_ref()
@@ -1824,7 +1832,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn options_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn options_operations_req(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -1880,7 +1888,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn market_scanners_operations_req(&mut self) -> Result<(), IBKRApiLibError> {
+    fn market_scanners_operations_req(&mut self) -> Result<(), IbkrError> {
         // Requesting list of valid scanner parameters which can be used in TWS
         self.client
             .as_ref()
@@ -1923,12 +1931,8 @@ impl<T: Streamer> TestWrapper<T> {
             ); // requires TWS v973+
 
         if result.is_err() {
-            if let IBKRApiLibError::ApiError(err) = result.unwrap_err() {
-                self.error(
-                    err.request_id,
-                    err.code.as_str().parse().unwrap(),
-                    err.description.as_ref(),
-                )
+            if let IbkrError::Connection(err) = result.unwrap_err() {
+                self.error(err.request_id, err.kind.code(), &err.description(), "")
             }
         }
 
@@ -1953,7 +1957,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn fundamentals_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn fundamentals_operations_req(&self) -> Result<(), IbkrError> {
         // Requesting Fundamentals
         self.client
             .as_ref()
@@ -2029,7 +2033,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn contract_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn contract_operations(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2073,7 +2077,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn tick_by_tick_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn tick_by_tick_operations_req(&self) -> Result<(), IbkrError> {
         // Requesting tick - by - tick data (only refresh)
         self.client
             .as_ref()
@@ -2179,7 +2183,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn historical_ticks_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn historical_ticks_operations(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2191,7 +2195,7 @@ impl<T: Streamer> TestWrapper<T> {
                 "20170712 21:39:33",
                 "",
                 10,
-                "TRADES",
+                WhatToShow::Trades,
                 1,
                 true,
                 &[],
@@ -2207,7 +2211,7 @@ impl<T: Streamer> TestWrapper<T> {
                 "20170712 21:39:33",
                 "",
                 10,
-                "BID_ASK",
+                WhatToShow::BidAsk,
                 1,
                 true,
                 &[],
@@ -2223,7 +2227,7 @@ impl<T: Streamer> TestWrapper<T> {
                 "20170712 21:39:33",
                 "",
                 10,
-                "MIDPOINT",
+                WhatToShow::Midpoint,
                 1,
                 true,
                 &[],
@@ -2234,7 +2238,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn histogram_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn histogram_operations_req(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2247,7 +2251,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn histogram_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn histogram_operations_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2258,7 +2262,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn continuous_futures_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn continuous_futures_operations_req(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2276,9 +2280,9 @@ impl<T: Streamer> TestWrapper<T> {
                 18002,
                 contract_samples::cont_fut().borrow(),
                 time_str.to_string().as_str(),
-                "1 Y",
-                "1 month",
-                "TRADES",
+                HistoricalDuration::Years(1),
+                BarSize::_1Month,
+                WhatToShow::Trades,
                 0,
                 1,
                 false,
@@ -2290,7 +2294,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn pnl_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn pnl_operations_req(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2310,7 +2314,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn pnl_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn pnl_operations_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2330,7 +2334,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //--------------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn market_rule_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn market_rule_operations(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2362,7 +2366,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn reroute_cfd_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn reroute_cfd_operations(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2439,7 +2443,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn financial_advisor_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn financial_advisor_operations(&self) -> Result<(), IbkrError> {
         // Requesting FA information
 
         self.client
@@ -2470,28 +2474,44 @@ impl<T: Streamer> TestWrapper<T> {
             .expect(CLIENT_IS_NONE)
             .lock()
             .expect(CLIENT_POISONED_MUTEX)
-            .replace_fa(FaDataType::GROUPS, fa_allocation_samples::FA_ONE_GROUP)?;
+            .replace_fa(
+                1000,
+                FaDataType::GROUPS,
+                fa_allocation_samples::FA_ONE_GROUP,
+            )?;
 
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
             .lock()
             .expect(CLIENT_POISONED_MUTEX)
-            .replace_fa(FaDataType::GROUPS, fa_allocation_samples::FA_TWO_GROUPS)?;
+            .replace_fa(
+                1001,
+                FaDataType::GROUPS,
+                fa_allocation_samples::FA_TWO_GROUPS,
+            )?;
 
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
             .lock()
             .expect(CLIENT_POISONED_MUTEX)
-            .replace_fa(FaDataType::PROFILES, fa_allocation_samples::FA_ONE_PROFILE)?;
+            .replace_fa(
+                1002,
+                FaDataType::PROFILES,
+                fa_allocation_samples::FA_ONE_PROFILE,
+            )?;
 
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
             .lock()
             .expect(CLIENT_POISONED_MUTEX)
-            .replace_fa(FaDataType::PROFILES, fa_allocation_samples::FA_TWO_PROFILES)?;
+            .replace_fa(
+                1003,
+                FaDataType::PROFILES,
+                fa_allocation_samples::FA_TWO_PROFILES,
+            )?;
 
         self.client
             .as_ref()
@@ -2505,7 +2525,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn news_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn news_operations_req(&self) -> Result<(), IbkrError> {
         // Requesting news ticks
         self.client
             .as_ref()
@@ -2557,7 +2577,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn news_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn news_operations_cancel(&self) -> Result<(), IbkrError> {
         // Canceling news ticks
         self.client
             .as_ref()
@@ -2569,7 +2589,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn bulletins_operations_req(&self) -> Result<(), IBKRApiLibError> {
+    fn bulletins_operations_req(&self) -> Result<(), IbkrError> {
         // Requesting Interactive Broker's news bulletins_operations_req
         self.client
             .as_ref()
@@ -2581,7 +2601,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn bulletins_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn bulletins_operations_cancel(&self) -> Result<(), IbkrError> {
         // Canceling IB's news bulletins_operations_req
         self.client
             .as_ref()
@@ -2593,7 +2613,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn miscelaneous_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn miscelaneous_operations(&self) -> Result<(), IbkrError> {
         // Request TWS' current time
         self.client
             .as_ref()
@@ -2614,7 +2634,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn linking_operations(&self) -> Result<(), IBKRApiLibError> {
+    fn linking_operations(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2634,7 +2654,7 @@ impl<T: Streamer> TestWrapper<T> {
             .expect(CLIENT_IS_NONE)
             .lock()
             .expect(CLIENT_POISONED_MUTEX)
-            .update_display_group(19002, "8314@SMART")?;
+            .update_display_group(19002, &DisplayGroupContract::new(8314, "SMART"))?;
 
         self.client
             .as_ref()
@@ -2648,7 +2668,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn tick_by_tick_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn tick_by_tick_operations_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2704,7 +2724,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn continuous_futures_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn continuous_futures_operations_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2715,7 +2735,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn fundamentals_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn fundamentals_operations_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2758,7 +2778,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn market_scanners_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn market_scanners_cancel(&self) -> Result<(), IbkrError> {
         // Canceling the scanner subscription
         self.client
             .as_ref()
@@ -2784,7 +2804,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn options_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn options_operations_cancel(&self) -> Result<(), IbkrError> {
         // Canceling implied volatility
         self.client
             .as_ref()
@@ -2805,7 +2825,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn historical_data_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn historical_data_operations_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -2838,7 +2858,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn real_time_bars_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn real_time_bars_operations_cancel(&self) -> Result<(), IbkrError> {
         // Canceling real time bars
         self.client
             .as_ref()
@@ -2850,7 +2870,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn market_depth_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn market_depth_operations_cancel(&self) -> Result<(), IbkrError> {
         // Canceling the Deep Book request
         self.client
             .as_ref()
@@ -2870,7 +2890,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn tick_data_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn tick_data_operations_cancel(&self) -> Result<(), IbkrError> {
         // Canceling the market data subscription
         self.client
             .as_ref()
@@ -2981,7 +3001,7 @@ impl<T: Streamer> TestWrapper<T> {
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn account_operations_cancel(&self) -> Result<(), IBKRApiLibError> {
+    fn account_operations_cancel(&self) -> Result<(), IbkrError> {
         self.client
             .as_ref()
             .expect(CLIENT_IS_NONE)
@@ -3043,13 +3063,29 @@ impl<T> Wrapper for TestWrapper<T>
 where
     T: Streamer + 'static,
 {
-    fn error(&mut self, request_id: i32, error_code: i32, error_string: &str) {
+    fn error(
+        &mut self,
+        request_id: i32,
+        error_code: i32,
+        error_string: &str,
+        advanced_order_reject_json: &str,
+    ) {
         error!(
-            "request_id: {} ,error_code: {} , error_string:{}",
-            request_id, error_code, error_string
+            "request_id: {} ,error_code: {} , error_string:{}, advanced_order_reject_json: {}",
+            request_id, error_code, error_string, advanced_order_reject_json
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn data_farm_status(&mut self, status: &DataFarmStatus) {
+        info!("data_farm_status -- status: {}", status);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn decode_error(&mut self, error: &DecodeError) {
+        error!("decode_error -- {}", error);
+    }
+
     //----------------------------------------------------------------------------------------------
     fn win_error(&mut self, text: &str, last_error: i32) {
         error!("text: {} , last_error:{}", text, last_error);
@@ -3077,7 +3113,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn tick_size(&mut self, request_id: i32, tick_type: TickType, size: i32) {
+    fn tick_size(&mut self, request_id: i32, tick_type: TickType, size: BigDecimal) {
         info!(
             "tick_size -- request_id: {}, tick_type: {}, size: {}",
             request_id, tick_type, size
@@ -3097,6 +3133,22 @@ where
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn trading_status(&mut self, request_id: i32, trading_status: TradingStatus) {
+        info!(
+            "trading_status -- request_id: {}, trading_status: {}",
+            request_id, trading_status
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn auction_state(&mut self, request_id: i32, auction_state: AuctionState) {
+        info!(
+            "auction_state -- request_id: {}, auction_state: {}",
+            request_id, auction_state
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn tick_string(&mut self, request_id: i32, tick_type: TickType, value: &str) {
         info!(
@@ -3146,8 +3198,8 @@ where
         &mut self,
         order_id: i32,
         status: &str,
-        filled: f64,
-        remaining: f64,
+        filled: BigDecimal,
+        remaining: BigDecimal,
         avg_fill_price: f64,
         perm_id: i32,
         parent_id: i32,
@@ -3200,7 +3252,7 @@ where
     fn update_portfolio(
         &mut self,
         contract: Contract,
-        position: f64,
+        position: BigDecimal,
         market_price: f64,
         market_value: f64,
         average_cost: f64,
@@ -3283,7 +3335,7 @@ where
         operation: i32,
         side: i32,
         price: f64,
-        size: i32,
+        size: BigDecimal,
     ) {
         info!(
             "update_market_depth -- request_id: {}, position: {}, operation: {}, side: {}, price: {}, size: {}",
@@ -3300,7 +3352,7 @@ where
         operation: i32,
         side: i32,
         price: f64,
-        size: i32,
+        size: BigDecimal,
         is_smart_depth: bool,
     ) {
         info!(
@@ -3310,17 +3362,8 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn update_news_bulletin(
-        &mut self,
-        msg_id: i32,
-        msg_type: i32,
-        news_message: &str,
-        origin_exch: &str,
-    ) {
-        info!(
-            "update_news_bulletin -- msg_id: {}, msg_type: {}, news_message: {}, origin_exch: {}",
-            msg_id, msg_type, news_message, origin_exch
-        );
+    fn update_news_bulletin(&mut self, bulletin: NewsBulletin) {
+        info!("update_news_bulletin -- bulletin: {}", bulletin);
     }
 
     //----------------------------------------------------------------------------------------------
@@ -3335,6 +3378,14 @@ where
         info!("receive_fa -- fa_data: {}, cxml: {}", fa_data, cxml);
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn replace_fa_end(&mut self, request_id: i32, text: &str) {
+        info!(
+            "replace_fa_end -- request_id: {}, text: {}",
+            request_id, text
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn historical_data(&mut self, request_id: i32, bar: BarData) {
         info!(
@@ -3439,7 +3490,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn position(&mut self, account: &str, contract: Contract, position: f64, avg_cost: f64) {
+    fn position(&mut self, account: &str, contract: Contract, position: BigDecimal, avg_cost: f64) {
         info!(
             "position -- account: {}, contract: [{}], position: {}, avg_cost: {}",
             account, contract, position, avg_cost
@@ -3501,15 +3552,15 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn display_group_list(&mut self, request_id: i32, groups: &str) {
+    fn display_group_list(&mut self, request_id: i32, groups: Vec<i32>) {
         info!(
-            "display_group_list -- request_id: {}, error_text: {}",
+            "display_group_list -- request_id: {}, groups: {:?}",
             request_id, groups
         );
     }
 
     //----------------------------------------------------------------------------------------------
-    fn display_group_updated(&mut self, request_id: i32, contract_info: &str) {
+    fn display_group_updated(&mut self, request_id: i32, contract_info: DisplayGroupContract) {
         info!(
             "display_group_updated -- request_id: {}, contract_info: {}",
             request_id, contract_info
@@ -3523,7 +3574,7 @@ where
         account: &str,
         model_code: &str,
         contract: Contract,
-        pos: f64,
+        pos: BigDecimal,
         avg_cost: f64,
     ) {
         info!(
@@ -3564,6 +3615,7 @@ where
         &mut self,
         request_id: i32,
         tick_type: TickType,
+        tick_attrib: i32,
         implied_vol: f64,
         delta: f64,
         opt_price: f64,
@@ -3574,10 +3626,12 @@ where
         und_price: f64,
     ) {
         info!(
-            "tick_option_computation -- request_id: {}, tick_type: {}, implied_vol: {}, delta: {}, \
-             opt_price: {}, pv_dividend: {},  gamma: {}, vega: {}, theta: {}, und_price: {}",
+            "tick_option_computation -- request_id: {}, tick_type: {}, tick_attrib: {}, \
+             implied_vol: {}, delta: {}, opt_price: {}, pv_dividend: {},  gamma: {}, vega: {}, \
+             theta: {}, und_price: {}",
             request_id,
             tick_type,
+            tick_attrib,
             implied_vol,
             delta,
             opt_price,
@@ -3657,26 +3711,17 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn tick_news(
-        &mut self,
-        ticker_id: i32,
-        time_stamp: i32,
-        provider_code: &str,
-        article_id: &str,
-        headline: &str,
-        extra_data: &str,
-    ) {
+    fn tick_news(&mut self, ticker_id: i32, headline: NewsHeadline) {
         info!(
-            "tick_news -- ticker_id: {}, time_stamp: {}, provider_code: {}, article_id: {}, \
-             headline: {}, extra_data: {},",
-            ticker_id, time_stamp, provider_code, article_id, headline, extra_data
+            "tick_news -- ticker_id: {}, headline: {}",
+            ticker_id, headline
         );
     }
 
     //----------------------------------------------------------------------------------------------
-    fn smart_components(&mut self, request_id: i32, smart_components: Vec<SmartComponent>) {
+    fn smart_components(&mut self, request_id: i32, smart_components: SmartComponentMap) {
         info!(
-            "smart_components -- request_id: {}, smart_components: {:?}",
+            "smart_components -- request_id: {}, smart_components: {}",
             request_id, smart_components
         );
     }
@@ -3701,25 +3746,18 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn news_article(&mut self, request_id: i32, article_type: i32, article_text: &str) {
+    fn news_article(&mut self, request_id: i32, article: NewsArticle) {
         info!(
-            "news_article -- request_id: {}, article_type: {}, article_text: {}",
-            request_id, article_type, article_text
+            "news_article -- request_id: {}, article: {}",
+            request_id, article
         );
     }
 
     //----------------------------------------------------------------------------------------------
-    fn historical_news(
-        &mut self,
-        request_id: i32,
-        time: &str,
-        provider_code: &str,
-        article_id: &str,
-        headline: &str,
-    ) {
+    fn historical_news(&mut self, request_id: i32, headline: NewsHeadline) {
         info!(
-            "historical_news -- request_id: {}, time: {}, provider_code: {}, article_id: {}, headline: {}",
-            request_id, time, provider_code, article_id, headline
+            "historical_news -- request_id: {}, headline: {}",
+            request_id, headline
         );
     }
 
@@ -3754,6 +3792,14 @@ where
         );
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn historical_schedule(&mut self, request_id: i32, schedule: HistoricalSchedule) {
+        info!(
+            "historical_schedule -- request_id: {}, schedule: {}",
+            request_id, schedule
+        );
+    }
+
     //----------------------------------------------------------------------------------------------
     fn reroute_market_data_request(&mut self, request_id: i32, con_id: i32, exchange: &str) {
         info!(
@@ -3796,7 +3842,7 @@ where
     fn profit_and_loss_single(
         &mut self,
         request_id: i32,
-        pos: i32,
+        pos: BigDecimal,
         daily_pn_l: f64,
         unrealized_pn_l: f64,
         realized_pn_l: f64,
@@ -3849,7 +3895,7 @@ where
         tick_type: TickByTickType,
         time: i64,
         price: f64,
-        size: i32,
+        size: BigDecimal,
         tick_attrib_last: TickAttribLast,
         exchange: &str,
         special_conditions: &str,
@@ -3868,8 +3914,8 @@ where
         time: i64,
         bid_price: f64,
         ask_price: f64,
-        bid_size: i32,
-        ask_size: i32,
+        bid_size: BigDecimal,
+        ask_size: BigDecimal,
         tick_attrib_bid_ask: TickAttribBidAsk,
     ) {
         info!(
@@ -3907,4 +3953,32 @@ where
     fn completed_orders_end(&mut self) {
         info!("completed_orders_end -- (no parameters for this message)");
     }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "wsh")]
+    fn wsh_meta_data(&mut self, request_id: i32, meta_data: serde_json::Value) {
+        info!(
+            "wsh_meta_data -- request_id: {}, meta_data: {}",
+            request_id, meta_data
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "wsh")]
+    fn wsh_event_data(&mut self, request_id: i32, events: Vec<WshEvent>) {
+        for event in events {
+            info!(
+                "wsh_event_data -- request_id: {}, event: {}",
+                request_id, event
+            );
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn user_info(&mut self, request_id: i32, white_branding_id: &str) {
+        info!(
+            "user_info -- request_id: {}, white_branding_id: {}",
+            request_id, white_branding_id
+        );
+    }
 }
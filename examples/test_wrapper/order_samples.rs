@@ -1,12 +1,11 @@
 #![allow(clippy::too_many_arguments)]
 //! Examples of populating fields of various order types
-use num_traits::FromPrimitive;
 
 use twsapi::core::common::TagValue;
 use twsapi::core::order::{AuctionStrategy, Order, OrderComboLeg};
 use twsapi::core::order_condition::{
     create_condition, ConditionType, ExecutionCondition, MarginCondition, PercentChangeCondition,
-    PriceCondition, TimeCondition, VolumeCondition,
+    PriceCondition, TimeCondition, TriggerMethod, VolumeCondition,
 };
 
 /// An auction order is entered into the electronic trading system during the
@@ -982,7 +981,7 @@ pub fn pegged_to_benchmark(
         // in the reference contract...
         reference_contract_id: reference_con_id,
         // being traded at...
-        reference_exchange_id: reference_exchange.parse().unwrap(),
+        reference_exchange_id: reference_exchange.to_string(),
         //starting reference price is...
         stock_ref_price: stock_reference_price,
         // Keep order active as long as reference contract trades between...
@@ -1090,7 +1089,7 @@ pub fn attach_adjustable_to_trail(
 
 //==================================================================================================
 pub fn price_condition(
-    trigger_method: i32,
+    trigger_method: TriggerMethod,
     con_id: i32,
     exchange: &str,
     price: f64,
@@ -1108,7 +1107,7 @@ pub fn price_condition(
         .contract_condition
         .operator_condition
         .is_more = is_more;
-    price_condition.trigger_method = FromPrimitive::from_i32(trigger_method).unwrap();
+    price_condition.trigger_method = trigger_method;
     // this quantity
     price_condition.price = price;
     // AND | OR next condition (will be ignored if no more conditions are added)
@@ -1134,7 +1133,7 @@ pub fn execution_condition(
     // at exchange
     exec_condition.exchange = exchange.to_string();
     // for this sec_type
-    exec_condition.sec_type = sec_type.to_string();
+    exec_condition.sec_type = sec_type.into();
     // AND | OR next condition (will be ignored if no more conditions are added)
     exec_condition.order_condition.is_conjunction_connection = is_conjunction;
     exec_condition.order_condition.cond_type = ConditionType::Execution;
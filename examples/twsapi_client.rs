@@ -1,6 +1,7 @@
 //! Binary for manually testing crate
 
 use log::*;
+use std::io;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -20,14 +21,12 @@ use color_eyre::eyre::Result;
 /// start sending tests requests to TWS (see the start_requests function inn
 /// TestWrapper which is called by next_valid_id
 //==================================================================================================
-pub fn main() -> Result<(), IBKRApiLibError> {
+pub fn main() -> Result<(), IbkrError> {
     match log4rs::init_file("./log_config.yml", Default::default()) {
         Ok(_) => (),
         Err(_) => {
-            return Err(IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                -1,
-                "-1".to_string(),
-                "Failed to create logger!!".to_string(),
+            return Err(IbkrError::Io(io::Error::other(
+                "Failed to create logger!!",
             )))
         }
     };
@@ -0,0 +1,233 @@
+//! Threshold alerts on account values
+//!
+//! `AccountAlerts` watches numeric account values of the account updates,
+//! e.g. `ExcessLiquidity` or `MaintMarginReq`, and emits an `AccountAlert`
+//! only when a value crosses one of the configured thresholds: once when it
+//! enters the alert zone and once when it leaves it again. Repeated updates
+//! on the same side of a threshold emit nothing.
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::account_updates::{
+    AccountUpdateEvent, AccountUpdateMultiEvent, AccountValue, AccountValueKey,
+};
+
+//==================================================================================================
+/// On which side of the level a value is in the alert zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ThresholdSide {
+    Below,
+    Above,
+}
+
+impl fmt::Display for ThresholdSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThresholdSide::Below => write!(f, "below"),
+            ThresholdSide::Above => write!(f, "above"),
+        }
+    }
+}
+
+//==================================================================================================
+/// A level of an account value
+///
+/// currency - the currency of the value, e.g. `USD` or `BASE`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountThreshold {
+    pub key: AccountValueKey,
+    pub currency: String,
+    pub side: ThresholdSide,
+    pub level: f64,
+}
+
+impl AccountThreshold {
+    pub fn new(key: AccountValueKey, currency: &str, side: ThresholdSide, level: f64) -> Self {
+        AccountThreshold {
+            key,
+            currency: currency.to_string(),
+            side,
+            level,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether a value is in the alert zone
+    pub fn is_breached(&self, value: f64) -> bool {
+        match self.side {
+            ThresholdSide::Below => value < self.level,
+            ThresholdSide::Above => value > self.level,
+        }
+    }
+}
+
+impl fmt::Display for AccountThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.key, self.currency, self.side, self.level
+        )
+    }
+}
+
+//==================================================================================================
+/// Whether a value entered or left the alert zone of a threshold
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AccountAlertKind {
+    Breached,
+    Recovered,
+}
+
+//==================================================================================================
+/// A value that crossed a threshold
+///
+/// previous - the value before, None if it is the first value received
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountAlert {
+    pub account: String,
+    pub kind: AccountAlertKind,
+    pub threshold: AccountThreshold,
+    pub previous: Option<f64>,
+    pub value: f64,
+}
+
+impl fmt::Display for AccountAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, kind: {:?}, threshold: {}, previous: {:?}, value: {}",
+            self.account, self.kind, self.threshold, self.previous, self.value
+        )
+    }
+}
+
+//==================================================================================================
+/// Compares account values with their previous value and the thresholds.
+/// A value that is in the alert zone when it is first received counts as a
+/// breach.
+#[derive(Clone, Debug, Default)]
+pub struct AccountAlerts {
+    thresholds: Vec<AccountThreshold>,
+    values: HashMap<(String, AccountValueKey, String), f64>,
+}
+
+impl AccountAlerts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a threshold
+    pub fn watch(
+        mut self,
+        key: AccountValueKey,
+        currency: &str,
+        side: ThresholdSide,
+        level: f64,
+    ) -> Self {
+        self.add_threshold(AccountThreshold::new(key, currency, side, level));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn add_threshold(&mut self, threshold: AccountThreshold) {
+        self.thresholds.push(threshold);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn thresholds(&self) -> &[AccountThreshold] {
+        &self.thresholds
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an account value and returns the thresholds it crossed.
+    /// Values without a watched key or without a number are ignored.
+    pub fn apply_value(&mut self, account: &str, value: &AccountValue) -> Vec<AccountAlert> {
+        let number = match value.value {
+            Some(number) => number,
+            None => return vec![],
+        };
+        if !self
+            .thresholds
+            .iter()
+            .any(|threshold| threshold.key == value.key && threshold.currency == value.currency)
+        {
+            return vec![];
+        }
+
+        let previous = self.values.insert(
+            (
+                account.to_string(),
+                value.key.clone(),
+                value.currency.clone(),
+            ),
+            number,
+        );
+        self.thresholds
+            .iter()
+            .filter(|threshold| threshold.key == value.key && threshold.currency == value.currency)
+            .filter_map(|threshold| {
+                let was_breached = previous.is_some_and(|previous| threshold.is_breached(previous));
+                let kind = match (was_breached, threshold.is_breached(number)) {
+                    (false, true) => AccountAlertKind::Breached,
+                    (true, false) => AccountAlertKind::Recovered,
+                    _ => return None,
+                };
+                Some(AccountAlert {
+                    account: account.to_string(),
+                    kind,
+                    threshold: threshold.clone(),
+                    previous,
+                    value: number,
+                })
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an event of `EClient::stream_account_updates`
+    pub fn apply(&mut self, event: &AccountUpdateEvent) -> Vec<AccountAlert> {
+        match event {
+            AccountUpdateEvent::Value { account, value } => self.apply_value(account, value),
+            _ => vec![],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an event of `EClient::stream_account_updates_multi`. Values
+    /// of models are told apart from the account by `account/model_code`.
+    pub fn apply_multi(&mut self, event: &AccountUpdateMultiEvent) -> Vec<AccountAlert> {
+        match event {
+            AccountUpdateMultiEvent::Value(value) if value.model_code.is_empty() => {
+                self.apply_value(&value.account, &value.value)
+            }
+            AccountUpdateMultiEvent::Value(value) => self.apply_value(
+                &format!("{}/{}", value.account, value.model_code),
+                &value.value,
+            ),
+            _ => vec![],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The last value received of a watched key
+    pub fn value(&self, account: &str, key: &AccountValueKey, currency: &str) -> Option<f64> {
+        self.values
+            .get(&(account.to_string(), key.clone(), currency.to_string()))
+            .copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forgets the values received, e.g. on reconnect
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
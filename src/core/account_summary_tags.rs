@@ -1,5 +1,10 @@
-//! Account summary tags
+//! Account summary tags and the values TWS reports for them
+use std::collections::BTreeSet;
 use std::fmt::{Display, Error, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::core::account_summary_tags::AccountSummaryTags::*;
 
@@ -47,7 +52,8 @@ use crate::core::account_summary_tags::AccountSummaryTags::*;
 /// currency. $LEDGER:CURRENCY — Single flag to relay all cash balance tags*,
 /// only in the specified currency. $LEDGER:ALL — Single flag to relay all cash
 /// balance tags* in all currencies.
-
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AccountSummaryTags {
     AccountType,
     NetLiquidation,
@@ -84,6 +90,39 @@ pub enum AccountSummaryTags {
     AllTags,
 }
 
+/// The tags `AllTags` stands for, without the `$LEDGER` tags
+pub const ALL_TAGS: [AccountSummaryTags; 29] = [
+    AccountType,
+    NetLiquidation,
+    TotalCashValue,
+    SettledCash,
+    AccruedCash,
+    BuyingPower,
+    EquityWithLoanValue,
+    PreviousEquityWithLoanValue,
+    GrossPositionValue,
+    ReqTEquity,
+    ReqTMargin,
+    SMA,
+    InitMarginReq,
+    MaintMarginReq,
+    AvailableFunds,
+    ExcessLiquidity,
+    Cushion,
+    FullInitMarginReq,
+    FullMaintMarginReq,
+    FullAvailableFunds,
+    FullExcessLiquidity,
+    LookAheadNextChange,
+    LookAheadInitMarginReq,
+    LookAheadMaintMarginReq,
+    LookAheadAvailableFunds,
+    LookAheadExcessLiquidity,
+    HighestSeverity,
+    DayTradesRemaining,
+    Leverage,
+];
+
 impl AccountSummaryTags {
     fn display(&self) -> &str {
         match self {
@@ -119,46 +158,240 @@ impl AccountSummaryTags {
             Ledger => "$LEDGER",
             LedgerCurrency => "$LEDGER:CURRENCY",
             LedgerAll => "$LEDGER:ALL",
+            AllTags => "",
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the value of the tag is a number. `AccountType` and
+    /// `LookAheadNextChange` are text.
+    pub fn is_numeric(&self) -> bool {
+        !matches!(self, AccountType | LookAheadNextChange)
+    }
+}
+
+impl Display for AccountSummaryTags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
             AllTags => {
-                "AccountType,
-            NetLiquidation,
-            TotalCashValue,
-            SettledCash,
-            AccruedCash,
-            BuyingPower,
-            EquityWithLoanValue,
-            PreviousEquityWithLoanValue,
-            GrossPositionValue,
-            ReqTEquity,
-            ReqTMargin,
-            SMA,
-            InitMarginReq,
-            MaintMarginReq,
-            AvailableFunds,
-            ExcessLiquidity,
-            Cushion,Cushion,
-            FullInitMarginReq,
-            FullMaintMarginReq,
-            FullAvailableFunds,
-            FullExcessLiquidity,
-            LookAheadNextChange,
-            LookAheadInitMarginReq,
-            LookAheadMaintMarginReq,
-            LookAheadAvailableFunds,
-            LookAheadExcessLiquidity,
-            HighestSeverity,
-            DayTradesRemaining,
-            Leverage,
-            $LEDGER,
-            $LEDGER:CURRENCY,
-            $LEDGER:ALL"
+                let tags: Vec<&str> = ALL_TAGS.iter().map(|tag| tag.display()).collect();
+                write!(f, "{}", tags.join(","))
             }
+            _ => write!(f, "{}", self.display()),
         }
     }
 }
 
-impl Display for AccountSummaryTags {
+impl FromStr for AccountSummaryTags {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "$LEDGER" => Ok(Ledger),
+            "$LEDGER:CURRENCY" => Ok(LedgerCurrency),
+            "$LEDGER:ALL" => Ok(LedgerAll),
+            _ => ALL_TAGS
+                .iter()
+                .find(|tag| tag.display() == s)
+                .copied()
+                .ok_or_else(|| format!("Unknown account summary tag {}", s)),
+        }
+    }
+}
+
+//==================================================================================================
+/// The tags of an account summary request, e.g.
+/// `AccountSummaryTagSet::new().tag(NetLiquidation).ledger_currency("EUR")`.
+/// Displays as the comma separated list `request_account_summary` takes.
+///
+/// ledger - the `$LEDGER` tag, which relays the cash balances in the base
+///          currency, `$LEDGER:<currency>` for one currency or `$LEDGER:ALL`
+///          for all of them
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountSummaryTagSet {
+    pub tags: BTreeSet<AccountSummaryTags>,
+    pub ledger: Option<String>,
+}
+
+impl AccountSummaryTagSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// All tags, without the cash balances
+    pub fn all() -> Self {
+        Self::new().tags(&ALL_TAGS)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a tag. `AllTags` adds all tags and the `$LEDGER` tags replace
+    /// the ledger of the set; use `ledger_currency` for the cash balances of
+    /// one currency.
+    pub fn tag(mut self, tag: AccountSummaryTags) -> Self {
+        match tag {
+            AllTags => self.tags.extend(ALL_TAGS.iter()),
+            Ledger | LedgerCurrency | LedgerAll => self.ledger = Some(tag.to_string()),
+            _ => {
+                self.tags.insert(tag);
+            }
+        }
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn tags(self, tags: &[AccountSummaryTags]) -> Self {
+        tags.iter().fold(self, |set, tag| set.tag(*tag))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The cash balances in the base currency
+    pub fn ledger(self) -> Self {
+        self.tag(Ledger)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The cash balances in `currency`, e.g. EUR
+    pub fn ledger_currency(mut self, currency: &str) -> Self {
+        self.ledger = Some(format!("$LEDGER:{}", currency.trim().to_uppercase()));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The cash balances in all currencies
+    pub fn ledger_all(self) -> Self {
+        self.tag(LedgerAll)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.ledger.is_none()
+    }
+}
+
+impl Display for AccountSummaryTagSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let mut tags: Vec<String> = self.tags.iter().map(|tag| tag.to_string()).collect();
+        tags.extend(self.ledger.iter().cloned());
+        write!(f, "{}", tags.join(","))
+    }
+}
+
+//==================================================================================================
+/// One value of an account summary
+///
+/// tag - the tag as sent by TWS; the cash balance tags of the `$LEDGER`
+///       requests, e.g. CashBalance, are not `AccountSummaryTags`
+/// value - the value as a number, `None` for text values like the
+///         AccountType
+/// text - the value as sent by TWS
+/// currency - the currency of the value, empty if it has none
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountSummaryValue {
+    pub account: String,
+    pub tag: String,
+    pub value: Option<f64>,
+    pub text: String,
+    pub currency: String,
+}
+
+impl AccountSummaryValue {
+    pub fn new(account: &str, tag: &str, text: &str, currency: &str) -> Self {
+        AccountSummaryValue {
+            account: account.to_string(),
+            tag: tag.to_string(),
+            value: text
+                .trim()
+                .parse()
+                .ok()
+                .filter(|value: &f64| value.is_finite()),
+            text: text.to_string(),
+            currency: currency.to_string(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The tag of the value, `None` for the cash balance tags
+    pub fn summary_tag(&self) -> Option<AccountSummaryTags> {
+        self.tag.parse().ok()
+    }
+}
+
+impl Display for AccountSummaryValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        write!(f, "{}", self.display())
+        write!(
+            f,
+            "account: {}, tag: {}, value: {}, currency: {}",
+            self.account, self.tag, self.text, self.currency
+        )
     }
 }
+
+//==================================================================================================
+/// The values of an account summary request once TWS has sent all of them
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountSummary {
+    pub values: Vec<AccountSummaryValue>,
+}
+
+impl AccountSummary {
+    pub fn new(values: Vec<AccountSummaryValue>) -> Self {
+        AccountSummary { values }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The accounts with values, in the order they were received
+    pub fn accounts(&self) -> Vec<&str> {
+        let mut accounts: Vec<&str> = vec![];
+        for value in &self.values {
+            if !accounts.contains(&value.account.as_str()) {
+                accounts.push(&value.account);
+            }
+        }
+        accounts
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The value of a tag of an account
+    pub fn get(&self, account: &str, tag: AccountSummaryTags) -> Option<&AccountSummaryValue> {
+        let tag = tag.to_string();
+        self.values
+            .iter()
+            .find(|value| value.account == account && value.tag == tag)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The numeric value of a tag of an account
+    pub fn value(&self, account: &str, tag: AccountSummaryTags) -> Option<f64> {
+        self.get(account, tag).and_then(|value| value.value)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The numeric value of a cash balance tag of a `$LEDGER` request, e.g.
+    /// TotalCashBalance in EUR
+    pub fn ledger_value(&self, account: &str, tag: &str, currency: &str) -> Option<f64> {
+        self.values
+            .iter()
+            .find(|value| {
+                value.account == account && value.tag == tag && value.currency == currency
+            })
+            .and_then(|value| value.value)
+    }
+}
+
+//==================================================================================================
+/// Event of an account summary request streamed through a channel instead
+/// of the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum AccountSummaryEvent {
+    Value(AccountSummaryValue),
+    /// All values have been sent once; changes follow every three minutes
+    End,
+    Error {
+        code: i32,
+        message: String,
+    },
+}
@@ -0,0 +1,454 @@
+//! Typed account values and portfolio of `EClient::request_account_updates`
+//!
+//! TWS first sends all values and portfolio rows of the account, then
+//! `account_download_end`, and from then on changes as they happen, the
+//! values at most every three minutes. `AccountUpdates` collects them into an
+//! `AccountSnapshot` that is only handed out once the first batch is
+//! complete.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use bigdecimal::{BigDecimal, Zero};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::contract::Contract;
+
+//==================================================================================================
+/// The key of an account value. Keys this crate does not know are kept as
+/// `Other`, e.g. the values of commodity segments like `NetLiquidation-C`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AccountValueKey {
+    AccountCode,
+    AccountReady,
+    AccountType,
+    AccruedCash,
+    AvailableFunds,
+    BuyingPower,
+    CashBalance,
+    Currency,
+    Cushion,
+    DayTradesRemaining,
+    EquityWithLoanValue,
+    ExcessLiquidity,
+    ExchangeRate,
+    FullAvailableFunds,
+    FullExcessLiquidity,
+    FullInitMarginReq,
+    FullMaintMarginReq,
+    FutureOptionValue,
+    FuturesPnL,
+    GrossPositionValue,
+    InitMarginReq,
+    MaintMarginReq,
+    NetDividend,
+    NetLiquidation,
+    NetLiquidationByCurrency,
+    OptionMarketValue,
+    RealizedPnL,
+    SettledCash,
+    Sma,
+    StockMarketValue,
+    TotalCashBalance,
+    TotalCashValue,
+    UnrealizedPnL,
+    Other(String),
+}
+
+impl fmt::Display for AccountValueKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match self {
+            AccountValueKey::AccountCode => "AccountCode",
+            AccountValueKey::AccountReady => "AccountReady",
+            AccountValueKey::AccountType => "AccountType",
+            AccountValueKey::AccruedCash => "AccruedCash",
+            AccountValueKey::AvailableFunds => "AvailableFunds",
+            AccountValueKey::BuyingPower => "BuyingPower",
+            AccountValueKey::CashBalance => "CashBalance",
+            AccountValueKey::Currency => "Currency",
+            AccountValueKey::Cushion => "Cushion",
+            AccountValueKey::DayTradesRemaining => "DayTradesRemaining",
+            AccountValueKey::EquityWithLoanValue => "EquityWithLoanValue",
+            AccountValueKey::ExcessLiquidity => "ExcessLiquidity",
+            AccountValueKey::ExchangeRate => "ExchangeRate",
+            AccountValueKey::FullAvailableFunds => "FullAvailableFunds",
+            AccountValueKey::FullExcessLiquidity => "FullExcessLiquidity",
+            AccountValueKey::FullInitMarginReq => "FullInitMarginReq",
+            AccountValueKey::FullMaintMarginReq => "FullMaintMarginReq",
+            AccountValueKey::FutureOptionValue => "FutureOptionValue",
+            AccountValueKey::FuturesPnL => "FuturesPNL",
+            AccountValueKey::GrossPositionValue => "GrossPositionValue",
+            AccountValueKey::InitMarginReq => "InitMarginReq",
+            AccountValueKey::MaintMarginReq => "MaintMarginReq",
+            AccountValueKey::NetDividend => "NetDividend",
+            AccountValueKey::NetLiquidation => "NetLiquidation",
+            AccountValueKey::NetLiquidationByCurrency => "NetLiquidationByCurrency",
+            AccountValueKey::OptionMarketValue => "OptionMarketValue",
+            AccountValueKey::RealizedPnL => "RealizedPnL",
+            AccountValueKey::SettledCash => "SettledCash",
+            AccountValueKey::Sma => "SMA",
+            AccountValueKey::StockMarketValue => "StockMarketValue",
+            AccountValueKey::TotalCashBalance => "TotalCashBalance",
+            AccountValueKey::TotalCashValue => "TotalCashValue",
+            AccountValueKey::UnrealizedPnL => "UnrealizedPnL",
+            AccountValueKey::Other(key) => key,
+        };
+        write!(f, "{}", key)
+    }
+}
+
+impl From<&str> for AccountValueKey {
+    fn from(key: &str) -> Self {
+        match key.trim() {
+            "AccountCode" => AccountValueKey::AccountCode,
+            "AccountReady" => AccountValueKey::AccountReady,
+            "AccountType" => AccountValueKey::AccountType,
+            "AccruedCash" => AccountValueKey::AccruedCash,
+            "AvailableFunds" => AccountValueKey::AvailableFunds,
+            "BuyingPower" => AccountValueKey::BuyingPower,
+            "CashBalance" => AccountValueKey::CashBalance,
+            "Currency" => AccountValueKey::Currency,
+            "Cushion" => AccountValueKey::Cushion,
+            "DayTradesRemaining" => AccountValueKey::DayTradesRemaining,
+            "EquityWithLoanValue" => AccountValueKey::EquityWithLoanValue,
+            "ExcessLiquidity" => AccountValueKey::ExcessLiquidity,
+            "ExchangeRate" => AccountValueKey::ExchangeRate,
+            "FullAvailableFunds" => AccountValueKey::FullAvailableFunds,
+            "FullExcessLiquidity" => AccountValueKey::FullExcessLiquidity,
+            "FullInitMarginReq" => AccountValueKey::FullInitMarginReq,
+            "FullMaintMarginReq" => AccountValueKey::FullMaintMarginReq,
+            "FutureOptionValue" => AccountValueKey::FutureOptionValue,
+            "FuturesPNL" => AccountValueKey::FuturesPnL,
+            "GrossPositionValue" => AccountValueKey::GrossPositionValue,
+            "InitMarginReq" => AccountValueKey::InitMarginReq,
+            "MaintMarginReq" => AccountValueKey::MaintMarginReq,
+            "NetDividend" => AccountValueKey::NetDividend,
+            "NetLiquidation" => AccountValueKey::NetLiquidation,
+            "NetLiquidationByCurrency" => AccountValueKey::NetLiquidationByCurrency,
+            "OptionMarketValue" => AccountValueKey::OptionMarketValue,
+            "RealizedPnL" => AccountValueKey::RealizedPnL,
+            "SettledCash" => AccountValueKey::SettledCash,
+            "SMA" => AccountValueKey::Sma,
+            "StockMarketValue" => AccountValueKey::StockMarketValue,
+            "TotalCashBalance" => AccountValueKey::TotalCashBalance,
+            "TotalCashValue" => AccountValueKey::TotalCashValue,
+            "UnrealizedPnL" => AccountValueKey::UnrealizedPnL,
+            key => AccountValueKey::Other(key.to_string()),
+        }
+    }
+}
+
+//==================================================================================================
+/// One value of an account
+///
+/// value - the value as a number, `None` for text values like the
+///         AccountType
+/// text - the value as sent by TWS
+/// currency - the currency of the value; BASE for the sum over all
+///            currencies of the per currency values like CashBalance
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountValue {
+    pub key: AccountValueKey,
+    pub value: Option<f64>,
+    pub text: String,
+    pub currency: String,
+}
+
+impl AccountValue {
+    pub fn new(key: &str, text: &str, currency: &str) -> Self {
+        AccountValue {
+            key: AccountValueKey::from(key),
+            value: text
+                .trim()
+                .parse()
+                .ok()
+                .filter(|value: &f64| value.is_finite()),
+            text: text.to_string(),
+            currency: currency.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AccountValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key: {}, value: {}, currency: {}",
+            self.key, self.text, self.currency
+        )
+    }
+}
+
+//==================================================================================================
+/// One position of the portfolio of an account
+///
+/// position - the size of the position, negative if short
+/// market_value - the value of the position in the currency of the contract
+/// average_cost - the average cost per contract, including the multiplier
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PortfolioItem {
+    pub contract: Contract,
+    pub position: BigDecimal,
+    pub market_price: f64,
+    pub market_value: BigDecimal,
+    pub average_cost: BigDecimal,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+}
+
+impl PortfolioItem {
+    pub fn new(
+        contract: Contract,
+        position: BigDecimal,
+        market_price: f64,
+        market_value: BigDecimal,
+        average_cost: BigDecimal,
+        unrealized_pnl: f64,
+        realized_pnl: f64,
+    ) -> Self {
+        PortfolioItem {
+            contract,
+            position,
+            market_price,
+            market_value,
+            average_cost,
+            unrealized_pnl,
+            realized_pnl,
+        }
+    }
+}
+
+impl fmt::Display for PortfolioItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "local_symbol: {}, position: {}, market_price: {}, market_value: {}, average_cost: {}, \
+             unrealized_pnl: {}, realized_pnl: {}",
+            self.contract.local_symbol,
+            self.position,
+            self.market_price,
+            self.market_value,
+            self.average_cost,
+            self.unrealized_pnl,
+            self.realized_pnl
+        )
+    }
+}
+
+//==================================================================================================
+/// Event of an account updates request streamed through a channel instead
+/// of the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum AccountUpdateEvent {
+    Value {
+        account: String,
+        value: AccountValue,
+    },
+    Portfolio {
+        account: String,
+        item: Box<PortfolioItem>,
+    },
+    /// The time of the last update, as HH:MM
+    Time(String),
+    /// The first batch of values and positions of the account is complete
+    End(String),
+}
+
+//==================================================================================================
+/// An account value of an account and/or model, as sent for
+/// `EClient::request_account_updates_multi`
+///
+/// model_code - the model of the value, empty for the whole account
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModelAccountValue {
+    pub account: String,
+    pub model_code: String,
+    pub value: AccountValue,
+}
+
+impl ModelAccountValue {
+    pub fn new(account: &str, model_code: &str, value: AccountValue) -> Self {
+        ModelAccountValue {
+            account: account.to_string(),
+            model_code: model_code.to_string(),
+            value,
+        }
+    }
+}
+
+impl fmt::Display for ModelAccountValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, model_code: {}, {}",
+            self.account, self.model_code, self.value
+        )
+    }
+}
+
+//==================================================================================================
+/// Event of an account updates multi request streamed through a channel
+/// instead of the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum AccountUpdateMultiEvent {
+    Value(ModelAccountValue),
+    /// All values have been sent once
+    End,
+    /// The request failed
+    Error {
+        code: i32,
+        message: String,
+    },
+}
+
+//==================================================================================================
+/// The values and positions of an account
+///
+/// updated - the time of the last update, as HH:MM
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountSnapshot {
+    pub account: String,
+    pub values: Vec<AccountValue>,
+    pub portfolio: Vec<PortfolioItem>,
+    pub updated: String,
+}
+
+impl AccountSnapshot {
+    pub fn new(account: &str) -> Self {
+        AccountSnapshot {
+            account: account.to_string(),
+            ..Default::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn get(&self, key: &AccountValueKey, currency: &str) -> Option<&AccountValue> {
+        self.values
+            .iter()
+            .find(|value| value.key == *key && value.currency == currency)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The numeric value of a key in a currency
+    pub fn value(&self, key: &AccountValueKey, currency: &str) -> Option<f64> {
+        self.get(key, currency).and_then(|value| value.value)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The position in a contract
+    pub fn position(&self, con_id: i32) -> Option<&PortfolioItem> {
+        self.portfolio
+            .iter()
+            .find(|item| item.contract.con_id == con_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replaces the value of the same key and currency
+    pub fn update_value(&mut self, value: AccountValue) {
+        match self
+            .values
+            .iter_mut()
+            .find(|old| old.key == value.key && old.currency == value.currency)
+        {
+            Some(old) => *old = value,
+            None => self.values.push(value),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replaces the position in the same contract. Positions that have been
+    /// closed are removed.
+    pub fn update_portfolio(&mut self, item: PortfolioItem) {
+        let con_id = item.contract.con_id;
+        if item.position.is_zero() {
+            self.portfolio.retain(|old| old.contract.con_id != con_id);
+        } else {
+            match self
+                .portfolio
+                .iter_mut()
+                .find(|old| old.contract.con_id == con_id)
+            {
+                Some(old) => *old = item,
+                None => self.portfolio.push(item),
+            }
+        }
+    }
+}
+
+impl fmt::Display for AccountSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, values: {}, positions: {}, updated: {}",
+            self.account,
+            self.values.len(),
+            self.portfolio.len(),
+            self.updated
+        )
+    }
+}
+
+//==================================================================================================
+/// Collects the events of an account updates request into a snapshot per
+/// account
+#[derive(Clone, Debug, Default)]
+pub struct AccountUpdates {
+    accounts: BTreeMap<String, AccountSnapshot>,
+    downloaded: BTreeSet<String>,
+}
+
+impl AccountUpdates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an event. Returns the snapshot of the account when its first
+    /// batch is complete.
+    pub fn apply(&mut self, event: AccountUpdateEvent) -> Option<&AccountSnapshot> {
+        match event {
+            AccountUpdateEvent::Value { account, value } => {
+                self.account(&account).update_value(value);
+                None
+            }
+            AccountUpdateEvent::Portfolio { account, item } => {
+                self.account(&account).update_portfolio(*item);
+                None
+            }
+            // Only one account is subscribed at a time
+            AccountUpdateEvent::Time(time) => {
+                for snapshot in self.accounts.values_mut() {
+                    snapshot.updated = time.clone();
+                }
+                None
+            }
+            AccountUpdateEvent::End(account) => {
+                self.account(&account);
+                self.downloaded.insert(account.clone());
+                self.accounts.get(&account)
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The snapshot of an account, once its first batch is complete
+    pub fn snapshot(&self, account: &str) -> Option<&AccountSnapshot> {
+        if self.downloaded.contains(account) {
+            self.accounts.get(account)
+        } else {
+            None
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn account(&mut self, account: &str) -> &mut AccountSnapshot {
+        self.accounts
+            .entry(account.to_string())
+            .or_insert_with(|| AccountSnapshot::new(account))
+    }
+}
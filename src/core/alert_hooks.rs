@@ -0,0 +1,294 @@
+//! Alert hooks for critical events
+//!
+//! `AlertHooks` is a registry of `AlertHook`s, each registered for the
+//! `AlertCondition`s it is to be called on: the connection to TWS or that of
+//! TWS to the IB servers being lost, rejected orders, errors of a category
+//! or with a code, fills and account values crossing a threshold, e.g. of
+//! `ExcessLiquidity`. It gives paging and monitoring one place to integrate.
+//!
+//! Set with `EClient::set_alert_hooks`, the hooks are called from the
+//! decoder thread as TWS reports the events, so they should hand the alert
+//! off, e.g. to a channel, rather than block.
+use std::fmt;
+
+use crate::core::account_alerts::{
+    AccountAlert, AccountAlertKind, AccountAlerts, AccountThreshold,
+};
+use crate::core::account_updates::AccountValue;
+use crate::core::contract::Contract;
+use crate::core::error_codes::{lookup, ErrorCategory, CONNECTIVITY_LOST, ORDER_REJECTED};
+use crate::core::execution::Execution;
+
+//==================================================================================================
+/// An event an alert hook is called on
+///
+/// ConnectionLost - code is None if the connection to TWS was lost, the
+///   error code if TWS lost its connection to the IB servers
+/// MarginThreshold - an account value that entered or left the alert zone
+///   of a threshold watched with `AlertHooks::watch`
+#[derive(Clone, Debug)]
+pub enum AlertEvent {
+    ConnectionLost {
+        code: Option<i32>,
+        message: String,
+    },
+    OrderRejected {
+        order_id: i32,
+        code: i32,
+        message: String,
+    },
+    Error {
+        request_id: i32,
+        code: i32,
+        message: String,
+    },
+    Fill {
+        contract: Box<Contract>,
+        execution: Box<Execution>,
+    },
+    MarginThreshold(AccountAlert),
+}
+
+impl AlertEvent {
+    /// The error code TWS sent with the event, if any
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            AlertEvent::ConnectionLost { code, .. } => *code,
+            AlertEvent::OrderRejected { code, .. } | AlertEvent::Error { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The category of the error code, if it is a known one
+    pub fn category(&self) -> Option<ErrorCategory> {
+        self.code().and_then(lookup).map(|info| info.category)
+    }
+}
+
+impl fmt::Display for AlertEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertEvent::ConnectionLost {
+                code: Some(code),
+                message,
+            } => write!(f, "connection lost ({}): {}", code, message),
+            AlertEvent::ConnectionLost {
+                code: None,
+                message,
+            } => write!(f, "connection lost: {}", message),
+            AlertEvent::OrderRejected {
+                order_id,
+                code,
+                message,
+            } => write!(f, "order {} rejected ({}): {}", order_id, code, message),
+            AlertEvent::Error {
+                request_id,
+                code,
+                message,
+            } => write!(f, "error {} of request {}: {}", code, request_id, message),
+            AlertEvent::Fill {
+                contract,
+                execution,
+            } => write!(
+                f,
+                "fill of order {}: {} {} {} @ {}",
+                execution.order_id,
+                execution.side,
+                execution.shares,
+                contract.symbol,
+                execution.price
+            ),
+            AlertEvent::MarginThreshold(alert) => {
+                write!(f, "account value {:?}: {}", alert.kind, alert)
+            }
+        }
+    }
+}
+
+//==================================================================================================
+/// What a hook is called on
+///
+/// ErrorCategory, ErrorCode - any event with an error code of the category
+///   or the code, including rejections and the loss of the connection of TWS
+/// MarginThreshold - both breaches and recoveries of a threshold
+/// MarginBreached - the breaches only
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertCondition {
+    ConnectionLost,
+    OrderRejected,
+    ErrorCategory(ErrorCategory),
+    ErrorCode(i32),
+    FillReceived,
+    MarginThreshold,
+    MarginBreached,
+}
+
+impl AlertCondition {
+    /// Whether an event meets the condition
+    pub fn matches(&self, event: &AlertEvent) -> bool {
+        match (self, event) {
+            (AlertCondition::ConnectionLost, AlertEvent::ConnectionLost { .. })
+            | (AlertCondition::OrderRejected, AlertEvent::OrderRejected { .. })
+            | (AlertCondition::FillReceived, AlertEvent::Fill { .. })
+            | (AlertCondition::MarginThreshold, AlertEvent::MarginThreshold(_)) => true,
+            (AlertCondition::MarginBreached, AlertEvent::MarginThreshold(alert)) => {
+                alert.kind == AccountAlertKind::Breached
+            }
+            (AlertCondition::ErrorCategory(category), _) => event.category() == Some(*category),
+            (AlertCondition::ErrorCode(code), _) => event.code() == Some(*code),
+            _ => false,
+        }
+    }
+}
+
+//==================================================================================================
+/// Gets the events of the conditions it was registered for, e.g. to page
+/// someone. Closures taking an `&AlertEvent` are hooks too.
+pub trait AlertHook: Send {
+    fn alert(&mut self, event: &AlertEvent);
+}
+
+impl<F: FnMut(&AlertEvent) + Send> AlertHook for F {
+    fn alert(&mut self, event: &AlertEvent) {
+        self(event)
+    }
+}
+
+//==================================================================================================
+/// Hooks with the conditions they are called on
+#[derive(Default)]
+pub struct AlertHooks {
+    hooks: Vec<(Vec<AlertCondition>, Box<dyn AlertHook>)>,
+    thresholds: AccountAlerts,
+}
+
+impl AlertHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a hook called on the events that meet any of `conditions`
+    pub fn hook<H: AlertHook + 'static>(mut self, conditions: &[AlertCondition], hook: H) -> Self {
+        self.add(conditions, Box::new(hook));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn add(&mut self, conditions: &[AlertCondition], hook: Box<dyn AlertHook>) {
+        self.hooks.push((conditions.to_vec(), hook));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Watches a threshold of the account values, e.g. `ExcessLiquidity`
+    /// below a level, for the `MarginThreshold` and `MarginBreached` conditions
+    pub fn watch(mut self, threshold: AccountThreshold) -> Self {
+        self.thresholds.add_threshold(threshold);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn thresholds(&self) -> &[AccountThreshold] {
+        self.thresholds.thresholds()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls the hooks whose conditions the event meets, each once. Returns
+    /// how many were called.
+    pub fn notify(&mut self, event: &AlertEvent) -> usize {
+        let mut called = 0;
+        for (conditions, hook) in self.hooks.iter_mut() {
+            if conditions.iter().any(|condition| condition.matches(event)) {
+                hook.alert(event);
+                called += 1;
+            }
+        }
+        called
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an error TWS sent with the `error` callback. Code 201 is an
+    /// order rejection and code 1100 the loss of the connection of TWS.
+    pub fn apply_error(&mut self, request_id: i32, code: i32, message: &str) -> usize {
+        let message = message.to_string();
+        let event = match code {
+            ORDER_REJECTED => AlertEvent::OrderRejected {
+                order_id: request_id,
+                code,
+                message,
+            },
+            CONNECTIVITY_LOST => AlertEvent::ConnectionLost {
+                code: Some(code),
+                message,
+            },
+            _ => AlertEvent::Error {
+                request_id,
+                code,
+                message,
+            },
+        };
+        self.notify(&event)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies the loss of the connection to TWS
+    pub fn apply_connection_lost(&mut self, message: &str) -> usize {
+        self.notify(&AlertEvent::ConnectionLost {
+            code: None,
+            message: message.to_string(),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an execution of the `exec_details` callback
+    pub fn apply_execution(&mut self, contract: &Contract, execution: &Execution) -> usize {
+        self.notify(&AlertEvent::Fill {
+            contract: Box::new(contract.clone()),
+            execution: Box::new(execution.clone()),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an account value of the account updates, calling the hooks
+    /// for each watched threshold it crossed
+    pub fn apply_account_value(&mut self, account: &str, value: &AccountValue) -> usize {
+        self.thresholds
+            .apply_value(account, value)
+            .into_iter()
+            .map(|alert| self.notify(&AlertEvent::MarginThreshold(alert)))
+            .sum()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forgets the account values received, e.g. on reconnect
+    pub fn clear(&mut self) {
+        self.thresholds.clear();
+    }
+}
+
+impl fmt::Debug for AlertHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let conditions: Vec<&Vec<AlertCondition>> = self
+            .hooks
+            .iter()
+            .map(|(conditions, _)| conditions)
+            .collect();
+        write!(
+            f,
+            "AlertHooks {{ hooks: {:?}, thresholds: {:?} }}",
+            conditions,
+            self.thresholds.thresholds()
+        )
+    }
+}
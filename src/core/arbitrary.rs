@@ -0,0 +1,436 @@
+//! Proptest strategies for orders, contracts, conditions and executions
+//!
+//! `Arbitrary` is implemented for `Contract`, `Order`, `OrderConditionEnum`,
+//! `Execution` and `ExecutionFilter`, so `any::<Order>()` can be used in
+//! `proptest!` blocks together with the functions of `core::round_trip`.
+//! Generated values stay within what TWS sends and accepts: prices are whole
+//! cents, strings are short and hold no field separators, and numbers are
+//! never the `UNSET_INTEGER` and `UNSET_DOUBLE` markers, which are encoded as
+//! empty fields and do not come back as themselves.
+use proptest::prelude::*;
+
+use crate::core::contract::{Contract, Right, SecType};
+use crate::core::execution::{Execution, ExecutionFilter};
+use crate::core::order::Order;
+use crate::core::order_condition::{
+    ExecutionCondition, MarginCondition, OrderCondition, OrderConditionEnum,
+    PercentChangeCondition, PriceCondition, TimeCondition, TriggerMethod, VolumeCondition,
+};
+
+//==================================================================================================
+/// A price in whole cents up to 100000
+pub fn price() -> impl Strategy<Value = f64> {
+    (0..10_000_000i64).prop_map(|cents| cents as f64 / 100.0)
+}
+
+//==================================================================================================
+/// A quantity in hundredths up to 100000, never 0
+pub fn quantity() -> impl Strategy<Value = f64> {
+    (1..10_000_000i64).prop_map(|hundredths| hundredths as f64 / 100.0)
+}
+
+//==================================================================================================
+/// A short text of letters, digits and the punctuation TWS uses in ids
+pub fn text() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9.:_-]{0,12}"
+}
+
+//==================================================================================================
+pub fn symbol() -> impl Strategy<Value = String> {
+    "[A-Z]{1,5}"
+}
+
+//==================================================================================================
+pub fn exchange() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("SMART"),
+        Just("NYSE"),
+        Just("NASDAQ"),
+        Just("ARCA"),
+        Just("CME"),
+        Just("GLOBEX"),
+        Just("IDEALPRO"),
+        Just("CBOE"),
+    ]
+    .prop_map(String::from)
+}
+
+//==================================================================================================
+pub fn currency() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("USD"),
+        Just("EUR"),
+        Just("GBP"),
+        Just("JPY"),
+        Just("CHF"),
+        Just("CAD"),
+    ]
+    .prop_map(String::from)
+}
+
+//==================================================================================================
+/// A date as `yyyyMMdd` in 2020 to 2039
+pub fn date() -> impl Strategy<Value = String> {
+    (2020..2040u32, 1..=12u32, 1..=28u32)
+        .prop_map(|(year, month, day)| format!("{}{:02}{:02}", year, month, day))
+}
+
+//==================================================================================================
+/// A time as `yyyyMMdd HH:mm:ss`
+pub fn date_time() -> impl Strategy<Value = String> {
+    (date(), 0..24u32, 0..60u32, 0..60u32).prop_map(|(date, hour, minute, second)| {
+        format!("{} {:02}:{:02}:{:02}", date, hour, minute, second)
+    })
+}
+
+//==================================================================================================
+/// One of the security types with a wire form
+pub fn sec_type() -> impl Strategy<Value = SecType> {
+    prop_oneof![
+        Just(SecType::Stock),
+        Just(SecType::Option),
+        Just(SecType::Future),
+        Just(SecType::ContinuousFuture),
+        Just(SecType::FuturesOption),
+        Just(SecType::Forex),
+        Just(SecType::Combo),
+        Just(SecType::Bond),
+        Just(SecType::Bill),
+        Just(SecType::Cfd),
+        Just(SecType::Fund),
+        Just(SecType::Commodity),
+        Just(SecType::Crypto),
+        Just(SecType::Warrant),
+        Just(SecType::StructuredProduct),
+        Just(SecType::Forward),
+        Just(SecType::Index),
+        Just(SecType::News),
+    ]
+}
+
+//==================================================================================================
+pub fn right() -> impl Strategy<Value = Right> {
+    prop_oneof![Just(Right::Call), Just(Right::Put), Just(Right::None)]
+}
+
+//==================================================================================================
+pub fn trigger_method() -> impl Strategy<Value = TriggerMethod> {
+    prop_oneof![
+        Just(TriggerMethod::Default),
+        Just(TriggerMethod::DoubleBidAsk),
+        Just(TriggerMethod::Last),
+        Just(TriggerMethod::DoubleLast),
+        Just(TriggerMethod::BidAsk),
+        Just(TriggerMethod::LastBidAsk),
+        Just(TriggerMethod::MidPoint),
+    ]
+}
+
+//==================================================================================================
+impl Arbitrary for Contract {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            (1..i32::MAX, symbol(), sec_type(), date(), price(), right()),
+            (
+                prop_oneof![Just(String::new()), Just("100".to_string())],
+                exchange(),
+                exchange(),
+                currency(),
+                text(),
+                text(),
+            ),
+        )
+            .prop_map(
+                |(
+                    (con_id, symbol, sec_type, last_trade_date_or_contract_month, strike, right),
+                    (multiplier, exchange, primary_exchange, currency, local_symbol, trading_class),
+                )| Contract {
+                    con_id,
+                    symbol,
+                    sec_type,
+                    last_trade_date_or_contract_month,
+                    strike,
+                    right,
+                    multiplier,
+                    exchange,
+                    primary_exchange,
+                    currency,
+                    local_symbol,
+                    trading_class,
+                    ..Default::default()
+                },
+            )
+            .boxed()
+    }
+}
+
+//==================================================================================================
+/// The connector to the condition before a condition, `and` if true
+fn order_condition(condition: &mut OrderConditionEnum) -> &mut OrderCondition {
+    match condition {
+        OrderConditionEnum::Execution(c) => &mut c.order_condition,
+        OrderConditionEnum::Margin(c) => &mut c.operator_condition.order_condition,
+        OrderConditionEnum::Time(c) => &mut c.operator_condition.order_condition,
+        OrderConditionEnum::Price(c) => {
+            &mut c.contract_condition.operator_condition.order_condition
+        }
+        OrderConditionEnum::Volume(c) => {
+            &mut c.contract_condition.operator_condition.order_condition
+        }
+        OrderConditionEnum::PercentChange(c) => {
+            &mut c.contract_condition.operator_condition.order_condition
+        }
+    }
+}
+
+impl Arbitrary for OrderConditionEnum {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        let condition = prop_oneof![
+            (
+                trigger_method(),
+                1..i32::MAX,
+                exchange(),
+                any::<bool>(),
+                price()
+            )
+                .prop_map(|(trigger_method, con_id, exchange, is_more, price)| {
+                    OrderConditionEnum::Price(PriceCondition::new(
+                        trigger_method,
+                        con_id,
+                        &exchange,
+                        is_more,
+                        price,
+                    ))
+                }),
+            (any::<bool>(), date_time()).prop_map(|(is_more, time)| {
+                OrderConditionEnum::Time(TimeCondition::new(is_more, time))
+            }),
+            (any::<bool>(), 0..100i32).prop_map(|(is_more, percent)| {
+                OrderConditionEnum::Margin(MarginCondition::new(is_more, percent as f64))
+            }),
+            (sec_type(), exchange(), symbol()).prop_map(|(sec_type, exchange, symbol)| {
+                OrderConditionEnum::Execution(ExecutionCondition::new(sec_type, exchange, symbol))
+            }),
+            (1..i32::MAX, exchange(), any::<bool>(), 0..i32::MAX).prop_map(
+                |(con_id, exchange, is_more, volume)| {
+                    OrderConditionEnum::Volume(VolumeCondition::new(
+                        con_id, &exchange, is_more, volume,
+                    ))
+                }
+            ),
+            (1..i32::MAX, exchange(), any::<bool>(), -10_000..10_000i32).prop_map(
+                |(con_id, exchange, is_more, basis_points)| {
+                    OrderConditionEnum::PercentChange(PercentChangeCondition::new(
+                        con_id,
+                        exchange,
+                        is_more,
+                        basis_points as f64 / 100.0,
+                    ))
+                }
+            ),
+        ];
+        (condition, any::<bool>())
+            .prop_map(|(mut condition, is_conjunction_connection)| {
+                order_condition(&mut condition).is_conjunction_connection =
+                    is_conjunction_connection;
+                condition
+            })
+            .boxed()
+    }
+}
+
+//==================================================================================================
+impl Arbitrary for Order {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            (
+                0..i32::MAX,
+                0..1000i32,
+                0..i32::MAX,
+                prop_oneof![Just("BUY"), Just("SELL")],
+                quantity(),
+                prop_oneof![
+                    Just("MKT"),
+                    Just("LMT"),
+                    Just("STP"),
+                    Just("STP LMT"),
+                    Just("TRAIL"),
+                    Just("MOC"),
+                ],
+                price(),
+                price(),
+            ),
+            (
+                prop_oneof![Just("DAY"), Just("GTC"), Just("IOC"), Just("OPG")],
+                text(),
+                text(),
+                any::<bool>(),
+                any::<bool>(),
+                prop::collection::vec(any::<OrderConditionEnum>(), 0..3),
+                any::<bool>(),
+                any::<bool>(),
+            ),
+        )
+            .prop_map(
+                |(
+                    (
+                        order_id,
+                        client_id,
+                        perm_id,
+                        action,
+                        total_quantity,
+                        order_type,
+                        lmt_price,
+                        aux_price,
+                    ),
+                    (
+                        tif,
+                        order_ref,
+                        account,
+                        outside_rth,
+                        transmit,
+                        conditions,
+                        conditions_cancel_order,
+                        conditions_ignore_rth,
+                    ),
+                )| Order {
+                    order_id,
+                    client_id,
+                    perm_id,
+                    action: action.to_string(),
+                    total_quantity,
+                    order_type: order_type.to_string(),
+                    lmt_price,
+                    aux_price,
+                    tif: tif.to_string(),
+                    order_ref,
+                    account,
+                    outside_rth,
+                    transmit,
+                    conditions,
+                    conditions_cancel_order,
+                    conditions_ignore_rth,
+                    ..Default::default()
+                },
+            )
+            .boxed()
+    }
+}
+
+//==================================================================================================
+impl Arbitrary for Execution {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            (
+                "[0-9a-f]{8}\\.[0-9a-f]{8}\\.01\\.01",
+                date_time(),
+                "[DU][0-9]{6,7}",
+                exchange(),
+                prop_oneof![Just("BOT"), Just("SLD")],
+                quantity(),
+                price(),
+                0..i32::MAX,
+                0..1000i32,
+                0..i32::MAX,
+            ),
+            (
+                0..2i32,
+                quantity(),
+                price(),
+                text(),
+                text(),
+                (1..1000i32).prop_map(|multiplier| multiplier as f64),
+                text(),
+                0..4i32,
+            ),
+        )
+            .prop_map(
+                |(
+                    (
+                        exec_id,
+                        time,
+                        acct_number,
+                        exchange,
+                        side,
+                        shares,
+                        price,
+                        perm_id,
+                        client_id,
+                        order_id,
+                    ),
+                    (
+                        liquidation,
+                        cum_qty,
+                        avg_price,
+                        order_ref,
+                        ev_rule,
+                        ev_multiplier,
+                        model_code,
+                        last_liquidity,
+                    ),
+                )| Execution {
+                    exec_id,
+                    time,
+                    acct_number,
+                    exchange,
+                    side: side.to_string(),
+                    shares,
+                    price,
+                    perm_id,
+                    client_id,
+                    order_id,
+                    liquidation,
+                    cum_qty,
+                    avg_price,
+                    order_ref,
+                    ev_rule,
+                    ev_multiplier,
+                    model_code,
+                    last_liquidity,
+                },
+            )
+            .boxed()
+    }
+}
+
+//==================================================================================================
+impl Arbitrary for ExecutionFilter {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (
+            0..1000i32,
+            text(),
+            prop_oneof![Just(String::new()), date_time()],
+            prop_oneof![Just(String::new()), symbol()],
+            sec_type(),
+            prop_oneof![Just(String::new()), exchange()],
+            prop_oneof![Just(""), Just("BUY"), Just("SELL")],
+        )
+            .prop_map(
+                |(client_id, acct_code, time, symbol, sec_type, exchange, side)| ExecutionFilter {
+                    client_id,
+                    acct_code,
+                    time,
+                    symbol,
+                    sec_type,
+                    exchange,
+                    side: side.to_string(),
+                },
+            )
+            .boxed()
+    }
+}
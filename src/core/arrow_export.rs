@@ -0,0 +1,401 @@
+//! Conversion of bars, ticks and volatility surfaces to Apache Arrow record
+//! batches and Parquet files, e.g. for loading them into pandas, polars or
+//! DuckDB
+//!
+//! Times are stored as UTC timestamps. The schemas carry a
+//! `twsapi.schema_version` metadata entry that is only changed when columns
+//! are renamed, removed or change their type.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, BooleanArray, Date32Array, Float64Array, Int32Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray, TimestampSecondArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::NaiveDate;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+
+use crate::core::common::{BarData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast};
+use crate::core::historical::HistoricalTicks;
+use crate::core::historical_downloader::bar_time;
+use crate::core::tick_recorder::RecordedTick;
+use crate::core::vol_surface::VolSurfaceSnapshot;
+
+/// Version of the schemas, stored in their metadata. Version 2 stores sizes
+/// and volumes as decimals.
+pub const SCHEMA_VERSION: &str = "2";
+
+//==================================================================================================
+fn schema(fields: Vec<Field>) -> SchemaRef {
+    let metadata = HashMap::from([(
+        "twsapi.schema_version".to_string(),
+        SCHEMA_VERSION.to_string(),
+    )]);
+    Arc::new(Schema::new_with_metadata(fields, metadata))
+}
+
+//==================================================================================================
+fn time_field(unit: TimeUnit, nullable: bool) -> Field {
+    Field::new(
+        "time",
+        DataType::Timestamp(unit, Some("UTC".into())),
+        nullable,
+    )
+}
+
+//==================================================================================================
+/// Schema of `bars_to_record_batch`. The time is null for bars whose date is
+/// neither seconds since the epoch nor `YYYYMMDD`; the date column keeps the
+/// date as received.
+pub fn bar_schema() -> SchemaRef {
+    schema(vec![
+        time_field(TimeUnit::Second, true),
+        Field::new("date", DataType::Utf8, false),
+        Field::new("open", DataType::Float64, false),
+        Field::new("high", DataType::Float64, false),
+        Field::new("low", DataType::Float64, false),
+        Field::new("close", DataType::Float64, false),
+        Field::new("volume", DataType::Float64, false),
+        Field::new("bar_count", DataType::Int32, false),
+        Field::new("average", DataType::Float64, false),
+    ])
+}
+
+//==================================================================================================
+/// Schema of `historical_ticks_to_record_batch` for `HistoricalTicks::Trades`
+pub fn trade_tick_schema() -> SchemaRef {
+    schema(vec![
+        time_field(TimeUnit::Second, false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("size", DataType::Float64, false),
+        Field::new("exchange", DataType::Utf8, false),
+        Field::new("special_conditions", DataType::Utf8, false),
+        Field::new("past_limit", DataType::Boolean, false),
+        Field::new("unreported", DataType::Boolean, false),
+    ])
+}
+
+//==================================================================================================
+/// Schema of `historical_ticks_to_record_batch` for `HistoricalTicks::BidAsk`
+pub fn bid_ask_tick_schema() -> SchemaRef {
+    schema(vec![
+        time_field(TimeUnit::Second, false),
+        Field::new("price_bid", DataType::Float64, false),
+        Field::new("price_ask", DataType::Float64, false),
+        Field::new("size_bid", DataType::Float64, false),
+        Field::new("size_ask", DataType::Float64, false),
+        Field::new("bid_past_low", DataType::Boolean, false),
+        Field::new("ask_past_high", DataType::Boolean, false),
+    ])
+}
+
+//==================================================================================================
+/// Schema of `historical_ticks_to_record_batch` for `HistoricalTicks::Midpoint`
+pub fn midpoint_tick_schema() -> SchemaRef {
+    schema(vec![
+        time_field(TimeUnit::Second, false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("size", DataType::Float64, false),
+    ])
+}
+
+//==================================================================================================
+/// Schema of `recorded_ticks_to_record_batch`
+pub fn recorded_tick_schema() -> SchemaRef {
+    schema(vec![
+        time_field(TimeUnit::Microsecond, false),
+        Field::new("ticker_id", DataType::Int32, false),
+        Field::new("tick_type", DataType::Utf8, false),
+        Field::new("price", DataType::Float64, true),
+        Field::new("size", DataType::Float64, true),
+    ])
+}
+
+//==================================================================================================
+/// Schema of `vol_surface_to_record_batch`: one row per point, with the
+/// time and spot of the snapshot repeated, so snapshots of several days can
+/// be appended into one table. `point_time` is when the volatility of the
+/// point was received.
+pub fn vol_surface_schema() -> SchemaRef {
+    schema(vec![
+        time_field(TimeUnit::Microsecond, false),
+        Field::new("underlying", DataType::Utf8, false),
+        Field::new("spot", DataType::Float64, true),
+        Field::new("expiry", DataType::Date32, false),
+        Field::new("strike", DataType::Float64, false),
+        Field::new("right", DataType::Utf8, false),
+        Field::new("implied_vol", DataType::Float64, false),
+        Field::new("delta", DataType::Float64, true),
+        Field::new("und_price", DataType::Float64, true),
+        Field::new(
+            "point_time",
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            false,
+        ),
+    ])
+}
+
+//==================================================================================================
+pub fn bars_to_record_batch(bars: &[BarData]) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampSecondArray::from(
+                bars.iter()
+                    .map(|bar| bar_time(bar).map(|time| time.timestamp()))
+                    .collect::<Vec<_>>(),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(StringArray::from_iter_values(
+            bars.iter().map(|bar| bar.date.as_str()),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            bars.iter().map(|bar| bar.open),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            bars.iter().map(|bar| bar.high),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            bars.iter().map(|bar| bar.low),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            bars.iter().map(|bar| bar.close),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            bars.iter().map(|bar| bar.volume),
+        )),
+        Arc::new(Int32Array::from_iter_values(
+            bars.iter().map(|bar| bar.bar_count),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            bars.iter().map(|bar| bar.average),
+        )),
+    ];
+    RecordBatch::try_new(bar_schema(), columns)
+}
+
+//==================================================================================================
+fn tick_times<T>(ticks: &[T], time: impl Fn(&T) -> i32) -> ArrayRef {
+    Arc::new(
+        TimestampSecondArray::from_iter_values(ticks.iter().map(|tick| time(tick) as i64))
+            .with_timezone("UTC"),
+    )
+}
+
+//==================================================================================================
+fn trade_ticks_to_record_batch(ticks: &[HistoricalTickLast]) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        tick_times(ticks, |tick| tick.time),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.price),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.size),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            ticks.iter().map(|tick| tick.exchange.as_str()),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            ticks.iter().map(|tick| tick.special_conditions.as_str()),
+        )),
+        Arc::new(BooleanArray::from(
+            ticks
+                .iter()
+                .map(|tick| tick.tick_attrib_last.past_limit)
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(BooleanArray::from(
+            ticks
+                .iter()
+                .map(|tick| tick.tick_attrib_last.unreported)
+                .collect::<Vec<_>>(),
+        )),
+    ];
+    RecordBatch::try_new(trade_tick_schema(), columns)
+}
+
+//==================================================================================================
+fn bid_ask_ticks_to_record_batch(
+    ticks: &[HistoricalTickBidAsk],
+) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        tick_times(ticks, |tick| tick.time),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.price_bid),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.price_ask),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.size_bid),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.size_ask),
+        )),
+        Arc::new(BooleanArray::from(
+            ticks
+                .iter()
+                .map(|tick| tick.tick_attrib_bid_ask.bid_past_low)
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(BooleanArray::from(
+            ticks
+                .iter()
+                .map(|tick| tick.tick_attrib_bid_ask.ask_past_high)
+                .collect::<Vec<_>>(),
+        )),
+    ];
+    RecordBatch::try_new(bid_ask_tick_schema(), columns)
+}
+
+//==================================================================================================
+fn midpoint_ticks_to_record_batch(ticks: &[HistoricalTick]) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        tick_times(ticks, |tick| tick.time),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.price),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            ticks.iter().map(|tick| tick.size),
+        )),
+    ];
+    RecordBatch::try_new(midpoint_tick_schema(), columns)
+}
+
+//==================================================================================================
+/// Converts historical ticks, using the schema of their kind
+pub fn historical_ticks_to_record_batch(
+    ticks: &HistoricalTicks,
+) -> Result<RecordBatch, ArrowError> {
+    match ticks {
+        HistoricalTicks::Trades(ticks) => trade_ticks_to_record_batch(ticks),
+        HistoricalTicks::BidAsk(ticks) => bid_ask_ticks_to_record_batch(ticks),
+        HistoricalTicks::Midpoint(ticks) => midpoint_ticks_to_record_batch(ticks),
+    }
+}
+
+//==================================================================================================
+pub fn recorded_ticks_to_record_batch(ticks: &[RecordedTick]) -> Result<RecordBatch, ArrowError> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                ticks.iter().map(|tick| tick.time.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(Int32Array::from_iter_values(
+            ticks.iter().map(|tick| tick.ticker_id),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            ticks.iter().map(|tick| tick.tick_type.to_string()),
+        )),
+        Arc::new(Float64Array::from(
+            ticks.iter().map(|tick| tick.price).collect::<Vec<_>>(),
+        )),
+        Arc::new(Float64Array::from(
+            ticks.iter().map(|tick| tick.size).collect::<Vec<_>>(),
+        )),
+    ];
+    RecordBatch::try_new(recorded_tick_schema(), columns)
+}
+
+//==================================================================================================
+pub fn vol_surface_to_record_batch(
+    snapshot: &VolSurfaceSnapshot,
+) -> Result<RecordBatch, ArrowError> {
+    let points = &snapshot.points;
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                points.iter().map(|_| snapshot.time.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(StringArray::from_iter_values(
+            points.iter().map(|_| snapshot.underlying.as_str()),
+        )),
+        Arc::new(Float64Array::from(
+            points.iter().map(|_| snapshot.spot).collect::<Vec<_>>(),
+        )),
+        Arc::new(Date32Array::from_iter_values(points.iter().map(|point| {
+            point.expiry.signed_duration_since(epoch).num_days() as i32
+        }))),
+        Arc::new(Float64Array::from_iter_values(
+            points.iter().map(|point| point.strike),
+        )),
+        Arc::new(StringArray::from_iter_values(
+            points.iter().map(|point| point.right.to_string()),
+        )),
+        Arc::new(Float64Array::from_iter_values(
+            points.iter().map(|point| point.implied_vol),
+        )),
+        Arc::new(Float64Array::from(
+            points.iter().map(|point| point.delta).collect::<Vec<_>>(),
+        )),
+        Arc::new(Float64Array::from(
+            points
+                .iter()
+                .map(|point| point.und_price)
+                .collect::<Vec<_>>(),
+        )),
+        Arc::new(
+            TimestampMicrosecondArray::from_iter_values(
+                points.iter().map(|point| point.time.timestamp_micros()),
+            )
+            .with_timezone("UTC"),
+        ),
+    ];
+    RecordBatch::try_new(vol_surface_schema(), columns)
+}
+
+//==================================================================================================
+/// Writes a record batch as a Snappy compressed Parquet file
+pub fn write_parquet<W: Write + Send>(writer: W, batch: &RecordBatch) -> Result<(), ParquetError> {
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), Some(properties))?;
+    writer.write(batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+//==================================================================================================
+pub fn write_bars_parquet<P: AsRef<Path>>(path: P, bars: &[BarData]) -> Result<(), ParquetError> {
+    write_parquet(File::create(path)?, &bars_to_record_batch(bars)?)
+}
+
+//==================================================================================================
+pub fn write_historical_ticks_parquet<P: AsRef<Path>>(
+    path: P,
+    ticks: &HistoricalTicks,
+) -> Result<(), ParquetError> {
+    write_parquet(
+        File::create(path)?,
+        &historical_ticks_to_record_batch(ticks)?,
+    )
+}
+
+//==================================================================================================
+pub fn write_recorded_ticks_parquet<P: AsRef<Path>>(
+    path: P,
+    ticks: &[RecordedTick],
+) -> Result<(), ParquetError> {
+    write_parquet(File::create(path)?, &recorded_ticks_to_record_batch(ticks)?)
+}
+
+//==================================================================================================
+pub fn write_vol_surface_parquet<P: AsRef<Path>>(
+    path: P,
+    snapshot: &VolSurfaceSnapshot,
+) -> Result<(), ParquetError> {
+    write_parquet(File::create(path)?, &vol_surface_to_record_batch(snapshot)?)
+}
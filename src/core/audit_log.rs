@@ -0,0 +1,230 @@
+//! Audit trail of the order messages sent to TWS
+//!
+//! An `OrderAuditLog` set with `EClient::set_audit_log` is handed every
+//! place order, cancel order and global cancel message before it is sent.
+//! Each `AuditRecord` carries a sequence number that counts up by one from
+//! record to record, the time the message was sent and the SHA-256 hash of
+//! the message as it went over the wire, so a trail can be checked for gaps
+//! and matched against captures of the connection. Records are passed to an
+//! `AuditSink`; `JsonLinesAuditSink` appends them to a file, one JSON object
+//! per line, and never rewrites what was written before. It needs the `serde`
+//! feature.
+use std::fmt;
+#[cfg(feature = "serde")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "serde")]
+use std::io::{self, BufRead, BufReader, Write};
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::client::POISONED_MUTEX;
+use crate::core::errors::IbkrError;
+use crate::core::messages::OutgoingMessageIds;
+
+//==================================================================================================
+/// An order message sent to TWS
+///
+/// sequence - counts up by one from the first record of a log, starting at 1
+/// message - the name of the message, e.g. `PlaceOrder`
+/// order_id - None for a global cancel
+/// payload_hash - the SHA-256 hash of the encoded message, including its
+///   length prefix, as lower case hex
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AuditRecord {
+    pub sequence: u64,
+    pub time: DateTime<Utc>,
+    pub message_id: i32,
+    pub message: String,
+    pub order_id: Option<i32>,
+    pub payload_len: usize,
+    pub payload_hash: String,
+}
+
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sequence: {}, time: {}, message: {}, order_id: {:?}, payload_len: {}, \
+             payload_hash: {}",
+            self.sequence,
+            self.time.to_rfc3339(),
+            self.message,
+            self.order_id,
+            self.payload_len,
+            self.payload_hash
+        )
+    }
+}
+
+//==================================================================================================
+/// The SHA-256 hash of a payload as lower case hex
+pub fn payload_hash(payload: &[u8]) -> String {
+    Sha256::digest(payload)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+//==================================================================================================
+/// Where audit records are written to. A sink only ever appends.
+pub trait AuditSink: Send {
+    fn append(&mut self, record: &AuditRecord) -> Result<(), IbkrError>;
+}
+
+//==================================================================================================
+/// Appends audit records to a file as JSON lines and flushes after each one
+#[cfg(feature = "serde")]
+pub struct JsonLinesAuditSink {
+    file: File,
+}
+
+#[cfg(feature = "serde")]
+impl JsonLinesAuditSink {
+    /// Opens a file for appending, creating it if it does not exist
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IbkrError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesAuditSink { file })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sequence number of the last record in a file, 0 if the file does
+    /// not exist or holds no records
+    pub fn last_sequence(path: impl AsRef<Path>) -> Result<u64, IbkrError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+        let mut last_sequence = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: AuditRecord = serde_json::from_str(&line)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            last_sequence = record.sequence;
+        }
+        Ok(last_sequence)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl AuditSink for JsonLinesAuditSink {
+    fn append(&mut self, record: &AuditRecord) -> Result<(), IbkrError> {
+        let mut line = serde_json::to_string(record)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+//==================================================================================================
+/// Keeps audit records in memory. Clones share the same records, so one can
+/// be handed to the client and the other used to read them back.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryAuditSink {
+    records: Arc<Mutex<Vec<AuditRecord>>>,
+}
+
+impl MemoryAuditSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn records(&self) -> Vec<AuditRecord> {
+        self.records.lock().expect(POISONED_MUTEX).clone()
+    }
+}
+
+impl AuditSink for MemoryAuditSink {
+    fn append(&mut self, record: &AuditRecord) -> Result<(), IbkrError> {
+        self.records
+            .lock()
+            .expect(POISONED_MUTEX)
+            .push(record.clone());
+        Ok(())
+    }
+}
+
+//==================================================================================================
+/// Numbers the order messages sent and passes them to a sink
+pub struct OrderAuditLog {
+    sink: Box<dyn AuditSink>,
+    last_sequence: u64,
+}
+
+impl OrderAuditLog {
+    pub fn new(sink: Box<dyn AuditSink>) -> Self {
+        Self::resume(sink, 0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Continues the numbering of a log that ended with `last_sequence`
+    pub fn resume(sink: Box<dyn AuditSink>, last_sequence: u64) -> Self {
+        OrderAuditLog {
+            sink,
+            last_sequence,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Appends to a JSON lines file, continuing the numbering of the records
+    /// already in it
+    #[cfg(feature = "serde")]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IbkrError> {
+        let last_sequence = JsonLinesAuditSink::last_sequence(&path)?;
+        Ok(Self::resume(
+            Box::new(JsonLinesAuditSink::open(&path)?),
+            last_sequence,
+        ))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sequence number of the last record appended
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Appends the record of a message that is about to be sent. The
+    /// sequence number is only used up if the sink took the record.
+    pub fn record(
+        &mut self,
+        message: OutgoingMessageIds,
+        order_id: Option<i32>,
+        payload: &[u8],
+    ) -> Result<AuditRecord, IbkrError> {
+        let info = message.info();
+        let record = AuditRecord {
+            sequence: self.last_sequence + 1,
+            time: Utc::now(),
+            message_id: info.id,
+            message: info.name.to_string(),
+            order_id,
+            payload_len: payload.len(),
+            payload_hash: payload_hash(payload),
+        };
+        self.sink.append(&record)?;
+        self.last_sequence = record.sequence;
+        Ok(record)
+    }
+}
+
+impl fmt::Debug for OrderAuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderAuditLog")
+            .field("last_sequence", &self.last_sequence)
+            .finish()
+    }
+}
@@ -0,0 +1,562 @@
+//! Backtesting strategies on recorded or historical data
+//!
+//! A `Backtest` holds market data per contract: ticks recorded with a
+//! `TickRecorder`, historical ticks, or bars, e.g. downloaded with
+//! `HistoricalDownloader`. `Backtest::run` plays the data in time order
+//! through a `StrategyRunner`, so a `Strategy` runs unchanged: its
+//! subscriptions receive the data of their contract, its orders are filled
+//! against the prices that follow them and its timer fires on the simulated
+//! clock, which `StrategyContext::now` returns.
+//!
+//! Fills are simple: market orders fill in full at the next price, limit
+//! and stop orders once the price reaches them. Other order types are
+//! rejected.
+//!
+//! `replay_ticks` and `replay_real_time_bars` feed the same data to a
+//! `Wrapper` instead, for code written against the callbacks.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::core::common::{BarData, HistoricalTickLast, RealTimeBar, TickAttrib, TickType};
+use crate::core::contract::Contract;
+use crate::core::decoder::size_to_decimal;
+use crate::core::execution::Execution;
+use crate::core::order::Order;
+use crate::core::quote_cache::{Quote, QuoteCache};
+use crate::core::strategy::{OrderEvent, Strategy, StrategyCommand, StrategyEvent, StrategyRunner};
+use crate::core::tick_recorder::RecordedTick;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// The real time bar of a historical bar, None if the bar has no time
+pub fn real_time_bar(bar: &BarData) -> Option<RealTimeBar> {
+    bar.time.map(|time| RealTimeBar {
+        date_time: time.with_timezone(&Utc),
+        open: bar.open,
+        high: bar.high,
+        low: bar.low,
+        close: bar.close,
+        volume: bar.volume,
+        wap: bar.average,
+        count: bar.bar_count,
+    })
+}
+
+//==================================================================================================
+/// The last price and last size ticks of historical trades
+pub fn recorded_ticks(ticker_id: i32, ticks: &[HistoricalTickLast]) -> Vec<RecordedTick> {
+    ticks
+        .iter()
+        .flat_map(|tick| {
+            let time = Utc
+                .timestamp_opt(tick.time as i64, 0)
+                .single()
+                .unwrap_or_default();
+            [
+                RecordedTick::price(time, ticker_id, TickType::Last, tick.price),
+                RecordedTick::size(time, ticker_id, TickType::LastSize, tick.size),
+            ]
+        })
+        .collect()
+}
+
+//==================================================================================================
+/// Calls `tick_price` and `tick_size` with recorded ticks, in the order given
+pub fn replay_ticks<W: Wrapper>(wrapper: &mut W, ticks: &[RecordedTick]) {
+    for tick in ticks {
+        if let Some(price) = tick.price {
+            wrapper.tick_price(tick.ticker_id, tick.tick_type, price, TickAttrib::default());
+        }
+        if let Some(size) = tick.size {
+            wrapper.tick_size(tick.ticker_id, tick.tick_type, size_to_decimal(size));
+        }
+    }
+}
+
+//==================================================================================================
+/// Calls `realtime_bar` with bars, in the order given
+pub fn replay_real_time_bars<W: Wrapper>(wrapper: &mut W, request_id: i32, bars: &[RealTimeBar]) {
+    for bar in bars {
+        wrapper.realtime_bar(request_id, bar.clone());
+    }
+}
+
+//==================================================================================================
+/// A simulated execution
+#[derive(Clone, Debug)]
+pub struct BacktestFill {
+    pub time: DateTime<Utc>,
+    pub contract: Contract,
+    pub execution: Execution,
+}
+
+impl BacktestFill {
+    /// The filled quantity, negative for sells
+    pub fn signed_shares(&self) -> f64 {
+        if self.execution.side == "SLD" {
+            -self.execution.shares
+        } else {
+            self.execution.shares
+        }
+    }
+}
+
+impl fmt::Display for BacktestFill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time: {}, symbol: {}, order_id: {}, side: {}, shares: {}, price: {}",
+            self.time.to_rfc3339(),
+            self.contract.symbol,
+            self.execution.order_id,
+            self.execution.side,
+            self.execution.shares,
+            self.execution.price
+        )
+    }
+}
+
+//==================================================================================================
+/// The outcome of a backtest
+///
+/// events - the number of ticks and bars played
+/// end_time - the time of the last event played, None if there was none
+/// working_orders - the ids of the orders that were not filled or cancelled
+#[derive(Clone, Debug, Default)]
+pub struct BacktestReport {
+    pub fills: Vec<BacktestFill>,
+    pub events: usize,
+    pub end_time: Option<DateTime<Utc>>,
+    pub working_orders: Vec<i32>,
+}
+
+impl BacktestReport {
+    /// The net quantity filled in a contract, negative if short
+    pub fn position(&self, contract: &Contract) -> f64 {
+        self.fills
+            .iter()
+            .filter(|fill| same_contract(&fill.contract, contract))
+            .map(BacktestFill::signed_shares)
+            .sum()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The cash paid for and received from fills in a contract, before
+    /// commissions and without the multiplier
+    pub fn cash_flow(&self, contract: &Contract) -> f64 {
+        self.fills
+            .iter()
+            .filter(|fill| same_contract(&fill.contract, contract))
+            .map(|fill| -fill.signed_shares() * fill.execution.price)
+            .sum()
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "events: {}, fills: {}, end_time: {:?}, working_orders: {:?}",
+            self.events,
+            self.fills.len(),
+            self.end_time.map(|time| time.to_rfc3339()),
+            self.working_orders
+        )
+    }
+}
+
+//==================================================================================================
+/// Whether two contracts are the same, by `con_id` if both have one and by
+/// symbol, security type and currency otherwise
+fn same_contract(a: &Contract, b: &Contract) -> bool {
+    if a.con_id != 0 && b.con_id != 0 {
+        a.con_id == b.con_id
+    } else {
+        a.symbol == b.symbol && a.sec_type == b.sec_type && a.currency == b.currency
+    }
+}
+
+//==================================================================================================
+#[derive(Clone, Debug)]
+enum FeedData {
+    Tick(RecordedTick),
+    Bar(RealTimeBar),
+}
+
+#[derive(Clone, Debug)]
+struct WorkingOrder {
+    contract: Contract,
+    order: Order,
+}
+
+//==================================================================================================
+/// Plays market data through a strategy on a simulated clock
+#[derive(Clone, Debug, Default)]
+pub struct Backtest {
+    // The contracts of the data, indexed by feed
+    contracts: Vec<Contract>,
+    // Keyed by time and then by the order the data was added in
+    data: BTreeMap<(DateTime<Utc>, usize), (usize, FeedData)>,
+    timer_interval: Option<Duration>,
+}
+
+impl Backtest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `on_timer` every `interval` of simulated time, measured from the
+    /// first event
+    pub fn timer_interval(mut self, interval: Duration) -> Self {
+        self.timer_interval = Some(interval);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds ticks of a contract. Their ticker ids are ignored; they go to
+    /// the market data subscriptions of the strategy on the contract.
+    pub fn add_ticks(&mut self, contract: &Contract, ticks: &[RecordedTick]) {
+        let feed = self.feed(contract);
+        for tick in ticks {
+            self.push(tick.time, feed, FeedData::Tick(tick.clone()));
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds bars of a contract. They go to the real time bar subscriptions
+    /// of the strategy on the contract, whatever the bar size.
+    pub fn add_bars(&mut self, contract: &Contract, bars: &[RealTimeBar]) {
+        let feed = self.feed(contract);
+        for bar in bars {
+            self.push(bar.date_time, feed, FeedData::Bar(bar.clone()));
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds historical bars of a contract. Bars without a time are skipped.
+    pub fn add_historical_bars(&mut self, contract: &Contract, bars: &[BarData]) {
+        let bars: Vec<RealTimeBar> = bars.iter().filter_map(real_time_bar).collect();
+        self.add_bars(contract, &bars);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The number of ticks and bars added
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Starts the strategy, plays all data until the strategy asks to stop,
+    /// and stops it
+    pub fn run<S: Strategy>(&mut self, runner: &mut StrategyRunner<S>) -> BacktestReport {
+        let mut run = Run {
+            contracts: self.contracts.clone(),
+            quotes: QuoteCache::new(),
+            market_data: vec![],
+            bars: vec![],
+            orders: BTreeMap::new(),
+            report: BacktestReport::default(),
+        };
+
+        if let Some(((time, _), _)) = self.data.iter().next() {
+            runner.context().set_clock(*time);
+        }
+        let mut next_timer = self
+            .timer_interval
+            .zip(self.data.keys().next())
+            .map(|(interval, (start, _))| *start + interval);
+
+        runner.start_strategy();
+        run.handle_commands(runner);
+        for ((time, _), (feed, data)) in &self.data {
+            while let Some(timer) = next_timer.filter(|timer| timer <= time) {
+                if runner.context().is_stopping() {
+                    break;
+                }
+                runner.context().set_clock(timer);
+                run.dispatch(runner, &StrategyEvent::Timer);
+                next_timer = self.timer_interval.map(|interval| timer + interval);
+            }
+            if runner.context().is_stopping() {
+                break;
+            }
+            runner.context().set_clock(*time);
+            run.play(runner, *time, *feed, data);
+            run.report.events += 1;
+            run.report.end_time = Some(*time);
+        }
+        runner.stop_strategy();
+        run.handle_commands(runner);
+
+        run.report.working_orders = run.orders.keys().copied().collect();
+        run.report
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn feed(&mut self, contract: &Contract) -> usize {
+        match self
+            .contracts
+            .iter()
+            .position(|feed| same_contract(feed, contract))
+        {
+            Some(feed) => feed,
+            None => {
+                self.contracts.push(contract.clone());
+                self.contracts.len() - 1
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn push(&mut self, time: DateTime<Utc>, feed: usize, data: FeedData) {
+        let key = (time, self.data.len());
+        self.data.insert(key, (feed, data));
+    }
+}
+
+//==================================================================================================
+/// The state of one run of a backtest
+struct Run {
+    contracts: Vec<Contract>,
+    // The quotes of the tick data, keyed by feed
+    quotes: QuoteCache,
+    // The subscriptions of the strategy as (request id, feed)
+    market_data: Vec<(i32, usize)>,
+    bars: Vec<(i32, usize)>,
+    orders: BTreeMap<i32, WorkingOrder>,
+    report: BacktestReport,
+}
+
+impl Run {
+    /// Fills the orders the data reaches, then hands the data to the
+    /// subscriptions. Orders are never filled by the data they were placed
+    /// on.
+    fn play<S: Strategy>(
+        &mut self,
+        runner: &mut StrategyRunner<S>,
+        time: DateTime<Utc>,
+        feed: usize,
+        data: &FeedData,
+    ) {
+        if let FeedData::Tick(tick) = data {
+            if let Some(price) = tick.price {
+                self.quotes
+                    .update_tick_price(feed as i32, tick.tick_type, price);
+            }
+            if let Some(size) = tick.size {
+                self.quotes
+                    .update_tick_size(feed as i32, tick.tick_type, size);
+            }
+        }
+        let quote = self.quotes.quote(feed as i32).cloned().unwrap_or_default();
+
+        let fills: Vec<(i32, f64)> = self
+            .orders
+            .iter()
+            .filter(|(_, working)| same_contract(&working.contract, &self.contracts[feed]))
+            .filter_map(|(order_id, working)| {
+                fill_price(&working.order, &quote, data).map(|price| (*order_id, price))
+            })
+            .collect();
+        for (order_id, price) in fills {
+            self.fill(runner, time, order_id, price);
+        }
+
+        match data {
+            FeedData::Tick(_) => {
+                let ticker_ids: Vec<i32> = self.subscriptions(&self.market_data, feed);
+                for ticker_id in ticker_ids {
+                    let quote = quote.clone();
+                    self.dispatch(runner, &StrategyEvent::Tick { ticker_id, quote });
+                }
+            }
+            FeedData::Bar(bar) => {
+                let request_ids: Vec<i32> = self.subscriptions(&self.bars, feed);
+                for request_id in request_ids {
+                    let bar = bar.clone();
+                    self.dispatch(runner, &StrategyEvent::Bar { request_id, bar });
+                }
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn subscriptions(&self, subscriptions: &[(i32, usize)], feed: usize) -> Vec<i32> {
+        subscriptions
+            .iter()
+            .filter(|(_, subscribed)| *subscribed == feed)
+            .map(|(request_id, _)| *request_id)
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn fill<S: Strategy>(
+        &mut self,
+        runner: &mut StrategyRunner<S>,
+        time: DateTime<Utc>,
+        order_id: i32,
+        price: f64,
+    ) {
+        let Some(WorkingOrder { contract, order }) = self.orders.remove(&order_id) else {
+            return;
+        };
+        let execution = Execution {
+            exec_id: format!("backtest.{}", self.report.fills.len() + 1),
+            time: time.format("%Y%m%d %H:%M:%S").to_string(),
+            acct_number: order.account.clone(),
+            exchange: contract.exchange.clone(),
+            side: if order.action == "BUY" { "BOT" } else { "SLD" }.to_string(),
+            shares: order.total_quantity,
+            price,
+            order_id,
+            cum_qty: order.total_quantity,
+            avg_price: price,
+            order_ref: order.order_ref.clone(),
+            ..Default::default()
+        };
+        self.report.fills.push(BacktestFill {
+            time,
+            contract,
+            execution,
+        });
+        self.dispatch(
+            runner,
+            &StrategyEvent::Order(OrderEvent::Status {
+                order_id,
+                status: "Filled".to_string(),
+            }),
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn dispatch<S: Strategy>(&mut self, runner: &mut StrategyRunner<S>, event: &StrategyEvent) {
+        runner.dispatch(event);
+        self.handle_commands(runner);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Carries out the commands the strategy queued, and those it queues in
+    /// response
+    fn handle_commands<S: Strategy>(&mut self, runner: &mut StrategyRunner<S>) {
+        loop {
+            let commands = runner.context().take_commands();
+            if commands.is_empty() {
+                return;
+            }
+            let mut events = vec![];
+            for command in commands {
+                match command {
+                    StrategyCommand::SubscribeMarketData {
+                        ticker_id,
+                        contract,
+                    } => {
+                        if let Some(feed) = self.feed(&contract) {
+                            self.market_data.push((ticker_id, feed));
+                        }
+                    }
+                    StrategyCommand::SubscribeBars {
+                        request_id,
+                        contract,
+                        ..
+                    } => {
+                        if let Some(feed) = self.feed(&contract) {
+                            self.bars.push((request_id, feed));
+                        }
+                    }
+                    StrategyCommand::Unsubscribe(request_id) => {
+                        self.market_data.retain(|(id, _)| *id != request_id);
+                        self.bars.retain(|(id, _)| *id != request_id);
+                    }
+                    StrategyCommand::PlaceOrder {
+                        order_id,
+                        contract,
+                        order,
+                    } => events.push(match order.order_type.as_str() {
+                        "MKT" | "LMT" | "STP" => {
+                            self.orders.insert(
+                                order_id,
+                                WorkingOrder {
+                                    contract: *contract,
+                                    order: *order,
+                                },
+                            );
+                            OrderEvent::Status {
+                                order_id,
+                                status: "Submitted".to_string(),
+                            }
+                        }
+                        order_type => OrderEvent::Rejected {
+                            order_id,
+                            reason: format!(
+                                "Order type {} is not supported in backtests.",
+                                order_type
+                            ),
+                        },
+                    }),
+                    StrategyCommand::CancelOrder(order_id) => {
+                        events.push(match self.orders.remove(&order_id) {
+                            Some(_) => OrderEvent::Status {
+                                order_id,
+                                status: "Cancelled".to_string(),
+                            },
+                            None => OrderEvent::Rejected {
+                                order_id,
+                                reason: "The order is not working.".to_string(),
+                            },
+                        })
+                    }
+                }
+            }
+            for event in events {
+                runner.dispatch(&StrategyEvent::Order(event));
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn feed(&self, contract: &Contract) -> Option<usize> {
+        self.contracts
+            .iter()
+            .position(|feed| same_contract(feed, contract))
+    }
+}
+
+//==================================================================================================
+/// The price an order fills at on a tick or a bar, None if it does not fill
+///
+/// On a tick, buys trade at the ask and sells at the bid, or at the last
+/// price if the side is missing. On a bar, orders trade at the open, and
+/// limit and stop orders at their price if the bar reaches it.
+fn fill_price(order: &Order, quote: &Quote, data: &FeedData) -> Option<f64> {
+    let buying = order.action == "BUY";
+    match data {
+        FeedData::Tick(tick) => {
+            tick.price?;
+            let price = if buying { quote.ask } else { quote.bid }.or(quote.last)?;
+            match order.order_type.as_str() {
+                "MKT" => Some(price),
+                "LMT" if buying && price <= order.lmt_price => Some(price),
+                "LMT" if !buying && price >= order.lmt_price => Some(price),
+                "STP" if buying && price >= order.aux_price => Some(price),
+                "STP" if !buying && price <= order.aux_price => Some(price),
+                _ => None,
+            }
+        }
+        FeedData::Bar(bar) => match order.order_type.as_str() {
+            "MKT" => Some(bar.open),
+            "LMT" if buying && bar.low <= order.lmt_price => Some(bar.open.min(order.lmt_price)),
+            "LMT" if !buying && bar.high >= order.lmt_price => Some(bar.open.max(order.lmt_price)),
+            "STP" if buying && bar.high >= order.aux_price => Some(bar.open.max(order.aux_price)),
+            "STP" if !buying && bar.low <= order.aux_price => Some(bar.open.min(order.aux_price)),
+            _ => None,
+        },
+    }
+}
@@ -0,0 +1,197 @@
+//! Aggregation of trade ticks into OHLCV bars of arbitrary size
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{BarData, TickType};
+
+//==================================================================================================
+/// When a bar built by the `BarAggregator` is complete
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BarKind {
+    /// Time bars of the given number of seconds, aligned to multiples of the
+    /// interval since the epoch
+    Time(i64),
+    /// Bars that are complete once they contain at least the given volume
+    Volume(f64),
+    /// Bars that are complete once they contain the given number of trades
+    TickCount(i32),
+}
+
+impl fmt::Display for BarKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BarKind::Time(seconds) => write!(f, "{} secs", seconds),
+            BarKind::Volume(volume) => write!(f, "{} volume", volume),
+            BarKind::TickCount(count) => write!(f, "{} ticks", count),
+        }
+    }
+}
+
+//==================================================================================================
+#[derive(Clone, Debug)]
+struct PartialBar {
+    start: i64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    count: i32,
+    notional: f64,
+}
+
+impl PartialBar {
+    fn new(start: i64, price: f64) -> Self {
+        PartialBar {
+            start,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0.0,
+            count: 0,
+            notional: 0.0,
+        }
+    }
+
+    fn add(&mut self, price: f64, size: f64) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume += size;
+        self.count += 1;
+        self.notional += price * size;
+    }
+
+    fn to_bar(&self) -> BarData {
+        let average = if self.volume > 0.0 {
+            self.notional / self.volume
+        } else {
+            self.close
+        };
+        BarData::new(
+            self.start.to_string(),
+            self.open,
+            self.high,
+            self.low,
+            self.close,
+            self.volume,
+            self.count,
+            average,
+        )
+    }
+}
+
+//==================================================================================================
+/// Builds OHLCV bars from the trades of a tick-by-tick (`AllLast`/`Last`) or
+/// L1 market data stream, since the native real time bars of TWS are fixed
+/// at 5 seconds.
+///
+/// The `date` of the bars produced is the time of the bar's start in seconds
+/// since the epoch, `bar_count` is the number of trades in the bar and
+/// `average` the volume weighted average price.
+#[derive(Clone, Debug)]
+pub struct BarAggregator {
+    kind: BarKind,
+    current: Option<PartialBar>,
+    last_price: Option<f64>,
+}
+
+impl BarAggregator {
+    pub fn new(kind: BarKind) -> Self {
+        BarAggregator {
+            kind,
+            current: None,
+            last_price: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn kind(&self) -> BarKind {
+        self.kind
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a trade and returns the bars completed by it, if any.
+    ///
+    /// # Arguments
+    /// * time - time of the trade in seconds since the epoch
+    /// * price - trade price
+    /// * size - trade size
+    pub fn update_trade(&mut self, time: i64, price: f64, size: f64) -> Vec<BarData> {
+        let mut bars = self.update_time(time);
+
+        let start = match self.kind {
+            BarKind::Time(seconds) => time - time.rem_euclid(seconds.max(1)),
+            _ => time,
+        };
+        let bar = self
+            .current
+            .get_or_insert_with(|| PartialBar::new(start, price));
+        bar.add(price, size);
+        self.last_price = Some(price);
+
+        let complete = match self.kind {
+            BarKind::Time(_) => false,
+            BarKind::Volume(volume) => bar.volume >= volume,
+            BarKind::TickCount(count) => bar.count >= count,
+        };
+        if complete {
+            bars.extend(self.flush());
+        }
+        bars
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Completes the current time bar if `time` lies past its end. Call this
+    /// periodically to receive bars for intervals that end without a further
+    /// trade. Does nothing for volume and tick count bars.
+    pub fn update_time(&mut self, time: i64) -> Vec<BarData> {
+        match (self.kind, &self.current) {
+            (BarKind::Time(seconds), Some(bar)) if time >= bar.start + seconds.max(1) => {
+                self.flush().into_iter().collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Feeds a price tick of an L1 market data stream as received by
+    /// `Wrapper::tick_price`. Only `TickType::Last` is used; the trade is
+    /// recorded once its size arrives through `update_tick_size`.
+    pub fn update_tick_price(&mut self, tick_type: TickType, price: f64) {
+        if tick_type == TickType::Last || tick_type == TickType::DelayedLast {
+            self.last_price = Some(price);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Feeds a size tick of an L1 market data stream as received by
+    /// `Wrapper::tick_size`. A `TickType::LastSize` tick records a trade at
+    /// the last price and returns the bars completed by it.
+    pub fn update_tick_size(&mut self, time: i64, tick_type: TickType, size: f64) -> Vec<BarData> {
+        match (tick_type, self.last_price) {
+            (TickType::LastSize, Some(price)) | (TickType::DelayedLastSize, Some(price))
+                if size > 0.0 =>
+            {
+                self.update_trade(time, price, size)
+            }
+            _ => self.update_time(time),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Completes and returns the bar currently being built, if any
+    pub fn flush(&mut self) -> Option<BarData> {
+        self.current.take().map(|bar| bar.to_bar())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The bar currently being built, if any
+    pub fn current(&self) -> Option<BarData> {
+        self.current.as_ref().map(|bar| bar.to_bar())
+    }
+}
@@ -0,0 +1,168 @@
+//! Local store of downloaded historical bars
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::core::common::{BarData, BarSize, WhatToShow};
+use crate::core::contract::Contract;
+use crate::core::historical_downloader::{bar_time, merge_bars};
+
+//==================================================================================================
+/// Identifies a series of bars in a `BarCache`
+///
+/// contract - the contract id, or if it is unknown the symbol, security type,
+///            expiry and currency of the contract
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BarCacheKey {
+    pub contract: String,
+    pub bar_size: String,
+    pub what_to_show: String,
+    pub use_rth: bool,
+}
+
+impl BarCacheKey {
+    pub fn new(
+        contract: &Contract,
+        bar_size: &BarSize,
+        what_to_show: &WhatToShow,
+        use_rth: bool,
+    ) -> Self {
+        let contract = if contract.con_id > 0 {
+            contract.con_id.to_string()
+        } else {
+            let sec_type = contract.sec_type.to_string();
+            [
+                contract.symbol.as_str(),
+                sec_type.as_str(),
+                contract.last_trade_date_or_contract_month.as_str(),
+                contract.currency.as_str(),
+            ]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .cloned()
+            .collect::<Vec<&str>>()
+            .join("-")
+        };
+        BarCacheKey {
+            contract,
+            bar_size: bar_size.to_string(),
+            what_to_show: what_to_show.to_string(),
+            use_rth,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The name of the file the series is stored in, e.g.
+    /// `265598_1_min_TRADES_rth.json`
+    pub fn file_name(&self) -> String {
+        let name = format!(
+            "{}_{}_{}{}.json",
+            self.contract,
+            self.bar_size,
+            self.what_to_show,
+            if self.use_rth { "_rth" } else { "" }
+        );
+        name.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for BarCacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "contract: {}, bar_size: {}, what_to_show: {}, use_rth: {}",
+            self.contract, self.bar_size, self.what_to_show, self.use_rth
+        )
+    }
+}
+
+//==================================================================================================
+/// Persists downloaded bars as one JSON file per series in a directory, so
+/// that later downloads only need to request the bars that are missing.
+///
+/// The bars must have been requested with dates as seconds since the epoch,
+/// as done by `HistoricalDownloader`.
+#[derive(Clone, Debug)]
+pub struct BarCache {
+    dir: PathBuf,
+}
+
+impl BarCache {
+    /// Opens the cache in `dir`, creating the directory if needed
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(BarCache {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The stored bars of a series, ordered by time. Empty if nothing is
+    /// stored.
+    pub fn load(&self, key: &BarCacheKey) -> io::Result<Vec<BarData>> {
+        match fs::read(self.path(key)) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+            Err(err) => Err(err),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Merges bars into the stored series. A stored bar is replaced by a new
+    /// bar with the same time. Returns the merged series.
+    pub fn store(&self, key: &BarCacheKey, bars: Vec<BarData>) -> io::Result<Vec<BarData>> {
+        let mut merged = bars;
+        merged.extend(self.load(key)?);
+        let merged = merge_bars(merged, &DateTime::<Utc>::MIN_UTC, &DateTime::<Utc>::MAX_UTC);
+
+        let contents = serde_json::to_vec(&merged)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        // Write to a temporary file first so an interrupted write does not
+        // destroy the stored series
+        let path = self.path(key);
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(merged)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The times of the first and the last stored bar of a series
+    pub fn range(&self, key: &BarCacheKey) -> io::Result<Option<(DateTime<Utc>, DateTime<Utc>)>> {
+        let bars = self.load(key)?;
+        let first = bars.first().and_then(bar_time);
+        let last = bars.last().and_then(bar_time);
+        Ok(first.zip(last))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Deletes the stored bars of a series
+    pub fn remove(&self, key: &BarCacheKey) -> io::Result<()> {
+        match fs::remove_file(self.path(key)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn path(&self, key: &BarCacheKey) -> PathBuf {
+        self.dir.join(key.file_name())
+    }
+}
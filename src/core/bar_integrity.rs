@@ -0,0 +1,302 @@
+//! Integrity checks of downloaded bar series
+//!
+//! Finds bars whose date can not be parsed, duplicate and out of order bars
+//! and, given the trading schedule of the contract, missing sessions and
+//! missing bars within sessions. Note that TWS leaves out the bars of
+//! intervals without trades, so gaps are to be expected for illiquid
+//! contracts.
+use std::collections::BTreeSet;
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{BarData, BarSize};
+use crate::core::historical::{HistoricalSchedule, HistoricalSession};
+use crate::core::historical_downloader::bar_time;
+
+//==================================================================================================
+/// A problem found in a bar series
+///
+/// index - the position of the bar in the series
+/// start, end - the range that should be fetched again
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BarIssue {
+    /// The date of the bar could not be parsed
+    UnparseableDate { index: usize, date: String },
+    /// The bar has the same time as a bar before it
+    Duplicate { index: usize, time: DateTime<Utc> },
+    /// The bar is older than a bar before it
+    OutOfOrder {
+        index: usize,
+        time: DateTime<Utc>,
+        previous: DateTime<Utc>,
+    },
+    /// A trading day of the schedule has no bars at all
+    MissingSession {
+        ref_date: NaiveDate,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+    /// Consecutive bars are missing within a session
+    MissingBars {
+        ref_date: NaiveDate,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        count: usize,
+    },
+}
+
+impl BarIssue {
+    /// The range to fetch again, for missing sessions and bars
+    pub fn missing_range(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        match self {
+            BarIssue::MissingSession { start, end, .. }
+            | BarIssue::MissingBars { start, end, .. } => Some((*start, *end)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BarIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BarIssue::UnparseableDate { index, date } => {
+                write!(f, "UnparseableDate(index: {}, date: {})", index, date)
+            }
+            BarIssue::Duplicate { index, time } => {
+                write!(f, "Duplicate(index: {}, time: {})", index, time)
+            }
+            BarIssue::OutOfOrder {
+                index,
+                time,
+                previous,
+            } => write!(
+                f,
+                "OutOfOrder(index: {}, time: {}, previous: {})",
+                index, time, previous
+            ),
+            BarIssue::MissingSession {
+                ref_date,
+                start,
+                end,
+            } => write!(
+                f,
+                "MissingSession(ref_date: {}, start: {}, end: {})",
+                ref_date, start, end
+            ),
+            BarIssue::MissingBars {
+                ref_date,
+                start,
+                end,
+                count,
+            } => write!(
+                f,
+                "MissingBars(ref_date: {}, start: {}, end: {}, count: {})",
+                ref_date, start, end, count
+            ),
+        }
+    }
+}
+
+//==================================================================================================
+/// The result of `check_bars`
+///
+/// bars - the number of bars checked
+/// issues - the problems found, first those of single bars in the order of
+///          the bars, then the missing sessions and bars
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BarIntegrityReport {
+    pub bars: usize,
+    pub issues: Vec<BarIssue>,
+}
+
+impl BarIntegrityReport {
+    pub fn new(bars: usize, issues: Vec<BarIssue>) -> Self {
+        BarIntegrityReport { bars, issues }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the series has duplicate, out of order or unparseable bars,
+    /// i.e. needs to be merged again, e.g. with `merge_bars`
+    pub fn needs_merge(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.missing_range().is_none())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ranges of the missing sessions and bars, ordered by time
+    pub fn missing_ranges(&self) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut ranges: Vec<(DateTime<Utc>, DateTime<Utc>)> = self
+            .issues
+            .iter()
+            .filter_map(BarIssue::missing_range)
+            .collect();
+        ranges.sort();
+        ranges
+    }
+}
+
+impl fmt::Display for BarIntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let issues: Vec<String> = self.issues.iter().map(|issue| issue.to_string()).collect();
+        write!(f, "bars: {}, issues: [{}]", self.bars, issues.join("; "))
+    }
+}
+
+//==================================================================================================
+/// Checks a series of bars, as returned by `HistoricalDownloader`
+///
+/// # Arguments
+/// * bars - the bars, ordered by time
+/// * bar_size - the bar size the bars were requested with
+/// * schedule - the trading schedule of the contract for the range of the
+///   bars, see `EClient::request_historical_schedule`. Without it only
+///   the order of the bars is checked. Weekly and monthly bars are never
+///   checked against the schedule.
+pub fn check_bars(
+    bars: &[BarData],
+    bar_size: &BarSize,
+    schedule: Option<&HistoricalSchedule>,
+) -> BarIntegrityReport {
+    let mut issues = vec![];
+    let mut times = BTreeSet::new();
+    let mut previous: Option<DateTime<Utc>> = None;
+    for (index, bar) in bars.iter().enumerate() {
+        let time = match bar_time(bar) {
+            Some(time) => time,
+            None => {
+                issues.push(BarIssue::UnparseableDate {
+                    index,
+                    date: bar.date.clone(),
+                });
+                continue;
+            }
+        };
+        match previous {
+            _ if times.contains(&time) => {
+                issues.push(BarIssue::Duplicate { index, time });
+            }
+            Some(previous) if time < previous => {
+                issues.push(BarIssue::OutOfOrder {
+                    index,
+                    time,
+                    previous,
+                });
+            }
+            _ => {}
+        }
+        previous = Some(previous.map_or(time, |previous| previous.max(time)));
+        times.insert(time);
+    }
+
+    if let Some(schedule) = schedule {
+        match bar_size {
+            BarSize::_1Day => issues.extend(missing_days(bars, schedule)),
+            BarSize::_1Week | BarSize::_1Month => {}
+            _ => issues.extend(missing_bars(&times, bar_size, schedule)),
+        }
+    }
+    BarIntegrityReport::new(bars.len(), issues)
+}
+
+//==================================================================================================
+/// The trading days of the schedule without a daily bar
+fn missing_days(bars: &[BarData], schedule: &HistoricalSchedule) -> Vec<BarIssue> {
+    let days: BTreeSet<NaiveDate> = bars
+        .iter()
+        .filter_map(|bar| NaiveDate::parse_from_str(bar.date.trim(), "%Y%m%d").ok())
+        .collect();
+    schedule
+        .trading_days()
+        .into_iter()
+        .filter(|day| !days.contains(day))
+        .filter_map(|ref_date| {
+            let sessions = schedule.sessions_for(ref_date);
+            Some(BarIssue::MissingSession {
+                ref_date,
+                start: sessions.first()?.start,
+                end: sessions.last()?.end,
+            })
+        })
+        .collect()
+}
+
+//==================================================================================================
+/// The sessions of the schedule without bars and the runs of missing bars
+/// within sessions. Bars are expected at the start of each session and at
+/// every multiple of the bar size after it.
+fn missing_bars(
+    times: &BTreeSet<DateTime<Utc>>,
+    bar_size: &BarSize,
+    schedule: &HistoricalSchedule,
+) -> Vec<BarIssue> {
+    let bar_seconds = bar_size.seconds();
+    let mut issues = vec![];
+    for session in &schedule.sessions {
+        let present: Vec<&DateTime<Utc>> = times.range(session.start..session.end).collect();
+        if present.is_empty() {
+            issues.push(BarIssue::MissingSession {
+                ref_date: session.ref_date,
+                start: session.start,
+                end: session.end,
+            });
+            continue;
+        }
+
+        let mut missing: Option<(DateTime<Utc>, usize)> = None;
+        for slot in slots(session, bar_seconds) {
+            if times.contains(&slot) {
+                if let Some((start, count)) = missing.take() {
+                    issues.push(BarIssue::MissingBars {
+                        ref_date: session.ref_date,
+                        start,
+                        end: slot,
+                        count,
+                    });
+                }
+            } else {
+                missing = match missing {
+                    Some((start, count)) => Some((start, count + 1)),
+                    None => Some((slot, 1)),
+                };
+            }
+        }
+        if let Some((start, count)) = missing {
+            issues.push(BarIssue::MissingBars {
+                ref_date: session.ref_date,
+                start,
+                end: session.end,
+                count,
+            });
+        }
+    }
+    issues
+}
+
+//==================================================================================================
+/// The start times of the bars of a session. TWS aligns bars to multiples of
+/// the bar size, the first bar starts with the session.
+fn slots(session: &HistoricalSession, bar_seconds: i64) -> Vec<DateTime<Utc>> {
+    let start = session.start.timestamp();
+    let end = session.end.timestamp();
+    let mut slots = vec![start];
+    let mut slot = (start / bar_seconds + 1) * bar_seconds;
+    while slot < end {
+        slots.push(slot);
+        slot += bar_seconds;
+    }
+    slots
+        .into_iter()
+        .filter_map(|slot| Utc.timestamp_opt(slot, 0).single())
+        .collect()
+}
@@ -0,0 +1,309 @@
+//! In-memory trade blotter
+//!
+//! A `Blotter` records what happens to orders in the order it happens:
+//! placements, amendments, cancels, fills and commissions. Feed it the
+//! orders passed to `place_order` and `cancel_order`, and the `exec_details`
+//! and `commission_report` callbacks. Every entry is stamped with the time
+//! it was recorded and a sequence number, and can be handed to a hook as it
+//! is recorded, e.g. to append it to a file or a `TradeStore`.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::CommissionReport;
+use crate::core::contract::Contract;
+use crate::core::execution::Execution;
+use crate::core::order::Order;
+
+//==================================================================================================
+/// What happened to an order
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BlotterEvent {
+    Placed(Box<Order>),
+    Amended(Box<Order>),
+    Cancelled,
+    Fill(Box<Execution>),
+    Commission(CommissionReport),
+}
+
+impl fmt::Display for BlotterEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlotterEvent::Placed(order) => write!(
+                f,
+                "placed {} {} {}",
+                order.action, order.total_quantity, order.order_type
+            ),
+            BlotterEvent::Amended(order) => write!(
+                f,
+                "amended {} {} {}",
+                order.action, order.total_quantity, order.order_type
+            ),
+            BlotterEvent::Cancelled => write!(f, "cancelled"),
+            BlotterEvent::Fill(execution) => write!(
+                f,
+                "fill {} {} @ {}",
+                execution.side, execution.shares, execution.price
+            ),
+            BlotterEvent::Commission(report) => {
+                write!(f, "commission {} {}", report.commission, report.currency)
+            }
+        }
+    }
+}
+
+//==================================================================================================
+/// A recorded event
+///
+/// sequence - the position of the entry in the blotter, counting from 0
+/// time - when the entry was recorded
+/// order_id - the order the event belongs to, 0 for commissions of unknown
+///   executions
+/// contract - the contract of the order, empty if it is not known
+/// order_ref - the strategy tag of the order
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BlotterEntry {
+    pub sequence: u64,
+    pub time: DateTime<Utc>,
+    pub order_id: i32,
+    pub contract: Contract,
+    pub order_ref: String,
+    pub event: BlotterEvent,
+}
+
+impl fmt::Display for BlotterEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} order_id: {}, symbol: {}, order_ref: {}, {}",
+            self.sequence,
+            self.time.to_rfc3339(),
+            self.order_id,
+            self.contract.symbol,
+            self.order_ref,
+            self.event
+        )
+    }
+}
+
+//==================================================================================================
+/// Selects entries of a `Blotter`. Empty criteria select everything.
+///
+/// from - the first time selected
+/// to - the time after the last time selected
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BlotterFilter {
+    pub symbol: Option<String>,
+    pub order_ref: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl BlotterFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn symbol(mut self, symbol: &str) -> Self {
+        self.symbol = Some(symbol.to_string());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Selects the entries of a strategy, i.e. of an `order_ref`
+    pub fn strategy(mut self, order_ref: &str) -> Self {
+        self.order_ref = Some(order_ref.to_string());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn between(mut self, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn matches(&self, entry: &BlotterEntry) -> bool {
+        self.symbol
+            .as_ref()
+            .is_none_or(|symbol| *symbol == entry.contract.symbol)
+            && self
+                .order_ref
+                .as_ref()
+                .is_none_or(|order_ref| *order_ref == entry.order_ref)
+            && self.from.is_none_or(|from| entry.time >= from)
+            && self.to.is_none_or(|to| entry.time < to)
+    }
+}
+
+/// Called with every entry as it is recorded
+pub type BlotterHook = Box<dyn FnMut(&BlotterEntry) + Send>;
+
+//==================================================================================================
+/// The entries recorded so far, oldest first
+#[derive(Default)]
+pub struct Blotter {
+    entries: Vec<BlotterEntry>,
+    orders: HashMap<i32, (Contract, String)>,
+    fills: HashMap<String, usize>,
+    commissions: HashSet<String>,
+    hook: Option<BlotterHook>,
+}
+
+impl Blotter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `hook` with every entry recorded from now on
+    pub fn set_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&BlotterEntry) + Send + 'static,
+    {
+        self.hook = Some(Box::new(hook));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear_hook(&mut self) {
+        self.hook = None;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Restores entries persisted by a hook, e.g. after a restart. They are
+    /// not passed to the hook again.
+    pub fn restore(&mut self, entries: Vec<BlotterEntry>) {
+        for entry in entries {
+            self.index(&entry);
+            self.entries.push(entry);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records an order passed to `place_order`. Placing an order id that
+    /// was placed before is recorded as an amendment.
+    pub fn record_order(&mut self, order_id: i32, contract: &Contract, order: &Order) {
+        let event = if self.orders.contains_key(&order_id) {
+            BlotterEvent::Amended(Box::new(order.clone()))
+        } else {
+            BlotterEvent::Placed(Box::new(order.clone()))
+        };
+        self.push(order_id, contract.clone(), order.order_ref.clone(), event);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records a `cancel_order`
+    pub fn record_cancel(&mut self, order_id: i32) {
+        let (contract, order_ref) = self.orders.get(&order_id).cloned().unwrap_or_default();
+        self.push(order_id, contract, order_ref, BlotterEvent::Cancelled);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records an execution of `exec_details`. Returns false if it was
+    /// recorded before, as TWS sends executions again on
+    /// `request_executions`.
+    pub fn record_execution(&mut self, contract: &Contract, execution: &Execution) -> bool {
+        if self.fills.contains_key(&execution.exec_id) {
+            return false;
+        }
+        self.push(
+            execution.order_id,
+            contract.clone(),
+            execution.order_ref.clone(),
+            BlotterEvent::Fill(Box::new(execution.clone())),
+        );
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records a commission report. It is attributed to the order of its
+    /// execution if that was recorded. Returns false if it was recorded
+    /// before.
+    pub fn record_commission_report(&mut self, report: &CommissionReport) -> bool {
+        if self.commissions.contains(&report.exec_id) {
+            return false;
+        }
+        let (order_id, contract, order_ref) = match self.fills.get(&report.exec_id) {
+            Some(index) => {
+                let fill = &self.entries[*index];
+                (fill.order_id, fill.contract.clone(), fill.order_ref.clone())
+            }
+            None => (0, Contract::default(), "".to_string()),
+        };
+        self.push(
+            order_id,
+            contract,
+            order_ref,
+            BlotterEvent::Commission(report.clone()),
+        );
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn entries(&self) -> &[BlotterEntry] {
+        &self.entries
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The entries selected by a filter, oldest first
+    pub fn query(&self, filter: &BlotterFilter) -> Vec<&BlotterEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The history of an order, oldest first
+    pub fn order_history(&self, order_id: i32) -> Vec<&BlotterEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.order_id == order_id)
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn push(&mut self, order_id: i32, contract: Contract, order_ref: String, event: BlotterEvent) {
+        let entry = BlotterEntry {
+            sequence: self.entries.len() as u64,
+            time: Utc::now(),
+            order_id,
+            contract,
+            order_ref,
+            event,
+        };
+        self.index(&entry);
+        if let Some(hook) = self.hook.as_mut() {
+            hook(&entry);
+        }
+        self.entries.push(entry);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn index(&mut self, entry: &BlotterEntry) {
+        match &entry.event {
+            BlotterEvent::Placed(_) | BlotterEvent::Amended(_) => {
+                self.orders.insert(
+                    entry.order_id,
+                    (entry.contract.clone(), entry.order_ref.clone()),
+                );
+            }
+            BlotterEvent::Fill(execution) => {
+                self.fills
+                    .insert(execution.exec_id.clone(), self.entries.len());
+            }
+            BlotterEvent::Commission(report) => {
+                self.commissions.insert(report.exec_id.clone());
+            }
+            BlotterEvent::Cancelled => (),
+        }
+    }
+}
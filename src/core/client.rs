@@ -1,14 +1,16 @@
 #![allow(clippy::too_many_arguments)]
 //! EClient and supporting structs.  Responsible for connecting to Trader
 //! Workstation or IB Gatway and sending requests
-use bigdecimal::ToPrimitive;
-use from_ascii::FromAscii;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use log::*;
 use num_derive::{FromPrimitive, ToPrimitive};
 use std::{
+    collections::HashMap,
     fmt::Debug,
     io::Write,
     marker::Sync,
+    mem,
     net::{Shutdown, TcpStream},
     ops::Deref,
     sync::{
@@ -17,26 +19,77 @@ use std::{
         Arc, Mutex,
     },
     thread,
+    time::{Duration, Instant},
 };
 
-use super::streamer::{Streamer, TcpStreamer};
+use super::streamer::{RequestSender, Streamer, TcpStreamer};
+use crate::core::account_summary_tags::{
+    AccountSummary, AccountSummaryEvent, AccountSummaryTagSet,
+};
+use crate::core::account_updates::{
+    AccountSnapshot, AccountUpdateEvent, AccountUpdateMultiEvent, AccountUpdates, ModelAccountValue,
+};
+use crate::core::alert_hooks::{AlertCondition, AlertHook, AlertHooks};
+use crate::core::audit_log::OrderAuditLog;
 use crate::core::common::*;
-use crate::core::contract::Contract;
+use crate::core::conflation::TickConflator;
+use crate::core::contract::{
+    unique_contract, Contract, ContractDescription, ContractDetails, ContractDetailsEvent,
+    SecIdType, SecType, SymbolSamplesEvent,
+};
 use crate::core::decoder::Decoder;
-use crate::core::errors::{IBKRApiLibError, TwsApiReportableError, TwsError};
+use crate::core::display_groups::DisplayGroupContract;
+use crate::core::error_codes::NO_SECURITY_DEFINITION;
+use crate::core::errors::{
+    ConnectionError, IbkrError, ResolveContractError, ServerError, TwsError,
+};
 use crate::core::execution::ExecutionFilter;
-use crate::core::messages::make_field;
-use crate::core::messages::{make_field_handle_empty, read_msg};
-use crate::core::messages::{make_message, read_fields, OutgoingMessageIds};
-use crate::core::order::Order;
-use crate::core::order_condition::Condition;
+use crate::core::execution_quality::{ArrivalQuote, ArrivalQuotes};
+use crate::core::flatten::closing_orders;
+use crate::core::historical::{
+    format_end_date_time, BarTimeParser, HistoricalDataStream, HistoricalRetryPolicy,
+    HistoricalTicks, HistoricalTicksEvent, HistoricalTicksQuery,
+};
+use crate::core::kill_switch::{KillSwitchFlatten, KillSwitchReport};
+use crate::core::managed_accounts::ManagedAccounts;
+use crate::core::margin::WhatIfEvent;
+use crate::core::market_rules::{MarketRule, MarketRuleCache};
+use crate::core::messages::{
+    begin_message, finish_message, make_message, read_fields, write_field, InvalidUtf8Policy,
+    OutgoingMessageIds, RawField, RawFieldLog,
+};
+use crate::core::model::Model;
+use crate::core::news::{format_historical_news_time, HistoricalNewsQuery};
+use crate::core::option_chain::{OptionChain, OptionChainEvent};
+use crate::core::order::{
+    Order, OrderState, SoftDollarTier, COMPETE_AGAINST_BEST_OFFSET_UP_TO_MID,
+};
+use crate::core::order_book::{BookMetrics, BookMetricsConfig, OrderBook};
+use crate::core::order_condition::encode_condition;
+use crate::core::order_journal::{JournalEntry, OrderIntent, OrderJournal};
+use crate::core::order_throttle::{OrderThrottle, ThrottleEvent};
+use crate::core::order_tracker::{DuplicateRefPolicy, OrderTracker};
+use crate::core::pnl::{PnlEvent, PnlSingleEvent};
+use crate::core::positions::{collect_positions, Position, PositionEvent};
+use crate::core::quote_cache::{AuctionState, Quote, QuoteCache};
 use crate::core::reader::Reader;
-use crate::core::scanner::ScannerSubscription;
+use crate::core::risk::{order_price, RiskChecks, RiskOrder};
+use crate::core::scanner::{ScanData, ScannerSubscription};
 use crate::core::server_versions::*;
+use crate::core::session_gate::{GateDecision, SessionGate};
+use crate::core::smart_components::{SmartComponentMap, SmartComponentRegistry};
+use crate::core::subscription::{HistoricalRetry, StreamRegistry, Subscription};
+use crate::core::trading_mode::TradingMode;
 use crate::core::wrapper::Wrapper;
+#[cfg(feature = "wsh")]
+use crate::core::wsh::WshEventData;
 
 pub(crate) static POISONED_MUTEX: &str = "Mutex was poisoned";
 
+/// The initial capacity of the buffer messages are written into, enough for
+/// an order with a few combo legs
+const MESSAGE_BUFFER_CAPACITY: usize = 1024;
+
 #[repr(i32)]
 #[derive(FromPrimitive, ToPrimitive, Copy, Clone, Debug)]
 pub enum DateTimeFormat {
@@ -72,9 +125,34 @@ where
     client_id: i32,
     pub(crate) server_version: i32,
     conn_time: String,
+    time_zone: Tz,
+    historical_retry_policy: HistoricalRetryPolicy,
+    retry_policies: HashMap<i32, HistoricalRetryPolicy>,
     pub conn_state: Arc<Mutex<ConnStatus>>,
     opt_capab: String,
     disconnect_requested: Arc<AtomicBool>,
+    tick_conflator: Arc<Mutex<TickConflator>>,
+    smart_components: Arc<Mutex<SmartComponentRegistry>>,
+    market_rules: Arc<Mutex<MarketRuleCache>>,
+    request_sender: RequestSender,
+    streams: Arc<Mutex<StreamRegistry>>,
+    pub(crate) quotes: Arc<Mutex<QuoteCache>>,
+    orders: Arc<Mutex<OrderTracker>>,
+    kill_switch_engaged: bool,
+    pub(crate) accounts: Arc<Mutex<ManagedAccounts>>,
+    message_buffer: Vec<u8>,
+    audit_log: Option<OrderAuditLog>,
+    utf8_policy: InvalidUtf8Policy,
+    raw_fields: RawFieldLog,
+    trading_mode: Option<TradingMode>,
+    apply_mode_defaults: bool,
+    risk_checks: Option<RiskChecks>,
+    order_throttle: Option<OrderThrottle>,
+    session_gate: Option<SessionGate>,
+    duplicate_ref_policy: DuplicateRefPolicy,
+    journal: Arc<Mutex<Option<OrderJournal>>>,
+    alerts: Arc<Mutex<Option<AlertHooks>>>,
+    arrival_quotes: Option<ArrivalQuotes>,
 }
 
 impl<T> EClient<T>
@@ -91,21 +169,92 @@ where
             client_id: 0,
             server_version: 0,
             conn_time: "".to_string(),
+            time_zone: Tz::UTC,
+            historical_retry_policy: HistoricalRetryPolicy::none(),
+            retry_policies: HashMap::new(),
             conn_state: Arc::new(Mutex::new(ConnStatus::DISCONNECTED)),
             opt_capab: "".to_string(),
             disconnect_requested: Arc::new(AtomicBool::new(false)),
+            tick_conflator: Arc::new(Mutex::new(TickConflator::new())),
+            smart_components: Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            market_rules: Arc::new(Mutex::new(MarketRuleCache::new())),
+            request_sender: RequestSender::new(None),
+            streams: Arc::new(Mutex::new(StreamRegistry::new())),
+            quotes: Arc::new(Mutex::new(QuoteCache::new())),
+            orders: Arc::new(Mutex::new(OrderTracker::new())),
+            kill_switch_engaged: false,
+            accounts: Arc::new(Mutex::new(ManagedAccounts::new())),
+            message_buffer: Vec::with_capacity(MESSAGE_BUFFER_CAPACITY),
+            audit_log: None,
+            utf8_policy: InvalidUtf8Policy::default(),
+            raw_fields: RawFieldLog::new(),
+            trading_mode: None,
+            apply_mode_defaults: false,
+            risk_checks: None,
+            order_throttle: None,
+            session_gate: None,
+            duplicate_ref_policy: DuplicateRefPolicy::default(),
+            journal: Arc::new(Mutex::new(None)),
+            alerts: Arc::new(Mutex::new(None)),
+            arrival_quotes: None,
         }
     }
 
-    fn send_request(&mut self, request: &str) -> Result<(), IBKRApiLibError> {
-        let bytes = make_message(request)?;
-        self.send_bytes(bytes.as_slice())?;
-        Ok(())
+    /// Takes the buffer of the connection to write a message into with
+    /// write_field(). Hand it back with send_message(), so its allocation is
+    /// reused for the next message.
+    fn message_buffer(&mut self) -> Vec<u8> {
+        let mut buf = mem::take(&mut self.message_buffer);
+        begin_message(&mut buf);
+        buf
+    }
+
+    fn send_message(&mut self, mut buf: Vec<u8>) -> Result<(), IbkrError> {
+        let result = self.send_bytes(finish_message(&mut buf));
+        self.message_buffer = buf;
+        result
+    }
+
+    /// Like send_message(), but hands the message to the audit log first.
+    /// The message is not sent if it could not be recorded.
+    fn send_order_message(
+        &mut self,
+        message: OutgoingMessageIds,
+        order_id: Option<i32>,
+        mut buf: Vec<u8>,
+    ) -> Result<(), IbkrError> {
+        let bytes = finish_message(&mut buf);
+        let result = self
+            .audit(message, order_id, bytes)
+            .and_then(|_| self.send_bytes(bytes));
+        self.message_buffer = buf;
+        result
+    }
+
+    fn audit(
+        &mut self,
+        message: OutgoingMessageIds,
+        order_id: Option<i32>,
+        payload: &[u8],
+    ) -> Result<(), IbkrError> {
+        match self.audit_log.as_mut() {
+            Some(audit_log) => audit_log.record(message, order_id, payload).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    fn write_journal(&self, entry: JournalEntry) -> Result<(), IbkrError> {
+        match self.journal.lock().expect(POISONED_MUTEX).as_mut() {
+            Some(journal) => journal.append(entry).map(|_| ()),
+            None => Ok(()),
+        }
     }
 
-    fn send_bytes(&mut self, bytes: &[u8]) -> Result<usize, IBKRApiLibError> {
-        let return_val = self.stream.as_mut().unwrap().write(bytes)?;
-        Ok(return_val)
+    fn send_bytes(&mut self, bytes: &[u8]) -> Result<(), IbkrError> {
+        match self.stream.as_mut() {
+            Some(stream) => Ok(stream.write_all(bytes)?),
+            None => Err(not_connected(NO_VALID_ID)),
+        }
     }
 
     pub(crate) fn set_streamer(&mut self, streamer: Option<Box<dyn Streamer>>) {
@@ -114,18 +263,13 @@ where
 
     //----------------------------------------------------------------------------------------------
     /// Establishes a connection to TWS or IB Gateway
-    pub fn connect(
-        &mut self,
-        host: &str,
-        port: u32,
-        client_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    pub fn connect(&mut self, host: &str, port: u32, client_id: i32) -> Result<(), IbkrError> {
         if self.is_connected() {
             info!("Already connected...");
-            return Err(IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            return Err(IbkrError::Connection(ConnectionError::new(
                 -1,
-                TwsError::AlreadyConnected.code().to_string(),
-                TwsError::AlreadyConnected.message().to_string(),
+                TwsError::AlreadyConnected,
+                "",
             )));
         }
         self.host = host.to_string();
@@ -136,9 +280,14 @@ where
         *self.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTING;
         let tcp_stream = TcpStream::connect(format!("{}:{}", self.host, port))?;
         let streamer = TcpStreamer::new(tcp_stream);
-        self.set_streamer(Option::from(Box::new(streamer.clone()) as Box<dyn Streamer>));
-        let (tx, rx) = channel::<String>();
+        self.set_streamer(Option::from(
+            Box::new(streamer.try_clone()?) as Box<dyn Streamer>
+        ));
+        self.request_sender
+            .set_stream(Some(Box::new(streamer.try_clone()?) as Box<dyn Streamer>));
+        let (tx, rx) = channel();
         let mut reader = Reader::new(Box::new(streamer), tx, self.disconnect_requested.clone());
+        reader.set_utf8_policy(self.utf8_policy, self.raw_fields.clone());
 
         let mut fields: Vec<String> = Vec::new();
 
@@ -158,6 +307,16 @@ where
             rx,
             self.server_version,
             self.conn_state.clone(),
+            self.tick_conflator.clone(),
+            self.smart_components.clone(),
+            self.market_rules.clone(),
+            self.request_sender.clone(),
+            self.streams.clone(),
+            self.quotes.clone(),
+            self.orders.clone(),
+            self.accounts.clone(),
+            self.journal.clone(),
+            self.alerts.clone(),
         );
 
         //An Interactive Broker's developer's note: "sometimes I get news before the
@@ -167,23 +326,35 @@ where
                 decoder.interpret(fields.as_slice())?;
             }
 
-            let buf = reader.recv_packet()?;
-
-            if !buf.is_empty() {
-                let (_size, msg, _remaining_messages) = read_msg(buf.as_slice())?;
-
-                fields.clear();
-                fields.extend_from_slice(read_fields(msg.as_ref()).as_slice());
-            } else {
-                fields.clear();
+            // Messages that arrive with the server version stay in the reader
+            fields = match reader.next_message()? {
+                Some(msg) => read_fields(&msg),
+                None => {
+                    *self.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::DISCONNECTED;
+                    return Err(IbkrError::Connection(ConnectionError::new(
+                        NO_VALID_ID,
+                        TwsError::ConnectFail,
+                        "",
+                    )));
+                }
+            };
+        }
+
+        self.server_version = match fields[0].parse() {
+            Ok(server_version) => server_version,
+            Err(_) => {
+                *self.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::DISCONNECTED;
+                return Err(IbkrError::Connection(ConnectionError::new(
+                    NO_VALID_ID,
+                    TwsError::BadMessage,
+                    &format!("Server version: {}", fields[0]),
+                )));
             }
-        }
-
-        self.server_version = i32::from_ascii(fields.get(0).unwrap().as_bytes()).unwrap();
+        };
 
         info!("Server version: {}", self.server_version);
 
-        self.conn_time = fields.get(1).unwrap().to_string();
+        self.conn_time = fields[1].to_string();
         decoder.server_version = self.server_version;
 
         thread::spawn(move || {
@@ -191,20 +362,23 @@ where
         });
 
         thread::spawn(move || {
-            if decoder.run().is_err() {
-                panic!("decoder.run() failed!!");
+            if let Err(err) = decoder.run() {
+                error!("The decoder has stopped: {}", err);
             }
         });
         *self.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::CONNECTED;
         info!("Connected");
         self.start_api()?;
+        if self.apply_mode_defaults {
+            self.apply_trading_mode_defaults()?;
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     /// Checks connection status
     pub fn is_connected(&self) -> bool {
-        let connected = match *self.conn_state.lock().unwrap().deref() {
+        let connected = match *self.conn_state.lock().expect(POISONED_MUTEX).deref() {
             ConnStatus::DISCONNECTED => false,
             ConnStatus::CONNECTED => true,
             ConnStatus::CONNECTING => false,
@@ -224,7 +398,7 @@ where
 
     //----------------------------------------------------------------------------------------------
     /// Sets server logging level
-    pub fn set_server_log_level(&mut self, log_evel: i32) -> Result<(), IBKRApiLibError> {
+    pub fn set_server_log_level(&mut self, log_evel: i32) -> Result<(), IbkrError> {
         //The pub default detail level is ERROR. For more details, see API
         //        Logging.
         //TODO Make log_level an enum
@@ -232,18 +406,18 @@ where
 
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::SetServerLoglevel.version();
         let _log_level = log_evel;
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::SetServerLoglevel as i32;
         let _x = message_id.to_be_bytes();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&_log_level)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &_log_level)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -255,30 +429,222 @@ where
         self.conn_time.clone()
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Sets the time zone set in TWS. Bar dates without a time zone are
+    /// parsed in it, see `BarData::time`. Defaults to UTC.
+    pub fn set_time_zone(&mut self, time_zone: Tz) {
+        self.time_zone = time_zone;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn time_zone(&self) -> Tz {
+        self.time_zone
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets how historical data requests that fail with a pacing violation
+    /// or a timeout of the historical data farm are sent again. The error is
+    /// only reported once all attempts have failed. Retrying is disabled by
+    /// default.
+    pub fn set_historical_retry_policy(&mut self, policy: HistoricalRetryPolicy) {
+        self.historical_retry_policy = policy;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the retry policy of the next historical data request made with
+    /// `request_id`, overriding the one of set_historical_retry_policy()
+    pub fn set_request_retry_policy(&mut self, request_id: i32, policy: HistoricalRetryPolicy) {
+        self.retry_policies.insert(request_id, policy);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the log every place order, cancel order and global cancel
+    /// message is recorded in before it is sent, None for none. A message
+    /// that cannot be recorded is not sent.
+    pub fn set_audit_log(&mut self, audit_log: Option<OrderAuditLog>) {
+        self.audit_log = audit_log;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn audit_log(&self) -> Option<&OrderAuditLog> {
+        self.audit_log.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the checks place_order() evaluates before an order is sent,
+    /// None for none
+    pub fn set_risk_checks(&mut self, risk_checks: Option<RiskChecks>) {
+        self.risk_checks = risk_checks;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn risk_checks(&self) -> Option<&RiskChecks> {
+        self.risk_checks.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the throttle that caps the rate of orders place_order() sends,
+    /// None for none. Orders still queued by the previous throttle are
+    /// dropped.
+    pub fn set_order_throttle(&mut self, order_throttle: Option<OrderThrottle>) {
+        if let Some(previous) = self.order_throttle.as_mut() {
+            previous.clear();
+        }
+        self.order_throttle = order_throttle;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn order_throttle(&self) -> Option<&OrderThrottle> {
+        self.order_throttle.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the gate that holds back orders for contracts outside their
+    /// trading sessions, None for none. Orders still queued by the previous
+    /// gate are dropped.
+    pub fn set_session_gate(&mut self, session_gate: Option<SessionGate>) {
+        self.session_gate = session_gate;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn session_gate(&self) -> Option<&SessionGate> {
+        self.session_gate.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The session gate, to add the sessions of contracts to it
+    pub fn session_gate_mut(&mut self) -> Option<&mut SessionGate> {
+        self.session_gate.as_mut()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the journal that gets every order before it is placed, every
+    /// cancel request and every order status and execution reported by TWS.
+    /// If an order or cancel request cannot be written to the journal, it is
+    /// not sent.
+    pub fn set_order_journal(&mut self, journal: Option<OrderJournal>) {
+        *self.journal.lock().expect(POISONED_MUTEX) = journal;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sequence number of the last record of the journal, None if there
+    /// is no journal
+    pub fn order_journal_sequence(&self) -> Option<u64> {
+        self.journal
+            .lock()
+            .expect(POISONED_MUTEX)
+            .as_ref()
+            .map(OrderJournal::last_sequence)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the hooks called on connection losses, rejected orders, errors,
+    /// fills and account values crossing a threshold as TWS reports them,
+    /// None to call none. The account values are those of
+    /// `req_account_updates`.
+    pub fn set_alert_hooks(&mut self, alerts: Option<AlertHooks>) {
+        *self.alerts.lock().expect(POISONED_MUTEX) = alerts;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a hook to the alert hooks, setting them if there are none
+    pub fn add_alert_hook(&mut self, conditions: &[AlertCondition], hook: Box<dyn AlertHook>) {
+        self.alerts
+            .lock()
+            .expect(POISONED_MUTEX)
+            .get_or_insert_with(AlertHooks::new)
+            .add(conditions, hook);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets where the quote of the contract of every order is kept when the
+    /// order is sent, for `ExecutionQuality`, None to keep none. The quotes
+    /// come from the quote cache, so the market data of the contracts must be
+    /// subscribed to with their `con_id`.
+    pub fn set_arrival_quotes(&mut self, arrival_quotes: Option<ArrivalQuotes>) {
+        self.arrival_quotes = arrival_quotes;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn arrival_quotes(&self) -> Option<&ArrivalQuotes> {
+        self.arrival_quotes.as_ref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The arrival quotes, to clear them
+    pub fn arrival_quotes_mut(&mut self) -> Option<&mut ArrivalQuotes> {
+        self.arrival_quotes.as_mut()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets what place_order() does with an order whose `order_ref` is that
+    /// of another working order, or of an order queued by the session gate
+    /// or the order throttle. By default it is placed.
+    pub fn set_duplicate_ref_policy(&mut self, policy: DuplicateRefPolicy) {
+        self.duplicate_ref_policy = policy;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn duplicate_ref_policy(&self) -> DuplicateRefPolicy {
+        self.duplicate_ref_policy
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets what is done with messages that are not valid UTF-8. By default
+    /// they are dropped with an error. Takes effect on the next connect.
+    pub fn set_utf8_policy(&mut self, policy: InvalidUtf8Policy) {
+        self.utf8_policy = policy;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn utf8_policy(&self) -> InvalidUtf8Policy {
+        self.utf8_policy
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes out the fields that were not valid UTF-8, as they were
+    /// received, if the policy is `InvalidUtf8Policy::LossyKeepRaw`
+    pub fn take_raw_fields(&mut self) -> Vec<RawField> {
+        self.raw_fields.take()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Formats the end time of a historical data, head time stamp or schedule
+    /// request in the form the connected server expects, e.g.
+    /// `20230103 16:00:00 US/Eastern`
+    pub fn format_end_date_time(&self, time: &DateTime<Tz>) -> String {
+        format_end_date_time(time, self.server_version)
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Request the current time according to TWS or IB Gateway
-    pub fn request_current_time(&mut self) -> Result<(), IBKRApiLibError> {
-        let version = 2;
+    pub fn request_current_time(&mut self) -> Result<(), IbkrError> {
+        let version = OutgoingMessageIds::ReqCurrentTime.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqCurrentTime as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
 
-        debug!("Requesting current time: {}", msg.as_str());
-        self.send_request(msg.as_str())
+        debug!("Requesting current time: {}", String::from_utf8_lossy(&msg));
+        self.send_message(msg)
     }
 
     //----------------------------------------------------------------------------------------------
     /// Disconnect from TWS
-    pub fn disconnect(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn disconnect(&mut self) -> Result<(), IbkrError> {
         if !self.is_connected() {
             info!("Already disconnected...");
             return Ok(());
         }
         info!("Disconnect requested.  Shutting down stream...");
         self.disconnect_requested.store(true, Ordering::Release);
-        self.stream.as_mut().unwrap().shutdown(Shutdown::Both)?;
+        if let Some(stream) = self.stream.as_mut() {
+            stream.shutdown(Shutdown::Both)?;
+        }
+        self.request_sender.set_stream(None);
+        self.streams.lock().expect(POISONED_MUTEX).clear();
+        self.quotes.lock().expect(POISONED_MUTEX).clear();
         *self.conn_state.lock().expect(POISONED_MUTEX) = ConnStatus::DISCONNECTED;
         Ok(())
     }
@@ -286,24 +652,19 @@ where
     //----------------------------------------------------------------------------------------------
     /// Initiates the message exchange between the client application and the
     /// TWS/IB Gateway
-    fn start_api(&mut self) -> Result<(), IBKRApiLibError> {
+    fn start_api(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 2;
-        let mut opt_capab = "".to_string();
+        let version = OutgoingMessageIds::StartApi.version();
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &(OutgoingMessageIds::StartApi as i32))?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &self.client_id)?;
         if self.server_version >= MIN_SERVER_VER_OPTIONAL_CAPABILITIES as i32 {
-            opt_capab = make_field(&self.opt_capab)?;
+            write_field(&mut msg, &self.opt_capab)?;
         }
 
-        let msg = format!(
-            "{}{}{}{}",
-            make_field(&(OutgoingMessageIds::StartApi as i32))?,
-            make_field(&version.to_string())?,
-            make_field(&self.client_id.to_string())?,
-            opt_capab
-        );
-
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -339,19 +700,15 @@ where
         snapshot: bool,
         regulatory_snapshot: bool,
         market_data_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
         if self.server_version() < MIN_SERVER_VER_DELTA_NEUTRAL {
             if let Some(_value) = &contract.delta_neutral_contract {
-                let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                let err = IbkrError::Connection(ConnectionError::new(
                     request_id,
-                    TwsError::UpdateTws.code().to_string(),
-                    format!(
-                        "{}{}",
-                        TwsError::UpdateTws.message(),
-                        " It does not support delta-neutral orders."
-                    ),
+                    TwsError::UpdateTws,
+                    "It does not support delta-neutral orders.",
                 ));
 
                 return Err(err);
@@ -359,118 +716,111 @@ where
         }
 
         if self.server_version() < MIN_SERVER_VER_REQ_MKT_DATA_CONID && contract.con_id > 0 {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::NotConnected.code().to_string(),
-                TwsError::NotConnected.message().to_string(),
-            ));
+            let err =
+                IbkrError::Connection(ConnectionError::new(request_id, TwsError::NotConnected, ""));
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && !contract.trading_class.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support trading_class parameter in request_market_data."
-                ),
+                TwsError::UpdateTws,
+                "It does not support trading_class parameter in request_market_data.",
             ));
 
             return Err(err);
         }
 
-        let version = 11;
+        let version = OutgoingMessageIds::ReqMktData.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqMktData as i32;
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         // send req market data msg
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
         // send contract fields
         if self.server_version() >= MIN_SERVER_VER_REQ_MKT_DATA_CONID {
-            msg.push_str(&make_field(&contract.con_id)?);
-            msg.push_str(&make_field(&contract.symbol)?);
+            write_field(&mut msg, &contract.con_id)?;
+            write_field(&mut msg, &contract.symbol)?;
 
-            msg.push_str(&make_field(&contract.sec_type)?);
-            msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-            msg.push_str(&make_field(&contract.strike)?);
-            msg.push_str(&make_field(&contract.right)?);
-            msg.push_str(&make_field(&contract.multiplier)?); // srv v15 and above
-            msg.push_str(&make_field(&contract.exchange)?);
-            msg.push_str(&make_field(&contract.primary_exchange)?); // srv v14 and above
-            msg.push_str(&make_field(&contract.currency)?);
-            msg.push_str(&make_field(&contract.local_symbol)?); //  srv v2 and
-                                                                // above
+            write_field(&mut msg, &contract.sec_type.to_string())?;
+            write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+            write_field(&mut msg, &contract.strike)?;
+            write_field(&mut msg, &contract.right.to_string())?;
+            write_field(&mut msg, &contract.multiplier)?; // srv v15 and above
+            write_field(&mut msg, &contract.exchange)?;
+            write_field(&mut msg, &contract.primary_exchange)?; // srv v14 and above
+            write_field(&mut msg, &contract.currency)?;
+            write_field(&mut msg, &contract.local_symbol)?; //  srv v2 and
+                                                            // above
         }
 
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
         // Send combo legs for BAG requests(srv v8 and above)
-        if contract.sec_type == "BAG" {
+        if contract.sec_type == SecType::Combo {
             let combo_legs_count = contract.combo_legs.len();
-            msg.push_str(&make_field(&combo_legs_count)?);
+            write_field(&mut msg, &combo_legs_count)?;
             for combo_leg in &contract.combo_legs {
-                msg.push_str(&make_field(&combo_leg.con_id)?);
-                msg.push_str(&make_field(&combo_leg.ratio)?);
-                msg.push_str(&make_field(&combo_leg.action)?);
-                msg.push_str(&make_field(&combo_leg.exchange)?);
+                write_field(&mut msg, &combo_leg.con_id)?;
+                write_field(&mut msg, &combo_leg.ratio)?;
+                write_field(&mut msg, &combo_leg.action)?;
+                write_field(&mut msg, &combo_leg.exchange)?;
             }
         }
 
         if self.server_version() >= MIN_SERVER_VER_DELTA_NEUTRAL {
-            if contract.delta_neutral_contract.is_some() {
-                msg.push_str(&make_field(&true)?);
-                msg.push_str(&make_field(
-                    &contract.delta_neutral_contract.as_ref().unwrap().con_id,
-                )?);
-                msg.push_str(&make_field(
-                    &contract.delta_neutral_contract.as_ref().unwrap().delta,
-                )?);
-                msg.push_str(&make_field(
-                    &contract.delta_neutral_contract.as_ref().unwrap().price,
-                )?);
-            } else {
-                msg.push_str(&make_field(&false)?);
+            match &contract.delta_neutral_contract {
+                Some(delta_neutral_contract) => {
+                    write_field(&mut msg, &true)?;
+                    write_field(&mut msg, &delta_neutral_contract.con_id)?;
+                    write_field(&mut msg, &delta_neutral_contract.delta)?;
+                    write_field(&mut msg, &delta_neutral_contract.price)?;
+                }
+                None => write_field(&mut msg, &false)?,
             }
 
-            msg.push_str(&make_field(&String::from(generic_tick_list))?); // srv v31 and above
-            msg.push_str(&make_field(&snapshot)?); // srv v35 and above
+            write_field(&mut msg, &String::from(generic_tick_list))?; // srv v31 and above
+            write_field(&mut msg, &snapshot)?; // srv v35 and above
         }
 
         if self.server_version() >= MIN_SERVER_VER_REQ_SMART_COMPONENTS {
-            msg.push_str(&make_field(&regulatory_snapshot)?);
+            write_field(&mut msg, &regulatory_snapshot)?;
         }
 
         // send marketDataOptions parameter
         if self.server_version() >= MIN_SERVER_VER_LINKING {
             // current doc says this part is for "internal use only" -> won't support it
             if !market_data_options.is_empty() {
-                let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                let err = IbkrError::Connection(ConnectionError::new(
                     request_id,
-                    TwsError::UpdateTws.code().to_string(),
-                    format!(
-                        "{}{}",
-                        TwsError::UpdateTws.message(),
-                        " Internal use only.  market_data_options not supported."
-                    ),
+                    TwsError::UpdateTws,
+                    "Internal use only.  market_data_options not supported.",
                 ));
 
                 return Err(err);
             }
             let market_data_options_str = "";
-            msg.push_str(&make_field(&market_data_options_str)?);
+            write_field(&mut msg, &market_data_options_str)?;
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
+
+        self.quotes.lock().expect(POISONED_MUTEX).add_ticker(
+            request_id,
+            if contract.con_id != 0 {
+                Some(contract.con_id)
+            } else {
+                None
+            },
+        );
         Ok(())
     }
 
@@ -481,21 +831,155 @@ where
     /// # Arguments
     /// * request_id - The ID that was specified in the call to
     ///   request_market_data()
-    pub fn cancel_market_data(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_market_data(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        let version = 2;
+        let version = OutgoingMessageIds::CancelMktData.version();
 
         let message_id: i32 = OutgoingMessageIds::CancelMktData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
-
-        self.send_request(msg.as_str())?;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+
+        self.send_message(msg)?;
+
+        self.tick_conflator
+            .lock()
+            .expect(POISONED_MUTEX)
+            .set_interval(request_id, None);
+        self.smart_components
+            .lock()
+            .expect(POISONED_MUTEX)
+            .remove_ticker(request_id);
+        self.quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .remove_ticker(request_id);
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// The latest quote and trading status received for a market data
+    /// subscription
+    ///
+    /// # Arguments
+    /// * ticker_id - The ID that was passed to request_market_data()
+    pub fn quote(&self, ticker_id: i32) -> Option<Quote> {
+        self.quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .quote(ticker_id)
+            .cloned()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests a market data snapshot and blocks until it carries a price:
+    /// the last price or else the midpoint of bid and ask.
+    ///
+    /// # Arguments
+    /// * request_id - The ID of the snapshot request.
+    /// * contract - The contract to price.
+    /// * timeout - how long to wait for a price, e.g. 11 seconds, after
+    ///   which TWS ends a snapshot
+    pub fn fetch_spot_price(
+        &mut self,
+        request_id: i32,
+        contract: &Contract,
+        timeout: Duration,
+    ) -> Result<f64, IbkrError> {
+        self.request_market_data(request_id, contract, "", true, false, &[])?;
+
+        let deadline = Instant::now() + timeout;
+        let price = loop {
+            let price = self.quote(request_id).and_then(|quote| {
+                quote
+                    .last
+                    .or_else(|| quote.midpoint())
+                    .filter(|price| *price > 0.0)
+            });
+            if price.is_some() || Instant::now() >= deadline {
+                break price;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        self.quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .remove_ticker(request_id);
+        price.ok_or_else(|| IbkrError::timeout(request_id, "a price"))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The latest auction price, volume and imbalances received for a market
+    /// data subscription. Request the generic tick 225 to receive them.
+    ///
+    /// # Arguments
+    /// * ticker_id - The ID that was passed to request_market_data()
+    pub fn auction_state(&self, ticker_id: i32) -> Option<AuctionState> {
+        self.quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .auction_state(ticker_id)
+            .cloned()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The auction state of a contract, taken from the market data
+    /// subscriptions requested with its con_id
+    pub fn contract_auction_state(&self, con_id: i32) -> Option<AuctionState> {
+        self.quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .contract_auction_state(con_id)
+            .cloned()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether trading is halted or paused for the instrument of a market
+    /// data subscription, as reported by its halted tick, which TWS sends by
+    /// default. Check this before submitting orders.
+    ///
+    /// # Arguments
+    /// * ticker_id - The ID that was passed to request_market_data()
+    pub fn is_halted(&self, ticker_id: i32) -> bool {
+        self.quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .is_halted(ticker_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether any market data subscription of a contract reports a halt.
+    /// Only subscriptions requested with a con_id are taken into account.
+    pub fn is_contract_halted(&self, con_id: i32) -> bool {
+        self.quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .is_contract_halted(con_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Conflates the tick_price and tick_size events of a market data
+    /// subscription. Bursts of ticks are coalesced so that every tick type is
+    /// delivered at most once per `interval`, always with its most recent
+    /// value. Useful for strategies that only need the latest quote.
+    ///
+    /// Can be called before or after request_market_data().
+    ///
+    /// # Arguments
+    /// * `request_id` - The ID that was (or will be) passed to
+    ///   request_market_data().
+    /// * `interval` - The minimum time between two deliveries of the same
+    ///   tick type. `None` turns conflation off again.
+    pub fn set_tick_conflation(&mut self, request_id: i32, interval: Option<Duration>) {
+        self.tick_conflator
+            .lock()
+            .expect(POISONED_MUTEX)
+            .set_interval(request_id, interval);
+    }
+
     //----------------------------------------------------------------------------------------------
     /// The API can receive frozen market data from Trader
     /// Workstation. Frozen market data is the last data recorded in our system.
@@ -509,35 +993,24 @@ where
     /// * market_data_type
     /// * 1 for real-time streaming market data
     /// * 2 for frozen market data
-    pub fn request_market_data_type(
-        &mut self,
-        market_data_type: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    pub fn request_market_data_type(&mut self, market_data_type: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_MARKET_DATA_TYPE {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support market data type requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqMarketDataType,
+            NO_VALID_ID,
+            "It does not support market data type requests.",
+        )?;
 
-        let mut msg = "".to_string();
-        let version = 1;
+        let mut msg = self.message_buffer();
+        let version = OutgoingMessageIds::ReqMarketDataType.version();
         let message_id = OutgoingMessageIds::ReqMarketDataType as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&market_data_type)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &market_data_type)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -553,35 +1026,96 @@ where
         &mut self,
         request_id: i32,
         bbo_exchange: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_SMART_COMPONENTS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support smart components request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqSmartComponents,
+            request_id,
+            "It does not support smart components request.",
+        )?;
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqSmartComponents as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(bbo_exchange))?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &String::from(bbo_exchange))?;
+
+        self.send_message(msg)?;
 
-        self.send_request(msg.as_str())?;
+        self.smart_components
+            .lock()
+            .expect(POISONED_MUTEX)
+            .add_request(request_id, bbo_exchange);
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Requests the SMART component mapping of a market data subscription.
+    /// The mapping identifier is taken from the bbo_exchange reported by
+    /// Wrapper::tick_request_parameters for that subscription.
+    ///
+    /// # Arguments
+    /// * request_id - The request id. Must be a unique value.
+    /// * ticker_id - The ID that was passed to request_market_data()
+    pub fn request_smart_components_for_ticker(
+        &mut self,
+        request_id: i32,
+        ticker_id: i32,
+    ) -> Result<(), IbkrError> {
+        let bbo_exchange = self.bbo_exchange(ticker_id).ok_or_else(|| {
+            IbkrError::rejected(
+                request_id,
+                &format!(
+                    "No bbo_exchange has been received for ticker id {} yet.",
+                    ticker_id
+                ),
+            )
+        })?;
+        self.request_smart_components(request_id, bbo_exchange.as_str())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The bbo_exchange mapping identifier reported by
+    /// Wrapper::tick_request_parameters for a market data subscription
+    pub fn bbo_exchange(&self, ticker_id: i32) -> Option<String> {
+        self.smart_components
+            .lock()
+            .expect(POISONED_MUTEX)
+            .bbo_exchange(ticker_id)
+            .map(|bbo_exchange| bbo_exchange.to_string())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The SMART component mapping previously received for a bbo_exchange
+    /// mapping identifier
+    pub fn smart_component_map(&self, bbo_exchange: &str) -> Option<SmartComponentMap> {
+        self.smart_components
+            .lock()
+            .expect(POISONED_MUTEX)
+            .map(bbo_exchange)
+            .cloned()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Translates the exchange letters of a SMART quote of a market data
+    /// subscription, e.g. the value of a TickType::BidExch tick, into exchange
+    /// names. Returns `None` until the component mapping for the
+    /// subscription has been received.
+    pub fn resolve_smart_exchanges(
+        &self,
+        ticker_id: i32,
+        exchange_letters: &str,
+    ) -> Option<Vec<String>> {
+        self.smart_components
+            .lock()
+            .expect(POISONED_MUTEX)
+            .map_for_ticker(ticker_id)
+            .map(|map| map.exchanges_for_letters(exchange_letters))
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Requests details about a given market rule
     /// The market rule for an instrument on a particular exchange provides
@@ -592,34 +1126,77 @@ where
     /// rule ID list will provide the market rule ID for the instrument in the
     /// correspond valid exchange list in contractDetails. # Arguments
     /// * market_rule_id -  the id of market rule
-    pub fn request_market_rule(&mut self, market_rule_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn request_market_rule(&mut self, market_rule_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_MARKET_RULES {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support market rule requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqMarketRule,
+            NO_VALID_ID,
+            "It does not support market rule requests.",
+        )?;
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqMarketRule as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&market_rule_id)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &market_rule_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
+
+        self.market_rules
+            .lock()
+            .expect(POISONED_MUTEX)
+            .mark_requested(market_rule_id);
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Returns a market rule received earlier, either through
+    /// request_market_rule() or because it was referenced by the
+    /// market_rule_ids of a ContractDetails.
+    ///
+    /// # Arguments
+    /// * market_rule_id -  the id of market rule
+    pub fn market_rule(&self, market_rule_id: i32) -> Option<MarketRule> {
+        self.market_rules
+            .lock()
+            .expect(POISONED_MUTEX)
+            .get(market_rule_id)
+            .cloned()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the cached market rule that applies to a contract on one of
+    /// its valid exchanges
+    ///
+    /// # Arguments
+    /// * contract_details - the details as received by
+    ///   Wrapper::contract_details
+    /// * exchange - one of the contract's valid exchanges
+    pub fn market_rule_for_exchange(
+        &self,
+        contract_details: &ContractDetails,
+        exchange: &str,
+    ) -> Option<MarketRule> {
+        self.market_rules
+            .lock()
+            .expect(POISONED_MUTEX)
+            .rule_for_exchange(contract_details, exchange)
+            .cloned()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// By default, the market rules referenced by every ContractDetails
+    /// received are requested automatically, unless they are cached
+    /// already. Their Wrapper::market_rule callbacks are delivered as usual.
+    pub fn set_auto_fetch_market_rules(&mut self, auto_fetch: bool) {
+        self.market_rules
+            .lock()
+            .expect(POISONED_MUTEX)
+            .set_auto_fetch(auto_fetch);
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Request tick by tick data
     ///
@@ -637,65 +1214,49 @@ where
         tick_type: TickByTickType,
         number_of_ticks: i32,
         ignore_size: bool,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_TICK_BY_TICK {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support tick-by-tick data requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqTickByTickData,
+            request_id,
+            "It does not support tick-by-tick data requests.",
+        )?;
 
         if self.server_version() < MIN_SERVER_VER_TICK_BY_TICK_IGNORE_SIZE {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support ignore_size and number_of_ticks parameters in tick-by-tick data requests."
-                ),
-            ));
+            let err = IbkrError::Connection(ConnectionError::new(request_id, TwsError::UpdateTws, "It does not support ignore_size and number_of_ticks parameters in tick-by-tick data requests."));
 
             return Err(err);
         }
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqTickByTickData as i32;
 
-        msg.push_str(&make_field(&message_id)?);
+        write_field(&mut msg, &message_id)?;
 
         //    msg.push_str(&make_field(&OUT.REQ_TICK_BY_TICK_DATA)\
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&contract.con_id)?);
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
-        msg.push_str(&make_field(&contract.trading_class)?);
-        msg.push_str(&make_field(&(tick_type.to_string()))?);
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &contract.con_id)?;
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
+        write_field(&mut msg, &contract.trading_class)?;
+        write_field(&mut msg, &(tick_type.to_string()))?;
 
         if self.server_version() >= MIN_SERVER_VER_TICK_BY_TICK_IGNORE_SIZE {
-            msg.push_str(&make_field(&number_of_ticks)?);
-            msg.push_str(&make_field(&ignore_size)?);
+            write_field(&mut msg, &number_of_ticks)?;
+            write_field(&mut msg, &ignore_size)?;
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -704,31 +1265,23 @@ where
     ///
     /// # Arguments
     /// * `request_id` - The identifier of the original request.
-    pub fn cancel_tick_by_tick_data(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_tick_by_tick_data(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_TICK_BY_TICK {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support tick-by-tick data requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::CancelTickByTickData,
+            request_id,
+            "It does not support tick-by-tick data requests.",
+        )?;
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::CancelTickByTickData as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -753,69 +1306,57 @@ where
         option_price: f64,
         under_price: f64,
         impl_vol_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support calculate_implied_volatility req."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqCalcImpliedVolat,
+            request_id,
+            "It does not support calculate_implied_volatility req.",
+        )?;
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && !contract.trading_class.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support trading_class parameter in calculate_implied_volatility."
-                ),
+                TwsError::UpdateTws,
+                "It does not support trading_class parameter in calculate_implied_volatility.",
             ));
 
             return Err(err);
         }
 
-        let version = 3;
+        let version = OutgoingMessageIds::ReqCalcImpliedVolat.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqCalcImpliedVolat as i32;
 
-        msg.push_str(&make_field(&message_id)?);
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
         // send contract fields
-        msg.push_str(&make_field(&contract.con_id)?);
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
+        write_field(&mut msg, &contract.con_id)?;
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
 
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
 
-        msg.push_str(&make_field(&option_price)?);
-        msg.push_str(&make_field(&under_price)?);
+        write_field(&mut msg, &option_price)?;
+        write_field(&mut msg, &under_price)?;
 
         if self.server_version() >= MIN_SERVER_VER_LINKING {
             let mut impl_vol_opt_str = "".to_string();
@@ -826,12 +1367,12 @@ where
                     .map(|x| format!("{}={};", x.tag, x.value))
                     .collect::<String>();
             }
-            msg.push_str(&make_field(&tag_values_count)?);
-            msg.push_str(&make_field(&impl_vol_opt_str)?);
+            write_field(&mut msg, &tag_values_count)?;
+            write_field(&mut msg, &impl_vol_opt_str)?;
         }
         error!("sending calculate_implied_volatility");
-        error!("{}", msg);
-        self.send_request(msg.as_str())?;
+        error!("{}", String::from_utf8_lossy(&msg));
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -851,68 +1392,56 @@ where
         volatility: f64,
         under_price: f64,
         opt_prc_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support calculateImpliedVolatility req."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqCalcOptionPrice,
+            request_id,
+            "It does not support calculateImpliedVolatility req.",
+        )?;
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && !contract.trading_class.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support trading_class parameter in calculateImpliedVolatility."
-                ),
+                TwsError::UpdateTws,
+                "It does not support trading_class parameter in calculateImpliedVolatility.",
             ));
 
             return Err(err);
         }
 
-        let version = 3;
+        let version = OutgoingMessageIds::ReqCalcOptionPrice.version();
 
         // send req market data msg
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqCalcOptionPrice as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
         // send contract fields
-        msg.push_str(&make_field(&contract.con_id)?);
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
+        write_field(&mut msg, &contract.con_id)?;
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
 
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
 
-        msg.push_str(&make_field(&volatility)?);
-        msg.push_str(&make_field(&under_price)?);
+        write_field(&mut msg, &volatility)?;
+        write_field(&mut msg, &under_price)?;
 
         if self.server_version() >= MIN_SERVER_VER_LINKING {
             let _opt_prc_opt_str = "".to_string();
@@ -923,11 +1452,11 @@ where
                     .map(|x| format!("{}={};", x.tag, x.value))
                     .collect::<String>();
 
-                msg.push_str(&make_field(&tag_values_count)?);
-                msg.push_str(&make_field(&opt_prc_opt_str)?);
+                write_field(&mut msg, &tag_values_count)?;
+                write_field(&mut msg, &opt_prc_opt_str)?;
             }
         }
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -937,37 +1466,26 @@ where
     ///
     /// # Arguments
     /// * request_id - The original request id.
-    pub fn cancel_calculate_option_price(
-        &mut self,
-        request_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_calculate_option_price(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support calculateImpliedVolatility req."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::CancelCalcOptionPrice,
+            request_id,
+            "It does not support calculateImpliedVolatility req.",
+        )?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::CancelCalcOptionPrice.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::CancelCalcOptionPrice as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -980,34 +1498,26 @@ where
     pub fn cancel_calculate_implied_volatility(
         &mut self,
         request_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support calculateImpliedVolatility req."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::CancelCalcImpliedVolat,
+            request_id,
+            "It does not support calculateImpliedVolatility req.",
+        )?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::CancelCalcImpliedVolat.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::CancelCalcImpliedVolat as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -1040,59 +1550,51 @@ where
         exercise_quantity: i32,
         account: &str,
         over_ride: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && !contract.trading_class.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support con_id, multiplier, trading_class parameter in exercise_options."
-                ),
-            ));
+            let err = IbkrError::Connection(ConnectionError::new(request_id, TwsError::UpdateTws, "It does not support con_id, multiplier, trading_class parameter in exercise_options."));
 
             return Err(err);
         }
 
-        let version = 2;
+        let version = OutgoingMessageIds::ExerciseOptions.version();
 
         // send req market data msg
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ExerciseOptions as i32;
 
-        msg.push_str(&make_field(&message_id)?);
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
         // send contract fields
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.con_id)?);
-        }
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
+            write_field(&mut msg, &contract.con_id)?;
+        }
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
-        msg.push_str(&make_field(&exercise_action)?);
-        msg.push_str(&make_field(&exercise_quantity)?);
-        msg.push_str(&make_field(&account.to_string())?);
-        msg.push_str(&make_field(&over_ride)?);
+        write_field(&mut msg, &exercise_action)?;
+        write_field(&mut msg, &exercise_quantity)?;
+        write_field(&mut msg, &account.to_string())?;
+        write_field(&mut msg, &over_ride)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -1112,25 +1614,263 @@ where
     /// * order - This structure contains the details of the order.
     ///
     /// Note: Each client MUST connect with a unique client_id.
+    ///
+    /// Orders without an account are placed for the default account, see
+    /// set_default_account(). Fails while the kill switch is engaged, if
+    /// the account is not managed by the login or if the order violates a
+    /// blocking risk check, see set_risk_checks(). Orders over the limit of
+    /// the order throttle fail or are queued, see set_order_throttle(), and
+    /// so do orders outside the trading sessions of their contract, see
+    /// set_session_gate(). An order with the `order_ref` of another working
+    /// or queued order is handled as set_duplicate_ref_policy() says.
     pub fn place_order(
         &mut self,
         order_id: i32,
         contract: &Contract,
         order: &Order,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
+        if self.kill_switch_engaged {
+            return Err(IbkrError::rejected(
+                order_id,
+                "The kill switch is engaged. Call rearm() to place orders again.",
+            ));
+        }
+        let working_order_id = match self.duplicate_ref_policy {
+            DuplicateRefPolicy::Allow => None,
+            _ => self
+                .pending_order_by_ref(&order.order_ref)
+                .filter(|working_order_id| *working_order_id != order_id),
+        };
+        if let Some(working_order_id) = working_order_id {
+            if self.duplicate_ref_policy == DuplicateRefPolicy::Reject {
+                return Err(IbkrError::rejected(
+                    order_id,
+                    &format!(
+                        "Order {} with the order ref {} is still working.",
+                        working_order_id, order.order_ref
+                    ),
+                ));
+            }
+            info!(
+                "Placing order {} as a modification of order {} with the order ref {}",
+                order_id, working_order_id, order.order_ref
+            );
+            let mut order = order.clone();
+            order.order_id = working_order_id;
+            return self.place_order(working_order_id, contract, &order);
+        }
+        self.check_risk(order_id, contract, order)?;
+        let account = self.resolve_account(order_id, &order.account)?;
+        if account == order.account {
+            self.gate_place_order(order_id, contract, order)
+        } else {
+            let mut order = order.clone();
+            order.account = account;
+            self.gate_place_order(order_id, contract, &order)
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The order with an order ref that is working or still queued by the
+    /// session gate or the order throttle
+    fn pending_order_by_ref(&self, order_ref: &str) -> Option<i32> {
+        self.orders
+            .lock()
+            .expect(POISONED_MUTEX)
+            .working_order_by_ref(order_ref)
+            .or_else(|| {
+                self.order_throttle
+                    .as_ref()
+                    .and_then(|order_throttle| order_throttle.queued_by_ref(order_ref))
+            })
+            .or_else(|| {
+                self.session_gate
+                    .as_ref()
+                    .and_then(|session_gate| session_gate.queued_by_ref(order_ref))
+            })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Passes an order on to the order throttle if the session gate lets it
+    /// through. A queued order counts as placed.
+    fn gate_place_order(
+        &mut self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IbkrError> {
+        let Some(session_gate) = self.session_gate.as_mut() else {
+            return self.throttle_place_order(order_id, contract, order);
+        };
+        match session_gate.admit(&Utc::now(), order_id, contract, order) {
+            GateDecision::Open => self.throttle_place_order(order_id, contract, order),
+            GateDecision::Queued { opens } => {
+                info!(
+                    "Order {} queued until the market opens at {}",
+                    order_id, opens
+                );
+                Ok(())
+            }
+            GateDecision::Rejected(reason) => Err(IbkrError::rejected(order_id, &reason)),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Passes the orders queued by the session gate whose market is open now
+    /// on to the order throttle. Call it regularly while orders are queued.
+    /// Returns the ids of the orders passed on. Nothing is passed on while
+    /// the kill switch is engaged. If an order cannot be passed on, it and
+    /// the orders after it go back on the queue and the error is returned.
+    pub fn release_session_orders(&mut self) -> Result<Vec<i32>, IbkrError> {
+        if self.kill_switch_engaged {
+            return Ok(vec![]);
+        }
+        let released = match self.session_gate.as_mut() {
+            Some(session_gate) => session_gate.release(&Utc::now()),
+            None => return Ok(vec![]),
+        };
+        let mut sent = vec![];
+        let mut released = released.into_iter();
+        while let Some(queued) = released.next() {
+            if let Err(err) =
+                self.throttle_place_order(queued.order_id, &queued.contract, &queued.order)
+            {
+                error!(
+                    "Could not send order {} released by the session gate, requeued it \
+                     after sending {:?}: {}",
+                    queued.order_id, sent, err
+                );
+                if let Some(session_gate) = self.session_gate.as_mut() {
+                    session_gate.requeue(std::iter::once(queued).chain(released).collect());
+                }
+                return Err(err);
+            }
+            sent.push(queued.order_id);
+        }
+        Ok(sent)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sends an order if the order throttle lets it through. A queued order
+    /// counts as placed.
+    fn throttle_place_order(
+        &mut self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IbkrError> {
+        let Some(order_throttle) = self.order_throttle.as_mut().filter(|_| !order.what_if) else {
+            return self.send_place_order(order_id, contract, order);
+        };
+        match order_throttle.admit(Instant::now(), order_id, contract, order) {
+            Ok(()) => self.send_place_order(order_id, contract, order),
+            Err(event) => {
+                warn!("Order throttle: {}", event);
+                match event {
+                    ThrottleEvent::Throttled { queued: true, .. } => Ok(()),
+                    _ => Err(IbkrError::rejected(
+                        order_id,
+                        "The order throttle is at its limit.",
+                    )),
+                }
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sends the orders queued by the order throttle that its limits allow
+    /// now. Call it regularly while orders are queued. Returns the ids of
+    /// the orders sent. Nothing is sent while the kill switch is engaged.
+    /// If an order cannot be sent, it and the orders after it go back on
+    /// the queue and the error is returned.
+    pub fn release_throttled_orders(&mut self) -> Result<Vec<i32>, IbkrError> {
+        if self.kill_switch_engaged {
+            return Ok(vec![]);
+        }
+        let released = match self.order_throttle.as_mut() {
+            Some(order_throttle) => order_throttle.release(Instant::now()),
+            None => return Ok(vec![]),
+        };
+        let mut sent = vec![];
+        let mut released = released.into_iter();
+        while let Some(queued) = released.next() {
+            if let Err(err) =
+                self.send_place_order(queued.order_id, &queued.contract, &queued.order)
+            {
+                error!(
+                    "Could not send order {} released by the order throttle, requeued it \
+                     after sending {:?}: {}",
+                    queued.order_id, sent, err
+                );
+                if let Some(order_throttle) = self.order_throttle.as_mut() {
+                    order_throttle.requeue(std::iter::once(queued).chain(released).collect());
+                }
+                return Err(err);
+            }
+            sent.push(queued.order_id);
+        }
+        Ok(sent)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Evaluates the risk checks, if any. Orders without a limit or stop
+    /// price are valued at the last price or the midpoint of a quote of the
+    /// contract.
+    fn check_risk(
+        &mut self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IbkrError> {
+        let Some(risk_checks) = self.risk_checks.as_mut() else {
+            return Ok(());
+        };
+        let price = order_price(order).or_else(|| {
+            self.quotes
+                .lock()
+                .expect(POISONED_MUTEX)
+                .contract_quote(contract.con_id)
+                .and_then(|quote| quote.last.or_else(|| quote.midpoint()))
+        });
+        let risk_order = RiskOrder {
+            order_id,
+            contract,
+            order,
+            price,
+        };
+        match risk_checks.enforce(&risk_order) {
+            Ok(warnings) => {
+                for warning in warnings {
+                    warn!("Risk check failed: {}", warning);
+                }
+                Ok(())
+            }
+            Err(violation) => Err(IbkrError::rejected(
+                order_id,
+                &format!(
+                    "Risk check {} failed: {}",
+                    violation.check, violation.message
+                ),
+            )),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn send_place_order(
+        &mut self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
         if self.server_version() < MIN_SERVER_VER_DELTA_NEUTRAL
             && contract.delta_neutral_contract.is_some()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support delta-neutral orders."
-                ),
+                TwsError::UpdateTws,
+                "It does not support delta-neutral orders.",
             ));
 
             return Err(err);
@@ -1139,42 +1879,30 @@ where
         if self.server_version() < MIN_SERVER_VER_SCALE_ORDERS2
             && order.scale_subs_level_size != UNSET_INTEGER
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support Subsequent Level Size for Scale orders."
-                ),
+                TwsError::UpdateTws,
+                "It does not support Subsequent Level Size for Scale orders.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_ALGO_ORDERS && !order.algo_strategy.is_empty() {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support algo orders."
-                ),
+                TwsError::UpdateTws,
+                "It does not support algo orders.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_NOT_HELD && order.not_held {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support notHeld parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support notHeld parameter.",
             ));
 
             return Err(err);
@@ -1183,28 +1911,20 @@ where
         if self.server_version() < MIN_SERVER_VER_SEC_ID_TYPE
             && (!contract.sec_id_type.is_empty() || !contract.sec_id.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support sec_id_type && secId parameters."
-                ),
+                TwsError::UpdateTws,
+                "It does not support sec_id_type && secId parameters.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_PLACE_ORDER_CONID && contract.con_id > 0 {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support con_id parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support con_id parameter.",
             ));
 
             return Err(err);
@@ -1212,14 +1932,10 @@ where
 
         if self.server_version() < MIN_SERVER_VER_SSHORTX {
             if order.exempt_code != -1 {
-                let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                let err = IbkrError::Connection(ConnectionError::new(
                     order_id,
-                    TwsError::UpdateTws.code().to_string(),
-                    format!(
-                        "{}{}",
-                        TwsError::UpdateTws.message(),
-                        " It does not support exempt_code parameter."
-                    ),
+                    TwsError::UpdateTws,
+                    "It does not support exempt_code parameter.",
                 ));
 
                 return Err(err);
@@ -1227,28 +1943,20 @@ where
             if !contract.combo_legs.is_empty()
                 && contract.combo_legs.iter().any(|x| x.exempt_code != -1)
             {
-                let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                let err = IbkrError::Connection(ConnectionError::new(
                     order_id,
-                    TwsError::UpdateTws.code().to_string(),
-                    format!(
-                        "{}{}",
-                        TwsError::UpdateTws.message(),
-                        " It does not support exempt_code parameter."
-                    ),
+                    TwsError::UpdateTws,
+                    "It does not support exempt_code parameter.",
                 ));
 
                 return Err(err);
             }
         }
         if self.server_version() < MIN_SERVER_VER_HEDGE_ORDERS && !order.hedge_type.is_empty() {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support hedge orders."
-                ),
+                TwsError::UpdateTws,
+                "It does not support hedge orders.",
             ));
 
             return Err(err);
@@ -1257,14 +1965,10 @@ where
         if self.server_version() < MIN_SERVER_VER_OPT_OUT_SMART_ROUTING
             && order.opt_out_smart_routing
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support optOutSmartRouting parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support optOutSmartRouting parameter.",
             ));
 
             return Err(err);
@@ -1276,15 +1980,7 @@ where
                 || !order.delta_neutral_clearing_account.is_empty()
                 || !order.delta_neutral_clearing_intent.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support deltaNeutral parameters: con_id, SettlingFirm, ClearingAccount, ClearingIntent."
-                ),
-            ));
+            let err = IbkrError::Connection(ConnectionError::new(order_id, TwsError::UpdateTws, "It does not support deltaNeutral parameters: con_id, SettlingFirm, ClearingAccount, ClearingIntent."));
 
             return Err(err);
         }
@@ -1295,15 +1991,7 @@ where
                 || order.delta_neutral_short_sale_slot > 0
                 || !order.delta_neutral_designated_location.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support deltaNeutral parameters: open_close, ShortSale, short_sale_slot, designated_location."
-                ),
-            ));
+            let err = IbkrError::Connection(ConnectionError::new(order_id, TwsError::UpdateTws, "It does not support deltaNeutral parameters: open_close, ShortSale, short_sale_slot, designated_location."));
 
             return Err(err);
         }
@@ -1319,36 +2007,24 @@ where
                 || order.scale_init_fill_qty != UNSET_INTEGER
                 || order.scale_random_percent)
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support Scale order parameters: PriceAdjustValue, \
-                PriceAdjustInterval, ProfitOffset, AutoReset, InitPosition, InitFillQty && RandomPercent"
-                ),
-            ));
+            let err = IbkrError::Connection(ConnectionError::new(order_id, TwsError::UpdateTws, "It does not support Scale order parameters: PriceAdjustValue, \
+                PriceAdjustInterval, ProfitOffset, AutoReset, InitPosition, InitFillQty && RandomPercent"));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_ORDER_COMBO_LEGS_PRICE
-            && contract.sec_type == "BAG"
+            && contract.sec_type == SecType::Combo
             && !order.order_combo_legs.is_empty()
             && order
                 .order_combo_legs
                 .iter()
                 .any(|x| x.price != UNSET_DOUBLE)
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support per-leg prices for order combo legs."
-                ),
+                TwsError::UpdateTws,
+                "It does not support per-leg prices for order combo legs.",
             ));
 
             return Err(err);
@@ -1357,14 +2033,10 @@ where
         if self.server_version() < MIN_SERVER_VER_TRAILING_PERCENT
             && order.trailing_percent != UNSET_DOUBLE
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support trailing percent parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support trailing percent parameter.",
             ));
 
             return Err(err);
@@ -1373,14 +2045,10 @@ where
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && !contract.trading_class.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support trading_class parameter in placeOrder."
-                ),
+                TwsError::UpdateTws,
+                "It does not support trading_class parameter in placeOrder.",
             ));
 
             return Err(err);
@@ -1391,70 +2059,50 @@ where
                 || !order.active_start_time.is_empty()
                 || !order.active_stop_time.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support scaleTable, activeStartTime && activeStopTime parameters."
-                ),
+                TwsError::UpdateTws,
+                "It does not support scaleTable, activeStartTime && activeStopTime parameters.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_ALGO_ID && !order.algo_id.is_empty() {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support algoId parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support algoId parameter.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_ORDER_SOLICITED && order.solicited {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support order solicited parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support order solicited parameter.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_MODELS_SUPPORT && !order.model_code.is_empty() {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support model code parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support model code parameter.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_EXT_OPERATOR && !order.ext_operator.is_empty() {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support ext operator parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support ext operator parameter.",
             ));
 
             return Err(err);
@@ -1463,28 +2111,20 @@ where
         if self.server_version() < MIN_SERVER_VER_SOFT_DOLLAR_TIER
             && (!order.soft_dollar_tier.name.is_empty() || !order.soft_dollar_tier.val.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support soft dollar tier."
-                ),
+                TwsError::UpdateTws,
+                "It does not support soft dollar tier.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_CASH_QTY && order.cash_qty != 0.0 {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support cash quantity parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support cash quantity parameter.",
             ));
 
             return Err(err);
@@ -1493,14 +2133,10 @@ where
         if self.server_version() < MIN_SERVER_VER_DECISION_MAKER
             && (!order.mifid2decision_maker.is_empty() || !order.mifid2decision_algo.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support MIFID II decision maker parameters."
-                ),
+                TwsError::UpdateTws,
+                "It does not support MIFID II decision maker parameters.",
             ));
 
             return Err(err);
@@ -1509,14 +2145,10 @@ where
         if self.server_version() < MIN_SERVER_VER_MIFID_EXECUTION
             && (!order.mifid2execution_trader.is_empty() || !order.mifid2execution_algo.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support MIFID II execution parameters."
-                ),
+                TwsError::UpdateTws,
+                "It does not support MIFID II execution parameters.",
             ));
 
             return Err(err);
@@ -1525,42 +2157,102 @@ where
         if self.server_version() < MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE
             && order.dont_use_auto_price_for_hedge
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support dontUseAutoPriceForHedge parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support dontUseAutoPriceForHedge parameter.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_ORDER_CONTAINER && order.is_oms_container {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support oms container parameter."
-                ),
+                TwsError::UpdateTws,
+                "It does not support oms container parameter.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_PRICE_MGMT_ALGO && order.use_price_mgmt_algo {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 order_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support Use price management algo requests."
-                ),
+                TwsError::UpdateTws,
+                "It does not support Use price management algo requests.",
+            ));
+
+            return Err(err);
+        }
+
+        if self.server_version() < MIN_SERVER_VER_DURATION && order.duration != UNSET_INTEGER {
+            let err = IbkrError::Connection(ConnectionError::new(
+                order_id,
+                TwsError::UpdateTws,
+                "It does not support duration attribute.",
+            ));
+
+            return Err(err);
+        }
+
+        if self.server_version() < MIN_SERVER_VER_POST_TO_ATS && order.post_to_ats != UNSET_INTEGER
+        {
+            let err = IbkrError::Connection(ConnectionError::new(
+                order_id,
+                TwsError::UpdateTws,
+                "It does not support postToAts attribute.",
+            ));
+
+            return Err(err);
+        }
+
+        if self.server_version() < MIN_SERVER_VER_AUTO_CANCEL_PARENT && order.auto_cancel_parent {
+            let err = IbkrError::Connection(ConnectionError::new(
+                order_id,
+                TwsError::UpdateTws,
+                "It does not support autoCancelParent attribute.",
+            ));
+
+            return Err(err);
+        }
+
+        if self.server_version() < MIN_SERVER_VER_ADVANCED_ORDER_REJECT
+            && !order.advanced_error_override.is_empty()
+        {
+            let err = IbkrError::Connection(ConnectionError::new(
+                order_id,
+                TwsError::UpdateTws,
+                "It does not support advanced error override attribute.",
+            ));
+
+            return Err(err);
+        }
+
+        if self.server_version() < MIN_SERVER_VER_MANUAL_ORDER_TIME
+            && !order.manual_order_time.is_empty()
+        {
+            let err = IbkrError::Connection(ConnectionError::new(
+                order_id,
+                TwsError::UpdateTws,
+                "It does not support manual order time attribute.",
+            ));
+
+            return Err(err);
+        }
+
+        if self.server_version() < MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS
+            && (order.min_trade_qty != UNSET_INTEGER
+                || order.min_compete_size != UNSET_INTEGER
+                || order.compete_against_best_offset != UNSET_DOUBLE
+                || order.mid_offset_at_whole != UNSET_DOUBLE
+                || order.mid_offset_at_half != UNSET_DOUBLE)
+        {
+            let err = IbkrError::Connection(ConnectionError::new(
+                order_id,
+                TwsError::UpdateTws,
+                "It does not support PEG BEST / PEG MID order parameters: minTradeQty, \
+                 minCompeteSize, competeAgainstBestOffset, midOffsetAtWhole and midOffsetAtHalf.",
             ));
 
             return Err(err);
@@ -1569,108 +2261,114 @@ where
         let version: i32 = if self.server_version() < MIN_SERVER_VER_NOT_HELD {
             27
         } else {
-            45
+            OutgoingMessageIds::PlaceOrder.version()
         };
 
         //send place order msg
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::PlaceOrder as i32;
 
-        msg.push_str(&make_field(&message_id)?);
+        write_field(&mut msg, &message_id)?;
 
         if self.server_version() < MIN_SERVER_VER_ORDER_CONTAINER {
-            msg.push_str(&make_field(&version)?);
+            write_field(&mut msg, &version)?;
         }
 
-        msg.push_str(&make_field(&order_id)?);
+        write_field(&mut msg, &order_id)?;
 
         // send contract fields
         if self.server_version() >= MIN_SERVER_VER_PLACE_ORDER_CONID {
-            msg.push_str(&make_field(&contract.con_id)?);
-        }
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?); // srv v15 && above
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?); // srv v14 && above
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?); // srv v2 && above
+            write_field(&mut msg, &contract.con_id)?;
+        }
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?; // srv v15 && above
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?; // srv v14 && above
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?; // srv v2 && above
 
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_SEC_ID_TYPE {
-            msg.push_str(&make_field(&contract.sec_id_type)?);
-            msg.push_str(&make_field(&contract.sec_id)?);
+            write_field(&mut msg, &contract.sec_id_type)?;
+            write_field(&mut msg, &contract.sec_id)?;
         }
 
         // send main order fields
-        msg.push_str(&make_field(&order.action)?);
+        write_field(&mut msg, &order.action)?;
 
         if self.server_version() >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
-            msg.push_str(&make_field(&order.total_quantity)?);
+            write_field(&mut msg, &order.total_quantity)?;
         } else {
-            msg.push_str(&make_field(&(order.total_quantity as i32))?);
+            write_field(&mut msg, &(order.total_quantity as i32))?;
         }
 
-        msg.push_str(&make_field(&order.order_type)?);
+        write_field(&mut msg, &order.order_type)?;
 
         if self.server_version() < MIN_SERVER_VER_ORDER_COMBO_LEGS_PRICE {
-            msg.push_str(&make_field(if order.lmt_price != UNSET_DOUBLE {
-                &order.lmt_price
-            } else {
-                &0
-            })?);
+            write_field(
+                &mut msg,
+                if order.lmt_price != UNSET_DOUBLE {
+                    &order.lmt_price
+                } else {
+                    &0.0
+                },
+            )?;
         } else {
-            msg.push_str(&make_field_handle_empty(&order.lmt_price)?);
+            write_field(&mut msg, &order.lmt_price)?;
         }
 
         if self.server_version() < MIN_SERVER_VER_TRAILING_PERCENT {
-            msg.push_str(&make_field(if order.aux_price != UNSET_DOUBLE {
-                &order.aux_price
-            } else {
-                &0
-            })?);
+            write_field(
+                &mut msg,
+                if order.aux_price != UNSET_DOUBLE {
+                    &order.aux_price
+                } else {
+                    &0.0
+                },
+            )?;
         } else {
-            msg.push_str(&make_field_handle_empty(&order.aux_price)?);
+            write_field(&mut msg, &order.aux_price)?;
         }
 
         // send extended order fields
-        msg.push_str(&make_field(&order.tif)?);
-        msg.push_str(&make_field(&order.oca_group)?);
-        msg.push_str(&make_field(&order.account)?);
-        msg.push_str(&make_field(&order.open_close)?);
-        msg.push_str(&make_field(&(order.origin as i32))?);
-        msg.push_str(&make_field(&order.order_ref)?);
-        msg.push_str(&make_field(&order.transmit)?);
-        msg.push_str(&make_field(&order.parent_id)?); // srv v4 && above
-        msg.push_str(&make_field(&order.block_order)?); // srv v5 && above
-        msg.push_str(&make_field(&order.sweep_to_fill)?); // srv v5 && above
-        msg.push_str(&make_field(&order.display_size)?); // srv v5 && above
-        msg.push_str(&make_field(&order.trigger_method)?); // srv v5 && above
-        msg.push_str(&make_field(&order.outside_rth)?); // srv v5 && above
-        msg.push_str(&make_field(&order.hidden)?); // srv v7 && above
+        write_field(&mut msg, &order.tif)?;
+        write_field(&mut msg, &order.oca_group)?;
+        write_field(&mut msg, &order.account)?;
+        write_field(&mut msg, &order.open_close)?;
+        write_field(&mut msg, &(order.origin as i32))?;
+        write_field(&mut msg, &order.order_ref)?;
+        write_field(&mut msg, &order.transmit)?;
+        write_field(&mut msg, &order.parent_id)?; // srv v4 && above
+        write_field(&mut msg, &order.block_order)?; // srv v5 && above
+        write_field(&mut msg, &order.sweep_to_fill)?; // srv v5 && above
+        write_field(&mut msg, &order.display_size)?; // srv v5 && above
+        write_field(&mut msg, &order.trigger_method)?; // srv v5 && above
+        write_field(&mut msg, &order.outside_rth)?; // srv v5 && above
+        write_field(&mut msg, &order.hidden)?; // srv v7 && above
 
         // Send combo legs for BAG requests (srv v8 && above)
-        if contract.sec_type == "BAG" {
+        if contract.sec_type == SecType::Combo {
             let combo_legs_count = contract.combo_legs.len();
-            msg.push_str(&make_field(&combo_legs_count)?);
+            write_field(&mut msg, &combo_legs_count)?;
             if combo_legs_count > 0 {
                 for combo_leg in &contract.combo_legs {
-                    msg.push_str(&make_field(&combo_leg.con_id)?);
-                    msg.push_str(&make_field(&combo_leg.ratio)?);
-                    msg.push_str(&make_field(&combo_leg.action)?);
-                    msg.push_str(&make_field(&combo_leg.exchange)?);
-                    msg.push_str(&make_field(&(combo_leg.open_close as i32))?);
-                    msg.push_str(&make_field(&combo_leg.short_sale_slot)?); //srv v35 && above
-                    msg.push_str(&make_field(&combo_leg.designated_location)?); // srv v35 && above
+                    write_field(&mut msg, &combo_leg.con_id)?;
+                    write_field(&mut msg, &combo_leg.ratio)?;
+                    write_field(&mut msg, &combo_leg.action)?;
+                    write_field(&mut msg, &combo_leg.exchange)?;
+                    write_field(&mut msg, &(combo_leg.open_close as i32))?;
+                    write_field(&mut msg, &combo_leg.short_sale_slot)?; //srv v35 && above
+                    write_field(&mut msg, &combo_leg.designated_location)?; // srv v35 && above
                     if self.server_version() >= MIN_SERVER_VER_SSHORTX_OLD {
-                        msg.push_str(&make_field(&combo_leg.exempt_code)?);
+                        write_field(&mut msg, &combo_leg.exempt_code)?;
                     }
                 }
             }
@@ -1678,27 +2376,27 @@ where
 
         // Send order combo legs for BAG requests
         if self.server_version() >= MIN_SERVER_VER_ORDER_COMBO_LEGS_PRICE
-            && contract.sec_type == "BAG"
+            && contract.sec_type == SecType::Combo
         {
             let order_combo_legs_count = order.order_combo_legs.len();
 
-            msg.push_str(&make_field(&order_combo_legs_count)?);
+            write_field(&mut msg, &order_combo_legs_count)?;
             if order_combo_legs_count > 0 {
                 for order_combo_leg in &order.order_combo_legs {
-                    msg.push_str(&make_field_handle_empty(&order_combo_leg.price)?);
+                    write_field(&mut msg, &order_combo_leg.price)?;
                 }
             }
         }
 
         if self.server_version() >= MIN_SERVER_VER_SMART_COMBO_ROUTING_PARAMS
-            && contract.sec_type == "BAG"
+            && contract.sec_type == SecType::Combo
         {
             let smart_combo_routing_params_count = order.smart_combo_routing_params.len();
-            msg.push_str(&make_field(&smart_combo_routing_params_count)?);
+            write_field(&mut msg, &smart_combo_routing_params_count)?;
             if smart_combo_routing_params_count > 0 {
                 for tag_value in &order.smart_combo_routing_params {
-                    msg.push_str(&make_field(&tag_value.tag)?);
-                    msg.push_str(&make_field(&tag_value.value)?);
+                    write_field(&mut msg, &tag_value.tag)?;
+                    write_field(&mut msg, &tag_value.value)?;
                 }
             }
         }
@@ -1717,179 +2415,172 @@ where
         //    #####################################################################
 
         // send deprecated sharesAllocation field
-        msg.push_str(&make_field(&"")?); // srv v9 && above
+        write_field(&mut msg, &"")?; // srv v9 && above
 
-        msg.push_str(&make_field(&order.discretionary_amt)?); // srv v10 && above
-        msg.push_str(&make_field(&order.good_after_time)?); // srv v11 && above
-        msg.push_str(&make_field(&order.good_till_date)?); // srv v12 && above
+        write_field(&mut msg, &order.discretionary_amt)?; // srv v10 && above
+        write_field(&mut msg, &order.good_after_time)?; // srv v11 && above
+        write_field(&mut msg, &order.good_till_date)?; // srv v12 && above
 
-        msg.push_str(&make_field(&order.fa_group)?); // srv v13 && above
-        msg.push_str(&make_field(&order.fa_method)?); // srv v13 && above
-        msg.push_str(&make_field(&order.fa_percentage)?); // srv v13 && above
-        msg.push_str(&make_field(&order.fa_profile)?); // srv v13 && above
+        write_field(&mut msg, &order.fa_group)?; // srv v13 && above
+        write_field(&mut msg, &order.fa_method)?; // srv v13 && above
+        write_field(&mut msg, &order.fa_percentage)?; // srv v13 && above
+        write_field(&mut msg, &order.fa_profile)?; // srv v13 && above
 
         if self.server_version() >= MIN_SERVER_VER_MODELS_SUPPORT {
-            msg.push_str(&make_field(&order.model_code)?);
+            write_field(&mut msg, &order.model_code)?;
         }
 
         // institutional short saleslot data (srv v18 && above)
-        msg.push_str(&make_field(&order.short_sale_slot)?); // 0 for retail, 1 || 2 for institutions
-        msg.push_str(&make_field(&order.designated_location)?); // populate only when shortSaleSlot = 2.
+        write_field(&mut msg, &order.short_sale_slot)?; // 0 for retail, 1 || 2 for institutions
+        write_field(&mut msg, &order.designated_location)?; // populate only when shortSaleSlot = 2.
 
         if self.server_version() >= MIN_SERVER_VER_SSHORTX_OLD {
-            msg.push_str(&make_field(&order.exempt_code)?);
+            write_field(&mut msg, &order.exempt_code)?;
         }
 
         // not needed anymore
         //bool isVolOrder = (order.orderType.CompareNoCase("VOL").as_ref() == 0)
 
         // srv v19 && above fields
-        msg.push_str(&make_field(&order.oca_type)?);
+        write_field(&mut msg, &order.oca_type)?;
         //if( self.server_version() < 38) {
         // will never happen
         //      send( /* order.rthOnly */ false);
         //}
-        msg.push_str(&make_field(&order.rule80a)?);
-        msg.push_str(&make_field(&order.settling_firm)?);
-        msg.push_str(&make_field(&order.all_or_none)?);
-        msg.push_str(&make_field_handle_empty(&order.min_qty)?);
-        msg.push_str(&make_field_handle_empty(&order.percent_offset)?);
-        msg.push_str(&make_field(&order.e_trade_only)?);
-        msg.push_str(&make_field(&order.firm_quote_only)?);
-        msg.push_str(&make_field_handle_empty(&order.nbbo_price_cap)?);
-        msg.push_str(&make_field(&(order.auction_strategy as i32))?); // AUCTION_MATCH, AUCTION_IMPROVEMENT, AUCTION_TRANSPARENT
-        msg.push_str(&make_field_handle_empty(&order.starting_price)?);
-        msg.push_str(&make_field_handle_empty(&order.stock_ref_price)?);
-        msg.push_str(&make_field_handle_empty(&order.delta)?);
-        msg.push_str(&make_field_handle_empty(&order.stock_range_lower)?);
-        msg.push_str(&make_field_handle_empty(&order.stock_range_upper)?);
-
-        msg.push_str(&make_field(&order.override_percentage_constraints)?); //srv v22 && above
+        write_field(&mut msg, &order.rule80a)?;
+        write_field(&mut msg, &order.settling_firm)?;
+        write_field(&mut msg, &order.all_or_none)?;
+        write_field(&mut msg, &order.min_qty)?;
+        write_field(&mut msg, &order.percent_offset)?;
+        write_field(&mut msg, &order.e_trade_only)?;
+        write_field(&mut msg, &order.firm_quote_only)?;
+        write_field(&mut msg, &order.nbbo_price_cap)?;
+        write_field(&mut msg, &(order.auction_strategy as i32))?; // AUCTION_MATCH, AUCTION_IMPROVEMENT, AUCTION_TRANSPARENT
+        write_field(&mut msg, &order.starting_price)?;
+        write_field(&mut msg, &order.stock_ref_price)?;
+        write_field(&mut msg, &order.delta)?;
+        write_field(&mut msg, &order.stock_range_lower)?;
+        write_field(&mut msg, &order.stock_range_upper)?;
+
+        write_field(&mut msg, &order.override_percentage_constraints)?; //srv v22 && above
 
         // volatility orders (srv v26 && above)
-        msg.push_str(&make_field_handle_empty(&order.volatility)?);
-        msg.push_str(&make_field_handle_empty(&order.volatility_type)?);
-        msg.push_str(&make_field(&order.delta_neutral_order_type)?); // srv v28 && above
-        msg.push_str(&make_field_handle_empty(&order.delta_neutral_aux_price)?); // srv v28 && above
+        write_field(&mut msg, &order.volatility)?;
+        write_field(&mut msg, &order.volatility_type)?;
+        write_field(&mut msg, &order.delta_neutral_order_type)?; // srv v28 && above
+        write_field(&mut msg, &order.delta_neutral_aux_price)?; // srv v28 && above
 
         if self.server_version() >= MIN_SERVER_VER_DELTA_NEUTRAL_CONID
             && !order.delta_neutral_order_type.is_empty()
         {
-            msg.push_str(&make_field(&order.delta_neutral_con_id)?);
-            msg.push_str(&make_field(&order.delta_neutral_settling_firm)?);
-            msg.push_str(&make_field(&order.delta_neutral_clearing_account)?);
-            msg.push_str(&make_field(&order.delta_neutral_clearing_intent)?);
+            write_field(&mut msg, &order.delta_neutral_con_id)?;
+            write_field(&mut msg, &order.delta_neutral_settling_firm)?;
+            write_field(&mut msg, &order.delta_neutral_clearing_account)?;
+            write_field(&mut msg, &order.delta_neutral_clearing_intent)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_DELTA_NEUTRAL_OPEN_CLOSE
             && !order.delta_neutral_order_type.is_empty()
         {
-            msg.push_str(&make_field(&order.delta_neutral_open_close)?);
-            msg.push_str(&make_field(&order.delta_neutral_short_sale)?);
-            msg.push_str(&make_field(&order.delta_neutral_short_sale_slot)?);
-            msg.push_str(&make_field(&order.delta_neutral_designated_location)?);
+            write_field(&mut msg, &order.delta_neutral_open_close)?;
+            write_field(&mut msg, &order.delta_neutral_short_sale)?;
+            write_field(&mut msg, &order.delta_neutral_short_sale_slot)?;
+            write_field(&mut msg, &order.delta_neutral_designated_location)?;
         }
 
-        msg.push_str(&make_field(&order.continuous_update)?);
-        msg.push_str(&make_field_handle_empty(&order.reference_price_type)?);
-        msg.push_str(&make_field_handle_empty(&order.trail_stop_price)?); // srv v30 && above
+        write_field(&mut msg, &order.continuous_update)?;
+        write_field(&mut msg, &order.reference_price_type)?;
+        write_field(&mut msg, &order.trail_stop_price)?; // srv v30 && above
 
         if self.server_version() >= MIN_SERVER_VER_TRAILING_PERCENT {
-            msg.push_str(&make_field_handle_empty(&order.trailing_percent)?);
+            write_field(&mut msg, &order.trailing_percent)?;
         }
 
         // SCALE orders
         if self.server_version() >= MIN_SERVER_VER_SCALE_ORDERS2 {
-            msg.push_str(&make_field_handle_empty(&order.scale_init_level_size)?);
-            msg.push_str(&make_field_handle_empty(&order.scale_subs_level_size)?);
+            write_field(&mut msg, &order.scale_init_level_size)?;
+            write_field(&mut msg, &order.scale_subs_level_size)?;
         } else {
             // srv v35 && above)
-            msg.push_str(&make_field(&"")?); // for not supported scaleNumComponents
-            msg.push_str(&make_field_handle_empty(&order.scale_init_level_size)?);
+            write_field(&mut msg, &"")?; // for not supported scaleNumComponents
+            write_field(&mut msg, &order.scale_init_level_size)?;
             // for scaleComponentSize
         }
 
-        msg.push_str(&make_field_handle_empty(&order.scale_price_increment)?);
+        write_field(&mut msg, &order.scale_price_increment)?;
 
         if self.server_version() >= MIN_SERVER_VER_SCALE_ORDERS3
             && order.scale_price_increment != UNSET_DOUBLE
             && order.scale_price_increment > 0.0
         {
-            msg.push_str(&make_field_handle_empty(&order.scale_price_adjust_value)?);
-            msg.push_str(&make_field_handle_empty(
-                &order.scale_price_adjust_interval,
-            )?);
-            msg.push_str(&make_field_handle_empty(&order.scale_profit_offset)?);
-            msg.push_str(&make_field(&order.scale_auto_reset)?);
-            msg.push_str(&make_field_handle_empty(&order.scale_init_position)?);
-            msg.push_str(&make_field_handle_empty(&order.scale_init_fill_qty)?);
-            msg.push_str(&make_field(&order.scale_random_percent)?);
+            write_field(&mut msg, &order.scale_price_adjust_value)?;
+            write_field(&mut msg, &order.scale_price_adjust_interval)?;
+            write_field(&mut msg, &order.scale_profit_offset)?;
+            write_field(&mut msg, &order.scale_auto_reset)?;
+            write_field(&mut msg, &order.scale_init_position)?;
+            write_field(&mut msg, &order.scale_init_fill_qty)?;
+            write_field(&mut msg, &order.scale_random_percent)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_SCALE_TABLE {
-            msg.push_str(&make_field(&order.scale_table)?);
-            msg.push_str(&make_field(&order.active_start_time)?);
-            msg.push_str(&make_field(&order.active_stop_time)?);
+            write_field(&mut msg, &order.scale_table)?;
+            write_field(&mut msg, &order.active_start_time)?;
+            write_field(&mut msg, &order.active_stop_time)?;
         }
 
         // HEDGE orders
         if self.server_version() >= MIN_SERVER_VER_HEDGE_ORDERS {
-            msg.push_str(&make_field(&order.hedge_type)?);
+            write_field(&mut msg, &order.hedge_type)?;
 
             if !order.hedge_type.is_empty() {
-                msg.push_str(&make_field(&order.hedge_param)?);
+                write_field(&mut msg, &order.hedge_param)?;
             }
         }
 
         if self.server_version() >= MIN_SERVER_VER_OPT_OUT_SMART_ROUTING {
-            msg.push_str(&make_field(&order.opt_out_smart_routing)?);
+            write_field(&mut msg, &order.opt_out_smart_routing)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_PTA_ORDERS {
-            msg.push_str(&make_field(&order.clearing_account)?);
-            msg.push_str(&make_field(&order.clearing_intent)?);
+            write_field(&mut msg, &order.clearing_account)?;
+            write_field(&mut msg, &order.clearing_intent)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_NOT_HELD {
-            msg.push_str(&make_field(&order.not_held)?);
+            write_field(&mut msg, &order.not_held)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_DELTA_NEUTRAL {
-            if contract.delta_neutral_contract.is_some() {
-                msg.push_str(&make_field(&true)?);
-                msg.push_str(&make_field(
-                    &contract.delta_neutral_contract.as_ref().unwrap().con_id,
-                )?);
-                msg.push_str(&make_field(
-                    &contract.delta_neutral_contract.as_ref().unwrap().delta,
-                )?);
-                msg.push_str(&make_field(
-                    &contract.delta_neutral_contract.as_ref().unwrap().price,
-                )?);
-            } else {
-                msg.push_str(&make_field(&false)?);
+            match &contract.delta_neutral_contract {
+                Some(delta_neutral_contract) => {
+                    write_field(&mut msg, &true)?;
+                    write_field(&mut msg, &delta_neutral_contract.con_id)?;
+                    write_field(&mut msg, &delta_neutral_contract.delta)?;
+                    write_field(&mut msg, &delta_neutral_contract.price)?;
+                }
+                None => write_field(&mut msg, &false)?,
             }
         }
 
         if self.server_version() >= MIN_SERVER_VER_ALGO_ORDERS {
-            msg.push_str(&make_field(&order.algo_strategy)?);
+            write_field(&mut msg, &order.algo_strategy)?;
             if !order.algo_strategy.is_empty() {
                 let algo_params_count = order.algo_params.len();
-                msg.push_str(&make_field(&algo_params_count)?);
+                write_field(&mut msg, &algo_params_count)?;
                 if algo_params_count > 0 {
                     for algo_param in &order.algo_params {
-                        msg.push_str(&make_field(&algo_param.tag)?);
-                        msg.push_str(&make_field(&algo_param.value)?);
+                        write_field(&mut msg, &algo_param.tag)?;
+                        write_field(&mut msg, &algo_param.value)?;
                     }
                 }
             }
         }
 
         if self.server_version() >= MIN_SERVER_VER_ALGO_ID {
-            msg.push_str(&make_field(&order.algo_id)?);
+            write_field(&mut msg, &order.algo_id)?;
         }
 
-        msg.push_str(&make_field(&order.what_if)?); // srv v36 && above
+        write_field(&mut msg, &order.what_if)?; // srv v36 && above
 
         // send miscOptions parameter
         if self.server_version() >= MIN_SERVER_VER_LINKING {
@@ -1898,112 +2589,279 @@ where
                 .iter()
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
-            msg.push_str(&make_field(&misc_options_str)?);
+            write_field(&mut msg, &misc_options_str)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_ORDER_SOLICITED {
-            msg.push_str(&make_field(&order.solicited)?);
+            write_field(&mut msg, &order.solicited)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_RANDOMIZE_SIZE_AND_PRICE {
-            msg.push_str(&make_field(&order.randomize_size)?);
-            msg.push_str(&make_field(&order.randomize_price)?);
+            write_field(&mut msg, &order.randomize_size)?;
+            write_field(&mut msg, &order.randomize_price)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_PEGGED_TO_BENCHMARK {
             if order.order_type == "PEG BENCH" {
-                msg.push_str(&make_field(&order.reference_contract_id)?);
-                msg.push_str(&make_field(&order.is_pegged_change_amount_decrease)?);
-                msg.push_str(&make_field(&order.pegged_change_amount)?);
-                msg.push_str(&make_field(&order.reference_change_amount)?);
-                msg.push_str(&make_field(&order.reference_exchange_id)?);
+                write_field(&mut msg, &order.reference_contract_id)?;
+                write_field(&mut msg, &order.is_pegged_change_amount_decrease)?;
+                write_field(&mut msg, &order.pegged_change_amount)?;
+                write_field(&mut msg, &order.reference_change_amount)?;
+                write_field(&mut msg, &order.reference_exchange_id)?;
             }
 
-            msg.push_str(&make_field(&order.conditions.len())?);
+            write_field(&mut msg, &order.conditions.len())?;
 
             if !order.conditions.is_empty() {
                 for cond in &order.conditions {
-                    msg.push_str(&make_field(&(cond.get_type() as i32))?);
-                    let vals = cond.make_fields()?;
-                    let vals_string = vals.iter().cloned().collect::<String>();
-                    msg.push_str(vals_string.as_ref());
+                    for field in encode_condition(cond)? {
+                        msg.extend_from_slice(field.as_bytes());
+                    }
                 }
 
-                msg.push_str(&make_field(&order.conditions_ignore_rth)?);
-                msg.push_str(&make_field(&order.conditions_cancel_order)?);
+                write_field(&mut msg, &order.conditions_ignore_rth)?;
+                write_field(&mut msg, &order.conditions_cancel_order)?;
             }
 
-            msg.push_str(&make_field(&order.adjusted_order_type)?);
-            msg.push_str(&make_field(&order.trigger_price)?);
-            msg.push_str(&make_field(&order.lmt_price_offset)?);
-            msg.push_str(&make_field(&order.adjusted_stop_price)?);
-            msg.push_str(&make_field(&order.adjusted_stop_limit_price)?);
-            msg.push_str(&make_field(&order.adjusted_trailing_amount)?);
-            msg.push_str(&make_field(&order.adjustable_trailing_unit)?);
+            write_field(&mut msg, &order.adjusted_order_type)?;
+            write_field(&mut msg, &order.trigger_price)?;
+            write_field(&mut msg, &order.lmt_price_offset)?;
+            write_field(&mut msg, &order.adjusted_stop_price)?;
+            write_field(&mut msg, &order.adjusted_stop_limit_price)?;
+            write_field(&mut msg, &order.adjusted_trailing_amount)?;
+            write_field(&mut msg, &order.adjustable_trailing_unit)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_EXT_OPERATOR {
-            msg.push_str(&make_field(&order.ext_operator)?);
+            write_field(&mut msg, &order.ext_operator)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_SOFT_DOLLAR_TIER {
-            msg.push_str(&make_field(&order.soft_dollar_tier.name)?);
-            msg.push_str(&make_field(&order.soft_dollar_tier.val)?);
+            write_field(&mut msg, &order.soft_dollar_tier.name)?;
+            write_field(&mut msg, &order.soft_dollar_tier.val)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_CASH_QTY {
-            msg.push_str(&make_field(&order.cash_qty)?);
+            write_field(&mut msg, &order.cash_qty)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_DECISION_MAKER {
-            msg.push_str(&make_field(&order.mifid2decision_maker)?);
-            msg.push_str(&make_field(&order.mifid2decision_algo)?);
+            write_field(&mut msg, &order.mifid2decision_maker)?;
+            write_field(&mut msg, &order.mifid2decision_algo)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_MIFID_EXECUTION {
-            msg.push_str(&make_field(&order.mifid2execution_trader)?);
-            msg.push_str(&make_field(&order.mifid2execution_algo)?);
+            write_field(&mut msg, &order.mifid2execution_trader)?;
+            write_field(&mut msg, &order.mifid2execution_algo)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE {
-            msg.push_str(&make_field(&order.dont_use_auto_price_for_hedge)?);
+            write_field(&mut msg, &order.dont_use_auto_price_for_hedge)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_ORDER_CONTAINER {
-            msg.push_str(&make_field(&order.is_oms_container)?);
+            write_field(&mut msg, &order.is_oms_container)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_D_PEG_ORDERS {
-            msg.push_str(&make_field(&order.discretionary_up_to_limit_price)?);
+            write_field(&mut msg, &order.discretionary_up_to_limit_price)?;
         }
 
         if self.server_version() >= MIN_SERVER_VER_PRICE_MGMT_ALGO {
-            msg.push_str(&make_field_handle_empty(&order.use_price_mgmt_algo)?);
+            write_field(&mut msg, &order.use_price_mgmt_algo)?;
+        }
+
+        if self.server_version() >= MIN_SERVER_VER_DURATION {
+            write_field(&mut msg, &order.duration)?;
+        }
+
+        if self.server_version() >= MIN_SERVER_VER_POST_TO_ATS {
+            write_field(&mut msg, &order.post_to_ats)?;
+        }
+
+        if self.server_version() >= MIN_SERVER_VER_AUTO_CANCEL_PARENT {
+            write_field(&mut msg, &order.auto_cancel_parent)?;
+        }
+
+        if self.server_version() >= MIN_SERVER_VER_ADVANCED_ORDER_REJECT {
+            write_field(&mut msg, &order.advanced_error_override)?;
+        }
+
+        if self.server_version() >= MIN_SERVER_VER_MANUAL_ORDER_TIME {
+            write_field(&mut msg, &order.manual_order_time)?;
+        }
+
+        if self.server_version() >= MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS {
+            let mut send_mid_offsets = false;
+            if contract.exchange == "IBKRATS" {
+                write_field(&mut msg, &order.min_trade_qty)?;
+            }
+            if order.is_peg_best_order() {
+                write_field(&mut msg, &order.min_compete_size)?;
+                write_field(&mut msg, &order.compete_against_best_offset)?;
+                send_mid_offsets =
+                    order.compete_against_best_offset == COMPETE_AGAINST_BEST_OFFSET_UP_TO_MID;
+            } else if order.is_peg_mid_order() {
+                send_mid_offsets = true;
+            }
+            if send_mid_offsets {
+                write_field(&mut msg, &order.mid_offset_at_whole)?;
+                write_field(&mut msg, &order.mid_offset_at_half)?;
+            }
         }
 
-        self.send_request(msg.as_str())?;
+        if !order.what_if {
+            self.write_journal(JournalEntry::Intent(OrderIntent::new(
+                order_id, contract, order,
+            )))?;
+        }
+        self.send_order_message(OutgoingMessageIds::PlaceOrder, Some(order_id), msg)?;
+        // What-if orders are never working orders
+        if !order.what_if {
+            let mut orders = self.orders.lock().expect(POISONED_MUTEX);
+            orders.track(order_id);
+            orders.set_order_ref(order_id, &order.order_ref);
+            drop(orders);
+            self.record_arrival_quote(order_id, contract);
+        }
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Keeps the quote of the contract of an order that was sent, if arrival
+    /// quotes are kept and the quote cache has a quote of the contract
+    fn record_arrival_quote(&mut self, order_id: i32, contract: &Contract) {
+        let Some(arrival_quotes) = self.arrival_quotes.as_mut() else {
+            return;
+        };
+        if let Some(quote) = self
+            .quotes
+            .lock()
+            .expect(POISONED_MUTEX)
+            .contract_quote(contract.con_id)
+        {
+            arrival_quotes.record(ArrivalQuote::new(
+                order_id,
+                contract.con_id,
+                Utc::now(),
+                quote,
+            ));
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Places an order as what-if and blocks until TWS returns its margin
+    /// and commission impact. The order is not transmitted and the result is
+    /// not passed to the Wrapper. With `margin::what_if_basket` several
+    /// orders can be checked at once.
+    ///
+    /// # Arguments
+    /// * order_id - an unused order id, it is not used by a working order
+    /// * contract - the contract of the order
+    /// * order - the order, its what_if flag is set on a copy
+    /// * timeout - how long to wait for the result
+    pub fn fetch_what_if(
+        &mut self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+        timeout: Duration,
+    ) -> Result<OrderState, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .what_if
+            .add(order_id);
+
+        let mut order = order.clone();
+        order.what_if = true;
+        let result = self
+            .place_order(order_id, contract, &order)
+            .and_then(|_| {
+                receiver
+                    .recv_timeout(timeout)
+                    .map_err(|_| IbkrError::timeout(order_id, "the what-if result"))
+            })
+            .and_then(|event| match event {
+                WhatIfEvent::Result(order_state) => Ok(*order_state),
+                WhatIfEvent::Error { code, message } => Err(IbkrError::Server(ServerError::new(
+                    order_id, code, &message,
+                ))),
+            });
+
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .what_if
+            .remove(order_id);
+        result
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Call this function to cancel an order.
     /// # Arguments
     /// * order_id - The order ID that was specified previously when placing the
     ///   order
-    pub fn cancel_order(&mut self, order_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_order(&mut self, order_id: i32) -> Result<(), IbkrError> {
+        self.cancel_order_with_manual_time(order_id, "")
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels an order and reports the time the cancel was decided on, for
+    /// orders that are entered manually, e.g. by a broker on behalf of a
+    /// client.
+    /// # Arguments
+    /// * order_id - The order ID that was specified previously when placing the
+    ///   order
+    /// * manual_order_cancel_time - the time of the cancel as "yyyymmdd
+    ///   hh:mm:ss", empty for none
+    pub fn cancel_order_with_manual_time(
+        &mut self,
+        order_id: i32,
+        manual_order_cancel_time: &str,
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 2;
+        // An order still queued by the throttle or the session gate never
+        // reached TWS
+        if let Some(order_throttle) = self.order_throttle.as_mut() {
+            if order_throttle.remove(order_id) {
+                return Ok(());
+            }
+        }
+        if let Some(session_gate) = self.session_gate.as_mut() {
+            if session_gate.remove(order_id) {
+                return Ok(());
+            }
+        }
+
+        if self.server_version() < MIN_SERVER_VER_MANUAL_ORDER_TIME
+            && !manual_order_cancel_time.is_empty()
+        {
+            return Err(IbkrError::Connection(ConnectionError::new(
+                order_id,
+                TwsError::UpdateTws,
+                "It does not support manual order cancel time attribute.",
+            )));
+        }
+
+        self.write_journal(JournalEntry::Cancel { order_id })?;
+
+        let version = OutgoingMessageIds::CancelOrder.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::CancelOrder as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&order_id)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &order_id)?;
+        if self.server_version() >= MIN_SERVER_VER_MANUAL_ORDER_TIME {
+            write_field(&mut msg, manual_order_cancel_time)?;
+        }
 
-        self.send_request(msg.as_str())?;
+        self.send_order_message(OutgoingMessageIds::CancelOrder, Some(order_id), msg)?;
         Ok(())
     }
 
@@ -2016,19 +2874,19 @@ where
     ///        open orders. These orders will be associated with the client and
     /// a new        order_id will be generated. This association will
     /// persist over multiple        API and TWS sessions
-    pub fn request_open_orders(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn request_open_orders(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqOpenOrders.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqOpenOrders as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -2044,20 +2902,20 @@ where
     /// * b_auto_bind - If set to TRUE, newly created TWS orders will be
     ///   implicitly associated with the client.If set to FALSE, no association
     ///   will be made.
-    pub fn request_auto_open_orders(&mut self, b_auto_bind: bool) -> Result<(), IBKRApiLibError> {
+    pub fn request_auto_open_orders(&mut self, b_auto_bind: bool) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqAutoOpenOrders.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqAutoOpenOrders as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&b_auto_bind)?); // TRUE = subscribe, FALSE = unsubscribe
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &b_auto_bind)?; // TRUE = subscribe, FALSE = unsubscribe
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
 
         Ok(())
     }
@@ -2068,19 +2926,19 @@ where
     /// open_order() and order_status() functions on the EWrapper.
     /// Note:  No association is made between the returned orders and the
     /// requesting client.
-    pub fn request_all_open_orders(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn request_all_open_orders(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqAllOpenOrders.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqAllOpenOrders as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
 
         Ok(())
     }
@@ -2089,24 +2947,140 @@ where
     /// Use this function to cancel all open orders globally. It
     /// cancels both API and TWS open orders.
     /// If the order was created in TWS, it also gets canceled. If the order
-    /// was initiated in the API client, it also gets canceled.
-    pub fn request_global_cancel(&mut self) -> Result<(), IBKRApiLibError> {
+    /// was initiated in the API client, it also gets canceled. Orders queued
+    /// by the order throttle are dropped.
+    pub fn request_global_cancel(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        if let Some(order_throttle) = self.order_throttle.as_mut() {
+            order_throttle.clear();
+        }
+        if let Some(session_gate) = self.session_gate.as_mut() {
+            session_gate.clear();
+        }
+
+        self.check_supported(
+            OutgoingMessageIds::ReqGlobalCancel,
+            NO_VALID_ID,
+            "It does not support global cancel requests.",
+        )?;
+
+        self.write_journal(JournalEntry::GlobalCancel)?;
+
+        let version = OutgoingMessageIds::ReqGlobalCancel.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqGlobalCancel as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_order_message(OutgoingMessageIds::ReqGlobalCancel, None, msg)?;
 
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// The latest status of an order placed by this client, e.g.
+    /// `Submitted` or `Cancelled`, as reported by `Wrapper::order_status`
+    pub fn order_status(&self, order_id: i32) -> Option<String> {
+        self.orders
+            .lock()
+            .expect(POISONED_MUTEX)
+            .status(order_id)
+            .map(String::from)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ids of the orders of this client that can still fill
+    pub fn working_orders(&self) -> Vec<i32> {
+        self.orders.lock().expect(POISONED_MUTEX).working_orders()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The working order placed with an order ref, if any
+    pub fn working_order_by_ref(&self, order_ref: &str) -> Option<i32> {
+        self.orders
+            .lock()
+            .expect(POISONED_MUTEX)
+            .working_order_by_ref(order_ref)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels all open orders with a global cancel and blocks placing
+    /// orders until `rearm()` is called. Waits until TWS confirms that the
+    /// working orders of this client are cancelled or filled, then
+    /// optionally flattens positions, whether or not all cancellations have
+    /// been confirmed.
+    ///
+    /// # Arguments
+    /// * flatten - the positions to close after the orders are cancelled
+    /// * timeout - how long to wait for the confirmations and for each of the
+    ///   positions
+    pub fn kill_switch(
+        &mut self,
+        flatten: Option<KillSwitchFlatten>,
+        timeout: Duration,
+    ) -> Result<KillSwitchReport, IbkrError> {
+        self.kill_switch_engaged = true;
+        let working = self.working_orders();
+        self.request_global_cancel()?;
+
+        let deadline = Instant::now() + timeout;
+        let unconfirmed = loop {
+            let unconfirmed: Vec<i32> = {
+                let orders = self.orders.lock().expect(POISONED_MUTEX);
+                working
+                    .iter()
+                    .copied()
+                    .filter(|order_id| orders.is_working(*order_id))
+                    .collect()
+            };
+            if unconfirmed.is_empty() || Instant::now() >= deadline {
+                break unconfirmed;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        let mut report = KillSwitchReport {
+            cancelled: working
+                .into_iter()
+                .filter(|order_id| !unconfirmed.contains(order_id))
+                .collect(),
+            unconfirmed,
+            closing_orders: vec![],
+        };
+
+        if let Some(flatten) = flatten {
+            let positions = self.fetch_positions(timeout)?;
+            let closing_orders = closing_orders(
+                &positions,
+                &flatten.filter,
+                flatten.style,
+                &self.quotes.lock().expect(POISONED_MUTEX),
+            )
+            .map_err(|message| IbkrError::rejected(NO_VALID_ID, &message))?;
+            for (order_id, mut closing_order) in (flatten.next_order_id..).zip(closing_orders) {
+                closing_order.order.order_id = order_id;
+                self.send_place_order(order_id, &closing_order.contract, &closing_order.order)?;
+                report.closing_orders.push(closing_order);
+            }
+        }
+        Ok(report)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the kill switch blocks placing orders
+    pub fn is_kill_switch_engaged(&self) -> bool {
+        self.kill_switch_engaged
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Allows placing orders again after `kill_switch()`
+    pub fn rearm(&mut self) {
+        self.kill_switch_engaged = false;
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Call this function to request from TWS the next valid ID that
     /// can be used when placing an order. After calling this function, the
@@ -2117,20 +3091,20 @@ where
     ///
     /// # Arguments
     /// * num_ids - deprecated
-    pub fn request_ids(&mut self, num_ids: i32) -> Result<(), IBKRApiLibError> {
+    pub fn request_ids(&mut self, num_ids: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
         info!("request_ids is connected...");
-        let version = 1;
+        let version = OutgoingMessageIds::ReqIds.version();
 
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
         let message_id = OutgoingMessageIds::ReqIds as i32;
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&num_ids)?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &num_ids)?;
         info!("request_ids... sending request...");
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -2147,12 +3121,12 @@ where
     ///   and Portfoliolio updates. If set to FALSE, the client will stop
     ///   receiving this information.
     /// * acct_code - The account code for which to receive account and
-    ///   portfolio updates.
+    ///   portfolio updates, empty for the default account.
     pub fn request_account_updates(
         &mut self,
         subscribe: bool,
         acct_code: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         info!("subscribe: {}, acct_code: {}", subscribe, acct_code);
         debug!(
             "request_account_updates: subscribe: {}, acct_code: {}",
@@ -2161,20 +3135,109 @@ where
 
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 2;
+        let acct_code = self.resolve_account(NO_VALID_ID, acct_code)?;
+        let msg = Self::account_updates_message(subscribe, &acct_code)?;
+        self.send_message(msg)?;
 
-        let mut msg = "".to_string();
+        Ok(())
+    }
 
-        let message_id = OutgoingMessageIds::ReqAcctData as i32;
+    //----------------------------------------------------------------------------------------------
+    fn account_updates_message(subscribe: bool, acct_code: &str) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::ReqAcctData.version();
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&subscribe)?); // TRUE = subscribe, FALSE = unsubscribe
-        msg.push_str(&make_field(&String::from(acct_code))?); // srv v9 and above, the account code.This will only be used for FA clients
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
 
-        self.send_request(msg.as_str())?;
+        let message_id = OutgoingMessageIds::ReqAcctData as i32;
 
-        Ok(())
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &subscribe)?; // TRUE = subscribe, FALSE = unsubscribe
+        write_field(&mut msg, &String::from(acct_code))?; // srv v9 and above, the account code.This will only be used for FA clients
+        finish_message(&mut msg);
+        Ok(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_account_updates() but delivers the account values,
+    /// portfolio and update times through the returned Subscription instead
+    /// of the Wrapper. Dropping the Subscription unsubscribes.
+    ///
+    /// TWS sends the updates of one account at a time, so subscribing to
+    /// another account ends the updates of the previous one. Use
+    /// `AccountUpdates` to collect the events into snapshots.
+    ///
+    /// # Arguments
+    /// * acct_code - The account code for which to receive account and
+    ///   portfolio updates, empty for the only account of a login.
+    pub fn stream_account_updates(
+        &mut self,
+        acct_code: &str,
+    ) -> Result<Subscription<AccountUpdateEvent>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .account_updates
+            .add(NO_VALID_ID);
+
+        if let Err(err) = self.request_account_updates(true, acct_code) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .account_updates
+                .remove(NO_VALID_ID);
+            return Err(err);
+        }
+
+        let cancel_msg = Self::account_updates_message(false, acct_code)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+        let acct_code = acct_code.to_string();
+
+        Ok(Subscription::new(NO_VALID_ID, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .account_updates
+                .remove(NO_VALID_ID);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel account updates of {}: {}", acct_code, err);
+            }
+        }))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes to the updates of an account, blocks until its values and
+    /// positions have all been received and then unsubscribes. The updates
+    /// are not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * acct_code - The account code, empty for the only account of a login.
+    /// * timeout - how long to wait for each of the updates
+    pub fn fetch_account_snapshot(
+        &mut self,
+        acct_code: &str,
+        timeout: Duration,
+    ) -> Result<AccountSnapshot, IbkrError> {
+        let subscription = self.stream_account_updates(acct_code)?;
+
+        let mut updates = AccountUpdates::new();
+        loop {
+            match subscription.recv_timeout(timeout) {
+                Ok(event) => {
+                    if let Some(snapshot) = updates.apply(event) {
+                        if acct_code.is_empty() || snapshot.account == acct_code {
+                            return Ok(snapshot.clone());
+                        }
+                    }
+                }
+                Err(_) => {
+                    return Err(IbkrError::timeout(NO_VALID_ID, "the account updates"));
+                }
+            }
+        }
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2192,27 +3255,34 @@ where
     ///   accounts, or set to a specific Advisor Account Group name that has
     ///   already been created in TWS Global Configuration.
     /// * tags- A comma-separated list of account tags.  See the
-    ///   AccountSummaryTags enum for valid values
+    ///   AccountSummaryTags enum for valid values and AccountSummaryTagSet to
+    ///   build the list
     pub fn request_account_summary(
         &mut self,
         request_id: i32,
         group_name: &str,
         tags: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 2;
+        self.check_supported(
+            OutgoingMessageIds::ReqAccountSummary,
+            request_id,
+            "It does not support account summary requests.",
+        )?;
+
+        let version = OutgoingMessageIds::ReqAccountSummary.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqAccountSummary as i32;
-        let mut msg = "".to_string();
+        let mut msg = self.message_buffer();
 
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(group_name))?);
-        msg.push_str(&make_field(&String::from(tags))?);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &String::from(group_name))?;
+        write_field(&mut msg, &String::from(tags))?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -2221,82 +3291,171 @@ where
     ///
     /// # Arguments
     /// * request_id - The ID of the data request being canceled.
-    pub fn cancel_account_summary(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_account_summary(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
-        let version = 1;
-
-        let message_id: i32 = OutgoingMessageIds::CancelAccountSummary as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
 
-        self.send_request(msg.as_str())?;
+        let msg = Self::cancel_account_summary_message(request_id)?;
+        self.send_message(msg)?;
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    /// Requests real-time position data for all accounts.
-    pub fn request_positions(&mut self) -> Result<(), IBKRApiLibError> {
-        self.check_connected(NO_VALID_ID)?;
+    fn cancel_account_summary_message(request_id: i32) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelAccountSummary.version();
 
-        if self.server_version() < MIN_SERVER_VER_POSITIONS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support positions request."
-                ),
-            ));
+        let message_id: i32 = OutgoingMessageIds::CancelAccountSummary as i32;
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        finish_message(&mut msg);
+        Ok(msg)
+    }
 
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_account_summary() but delivers the values through the
+    /// returned Subscription instead of Wrapper::account_summary. Dropping the
+    /// Subscription cancels the request.
+    ///
+    /// # Arguments
+    /// * request_id - The ID of the data request.
+    /// * group_name - All, or the name of an Advisor Account Group
+    /// * tags - the tags to request, e.g.
+    ///   `AccountSummaryTagSet::new().tag(AccountSummaryTags::NetLiquidation)`
+    pub fn stream_account_summary(
+        &mut self,
+        request_id: i32,
+        group_name: &str,
+        tags: &AccountSummaryTagSet,
+    ) -> Result<Subscription<AccountSummaryEvent>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .account_summaries
+            .add(request_id);
+
+        if let Err(err) = self.request_account_summary(request_id, group_name, &tags.to_string()) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .account_summaries
+                .remove(request_id);
             return Err(err);
         }
 
-        let version = 1;
+        let cancel_msg = Self::cancel_account_summary_message(request_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .account_summaries
+                .remove(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel account summary {}: {}", request_id, err);
+            }
+        }))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests an account summary, blocks until all values have been
+    /// received and then cancels the request. The values are not passed to
+    /// the Wrapper.
+    ///
+    /// # Arguments
+    /// * request_id - The ID of the data request.
+    /// * group_name - All, or the name of an Advisor Account Group
+    /// * tags - the tags to request
+    /// * timeout - how long to wait for each of the values
+    pub fn fetch_account_summary(
+        &mut self,
+        request_id: i32,
+        group_name: &str,
+        tags: &AccountSummaryTagSet,
+        timeout: Duration,
+    ) -> Result<AccountSummary, IbkrError> {
+        let subscription = self.stream_account_summary(request_id, group_name, tags)?;
+
+        let mut values = vec![];
+        loop {
+            match subscription.recv_timeout(timeout) {
+                Ok(AccountSummaryEvent::Value(value)) => values.push(value),
+                Ok(AccountSummaryEvent::End) => break,
+                Ok(AccountSummaryEvent::Error { code, message }) => {
+                    return Err(IbkrError::Server(ServerError::new(
+                        request_id, code, &message,
+                    )));
+                }
+                Err(_) => {
+                    return Err(IbkrError::timeout(request_id, "the account summary"));
+                }
+            }
+        }
+        Ok(AccountSummary::new(values))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests real-time position data for all accounts.
+    pub fn request_positions(&mut self) -> Result<(), IbkrError> {
+        self.check_connected(NO_VALID_ID)?;
+
+        self.check_supported(
+            OutgoingMessageIds::ReqPositions,
+            NO_VALID_ID,
+            "It does not support positions request.",
+        )?;
+
+        let version = OutgoingMessageIds::ReqPositions.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqPositions as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     /// Cancels real-time position updates.
-    pub fn cancel_positions(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_positions(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
         if self.server_version() < MIN_SERVER_VER_POSITIONS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support positions request."
-                ),
+                TwsError::UpdateTws,
+                "It does not support positions request.",
             ));
 
             return Err(err);
         }
 
-        let version = 1;
-
-        let message_id: i32 = OutgoingMessageIds::CancelPositions as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        self.send_request(msg.as_str())?;
+        let msg = Self::cancel_positions_message()?;
+        self.send_message(msg)?;
 
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    fn cancel_positions_message() -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelPositions.version();
+
+        let message_id: i32 = OutgoingMessageIds::CancelPositions as i32;
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        finish_message(&mut msg);
+        Ok(msg)
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Requests position subscription for account and/or model Initially all
     /// positions are returned, and then updates are returned for any
@@ -2312,36 +3471,30 @@ where
         request_id: i32,
         account: &str,
         model_code: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_POSITIONS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support positions multi request."
-                ),
-            ));
+        self.check_supported(
+            OutgoingMessageIds::ReqPositionsMulti,
+            request_id,
+            "It does not support positions multi request.",
+        )?;
 
-            return Err(err);
-        }
+        self.check_account(request_id, account)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqPositionsMulti.version();
         let mut_request_id = request_id;
         let mut_account = account;
         let mut_model_code = model_code;
         let message_id: i32 = OutgoingMessageIds::ReqPositionsMulti as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&mut_request_id)?);
-        msg.push_str(&make_field(&String::from(mut_account))?);
-        msg.push_str(&make_field(&String::from(mut_model_code))?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &mut_request_id)?;
+        write_field(&mut msg, &String::from(mut_account))?;
+        write_field(&mut msg, &String::from(mut_model_code))?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
 
         Ok(())
     }
@@ -2351,33 +3504,159 @@ where
     ///
     /// # Arguments
     /// * request_id - The id of the original request
-    pub fn cancel_positions_multi(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_positions_multi(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
         if self.server_version() < MIN_SERVER_VER_POSITIONS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support positions multi request."
-                ),
+                TwsError::UpdateTws,
+                "It does not support positions multi request.",
             ));
 
             return Err(err);
         }
 
-        let version = 1;
-        let mut_request_id = request_id;
+        let msg = Self::cancel_positions_multi_message(request_id)?;
+        self.send_message(msg)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn cancel_positions_multi_message(request_id: i32) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelPositionsMulti.version();
         let message_id: i32 = OutgoingMessageIds::CancelPositionsMulti as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&mut_request_id)?);
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        finish_message(&mut msg);
+        Ok(msg)
+    }
 
-        self.send_request(msg.as_str())?;
-        Ok(())
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_positions() but delivers the positions of all accounts
+    /// through the returned Subscription instead of Wrapper::position.
+    /// Dropping the Subscription cancels the request.
+    ///
+    /// Fails if the positions are already streamed, as TWS only knows one
+    /// such subscription and cancelling one would end the others.
+    pub fn stream_positions(&mut self) -> Result<Subscription<PositionEvent>, IbkrError> {
+        let receiver = {
+            let mut streams = self.streams.lock().expect(POISONED_MUTEX);
+            if streams.positions.contains(NO_VALID_ID) {
+                return Err(IbkrError::rejected(
+                    NO_VALID_ID,
+                    "The positions are already streamed.",
+                ));
+            }
+            streams.positions.add(NO_VALID_ID)
+        };
+
+        if let Err(err) = self.request_positions() {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .positions
+                .remove(NO_VALID_ID);
+            return Err(err);
+        }
+
+        let cancel_msg = Self::cancel_positions_message()?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+
+        Ok(Subscription::new(NO_VALID_ID, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .positions
+                .remove(NO_VALID_ID);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel positions: {}", err);
+            }
+        }))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the positions of all accounts, blocks until all of them have
+    /// been received and then cancels the request. Closed positions are left
+    /// out. The positions are not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * timeout - how long to wait for each of the positions
+    pub fn fetch_positions(&mut self, timeout: Duration) -> Result<Vec<Position>, IbkrError> {
+        let subscription = self.stream_positions()?;
+        collect_positions(&subscription, timeout)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_positions_multi() but delivers the positions through
+    /// the returned Subscription instead of Wrapper::position_multi. Dropping
+    /// the Subscription cancels the request.
+    ///
+    /// # Arguments
+    /// * request_id - Request's identifier
+    /// * account - the account of the positions, empty for all accounts
+    /// * model_code - the model of the positions, empty for all models
+    pub fn stream_positions_multi(
+        &mut self,
+        request_id: i32,
+        account: &str,
+        model_code: &str,
+    ) -> Result<Subscription<PositionEvent>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .positions_multi
+            .add(request_id);
+
+        if let Err(err) = self.request_positions_multi(request_id, account, model_code) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .positions_multi
+                .remove(request_id);
+            return Err(err);
+        }
+
+        let cancel_msg = Self::cancel_positions_multi_message(request_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .positions_multi
+                .remove(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel positions multi {}: {}", request_id, err);
+            }
+        }))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the positions of an account and/or model, blocks until all
+    /// of them have been received and then cancels the request. Closed
+    /// positions are left out. The positions are not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * request_id - Request's identifier
+    /// * account - the account of the positions, empty for all accounts
+    /// * model_code - the model of the positions, empty for all models
+    /// * timeout - how long to wait for each of the positions
+    pub fn fetch_positions_multi(
+        &mut self,
+        request_id: i32,
+        account: &str,
+        model_code: &str,
+        timeout: Duration,
+    ) -> Result<Vec<Position>, IbkrError> {
+        let subscription = self.stream_positions_multi(request_id, account, model_code)?;
+        collect_positions(&subscription, timeout)
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2395,39 +3674,33 @@ where
         account: &str,
         model_code: &str,
         ledger_and_nlv: bool,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_MODELS_SUPPORT {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support account updates multi request."
-                ),
-            ));
+        self.check_supported(
+            OutgoingMessageIds::ReqAccountUpdatesMulti,
+            request_id,
+            "It does not support account updates multi request.",
+        )?;
 
-            return Err(err);
-        }
+        self.check_account(request_id, account)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqAccountUpdatesMulti.version();
         let mut_request_id = request_id;
         let mut_account = account;
         let mut_model_code = model_code;
         let mut_ledger_and_nlv = ledger_and_nlv;
 
         let message_id: i32 = OutgoingMessageIds::ReqAccountUpdatesMulti as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&mut_request_id)?);
-        msg.push_str(&make_field(&String::from(mut_account))?);
-        msg.push_str(&make_field(&String::from(mut_model_code))?);
-        msg.push_str(&make_field(&mut_ledger_and_nlv)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &mut_request_id)?;
+        write_field(&mut msg, &String::from(mut_account))?;
+        write_field(&mut msg, &String::from(mut_model_code))?;
+        write_field(&mut msg, &mut_ledger_and_nlv)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
 
         Ok(())
     }
@@ -2437,32 +3710,170 @@ where
     ///
     /// # Arguments
     /// * request_id - The id of the original request
-    pub fn cancel_account_updates_multi(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_account_updates_multi(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
         if self.server_version() < MIN_SERVER_VER_MODELS_SUPPORT {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support account updates multi request."
-                ),
+                TwsError::UpdateTws,
+                "It does not support account updates multi request.",
             ));
 
             return Err(err);
         }
 
-        let version = 1;
-        let mut_request_id = request_id;
+        let msg = Self::cancel_account_updates_multi_message(request_id)?;
+        self.send_message(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn cancel_account_updates_multi_message(request_id: i32) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelAccountUpdatesMulti.version();
         let message_id: i32 = OutgoingMessageIds::CancelAccountUpdatesMulti as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&mut_request_id)?);
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        finish_message(&mut msg);
+        Ok(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_account_updates_multi() but delivers the values
+    /// through the returned Subscription instead of
+    /// Wrapper::account_update_multi. Dropping the Subscription cancels the
+    /// request.
+    ///
+    /// # Arguments
+    /// * request_id - identifier to tag the request
+    /// * account - the account of the values
+    /// * model_code - the model of the values, empty for the whole account
+    /// * ledger_and_nlv - only request the cash balances and net liquidation
+    pub fn stream_account_updates_multi(
+        &mut self,
+        request_id: i32,
+        account: &str,
+        model_code: &str,
+        ledger_and_nlv: bool,
+    ) -> Result<Subscription<AccountUpdateMultiEvent>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .account_updates_multi
+            .add(request_id);
+
+        if let Err(err) =
+            self.request_account_updates_multi(request_id, account, model_code, ledger_and_nlv)
+        {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .account_updates_multi
+                .remove(request_id);
+            return Err(err);
+        }
+
+        let cancel_msg = Self::cancel_account_updates_multi_message(request_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .account_updates_multi
+                .remove(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!(
+                    "Failed to cancel account updates multi {}: {}",
+                    request_id, err
+                );
+            }
+        }))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the values of an account and/or model, blocks until all of
+    /// them have been received and then cancels the request. The values are
+    /// not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * request_id - identifier to tag the request
+    /// * account - the account of the values
+    /// * model_code - the model of the values, empty for the whole account
+    /// * timeout - how long to wait for each of the values
+    pub fn fetch_account_values_multi(
+        &mut self,
+        request_id: i32,
+        account: &str,
+        model_code: &str,
+        timeout: Duration,
+    ) -> Result<Vec<ModelAccountValue>, IbkrError> {
+        let subscription =
+            self.stream_account_updates_multi(request_id, account, model_code, false)?;
+
+        let mut values = vec![];
+        loop {
+            match subscription.recv_timeout(timeout) {
+                Ok(AccountUpdateMultiEvent::Value(value)) => values.push(value),
+                Ok(AccountUpdateMultiEvent::End) => return Ok(values),
+                Ok(AccountUpdateMultiEvent::Error { code, message }) => {
+                    return Err(IbkrError::Server(ServerError::new(
+                        request_id, code, &message,
+                    )));
+                }
+                Err(_) => {
+                    return Err(IbkrError::timeout(request_id, "the account values"));
+                }
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Places an order in a model, see Model::apply()
+    pub fn place_model_order(
+        &mut self,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+        model: &Model,
+    ) -> Result<(), IbkrError> {
+        self.place_order(order_id, contract, &model.order(order))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The open positions of a model, see fetch_positions_multi()
+    pub fn fetch_model_positions(
+        &mut self,
+        request_id: i32,
+        model: &Model,
+        timeout: Duration,
+    ) -> Result<Vec<Position>, IbkrError> {
+        self.fetch_positions_multi(request_id, &model.account, &model.code, timeout)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The account values of a model, see fetch_account_values_multi()
+    pub fn fetch_model_values(
+        &mut self,
+        request_id: i32,
+        model: &Model,
+        timeout: Duration,
+    ) -> Result<Vec<ModelAccountValue>, IbkrError> {
+        self.fetch_account_values_multi(request_id, &model.account, &model.code, timeout)
+    }
 
-        self.send_request(msg.as_str())
+    //----------------------------------------------------------------------------------------------
+    /// Streams the profit and loss of a model, see stream_profit_and_loss()
+    pub fn stream_model_profit_and_loss(
+        &mut self,
+        request_id: i32,
+        model: &Model,
+    ) -> Result<Subscription<PnlEvent>, IbkrError> {
+        self.stream_profit_and_loss(request_id, &model.account, &model.code)
     }
 
     //#########################################################################
@@ -2472,38 +3883,32 @@ where
     ///
     /// # Arguments
     /// * request_id - identifier to tag the request
-    /// * account - account values can be requested for a particular account
+    /// * account - account values can be requested for a particular account,
+    ///   empty for the default account
     /// * model_code - values can also be requested for a model
     pub fn request_profit_and_loss(
         &mut self,
         request_id: i32,
         account: &str,
         model_code: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_PNL {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support PnL request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqPnl,
+            request_id,
+            "It does not support PnL request.",
+        )?;
 
+        let account = self.resolve_account(request_id, account)?;
         let message_id: i32 = OutgoingMessageIds::ReqPnl as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(account))?);
-        msg.push_str(&make_field(&String::from(model_code))?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &account)?;
+        write_field(&mut msg, &String::from(model_code))?;
 
-        self.send_request(msg.as_str())
+        self.send_message(msg)
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2511,29 +3916,31 @@ where
     ///
     /// # Arguments
     /// * request_id - The id of the original request
-    pub fn cancel_profit_and_loss(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_profit_and_loss(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_PNL {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support PnL request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::CancelPnl,
+            request_id,
+            "It does not support PnL request.",
+        )?;
 
-        let message_id: i32 = OutgoingMessageIds::CancelPnl as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&request_id)?);
+        let msg = Self::cancel_profit_and_loss_message(OutgoingMessageIds::CancelPnl, request_id)?;
+        self.send_message(msg)
+    }
 
-        self.send_request(msg.as_str())
+    //----------------------------------------------------------------------------------------------
+    fn cancel_profit_and_loss_message(
+        message_id: OutgoingMessageIds,
+        request_id: i32,
+    ) -> Result<Vec<u8>, IbkrError> {
+        let message_id: i32 = message_id as i32;
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &request_id)?;
+        finish_message(&mut msg);
+        Ok(msg)
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2542,7 +3949,8 @@ where
     ///
     /// # Arguments
     /// * request_id - identifier to tag the request
-    /// * account - account values can be requested for a particular account
+    /// * account - account values can be requested for a particular account,
+    ///   empty for the default account
     /// * model_code - values can also be requested for a model
     /// * con_id - contract id of the specific contact of interest
     pub fn request_profit_and_loss_single(
@@ -2551,32 +3959,25 @@ where
         account: &str,
         model_code: &str,
         con_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        if self.server_version() < MIN_SERVER_VER_PNL {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support PnL request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqPnlSingle,
+            request_id,
+            "It does not support PnL request.",
+        )?;
 
+        let account = self.resolve_account(request_id, account)?;
         let message_id: i32 = OutgoingMessageIds::ReqPnlSingle as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(account))?);
-        msg.push_str(&make_field(&String::from(model_code))?);
-        msg.push_str(&make_field(&con_id)?);
-
-        self.send_request(msg.as_str())
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &account)?;
+        write_field(&mut msg, &String::from(model_code))?;
+        write_field(&mut msg, &con_id)?;
+
+        self.send_message(msg)
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2584,32 +3985,117 @@ where
     ///
     /// # Arguments
     /// * request_id - The id of the original request
-    pub fn cancel_profit_and_loss_single(
+    pub fn cancel_profit_and_loss_single(&mut self, request_id: i32) -> Result<(), IbkrError> {
+        self.check_connected(request_id)?;
+
+        self.check_supported(
+            OutgoingMessageIds::CancelPnlSingle,
+            request_id,
+            "It does not support PnL request.",
+        )?;
+
+        let msg =
+            Self::cancel_profit_and_loss_message(OutgoingMessageIds::CancelPnlSingle, request_id)?;
+        self.send_message(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_profit_and_loss() but delivers the profit and loss
+    /// through the returned Subscription instead of
+    /// Wrapper::profit_and_loss. Dropping the Subscription cancels the
+    /// request.
+    ///
+    /// # Arguments
+    /// * request_id - identifier to tag the request
+    /// * account - the account of the profit and loss
+    /// * model_code - the model of the profit and loss, empty for the whole
+    ///   account
+    pub fn stream_profit_and_loss(
         &mut self,
         request_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
-        self.check_connected(request_id)?;
+        account: &str,
+        model_code: &str,
+    ) -> Result<Subscription<PnlEvent>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .pnl
+            .add(request_id);
+
+        if let Err(err) = self.request_profit_and_loss(request_id, account, model_code) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .pnl
+                .remove(request_id);
+            return Err(err);
+        }
 
-        if self.server_version() < MIN_SERVER_VER_PNL {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support PnL request."
-                ),
-            ));
+        let cancel_msg =
+            Self::cancel_profit_and_loss_message(OutgoingMessageIds::CancelPnl, request_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
 
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams.lock().expect(POISONED_MUTEX).pnl.remove(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel PnL {}: {}", request_id, err);
+            }
+        }))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_profit_and_loss_single() but delivers the profit and
+    /// loss of the position through the returned Subscription instead of
+    /// Wrapper::profit_and_loss_single. Dropping the Subscription cancels the
+    /// request.
+    ///
+    /// # Arguments
+    /// * request_id - identifier to tag the request
+    /// * account - the account of the position
+    /// * model_code - the model of the position, empty for the whole account
+    /// * con_id - contract id of the position
+    pub fn stream_profit_and_loss_single(
+        &mut self,
+        request_id: i32,
+        account: &str,
+        model_code: &str,
+        con_id: i32,
+    ) -> Result<Subscription<PnlSingleEvent>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .pnl_single
+            .add(request_id);
+
+        if let Err(err) =
+            self.request_profit_and_loss_single(request_id, account, model_code, con_id)
+        {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .pnl_single
+                .remove(request_id);
             return Err(err);
         }
 
-        let message_id: i32 = OutgoingMessageIds::CancelPnlSingle as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&request_id)?);
+        let cancel_msg =
+            Self::cancel_profit_and_loss_message(OutgoingMessageIds::CancelPnlSingle, request_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
 
-        self.send_request(msg.as_str())
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .pnl_single
+                .remove(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel PnL single {}: {}", request_id, err);
+            }
+        }))
     }
 
     //#########################################################################
@@ -2626,33 +4112,34 @@ where
     /// * request_id - The ID of the data request. Ensures that responses are
     ///   matched to requests if several requests are in process.
     /// * exec_filter - This object contains attributes that describe the filter
-    ///   criteria used to determine which execution reports are returned.
+    ///   criteria used to determine which execution reports are returned,
+    ///   e.g. `ExecutionFilter::builder().today().build()`.
     ///
     /// NOTE: Time format must be 'yyyymmdd-hh:mm:ss' Eg: '20030702-14:55'
     pub fn request_executions(
         &mut self,
         request_id: i32,
         exec_filter: &ExecutionFilter,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
-        let version = 3;
+        let version = OutgoingMessageIds::ReqExecutions.version();
         let message_id: i32 = OutgoingMessageIds::ReqExecutions as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
         if self.server_version() >= MIN_SERVER_VER_EXECUTION_DATA_CHAIN {
-            msg.push_str(&make_field(&request_id)?);
+            write_field(&mut msg, &request_id)?;
         }
-        msg.push_str(&make_field(&exec_filter.client_id)?);
-        msg.push_str(&make_field(&exec_filter.acct_code)?);
-        msg.push_str(&make_field(&exec_filter.time)?);
-        msg.push_str(&make_field(&exec_filter.symbol)?);
-        msg.push_str(&make_field(&exec_filter.sec_type)?);
-        msg.push_str(&make_field(&exec_filter.exchange)?);
-        msg.push_str(&make_field(&exec_filter.side)?);
+        write_field(&mut msg, &exec_filter.client_id)?;
+        write_field(&mut msg, &exec_filter.acct_code)?;
+        write_field(&mut msg, &exec_filter.time)?;
+        write_field(&mut msg, &exec_filter.symbol)?;
+        write_field(&mut msg, &exec_filter.sec_type.to_string())?;
+        write_field(&mut msg, &exec_filter.exchange)?;
+        write_field(&mut msg, &exec_filter.side)?;
 
-        self.send_request(msg.as_str())
+        self.send_message(msg)
     }
 
     //#########################################################################
@@ -2671,20 +4158,16 @@ where
         &mut self,
         request_id: i32,
         contract: &Contract,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
         if self.server_version() < MIN_SERVER_VER_SEC_ID_TYPE
             && (!contract.sec_id_type.is_empty() || !contract.sec_id.is_empty())
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support sec_id_type and secId parameters."
-                ),
+                TwsError::UpdateTws,
+                "It does not support sec_id_type and secId parameters.",
             ));
 
             return Err(err);
@@ -2693,113 +4176,205 @@ where
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && !contract.trading_class.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support trading_class parameter in request_contract_details."
-                ),
+                TwsError::UpdateTws,
+                "It does not support trading_class parameter in request_contract_details.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_LINKING && !contract.primary_exchange.is_empty() {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support primary_exchange parameter in request_contract_details."
-                ),
+                TwsError::UpdateTws,
+                "It does not support primary_exchange parameter in request_contract_details.",
             ));
 
             return Err(err);
         }
 
-        let version = 8;
+        let version = OutgoingMessageIds::ReqContractData.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqContractData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
 
         if self.server_version() >= MIN_SERVER_VER_CONTRACT_DATA_CHAIN {
-            msg.push_str(&make_field(&request_id)?);
+            write_field(&mut msg, &request_id)?;
         }
 
         // send contract fields
-        msg.push_str(&make_field(&contract.con_id)?); // srv v37 and above
-        msg.push_str(&make_field(&contract.symbol)?);
+        write_field(&mut msg, &contract.con_id)?; // srv v37 and above
+        write_field(&mut msg, &contract.symbol)?;
 
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?); // srv v15 and above
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?; // srv v15 and above
 
         if self.server_version() >= MIN_SERVER_VER_PRIMARYEXCH {
-            msg.push_str(&make_field(&contract.exchange)?);
-            msg.push_str(&make_field(&contract.primary_exchange)?);
+            write_field(&mut msg, &contract.exchange)?;
+            write_field(&mut msg, &contract.primary_exchange)?;
         } else if self.server_version() >= MIN_SERVER_VER_LINKING {
             if !contract.primary_exchange.is_empty()
                 && (contract.exchange == "BEST" || contract.exchange == "SMART")
             {
-                msg.push_str(&make_field(&format!(
-                    "{}:{}",
-                    &contract.exchange, &contract.primary_exchange
-                ))?);
+                write_field(
+                    &mut msg,
+                    &format!("{}:{}", &contract.exchange, &contract.primary_exchange),
+                )?;
             }
         } else {
-            msg.push_str(&make_field(&contract.exchange)?);
+            write_field(&mut msg, &contract.exchange)?;
         }
 
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
 
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
-            msg.push_str(&make_field(&contract.include_expired)?); // srv v31 and above
+            write_field(&mut msg, &contract.trading_class)?;
+            write_field(&mut msg, &contract.include_expired)?; // srv v31 and above
         }
 
         if self.server_version() >= MIN_SERVER_VER_SEC_ID_TYPE {
-            msg.push_str(&make_field(&contract.sec_id_type)?);
-            msg.push_str(&make_field(&contract.sec_id)?);
+            write_field(&mut msg, &contract.sec_id_type)?;
+            write_field(&mut msg, &contract.sec_id)?;
         }
 
-        self.send_request(msg.as_str())
+        self.send_message(msg)
     }
 
-    //#########################################################################
-    //################## Market Depth
-    //#########################################################################
-    /// Requests venues for which market data is returned to
-    /// update_market_depth_l2 (those with market makers)
-    pub fn request_market_depth_exchanges(&mut self) -> Result<(), IBKRApiLibError> {
-        self.check_connected(NO_VALID_ID)?;
-
-        if self.server_version() < MIN_SERVER_VER_REQ_MKT_DEPTH_EXCHANGES {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support market depth exchanges request."
-                ),
-            ));
+    //----------------------------------------------------------------------------------------------
+    /// Requests contract details and blocks until all matching contracts have
+    /// been received. The details are not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * request_id - The ID of the data request.
+    /// * contract - The summary description of the contract being looked up.
+    /// * timeout - how long to wait for each of the contract details
+    pub fn fetch_contract_details(
+        &mut self,
+        request_id: i32,
+        contract: &Contract,
+        timeout: Duration,
+    ) -> Result<Vec<ContractDetails>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .contract_details
+            .add(request_id);
+
+        let mut result = self
+            .request_contract_details(request_id, contract)
+            .map(|_| vec![]);
+        while let Ok(details) = result.as_mut() {
+            match receiver.recv_timeout(timeout) {
+                Ok(ContractDetailsEvent::Details(contract_details)) => {
+                    details.push(*contract_details)
+                }
+                Ok(ContractDetailsEvent::End) => break,
+                Ok(ContractDetailsEvent::Error { code, message }) => {
+                    result = Err(IbkrError::Server(ServerError::new(
+                        request_id, code, &message,
+                    )));
+                }
+                Err(_) => {
+                    result = Err(IbkrError::timeout(request_id, "contract details"));
+                }
+            }
+        }
 
-            return Err(err);
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .contract_details
+            .remove(request_id);
+        result
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Looks up the one contract matching a partial description, e.g.
+    /// `Contract::stock("AAPL", "SMART", "USD")`, and returns it with all
+    /// fields set, including `con_id`.
+    ///
+    /// # Arguments
+    /// * request_id - The ID of the data request.
+    /// * partial - The summary description of the contract.
+    /// * timeout - how long to wait for each of the contract details
+    ///
+    /// Fails with `ResolveContractError::NotFound` if no contract matches,
+    /// including when TWS answers with error 200, and with
+    /// `ResolveContractError::Ambiguous`, listing the candidates, if more
+    /// than one does.
+    pub fn resolve_contract(
+        &mut self,
+        request_id: i32,
+        partial: &Contract,
+        timeout: Duration,
+    ) -> Result<Contract, ResolveContractError> {
+        match self.fetch_contract_details(request_id, partial, timeout) {
+            Ok(details) => unique_contract(partial, details),
+            Err(IbkrError::Server(err)) if err.code == NO_SECURITY_DEFINITION => {
+                Err(ResolveContractError::NotFound(Box::new(partial.clone())))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Looks up the one contract with an ISIN, CUSIP, FIGI, SEDOL or RIC,
+    /// see resolve_contract(). Identifiers with a wrong check digit fail
+    /// with `ResolveContractError::InvalidSecId` without sending a request.
+    ///
+    /// # Arguments
+    /// * request_id - The ID of the data request.
+    /// * query - A contract with `sec_id_type` and `sec_id` set, e.g.
+    ///   `Contract::isin("US0378331005")`, and optionally more fields to
+    ///   choose between listings.
+    /// * timeout - how long to wait for each of the contract details
+    pub fn resolve_sec_id(
+        &mut self,
+        request_id: i32,
+        query: &Contract,
+        timeout: Duration,
+    ) -> Result<Contract, ResolveContractError> {
+        let valid = query
+            .sec_id_type
+            .parse::<SecIdType>()
+            .is_ok_and(|sec_id_type| sec_id_type.is_valid(&query.sec_id));
+        if !valid {
+            return Err(ResolveContractError::InvalidSecId(format!(
+                "{} {}",
+                query.sec_id_type, query.sec_id
+            )));
         }
+        self.resolve_contract(request_id, query, timeout)
+    }
+
+    //#########################################################################
+    //################## Market Depth
+    //#########################################################################
+    /// Requests venues for which market data is returned to
+    /// update_market_depth_l2 (those with market makers)
+    pub fn request_market_depth_exchanges(&mut self) -> Result<(), IbkrError> {
+        self.check_connected(NO_VALID_ID)?;
+
+        self.check_supported(
+            OutgoingMessageIds::ReqMktDepthExchanges,
+            NO_VALID_ID,
+            "It does not support market depth exchanges request.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqMktDepthExchanges as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        self.send_request(msg.as_str())
+        self.send_message(msg)
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2832,34 +4407,26 @@ where
         num_rows: i32,
         is_smart_depth: bool,
         market_depth_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && (!contract.trading_class.is_empty() || contract.con_id > 0)
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support con_id and trading_class parameters in request_market_depth."
-                ),
+                TwsError::UpdateTws,
+                "It does not support con_id and trading_class parameters in request_market_depth.",
             ));
 
             return Err(err);
         }
 
         if self.server_version() < MIN_SERVER_VER_SMART_DEPTH && is_smart_depth {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support SMART depth request."
-                ),
+                TwsError::UpdateTws,
+                "It does not support SMART depth request.",
             ));
 
             return Err(err);
@@ -2868,75 +4435,67 @@ where
         if self.server_version() < MIN_SERVER_VER_MKT_DEPTH_PRIM_EXCHANGE
             && !contract.primary_exchange.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support primary_exchange parameter in request_market_depth."
-                ),
+                TwsError::UpdateTws,
+                "It does not support primary_exchange parameter in request_market_depth.",
             ));
 
             return Err(err);
         }
 
-        let version = 5;
+        let version = OutgoingMessageIds::ReqMktDepth.version();
 
         // send req market depth msg
 
         let message_id: i32 = OutgoingMessageIds::ReqMktDepth as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
         // send contract fields
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.con_id)?);
+            write_field(&mut msg, &contract.con_id)?;
         }
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?); // srv v15 and above
-        msg.push_str(&make_field(&contract.exchange)?);
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?; // srv v15 and above
+        write_field(&mut msg, &contract.exchange)?;
 
         if self.server_version() >= MIN_SERVER_VER_MKT_DEPTH_PRIM_EXCHANGE {
-            msg.push_str(&make_field(&contract.primary_exchange)?);
+            write_field(&mut msg, &contract.primary_exchange)?;
         }
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
 
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
-        msg.push_str(&make_field(&num_rows)?); // srv v19 and above
+        write_field(&mut msg, &num_rows)?; // srv v19 and above
 
         if self.server_version() >= MIN_SERVER_VER_SMART_DEPTH {
-            msg.push_str(&make_field(&is_smart_depth)?);
+            write_field(&mut msg, &is_smart_depth)?;
         }
         // send market_depth_options parameter
         if self.server_version() >= MIN_SERVER_VER_LINKING {
             // current doc says this part if for "internal use only" -> won't support it
             if !market_depth_options.is_empty() {
-                let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+                let err = IbkrError::Connection(ConnectionError::new(
                     request_id,
-                    TwsError::Unsupported.code().to_string(),
-                    format!(
-                        "{}{}",
-                        TwsError::Unsupported.message(),
-                        " market_depth_options."
-                    ),
+                    TwsError::Unsupported,
+                    "market_depth_options.",
                 ));
 
                 return Err(err);
             }
             let market_data_options_str = "";
-            msg.push_str(&make_field(&market_data_options_str)?);
+            write_field(&mut msg, &market_data_options_str)?;
         }
-        self.send_request(msg.as_str())
+        self.send_message(msg)
     }
 
     //----------------------------------------------------------------------------------------------
@@ -2953,75 +4512,152 @@ where
         &mut self,
         request_id: i32,
         is_smart_depth: bool,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
         if self.server_version() < MIN_SERVER_VER_SMART_DEPTH && is_smart_depth {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support SMART depth cancel."
-                ),
+                TwsError::UpdateTws,
+                "It does not support SMART depth cancel.",
             ));
 
             return Err(err);
         }
 
-        let version = 1;
+        let msg =
+            Self::cancel_market_depth_message(request_id, is_smart_depth, self.server_version())?;
+
+        self.send_message(msg)?;
+
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .remove_book_metrics(request_id);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn cancel_market_depth_message(
+        request_id: i32,
+        is_smart_depth: bool,
+        server_version: i32,
+    ) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelMktDepth.version();
 
         let message_id: i32 = OutgoingMessageIds::CancelMktDepth as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
-        if self.server_version() >= MIN_SERVER_VER_SMART_DEPTH {
-            msg.push_str(&make_field(&is_smart_depth)?);
+        if server_version >= MIN_SERVER_VER_SMART_DEPTH {
+            write_field(&mut msg, &is_smart_depth)?;
+        }
+        finish_message(&mut msg);
+        Ok(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_market_depth() but keeps the order book and delivers
+    /// its metrics after every change through the returned Subscription. The
+    /// rows of the book are still passed to the Wrapper. Dropping the
+    /// Subscription cancels the request.
+    ///
+    /// # Arguments
+    /// * request_id - The request's identifier
+    /// * contract - The Contract for which the depth is being requested
+    /// * num_rows - The number of rows on each side of the order book
+    /// * is_smart_depth - flag indicates that this is smart depth request
+    /// * market_depth_options - For internal use only. Use default value XYZ.
+    /// * config - what the metrics are computed over
+    pub fn stream_book_metrics(
+        &mut self,
+        request_id: i32,
+        contract: &Contract,
+        num_rows: i32,
+        is_smart_depth: bool,
+        market_depth_options: &[TagValue],
+        config: BookMetricsConfig,
+    ) -> Result<Subscription<BookMetrics>, IbkrError> {
+        let receiver = {
+            let mut streams = self.streams.lock().expect(POISONED_MUTEX);
+            streams
+                .order_books
+                .insert(request_id, (OrderBook::new(), config));
+            streams.book_metrics.add(request_id)
+        };
+
+        if let Err(err) = self.request_market_depth(
+            request_id,
+            contract,
+            num_rows,
+            is_smart_depth,
+            market_depth_options,
+        ) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .remove_book_metrics(request_id);
+            return Err(err);
         }
 
-        self.send_request(msg.as_str())
+        let cancel_msg =
+            Self::cancel_market_depth_message(request_id, is_smart_depth, self.server_version())?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .remove_book_metrics(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel market depth {}: {}", request_id, err);
+            }
+        }))
     }
 
     //#########################################################################
     //################## News Bulletins
     //#########################################################################
     /// Call this function to start receiving news bulletins. Each bulletin
-    /// will be returned by the update_news_bulletin() event.
-    //
+    /// will be returned as a NewsBulletin by the update_news_bulletin() event.
+    /// Besides regular news, bulletins report exchanges becoming unavailable
+    /// or available for trading again.
+    ///
     /// # Arguments
     /// * all_msgs - If set to TRUE, returns all the existing bulletins for
-    //               the current day and any new ones. If set to FALSE, will only
-    //               return new bulletins.
-    pub fn request_news_bulletins(&mut self, all_msgs: bool) -> Result<(), IBKRApiLibError> {
+    ///   the current day and any new ones. If set to FALSE, will only return
+    ///   new bulletins.
+    pub fn request_news_bulletins(&mut self, all_msgs: bool) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqNewsBulletins.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqNewsBulletins as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&all_msgs)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &all_msgs)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     ///Call this function to stop receiving news bulletins.
-    pub fn cancel_news_bulletins(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_news_bulletins(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::CancelNewsBulletins.version();
 
         let message_id: i32 = OutgoingMessageIds::CancelNewsBulletins as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        self.send_request(msg.as_str())?;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3033,15 +4669,126 @@ where
     ///
     /// Note:  This request can only be made when connected to a FA managed
     /// account.
-    pub fn request_managed_accts(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn request_managed_accts(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqManagedAccts.version();
         let message_id: i32 = OutgoingMessageIds::ReqManagedAccts as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        self.send_request(msg.as_str())
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        self.send_message(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The accounts managed by the login, as TWS sent them on connect or in
+    /// reply to request_managed_accts(). Empty until they have arrived.
+    pub fn accounts(&self) -> Vec<String> {
+        self.accounts
+            .lock()
+            .expect(POISONED_MUTEX)
+            .accounts()
+            .to_vec()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn default_account(&self) -> Option<String> {
+        self.accounts
+            .lock()
+            .expect(POISONED_MUTEX)
+            .default_account()
+            .map(str::to_string)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the account used by place_order(), request_account_updates() and
+    /// the PnL requests when they are made without one. Fails if the account
+    /// is not managed by the login.
+    pub fn set_default_account(&mut self, account: &str) -> Result<(), IbkrError> {
+        self.accounts
+            .lock()
+            .expect(POISONED_MUTEX)
+            .set_default_account(account)
+            .map_err(|message| IbkrError::rejected(NO_VALID_ID, &message))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear_default_account(&mut self) {
+        self.accounts
+            .lock()
+            .expect(POISONED_MUTEX)
+            .clear_default_account();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the session is a paper or a live one. Goes by the managed
+    /// accounts once they have arrived and by the port before that, unless
+    /// the mode has been set with set_trading_mode().
+    pub fn trading_mode(&self) -> TradingMode {
+        self.trading_mode.unwrap_or_else(|| {
+            TradingMode::detect(
+                self.port,
+                self.accounts.lock().expect(POISONED_MUTEX).accounts(),
+            )
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the mode trading_mode() reports, e.g. for a TWS on a port that
+    /// is not a default one. None goes back to detecting it.
+    pub fn set_trading_mode(&mut self, trading_mode: Option<TradingMode>) {
+        self.trading_mode = trading_mode;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fails unless the session is in `trading_mode`, e.g. to keep code
+    /// meant for paper trading off a live account
+    pub fn check_trading_mode(&self, trading_mode: TradingMode) -> Result<(), IbkrError> {
+        let current = self.trading_mode();
+        if current == trading_mode {
+            Ok(())
+        } else {
+            Err(IbkrError::rejected(
+                NO_VALID_ID,
+                &format!(
+                    "The session is in {} mode, not in {} mode.",
+                    current, trading_mode
+                ),
+            ))
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether connect() applies the defaults of the trading mode, see
+    /// apply_trading_mode_defaults(). Off by default.
+    pub fn set_apply_mode_defaults(&mut self, apply_mode_defaults: bool) {
+        self.apply_mode_defaults = apply_mode_defaults;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the market data type of the trading mode, delayed data for
+    /// paper sessions and real time data otherwise
+    pub fn apply_trading_mode_defaults(&mut self) -> Result<(), IbkrError> {
+        let market_data_type = self.trading_mode().default_market_data_type();
+        info!("Requesting {} market data", market_data_type);
+        self.request_market_data_type(market_data_type as i32)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fails if the account is not managed by the login
+    fn check_account(&self, request_id: i32, account: &str) -> Result<(), IbkrError> {
+        self.resolve_account(request_id, account).map(|_| ())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The account to use for a request: the default account if `account`
+    /// is empty, else `account` if it is managed by the login
+    fn resolve_account(&self, request_id: i32, account: &str) -> Result<String, IbkrError> {
+        self.accounts
+            .lock()
+            .expect(POISONED_MUTEX)
+            .resolve(account)
+            .map_err(|message| IbkrError::rejected(request_id, &message))
     }
 
     //----------------------------------------------------------------------------------------------
@@ -3054,17 +4801,17 @@ where
     ///     * 1 = GROUPS
     ///     * 2 = PROFILE
     ///     * 3 = ACCOUNT ALIASES
-    pub fn request_fa(&mut self, fa_data: FaDataType) -> Result<(), IBKRApiLibError> {
+    pub fn request_fa(&mut self, fa_data: FaDataType) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqFa.version();
         let message_id: i32 = OutgoingMessageIds::ReqFa as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&(fa_data as i32))?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &(fa_data as i32))?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3072,6 +4819,8 @@ where
     /// Call this function to modify FA configuration information from the
     /// API. Note that this can also be done manually in TWS itself.
     ///
+    /// * request_id - the id `Wrapper::replace_fa_end` confirms the change
+    ///   with, on servers from `MIN_SERVER_VER_REPLACE_FA_END` on
     /// * fa_data - See the FaDataType enum. Specifies the type of Financial
     ///   Advisor configuration data beingingg requested. Valid values include:
     ///     * 1 = GROUPS
@@ -3079,19 +4828,66 @@ where
     ///     * 3 = ACCOUNT ALIASES
     /// *cxml - The XML string containing the new FA configuration
     ///         information.
-    pub fn replace_fa(&mut self, fa_data: FaDataType, cxml: &str) -> Result<(), IBKRApiLibError> {
+    pub fn replace_fa(
+        &mut self,
+        request_id: i32,
+        fa_data: FaDataType,
+        cxml: &str,
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReplaceFa.version();
         let message_id: i32 = OutgoingMessageIds::ReplaceFa as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &(fa_data as i32))?;
+        write_field(&mut msg, &String::from(cxml))?;
+        if self.server_version() >= MIN_SERVER_VER_REPLACE_FA_END {
+            write_field(&mut msg, &request_id)?;
+        }
+
+        self.send_message(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests FA configuration information and blocks until it arrives.
+    /// The XML is not passed to the Wrapper. With the `fa-config` feature,
+    /// `fa_config` parses it into groups, profiles and aliases.
+    ///
+    /// # Arguments
+    /// * fa_data - the type of configuration to request
+    /// * timeout - how long to wait for the configuration
+    pub fn fetch_fa(
+        &mut self,
+        fa_data: FaDataType,
+        timeout: Duration,
+    ) -> Result<String, IbkrError> {
+        let code = fa_data as i32;
+        let receiver = {
+            let mut streams = self.streams.lock().expect(POISONED_MUTEX);
+            if streams.fa_data.contains(code) {
+                return Err(IbkrError::rejected(
+                    NO_VALID_ID,
+                    "This FA configuration is already being fetched.",
+                ));
+            }
+            streams.fa_data.add(code)
+        };
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&fa_data)?);
-        msg.push_str(&make_field(&String::from(cxml))?);
+        let result = self.request_fa(fa_data).and_then(|_| {
+            receiver
+                .recv_timeout(timeout)
+                .map_err(|_| IbkrError::timeout(NO_VALID_ID, "the FA configuration"))
+        });
 
-        self.send_request(msg.as_str())
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .fa_data
+            .remove(code);
+        result
     }
 
     //#########################################################################
@@ -3110,14 +4906,12 @@ where
     /// * end_date_time - Defines a query end date and time at any point during
     ///   the past 6 mos. Valid values include any date/time within the past six
     ///   months in the format: yyyymmdd HH:mm:ss ttt where "ttt" is the
-    ///   optional time zone.
-    /// * duration_str - Set the query duration up to one week, using a time
-    ///   unit of seconds, days or weeks. Valid values include any integer
-    ///   followed by a space and then S (seconds)); D (days) or W (week). If no
-    ///   unit is specified, seconds is used.
-    /// * bar_size_setting - See the BarSize enum for valid values. Specifies
-    ///   the size of the bars that will be returned (within IB/TWS listimits).
-    ///   Valid values include:
+    ///   optional time zone. Use format_end_date_time() to build it.
+    /// * duration - The length of the query in seconds, days, weeks, months
+    ///   or years. Must be compatible with the bar size, see
+    ///   HistoricalDuration::is_compatible.
+    /// * bar_size - Specifies the size of the bars that will be returned
+    ///   (within IB/TWS limits). Valid values include:
     ///     * 1 sec
     ///     * 5 secs
     ///     * 15 secs
@@ -3140,6 +4934,8 @@ where
     ///     * BID_ASK
     ///     * HISTORICAL_VOLATILITY
     ///     * OPTION_IMPLIED_VOLATILITY
+    ///     * ADJUSTED_LAST - only without an end_date_time
+    ///     * SCHEDULE - see request_historical_schedule()
     /// * regular_trading_hours_only - Determines whether to return all data
     ///   available during the requested time span, or only data that falls
     ///   within regular trading hours. Valid values include:
@@ -3169,76 +4965,68 @@ where
         format_date: Option<DateTimeFormat>,
         keep_up_to_date: Option<bool>,
         chart_options: Option<&[TagValue]>,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && (!contract.trading_class.is_empty() || contract.con_id > 0)
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support con_id and trading_class parameters in request_historical_data."
-                ),
-            ));
+            let err = IbkrError::Connection(ConnectionError::new(request_id, TwsError::UpdateTws, "It does not support con_id and trading_class parameters in request_historical_data."));
 
             return Err(err);
         }
 
-        let version = 6;
+        let version = OutgoingMessageIds::ReqHistoricalData.version();
 
         // send req market data msg
         let message_id: i32 = OutgoingMessageIds::ReqHistoricalData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
         if self.server_version() < MIN_SERVER_VER_SYNT_REALTIME_BARS {
-            msg.push_str(&make_field(&version)?);
+            write_field(&mut msg, &version)?;
         }
 
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &request_id)?;
 
         // Send contract fields
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.con_id)?);
-            msg.push_str(&make_field(&contract.symbol)?);
-            msg.push_str(&make_field(&contract.sec_type)?);
-            msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-            msg.push_str(&make_field(&contract.strike)?);
-            msg.push_str(&make_field(&contract.right)?);
-            msg.push_str(&make_field(&contract.multiplier)?);
-            msg.push_str(&make_field(&contract.exchange)?);
-            msg.push_str(&make_field(&contract.primary_exchange)?);
-            msg.push_str(&make_field(&contract.currency)?);
-            msg.push_str(&make_field(&contract.local_symbol)?);
+            write_field(&mut msg, &contract.con_id)?;
+            write_field(&mut msg, &contract.symbol)?;
+            write_field(&mut msg, &contract.sec_type.to_string())?;
+            write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+            write_field(&mut msg, &contract.strike)?;
+            write_field(&mut msg, &contract.right.to_string())?;
+            write_field(&mut msg, &contract.multiplier)?;
+            write_field(&mut msg, &contract.exchange)?;
+            write_field(&mut msg, &contract.primary_exchange)?;
+            write_field(&mut msg, &contract.currency)?;
+            write_field(&mut msg, &contract.local_symbol)?;
         }
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
-        msg.push_str(&make_field(&contract.include_expired)?); // srv v31 and above
+        write_field(&mut msg, &contract.include_expired)?; // srv v31 and above
 
-        msg.push_str(&make_field(&String::from(end_date_time))?); // srv v20 and above
-        msg.push_str(&make_field(&String::from(bar_size_setting))?); // srv v20 and above
-        msg.push_str(&make_field(&String::from(duration_str))?);
-        msg.push_str(&make_field(if regular_trading_hours_only.unwrap_or(true) { &1 } else { &0 })?);
-        msg.push_str(&make_field(&String::from(what_to_show))?);
-        msg.push_str(&make_field(&format_date.unwrap_or(DateTimeFormat::DateTime).to_i32().unwrap())?); // srv v16 and above
+        write_field(&mut msg, &String::from(end_date_time))?; // srv v20 and above
+        write_field(&mut msg, &String::from(bar_size_setting))?; // srv v20 and above
+        write_field(&mut msg, &String::from(duration_str))?;
+        write_field(&mut msg, if regular_trading_hours_only.unwrap_or(true) { &1 } else { &0 })?;
+        write_field(&mut msg, &String::from(what_to_show))?;
+        write_field(&mut msg, &(format_date.unwrap_or(DateTimeFormat::DateTime) as i32))?; // srv v16 and above
 
         // Send combo legs for BAG requests
-        if contract.sec_type == "BAG" {
-            msg.push_str(&make_field(&contract.combo_legs.len())?);
+        if contract.sec_type == SecType::Combo {
+            write_field(&mut msg, &contract.combo_legs.len())?;
             for combo_leg in &contract.combo_legs {
-                msg.push_str(&make_field(&combo_leg.con_id)?);
-                msg.push_str(&make_field(&combo_leg.ratio)?);
-                msg.push_str(&make_field(&combo_leg.action)?);
-                msg.push_str(&make_field(&combo_leg.exchange)?);
+                write_field(&mut msg, &combo_leg.con_id)?;
+                write_field(&mut msg, &combo_leg.ratio)?;
+                write_field(&mut msg, &combo_leg.action)?;
+                write_field(&mut msg, &combo_leg.exchange)?;
             }
         }
         if self.server_version() >= MIN_SERVER_VER_SYNT_REALTIME_BARS {
-            msg.push_str(&make_field(&if regular_trading_hours_only.unwrap_or(true) { &1 } else { &0 })?);
+            write_field(&mut msg, &if regular_trading_hours_only.unwrap_or(true) { &1 } else { &0 })?;
         }
         // Send chart_options parameter
         if self.server_version() >= MIN_SERVER_VER_LINKING {
@@ -3246,10 +5034,10 @@ where
                 .iter()
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
-            msg.push_str(&make_field(&chart_options_str)?);
+            write_field(&mut msg, &chart_options_str)?;
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
 
         Ok(())
     }*/
@@ -3259,83 +5047,98 @@ where
         req_id: i32,
         contract: &Contract,
         end_date_time: &str,
-        duration_str: &str,
-        bar_size_setting: &str,
-        what_to_show: &str,
+        duration: HistoricalDuration,
+        bar_size: BarSize,
+        what_to_show: WhatToShow,
         use_rth: i32,
         format_date: i32,
         keep_up_to_date: bool,
         chart_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
+        self.check_what_to_show(req_id, &what_to_show, DataRequestType::HistoricalData)?;
+
+        if let Err(message) = duration.check_bar_size(&bar_size) {
+            return Err(IbkrError::rejected(req_id, &message));
+        }
+
+        if keep_up_to_date && bar_size.seconds() < 5 {
+            return Err(IbkrError::rejected(
+                req_id,
+                "Bars kept up to date must be at least 5 secs.",
+            ));
+        }
+
+        if what_to_show == WhatToShow::AdjustedLast && !end_date_time.is_empty() {
+            return Err(IbkrError::rejected(
+                req_id,
+                "ADJUSTED_LAST can not be requested with an end date.",
+            ));
+        }
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && (!contract.trading_class.is_empty() || contract.con_id > 0)
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                    req_id,
-                    TwsError::UpdateTws.code().to_string(),
-                    format!(
-                        "{}{}",
-                        TwsError::UpdateTws.message(),
-                        " It does not support con_id and trading_class parameters in req_historical_data."
-                    ),
-                ));
+            let err = IbkrError::Connection(ConnectionError::new(
+                req_id,
+                TwsError::UpdateTws,
+                "It does not support con_id and trading_class parameters in req_historical_data.",
+            ));
 
             return Err(err);
         }
 
-        let version = 6;
+        let version = OutgoingMessageIds::ReqHistoricalData.version();
 
         // send req market data msg
         let message_id: i32 = OutgoingMessageIds::ReqHistoricalData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
         if self.server_version() < MIN_SERVER_VER_SYNT_REALTIME_BARS {
-            msg.push_str(&make_field(&version)?);
+            write_field(&mut msg, &version)?;
         }
 
-        msg.push_str(&make_field(&req_id)?);
+        write_field(&mut msg, &req_id)?;
 
         // Send contract fields
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.con_id)?);
-            msg.push_str(&make_field(&contract.symbol)?);
-            msg.push_str(&make_field(&contract.sec_type)?);
-            msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-            msg.push_str(&make_field(&contract.strike)?);
-            msg.push_str(&make_field(&contract.right)?);
-            msg.push_str(&make_field(&contract.multiplier)?);
-            msg.push_str(&make_field(&contract.exchange)?);
-            msg.push_str(&make_field(&contract.primary_exchange)?);
-            msg.push_str(&make_field(&contract.currency)?);
-            msg.push_str(&make_field(&contract.local_symbol)?);
+            write_field(&mut msg, &contract.con_id)?;
+            write_field(&mut msg, &contract.symbol)?;
+            write_field(&mut msg, &contract.sec_type.to_string())?;
+            write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+            write_field(&mut msg, &contract.strike)?;
+            write_field(&mut msg, &contract.right.to_string())?;
+            write_field(&mut msg, &contract.multiplier)?;
+            write_field(&mut msg, &contract.exchange)?;
+            write_field(&mut msg, &contract.primary_exchange)?;
+            write_field(&mut msg, &contract.currency)?;
+            write_field(&mut msg, &contract.local_symbol)?;
         }
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
-        msg.push_str(&make_field(&contract.include_expired)?); // srv v31 and above
+        write_field(&mut msg, &contract.include_expired)?; // srv v31 and above
 
-        msg.push_str(&make_field(&String::from(end_date_time))?); // srv v20 and above
-        msg.push_str(&make_field(&String::from(bar_size_setting))?); // srv v20 and above
-        msg.push_str(&make_field(&String::from(duration_str))?);
-        msg.push_str(&make_field(&use_rth)?);
-        msg.push_str(&make_field(&String::from(what_to_show))?);
-        msg.push_str(&make_field(&format_date)?); // srv v16 and above
+        write_field(&mut msg, &String::from(end_date_time))?; // srv v20 and above
+        write_field(&mut msg, &bar_size.to_string())?; // srv v20 and above
+        write_field(&mut msg, &duration.to_string())?;
+        write_field(&mut msg, &use_rth)?;
+        write_field(&mut msg, &what_to_show.to_string())?;
+        write_field(&mut msg, &format_date)?; // srv v16 and above
 
         // Send combo legs for BAG requests
-        if contract.sec_type == "BAG" {
-            msg.push_str(&make_field(&contract.combo_legs.len())?);
+        if contract.sec_type == SecType::Combo {
+            write_field(&mut msg, &contract.combo_legs.len())?;
             for combo_leg in &contract.combo_legs {
-                msg.push_str(&make_field(&combo_leg.con_id)?);
-                msg.push_str(&make_field(&combo_leg.ratio)?);
-                msg.push_str(&make_field(&combo_leg.action)?);
-                msg.push_str(&make_field(&combo_leg.exchange)?);
+                write_field(&mut msg, &combo_leg.con_id)?;
+                write_field(&mut msg, &combo_leg.ratio)?;
+                write_field(&mut msg, &combo_leg.action)?;
+                write_field(&mut msg, &combo_leg.exchange)?;
             }
         }
         if self.server_version() >= MIN_SERVER_VER_SYNT_REALTIME_BARS {
-            msg.push_str(&make_field(&keep_up_to_date)?);
+            write_field(&mut msg, &keep_up_to_date)?;
         }
         // Send chart_options parameter
         if self.server_version() >= MIN_SERVER_VER_LINKING {
@@ -3343,13 +5146,70 @@ where
                 .iter()
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
-            msg.push_str(&make_field(&chart_options_str)?);
+            write_field(&mut msg, &chart_options_str)?;
+        }
+
+        self.send_message(msg)?;
+
+        let retry_policy = self
+            .retry_policies
+            .remove(&req_id)
+            .unwrap_or(self.historical_retry_policy);
+        let mut streams = self.streams.lock().expect(POISONED_MUTEX);
+        streams.bar_times.insert(
+            req_id,
+            (
+                BarTimeParser::new(format_date, self.time_zone),
+                keep_up_to_date,
+            ),
+        );
+        if retry_policy.is_enabled() {
+            // The buffer still holds the request just sent
+            streams.historical_retries.insert(
+                req_id,
+                HistoricalRetry::new(self.message_buffer.clone(), retry_policy),
+            );
+        } else {
+            streams.historical_retries.remove(&req_id);
         }
-
-        self.send_request(msg.as_str())?;
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Requests the trading sessions of a contract. The sessions are
+    /// returned through Wrapper::historical_schedule.
+    ///
+    /// # Arguments
+    /// * request_id - The id of the request. Must be a unique value.
+    /// * contract - The contract the sessions are requested for
+    /// * end_date_time - The end of the range, i.e. "20230103 16:00:00
+    ///   US/Eastern". Leave empty for the current time.
+    /// * duration - The length of the range, i.e. one month. The range can
+    ///   reach into the future to get upcoming sessions.
+    /// * use_rth - Return the regular trading hours (1) or all available
+    ///   trading hours (0)
+    pub fn request_historical_schedule(
+        &mut self,
+        request_id: i32,
+        contract: &Contract,
+        end_date_time: &str,
+        duration: HistoricalDuration,
+        use_rth: i32,
+    ) -> Result<(), IbkrError> {
+        self.request_historical_data(
+            request_id,
+            contract,
+            end_date_time,
+            duration,
+            BarSize::_1Day,
+            WhatToShow::Schedule,
+            use_rth,
+            1,
+            false,
+            &[],
+        )
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Used if an internet disconnect has occurred or the results of a query
     /// are otherwise delayed and the client is no longer interested in
@@ -3357,21 +5217,109 @@ where
     ///
     /// # Arguments
     /// * request_id - the id of the original request
-    pub fn cancel_historical_data(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_historical_data(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let msg = Self::cancel_historical_data_message(request_id)?;
 
-        let message_id: i32 = OutgoingMessageIds::CancelHistoricalData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        self.send_message(msg)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .remove_historical_data(request_id);
+        Ok(())
+    }
 
-        self.send_request(msg.as_str())?;
+    //----------------------------------------------------------------------------------------------
+    fn cancel_historical_data_message(request_id: i32) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelHistoricalData.version();
 
-        Ok(())
+        let message_id: i32 = OutgoingMessageIds::CancelHistoricalData as i32;
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        finish_message(&mut msg);
+        Ok(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_historical_data() but delivers the bars through the
+    /// returned HistoricalDataStream instead of Wrapper::historical_data and
+    /// Wrapper::historical_data_end. The stream completes on the end marker
+    /// or yields the error TWS reported for the request. With
+    /// `keep_up_to_date` the bar updates follow, see
+    /// HistoricalDataStream::next_update.
+    ///
+    /// Dropping the stream cancels the request unless it has completed.
+    ///
+    /// # Arguments
+    /// See request_historical_data()
+    pub fn stream_historical_data(
+        &mut self,
+        req_id: i32,
+        contract: &Contract,
+        end_date_time: &str,
+        duration: HistoricalDuration,
+        bar_size: BarSize,
+        what_to_show: WhatToShow,
+        use_rth: i32,
+        format_date: i32,
+        keep_up_to_date: bool,
+        chart_options: &[TagValue],
+    ) -> Result<HistoricalDataStream, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .historical_data
+            .add(req_id);
+
+        if let Err(err) = self.request_historical_data(
+            req_id,
+            contract,
+            end_date_time,
+            duration,
+            bar_size,
+            what_to_show,
+            use_rth,
+            format_date,
+            keep_up_to_date,
+            chart_options,
+        ) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .historical_data
+                .remove(req_id);
+            return Err(err);
+        }
+
+        let cancel_msg = Self::cancel_historical_data_message(req_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+        let active = Arc::new(AtomicBool::new(true));
+        let still_active = active.clone();
+
+        let subscription = Subscription::new(req_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .remove_historical_data(req_id);
+            if still_active.load(Ordering::Acquire) {
+                if let Err(err) = request_sender.send_message(&cancel_msg) {
+                    error!("Failed to cancel historical data {}: {}", req_id, err);
+                }
+            }
+        });
+        Ok(HistoricalDataStream::new(
+            subscription,
+            keep_up_to_date,
+            active,
+        ))
     }
 
     //----------------------------------------------------------------------------------------------
@@ -3394,60 +5342,52 @@ where
         &mut self,
         request_id: i32,
         contract: &Contract,
-        what_to_show: &str,
+        what_to_show: WhatToShow,
         regular_trading_hours_only: Option<bool>,
         format_date: Option<DateTimeFormat>,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
+        self.check_what_to_show(request_id, &what_to_show, DataRequestType::HeadTimestamp)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_HEAD_TIMESTAMP {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support head time stamp requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqHeadTimestamp,
+            request_id,
+            "It does not support head time stamp requests.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqHeadTimestamp as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&contract.con_id)?);
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
-        msg.push_str(&make_field(&contract.trading_class)?);
-        msg.push_str(&make_field(&contract.include_expired)?);
-        msg.push_str(&make_field(
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &contract.con_id)?;
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
+        write_field(&mut msg, &contract.trading_class)?;
+        write_field(&mut msg, &contract.include_expired)?;
+        write_field(
+            &mut msg,
             if regular_trading_hours_only.unwrap_or(true) {
                 &1
             } else {
                 &0
             },
-        )?);
-        msg.push_str(&make_field(&String::from(what_to_show))?);
-        msg.push_str(&make_field(
-            &format_date
-                .unwrap_or(DateTimeFormat::DateTime)
-                .to_i32()
-                .unwrap(),
-        )?);
-
-        self.send_request(msg.as_str())?;
+        )?;
+        write_field(&mut msg, &what_to_show.to_string())?;
+        write_field(
+            &mut msg,
+            &(format_date.unwrap_or(DateTimeFormat::DateTime) as i32),
+        )?;
+
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3456,30 +5396,22 @@ where
     ///
     /// # Arguments
     /// * request_id - the id of the original request
-    pub fn cancel_head_time_stamp(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_head_time_stamp(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_CANCEL_HEADTIMESTAMP {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support head time stamp requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::CancelHeadTimestamp,
+            request_id,
+            "It does not support head time stamp requests.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::CancelHeadTimestamp as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3498,45 +5430,37 @@ where
         contract: &Contract,
         regular_trading_hours_only: bool,
         time_period: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_HISTOGRAM {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support histogram requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqHistogramData,
+            NO_VALID_ID,
+            "It does not support histogram requests.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqHistogramData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-
-        msg.push_str(&make_field(&ticker_id)?);
-        msg.push_str(&make_field(&contract.con_id)?);
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
-        msg.push_str(&make_field(&contract.trading_class)?);
-        msg.push_str(&make_field(&contract.include_expired)?);
-        msg.push_str(&make_field(&regular_trading_hours_only)?);
-        msg.push_str(&make_field(&String::from(time_period))?);
-
-        self.send_request(msg.as_str())?;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &ticker_id)?;
+        write_field(&mut msg, &contract.con_id)?;
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
+        write_field(&mut msg, &contract.trading_class)?;
+        write_field(&mut msg, &contract.include_expired)?;
+        write_field(&mut msg, &regular_trading_hours_only)?;
+        write_field(&mut msg, &String::from(time_period))?;
+
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3545,29 +5469,21 @@ where
     ///
     /// # Arguments
     /// * request_id - the id of the original request
-    pub fn cancel_histogram_data(&mut self, ticker_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_histogram_data(&mut self, ticker_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_HISTOGRAM {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support histogram requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::CancelHistogramData,
+            NO_VALID_ID,
+            "It does not support histogram requests.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::CancelHistogramData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&ticker_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &ticker_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3595,80 +5511,149 @@ where
         start_date_time: &str,
         end_date_time: &str,
         number_of_ticks: i32,
-        what_to_show: &str,
+        what_to_show: WhatToShow,
         regular_trading_hours_only: i32,
         ignore_size: bool,
         misc_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
+        self.check_what_to_show(request_id, &what_to_show, DataRequestType::HistoricalTicks)?;
 
-        if self.server_version() < MIN_SERVER_VER_HISTORICAL_TICKS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support historical ticks requests."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqHistoricalTicks,
+            NO_VALID_ID,
+            "It does not support historical ticks requests.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqHistoricalTicks as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&contract.con_id)?);
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
-        msg.push_str(&make_field(&contract.trading_class)?);
-        msg.push_str(&make_field(&contract.include_expired)?);
-        msg.push_str(&make_field(&String::from(start_date_time))?);
-        msg.push_str(&make_field(&String::from(end_date_time))?);
-        msg.push_str(&make_field(&number_of_ticks)?);
-        msg.push_str(&make_field(&String::from(what_to_show))?);
-        msg.push_str(&make_field(&regular_trading_hours_only)?);
-        msg.push_str(&make_field(&ignore_size)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &contract.con_id)?;
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
+        write_field(&mut msg, &contract.trading_class)?;
+        write_field(&mut msg, &contract.include_expired)?;
+        write_field(&mut msg, &String::from(start_date_time))?;
+        write_field(&mut msg, &String::from(end_date_time))?;
+        write_field(&mut msg, &number_of_ticks)?;
+        write_field(&mut msg, &what_to_show.to_string())?;
+        write_field(&mut msg, &regular_trading_hours_only)?;
+        write_field(&mut msg, &ignore_size)?;
 
         let misc_options_string = misc_options
             .iter()
             .map(|x| format!("{}={};", x.tag, x.value))
             .collect::<String>();
 
-        msg.push_str(&make_field(&misc_options_string)?);
+        write_field(&mut msg, &misc_options_string)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Fetches the historical ticks of a range, blocking until all of them
+    /// have arrived. Ranges with more ticks than a single request returns
+    /// are fetched with further requests that continue after the last tick
+    /// received. The ticks are not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * request_id - id used for each of the requests
+    /// * contract - Contract object that is subject of query
+    /// * query - the kind of ticks and the range to fetch
+    /// * timeout - how long to wait for each request to be answered
+    pub fn fetch_historical_ticks(
+        &mut self,
+        request_id: i32,
+        contract: &Contract,
+        query: &HistoricalTicksQuery,
+        timeout: Duration,
+    ) -> Result<HistoricalTicks, IbkrError> {
+        if !query.what_to_show.is_valid_for_historical_ticks() {
+            return Err(IbkrError::rejected(
+                request_id,
+                &format!(
+                    "{} is not supported for historical ticks.",
+                    query.what_to_show
+                ),
+            ));
+        }
+
+        let mut ticks = HistoricalTicks::empty(&query.what_to_show);
+        let mut next_query = Some(query.clone());
+
+        while let Some(page_query) = next_query {
+            let receiver = self
+                .streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .historical_ticks
+                .add(request_id);
+
+            let result = self.request_historical_ticks(
+                request_id,
+                contract,
+                page_query.start_date_time_param().as_str(),
+                "",
+                page_query.number_of_ticks,
+                page_query.what_to_show.clone(),
+                page_query.regular_trading_hours_only as i32,
+                page_query.ignore_size,
+                &[],
+            );
+            let event = result.map(|_| receiver.recv_timeout(timeout));
+
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .historical_ticks
+                .remove(request_id);
+
+            match event? {
+                Ok(HistoricalTicksEvent::Ticks(mut page)) => {
+                    next_query = page_query.next_page(&mut page);
+                    ticks.append(page);
+                }
+                Ok(HistoricalTicksEvent::Error { code, message }) => {
+                    return Err(IbkrError::Server(ServerError::new(
+                        request_id, code, &message,
+                    )));
+                }
+                Err(_) => {
+                    return Err(IbkrError::timeout(request_id, "historical ticks"));
+                }
+            }
+        }
+        Ok(ticks)
+    }
+
     //#########################################################################
     //################## Market Scanners
     //#########################################################################
     /// Requests an XML list of scanner parameters valid in TWS.
     /// Not all parameters are valid from API scanner.
-    pub fn request_scanner_parameters(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn request_scanner_parameters(&mut self) -> Result<(), IbkrError> {
         /* Requests an XML string that describes all possible scanner queries */
 
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::ReqScannerParameters.version();
         let message_id: i32 = OutgoingMessageIds::ReqScannerParameters as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3682,73 +5667,71 @@ where
     ///   filter results.
     /// * scanner_subscription_options -  For internal use only. Use default
     ///   value XYZ
+    /// * scanner_subscription_filter_options - Generic filters, sent along with
+    ///   the filter_options of the subscription
     pub fn request_scanner_subscription(
         &mut self,
         request_id: i32,
         subscription: ScannerSubscription,
         scanner_subscription_options: &[TagValue],
         scanner_subscription_filter_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(request_id)?;
 
         error!("Server version: {}", self.server_version());
         if self.server_version() < MIN_SERVER_VER_SCANNER_GENERIC_OPTS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support API scanner subscription generic filter options"
-                ),
+                TwsError::UpdateTws,
+                "It does not support API scanner subscription generic filter options",
             ));
 
             return Err(err);
         }
 
-        let version = 4;
+        let version = OutgoingMessageIds::ReqScannerSubscription.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqScannerSubscription as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
         if self.server_version() < MIN_SERVER_VER_SCANNER_GENERIC_OPTS {
-            msg.push_str(&make_field(&version)?);
-        }
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field_handle_empty(&subscription.number_of_rows)?);
-        msg.push_str(&make_field(&subscription.instrument)?);
-        msg.push_str(&make_field(&subscription.location_code)?);
-        msg.push_str(&make_field(&subscription.scan_code)?);
-        msg.push_str(&make_field_handle_empty(&subscription.above_price)?);
-        msg.push_str(&make_field_handle_empty(&subscription.below_price)?);
-        msg.push_str(&make_field_handle_empty(&subscription.above_volume)?);
-        msg.push_str(&make_field_handle_empty(&subscription.market_cap_above)?);
-        msg.push_str(&make_field_handle_empty(&subscription.market_cap_below)?);
-        msg.push_str(&make_field(&subscription.moody_rating_above)?);
-        msg.push_str(&make_field(&subscription.moody_rating_below)?);
-        msg.push_str(&make_field(&subscription.sp_rating_above)?);
-        msg.push_str(&make_field(&subscription.sp_rating_below)?);
-        msg.push_str(&make_field(&subscription.maturity_date_above)?);
-        msg.push_str(&make_field(&subscription.maturity_date_below)?);
-        msg.push_str(&make_field_handle_empty(&subscription.coupon_rate_above)?);
-        msg.push_str(&make_field_handle_empty(&subscription.coupon_rate_below)?);
-        msg.push_str(&make_field(&subscription.exclude_convertible)?);
-        msg.push_str(&make_field_handle_empty(
-            &subscription.average_option_volume_above,
-        )?); // srv v25 and above
-        msg.push_str(&make_field(&subscription.scanner_setting_pairs)?); // srv v25 and above
-        msg.push_str(&make_field(&subscription.stock_type_filter)?); // srv v27 and above
+            write_field(&mut msg, &version)?;
+        }
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &subscription.number_of_rows)?;
+        write_field(&mut msg, &subscription.instrument)?;
+        write_field(&mut msg, &subscription.location_code)?;
+        write_field(&mut msg, &subscription.scan_code)?;
+        write_field(&mut msg, &subscription.above_price)?;
+        write_field(&mut msg, &subscription.below_price)?;
+        write_field(&mut msg, &subscription.above_volume)?;
+        write_field(&mut msg, &subscription.market_cap_above)?;
+        write_field(&mut msg, &subscription.market_cap_below)?;
+        write_field(&mut msg, &subscription.moody_rating_above)?;
+        write_field(&mut msg, &subscription.moody_rating_below)?;
+        write_field(&mut msg, &subscription.sp_rating_above)?;
+        write_field(&mut msg, &subscription.sp_rating_below)?;
+        write_field(&mut msg, &subscription.maturity_date_above)?;
+        write_field(&mut msg, &subscription.maturity_date_below)?;
+        write_field(&mut msg, &subscription.coupon_rate_above)?;
+        write_field(&mut msg, &subscription.coupon_rate_below)?;
+        write_field(&mut msg, &subscription.exclude_convertible)?;
+        write_field(&mut msg, &subscription.average_option_volume_above)?; // srv v25 and above
+        write_field(&mut msg, &subscription.scanner_setting_pairs)?; // srv v25 and above
+        write_field(&mut msg, &subscription.stock_type_filter)?; // srv v27 and above
 
         // Send scanner_subscription_filter_options parameter
         if self.server_version() >= MIN_SERVER_VER_SCANNER_GENERIC_OPTS {
             error!("!!!!!!!! making scanner options");
-            let scanner_subscription_filter = scanner_subscription_filter_options
+            let scanner_subscription_filter = subscription
+                .filter_options
                 .iter()
+                .chain(scanner_subscription_filter_options.iter())
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
 
-            msg.push_str(&make_field(&scanner_subscription_filter)?);
+            write_field(&mut msg, &scanner_subscription_filter)?;
         }
         // Send scanner_subscription_options parameter
         if self.server_version() >= MIN_SERVER_VER_LINKING {
@@ -3756,11 +5739,11 @@ where
                 .iter()
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
-            msg.push_str(&make_field(&scanner_subscription_options)?);
+            write_field(&mut msg, &scanner_subscription_options)?;
         }
         error!("request_scanner_subscription");
-        error!("{}", msg);
-        self.send_request(msg.as_str())?;
+        error!("{}", String::from_utf8_lossy(&msg));
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3769,34 +5752,105 @@ where
     ///
     /// # Arguments
     /// * request_id - the id of the original request
-    pub fn cancel_scanner_subscription(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_scanner_subscription(&mut self, request_id: i32) -> Result<(), IbkrError> {
         /* reqId:i32 - The ticker ID. Must be a unique value */
 
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let msg = Self::cancel_scanner_subscription_message(request_id)?;
 
-        let message_id: i32 = OutgoingMessageIds::CancelScannerSubscription as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        self.send_message(msg)?;
 
-        self.send_request(msg.as_str())?;
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .scanner_data
+            .remove(request_id);
         Ok(())
     }
 
-    //#########################################################################
-    //################## Real Time Bars
-    //#########################################################################
-    /// Call the request_real_time_bars() function to start receiving real time
-    /// bar results through the realtimeBar() EWrapper function.
+    //----------------------------------------------------------------------------------------------
+    fn cancel_scanner_subscription_message(request_id: i32) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelScannerSubscription.version();
+
+        let message_id: i32 = OutgoingMessageIds::CancelScannerSubscription as i32;
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        finish_message(&mut msg);
+        Ok(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_scanner_subscription() but delivers each scan through
+    /// the returned Subscription as a list of results ordered by rank instead
+    /// of through Wrapper::scanner_data. Dropping the Subscription cancels the
+    /// request.
     ///
     /// # Arguments
-    /// * request_id - The Id for the request. Must be a unique value. When the
-    ///   data is received, it will be identified by this Id. This is also used
-    ///   when canceling the request.
+    /// * request_id - The ticker ID. Must be a unique value.
+    /// * subscription - The scan, e.g. built with ScannerSubscription::builder
+    /// * scanner_subscription_options -  For internal use only. Use default
+    ///   value XYZ
+    pub fn stream_scanner_subscription(
+        &mut self,
+        request_id: i32,
+        subscription: ScannerSubscription,
+        scanner_subscription_options: &[TagValue],
+    ) -> Result<Subscription<Vec<ScanData>>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .scanner_data
+            .add(request_id);
+
+        if let Err(err) = self.request_scanner_subscription(
+            request_id,
+            subscription,
+            scanner_subscription_options,
+            &[],
+        ) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .scanner_data
+                .remove(request_id);
+            return Err(err);
+        }
+
+        let cancel_msg = Self::cancel_scanner_subscription_message(request_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
+
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .scanner_data
+                .remove(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!(
+                    "Failed to cancel scanner subscription {}: {}",
+                    request_id, err
+                );
+            }
+        }))
+    }
+
+    //#########################################################################
+    //################## Real Time Bars
+    //#########################################################################
+    /// Call the request_real_time_bars() function to start receiving real time
+    /// bar results through the realtimeBar() EWrapper function.
+    ///
+    /// # Arguments
+    /// * request_id - The Id for the request. Must be a unique value. When the
+    ///   data is received, it will be identified by this Id. This is also used
+    ///   when canceling the request.
     /// * contract - This object contains a description of the contract for
     ///   which real time bars are being requested
     /// * bar_size - Currently only 5 second bars are supported, if any other
@@ -3822,57 +5876,54 @@ where
         request_id: i32,
         contract: &Contract,
         bar_size: i32,
-        what_to_show: &str,
+        what_to_show: WhatToShow,
         regular_trading_hours_only: bool,
         real_time_bars_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
+        self.check_what_to_show(request_id, &what_to_show, DataRequestType::RealTimeBars)?;
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS
             && !contract.trading_class.is_empty()
         {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support con_id and trading_class parameter in request_real_time_bars."
-                ),
+                TwsError::UpdateTws,
+                "It does not support con_id and trading_class parameter in request_real_time_bars.",
             ));
 
             return Err(err);
         }
 
-        let version = 3;
+        let version = OutgoingMessageIds::ReqRealTimeBars.version();
 
         let message_id: i32 = OutgoingMessageIds::ReqRealTimeBars as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
         // Send contract fields
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.con_id)?);
-        }
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
-        msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
-        msg.push_str(&make_field(&contract.multiplier)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
+            write_field(&mut msg, &contract.con_id)?;
+        }
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.last_trade_date_or_contract_month)?;
+        write_field(&mut msg, &contract.strike)?;
+        write_field(&mut msg, &contract.right.to_string())?;
+        write_field(&mut msg, &contract.multiplier)?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.trading_class)?);
+            write_field(&mut msg, &contract.trading_class)?;
         }
-        msg.push_str(&make_field(&bar_size)?);
-        msg.push_str(&make_field(&String::from(what_to_show))?);
-        msg.push_str(&make_field(&regular_trading_hours_only)?);
+        write_field(&mut msg, &bar_size)?;
+        write_field(&mut msg, &what_to_show.to_string())?;
+        write_field(&mut msg, &regular_trading_hours_only)?;
 
         // Send real_time_bars_options parameter
         if self.server_version() >= MIN_SERVER_VER_LINKING {
@@ -3881,10 +5932,10 @@ where
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
 
-            msg.push_str(&make_field(&real_time_bars_options_str)?);
+            write_field(&mut msg, &real_time_bars_options_str)?;
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -3894,21 +5945,97 @@ where
     /// # Arguments
     /// * request_id - The Id that was specified in the call to
     ///   request_real_time_bars().
-    pub fn cancel_real_time_bars(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_real_time_bars(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 1;
+        let msg = Self::cancel_real_time_bars_message(request_id)?;
+
+        self.send_message(msg)?;
+
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .real_time_bars
+            .remove(request_id);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn cancel_real_time_bars_message(request_id: i32) -> Result<Vec<u8>, IbkrError> {
+        let version = OutgoingMessageIds::CancelRealTimeBars.version();
 
         // Send req market data msg
         let message_id: i32 = OutgoingMessageIds::CancelRealTimeBars as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = Vec::new();
+        begin_message(&mut msg);
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        finish_message(&mut msg);
+        Ok(msg)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_real_time_bars() but delivers the 5 second bars through
+    /// the returned Subscription instead of Wrapper::realtime_bar. Dropping the
+    /// Subscription cancels the request.
+    ///
+    /// # Arguments
+    /// * request_id - The Id for the request. Must be a unique value.
+    /// * contract - This object contains a description of the contract for
+    ///   which real time bars are being requested
+    /// * what_to_show - One of WhatToShow::Trades, WhatToShow::Midpoint,
+    ///   WhatToShow::Bid or WhatToShow::Ask
+    /// * regular_trading_hours_only - Only return data within the regular
+    ///   trading hours of the product
+    /// * real_time_bars_options: - For internal use only. Use default value
+    ///   XYZ
+    pub fn stream_real_time_bars(
+        &mut self,
+        request_id: i32,
+        contract: &Contract,
+        what_to_show: WhatToShow,
+        regular_trading_hours_only: bool,
+        real_time_bars_options: &[TagValue],
+    ) -> Result<Subscription<RealTimeBar>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .real_time_bars
+            .add(request_id);
+
+        if let Err(err) = self.request_real_time_bars(
+            request_id,
+            contract,
+            5,
+            what_to_show,
+            regular_trading_hours_only,
+            real_time_bars_options,
+        ) {
+            self.streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .real_time_bars
+                .remove(request_id);
+            return Err(err);
+        }
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        let cancel_msg = Self::cancel_real_time_bars_message(request_id)?;
+        let request_sender = self.request_sender.clone();
+        let streams = self.streams.clone();
 
-        self.send_request(msg.as_str())?;
-        Ok(())
+        Ok(Subscription::new(request_id, receiver, move || {
+            streams
+                .lock()
+                .expect(POISONED_MUTEX)
+                .real_time_bars
+                .remove(request_id);
+            if let Err(err) = request_sender.send_message(&cancel_msg) {
+                error!("Failed to cancel real time bars {}: {}", request_id, err);
+            }
+        }))
     }
 
     //#########################################################################
@@ -3936,63 +6063,54 @@ where
     ///     * ReportsFinStatements (financial statements)
     ///     * RESC (analyst estimates)
     ///     * CalendarReport (company calendar)
+    ///
+    ///   With the `fundamentals` feature, `FundamentalReportType` names
+    ///   them and the `fundamentals` module parses the reports.
     pub fn request_fundamental_data(
         &mut self,
         request_id: i32,
         contract: &Contract,
         report_type: &str,
         fundamental_data_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        let version = 2;
+        let version = OutgoingMessageIds::ReqFundamentalData.version();
 
-        if self.server_version() < MIN_SERVER_VER_FUNDAMENTAL_DATA {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support fundamental data request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqFundamentalData,
+            request_id,
+            "It does not support fundamental data request.",
+        )?;
 
         if self.server_version() < MIN_SERVER_VER_TRADING_CLASS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support con_id parameter in fundamental data request."
-                ),
+                TwsError::UpdateTws,
+                "It does not support con_id parameter in fundamental data request.",
             ));
 
             return Err(err);
         }
 
         let message_id: i32 = OutgoingMessageIds::ReqFundamentalData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
         // Send contract fields
         if self.server_version() >= MIN_SERVER_VER_TRADING_CLASS {
-            msg.push_str(&make_field(&contract.con_id)?);
+            write_field(&mut msg, &contract.con_id)?;
         }
-        msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
-        msg.push_str(&make_field(&contract.exchange)?);
-        msg.push_str(&make_field(&contract.primary_exchange)?);
-        msg.push_str(&make_field(&contract.currency)?);
-        msg.push_str(&make_field(&contract.local_symbol)?);
-        msg.push_str(&make_field(&String::from(report_type))?);
+        write_field(&mut msg, &contract.symbol)?;
+        write_field(&mut msg, &contract.sec_type.to_string())?;
+        write_field(&mut msg, &contract.exchange)?;
+        write_field(&mut msg, &contract.primary_exchange)?;
+        write_field(&mut msg, &contract.currency)?;
+        write_field(&mut msg, &contract.local_symbol)?;
+        write_field(&mut msg, &String::from(report_type))?;
 
         if self.server_version() >= MIN_SERVER_VER_LINKING {
             let tags_value_count = fundamental_data_options.len();
@@ -4001,11 +6119,11 @@ where
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
 
-            msg.push_str(&make_field(&tags_value_count)?);
-            msg.push_str(&make_field(&fund_data_opt_str)?);
+            write_field(&mut msg, &tags_value_count)?;
+            write_field(&mut msg, &fund_data_opt_str)?;
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4014,33 +6132,25 @@ where
     ///
     /// # Arguments
     /// * request_id - The ID of the data request
-    pub fn cancel_fundamental_data(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn cancel_fundamental_data(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_FUNDAMENTAL_DATA {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support fundamental data request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::CancelFundamentalData,
+            request_id,
+            "It does not support fundamental data request.",
+        )?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::CancelFundamentalData.version();
 
         let message_id: i32 = OutgoingMessageIds::CancelFundamentalData as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4050,28 +6160,20 @@ where
     /// Requests all open orders places by this specific API client (identified
     /// by the API client id). For client ID 0, this will bind previous
     /// manual TWS orders.
-    pub fn request_news_providers(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn request_news_providers(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_NEWS_PROVIDERS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support news providers request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqNewsProviders,
+            NO_VALID_ID,
+            "It does not support news providers request.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqNewsProviders as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4091,30 +6193,22 @@ where
         provider_code: &str,
         article_id: &str,
         news_article_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_NEWS_ARTICLE {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support news article request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqNewsArticle,
+            request_id,
+            "It does not support news article request.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqNewsArticle as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(provider_code))?);
-        msg.push_str(&make_field(&String::from(article_id))?);
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &String::from(provider_code))?;
+        write_field(&mut msg, &String::from(article_id))?;
 
         // Send news_article_options parameter
         if self.server_version() >= MIN_SERVER_VER_NEWS_QUERY_ORIGINS {
@@ -4122,10 +6216,10 @@ where
                 .iter()
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
-            msg.push_str(&make_field(&news_article_options_str)?);
+            write_field(&mut msg, &news_article_options_str)?;
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4153,33 +6247,25 @@ where
         end_date_time: &str,
         total_results: i32,
         historical_news_options: &[TagValue],
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_HISTORICAL_NEWS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support historical news request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqHistoricalNews,
+            request_id,
+            "It does not support historical news request.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqHistoricalNews as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&con_id)?);
-        msg.push_str(&make_field(&String::from(provider_codes))?);
-        msg.push_str(&make_field(&String::from(start_date_time))?);
-        msg.push_str(&make_field(&String::from(end_date_time))?);
-        msg.push_str(&make_field(&total_results)?);
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &con_id)?;
+        write_field(&mut msg, &String::from(provider_codes))?;
+        write_field(&mut msg, &String::from(start_date_time))?;
+        write_field(&mut msg, &String::from(end_date_time))?;
+        write_field(&mut msg, &total_results)?;
 
         // Send historical_news_options parameter
         if self.server_version() >= MIN_SERVER_VER_NEWS_QUERY_ORIGINS {
@@ -4187,53 +6273,105 @@ where
                 .iter()
                 .map(|x| format!("{}={};", x.tag, x.value))
                 .collect::<String>();
-            msg.push_str(&make_field(&historical_news_options_str)?);
+            write_field(&mut msg, &historical_news_options_str)?;
         }
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Same as request_historical_news() but takes its parameters from a
+    /// HistoricalNewsQuery. Use HistoricalNewsQuery::next_page() to request
+    /// the following page while Wrapper::historical_news_end reports more.
+    ///
+    /// # Arguments
+    ///
+    /// * request_id - id of the request
+    /// * query - the contract, providers, date range and number of headlines
+    pub fn request_historical_news_query(
+        &mut self,
+        request_id: i32,
+        query: &HistoricalNewsQuery,
+    ) -> Result<(), IbkrError> {
+        let start_date_time = query
+            .start_date_time
+            .map_or("".to_string(), |time| format_historical_news_time(&time));
+        let end_date_time = query
+            .end_date_time
+            .map_or("".to_string(), |time| format_historical_news_time(&time));
+
+        self.request_historical_news(
+            request_id,
+            query.con_id,
+            query.provider_codes_param().as_str(),
+            start_date_time.as_str(),
+            end_date_time.as_str(),
+            query.total_results,
+            &[],
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes to live news headlines which are delivered through
+    /// Wrapper::tick_news. For headlines about a single contract pass e.g.
+    /// a stock; for a provider's whole feed pass Contract::news(). Cancel with
+    /// cancel_market_data().
+    ///
+    /// # Arguments
+    ///
+    /// * ticker_id - the request's unique identifier
+    /// * contract - the contract to receive headlines for
+    /// * provider_codes - the news providers, e.g. `["BRFG", "DJNL"]`
+    pub fn request_news_ticks(
+        &mut self,
+        ticker_id: i32,
+        contract: &Contract,
+        provider_codes: &[&str],
+    ) -> Result<(), IbkrError> {
+        let generic_tick_list = if contract.sec_type == SecType::News {
+            "mdoff,292".to_string()
+        } else {
+            format!("mdoff,292:{}", provider_codes.join("+"))
+        };
+
+        self.request_market_data(
+            ticker_id,
+            contract,
+            generic_tick_list.as_str(),
+            false,
+            false,
+            &[],
+        )
+    }
+
     //#########################################################################
     //################## Display Groups
     //#########################################################################
-    /// Replaces Financial Advisor's settings A Financial Advisor can define
-    /// three different configurations: 1. Groups - offer traders a way to
-    /// create a group of accounts and apply a single allocation method to all
-    /// accounts in the group. 2. Profiles - let you allocate shares on an
-    /// account-by-account basis using a predefined calculation value.
-    /// 3. Account Aliases - let you easily identify the accounts by meaningful
-    /// names rather than account numbers.                     More information at <https://www.interactivebrokers.com/en/?f=%2Fen%2Fsoftware%2Fpdfhighlights%2FPDF-AdvisorAllocations.php%3Fib_entity%3Dllc>
+    /// Requests the display groups in TWS. The ids of the groups are returned
+    /// by the display_group_list() event.
     ///
     /// # Arguments
     /// * request_id - The unique number that will be associated with the
     ///   response
-    pub fn query_display_groups(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn query_display_groups(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support query_display_groups request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::QueryDisplayGroups,
+            request_id,
+            "It does not support query_display_groups request.",
+        )?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::QueryDisplayGroups.version();
 
         let message_id: i32 = OutgoingMessageIds::QueryDisplayGroups as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4248,34 +6386,26 @@ where
         &mut self,
         request_id: i32,
         group_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support subscribe_to_group_events request."
-                ),
-            ));
+        self.check_supported(
+            OutgoingMessageIds::SubscribeToGroupEvents,
+            request_id,
+            "It does not support subscribe_to_group_events request.",
+        )?;
 
-            return Err(err);
-        }
-
-        let version = 1;
+        let version = OutgoingMessageIds::SubscribeToGroupEvents.version();
 
         let message_id: i32 = OutgoingMessageIds::SubscribeToGroupEvents as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&group_id)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &group_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4284,44 +6414,34 @@ where
     ///
     /// # Arguments
     /// * request_id - The requestId specified in subscribe_to_group_events().
-    /// * contract_info - is an encoded value designating a unique IB contract.
-    ///   Possible values include: 1. none - empty selection 2. contract_id -
-    ///   any non-combination contract. Examples 8314 for IBM SMART; 8314 for
-    ///   IBM ARCA 3. combo - if any combo is selected Note: This request from
-    ///   the API does not get a TWS response unless an error occurs.
-
+    /// * contract_info - The contract to display: nothing, a single contract,
+    ///   e.g. DisplayGroupContract::new(8314, "SMART") for IBM SMART, or a
+    ///   combo. Note: This request from the API does not get a TWS response
+    ///   unless an error occurs.
     pub fn update_display_group(
         &mut self,
         request_id: i32,
-        contract_info: &str,
-    ) -> Result<(), IBKRApiLibError> {
+        contract_info: &DisplayGroupContract,
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support update_display_group request."
-                ),
-            ));
+        self.check_supported(
+            OutgoingMessageIds::UpdateDisplayGroup,
+            request_id,
+            "It does not support update_display_group request.",
+        )?;
 
-            return Err(err);
-        }
-
-        let version = 1;
+        let version = OutgoingMessageIds::UpdateDisplayGroup.version();
 
         let message_id: i32 = OutgoingMessageIds::UpdateDisplayGroup as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(contract_info))?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &contract_info.to_string())?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4330,121 +6450,86 @@ where
     ///
     /// # Arguments
     /// * request_id - The request Id specified in subscribe_to_group_events()
-    pub fn unsubscribe_from_group_events(
-        &mut self,
-        request_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    pub fn unsubscribe_from_group_events(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                request_id,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support unsubscribe_from_group_events request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::UnsubscribeFromGroupEvents,
+            request_id,
+            "It does not support unsubscribe_from_group_events request.",
+        )?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::UnsubscribeFromGroupEvents.version();
 
         let message_id: i32 = OutgoingMessageIds::UnsubscribeFromGroupEvents as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     /// For IB's internal purpose. Allows to provide means of verification
     /// between the TWS and third party programs.
-    pub fn verify_request(
-        &mut self,
-        api_name: &str,
-        api_version: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    pub fn verify_request(&mut self, api_name: &str, api_version: &str) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support verification request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::VerifyRequest,
+            NO_VALID_ID,
+            "It does not support verification request.",
+        )?;
 
         if !self.extra_auth {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 NO_VALID_ID,
-                TwsError::BadMessage.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::BadMessage.message(),
-                    " Intent to authenticate needs to be expressed during initial connect request."
-                ),
+                TwsError::BadMessage,
+                "Intent to authenticate needs to be expressed during initial connect request.",
             ));
 
             return Err(err);
         }
 
-        let version = 1;
+        let version = OutgoingMessageIds::VerifyRequest.version();
 
         let message_id: i32 = OutgoingMessageIds::VerifyRequest as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&String::from(api_name))?);
-        msg.push_str(&make_field(&String::from(api_version))?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &String::from(api_name))?;
+        write_field(&mut msg, &String::from(api_version))?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     /// For IB's internal purpose. Allows to provide means of verification
     /// between the TWS and third party programs.
-    pub fn verify_message(&mut self, api_data: &'static str) -> Result<(), IBKRApiLibError> {
+    pub fn verify_message(&mut self, api_data: &'static str) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support verification request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::VerifyMessage,
+            NO_VALID_ID,
+            "It does not support verification request.",
+        )?;
 
-        let version = 1;
+        let version = OutgoingMessageIds::VerifyMessage.version();
 
         let message_id: i32 = OutgoingMessageIds::VerifyMessage as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&api_data)?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &api_data)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4456,49 +6541,37 @@ where
         api_name: &str,
         api_version: &str,
         opaque_isv_key: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support verification request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::VerifyAndAuthRequest,
+            NO_VALID_ID,
+            "It does not support verification request.",
+        )?;
 
         if !self.extra_auth {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
+            let err = IbkrError::Connection(ConnectionError::new(
                 NO_VALID_ID,
-                TwsError::BadMessage.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::BadMessage.message(),
-                    " Intent to authenticate needs to be expressed during initial connect request."
-                ),
+                TwsError::BadMessage,
+                "Intent to authenticate needs to be expressed during initial connect request.",
             ));
 
             return Err(err);
         }
 
-        let version = 1;
+        let version = OutgoingMessageIds::VerifyAndAuthRequest.version();
 
         let message_id: i32 = OutgoingMessageIds::VerifyAndAuthRequest as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&String::from(api_name))?);
-        msg.push_str(&make_field(&String::from(api_version))?);
-        msg.push_str(&make_field(&String::from(opaque_isv_key))?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &String::from(api_name))?;
+        write_field(&mut msg, &String::from(api_version))?;
+        write_field(&mut msg, &String::from(opaque_isv_key))?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4509,34 +6582,26 @@ where
         &mut self,
         api_data: &str,
         xyz_response: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_LINKING {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support verification request."
-                ),
-            ));
+        self.check_supported(
+            OutgoingMessageIds::VerifyAndAuthMessage,
+            NO_VALID_ID,
+            "It does not support verification request.",
+        )?;
 
-            return Err(err);
-        }
-
-        let version = 1;
+        let version = OutgoingMessageIds::VerifyAndAuthMessage.version();
 
         let message_id: i32 = OutgoingMessageIds::VerifyAndAuthMessage as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&version)?);
-        msg.push_str(&make_field(&String::from(api_data))?);
-        msg.push_str(&make_field(&String::from(xyz_response))?);
+        write_field(&mut msg, &version)?;
+        write_field(&mut msg, &String::from(api_data))?;
+        write_field(&mut msg, &String::from(xyz_response))?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4558,37 +6623,94 @@ where
         fut_fop_exchange: &str,
         underlying_sec_type: &str,
         underlying_con_id: i32,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_SEC_DEF_OPT_PARAMS_REQ {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support security pub fninition option request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqSecDefOptParams,
+            NO_VALID_ID,
+            "It does not support security definition option request.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqSecDefOptParams as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(underlying_symbol))?);
-        msg.push_str(&make_field(&String::from(fut_fop_exchange))?);
-        msg.push_str(&make_field(&String::from(underlying_sec_type))?);
-        msg.push_str(&make_field(&underlying_con_id)?);
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &String::from(underlying_symbol))?;
+        write_field(&mut msg, &String::from(fut_fop_exchange))?;
+        write_field(&mut msg, &String::from(underlying_sec_type))?;
+        write_field(&mut msg, &underlying_con_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Requests the option chain of an underlying and blocks until the
+    /// parameters of all exchanges and trading classes have been received.
+    /// The parameters are not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * request_id - the ID chosen for the request
+    /// * underlying - the underlying with its con_id set, see
+    ///   resolve_contract()
+    /// * fut_fop_exchange - The exchange of the options on futures. Empty for
+    ///   options on stocks and indices.
+    /// * timeout - how long to wait for the parameters of each exchange
+    pub fn fetch_option_chain(
+        &mut self,
+        request_id: i32,
+        underlying: &Contract,
+        fut_fop_exchange: &str,
+        timeout: Duration,
+    ) -> Result<OptionChain, IbkrError> {
+        if underlying.con_id <= 0 {
+            return Err(IbkrError::rejected(
+                request_id,
+                "The underlying of an option chain needs a con_id.",
+            ));
+        }
+
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .option_chains
+            .add(request_id);
+
+        let mut result = self
+            .request_sec_def_opt_params(
+                request_id,
+                &underlying.symbol,
+                fut_fop_exchange,
+                &underlying.sec_type.to_string(),
+                underlying.con_id,
+            )
+            .map(|_| OptionChain::new(underlying.clone(), vec![]));
+        while let Ok(chain) = result.as_mut() {
+            match receiver.recv_timeout(timeout) {
+                Ok(OptionChainEvent::Parameters(parameters)) => chain.parameters.push(*parameters),
+                Ok(OptionChainEvent::End) => break,
+                Ok(OptionChainEvent::Error { code, message }) => {
+                    result = Err(IbkrError::Server(ServerError::new(
+                        request_id, code, &message,
+                    )));
+                }
+                Err(_) => {
+                    result = Err(IbkrError::timeout(request_id, "the option chain"));
+                }
+            }
+        }
+
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .option_chains
+            .remove(request_id);
+        result
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Requests pre-defined Soft Dollar Tiers. This is only supported for
     /// registered professional advisors and hedge and mutual funds who have
@@ -4596,44 +6718,76 @@ where
     ///
     /// # Arguments
     /// * request_id - the identifier for this request
-    pub fn request_soft_dollar_tiers(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    pub fn request_soft_dollar_tiers(&mut self, request_id: i32) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
+        self.check_supported(
+            OutgoingMessageIds::ReqSoftDollarTiers,
+            request_id,
+            "It does not support soft dollar tier requests.",
+        )?;
+
         let message_id: i32 = OutgoingMessageIds::ReqSoftDollarTiers as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&request_id)?);
+        write_field(&mut msg, &request_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Requests the Soft Dollar Tiers and blocks until they arrive. The tiers
+    /// are not passed to the Wrapper. Any of them can be set as the
+    /// soft_dollar_tier of an order.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    /// * timeout - how long to wait for the tiers
+    pub fn fetch_soft_dollar_tiers(
+        &mut self,
+        request_id: i32,
+        timeout: Duration,
+    ) -> Result<Vec<SoftDollarTier>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .soft_dollar_tiers
+            .add(request_id);
+
+        let result = self.request_soft_dollar_tiers(request_id).and_then(|_| {
+            receiver
+                .recv_timeout(timeout)
+                .map_err(|_| IbkrError::timeout(request_id, "the Soft Dollar Tiers"))
+        });
+
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .soft_dollar_tiers
+            .remove(request_id);
+        result
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Requests family codes for an account, for instance if it is a FA,
     /// IBroker, or associated account.
-    pub fn request_family_codes(&mut self) -> Result<(), IBKRApiLibError> {
+    pub fn request_family_codes(&mut self) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_FAMILY_CODES {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " It does not support family codes request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqFamilyCodes,
+            NO_VALID_ID,
+            "It does not support family codes request.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqFamilyCodes as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
@@ -4648,34 +6802,68 @@ where
         &mut self,
         request_id: i32,
         pattern: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
-        if self.server_version() < MIN_SERVER_VER_REQ_MATCHING_SYMBOLS {
-            let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                NO_VALID_ID,
-                TwsError::UpdateTws.code().to_string(),
-                format!(
-                    "{}{}",
-                    TwsError::UpdateTws.message(),
-                    " t does not support matching symbols request."
-                ),
-            ));
-
-            return Err(err);
-        }
+        self.check_supported(
+            OutgoingMessageIds::ReqMatchingSymbols,
+            NO_VALID_ID,
+            "It does not support matching symbols request.",
+        )?;
 
         let message_id: i32 = OutgoingMessageIds::ReqMatchingSymbols as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&request_id)?);
-        msg.push_str(&make_field(&String::from(pattern))?);
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &String::from(pattern))?;
 
-        self.send_request(msg.as_str())?;
+        self.send_message(msg)?;
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    /// Searches contracts by the start of their symbol or, for longer
+    /// patterns, by company name and blocks until the matches have been
+    /// received. The matches are not passed to the Wrapper. TWS returns at
+    /// most 16 matches and allows one search per second.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    /// * pattern - either start of ticker symbol or (for larger strings)
+    ///   company name
+    /// * timeout - how long to wait for the matches
+    pub fn search_symbols(
+        &mut self,
+        request_id: i32,
+        pattern: &str,
+        timeout: Duration,
+    ) -> Result<Vec<ContractDescription>, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .symbol_samples
+            .add(request_id);
+
+        let result =
+            self.request_matching_symbols(request_id, pattern)
+                .and_then(|_| match receiver.recv_timeout(timeout) {
+                    Ok(SymbolSamplesEvent::Descriptions(descriptions)) => Ok(descriptions),
+                    Ok(SymbolSamplesEvent::Error { code, message }) => Err(IbkrError::Server(
+                        ServerError::new(request_id, code, &message),
+                    )),
+                    Err(_) => Err(IbkrError::timeout(request_id, "matching symbols")),
+                });
+
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .symbol_samples
+            .remove(request_id);
+        result
+    }
+
     //----------------------------------------------------------------------------------------------
     /// Requests completed orders.
     ///
@@ -4683,32 +6871,291 @@ where
     /// * api_only - If api_only parameter is true, then only completed orders
     ///   placed from API are requested. Each completed order will be fed back
     ///   through the completed_order() function on the Wrapper
-    pub fn request_completed_orders(&mut self, api_only: bool) -> Result<(), IBKRApiLibError> {
+    pub fn request_completed_orders(&mut self, api_only: bool) -> Result<(), IbkrError> {
         self.check_connected(NO_VALID_ID)?;
 
+        self.check_supported(
+            OutgoingMessageIds::ReqCompletedOrders,
+            NO_VALID_ID,
+            "It does not support completed orders requests.",
+        )?;
+
         let message_id: i32 = OutgoingMessageIds::ReqCompletedOrders as i32;
-        let mut msg = "".to_string();
-        msg.push_str(&make_field(&message_id)?);
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &api_only)?;
+
+        self.send_message(msg)?;
+        Ok(())
+    }
+
+    //#########################################################################
+    //################## Wall Street Horizon
+    //#########################################################################
+    //----------------------------------------------------------------------------------------------
+    /// Requests the meta data of the Wall Street Horizon corporate event
+    /// calendar, i.e. the available event types and filters. The meta data
+    /// is returned as JSON by the wsh_meta_data() event. Requires a
+    /// subscription to WSH data.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    #[cfg(feature = "wsh")]
+    pub fn request_wsh_meta_data(&mut self, request_id: i32) -> Result<(), IbkrError> {
+        self.check_connected(request_id)?;
+
+        self.check_supported(
+            OutgoingMessageIds::ReqWshMetaData,
+            request_id,
+            "It does not support WSH meta data request.",
+        )?;
+
+        let message_id: i32 = OutgoingMessageIds::ReqWshMetaData as i32;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &request_id)?;
+
+        self.send_message(msg)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels a pending request for WSH meta data.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    #[cfg(feature = "wsh")]
+    pub fn cancel_wsh_meta_data(&mut self, request_id: i32) -> Result<(), IbkrError> {
+        self.check_connected(request_id)?;
+
+        self.check_supported(
+            OutgoingMessageIds::CancelWshMetaData,
+            request_id,
+            "It does not support WSH meta data cancellation.",
+        )?;
+
+        let message_id: i32 = OutgoingMessageIds::CancelWshMetaData as i32;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &request_id)?;
+
+        self.send_message(msg)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests Wall Street Horizon corporate events such as earnings dates
+    /// or dividends. The events are returned by the wsh_event_data() event.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    /// * wsh_event_data - the contract or filter selecting the events and
+    ///   the range of their dates
+    #[cfg(feature = "wsh")]
+    pub fn request_wsh_event_data(
+        &mut self,
+        request_id: i32,
+        wsh_event_data: &WshEventData,
+    ) -> Result<(), IbkrError> {
+        self.check_connected(request_id)?;
+
+        self.check_supported(
+            OutgoingMessageIds::ReqWshEventData,
+            request_id,
+            "It does not support WSH event data request.",
+        )?;
+
+        if wsh_event_data.uses_filters()
+            && self.server_version() < MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS
+        {
+            let err = IbkrError::Connection(ConnectionError::new(
+                request_id,
+                TwsError::UpdateTws,
+                "It does not support WSH event data filters.",
+            ));
+
+            return Err(err);
+        }
+
+        if wsh_event_data.uses_date_range()
+            && self.server_version() < MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS_DATE
+        {
+            let err = IbkrError::Connection(ConnectionError::new(
+                request_id,
+                TwsError::UpdateTws,
+                "It does not support WSH event data date filters.",
+            ));
+
+            return Err(err);
+        }
+
+        let message_id: i32 = OutgoingMessageIds::ReqWshEventData as i32;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &request_id)?;
+        write_field(&mut msg, &wsh_event_data.con_id.unwrap_or(UNSET_INTEGER))?;
+
+        if self.server_version() >= MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS {
+            write_field(&mut msg, &wsh_event_data.filter_param())?;
+            write_field(&mut msg, &wsh_event_data.fill_watchlist)?;
+            write_field(&mut msg, &wsh_event_data.fill_portfolio)?;
+            write_field(&mut msg, &wsh_event_data.fill_competitors)?;
+        }
+
+        if self.server_version() >= MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS_DATE {
+            write_field(&mut msg, &wsh_event_data.start_date_param())?;
+            write_field(&mut msg, &wsh_event_data.end_date_param())?;
+            write_field(
+                &mut msg,
+                &wsh_event_data.total_limit.unwrap_or(UNSET_INTEGER),
+            )?;
+        }
+
+        self.send_message(msg)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels a pending request for WSH event data.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    #[cfg(feature = "wsh")]
+    pub fn cancel_wsh_event_data(&mut self, request_id: i32) -> Result<(), IbkrError> {
+        self.check_connected(request_id)?;
+
+        self.check_supported(
+            OutgoingMessageIds::CancelWshEventData,
+            request_id,
+            "It does not support WSH event data cancellation.",
+        )?;
+
+        let message_id: i32 = OutgoingMessageIds::CancelWshEventData as i32;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
+
+        write_field(&mut msg, &request_id)?;
+
+        self.send_message(msg)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the user information of the login. It is returned by the
+    /// user_info() event.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    pub fn request_user_info(&mut self, request_id: i32) -> Result<(), IbkrError> {
+        self.check_connected(request_id)?;
+
+        self.check_supported(
+            OutgoingMessageIds::ReqUserInfo,
+            request_id,
+            "It does not support user info requests.",
+        )?;
+
+        let message_id: i32 = OutgoingMessageIds::ReqUserInfo as i32;
+        let mut msg = self.message_buffer();
+        write_field(&mut msg, &message_id)?;
 
-        msg.push_str(&make_field(&api_only)?);
+        write_field(&mut msg, &request_id)?;
+
+        self.send_message(msg)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the white branding id of the login and blocks until it
+    /// arrives. It is not passed to the Wrapper.
+    ///
+    /// # Arguments
+    /// * request_id - the identifier for this request
+    /// * timeout - how long to wait for the user information
+    pub fn fetch_white_branding_id(
+        &mut self,
+        request_id: i32,
+        timeout: Duration,
+    ) -> Result<String, IbkrError> {
+        let receiver = self
+            .streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .user_info
+            .add(request_id);
+
+        let result = self.request_user_info(request_id).and_then(|_| {
+            receiver
+                .recv_timeout(timeout)
+                .map_err(|_| IbkrError::timeout(request_id, "the user info"))
+        });
+
+        self.streams
+            .lock()
+            .expect(POISONED_MUTEX)
+            .user_info
+            .remove(request_id);
+        result
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn check_what_to_show(
+        &self,
+        request_id: i32,
+        what_to_show: &WhatToShow,
+        request: DataRequestType,
+    ) -> Result<(), IbkrError> {
+        if !what_to_show.is_valid_for(request) {
+            return Err(IbkrError::rejected(
+                request_id,
+                &format!("{} is not supported for {}.", what_to_show, request),
+            ));
+        }
 
-        self.send_request(msg.as_str())?;
+        if self.server_version() < what_to_show.min_server_version() {
+            return Err(IbkrError::Connection(ConnectionError::new(
+                request_id,
+                TwsError::UpdateTws,
+                &format!("It does not support {} for {}.", what_to_show, request),
+            )));
+        }
         Ok(())
     }
 
     //------------------------------------------------------------------------------------------------
     /// check if client is connected to TWS
-    fn check_connected(&mut self, request_id: i32) -> Result<(), IBKRApiLibError> {
+    fn check_connected(&mut self, request_id: i32) -> Result<(), IbkrError> {
         match self.is_connected() {
-            false => {
-                let err = IBKRApiLibError::ApiError(TwsApiReportableError::new(
-                    request_id,
-                    TwsError::NotConnected.code().to_string(),
-                    TwsError::NotConnected.message().to_string(),
-                ));
-                Err(err)
-            }
+            false => Err(not_connected(request_id)),
             true => Ok(()),
         }
     }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fails with `TwsError::UpdateTws` if the server is older than the first
+    /// version that knows the message
+    fn check_supported(
+        &self,
+        message: OutgoingMessageIds,
+        request_id: i32,
+        text: &str,
+    ) -> Result<(), IbkrError> {
+        if self.server_version() < message.min_server_version() {
+            return Err(IbkrError::Connection(ConnectionError::new(
+                request_id,
+                TwsError::UpdateTws,
+                text,
+            )));
+        }
+        Ok(())
+    }
+}
+
+//==================================================================================================
+fn not_connected(request_id: i32) -> IbkrError {
+    IbkrError::Connection(ConnectionError::new(request_id, TwsError::NotConnected, ""))
 }
@@ -2,11 +2,21 @@
 //! Common types
 use std::fmt::Display;
 use std::fmt::{self, Error, Formatter};
+use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use num_derive::FromPrimitive;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::core::error_codes::{
+    HISTORICAL_DATA_FARM_BROKEN, HISTORICAL_DATA_FARM_OK, MARKET_DATA_FARM_BROKEN,
+    MARKET_DATA_FARM_OK, SEC_DEF_FARM_BROKEN, SEC_DEF_FARM_OK,
+};
+use crate::core::server_versions::MIN_SERVER_VER_HISTORICAL_SCHEDULE;
+
 pub const NO_VALID_ID: i32 = -1;
 pub const MAX_MSG_LEN: i64 = 0xFFFFFF; //16Mb - 1byte
 
@@ -17,7 +27,8 @@ pub const UNSET_LONG: i64 = std::i64::MAX;
 //==================================================================================================
 /// Tick types
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, Debug, FromPrimitive, Copy, PartialEq)]
+#[derive(Clone, Debug, FromPrimitive, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TickType {
     BidSize = 0,
     Bid = 1,
@@ -109,6 +120,7 @@ pub enum TickType {
     AverageOptionVolume = 87,
     DelayedLastTimestamp = 88,
     ShortableShares = 89,
+    DelayedHalted = 90,
     ExchangeTradedFundNetAssetValueClose = 92,
     ExchangeTradedFundNetAssetValuePriorClose = 93,
     ExchangeTradedFundNetAssetValueBid = 94,
@@ -117,6 +129,11 @@ pub enum TickType {
     ExchangeTradedFundNetAssetValueFrozenLast = 97,
     ExchangeTradedFundNetAssetValueHigh = 98,
     ExchangeTradedFundNetAssetValueLow = 99,
+    SocialMarketAnalytics = 100,
+    EstimatedIpoMidpoint = 101,
+    FinalIpoLast = 102,
+    DelayedYieldBid = 103,
+    DelayedYieldAsk = 104,
     /*OptionVolume = 100,
     OptionOpenInterest = 101,
     OptionHistoricalVolatility = 104,
@@ -234,6 +251,7 @@ impl fmt::Display for TickType {
             TickType::AverageOptionVolume => write!(fmt, "avgOptVolume"),
             TickType::DelayedLastTimestamp => write!(fmt, "delayedLastTimestamp"),
             TickType::ShortableShares => write!(fmt, "shortableShares"),
+            TickType::DelayedHalted => write!(fmt, "delayedHalted"),
             TickType::ExchangeTradedFundNetAssetValueClose => write!(fmt, "EtfNavClose"),
             TickType::ExchangeTradedFundNetAssetValuePriorClose => write!(fmt, "EtfNavClose"),
             TickType::ExchangeTradedFundNetAssetValueBid => write!(fmt, "EtfNavBid"),
@@ -242,6 +260,11 @@ impl fmt::Display for TickType {
             TickType::ExchangeTradedFundNetAssetValueFrozenLast => write!(fmt, "EtfNavFrozenLast"),
             TickType::ExchangeTradedFundNetAssetValueHigh => write!(fmt, "EtfNavHigh"),
             TickType::ExchangeTradedFundNetAssetValueLow => write!(fmt, "EtfNavLow"),
+            TickType::SocialMarketAnalytics => write!(fmt, "socialMarketAnalytics"),
+            TickType::EstimatedIpoMidpoint => write!(fmt, "estimatedIPOMidpoint"),
+            TickType::FinalIpoLast => write!(fmt, "finalIPOLast"),
+            TickType::DelayedYieldBid => write!(fmt, "delayedYieldBid"),
+            TickType::DelayedYieldAsk => write!(fmt, "delayedYieldAsk"),
             /*TickType::OptionVolume => write!(fmt, "OptionVolume"),
             TickType::OptionOpenInterest  => write!(fmt, "OptionOpenInterest"),
             TickType::OptionHistoricalVolatility  => write!(fmt, "OptionHistoricalVolatility"),
@@ -270,7 +293,8 @@ impl fmt::Display for TickType {
 //==================================================================================================
 /// Financial advisor data types
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, Copy, FromPrimitive, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FaDataType {
     // #[default]
     NA = 0,
@@ -281,14 +305,20 @@ pub enum FaDataType {
 
 impl fmt::Display for FaDataType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Type: {}", self)
+        match self {
+            FaDataType::NA => write!(f, "N/A"),
+            FaDataType::GROUPS => write!(f, "GROUPS"),
+            FaDataType::PROFILES => write!(f, "PROFILES"),
+            FaDataType::ALIASES => write!(f, "ALIASES"),
+        }
     }
 }
 
 //==================================================================================================
 /// Tick by tick types
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TickByTickType {
     // #[default]
     None = 0,
@@ -322,16 +352,22 @@ impl fmt::Display for TickByTickType {
 ///         for TRADES).
 /// bar_count - running count of the bars received for this request
 /// average - average price of the bar
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// time - the parsed date, in the time zone of the date or, if it has none,
+///        the time zone the request was made with. Daily and longer bars
+///        are dated at midnight. None if the date could not be parsed.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BarData {
     pub date: String,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
-    pub volume: i64,
+    pub volume: f64,
     pub bar_count: i32,
     pub average: f64,
+    #[cfg_attr(feature = "serde", serde(default, with = "bar_time_serde"))]
+    pub time: Option<DateTime<Tz>>,
 }
 
 impl BarData {
@@ -341,7 +377,7 @@ impl BarData {
         high: f64,
         low: f64,
         close: f64,
-        volume: i64,
+        volume: f64,
         bar_count: i32,
         average: f64,
     ) -> Self {
@@ -354,6 +390,7 @@ impl BarData {
             volume,
             bar_count,
             average,
+            time: None,
         }
     }
 }
@@ -366,8 +403,32 @@ impl fmt::Display for BarData {
 }
 
 //==================================================================================================
-/// date_time - the bar's date and time (either as a yyyymmss hh:mm:ssformatted
-///        string or as system time according to the request)
+/// Stores the time of a bar as seconds since the epoch and the name of its
+/// time zone, as chrono can not deserialize a `DateTime<Tz>`
+#[cfg(feature = "serde")]
+mod bar_time_serde {
+    use chrono::{DateTime, TimeZone};
+    use chrono_tz::Tz;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<DateTime<Tz>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        time.map(|time| (time.timestamp(), time.timezone()))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Tz>>, D::Error> {
+        Ok(Option::<(i64, Tz)>::deserialize(deserializer)?
+            .and_then(|(seconds, time_zone)| time_zone.timestamp_opt(seconds, 0).single()))
+    }
+}
+
+//==================================================================================================
+/// date_time - the start of the bar
 /// open  - the bar's open point
 /// high  - the bar's high point
 /// low   - the bar's low point
@@ -377,26 +438,27 @@ impl fmt::Display for BarData {
 ///         for TRADES).
 /// wap -   the bar's Weighted Average Price
 /// count - running count of the bars for this request
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RealTimeBar {
-    pub date_time: String,
+    pub date_time: DateTime<Utc>,
     pub open: f64,
     pub high: f64,
     pub low: f64,
     pub close: f64,
-    pub volume: i64,
+    pub volume: f64,
     pub wap: f64,
     pub count: i32,
 }
 
 impl RealTimeBar {
     pub fn new(
-        date_time: String,
+        date_time: DateTime<Utc>,
         open: f64,
         high: f64,
         low: f64,
         close: f64,
-        volume: i64,
+        volume: f64,
         wap: f64,
         count: i32,
     ) -> Self {
@@ -431,14 +493,15 @@ impl fmt::Display for RealTimeBar {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HistogramData {
     pub price: f64,
-    pub count: i32,
+    pub count: f64,
 }
 
 impl HistogramData {
-    pub fn new(price: f64, count: i32) -> Self {
+    pub fn new(price: f64, count: f64) -> Self {
         HistogramData { price, count }
     }
 }
@@ -450,7 +513,8 @@ impl fmt::Display for HistogramData {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DepthMktDataDescription {
     pub exchange: String,
     pub sec_type: String,
@@ -496,7 +560,8 @@ impl fmt::Display for DepthMktDataDescription {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SmartComponent {
     pub bit_number: i32,
     pub exchange: String,
@@ -524,7 +589,8 @@ impl fmt::Display for SmartComponent {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TickAttrib {
     pub can_auto_execute: bool,
     pub past_limit: bool,
@@ -552,7 +618,8 @@ impl fmt::Display for TickAttrib {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TickAttribBidAsk {
     pub bid_past_low: bool,
     pub ask_past_high: bool,
@@ -578,7 +645,8 @@ impl fmt::Display for TickAttribBidAsk {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TickAttribLast {
     pub past_limit: bool,
     pub unreported: bool,
@@ -604,7 +672,8 @@ impl fmt::Display for TickAttribLast {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FamilyCode {
     pub account_id: String,
     pub family_code_str: String,
@@ -630,7 +699,8 @@ impl fmt::Display for FamilyCode {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PriceIncrement {
     pub low_edge: f64,
     pub increment: f64,
@@ -656,15 +726,16 @@ impl fmt::Display for PriceIncrement {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HistoricalTick {
     pub time: i32,
     pub price: f64,
-    pub size: i32,
+    pub size: f64,
 }
 
 impl HistoricalTick {
-    pub fn new(time: i32, price: f64, size: i32) -> Self {
+    pub fn new(time: i32, price: f64, size: f64) -> Self {
         HistoricalTick { time, price, size }
     }
 }
@@ -680,14 +751,15 @@ impl fmt::Display for HistoricalTick {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HistoricalTickBidAsk {
     pub time: i32,
     pub tick_attrib_bid_ask: TickAttribBidAsk,
     pub price_bid: f64,
     pub price_ask: f64,
-    pub size_bid: i32,
-    pub size_ask: i32,
+    pub size_bid: f64,
+    pub size_ask: f64,
 }
 
 impl HistoricalTickBidAsk {
@@ -696,8 +768,8 @@ impl HistoricalTickBidAsk {
         tick_attrib_bid_ask: TickAttribBidAsk,
         price_bid: f64,
         price_ask: f64,
-        size_bid: i32,
-        size_ask: i32,
+        size_bid: f64,
+        size_ask: f64,
     ) -> Self {
         HistoricalTickBidAsk {
             time,
@@ -726,12 +798,13 @@ impl fmt::Display for HistoricalTickBidAsk {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HistoricalTickLast {
     pub time: i32,
     pub tick_attrib_last: TickAttribLast,
     pub price: f64,
-    pub size: i32,
+    pub size: f64,
     pub exchange: String,
     pub special_conditions: String,
 }
@@ -741,7 +814,7 @@ impl HistoricalTickLast {
         time: i32,
         tick_attrib_last: TickAttribLast,
         price: f64,
-        size: i32,
+        size: f64,
         exchange: String,
         special_conditions: String,
     ) -> Self {
@@ -769,7 +842,8 @@ impl fmt::Display for HistoricalTickLast {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CommissionReport {
     pub exec_id: String,
     pub commission: f64,
@@ -812,7 +886,8 @@ impl fmt::Display for CommissionReport {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct NewsProvider {
     pub code: String,
     pub name: String,
@@ -831,7 +906,8 @@ impl fmt::Display for NewsProvider {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TagValue {
     pub tag: String,
     pub value: String,
@@ -851,7 +927,8 @@ impl fmt::Display for TagValue {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ComboParam {
     // #[default]
     NonGuaranteed,
@@ -885,7 +962,8 @@ impl Display for ComboParam {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HedgeType {
     // #[default]
     None,
@@ -909,7 +987,8 @@ impl Display for HedgeType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Right {
     // #[default]
     None,
@@ -929,7 +1008,8 @@ impl Display for Right {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum VolatilityType {
     // #[default]
     None,
@@ -949,7 +1029,8 @@ impl Display for VolatilityType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ReferencePriceType {
     // #[default]
     None,
@@ -969,7 +1050,8 @@ impl Display for ReferencePriceType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TriggerMethod {
     // #[default]
     Default,
@@ -997,7 +1079,8 @@ impl Display for TriggerMethod {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Action {
     // #[default]
     Buy,
@@ -1017,7 +1100,8 @@ impl Display for Action {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Rule80A {
     // #[default]
     None,
@@ -1051,7 +1135,8 @@ impl Display for Rule80A {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OcaType {
     // #[default]
     None,
@@ -1073,7 +1158,8 @@ impl Display for OcaType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TimeInForce {
     // #[default]
     DAY,
@@ -1107,7 +1193,8 @@ impl Display for TimeInForce {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ExerciseType {
     // #[default]
     None,
@@ -1127,7 +1214,8 @@ impl Display for ExerciseType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum FundamentalType {
     // #[default]
     ReportSnapshot,
@@ -1155,7 +1243,8 @@ impl Display for FundamentalType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WhatToShow {
     // #[default]
     Trades,
@@ -1171,6 +1260,9 @@ pub enum WhatToShow {
     YieldBidAsk,
     YieldLast,
     AdjustedLast,
+    /// The trading sessions instead of bars, see
+    /// `EClient::request_historical_schedule`
+    Schedule,
 }
 
 impl Display for WhatToShow {
@@ -1184,17 +1276,103 @@ impl Display for WhatToShow {
             WhatToShow::HistoricalVolatility => write!(f, "HISTORICAL_VOLATILITY"),
             WhatToShow::OptionImpliedVolatility => write!(f, "OPTION_IMPLIED_VOLATILITY"),
             WhatToShow::YieldAsk => write!(f, "YIELD_ASK"),
-            WhatToShow::YieldBid => write!(f, "YIELD_ASK"),
+            WhatToShow::YieldBid => write!(f, "YIELD_BID"),
             WhatToShow::YieldBidAsk => write!(f, "YIELD_BID_ASK"),
             WhatToShow::YieldLast => write!(f, "YIELD_LAST"),
             WhatToShow::AdjustedLast => write!(f, "ADJUSTED_LAST"),
+            WhatToShow::Schedule => write!(f, "SCHEDULE"),
+        }
+    }
+}
+
+impl WhatToShow {
+    /// Whether the value can be used with a kind of request
+    pub fn is_valid_for(&self, request: DataRequestType) -> bool {
+        match request {
+            DataRequestType::HistoricalData => true,
+            DataRequestType::HeadTimestamp => *self != WhatToShow::Schedule,
+            DataRequestType::HistoricalTicks => self.is_valid_for_historical_ticks(),
+            DataRequestType::RealTimeBars => self.is_valid_for_real_time_bars(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the value can be used with `EClient::request_real_time_bars`
+    pub fn is_valid_for_real_time_bars(&self) -> bool {
+        matches!(
+            self,
+            WhatToShow::Trades | WhatToShow::Midpoint | WhatToShow::Bid | WhatToShow::Ask
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the value can be used with `EClient::request_historical_ticks`
+    pub fn is_valid_for_historical_ticks(&self) -> bool {
+        matches!(
+            self,
+            WhatToShow::Trades | WhatToShow::Midpoint | WhatToShow::BidAsk
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The oldest server version that supports the value
+    pub fn min_server_version(&self) -> i32 {
+        match self {
+            WhatToShow::Schedule => MIN_SERVER_VER_HISTORICAL_SCHEDULE,
+            _ => 0,
+        }
+    }
+}
+
+impl FromStr for WhatToShow {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_uppercase().as_str() {
+            "TRADES" => Ok(WhatToShow::Trades),
+            "MIDPOINT" => Ok(WhatToShow::Midpoint),
+            "BID" => Ok(WhatToShow::Bid),
+            "ASK" => Ok(WhatToShow::Ask),
+            "BID_ASK" => Ok(WhatToShow::BidAsk),
+            "HISTORICAL_VOLATILITY" => Ok(WhatToShow::HistoricalVolatility),
+            "OPTION_IMPLIED_VOLATILITY" => Ok(WhatToShow::OptionImpliedVolatility),
+            "YIELD_ASK" => Ok(WhatToShow::YieldAsk),
+            "YIELD_BID" => Ok(WhatToShow::YieldBid),
+            "YIELD_BID_ASK" => Ok(WhatToShow::YieldBidAsk),
+            "YIELD_LAST" => Ok(WhatToShow::YieldLast),
+            "ADJUSTED_LAST" => Ok(WhatToShow::AdjustedLast),
+            "SCHEDULE" => Ok(WhatToShow::Schedule),
+            _ => Err(format!("Unknown what to show value: {}", value)),
+        }
+    }
+}
+
+//==================================================================================================
+/// The requests that take a `WhatToShow` value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DataRequestType {
+    HistoricalData,
+    HeadTimestamp,
+    HistoricalTicks,
+    RealTimeBars,
+}
+
+impl Display for DataRequestType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match *self {
+            DataRequestType::HistoricalData => write!(f, "historical data"),
+            DataRequestType::HeadTimestamp => write!(f, "head time stamps"),
+            DataRequestType::HistoricalTicks => write!(f, "historical ticks"),
+            DataRequestType::RealTimeBars => write!(f, "real time bars"),
         }
     }
 }
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum BarSize {
     // #[default]
     _1Secs,
@@ -1242,9 +1420,174 @@ impl Display for BarSize {
     }
 }
 
+impl BarSize {
+    /// The length of a bar. Months are counted as 30 days.
+    pub fn seconds(&self) -> i64 {
+        match *self {
+            BarSize::_1Secs => 1,
+            BarSize::_5Secs => 5,
+            BarSize::_10Secs => 10,
+            BarSize::_15Secs => 15,
+            BarSize::_30Secs => 30,
+            BarSize::_1Min => 60,
+            BarSize::_2Mins => 120,
+            BarSize::_3Mins => 180,
+            BarSize::_5Mins => 300,
+            BarSize::_10Mins => 600,
+            BarSize::_15Mins => 900,
+            BarSize::_20Mins => 1200,
+            BarSize::_30Mins => 1800,
+            BarSize::_1Hour => 3600,
+            BarSize::_4Hours => 14400,
+            BarSize::_1Day => 86400,
+            BarSize::_1Week => 7 * 86400,
+            BarSize::_1Month => 30 * 86400,
+        }
+    }
+}
+
+impl FromStr for BarSize {
+    type Err = String;
+
+    /// Parses the bar size settings of TWS, e.g. "1 min" or "5 mins"
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value.split_whitespace().collect::<Vec<&str>>().join(" ");
+        match normalized.to_lowercase().as_str() {
+            "1 sec" | "1 secs" => Ok(BarSize::_1Secs),
+            "5 secs" => Ok(BarSize::_5Secs),
+            "10 secs" => Ok(BarSize::_10Secs),
+            "15 secs" => Ok(BarSize::_15Secs),
+            "30 secs" => Ok(BarSize::_30Secs),
+            "1 min" | "1 mins" => Ok(BarSize::_1Min),
+            "2 mins" => Ok(BarSize::_2Mins),
+            "3 mins" => Ok(BarSize::_3Mins),
+            "5 mins" => Ok(BarSize::_5Mins),
+            "10 mins" => Ok(BarSize::_10Mins),
+            "15 mins" => Ok(BarSize::_15Mins),
+            "20 mins" => Ok(BarSize::_20Mins),
+            "30 mins" => Ok(BarSize::_30Mins),
+            "1 hour" | "1 hours" => Ok(BarSize::_1Hour),
+            "4 hours" => Ok(BarSize::_4Hours),
+            "1 day" | "1 days" => Ok(BarSize::_1Day),
+            "1 week" | "1 weeks" | "1w" => Ok(BarSize::_1Week),
+            "1 month" | "1 months" | "1m" => Ok(BarSize::_1Month),
+            _ => Err(format!("Unknown bar size: {}", value)),
+        }
+    }
+}
+
+//==================================================================================================
+/// The length of the range of a historical data request, sent to TWS as
+/// e.g. "60 S", "5 D" or "1 Y"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HistoricalDuration {
+    Seconds(u32),
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+    Years(u32),
+}
+
+impl HistoricalDuration {
+    /// The longest the range can be. Months are counted as 31 days, years as
+    /// 365 days.
+    pub fn seconds(&self) -> i64 {
+        match *self {
+            HistoricalDuration::Seconds(n) => n as i64,
+            HistoricalDuration::Days(n) => n as i64 * 86400,
+            HistoricalDuration::Weeks(n) => n as i64 * 7 * 86400,
+            HistoricalDuration::Months(n) => n as i64 * 31 * 86400,
+            HistoricalDuration::Years(n) => n as i64 * 365 * 86400,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The smallest bar size TWS returns for the duration. Second bars are
+    /// limited to a few hours, minute bars to a month and bars shorter than
+    /// a day to a year.
+    pub fn min_bar_size(&self) -> BarSize {
+        match self.seconds() {
+            seconds if seconds <= 1800 => BarSize::_1Secs,
+            seconds if seconds <= 3600 => BarSize::_5Secs,
+            seconds if seconds <= 14400 => BarSize::_10Secs,
+            seconds if seconds <= 28800 => BarSize::_30Secs,
+            seconds if seconds <= 31 * 86400 => BarSize::_1Min,
+            seconds if seconds <= 365 * 86400 => BarSize::_30Mins,
+            _ => BarSize::_1Day,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether TWS returns bars of the size for the duration. Bars must be
+    /// at least `min_bar_size` and not longer than the duration.
+    pub fn is_compatible(&self, bar_size: &BarSize) -> bool {
+        bar_size.seconds() >= self.min_bar_size().seconds() && bar_size.seconds() <= self.seconds()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Like `is_compatible`, but describes why the bar size can not be used
+    pub fn check_bar_size(&self, bar_size: &BarSize) -> Result<(), String> {
+        if bar_size.seconds() < self.min_bar_size().seconds() {
+            Err(format!(
+                "Bars of {} are too small for a duration of {}, the minimum is {}.",
+                bar_size,
+                self,
+                self.min_bar_size()
+            ))
+        } else if bar_size.seconds() > self.seconds() {
+            Err(format!(
+                "Bars of {} are longer than the duration of {}.",
+                bar_size, self
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Display for HistoricalDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match *self {
+            HistoricalDuration::Seconds(n) => write!(f, "{} S", n),
+            HistoricalDuration::Days(n) => write!(f, "{} D", n),
+            HistoricalDuration::Weeks(n) => write!(f, "{} W", n),
+            HistoricalDuration::Months(n) => write!(f, "{} M", n),
+            HistoricalDuration::Years(n) => write!(f, "{} Y", n),
+        }
+    }
+}
+
+impl FromStr for HistoricalDuration {
+    type Err = String;
+
+    /// Parses the duration strings of TWS, e.g. "3600 S" or "1 Y"
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid duration: {}", value);
+        let mut parts = value.split_whitespace();
+        let count: u32 = parts
+            .next()
+            .and_then(|count| count.parse().ok())
+            .ok_or_else(error)?;
+        let duration = match parts.next().map(|unit| unit.to_uppercase()).as_deref() {
+            Some("S") => HistoricalDuration::Seconds(count),
+            Some("D") => HistoricalDuration::Days(count),
+            Some("W") => HistoricalDuration::Weeks(count),
+            Some("M") => HistoricalDuration::Months(count),
+            Some("Y") => HistoricalDuration::Years(count),
+            _ => return Err(error()),
+        };
+        if count == 0 || parts.next().is_some() {
+            return Err(error());
+        }
+        Ok(duration)
+    }
+}
+
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DurationUnit {
     // #[default]
     SECOND,
@@ -1268,7 +1611,8 @@ impl Display for DurationUnit {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DeepType {
     // #[default]
     Insert,
@@ -1287,7 +1631,8 @@ impl Display for DeepType {
 }
 
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum DeepSide {
     // #[default]
     Sell,
@@ -1305,7 +1650,8 @@ impl Display for DeepSide {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum NewsType {
     // #[default]
     Unknown,
@@ -1333,7 +1679,8 @@ impl Display for NewsType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SecurityIdType {
     // #[default]
     None,
@@ -1357,7 +1704,8 @@ impl Display for SecurityIdType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SecurityType {
     // #[default]
     None,
@@ -1417,7 +1765,8 @@ impl Display for SecurityType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum MarketDataTypeEnum {
     // #[default]
     Unknown,
@@ -1441,7 +1790,8 @@ impl Display for MarketDataTypeEnum {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Method {
     // #[default]
     None,
@@ -1465,7 +1815,8 @@ impl Display for Method {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Debug)]
+#[derive(Clone, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UsePriceManagementAlgorithm {
     // #[default]
     Default,
@@ -1482,3 +1833,81 @@ impl UsePriceManagementAlgorithm {
         }
     }
 }
+
+//==================================================================================================
+/// The kind of data farm a `DataFarmStatus` refers to
+#[repr(i32)]
+#[derive(Clone, Copy, FromPrimitive, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DataFarmKind {
+    // #[default]
+    MarketData,
+    Hmds,
+    SecDef,
+}
+
+impl Display for DataFarmKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            DataFarmKind::MarketData => write!(f, "Market data"),
+            DataFarmKind::Hmds => write!(f, "HMDS"),
+            DataFarmKind::SecDef => write!(f, "Sec-def"),
+        }
+    }
+}
+
+//==================================================================================================
+/// farm_name - the name of the farm, e.g. "usfarm" or "ushmds"
+/// kind - whether this is a market data, historical data (HMDS) or security
+///        definition farm
+/// connected - whether the connection to the farm is OK or broken
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DataFarmStatus {
+    pub farm_name: String,
+    pub kind: DataFarmKind,
+    pub connected: bool,
+}
+
+impl DataFarmStatus {
+    pub fn new(farm_name: String, kind: DataFarmKind, connected: bool) -> Self {
+        DataFarmStatus {
+            farm_name,
+            kind,
+            connected,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Builds the status from one of the data farm connectivity messages
+    /// (error codes 2103, 2104, 2105, 2106, 2157 and 2158). Returns `None` for
+    /// any other error code.
+    pub fn from_error(error_code: i32, error_string: &str) -> Option<Self> {
+        let (kind, connected) = match error_code {
+            MARKET_DATA_FARM_BROKEN => (DataFarmKind::MarketData, false),
+            MARKET_DATA_FARM_OK => (DataFarmKind::MarketData, true),
+            HISTORICAL_DATA_FARM_BROKEN => (DataFarmKind::Hmds, false),
+            HISTORICAL_DATA_FARM_OK => (DataFarmKind::Hmds, true),
+            SEC_DEF_FARM_BROKEN => (DataFarmKind::SecDef, false),
+            SEC_DEF_FARM_OK => (DataFarmKind::SecDef, true),
+            _ => return None,
+        };
+        // The farm name follows the last colon, e.g.
+        // "Market data farm connection is OK:usfarm"
+        let farm_name = match error_string.rfind(':') {
+            Some(position) => error_string[position + 1..].trim(),
+            None => "",
+        };
+        Some(DataFarmStatus::new(farm_name.to_string(), kind, connected))
+    }
+}
+
+impl fmt::Display for DataFarmStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "farm_name: {}, kind: {}, connected: {}",
+            self.farm_name, self.kind, self.connected
+        )
+    }
+}
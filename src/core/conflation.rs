@@ -0,0 +1,152 @@
+//! Conflation of bursts of market data ticks into their most recent values
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::core::common::{TickAttrib, TickType};
+
+//==================================================================================================
+/// A price or size tick that passes through the `TickConflator`
+#[derive(Clone, Debug)]
+pub enum ConflatedTick {
+    Price {
+        request_id: i32,
+        tick_type: TickType,
+        price: f64,
+        attrib: TickAttrib,
+    },
+    Size {
+        request_id: i32,
+        tick_type: TickType,
+        size: f64,
+    },
+}
+
+impl ConflatedTick {
+    pub fn request_id(&self) -> i32 {
+        match self {
+            ConflatedTick::Price { request_id, .. } => *request_id,
+            ConflatedTick::Size { request_id, .. } => *request_id,
+        }
+    }
+
+    pub fn tick_type(&self) -> TickType {
+        match self {
+            ConflatedTick::Price { tick_type, .. } => *tick_type,
+            ConflatedTick::Size { tick_type, .. } => *tick_type,
+        }
+    }
+}
+
+//==================================================================================================
+#[derive(Debug)]
+struct ConflatedSubscription {
+    interval: Duration,
+    last_delivery: Option<Instant>,
+    // Keyed by tick type so only the latest value of each type is kept
+    pending: BTreeMap<i32, ConflatedTick>,
+}
+
+impl ConflatedSubscription {
+    fn is_due(&self, now: Instant) -> bool {
+        match self.last_delivery {
+            Some(last_delivery) => now.duration_since(last_delivery) >= self.interval,
+            None => true,
+        }
+    }
+
+    fn take_pending(&mut self, now: Instant) -> Vec<ConflatedTick> {
+        self.last_delivery = Some(now);
+        std::mem::take(&mut self.pending).into_values().collect()
+    }
+}
+
+//==================================================================================================
+/// Coalesces the `tick_price` and `tick_size` events of market data
+/// subscriptions so that each tick type is delivered at most once per
+/// configured interval, always with its most recent value.
+///
+/// The first tick after a quiet period is delivered immediately. Ticks
+/// arriving within the interval replace each other and are delivered when
+/// the interval has elapsed. Subscriptions without an interval are passed
+/// through untouched.
+#[derive(Debug, Default)]
+pub struct TickConflator {
+    subscriptions: HashMap<i32, ConflatedSubscription>,
+}
+
+impl TickConflator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the minimum interval between two deliveries for a subscription.
+    /// `None` turns conflation off and drops any ticks still held back.
+    pub fn set_interval(&mut self, request_id: i32, interval: Option<Duration>) {
+        match interval {
+            Some(interval) => {
+                self.subscriptions
+                    .entry(request_id)
+                    .and_modify(|subscription| subscription.interval = interval)
+                    .or_insert(ConflatedSubscription {
+                        interval,
+                        last_delivery: None,
+                        pending: BTreeMap::new(),
+                    });
+            }
+            None => {
+                self.subscriptions.remove(&request_id);
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_conflated(&self, request_id: i32) -> bool {
+        self.subscriptions.contains_key(&request_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Hands a tick to the conflator and returns the ticks that should be
+    /// delivered right now.
+    pub fn offer(&mut self, tick: ConflatedTick, now: Instant) -> Vec<ConflatedTick> {
+        match self.subscriptions.get_mut(&tick.request_id()) {
+            Some(subscription) => {
+                subscription.pending.insert(tick.tick_type() as i32, tick);
+                if subscription.is_due(now) {
+                    subscription.take_pending(now)
+                } else {
+                    vec![]
+                }
+            }
+            None => vec![tick],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the held back ticks of all subscriptions whose interval has
+    /// elapsed.
+    pub fn drain_due(&mut self, now: Instant) -> Vec<ConflatedTick> {
+        let mut ticks = vec![];
+        for subscription in self.subscriptions.values_mut() {
+            if !subscription.pending.is_empty() && subscription.is_due(now) {
+                ticks.extend(subscription.take_pending(now));
+            }
+        }
+        ticks
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Time until the next held back tick becomes due, if there is any.
+    pub fn next_due(&self, now: Instant) -> Option<Duration> {
+        self.subscriptions
+            .values()
+            .filter(|subscription| !subscription.pending.is_empty())
+            .map(|subscription| match subscription.last_delivery {
+                Some(last_delivery) => {
+                    (last_delivery + subscription.interval).saturating_duration_since(now)
+                }
+                None => Duration::from_secs(0),
+            })
+            .min()
+    }
+}
@@ -0,0 +1,468 @@
+//! Continuous futures series stitched together from the bars of consecutive
+//! expiries
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::{BarData, BarSize, WhatToShow};
+use crate::core::contract::{Contract, ContractDetails, SecType};
+use crate::core::errors::IbkrError;
+use crate::core::historical_downloader::{bar_time, DownloadProgress, HistoricalDownloader};
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// When a continuous series switches from one expiry to the next
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RollRule {
+    /// Roll a fixed number of days before the front contract expires
+    Calendar { days_before_expiry: i64 },
+    /// Roll on the day after the next contract traded more volume than the
+    /// front contract
+    Volume,
+    /// Roll on the day after the next contract had a higher open interest
+    /// than the front contract. Historical bars carry no open interest, so
+    /// it has to be supplied with the segments; segments without it roll
+    /// by volume.
+    OpenInterest,
+}
+
+impl fmt::Display for RollRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollRule::Calendar { days_before_expiry } => {
+                write!(f, "Calendar({} days before expiry)", days_before_expiry)
+            }
+            RollRule::Volume => write!(f, "Volume"),
+            RollRule::OpenInterest => write!(f, "OpenInterest"),
+        }
+    }
+}
+
+//==================================================================================================
+/// How the prices before a roll are adjusted to remove the gap between the
+/// expiries
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BackAdjustment {
+    /// Keep the traded prices
+    None,
+    /// Add the price difference at each roll to all earlier bars
+    Difference,
+    /// Multiply all earlier bars by the price ratio at each roll
+    Ratio,
+}
+
+impl fmt::Display for BackAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackAdjustment::None => write!(f, "None"),
+            BackAdjustment::Difference => write!(f, "Difference"),
+            BackAdjustment::Ratio => write!(f, "Ratio"),
+        }
+    }
+}
+
+//==================================================================================================
+/// The bars of one expiry. The bar dates must be seconds since the epoch
+/// or, for daily bars, `YYYYMMDD`.
+///
+/// open_interest - open interest by day, only used by `RollRule::OpenInterest`
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FuturesSegment {
+    pub contract: Contract,
+    pub expiry: NaiveDate,
+    pub bars: Vec<BarData>,
+    pub open_interest: BTreeMap<NaiveDate, f64>,
+}
+
+impl FuturesSegment {
+    pub fn new(contract: Contract, expiry: NaiveDate, bars: Vec<BarData>) -> Self {
+        FuturesSegment {
+            contract,
+            expiry,
+            bars,
+            open_interest: BTreeMap::new(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Creates a segment for the contract of `details`. Returns `None` if
+    /// the expiry can not be parsed.
+    pub fn from_contract_details(details: &ContractDetails, bars: Vec<BarData>) -> Option<Self> {
+        let expiry = parse_expiry(&details.real_expiration_date)
+            .or_else(|| parse_expiry(&details.contract.last_trade_date_or_contract_month))?;
+        Some(FuturesSegment::new(details.contract.clone(), expiry, bars))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn daily_volume(&self) -> BTreeMap<NaiveDate, f64> {
+        let mut volume = BTreeMap::new();
+        for bar in &self.bars {
+            if let Some(time) = bar_time(bar) {
+                *volume.entry(time.date_naive()).or_insert(0.0) += bar.volume;
+            }
+        }
+        volume
+    }
+
+    //----------------------------------------------------------------------------------------------
+    // The last bar before `time`
+    fn bar_before(&self, time: &DateTime<Utc>) -> Option<(DateTime<Utc>, &BarData)> {
+        self.bars
+            .iter()
+            .filter_map(|bar| bar_time(bar).map(|bar_time| (bar_time, bar)))
+            .filter(|(bar_time, _)| bar_time < time)
+            .max_by_key(|(bar_time, _)| *bar_time)
+    }
+}
+
+//==================================================================================================
+/// A switch of a continuous series to the next expiry
+///
+/// time - the time of the first bar of the next expiry
+/// adjustment - the difference or ratio applied to the earlier bars; 0 or 1
+///              respectively if the prices could not be compared
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RollEvent {
+    pub time: DateTime<Utc>,
+    pub from: Contract,
+    pub to: Contract,
+    pub adjustment: f64,
+}
+
+impl fmt::Display for RollEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time: {}, from: {}, to: {}, adjustment: {}",
+            self.time, self.from.local_symbol, self.to.local_symbol, self.adjustment
+        )
+    }
+}
+
+//==================================================================================================
+/// The stitched bars and the rolls between the expiries
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContinuousSeries {
+    pub bars: Vec<BarData>,
+    pub rolls: Vec<RollEvent>,
+}
+
+//==================================================================================================
+/// Stitches the segments of consecutive expiries into one continuous series
+pub fn stitch(
+    mut segments: Vec<FuturesSegment>,
+    roll_rule: &RollRule,
+    adjustment: &BackAdjustment,
+) -> ContinuousSeries {
+    segments.sort_by_key(|segment| segment.expiry);
+
+    let mut rolls: Vec<RollEvent> = vec![];
+    for pair in segments.windows(2) {
+        let (front, next) = (&pair[0], &pair[1]);
+        let mut time = roll_time(front, next, roll_rule);
+        if let Some(previous) = rolls.last() {
+            time = time.max(previous.time);
+        }
+        rolls.push(RollEvent {
+            time,
+            from: front.contract.clone(),
+            to: next.contract.clone(),
+            adjustment: roll_adjustment(front, next, &time, adjustment),
+        });
+    }
+
+    let mut bars = vec![];
+    for (index, segment) in segments.iter().enumerate() {
+        let from = index.checked_sub(1).map(|index| rolls[index].time);
+        let to = rolls.get(index).map(|roll| roll.time);
+        // The adjustments of all later rolls apply to the segment
+        let later_rolls = &rolls[index.min(rolls.len())..];
+
+        let mut segment_bars: Vec<(DateTime<Utc>, BarData)> = segment
+            .bars
+            .iter()
+            .filter_map(|bar| bar_time(bar).map(|time| (time, bar.clone())))
+            .filter(|(time, _)| {
+                from.is_none_or(|from| *time >= from) && to.is_none_or(|to| *time < to)
+            })
+            .collect();
+        segment_bars.sort_by_key(|(time, _)| *time);
+        segment_bars.dedup_by_key(|(time, _)| *time);
+
+        for (_, mut bar) in segment_bars {
+            for roll in later_rolls {
+                adjust_bar(&mut bar, roll.adjustment, adjustment);
+            }
+            bars.push(bar);
+        }
+    }
+    ContinuousSeries { bars, rolls }
+}
+
+//==================================================================================================
+/// Parses an expiry given as `YYYYMMDD`, optionally followed by a time
+pub fn parse_expiry(expiry: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(expiry.trim().get(..8)?, "%Y%m%d").ok()
+}
+
+//==================================================================================================
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+//==================================================================================================
+fn roll_time(front: &FuturesSegment, next: &FuturesSegment, roll_rule: &RollRule) -> DateTime<Utc> {
+    let expiry = start_of_day(front.expiry);
+    let (front_values, next_values) = match roll_rule {
+        RollRule::Calendar { days_before_expiry } => {
+            return expiry - chrono::Duration::days(*days_before_expiry);
+        }
+        RollRule::OpenInterest
+            if !front.open_interest.is_empty() && !next.open_interest.is_empty() =>
+        {
+            (front.open_interest.clone(), next.open_interest.clone())
+        }
+        _ => (front.daily_volume(), next.daily_volume()),
+    };
+
+    next_values
+        .iter()
+        .find(|(day, value)| **value > front_values.get(day).cloned().unwrap_or(0.0))
+        .and_then(|(day, _)| day.succ_opt())
+        .map_or(expiry, |day| start_of_day(day).min(expiry))
+}
+
+//==================================================================================================
+// Compares the closes of both expiries at the last bar of the front contract
+// before the roll
+fn roll_adjustment(
+    front: &FuturesSegment,
+    next: &FuturesSegment,
+    time: &DateTime<Utc>,
+    adjustment: &BackAdjustment,
+) -> f64 {
+    let neutral = match adjustment {
+        BackAdjustment::None => return 0.0,
+        BackAdjustment::Difference => 0.0,
+        BackAdjustment::Ratio => 1.0,
+    };
+    let (front_time, front_bar) = match front.bar_before(time) {
+        Some(bar) => bar,
+        None => return neutral,
+    };
+    let next_close = match next.bar_before(&(front_time + chrono::Duration::seconds(1))) {
+        Some((_, bar)) => bar.close,
+        None => return neutral,
+    };
+
+    match adjustment {
+        BackAdjustment::Ratio if front_bar.close != 0.0 => next_close / front_bar.close,
+        BackAdjustment::Difference => next_close - front_bar.close,
+        _ => neutral,
+    }
+}
+
+//==================================================================================================
+fn adjust_bar(bar: &mut BarData, value: f64, adjustment: &BackAdjustment) {
+    let adjust = |price: f64| match adjustment {
+        BackAdjustment::None => price,
+        BackAdjustment::Difference => price + value,
+        BackAdjustment::Ratio => price * value,
+    };
+    bar.open = adjust(bar.open);
+    bar.high = adjust(bar.high);
+    bar.low = adjust(bar.low);
+    bar.close = adjust(bar.close);
+    // Bars without trades have no average price
+    if bar.average > 0.0 {
+        bar.average = adjust(bar.average);
+    }
+}
+
+//==================================================================================================
+/// Resolves the expiries of a futures root, downloads their bars and
+/// stitches them into a continuous series.
+///
+/// Each expiry is downloaded from `overlap` before the expiry of the
+/// previous contract, so that volume based rolls can compare both.
+#[derive(Debug)]
+pub struct ContinuousFuturesDownloader {
+    root: Contract,
+    bar_size: BarSize,
+    what_to_show: WhatToShow,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    use_rth: bool,
+    roll_rule: RollRule,
+    adjustment: BackAdjustment,
+    overlap: chrono::Duration,
+    timeout: Duration,
+}
+
+impl ContinuousFuturesDownloader {
+    /// # Arguments
+    /// * root - the symbol, exchange and currency of the futures, e.g. ES on
+    ///   CME in USD
+    pub fn new(
+        root: Contract,
+        bar_size: BarSize,
+        what_to_show: WhatToShow,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Self {
+        ContinuousFuturesDownloader {
+            root,
+            bar_size,
+            what_to_show,
+            start,
+            end,
+            use_rth: false,
+            roll_rule: RollRule::Volume,
+            adjustment: BackAdjustment::Difference,
+            overlap: chrono::Duration::days(14),
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn use_rth(mut self, use_rth: bool) -> Self {
+        self.use_rth = use_rth;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Defaults to `RollRule::Volume`
+    pub fn roll_rule(mut self, roll_rule: RollRule) -> Self {
+        self.roll_rule = roll_rule;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Defaults to `BackAdjustment::Difference`
+    pub fn adjustment(mut self, adjustment: BackAdjustment) -> Self {
+        self.adjustment = adjustment;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How long before the previous expiry each contract is downloaded.
+    /// Defaults to 14 days.
+    pub fn overlap(mut self, overlap: chrono::Duration) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How long to wait for the contract details and the bars of each chunk.
+    /// Defaults to 60 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The expiries needed for the range, ordered by expiry
+    pub fn resolve_chain<T>(
+        &self,
+        client: &Mutex<EClient<T>>,
+        request_id: i32,
+    ) -> Result<Vec<ContractDetails>, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        let mut query = self.root.clone();
+        query.sec_type = SecType::Future;
+        query.include_expired = true;
+
+        let mut chain: Vec<(NaiveDate, ContractDetails)> = client
+            .lock()
+            .expect(POISONED_MUTEX)
+            .fetch_contract_details(request_id, &query, self.timeout)?
+            .into_iter()
+            .filter_map(|details| {
+                FuturesSegment::from_contract_details(&details, vec![])
+                    .map(|segment| (segment.expiry, details))
+            })
+            .filter(|(expiry, _)| start_of_day(*expiry) >= start_of_day(self.start.date_naive()))
+            .collect();
+        chain.sort_by_key(|(expiry, _)| *expiry);
+        chain.dedup_by_key(|(_, details)| details.contract.con_id);
+
+        // Keep the first expiry after the end of the range, drop the rest
+        if let Some(last) = chain
+            .iter()
+            .position(|(expiry, _)| start_of_day(*expiry) >= self.end)
+        {
+            chain.truncate(last + 1);
+        }
+        Ok(chain.into_iter().map(|(_, details)| details).collect())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Resolves the expiries, downloads their bars and stitches them.
+    ///
+    /// # Arguments
+    /// * client - a connected client
+    /// * first_request_id - the id of the contract details request; the
+    ///   historical data requests use the following ids
+    /// * progress - called after each chunk of each expiry
+    pub fn download<T, F>(
+        &self,
+        client: &Mutex<EClient<T>>,
+        first_request_id: i32,
+        mut progress: F,
+    ) -> Result<ContinuousSeries, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+        F: FnMut(&DownloadProgress),
+    {
+        let chain = self.resolve_chain(client, first_request_id)?;
+        let mut request_id = first_request_id + 1;
+        let mut previous_expiry: Option<DateTime<Utc>> = None;
+        let mut segments = vec![];
+
+        for details in chain {
+            let mut segment = match FuturesSegment::from_contract_details(&details, vec![]) {
+                Some(segment) => segment,
+                None => continue,
+            };
+            segment.contract.include_expired = true;
+
+            let expiry = start_of_day(segment.expiry) + chrono::Duration::days(1);
+            let start = previous_expiry.map_or(self.start, |previous| {
+                (previous - self.overlap).max(self.start)
+            });
+            let end = expiry.min(self.end);
+            previous_expiry = Some(expiry);
+            if start >= end {
+                continue;
+            }
+
+            let mut downloader = HistoricalDownloader::new(
+                segment.contract.clone(),
+                self.bar_size.clone(),
+                self.what_to_show.clone(),
+                start,
+                end,
+            )
+            .use_rth(self.use_rth)
+            .timeout(self.timeout);
+            let request_ids = downloader.request_ids();
+            segment.bars = downloader.download(client, request_id, &mut progress)?;
+            request_id += request_ids;
+            segments.push(segment);
+        }
+        Ok(stitch(segments, &self.roll_rule, &self.adjustment))
+    }
+}
@@ -1,16 +1,28 @@
 #![allow(clippy::too_many_arguments)]
 //! Types related to Contracts
+use std::borrow::Cow;
+use std::convert::Infallible;
 use std::fmt::{Display, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
+use chrono::{DateTime, Duration, NaiveDate, TimeZone};
+use chrono_tz::Tz;
 use num_derive::FromPrimitive;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 // 0.2.6 (the trait)
 
 use crate::core::common::TagValue;
+use crate::core::errors::ResolveContractError;
+use crate::core::trading_hours::{
+    next_session, parse_time_zone_id, parse_trading_hours, session_at, Session,
+};
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, Copy, FromPrimitive, Debug)]
+#[derive(Clone, Copy, FromPrimitive, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PositionType {
     SamePos = 0,
     //open/close leg value is same as combo
@@ -39,8 +51,417 @@ impl Default for PositionType {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-#[serde(default)]
+/// The security type of a contract. Types without a variant of their own,
+/// e.g. `FIXED` or `SLB`, are kept as `Other`; the default is an empty
+/// `Other`, i.e. no type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "String", into = "String"))]
+pub enum SecType {
+    /// `STK`
+    Stock,
+    /// `OPT`
+    Option,
+    /// `FUT`
+    Future,
+    /// `CONTFUT`
+    ContinuousFuture,
+    /// `FOP`
+    FuturesOption,
+    /// `CASH`
+    Forex,
+    /// `BAG`
+    Combo,
+    /// `BOND`
+    Bond,
+    /// `BILL`
+    Bill,
+    /// `CFD`
+    Cfd,
+    /// `FUND`
+    Fund,
+    /// `CMDTY`
+    Commodity,
+    /// `CRYPTO`
+    Crypto,
+    /// `WAR`
+    Warrant,
+    /// `IOPT`, structured products like Dutch turbos
+    StructuredProduct,
+    /// `FWD`
+    Forward,
+    /// `IND`
+    Index,
+    /// `NEWS`
+    News,
+    Other(String),
+}
+
+impl SecType {
+    /// The code TWS uses for the type, e.g. `STK`
+    pub fn as_str(&self) -> &str {
+        match self {
+            SecType::Stock => "STK",
+            SecType::Option => "OPT",
+            SecType::Future => "FUT",
+            SecType::ContinuousFuture => "CONTFUT",
+            SecType::FuturesOption => "FOP",
+            SecType::Forex => "CASH",
+            SecType::Combo => "BAG",
+            SecType::Bond => "BOND",
+            SecType::Bill => "BILL",
+            SecType::Cfd => "CFD",
+            SecType::Fund => "FUND",
+            SecType::Commodity => "CMDTY",
+            SecType::Crypto => "CRYPTO",
+            SecType::Warrant => "WAR",
+            SecType::StructuredProduct => "IOPT",
+            SecType::Forward => "FWD",
+            SecType::Index => "IND",
+            SecType::News => "NEWS",
+            SecType::Other(sec_type) => sec_type,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the type is an option, i.e. has a strike and a right
+    pub fn is_option(&self) -> bool {
+        matches!(
+            self,
+            SecType::Option | SecType::FuturesOption | SecType::Warrant
+        )
+    }
+}
+
+impl Default for SecType {
+    fn default() -> Self {
+        SecType::Other(String::new())
+    }
+}
+
+impl Display for SecType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SecType {
+    type Err = Infallible;
+
+    fn from_str(sec_type: &str) -> Result<Self, Self::Err> {
+        Ok(SecType::from(sec_type))
+    }
+}
+
+impl From<&str> for SecType {
+    fn from(sec_type: &str) -> Self {
+        let sec_type = sec_type.trim().to_uppercase();
+        match sec_type.as_str() {
+            "STK" => SecType::Stock,
+            "OPT" => SecType::Option,
+            "FUT" => SecType::Future,
+            "CONTFUT" => SecType::ContinuousFuture,
+            "FOP" => SecType::FuturesOption,
+            "CASH" => SecType::Forex,
+            "BAG" => SecType::Combo,
+            "BOND" => SecType::Bond,
+            "BILL" => SecType::Bill,
+            "CFD" => SecType::Cfd,
+            "FUND" => SecType::Fund,
+            "CMDTY" => SecType::Commodity,
+            "CRYPTO" => SecType::Crypto,
+            "WAR" => SecType::Warrant,
+            "IOPT" => SecType::StructuredProduct,
+            "FWD" => SecType::Forward,
+            "IND" => SecType::Index,
+            "NEWS" => SecType::News,
+            _ => SecType::Other(sec_type),
+        }
+    }
+}
+
+impl From<String> for SecType {
+    fn from(sec_type: String) -> Self {
+        SecType::from(sec_type.as_str())
+    }
+}
+
+impl From<SecType> for String {
+    fn from(sec_type: SecType) -> Self {
+        sec_type.to_string()
+    }
+}
+
+//==================================================================================================
+/// The name of an exchange, as used in `Contract::exchange` and
+/// `Contract::primary_exchange`. The constants cover common venues, other
+/// exchanges are created from their name, e.g. `Exchange::from("IBIS")`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "String", into = "String"))]
+pub struct Exchange(Cow<'static, str>);
+
+impl Exchange {
+    /// IB's smart routing
+    pub const SMART: Exchange = Exchange(Cow::Borrowed("SMART"));
+    /// Nasdaq, the name IB uses to route orders to Nasdaq
+    pub const ISLAND: Exchange = Exchange(Cow::Borrowed("ISLAND"));
+    /// Nasdaq, the primary exchange of Nasdaq listed stocks
+    pub const NASDAQ: Exchange = Exchange(Cow::Borrowed("NASDAQ"));
+    pub const NYSE: Exchange = Exchange(Cow::Borrowed("NYSE"));
+    /// NYSE Arca
+    pub const ARCA: Exchange = Exchange(Cow::Borrowed("ARCA"));
+    pub const CBOE: Exchange = Exchange(Cow::Borrowed("CBOE"));
+    /// CME Globex, the former name of `CME`
+    pub const GLOBEX: Exchange = Exchange(Cow::Borrowed("GLOBEX"));
+    pub const CME: Exchange = Exchange(Cow::Borrowed("CME"));
+    /// IB's forex venue
+    pub const IDEALPRO: Exchange = Exchange(Cow::Borrowed("IDEALPRO"));
+    /// The venue of crypto currencies
+    pub const PAXOS: Exchange = Exchange(Cow::Borrowed("PAXOS"));
+    /// Overnight trading of US stocks and ETFs
+    pub const OVERNIGHT: Exchange = Exchange(Cow::Borrowed("OVERNIGHT"));
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_smart(&self) -> bool {
+        *self == Exchange::SMART
+    }
+}
+
+impl Display for Exchange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for Exchange {
+    fn from(exchange: &str) -> Self {
+        Exchange(Cow::Owned(exchange.trim().to_uppercase()))
+    }
+}
+
+impl From<String> for Exchange {
+    fn from(exchange: String) -> Self {
+        Exchange::from(exchange.as_str())
+    }
+}
+
+impl From<Exchange> for String {
+    fn from(exchange: Exchange) -> Self {
+        exchange.0.into_owned()
+    }
+}
+
+impl PartialEq<str> for Exchange {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<String> for Exchange {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Exchange> for String {
+    fn eq(&self, other: &Exchange) -> bool {
+        self == other.as_str()
+    }
+}
+
+//==================================================================================================
+/// The right of an option or warrant. Sent as `C` or `P`; TWS also reports
+/// `CALL` and `PUT`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub enum Right {
+    Call,
+    Put,
+    /// Contracts that are neither options nor warrants
+    #[default]
+    None,
+}
+
+impl Right {
+    /// The code TWS uses for the right, `C`, `P` or empty
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Right::Call => "C",
+            Right::Put => "P",
+            Right::None => "",
+        }
+    }
+}
+
+impl Display for Right {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Right {
+    type Err = String;
+
+    fn from_str(right: &str) -> Result<Self, Self::Err> {
+        match right.trim().to_uppercase().as_str() {
+            "C" | "CALL" => Ok(Right::Call),
+            "P" | "PUT" => Ok(Right::Put),
+            "" | "?" | "0" => Ok(Right::None),
+            _ => Err(format!("Unknown option right: {}", right)),
+        }
+    }
+}
+
+impl TryFrom<String> for Right {
+    type Error = String;
+
+    fn try_from(right: String) -> Result<Self, Self::Error> {
+        right.parse()
+    }
+}
+
+impl From<Right> for String {
+    fn from(right: Right) -> Self {
+        right.to_string()
+    }
+}
+
+//==================================================================================================
+/// The kind of identifier in `Contract::sec_id`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SecIdType {
+    Isin,
+    Cusip,
+    Figi,
+    Sedol,
+    Ric,
+}
+
+impl SecIdType {
+    /// Whether `sec_id` has the length, characters and, for ISIN, CUSIP,
+    /// FIGI and SEDOL, the check digit of an identifier of the type. RICs
+    /// are only checked for not being empty.
+    pub fn is_valid(&self, sec_id: &str) -> bool {
+        let sec_id = sec_id.trim().to_uppercase();
+        let alphanumeric = sec_id.chars().all(|c| c.is_ascii_alphanumeric());
+        match self {
+            SecIdType::Isin => {
+                sec_id.len() == 12
+                    && alphanumeric
+                    && sec_id[..2].chars().all(|c| c.is_ascii_alphabetic())
+                    && isin_check_digit(&sec_id[..11]) == sec_id.chars().nth(11)
+            }
+            SecIdType::Cusip => {
+                sec_id.len() == 9
+                    && alphanumeric
+                    && cusip_check_digit(&sec_id[..8]) == sec_id.chars().nth(8)
+            }
+            SecIdType::Figi => {
+                sec_id.len() == 12
+                    && alphanumeric
+                    && sec_id.chars().nth(2) == Some('G')
+                    && !sec_id[..11].contains(['A', 'E', 'I', 'O', 'U'])
+                    && cusip_check_digit(&sec_id[..11]) == sec_id.chars().nth(11)
+            }
+            SecIdType::Sedol => {
+                sec_id.len() == 7
+                    && alphanumeric
+                    && sedol_check_digit(&sec_id[..6]) == sec_id.chars().nth(6)
+            }
+            SecIdType::Ric => !sec_id.is_empty(),
+        }
+    }
+}
+
+impl Display for SecIdType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match *self {
+            SecIdType::Isin => write!(f, "ISIN"),
+            SecIdType::Cusip => write!(f, "CUSIP"),
+            SecIdType::Figi => write!(f, "FIGI"),
+            SecIdType::Sedol => write!(f, "SEDOL"),
+            SecIdType::Ric => write!(f, "RIC"),
+        }
+    }
+}
+
+impl FromStr for SecIdType {
+    type Err = String;
+
+    fn from_str(sec_id_type: &str) -> Result<Self, Self::Err> {
+        match sec_id_type.trim().to_uppercase().as_str() {
+            "ISIN" => Ok(SecIdType::Isin),
+            "CUSIP" => Ok(SecIdType::Cusip),
+            "FIGI" => Ok(SecIdType::Figi),
+            "SEDOL" => Ok(SecIdType::Sedol),
+            "RIC" => Ok(SecIdType::Ric),
+            _ => Err(format!("Unknown security id type: {}", sec_id_type)),
+        }
+    }
+}
+
+//==================================================================================================
+/// The value of a digit or letter in a check digit, `A` being 10
+fn char_value(c: char) -> Option<u32> {
+    c.to_digit(36)
+}
+
+//==================================================================================================
+/// The Luhn check digit of the ISIN prefix, with letters expanded to two
+/// digits
+fn isin_check_digit(prefix: &str) -> Option<char> {
+    let digits = prefix
+        .chars()
+        .map(|c| char_value(c).map(|value| value.to_string()))
+        .collect::<Option<String>>()?;
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(index, digit)| {
+            let value = if index % 2 == 0 { digit * 2 } else { digit };
+            value / 10 + value % 10
+        })
+        .sum();
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
+
+//==================================================================================================
+/// The check digit of CUSIPs and FIGIs: every second value is doubled and
+/// the digits of all values are summed
+fn cusip_check_digit(prefix: &str) -> Option<char> {
+    let mut sum = 0;
+    for (index, c) in prefix.chars().enumerate() {
+        let mut value = char_value(c)?;
+        if index % 2 == 1 {
+            value *= 2;
+        }
+        sum += value / 10 + value % 10;
+    }
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
+
+//==================================================================================================
+fn sedol_check_digit(prefix: &str) -> Option<char> {
+    let mut sum = 0;
+    for (c, weight) in prefix.chars().zip([1, 3, 1, 7, 3, 9]) {
+        sum += char_value(c)? * weight;
+    }
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
+
+//==================================================================================================
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct ComboLeg {
     pub con_id: i32,
     pub ratio: f64,
@@ -103,7 +524,12 @@ impl Display for ComboLeg {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// The underlying that hedges a combo or an option order delta neutrally
+///
+/// delta - the delta of the combo or option per unit
+/// price - the price of the underlying the delta refers to
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DeltaNeutralContract {
     pub con_id: i32,
     pub delta: f64,
@@ -133,15 +559,16 @@ impl Display for DeltaNeutralContract {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-#[serde(default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Contract {
     pub con_id: i32,
     pub symbol: String,
-    pub sec_type: String,
+    pub sec_type: SecType,
     pub last_trade_date_or_contract_month: String,
     pub strike: f64,
-    pub right: String,
+    pub right: Right,
     pub multiplier: String,
     pub exchange: String,
     pub primary_exchange: String,
@@ -165,10 +592,10 @@ impl Contract {
     pub fn new(
         con_id: i32,
         symbol: String,
-        sec_type: String,
+        sec_type: SecType,
         last_trade_date_or_contract_month: String,
         strike: f64,
-        right: String,
+        right: Right,
         multiplier: String,
         exchange: String,
         primary_exchange: String,
@@ -204,48 +631,78 @@ impl Contract {
         }
     }
 
-    /// Foreign exchange currency pair.
+    /// A stock, e.g. `Contract::stock("AAPL", "SMART", "USD")`.
     /// # Arguments
-    /// * pair: Shortcut for specifying symbol and currency, like `"EURUSD"`.
-    /// * exchange: Destination exchange.
-    /// * symbol: Base currency.
-    /// * currency: Quote currency.
-    pub fn forex(
-        pair: &str,
-        exchange: Option<&str>,
-        symbol: Option<&str>,
-        currency: Option<&str>,
-    ) -> Self {
-        assert!(pair.len() == 6);
+    /// * exchange: Destination exchange, usually `"SMART"`. Set
+    ///   `primary_exchange` to tell apart listings with the same symbol.
+    pub fn stock(symbol: &str, exchange: &str, currency: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            sec_type: SecType::Stock,
+            exchange: exchange.to_string(),
+            currency: currency.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A foreign exchange currency pair traded on IDEALPRO.
+    /// # Arguments
+    /// * pair: The base and the quote currency, like `"EURUSD"` or
+    ///   `"EUR.USD"`.
+    pub fn forex(pair: &str) -> Self {
+        let pair: String = pair.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+        assert!(pair.len() == 6, "A currency pair has six letters");
 
         Self {
-            symbol: symbol.unwrap_or(&pair[..3]).to_string(),
-            sec_type: "CASH".to_string(),
-            exchange: exchange.unwrap_or("IDEALPRO").to_string(),
-            currency: currency.unwrap_or(&pair[3..]).to_string(),
+            symbol: pair[..3].to_uppercase(),
+            sec_type: SecType::Forex,
+            exchange: Exchange::IDEALPRO.to_string(),
+            currency: pair[3..].to_uppercase(),
             ..Default::default()
         }
     }
 
+    /// A contract for difference on a stock or an index, routed through
+    /// SMART, e.g. `Contract::cfd("IBDE40", "EUR")`. See
+    /// `fx::validate_cfd_order` for the orders CFDs accept.
+    pub fn cfd(symbol: &str, currency: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            sec_type: SecType::Cfd,
+            exchange: Exchange::SMART.to_string(),
+            currency: currency.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A futures contract, e.g. `Contract::future("ES", "202506", "CME")`.
+    /// # Arguments
+    /// * last_trade_date_or_contract_month: The contract month as
+    ///   `YYYYMM` or the last trading day as `YYYYMMDD`.
     pub fn future(symbol: &str, last_trade_date_or_contract_month: &str, exchange: &str) -> Self {
         Self {
             symbol: symbol.to_string(),
-            sec_type: "FUT".to_string(),
+            sec_type: SecType::Future,
             exchange: exchange.to_string(),
-            //currency: "USD".to_string(),
             last_trade_date_or_contract_month: last_trade_date_or_contract_month.to_string(),
             ..Default::default()
         }
     }
 
-    pub fn futures_option(symbol: &str, last_trade_date_or_contract_month: &str, strike: f64, right: &str, exchange: &str) -> Self {
+    /// An option on a futures contract.
+    pub fn futures_option(
+        symbol: &str,
+        last_trade_date_or_contract_month: &str,
+        strike: f64,
+        right: Right,
+        exchange: &str,
+    ) -> Self {
         Self {
             symbol: symbol.to_string(),
-            sec_type: "FOP".to_string(),
+            sec_type: SecType::FuturesOption,
             strike,
-            right: right.to_string(),
+            right,
             exchange: exchange.to_string(),
-            //currency: "USD".to_string(),
             last_trade_date_or_contract_month: last_trade_date_or_contract_month.to_string(),
             ..Default::default()
         }
@@ -254,44 +711,296 @@ impl Contract {
     pub fn index(symbol: &str, exchange: &str, currency: &str) -> Self {
         Self {
             symbol: symbol.to_string(),
-            sec_type: "IND".to_string(),
+            sec_type: SecType::Index,
             exchange: exchange.to_string(),
             currency: currency.to_string(),
             ..Default::default()
         }
     }
 
-    pub fn stock(symbol: &str, exchange: &str, currency: Option<&str>) -> Self {
+    /// A US equity option with a multiplier of 100, routed through SMART,
+    /// e.g. `Contract::option("AAPL", "20250620", 200.0, Right::Call)`.
+    /// Change `exchange`, `currency` and `multiplier` for other markets.
+    pub fn option(
+        symbol: &str,
+        last_trade_date_or_contract_month: &str,
+        strike: f64,
+        right: Right,
+    ) -> Self {
         Self {
             symbol: symbol.to_string(),
-            sec_type: "STK".to_string(),
-            exchange: exchange.to_string(),
-            currency: currency.unwrap_or("USD").to_string(),
+            sec_type: SecType::Option,
+            exchange: Exchange::SMART.to_string(),
+            currency: "USD".to_string(),
+            last_trade_date_or_contract_month: last_trade_date_or_contract_month.to_string(),
+            strike,
+            right,
+            multiplier: "100".to_string(),
             ..Default::default()
         }
     }
 
-    pub fn option(
+    /// A warrant, e.g.
+    /// `Contract::warrant("DTE", "20251219", 20.0, Right::Call, "FWB", "EUR")`.
+    /// Warrants of several issuers often share symbol, expiry and strike;
+    /// set `local_symbol` or look the warrant up by its ISIN to pick one.
+    /// The `multiplier` is the ratio of the warrant, e.g. `"0.1"` for ten
+    /// warrants per share, and the `long_name` of its contract details
+    /// names the issuer.
+    pub fn warrant(
         symbol: &str,
         last_trade_date_or_contract_month: &str,
-        strike_price: f64,
-        right: &str,
+        strike: f64,
+        right: Right,
         exchange: &str,
-        multiplier: Option<f64>,
-        currency: Option<&str>,
+        currency: &str,
     ) -> Self {
         Self {
             symbol: symbol.to_string(),
-            sec_type: "OPT".to_string(),
+            sec_type: SecType::Warrant,
             exchange: exchange.to_string(),
-            currency: currency.map_or("".to_string(), |v| v.to_string()),
+            currency: currency.to_string(),
             last_trade_date_or_contract_month: last_trade_date_or_contract_month.to_string(),
-            strike: strike_price,
-            right: right.to_string(),
-            multiplier: multiplier.map_or("100".to_string(), |v| v.to_string()),
+            strike,
+            right,
+            ..Default::default()
+        }
+    }
+
+    /// A structured product like a turbo or a knock-out certificate,
+    /// identified by its local symbol, e.g. the exchange code or ISIN.
+    /// Their strike, right and multiplier are returned with the contract
+    /// details.
+    pub fn structured_product(local_symbol: &str, exchange: &str, currency: &str) -> Self {
+        Self {
+            local_symbol: local_symbol.to_string(),
+            sec_type: SecType::StructuredProduct,
+            exchange: exchange.to_string(),
+            currency: currency.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A crypto currency traded against USD on PAXOS, e.g.
+    /// `Contract::crypto("BTC")`.
+    pub fn crypto(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            sec_type: SecType::Crypto,
+            exchange: Exchange::PAXOS.to_string(),
+            currency: "USD".to_string(),
             ..Default::default()
         }
     }
+
+    /// Routes orders for the contract through SMART. The primary exchange
+    /// tells apart listings with the same symbol, e.g. `Exchange::NASDAQ`.
+    pub fn smart_routed(mut self, primary_exchange: &Exchange) -> Self {
+        assert!(
+            !primary_exchange.is_smart(),
+            "SMART is not a primary exchange"
+        );
+        self.exchange = Exchange::SMART.to_string();
+        self.primary_exchange = primary_exchange.to_string();
+        self
+    }
+
+    /// Routes orders for the contract directly to `exchange`, e.g. a Nasdaq
+    /// listed stock to `Exchange::ARCA` with `Exchange::NASDAQ` as its
+    /// primary exchange. Directed orders must be enabled in TWS.
+    pub fn directed(mut self, exchange: &Exchange, primary_exchange: &Exchange) -> Self {
+        assert!(
+            !exchange.is_smart() && !primary_exchange.is_smart(),
+            "A directed order is not routed through SMART"
+        );
+        self.exchange = exchange.to_string();
+        self.primary_exchange = primary_exchange.to_string();
+        self
+    }
+
+    /// Whether orders for the contract are routed directly to an exchange
+    pub fn is_directed(&self) -> bool {
+        !self.exchange.is_empty() && self.exchange != Exchange::SMART
+    }
+
+    /// Hedges the contract, a combo or an option, with the underlying
+    /// `con_id` at `delta` and the underlying `price`. Used with VOL orders
+    /// whose `delta_neutral_order_type` is set; TWS confirms the hedge
+    /// through `Wrapper::delta_neutral_validation` and returns it with the
+    /// contract of open and completed orders. Position callbacks do not
+    /// carry it.
+    pub fn delta_neutral(mut self, con_id: i32, delta: f64, price: f64) -> Self {
+        self.delta_neutral_contract = Some(DeltaNeutralContract::new(con_id, delta, price));
+        self
+    }
+
+    /// The broad tape news feed of a provider, for use with
+    /// `EClient::request_news_ticks`.
+    /// # Arguments
+    /// * provider_code: The news provider, like `"BRFG"`.
+    pub fn news(provider_code: &str) -> Self {
+        Self {
+            symbol: format!("{}:{}_ALL", provider_code, provider_code),
+            sec_type: SecType::News,
+            exchange: provider_code.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A security identified by an ISIN, CUSIP, FIGI, SEDOL or RIC, routed
+    /// through SMART, e.g.
+    /// `Contract::by_sec_id(SecIdType::Isin, "US0378331005")`. Set
+    /// `currency`, `sec_type` or `primary_exchange` if the identifier
+    /// matches several listings, see `EClient::resolve_sec_id`.
+    pub fn by_sec_id(sec_id_type: SecIdType, sec_id: &str) -> Self {
+        Self {
+            sec_id_type: sec_id_type.to_string(),
+            sec_id: sec_id.trim().to_uppercase(),
+            exchange: Exchange::SMART.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn isin(isin: &str) -> Self {
+        Self::by_sec_id(SecIdType::Isin, isin)
+    }
+
+    pub fn cusip(cusip: &str) -> Self {
+        Self::by_sec_id(SecIdType::Cusip, cusip)
+    }
+
+    pub fn figi(figi: &str) -> Self {
+        Self::by_sec_id(SecIdType::Figi, figi)
+    }
+
+    /// Trims the symbol, exchanges and currency and converts them to upper
+    /// case, e.g. to compare a contract entered by a user with one returned
+    /// by TWS
+    pub fn normalize(mut self) -> Self {
+        for field in [
+            &mut self.symbol,
+            &mut self.exchange,
+            &mut self.primary_exchange,
+            &mut self.currency,
+        ] {
+            *field = field.trim().to_uppercase();
+        }
+        self
+    }
+
+    /// What tells the contract apart from others: `CONID:` and the
+    /// `con_id` if it has one, otherwise its fields trimmed and in upper
+    /// case, joined by `|`, followed by the `con_id`, ratio and action of
+    /// each combo leg. Contracts are equal and hash alike if their
+    /// identities are, so a contract with a `con_id` never equals one
+    /// without.
+    pub fn identity(&self) -> String {
+        if self.con_id > 0 {
+            return format!("CONID:{}", self.con_id);
+        }
+        let strike = if self.strike != 0.0 {
+            self.strike.to_string()
+        } else {
+            "".to_string()
+        };
+        let [symbol, sec_type, expiry, fields @ ..] = self.identity_fields();
+        let legs = self.combo_legs.iter().map(|leg| {
+            format!(
+                "LEG:{}:{}:{}",
+                leg.con_id,
+                leg.ratio,
+                leg.action.trim().to_ascii_uppercase()
+            )
+        });
+        [symbol, sec_type, expiry, strike.as_str()]
+            .into_iter()
+            .chain(fields)
+            .map(|field| field.trim().to_ascii_uppercase())
+            .chain(legs)
+            .collect::<Vec<String>>()
+            .join("|")
+    }
+
+    /// The fields of the identity of a contract without a `con_id`, but for
+    /// the strike, as they are
+    fn identity_fields(&self) -> [&str; 12] {
+        [
+            &self.symbol,
+            self.sec_type.as_str(),
+            &self.last_trade_date_or_contract_month,
+            self.right.as_str(),
+            &self.multiplier,
+            &self.exchange,
+            &self.primary_exchange,
+            &self.currency,
+            &self.local_symbol,
+            &self.trading_class,
+            &self.sec_id_type,
+            &self.sec_id,
+        ]
+    }
+
+    /// The strike as the identity sees it, with no strike and 0 alike
+    fn identity_strike(&self) -> u64 {
+        if self.strike != 0.0 {
+            self.strike.to_bits()
+        } else {
+            0
+        }
+    }
+}
+
+// Equality and hashing follow identity() without building it, as contracts
+// are compared and hashed a lot, e.g. as map keys
+impl PartialEq for Contract {
+    fn eq(&self, other: &Self) -> bool {
+        if self.con_id > 0 || other.con_id > 0 {
+            return self.con_id == other.con_id;
+        }
+        self.identity_strike() == other.identity_strike()
+            && self
+                .identity_fields()
+                .iter()
+                .zip(other.identity_fields().iter())
+                .all(|(field, other)| field.trim().eq_ignore_ascii_case(other.trim()))
+            && self.combo_legs.len() == other.combo_legs.len()
+            && self
+                .combo_legs
+                .iter()
+                .zip(other.combo_legs.iter())
+                .all(|(leg, other)| {
+                    leg.con_id == other.con_id
+                        && leg.ratio.to_bits() == other.ratio.to_bits()
+                        && leg.action.trim().eq_ignore_ascii_case(other.action.trim())
+                })
+    }
+}
+
+impl Eq for Contract {}
+
+impl Hash for Contract {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if self.con_id > 0 {
+            return self.con_id.hash(state);
+        }
+        self.identity_strike().hash(state);
+        for field in self.identity_fields() {
+            for byte in field.trim().bytes() {
+                state.write_u8(byte.to_ascii_uppercase());
+            }
+            // Not valid UTF-8, so no field can run into the next
+            state.write_u8(0xff);
+        }
+        self.combo_legs.len().hash(state);
+        for leg in &self.combo_legs {
+            leg.con_id.hash(state);
+            leg.ratio.to_bits().hash(state);
+            for byte in leg.action.trim().bytes() {
+                state.write_u8(byte.to_ascii_uppercase());
+            }
+            state.write_u8(0xff);
+        }
+    }
 }
 
 impl Display for Contract {
@@ -349,8 +1058,9 @@ impl From<ContractDetails> for Contract {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-#[serde(default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct ContractDetails {
     pub contract: Contract,
     pub market_name: String,
@@ -377,6 +1087,10 @@ pub struct ContractDetails {
     pub sec_id_list: Vec<TagValue>,
     pub real_expiration_date: String,
     pub last_trade_time: String,
+    pub stock_type: String,
+    pub min_size: f64,
+    pub size_increment: f64,
+    pub suggested_size_increment: f64,
 
     // BOND values
     pub cusip: String,
@@ -423,6 +1137,10 @@ impl ContractDetails {
         sec_id_list: Vec<TagValue>,
         real_expiration_date: String,
         last_trade_time: String,
+        stock_type: String,
+        min_size: f64,
+        size_increment: f64,
+        suggested_size_increment: f64,
         cusip: String,
         ratings: String,
         desc_append: String,
@@ -465,6 +1183,10 @@ impl ContractDetails {
             sec_id_list,
             real_expiration_date,
             last_trade_time,
+            stock_type,
+            min_size,
+            size_increment,
+            suggested_size_increment,
             cusip,
             ratings,
             desc_append,
@@ -482,6 +1204,94 @@ impl ContractDetails {
             notes,
         }
     }
+
+    //----------------------------------------------------------------------------------------------
+    /// The bond specific fields, typed. `None` unless the contract is a
+    /// bond.
+    pub fn bond_details(&self) -> Option<BondDetails> {
+        if self.contract.sec_type != SecType::Bond {
+            return None;
+        }
+        Some(BondDetails {
+            cusip: self.cusip.clone(),
+            ratings: self
+                .ratings
+                .split('/')
+                .map(str::trim)
+                .filter(|rating| !rating.is_empty())
+                .map(str::to_string)
+                .collect(),
+            bond_type: self.bond_type.clone(),
+            coupon_type: self.coupon_type.clone(),
+            coupon: self.coupon,
+            callable: self.callable,
+            putable: self.putable,
+            convertible: self.convertible,
+            maturity: parse_bond_date(&self.maturity),
+            issue_date: parse_bond_date(&self.issue_date),
+            next_option_date: parse_bond_date(&self.next_option_date),
+            next_option_type: self.next_option_type.clone(),
+            next_option_partial: self.next_option_partial,
+            notes: self.notes.clone(),
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The time zone of the exchange, see `parse_time_zone_id`
+    pub fn time_zone(&self) -> Option<Tz> {
+        parse_time_zone_id(&self.time_zone_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sessions of `trading_hours`, ordered by their open
+    pub fn trading_sessions(&self) -> Result<Vec<Session>, String> {
+        self.sessions(&self.trading_hours)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sessions of `liquid_hours`, i.e. the regular trading hours
+    pub fn liquid_sessions(&self) -> Result<Vec<Session>, String> {
+        self.sessions(&self.liquid_hours)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the market is open at `time`, according to `trading_hours`.
+    /// Like the other schedule queries it treats hours that can not be
+    /// parsed as closed; use `trading_sessions` to tell these apart.
+    pub fn is_open_at<T: TimeZone>(&self, time: &DateTime<T>) -> bool {
+        session_at(&self.trading_sessions().unwrap_or_default(), time).is_some()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The next time after `time` the market opens. Only the days sent with
+    /// the details, usually about a week, are known.
+    pub fn next_open<T: TimeZone>(&self, time: &DateTime<T>) -> Option<DateTime<Tz>> {
+        next_session(&self.trading_sessions().unwrap_or_default(), time).map(|session| session.open)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The close of the session open at `time` or else of the next one
+    pub fn next_close<T: TimeZone>(&self, time: &DateTime<T>) -> Option<DateTime<Tz>> {
+        let sessions = self.trading_sessions().unwrap_or_default();
+        session_at(&sessions, time)
+            .or_else(|| next_session(&sessions, time))
+            .map(|session| session.close)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The time left from `time` until the market closes, if it is open
+    pub fn time_until_close<T: TimeZone>(&self, time: &DateTime<T>) -> Option<Duration> {
+        session_at(&self.trading_sessions().unwrap_or_default(), time)
+            .map(|session| session.close - time.with_timezone(&session.close.timezone()))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn sessions(&self, hours: &str) -> Result<Vec<Session>, String> {
+        let time_zone = self
+            .time_zone()
+            .ok_or_else(|| format!("Unknown time zone: {}", self.time_zone_id))?;
+        parse_trading_hours(hours, &time_zone)
+    }
 }
 
 impl Display for ContractDetails {
@@ -513,6 +1323,10 @@ impl Display for ContractDetails {
             sec_id_list: {}\n\
             real_expiration_date: {}\n\
             last_trade_time: {}\n\
+            stock_type: {}\n\
+            min_size: {}\n\
+            size_increment: {}\n\
+            suggested_size_increment: {}\n\
             cusip: {}\n\
             ratings: {}\n\
             desc_append: {}\n\
@@ -557,6 +1371,10 @@ impl Display for ContractDetails {
                 .join(","),
             self.real_expiration_date,
             self.last_trade_time,
+            self.stock_type,
+            self.min_size,
+            self.size_increment,
+            self.suggested_size_increment,
             self.cusip,
             self.ratings,
             self.desc_append,
@@ -577,20 +1395,112 @@ impl Display for ContractDetails {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-#[serde(default)]
+/// The unique contract of the details returned for `query`, see
+/// `EClient::resolve_contract`. Details of the same contract that were
+/// returned more than once count as one.
+pub fn unique_contract(
+    query: &Contract,
+    details: Vec<ContractDetails>,
+) -> Result<Contract, ResolveContractError> {
+    let mut contracts: Vec<Contract> = details
+        .into_iter()
+        .map(|details| details.contract)
+        .collect();
+    contracts.sort_by_key(|contract| contract.con_id);
+    contracts.dedup_by_key(|contract| contract.con_id);
+    match contracts.len() {
+        0 => Err(ResolveContractError::NotFound(Box::new(query.clone()))),
+        1 => Ok(contracts.remove(0)),
+        _ => Err(ResolveContractError::Ambiguous(contracts)),
+    }
+}
+
+//==================================================================================================
+/// The bond fields of `ContractDetails` with dates and ratings parsed.
+/// Dates that are missing or can not be parsed are `None`.
+///
+/// ratings - the ratings of the agencies, e.g. `["AA+", "Aaa"]` for `AA+/Aaa`
+/// coupon - the annual coupon in percent
+/// next_option_type - `Call` or `Put` for the option at `next_option_date`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BondDetails {
+    pub cusip: String,
+    pub ratings: Vec<String>,
+    pub bond_type: String,
+    pub coupon_type: String,
+    pub coupon: f64,
+    pub callable: bool,
+    pub putable: bool,
+    pub convertible: bool,
+    pub maturity: Option<NaiveDate>,
+    pub issue_date: Option<NaiveDate>,
+    pub next_option_date: Option<NaiveDate>,
+    pub next_option_type: String,
+    pub next_option_partial: bool,
+    pub notes: String,
+}
+
+impl BondDetails {
+    /// The years from `today` to maturity, counting days as 1/365.25 year
+    pub fn years_to_maturity(&self, today: NaiveDate) -> Option<f64> {
+        self.maturity
+            .map(|maturity| (maturity - today).num_days() as f64 / 365.25)
+    }
+}
+
+impl Display for BondDetails {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let date = |date: &Option<NaiveDate>| date.map(|date| date.to_string()).unwrap_or_default();
+        write!(
+            f,
+            "cusip: {}, ratings: {}, coupon: {}, maturity: {}, issue_date: {}, callable: {}, \
+             putable: {}, convertible: {}, next_option_date: {}",
+            self.cusip,
+            self.ratings.join("/"),
+            self.coupon,
+            date(&self.maturity),
+            date(&self.issue_date),
+            self.callable,
+            self.putable,
+            self.convertible,
+            date(&self.next_option_date),
+        )
+    }
+}
+
+//==================================================================================================
+/// Parses the dates of bond details, sent as `YYYYMMDD` or `MM/DD/YYYY`
+fn parse_bond_date(date: &str) -> Option<NaiveDate> {
+    let date = date.split_whitespace().next()?;
+    NaiveDate::parse_from_str(date, "%Y%m%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%m/%d/%Y"))
+        .ok()
+}
+
+//==================================================================================================
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct ContractDescription {
     pub contract: Contract,
-    pub derivative_sec_types: Vec<String>, // type: list of strings
+    pub derivative_sec_types: Vec<SecType>,
 }
 
 impl ContractDescription {
-    pub fn new(contract: Contract, derivative_sec_types: Vec<String>) -> Self {
+    pub fn new(contract: Contract, derivative_sec_types: Vec<SecType>) -> Self {
         ContractDescription {
             contract,
             derivative_sec_types,
         }
     }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether derivatives of the type, e.g. options, are listed on the
+    /// contract
+    pub fn has_derivatives(&self, sec_type: &SecType) -> bool {
+        self.derivative_sec_types.contains(sec_type)
+    }
 }
 
 impl Display for ContractDescription {
@@ -602,9 +1512,35 @@ impl Display for ContractDescription {
             self.contract,
             self.derivative_sec_types
                 .iter()
-                .map(|x| { x.to_owned() })
+                .map(|x| x.to_string())
                 .collect::<Vec<String>>()
                 .join(",")
         )
     }
 }
+
+//==================================================================================================
+/// Event of a matching symbols request fetched through a channel instead of
+/// the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum SymbolSamplesEvent {
+    /// The matching contracts, all sent in one message
+    Descriptions(Vec<ContractDescription>),
+    Error {
+        code: i32,
+        message: String,
+    },
+}
+
+//==================================================================================================
+/// Event of a contract details request fetched through a channel instead of
+/// the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum ContractDetailsEvent {
+    /// Details of one matching contract
+    Details(Box<ContractDetails>),
+    /// All matching contracts have been received
+    End,
+    /// The request failed, e.g. because no contract matched
+    Error { code: i32, message: String },
+}
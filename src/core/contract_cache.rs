@@ -0,0 +1,242 @@
+//! Cache of contract details, optionally persisted to a file
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::fs;
+use std::io;
+#[cfg(feature = "serde")]
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::contract::{Contract, ContractDetails};
+use crate::core::errors::IbkrError;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// The key of a contract details request. Contracts with a `con_id` are
+/// keyed by it alone, others by their other fields, trimmed and in upper
+/// case, so that e.g. `aapl` and `AAPL` share an entry.
+pub fn request_key(contract: &Contract) -> String {
+    if contract.con_id > 0 {
+        return contract.identity();
+    }
+    format!(
+        "{}|{}",
+        contract.identity(),
+        contract.include_expired.to_string().to_uppercase()
+    )
+}
+
+//==================================================================================================
+/// The details returned for a request and when they were received
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CachedContractDetails {
+    pub fetched: DateTime<Utc>,
+    pub details: Vec<ContractDetails>,
+}
+
+//==================================================================================================
+/// Memoizes the results of `EClient::fetch_contract_details`, both by the
+/// request and by the `con_id` of each returned contract. Entries older
+/// than the time to live are ignored and replaced on the next fetch.
+///
+/// A cache opened with `ContractCache::open` writes its entries to a JSON
+/// file whenever they change, so they survive restarts of the process. This
+/// needs the `serde` feature.
+#[derive(Debug)]
+pub struct ContractCache {
+    time_to_live: chrono::Duration,
+    requests: HashMap<String, CachedContractDetails>,
+    con_ids: HashMap<i32, (DateTime<Utc>, ContractDetails)>,
+    #[cfg(feature = "serde")]
+    path: Option<PathBuf>,
+}
+
+impl ContractCache {
+    /// A cache that is only kept in memory
+    pub fn new(time_to_live: chrono::Duration) -> Self {
+        ContractCache {
+            time_to_live,
+            requests: HashMap::new(),
+            con_ids: HashMap::new(),
+            #[cfg(feature = "serde")]
+            path: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A cache persisted to the file at `path`, loading the entries stored
+    /// there if the file exists
+    #[cfg(feature = "serde")]
+    pub fn open<P: AsRef<Path>>(path: P, time_to_live: chrono::Duration) -> io::Result<Self> {
+        let mut cache = ContractCache::new(time_to_live);
+        cache.path = Some(path.as_ref().to_path_buf());
+        match fs::read(path.as_ref()) {
+            Ok(contents) => {
+                let requests: HashMap<String, CachedContractDetails> =
+                    serde_json::from_slice(&contents)
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                for cached in requests.values() {
+                    cache.index(cached);
+                }
+                cache.requests = requests;
+            }
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        Ok(cache)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn time_to_live(&self) -> chrono::Duration {
+        self.time_to_live
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The number of cached requests, including expired ones
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The details of a request that have not expired. Contracts with a
+    /// `con_id` are also found if they were returned by another request.
+    pub fn get(&self, contract: &Contract) -> Option<Vec<ContractDetails>> {
+        if let Some(details) = self.get_by_con_id(contract.con_id) {
+            return Some(vec![details.clone()]);
+        }
+        self.requests
+            .get(&request_key(contract))
+            .filter(|cached| self.is_fresh(&cached.fetched))
+            .map(|cached| cached.details.clone())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The details of a contract that have not expired
+    pub fn get_by_con_id(&self, con_id: i32) -> Option<&ContractDetails> {
+        self.con_ids
+            .get(&con_id)
+            .filter(|(fetched, _)| self.is_fresh(fetched))
+            .map(|(_, details)| details)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Stores the details returned for a request
+    pub fn insert(&mut self, contract: &Contract, details: Vec<ContractDetails>) -> io::Result<()> {
+        let cached = CachedContractDetails {
+            fetched: Utc::now(),
+            details,
+        };
+        self.index(&cached);
+        self.requests.insert(request_key(contract), cached);
+        self.save()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Drops the expired entries
+    pub fn remove_expired(&mut self) -> io::Result<()> {
+        let now = Utc::now();
+        let time_to_live = self.time_to_live;
+        self.requests
+            .retain(|_, cached| now - cached.fetched < time_to_live);
+        self.con_ids
+            .retain(|_, (fetched, _)| now - *fetched < time_to_live);
+        self.save()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.requests.clear();
+        self.con_ids.clear();
+        self.save()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The cached details of a request or, if there are none, the details
+    /// fetched from TWS, which are then cached. Requests that return no
+    /// details are not cached.
+    ///
+    /// # Arguments
+    /// * client - a connected client
+    /// * request_id - the id of the request, if one is sent
+    /// * timeout - how long to wait for each of the details
+    pub fn fetch<T>(
+        &mut self,
+        client: &Mutex<EClient<T>>,
+        request_id: i32,
+        contract: &Contract,
+        timeout: Duration,
+    ) -> Result<Vec<ContractDetails>, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        if let Some(details) = self.get(contract) {
+            return Ok(details);
+        }
+        let details = client
+            .lock()
+            .expect(POISONED_MUTEX)
+            .fetch_contract_details(request_id, contract, timeout)?;
+        if !details.is_empty() {
+            self.insert(contract, details.clone())?;
+        }
+        Ok(details)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn is_fresh(&self, fetched: &DateTime<Utc>) -> bool {
+        Utc::now() - *fetched < self.time_to_live
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn index(&mut self, cached: &CachedContractDetails) {
+        for details in &cached.details {
+            let older = matches!(
+                self.con_ids.get(&details.contract.con_id),
+                Some((fetched, _)) if *fetched > cached.fetched
+            );
+            if details.contract.con_id > 0 && !older {
+                self.con_ids
+                    .insert(details.contract.con_id, (cached.fetched, details.clone()));
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "serde")]
+    fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let contents = serde_json::to_vec(&self.requests)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write to a temporary file first so an interrupted write does not
+        // destroy the cache
+        let mut temp_path = path.clone().into_os_string();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, path)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only caches opened with `open` are persisted
+    #[cfg(not(feature = "serde"))]
+    fn save(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+//! CSV export of bars, ticks and executions
+//!
+//! Every file starts with a header row. Times are written as RFC 3339 UTC
+//! timestamps, e.g. `2023-01-03T14:30:00Z`. Fields are quoted as described
+//! in RFC 4180 where needed.
+use std::io::{self, Write};
+
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::core::common::BarData;
+use crate::core::contract::Contract;
+pub use crate::core::execution::parse_execution_time;
+use crate::core::execution::Execution;
+use crate::core::historical::HistoricalTicks;
+use crate::core::historical_downloader::bar_time;
+use crate::core::tick_recorder::RecordedTick;
+use crate::core::trade_report::TradeSummary;
+
+pub const BAR_HEADER: [&str; 8] = [
+    "time",
+    "open",
+    "high",
+    "low",
+    "close",
+    "volume",
+    "bar_count",
+    "average",
+];
+
+pub const TRADE_TICK_HEADER: [&str; 7] = [
+    "time",
+    "price",
+    "size",
+    "exchange",
+    "special_conditions",
+    "past_limit",
+    "unreported",
+];
+
+pub const BID_ASK_TICK_HEADER: [&str; 7] = [
+    "time",
+    "price_bid",
+    "price_ask",
+    "size_bid",
+    "size_ask",
+    "bid_past_low",
+    "ask_past_high",
+];
+
+pub const MIDPOINT_TICK_HEADER: [&str; 3] = ["time", "price", "size"];
+
+pub const RECORDED_TICK_HEADER: [&str; 5] = ["time", "ticker_id", "tick_type", "price", "size"];
+
+pub const EXECUTION_HEADER: [&str; 20] = [
+    "time",
+    "exec_id",
+    "account",
+    "con_id",
+    "symbol",
+    "sec_type",
+    "local_symbol",
+    "currency",
+    "exchange",
+    "side",
+    "shares",
+    "price",
+    "cum_qty",
+    "avg_price",
+    "order_id",
+    "perm_id",
+    "client_id",
+    "order_ref",
+    "liquidation",
+    "last_liquidity",
+];
+
+pub const TRADE_SUMMARY_HEADER: [&str; 13] = [
+    "name",
+    "currency",
+    "executions",
+    "bought",
+    "sold",
+    "volume",
+    "notional",
+    "realized_pnl",
+    "commissions",
+    "net_pnl",
+    "slippage",
+    "slippage_bps",
+    "arrival_notional",
+];
+
+//==================================================================================================
+/// Writes rows of fields, quoting fields that contain a separator, a quote
+/// or a line break
+#[derive(Debug)]
+pub struct CsvWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        CsvWriter { writer }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn write_row<S: AsRef<str>>(&mut self, fields: &[S]) -> io::Result<()> {
+        for (index, field) in fields.iter().enumerate() {
+            if index > 0 {
+                self.writer.write_all(b",")?;
+            }
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                write!(self.writer, "\"{}\"", field.replace('"', "\"\""))?;
+            } else {
+                self.writer.write_all(field.as_bytes())?;
+            }
+        }
+        self.writer.write_all(b"\r\n")
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+//==================================================================================================
+/// Formats a time as RFC 3339 in UTC, with fractional seconds only if
+/// needed
+pub fn format_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+//==================================================================================================
+fn format_epoch(seconds: i32) -> String {
+    Utc.timestamp_opt(seconds as i64, 0)
+        .single()
+        .map(|time| format_time(&time))
+        .unwrap_or_default()
+}
+
+//==================================================================================================
+/// Writes bars requested with dates as seconds since the epoch or as
+/// `YYYYMMDD`. Bars with other dates are written with their date as
+/// received.
+pub fn write_bars<W: Write>(writer: W, bars: &[BarData]) -> io::Result<()> {
+    let mut writer = CsvWriter::new(writer);
+    writer.write_row(&BAR_HEADER)?;
+    for bar in bars {
+        let time = bar_time(bar).map_or_else(|| bar.date.clone(), |time| format_time(&time));
+        writer.write_row(&[
+            time,
+            bar.open.to_string(),
+            bar.high.to_string(),
+            bar.low.to_string(),
+            bar.close.to_string(),
+            bar.volume.to_string(),
+            bar.bar_count.to_string(),
+            bar.average.to_string(),
+        ])?;
+    }
+    writer.flush()
+}
+
+//==================================================================================================
+/// Writes historical ticks with the header of their kind
+pub fn write_historical_ticks<W: Write>(writer: W, ticks: &HistoricalTicks) -> io::Result<()> {
+    let mut writer = CsvWriter::new(writer);
+    match ticks {
+        HistoricalTicks::Trades(ticks) => {
+            writer.write_row(&TRADE_TICK_HEADER)?;
+            for tick in ticks {
+                writer.write_row(&[
+                    format_epoch(tick.time),
+                    tick.price.to_string(),
+                    tick.size.to_string(),
+                    tick.exchange.clone(),
+                    tick.special_conditions.clone(),
+                    tick.tick_attrib_last.past_limit.to_string(),
+                    tick.tick_attrib_last.unreported.to_string(),
+                ])?;
+            }
+        }
+        HistoricalTicks::BidAsk(ticks) => {
+            writer.write_row(&BID_ASK_TICK_HEADER)?;
+            for tick in ticks {
+                writer.write_row(&[
+                    format_epoch(tick.time),
+                    tick.price_bid.to_string(),
+                    tick.price_ask.to_string(),
+                    tick.size_bid.to_string(),
+                    tick.size_ask.to_string(),
+                    tick.tick_attrib_bid_ask.bid_past_low.to_string(),
+                    tick.tick_attrib_bid_ask.ask_past_high.to_string(),
+                ])?;
+            }
+        }
+        HistoricalTicks::Midpoint(ticks) => {
+            writer.write_row(&MIDPOINT_TICK_HEADER)?;
+            for tick in ticks {
+                writer.write_row(&[
+                    format_epoch(tick.time),
+                    tick.price.to_string(),
+                    tick.size.to_string(),
+                ])?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+//==================================================================================================
+/// Writes recorded live ticks. Price ticks leave the size empty and vice
+/// versa.
+pub fn write_recorded_ticks<W: Write>(writer: W, ticks: &[RecordedTick]) -> io::Result<()> {
+    let mut writer = CsvWriter::new(writer);
+    writer.write_row(&RECORDED_TICK_HEADER)?;
+    for tick in ticks {
+        writer.write_row(&[
+            format_time(&tick.time),
+            tick.ticker_id.to_string(),
+            tick.tick_type.to_string(),
+            tick.price
+                .map(|price| price.to_string())
+                .unwrap_or_default(),
+            tick.size.map(|size| size.to_string()).unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()
+}
+
+//==================================================================================================
+/// Writes executions as received by `Wrapper::exec_details`
+///
+/// # Arguments
+/// * executions - the executions and their contracts
+/// * default_time_zone - the time zone of execution times without one,
+///   i.e. the time zone set in TWS
+pub fn write_executions<W: Write>(
+    writer: W,
+    executions: &[(Contract, Execution)],
+    default_time_zone: Tz,
+) -> io::Result<()> {
+    let mut writer = CsvWriter::new(writer);
+    writer.write_row(&EXECUTION_HEADER)?;
+    for (contract, execution) in executions {
+        let time = parse_execution_time(&execution.time, default_time_zone)
+            .map_or_else(|| execution.time.clone(), |time| format_time(&time));
+        writer.write_row(&[
+            time,
+            execution.exec_id.clone(),
+            execution.acct_number.clone(),
+            contract.con_id.to_string(),
+            contract.symbol.clone(),
+            contract.sec_type.to_string(),
+            contract.local_symbol.clone(),
+            contract.currency.clone(),
+            execution.exchange.clone(),
+            execution.side.clone(),
+            execution.shares.to_string(),
+            execution.price.to_string(),
+            execution.cum_qty.to_string(),
+            execution.avg_price.to_string(),
+            execution.order_id.to_string(),
+            execution.perm_id.to_string(),
+            execution.client_id.to_string(),
+            execution.order_ref.clone(),
+            execution.liquidation.to_string(),
+            execution.last_liquidity.to_string(),
+        ])?;
+    }
+    writer.flush()
+}
+
+//==================================================================================================
+/// Writes the summaries of a `TradeReport`, e.g. its `by_symbol` or
+/// `by_strategy` summaries. The slippage in basis points is left empty for
+/// summaries without arrival prices.
+pub fn write_trade_summaries<W: Write>(writer: W, summaries: &[TradeSummary]) -> io::Result<()> {
+    let mut writer = CsvWriter::new(writer);
+    writer.write_row(&TRADE_SUMMARY_HEADER)?;
+    for summary in summaries {
+        writer.write_row(&[
+            summary.name.clone(),
+            summary.currency.clone(),
+            summary.executions.to_string(),
+            summary.bought.to_string(),
+            summary.sold.to_string(),
+            summary.volume().to_string(),
+            summary.notional.to_string(),
+            summary.realized_pnl.to_string(),
+            summary.commissions.to_string(),
+            summary.net_pnl().to_string(),
+            summary.slippage.to_string(),
+            summary
+                .slippage_bps()
+                .map(|bps| bps.to_string())
+                .unwrap_or_default(),
+            summary.arrival_notional.to_string(),
+        ])?;
+    }
+    writer.flush()
+}
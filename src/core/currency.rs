@@ -0,0 +1,358 @@
+//! Conversion of cash values between currencies at live FX rates
+//!
+//! `CurrencyConverter` keeps the latest rate of every currency pair it is
+//! given, either set directly, taken from IDEALPRO market data
+//! subscriptions or from snapshots. Conversions use the pair itself, its
+//! inverse or a cross through USD, and carry the time of the oldest rate
+//! they used, so stale conversions can be told apart.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::contract::Contract;
+use crate::core::errors::IbkrError;
+use crate::core::wrapper::Wrapper;
+
+/// The currency crosses go through
+pub const CROSS_CURRENCY: &str = "USD";
+
+/// The major currencies in the order IDEALPRO quotes them: the one that
+/// comes first is the base currency of a pair, e.g. EUR.USD and USD.JPY.
+/// Other currencies are quoted against any of these.
+const PAIR_PRECEDENCE: [&str; 8] = ["EUR", "GBP", "AUD", "NZD", "USD", "CAD", "CHF", "JPY"];
+
+//==================================================================================================
+/// The base and quote currency of the IDEALPRO pair of two currencies, e.g.
+/// `("EUR", "USD")` for USD and EUR
+pub fn fx_pair(currency: &str, other: &str) -> (String, String) {
+    let rank = |currency: &str| {
+        PAIR_PRECEDENCE
+            .iter()
+            .position(|major| *major == currency)
+            .unwrap_or(PAIR_PRECEDENCE.len())
+    };
+    let (currency, other) = (currency.to_uppercase(), other.to_uppercase());
+    if (rank(&currency), &currency) <= (rank(&other), &other) {
+        (currency, other)
+    } else {
+        (other, currency)
+    }
+}
+
+//==================================================================================================
+/// The IDEALPRO contract of a currency pair
+pub fn fx_contract(base: &str, quote: &str) -> Contract {
+    Contract::forex(&format!("{}{}", base, quote))
+}
+
+//==================================================================================================
+/// The rate of a currency pair: the price of one unit of the base currency
+/// in the quote currency
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FxRate {
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+    pub time: DateTime<Utc>,
+}
+
+impl FxRate {
+    pub fn new(base: &str, quote: &str, rate: f64, time: DateTime<Utc>) -> Self {
+        FxRate {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            rate,
+            time,
+        }
+    }
+}
+
+impl fmt::Display for FxRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}: {} at {}",
+            self.base,
+            self.quote,
+            self.rate,
+            self.time.to_rfc3339()
+        )
+    }
+}
+
+//==================================================================================================
+/// An amount converted into another currency
+///
+/// rate - the units of `currency` per unit of the original currency
+/// time - the time of the oldest rate the conversion used
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Conversion {
+    pub amount: f64,
+    pub currency: String,
+    pub rate: f64,
+    pub time: DateTime<Utc>,
+}
+
+impl fmt::Display for Conversion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} at {} ({})",
+            self.amount,
+            self.currency,
+            self.rate,
+            self.time.to_rfc3339()
+        )
+    }
+}
+
+//==================================================================================================
+/// The latest FX rates and the market data subscriptions they come from
+#[derive(Clone, Debug, Default)]
+pub struct CurrencyConverter {
+    rates: HashMap<(String, String), FxRate>,
+    tickers: BTreeMap<i32, (String, String)>,
+}
+
+impl CurrencyConverter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the rate of a currency pair. Non-positive rates are ignored.
+    pub fn set_rate(&mut self, base: &str, quote: &str, rate: f64, time: DateTime<Utc>) {
+        if rate > 0.0 {
+            self.rates.insert(
+                (base.to_string(), quote.to_string()),
+                FxRate::new(base, quote, rate, time),
+            );
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The pairs of the rates that are known
+    pub fn rates(&self) -> impl Iterator<Item = &FxRate> {
+        self.rates.values()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes the rate of a pair from a market data subscription, see
+    /// update_from_quote()
+    pub fn track(&mut self, ticker_id: i32, base: &str, quote: &str) {
+        self.tickers
+            .insert(ticker_id, (base.to_string(), quote.to_string()));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn untrack(&mut self, ticker_id: i32) {
+        self.tickers.remove(&ticker_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ticker ids of the tracked market data subscriptions
+    pub fn tickers(&self) -> Vec<i32> {
+        self.tickers.keys().copied().collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Updates the rate of a tracked subscription with the midpoint of its
+    /// quote, or the last price if bid or ask are missing. Returns whether
+    /// the rate was updated.
+    pub fn update_from_quote(
+        &mut self,
+        ticker_id: i32,
+        bid: Option<f64>,
+        ask: Option<f64>,
+        last: Option<f64>,
+        time: DateTime<Utc>,
+    ) -> bool {
+        let (base, quote) = match self.tickers.get(&ticker_id) {
+            Some(pair) => pair.clone(),
+            None => return false,
+        };
+        let rate = match (bid, ask) {
+            (Some(bid), Some(ask)) if bid > 0.0 && ask > 0.0 => (bid + ask) / 2.0,
+            _ => match last {
+                Some(last) if last > 0.0 => last,
+                _ => return false,
+            },
+        };
+        self.set_rate(&base, &quote, rate, time);
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The rate to convert `from` into `to`, from the pair, its inverse or a
+    /// cross through `CROSS_CURRENCY`. The rate of a currency to itself is 1
+    /// at the current time.
+    pub fn rate(&self, from: &str, to: &str) -> Option<(f64, DateTime<Utc>)> {
+        if from == to {
+            return Some((1.0, Utc::now()));
+        }
+        if let Some(rate) = self.direct_rate(from, to) {
+            return Some(rate);
+        }
+        let (to_cross, to_cross_time) = self.direct_rate(from, CROSS_CURRENCY)?;
+        let (from_cross, from_cross_time) = self.direct_rate(CROSS_CURRENCY, to)?;
+        Some((to_cross * from_cross, to_cross_time.min(from_cross_time)))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Converts an amount, if the rate is known
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Option<Conversion> {
+        self.rate(from, to).map(|(rate, time)| Conversion {
+            amount: amount * rate,
+            currency: to.to_string(),
+            rate,
+            time,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Converts amounts per currency, e.g. the exposure of a `Portfolio`,
+    /// into one currency and adds them up. Fails with the currencies whose
+    /// rate is not known.
+    pub fn convert_sum(
+        &self,
+        amounts: &BTreeMap<String, f64>,
+        to: &str,
+    ) -> Result<Conversion, Vec<String>> {
+        let mut total = Conversion {
+            amount: 0.0,
+            currency: to.to_string(),
+            rate: 1.0,
+            time: Utc::now(),
+        };
+        let mut missing = vec![];
+        for (currency, amount) in amounts {
+            match self.convert(*amount, currency, to) {
+                Some(conversion) => {
+                    total.amount += conversion.amount;
+                    total.time = total.time.min(conversion.time);
+                }
+                None => missing.push(currency.clone()),
+            }
+        }
+        if missing.is_empty() {
+            Ok(total)
+        } else {
+            Err(missing)
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The pairs to subscribe to in order to convert the currencies into
+    /// `to`: each currency directly against `to`
+    pub fn required_pairs(currencies: &[&str], to: &str) -> Vec<(String, String)> {
+        currencies
+            .iter()
+            .filter(|currency| **currency != to)
+            .map(|currency| fx_pair(currency, to))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn direct_rate(&self, from: &str, to: &str) -> Option<(f64, DateTime<Utc>)> {
+        if let Some(rate) = self.rates.get(&(from.to_string(), to.to_string())) {
+            return Some((rate.rate, rate.time));
+        }
+        self.rates
+            .get(&(to.to_string(), from.to_string()))
+            .map(|rate| (1.0 / rate.rate, rate.time))
+    }
+}
+
+//==================================================================================================
+/// Subscribes to the market data of the pairs needed to convert the
+/// currencies into `to` and tracks them in the converter. Call
+/// `refresh_rates` to take the latest quotes. Returns the ticker ids used,
+/// counting up from `first_ticker_id`.
+pub fn subscribe_rates<T>(
+    client: &Mutex<EClient<T>>,
+    converter: &mut CurrencyConverter,
+    currencies: &[&str],
+    to: &str,
+    first_ticker_id: i32,
+) -> Result<Vec<i32>, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut client = client.lock().expect(POISONED_MUTEX);
+    let mut ticker_ids = vec![];
+    for (ticker_id, (base, quote)) in
+        (first_ticker_id..).zip(CurrencyConverter::required_pairs(currencies, to))
+    {
+        client.request_market_data(
+            ticker_id,
+            &fx_contract(&base, &quote),
+            "",
+            false,
+            false,
+            &[],
+        )?;
+        converter.track(ticker_id, &base, &quote);
+        ticker_ids.push(ticker_id);
+    }
+    Ok(ticker_ids)
+}
+
+//==================================================================================================
+/// Updates the rates of the tracked subscriptions from the client's latest
+/// quotes. Returns the number of rates updated.
+pub fn refresh_rates<T>(client: &Mutex<EClient<T>>, converter: &mut CurrencyConverter) -> usize
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let client = client.lock().expect(POISONED_MUTEX);
+    let now = Utc::now();
+    converter
+        .tickers()
+        .into_iter()
+        .filter(|ticker_id| match client.quote(*ticker_id) {
+            Some(quote) => {
+                converter.update_from_quote(*ticker_id, quote.bid, quote.ask, quote.last, now)
+            }
+            None => false,
+        })
+        .count()
+}
+
+//==================================================================================================
+/// Sets the rates of the pairs needed to convert the currencies into `to`
+/// from market data snapshots, without keeping subscriptions
+///
+/// # Arguments
+/// * first_request_id - the id of the first snapshot; the ids that follow
+///   are used for the other pairs
+/// * timeout - how long to wait for each snapshot
+pub fn fetch_rates<T>(
+    client: &Mutex<EClient<T>>,
+    converter: &mut CurrencyConverter,
+    currencies: &[&str],
+    to: &str,
+    first_request_id: i32,
+    timeout: Duration,
+) -> Result<(), IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut client = client.lock().expect(POISONED_MUTEX);
+    for (request_id, (base, quote)) in
+        (first_request_id..).zip(CurrencyConverter::required_pairs(currencies, to))
+    {
+        let rate = client.fetch_spot_price(request_id, &fx_contract(&base, &quote), timeout)?;
+        converter.set_rate(&base, &quote, rate, Utc::now());
+    }
+    Ok(())
+}
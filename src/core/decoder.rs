@@ -1,33 +1,76 @@
+#![allow(clippy::too_many_arguments)]
 //! Receives messages from Reader, decodes messages, and feeds them to Wrapper
+#[cfg(feature = "wsh")]
+use crate::core::wsh::WshEvent;
 use crate::core::{
+    account_summary_tags::{AccountSummaryEvent, AccountSummaryValue},
+    account_updates::{
+        AccountUpdateEvent, AccountUpdateMultiEvent, AccountValue, ModelAccountValue, PortfolioItem,
+    },
+    alert_hooks::AlertHooks,
     client::ConnStatus,
     common::{
-        BarData, CommissionReport, DepthMktDataDescription, FamilyCode, HistogramData,
-        HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider, PriceIncrement,
-        RealTimeBar, SmartComponent, TagValue, TickAttrib, TickAttribBidAsk, TickAttribLast,
-        TickType, MAX_MSG_LEN, NO_VALID_ID, UNSET_DOUBLE, UNSET_INTEGER,
+        BarData, CommissionReport, DataFarmStatus, DepthMktDataDescription, FaDataType, FamilyCode,
+        HistogramData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider,
+        PriceIncrement, RealTimeBar, SmartComponent, TagValue, TickAttrib, TickAttribBidAsk,
+        TickAttribLast, TickByTickType, TickType, MAX_MSG_LEN, NO_VALID_ID, UNSET_DOUBLE,
+        UNSET_INTEGER,
+    },
+    conflation::{ConflatedTick, TickConflator},
+    contract::{
+        Contract, ContractDescription, ContractDetails, ContractDetailsEvent, DeltaNeutralContract,
+        Right, SymbolSamplesEvent,
     },
-    contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract},
-    errors::{IBKRApiLibError, TwsError},
+    display_groups::{parse_display_groups, DisplayGroupContract},
+    error_codes::{is_warning, ORDER_MESSAGE},
+    errors::{DecodeError, DecodeErrorReason, IbkrError, TwsError},
     execution::Execution,
-    messages::{read_fields, IncomingMessageIds},
+    historical::{
+        is_retryable_historical_error, HistoricalDataEvent, HistoricalSchedule, HistoricalTicks,
+        HistoricalTicksEvent,
+    },
+    managed_accounts::ManagedAccounts,
+    margin::WhatIfEvent,
+    market_rules::{MarketRule, MarketRuleCache},
+    messages::{
+        begin_message, finish_message, split_fields, write_field, IncomingMessageIds,
+        OutgoingMessageIds,
+    },
+    news::{
+        parse_historical_news_time, NewsArticle, NewsArticleType, NewsBulletin, NewsBulletinType,
+        NewsHeadline,
+    },
+    option_chain::{OptionChainEvent, OptionChainParameters},
     order::{Order, OrderState, SoftDollarTier},
+    order_book::DepthUpdate,
     order_decoder::OrderDecoder,
+    order_journal::{JournalEntry, JournalFill, OrderJournal},
+    order_tracker::OrderTracker,
+    pnl::{PnlEvent, PnlSingleEvent, ProfitAndLoss, SingleProfitAndLoss},
+    positions::{Position, PositionEvent},
+    quote_cache::{is_auction_tick, QuoteCache, TradingStatus},
     scanner::ScanData,
     server_versions::{
-        MIN_SERVER_VER_AGG_GROUP, MIN_SERVER_VER_FRACTIONAL_POSITIONS,
-        MIN_SERVER_VER_LAST_LIQUIDITY, MIN_SERVER_VER_MARKET_CAP_PRICE,
-        MIN_SERVER_VER_MARKET_RULES, MIN_SERVER_VER_MD_SIZE_MULTIPLIER,
-        MIN_SERVER_VER_MODELS_SUPPORT, MIN_SERVER_VER_ORDER_CONTAINER, MIN_SERVER_VER_PAST_LIMIT,
-        MIN_SERVER_VER_PRE_OPEN_BID_ASK, MIN_SERVER_VER_REALIZED_PNL,
+        MIN_SERVER_VER_ADVANCED_ORDER_REJECT, MIN_SERVER_VER_AGG_GROUP,
+        MIN_SERVER_VER_ENCODE_MSG_ASCII7, MIN_SERVER_VER_FRACTIONAL_POSITIONS,
+        MIN_SERVER_VER_FRACTIONAL_SIZE_SUPPORT, MIN_SERVER_VER_LAST_LIQUIDITY,
+        MIN_SERVER_VER_MARKET_CAP_PRICE, MIN_SERVER_VER_MARKET_RULES,
+        MIN_SERVER_VER_MD_SIZE_MULTIPLIER, MIN_SERVER_VER_MODELS_SUPPORT,
+        MIN_SERVER_VER_ORDER_CONTAINER, MIN_SERVER_VER_PAST_LIMIT, MIN_SERVER_VER_PRE_OPEN_BID_ASK,
+        MIN_SERVER_VER_PRICE_BASED_VOLATILITY, MIN_SERVER_VER_REALIZED_PNL,
         MIN_SERVER_VER_REAL_EXPIRATION_DATE, MIN_SERVER_VER_SERVICE_DATA_TYPE,
-        MIN_SERVER_VER_SMART_DEPTH, MIN_SERVER_VER_SYNT_REALTIME_BARS,
-        MIN_SERVER_VER_UNDERLYING_INFO, MIN_SERVER_VER_UNREALIZED_PNL,
+        MIN_SERVER_VER_SIZE_RULES, MIN_SERVER_VER_SMART_DEPTH, MIN_SERVER_VER_STOCK_TYPE,
+        MIN_SERVER_VER_SYNT_REALTIME_BARS, MIN_SERVER_VER_UNDERLYING_INFO,
+        MIN_SERVER_VER_UNREALIZED_PNL,
     },
+    smart_components::{SmartComponentMap, SmartComponentRegistry},
+    streamer::RequestSender,
+    subscription::StreamRegistry,
     wrapper::Wrapper,
 };
 
 use bigdecimal::BigDecimal;
+use chrono::{TimeZone, Utc};
 use float_cmp::*;
 use log::*;
 use num_traits::{float::FloatCore, FromPrimitive};
@@ -38,72 +81,364 @@ use std::{
     slice::Iter,
     str::FromStr,
     string::ToString,
-    sync::{mpsc::Receiver, Arc, Mutex},
+    sync::{
+        mpsc::{Receiver, RecvError, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Instant,
 };
 
 const WRAPPER_POISONED_MUTEX: &str = "Wrapper mutex was poisoned";
+const CONFLATOR_POISONED_MUTEX: &str = "Tick conflator mutex was poisoned";
+const SMART_COMPONENTS_POISONED_MUTEX: &str = "Smart components mutex was poisoned";
+const MARKET_RULES_POISONED_MUTEX: &str = "Market rules mutex was poisoned";
+const STREAMS_POISONED_MUTEX: &str = "Stream registry mutex was poisoned";
+const QUOTES_POISONED_MUTEX: &str = "Quote cache mutex was poisoned";
+const ORDERS_POISONED_MUTEX: &str = "Order tracker mutex was poisoned";
+const ACCOUNTS_POISONED_MUTEX: &str = "Managed accounts mutex was poisoned";
+const JOURNAL_POISONED_MUTEX: &str = "Order journal mutex was poisoned";
+const ALERTS_POISONED_MUTEX: &str = "Alert hooks mutex was poisoned";
+
+/// The sizes servers send for sizes they do not know
+const UNSET_SIZES: [&str; 3] = [
+    "2147483647",
+    "9223372036854775807",
+    "1.7976931348623157E308",
+];
+//==================================================================================================
+/// Parses a number field with lexical-core, which is faster than
+/// `str::parse` on the decimals TWS sends, see `benches/decode.rs`.
+/// Spellings only `str::parse` accepts, like `inf`, are parsed by it.
+pub fn parse_number<N>(field: &str) -> Option<N>
+where
+    N: lexical_core::FromLexical + FromStr,
+{
+    if field.is_empty() {
+        return None;
+    }
+    lexical_core::parse(field.as_bytes())
+        .ok()
+        .or_else(|| field.parse().ok())
+}
+
 //==================================================================================================
-pub fn decode_i32(iter: &mut Iter<String>) -> Result<i32, IBKRApiLibError> {
-    let next = iter.next();
+/// The next field of a message, an error if the message ends before it
+pub fn decode_field<'a>(iter: &mut Iter<'a, &str>) -> Result<&'a str, IbkrError> {
+    iter.next()
+        .copied()
+        .ok_or_else(|| DecodeError::at(0, DecodeErrorReason::MissingField).into())
+}
+
+//==================================================================================================
+/// Decodes a number field, None if it is empty
+fn decode_number<N>(iter: &mut Iter<&str>) -> Result<Option<N>, IbkrError>
+where
+    N: lexical_core::FromLexical + FromStr,
+{
+    let field = decode_field(iter)?;
+    if field.is_empty() {
+        return Ok(None);
+    }
+    match parse_number(field) {
+        Some(val) => Ok(Some(val)),
+        None => Err(DecodeError::at(
+            iter.len() + 1,
+            DecodeErrorReason::InvalidNumber(field.to_string()),
+        )
+        .into()),
+    }
+}
 
-    let val: i32 = next.unwrap().parse().unwrap_or(0);
-    Ok(val)
+//==================================================================================================
+pub fn decode_i32(iter: &mut Iter<&str>) -> Result<i32, IbkrError> {
+    Ok(decode_number(iter)?.unwrap_or(0))
 }
 
 //==================================================================================================
-pub fn decode_i32_show_unset(iter: &mut Iter<String>) -> Result<i32, IBKRApiLibError> {
-    let next = iter.next();
-    //info!("{:?}", next);
-    let retval: i32 = next.unwrap().parse().unwrap_or(0);
+pub fn decode_i32_show_unset(iter: &mut Iter<&str>) -> Result<i32, IbkrError> {
+    let retval: i32 = decode_number(iter)?.unwrap_or(0);
     Ok(if retval == 0 { UNSET_INTEGER } else { retval })
 }
 
 //==================================================================================================
-pub fn decode_i64(iter: &mut Iter<String>) -> Result<i64, IBKRApiLibError> {
-    let next = iter.next();
-    //info!("{:?}", next);
-    let val: i64 = next.unwrap().parse().unwrap_or(0);
-    Ok(val)
+pub fn decode_i64(iter: &mut Iter<&str>) -> Result<i64, IbkrError> {
+    Ok(decode_number(iter)?.unwrap_or(0))
 }
 
 //==================================================================================================
-pub fn decode_f64(iter: &mut Iter<String>) -> Result<f64, IBKRApiLibError> {
-    let next = iter.next();
-    //info!("{:?}", next);
-    let val = next.unwrap().parse().unwrap_or(0.0);
-    Ok(val)
+pub fn decode_f64(iter: &mut Iter<&str>) -> Result<f64, IbkrError> {
+    Ok(decode_number(iter)?.unwrap_or(0.0))
 }
 
 //==================================================================================================
-pub fn decode_f64_show_unset(iter: &mut Iter<String>) -> Result<f64, IBKRApiLibError> {
-    let next = iter.next();
-    //info!("{:?}", next);
-    let retval: f64 = next.unwrap().parse().unwrap_or(0.0);
+pub fn decode_f64_show_unset(iter: &mut Iter<&str>) -> Result<f64, IbkrError> {
+    let retval: f64 = decode_number(iter)?.unwrap_or(0.0);
     Ok(if retval == 0.0 { UNSET_DOUBLE } else { retval })
 }
 
 //==================================================================================================
-pub fn decode_string(iter: &mut Iter<String>) -> Result<String, IBKRApiLibError> {
-    let next = iter.next();
-    //info!("{:?}", next);
-    let val = next.unwrap().parse().unwrap_or_else(|_| "".to_string());
-    Ok(val)
+/// Decodes a size or volume. Servers from `MIN_SERVER_VER_FRACTIONAL_SIZE_SUPPORT`
+/// on send them as decimals and the maximum integers for sizes they do not
+/// know, which are decoded as `UNSET_DOUBLE`.
+pub fn decode_size(iter: &mut Iter<&str>) -> Result<f64, IbkrError> {
+    let field = decode_field(iter)?;
+    if UNSET_SIZES.contains(&field) {
+        return Ok(UNSET_DOUBLE);
+    }
+    match field {
+        "" => Ok(0.0),
+        _ => parse_number(field).ok_or_else(|| {
+            DecodeError::at(
+                iter.len() + 1,
+                DecodeErrorReason::InvalidNumber(field.to_string()),
+            )
+            .into()
+        }),
+    }
+}
+
+//==================================================================================================
+/// Decodes a decimal field like a position without rounding it to an `f64`
+pub fn decode_decimal(iter: &mut Iter<&str>) -> Result<BigDecimal, IbkrError> {
+    let field = decode_field(iter)?.trim();
+    if field.is_empty() {
+        return Ok(BigDecimal::default());
+    }
+    BigDecimal::from_str(field).map_err(|_| {
+        DecodeError::at(
+            iter.len() + 1,
+            DecodeErrorReason::InvalidNumber(field.to_string()),
+        )
+        .into()
+    })
+}
+
+//==================================================================================================
+/// Decodes a field of an enum that is sent as a number, e.g. a tick type
+pub fn decode_enum<E: FromPrimitive>(iter: &mut Iter<&str>) -> Result<E, IbkrError> {
+    let val = decode_i32(iter)?;
+    E::from_i32(val).ok_or_else(|| {
+        DecodeError::at(
+            iter.len() + 1,
+            DecodeErrorReason::UnknownValue(val.to_string()),
+        )
+        .into()
+    })
+}
+
+//==================================================================================================
+/// The `f64` of a decimal field as `decode_f64` decodes it, for the
+/// `Wrapper` callbacks
+pub(crate) fn decimal_to_f64(val: &BigDecimal) -> f64 {
+    val.to_string().parse().unwrap_or(0.0)
+}
+
+//==================================================================================================
+/// The decimal of a size decoded with `decode_size`, with the digits it was
+/// sent with, for the `Wrapper` callbacks
+pub(crate) fn size_to_decimal(val: f64) -> BigDecimal {
+    BigDecimal::from_str(&val.to_string()).unwrap_or_default()
+}
+
+//==================================================================================================
+pub fn decode_string(iter: &mut Iter<&str>) -> Result<String, IbkrError> {
+    Ok(decode_field(iter)?.to_string())
+}
+
+//==================================================================================================
+/// Replaces the `\uXXXX` escapes servers from `MIN_SERVER_VER_ENCODE_MSG_ASCII7`
+/// on send for non-ASCII characters, e.g. in error messages, with the
+/// characters. Malformed escapes are kept as they are.
+pub fn unescape_unicode(text: &str) -> String {
+    if !text.contains("\\u") {
+        return text.to_string();
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find("\\u") {
+        unescaped.push_str(&rest[..index]);
+        let escape = &rest[index..];
+        match escape
+            .get(2..6)
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32)
+        {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &escape[6..];
+            }
+            None => {
+                unescaped.push_str("\\u");
+                rest = &escape[2..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+//==================================================================================================
+/// Decodes the right of an option. Rights this crate does not know are
+/// decoded as `Right::None`.
+pub fn decode_right(iter: &mut Iter<&str>) -> Result<Right, IbkrError> {
+    Ok(decode_string(iter)?.parse().unwrap_or_default())
+}
+
+//==================================================================================================
+pub fn decode_bool(iter: &mut Iter<&str>) -> Result<bool, IbkrError> {
+    Ok(decode_i32(iter)? != 0)
+}
+
+//==================================================================================================
+/// Decodes an execution details message into the request id, the contract
+/// and the execution
+pub fn decode_execution_data(
+    fields: &[&str],
+    server_version: i32,
+) -> Result<(i32, Contract, Execution), IbkrError> {
+    let mut fields_itr = fields.iter();
+
+    // Throw away message_id.
+    fields_itr.next();
+
+    let mut version = IncomingMessageIds::ExecutionData.version();
+
+    if server_version < MIN_SERVER_VER_LAST_LIQUIDITY {
+        version = decode_i32(&mut fields_itr)?;
+    }
+
+    let mut request_id = -1;
+
+    if version >= 7 {
+        request_id = decode_i32(&mut fields_itr)?;
+    }
+
+    let order_id = decode_i32(&mut fields_itr)?;
+
+    // decode contract fields
+    let mut contract = Contract {
+        con_id: decode_i32(&mut fields_itr)?, // ver 5 field
+        symbol: decode_string(&mut fields_itr)?,
+        sec_type: decode_string(&mut fields_itr)?.into(),
+        last_trade_date_or_contract_month: decode_string(&mut fields_itr)?,
+        strike: decode_f64(&mut fields_itr)?,
+        right: decode_right(&mut fields_itr)?,
+        exchange: decode_string(&mut fields_itr)?,
+        currency: decode_string(&mut fields_itr)?,
+        local_symbol: decode_string(&mut fields_itr)?,
+        ..Default::default()
+    };
+
+    if version >= 9 {
+        contract.multiplier = decode_string(&mut fields_itr)?;
+    }
+
+    if version >= 10 {
+        contract.trading_class = decode_string(&mut fields_itr)?;
+    }
+
+    // decode execution fields
+    let mut execution = Execution {
+        order_id,
+        exec_id: decode_string(&mut fields_itr)?,
+        time: decode_string(&mut fields_itr)?,
+        acct_number: decode_string(&mut fields_itr)?,
+        exchange: decode_string(&mut fields_itr)?,
+        side: decode_string(&mut fields_itr)?,
+        ..Default::default()
+    };
+
+    if server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
+        execution.shares = decode_f64(&mut fields_itr)?;
+    } else {
+        execution.shares = decode_i32(&mut fields_itr)? as f64;
+    }
+
+    execution.price = decode_f64(&mut fields_itr)?;
+    execution.perm_id = decode_i32(&mut fields_itr)?; // ver 2 field
+    execution.client_id = decode_i32(&mut fields_itr)?; // ver 3 field
+    execution.liquidation = decode_i32(&mut fields_itr)?; // ver 4 field
+
+    if version >= 6 {
+        execution.cum_qty = decode_f64(&mut fields_itr)?;
+        execution.avg_price = decode_f64(&mut fields_itr)?;
+    }
+
+    if version >= 8 {
+        execution.order_ref = decode_string(&mut fields_itr)?;
+    }
+
+    if version >= 9 {
+        execution.ev_rule = decode_string(&mut fields_itr)?;
+
+        let tmp_ev_mult = fields_itr.as_slice().first().copied().unwrap_or("");
+        if !tmp_ev_mult.is_empty() {
+            execution.ev_multiplier = decode_f64(&mut fields_itr)?;
+        } else {
+            fields_itr.next();
+            execution.ev_multiplier = 1.0;
+        }
+    }
+
+    if server_version >= MIN_SERVER_VER_MODELS_SUPPORT {
+        execution.model_code = decode_string(&mut fields_itr)?;
+    }
+    if server_version >= MIN_SERVER_VER_LAST_LIQUIDITY {
+        execution.last_liquidity = decode_i32(&mut fields_itr)?;
+    }
+
+    Ok((request_id, contract, execution))
 }
 
 //==================================================================================================
-pub fn decode_bool(iter: &mut Iter<String>) -> Result<bool, IBKRApiLibError> {
-    let next = iter.next();
-    //info!("{:?}", next);
-    let retval: i32 = next.unwrap_or(&"0".to_string()).parse().unwrap_or(0);
-    Ok(retval != 0)
+/// Decodes an open order message into the contract, the order and its state
+pub fn decode_open_order(
+    fields: &[&str],
+    server_version: i32,
+) -> Result<(Contract, Order, OrderState), IbkrError> {
+    let mut fields_itr = fields.iter();
+
+    // Throw away message_id.
+    fields_itr.next();
+
+    let mut order = Order::default();
+    let mut contract = Contract::default();
+    let mut order_state = OrderState::default();
+
+    let mut version = IncomingMessageIds::OpenOrder.version();
+    if server_version < MIN_SERVER_VER_ORDER_CONTAINER {
+        version = decode_i32(&mut fields_itr)?;
+    }
+
+    OrderDecoder::new(
+        &mut contract,
+        &mut order,
+        &mut order_state,
+        version,
+        server_version,
+    )
+    .decode_open(&mut fields_itr)?;
+
+    Ok((contract, order, order_state))
 }
 
 //==================================================================================================
 pub struct Decoder<T: Wrapper> {
-    msg_queue: Receiver<String>,
+    msg_queue: Receiver<Result<String, DecodeError>>,
     pub wrapper: Arc<Mutex<T>>,
     pub server_version: i32,
     conn_state: Arc<Mutex<ConnStatus>>,
+    tick_conflator: Arc<Mutex<TickConflator>>,
+    smart_components: Arc<Mutex<SmartComponentRegistry>>,
+    market_rules: Arc<Mutex<MarketRuleCache>>,
+    request_sender: RequestSender,
+    streams: Arc<Mutex<StreamRegistry>>,
+    quotes: Arc<Mutex<QuoteCache>>,
+    orders: Arc<Mutex<OrderTracker>>,
+    accounts: Arc<Mutex<ManagedAccounts>>,
+    journal: Arc<Mutex<Option<OrderJournal>>>,
+    alerts: Arc<Mutex<Option<AlertHooks>>>,
 }
 
 impl<T> Decoder<T>
@@ -112,172 +447,202 @@ where
 {
     pub fn new(
         wrapper: Arc<Mutex<T>>,
-        msg_queue: Receiver<String>,
+        msg_queue: Receiver<Result<String, DecodeError>>,
         server_version: i32,
         conn_state: Arc<Mutex<ConnStatus>>,
+        tick_conflator: Arc<Mutex<TickConflator>>,
+        smart_components: Arc<Mutex<SmartComponentRegistry>>,
+        market_rules: Arc<Mutex<MarketRuleCache>>,
+        request_sender: RequestSender,
+        streams: Arc<Mutex<StreamRegistry>>,
+        quotes: Arc<Mutex<QuoteCache>>,
+        orders: Arc<Mutex<OrderTracker>>,
+        accounts: Arc<Mutex<ManagedAccounts>>,
+        journal: Arc<Mutex<Option<OrderJournal>>>,
+        alerts: Arc<Mutex<Option<AlertHooks>>>,
     ) -> Self {
         Decoder {
             wrapper,
             msg_queue,
             server_version,
             conn_state,
+            tick_conflator,
+            smart_components,
+            market_rules,
+            request_sender,
+            streams,
+            quotes,
+            orders,
+            accounts,
+            journal,
+            alerts,
         }
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn interpret(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    /// Decodes the fields of a message and dispatches it
+    pub fn interpret<S: AsRef<str>>(&mut self, fields: &[S]) -> Result<(), IbkrError> {
+        let fields: Vec<&str> = fields.iter().map(AsRef::as_ref).collect();
+        self.dispatch(&fields)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn dispatch(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         if fields.is_empty() {
             return Ok(());
         }
 
-        let msg_id = i32::from_str(fields.get(0).unwrap().as_str())?;
-
-        match FromPrimitive::from_i32(msg_id) {
-            Some(IncomingMessageIds::TickPrice) => self.process_tick_price(fields)?,
-            Some(IncomingMessageIds::AccountSummary) => self.process_account_summary(fields)?,
-            Some(IncomingMessageIds::AccountSummaryEnd) => {
-                self.process_account_summary_end(fields)?
+        let msg_id = match parse_number(fields[0]) {
+            Some(msg_id) => msg_id,
+            None => {
+                let reason = DecodeErrorReason::InvalidNumber(fields[0].to_string());
+                return Err(DecodeError::at(fields.len(), reason)
+                    .locate(NO_VALID_ID, fields)
+                    .into());
             }
+        };
+
+        let result = match FromPrimitive::from_i32(msg_id) {
+            Some(IncomingMessageIds::TickPrice) => self.process_tick_price(fields),
+            Some(IncomingMessageIds::AccountSummary) => self.process_account_summary(fields),
+            Some(IncomingMessageIds::AccountSummaryEnd) => self.process_account_summary_end(fields),
             Some(IncomingMessageIds::AccountUpdateMulti) => {
-                self.process_account_update_multi(fields)?
+                self.process_account_update_multi(fields)
             }
             Some(IncomingMessageIds::AccountUpdateMultiEnd) => {
-                self.process_account_update_multi_end(fields)?
-            }
-            Some(IncomingMessageIds::AcctDownloadEnd) => {
-                self.process_account_download_end(fields)?
+                self.process_account_update_multi_end(fields)
             }
-            Some(IncomingMessageIds::AcctUpdateTime) => self.process_account_update_time(fields)?,
-            Some(IncomingMessageIds::AcctValue) => self.process_account_value(fields)?,
-            Some(IncomingMessageIds::BondContractData) => {
-                self.process_bond_contract_data(fields)?
-            }
-            Some(IncomingMessageIds::CommissionReport) => self.process_commission_report(fields)?,
-            Some(IncomingMessageIds::CompletedOrder) => self.process_completed_order(fields)?,
+            Some(IncomingMessageIds::AcctDownloadEnd) => self.process_account_download_end(fields),
+            Some(IncomingMessageIds::AcctUpdateTime) => self.process_account_update_time(fields),
+            Some(IncomingMessageIds::AcctValue) => self.process_account_value(fields),
+            Some(IncomingMessageIds::BondContractData) => self.process_bond_contract_data(fields),
+            Some(IncomingMessageIds::CommissionReport) => self.process_commission_report(fields),
+            Some(IncomingMessageIds::CompletedOrder) => self.process_completed_order(fields),
             Some(IncomingMessageIds::CompletedOrdersEnd) => {
-                self.process_complete_orders_end(fields)?
-            }
-            Some(IncomingMessageIds::ContractData) => self.process_contract_details(fields)?,
-            Some(IncomingMessageIds::ContractDataEnd) => {
-                self.process_contract_details_end(fields)?
+                self.process_complete_orders_end(fields)
             }
-            Some(IncomingMessageIds::CurrentTime) => self.process_current_time(fields)?,
+            Some(IncomingMessageIds::ContractData) => self.process_contract_details(fields),
+            Some(IncomingMessageIds::ContractDataEnd) => self.process_contract_details_end(fields),
+            Some(IncomingMessageIds::CurrentTime) => self.process_current_time(fields),
             Some(IncomingMessageIds::DeltaNeutralValidation) => {
-                self.process_delta_neutral_validation(fields)?
-            }
-            Some(IncomingMessageIds::DisplayGroupList) => {
-                self.process_display_group_list(fields)?
+                self.process_delta_neutral_validation(fields)
             }
+            Some(IncomingMessageIds::DisplayGroupList) => self.process_display_group_list(fields),
             Some(IncomingMessageIds::DisplayGroupUpdated) => {
-                self.process_display_group_updated(fields)?
-            }
-            Some(IncomingMessageIds::ErrMsg) => self.process_error_message(fields)?,
-            Some(IncomingMessageIds::ExecutionData) => self.process_execution_data(fields)?,
-            Some(IncomingMessageIds::ExecutionDataEnd) => {
-                self.process_execution_data_end(fields)?
+                self.process_display_group_updated(fields)
             }
-            Some(IncomingMessageIds::FamilyCodes) => self.process_family_codes(fields)?,
-            Some(IncomingMessageIds::FundamentalData) => self.process_fundamental_data(fields)?,
-            Some(IncomingMessageIds::HeadTimestamp) => self.process_head_timestamp(fields)?,
-            Some(IncomingMessageIds::HistogramData) => self.process_histogram_data(fields)?,
-            Some(IncomingMessageIds::HistoricalData) => self.process_historical_data(fields)?,
+            Some(IncomingMessageIds::ErrMsg) => self.process_error_message(fields),
+            Some(IncomingMessageIds::ExecutionData) => self.process_execution_data(fields),
+            Some(IncomingMessageIds::ExecutionDataEnd) => self.process_execution_data_end(fields),
+            Some(IncomingMessageIds::FamilyCodes) => self.process_family_codes(fields),
+            Some(IncomingMessageIds::FundamentalData) => self.process_fundamental_data(fields),
+            Some(IncomingMessageIds::HeadTimestamp) => self.process_head_timestamp(fields),
+            Some(IncomingMessageIds::HistogramData) => self.process_histogram_data(fields),
+            Some(IncomingMessageIds::HistoricalData) => self.process_historical_data(fields),
             Some(IncomingMessageIds::HistoricalDataUpdate) => {
-                self.process_historical_data_update(fields)?
+                self.process_historical_data_update(fields)
             }
-            Some(IncomingMessageIds::HistoricalNews) => self.process_historical_news(fields)?,
-            Some(IncomingMessageIds::HistoricalNewsEnd) => {
-                self.process_historical_news_end(fields)?
-            }
-            Some(IncomingMessageIds::HistoricalTicks) => self.process_historical_ticks(fields)?,
+            Some(IncomingMessageIds::HistoricalNews) => self.process_historical_news(fields),
+            Some(IncomingMessageIds::HistoricalNewsEnd) => self.process_historical_news_end(fields),
+            Some(IncomingMessageIds::HistoricalTicks) => self.process_historical_ticks(fields),
             Some(IncomingMessageIds::HistoricalTicksBidAsk) => {
-                self.process_historical_ticks_bid_ask(fields)?
+                self.process_historical_ticks_bid_ask(fields)
             }
 
             Some(IncomingMessageIds::HistoricalTicksLast) => {
-                self.process_historical_ticks_last(fields)?
+                self.process_historical_ticks_last(fields)
             }
-            Some(IncomingMessageIds::ManagedAccts) => self.process_managed_accounts(fields)?,
-            Some(IncomingMessageIds::MarketDataType) => self.process_market_data_type(fields)?,
-            Some(IncomingMessageIds::MarketDepth) => self.process_market_depth(fields)?,
-            Some(IncomingMessageIds::MarketDepthL2) => self.process_market_depth_l2(fields)?,
-            Some(IncomingMessageIds::MarketRule) => self.process_market_rule(fields)?,
+            Some(IncomingMessageIds::ManagedAccts) => self.process_managed_accounts(fields),
+            Some(IncomingMessageIds::MarketDataType) => self.process_market_data_type(fields),
+            Some(IncomingMessageIds::MarketDepth) => self.process_market_depth(fields),
+            Some(IncomingMessageIds::MarketDepthL2) => self.process_market_depth_l2(fields),
+            Some(IncomingMessageIds::MarketRule) => self.process_market_rule(fields),
             Some(IncomingMessageIds::MktDepthExchanges) => {
-                self.process_market_depth_exchanges(fields)?
-            }
-            Some(IncomingMessageIds::NewsArticle) => self.process_news_article(fields)?,
-            Some(IncomingMessageIds::NewsBulletins) => self.process_news_bulletins(fields)?,
-            Some(IncomingMessageIds::NewsProviders) => self.process_news_providers(fields)?,
-            Some(IncomingMessageIds::NextValidId) => self.process_next_valid_id(fields)?,
-            Some(IncomingMessageIds::OpenOrder) => self.process_open_order(fields)?,
-            Some(IncomingMessageIds::OpenOrderEnd) => self.process_open_order_end(fields)?,
-            Some(IncomingMessageIds::OrderStatus) => self.process_order_status(fields)?,
-            Some(IncomingMessageIds::OrderBound) => self.process_order_bound(fields)?,
-            Some(IncomingMessageIds::Pnl) => self.process_pnl(fields)?,
-            Some(IncomingMessageIds::PnlSingle) => self.process_pnl_single(fields)?,
-            Some(IncomingMessageIds::PortfolioValue) => self.process_portfolio_value(fields)?,
-            Some(IncomingMessageIds::PositionData) => self.process_position_data(fields)?,
-            Some(IncomingMessageIds::PositionEnd) => self.process_position_end(fields)?,
-            Some(IncomingMessageIds::RealTimeBars) => self.process_real_time_bars(fields)?,
-            Some(IncomingMessageIds::ReceiveFa) => {
-                self.process_receive_financial_advisor(fields)?
+                self.process_market_depth_exchanges(fields)
             }
+            Some(IncomingMessageIds::NewsArticle) => self.process_news_article(fields),
+            Some(IncomingMessageIds::NewsBulletins) => self.process_news_bulletins(fields),
+            Some(IncomingMessageIds::NewsProviders) => self.process_news_providers(fields),
+            Some(IncomingMessageIds::NextValidId) => self.process_next_valid_id(fields),
+            Some(IncomingMessageIds::OpenOrder) => self.process_open_order(fields),
+            Some(IncomingMessageIds::OpenOrderEnd) => self.process_open_order_end(fields),
+            Some(IncomingMessageIds::OrderStatus) => self.process_order_status(fields),
+            Some(IncomingMessageIds::OrderBound) => self.process_order_bound(fields),
+            Some(IncomingMessageIds::Pnl) => self.process_pnl(fields),
+            Some(IncomingMessageIds::PnlSingle) => self.process_pnl_single(fields),
+            Some(IncomingMessageIds::PortfolioValue) => self.process_portfolio_value(fields),
+            Some(IncomingMessageIds::PositionData) => self.process_position_data(fields),
+            Some(IncomingMessageIds::PositionEnd) => self.process_position_end(fields),
+            Some(IncomingMessageIds::RealTimeBars) => self.process_real_time_bars(fields),
+            Some(IncomingMessageIds::ReceiveFa) => self.process_receive_financial_advisor(fields),
             Some(IncomingMessageIds::RerouteMktDataReq) => {
-                self.process_reroute_market_data_request(fields)?
+                self.process_reroute_market_data_request(fields)
             }
 
-            Some(IncomingMessageIds::PositionMulti) => self.process_position_multi(fields)?,
-            Some(IncomingMessageIds::PositionMultiEnd) => {
-                self.process_position_multi_end(fields)?
-            }
-            Some(IncomingMessageIds::ScannerData) => self.process_scanner_data(fields)?,
-            Some(IncomingMessageIds::ScannerParameters) => {
-                self.process_scanner_parameters(fields)?
-            }
+            Some(IncomingMessageIds::PositionMulti) => self.process_position_multi(fields),
+            Some(IncomingMessageIds::PositionMultiEnd) => self.process_position_multi_end(fields),
+            Some(IncomingMessageIds::ScannerData) => self.process_scanner_data(fields),
+            Some(IncomingMessageIds::ScannerParameters) => self.process_scanner_parameters(fields),
             Some(IncomingMessageIds::SecurityDefinitionOptionParameter) => {
-                self.process_security_definition_option_parameter(fields)?
+                self.process_security_definition_option_parameter(fields)
             }
             Some(IncomingMessageIds::SecurityDefinitionOptionParameterEnd) => {
-                self.process_security_definition_option_parameter_end(fields)?
+                self.process_security_definition_option_parameter_end(fields)
             }
 
-            Some(IncomingMessageIds::SmartComponents) => self.process_smart_components(fields)?,
-            Some(IncomingMessageIds::SoftDollarTiers) => self.process_soft_dollar_tiers(fields)?,
-            Some(IncomingMessageIds::SymbolSamples) => self.process_symbol_samples(fields)?,
-            Some(IncomingMessageIds::TickByTick) => self.process_tick_by_tick(fields)?,
-            Some(IncomingMessageIds::TickEfp) => self.process_tick_by_tick(fields)?,
-            Some(IncomingMessageIds::TickGeneric) => self.process_tick_generic(fields)?,
-            Some(IncomingMessageIds::TickNews) => self.process_tick_news(fields)?,
+            Some(IncomingMessageIds::SmartComponents) => self.process_smart_components(fields),
+            Some(IncomingMessageIds::SoftDollarTiers) => self.process_soft_dollar_tiers(fields),
+            Some(IncomingMessageIds::SymbolSamples) => self.process_symbol_samples(fields),
+            Some(IncomingMessageIds::TickByTick) => self.process_tick_by_tick(fields),
+            Some(IncomingMessageIds::TickEfp) => self.process_tick_by_tick(fields),
+            Some(IncomingMessageIds::TickGeneric) => self.process_tick_generic(fields),
+            Some(IncomingMessageIds::TickNews) => self.process_tick_news(fields),
             Some(IncomingMessageIds::TickOptionComputation) => {
-                self.process_tick_option_computation(fields)?
+                self.process_tick_option_computation(fields)
             }
-            Some(IncomingMessageIds::TickReqParams) => {
-                self.process_tick_request_parameters(fields)?
-            }
-            Some(IncomingMessageIds::TickSize) => self.process_tick_size(fields)?,
-            Some(IncomingMessageIds::TickSnapshotEnd) => self.process_tick_snapshot_end(fields)?,
-            Some(IncomingMessageIds::TickString) => self.process_tick_string(fields)?,
+            Some(IncomingMessageIds::TickReqParams) => self.process_tick_request_parameters(fields),
+            Some(IncomingMessageIds::TickSize) => self.process_tick_size(fields),
+            Some(IncomingMessageIds::TickSnapshotEnd) => self.process_tick_snapshot_end(fields),
+            Some(IncomingMessageIds::TickString) => self.process_tick_string(fields),
             Some(IncomingMessageIds::VerifyAndAuthCompleted) => {
-                self.process_verify_and_auth_completed(fields)?
+                self.process_verify_and_auth_completed(fields)
             }
 
-            Some(IncomingMessageIds::VerifyCompleted) => self.process_verify_completed(fields)?,
+            Some(IncomingMessageIds::VerifyCompleted) => self.process_verify_completed(fields),
 
-            Some(IncomingMessageIds::VerifyMessageApi) => self.process_verify_completed(fields)?,
+            Some(IncomingMessageIds::VerifyMessageApi) => self.process_verify_completed(fields),
 
             Some(IncomingMessageIds::VerifyAndAuthMessageApi) => {
-                self.process_verify_and_auth_message_api(fields)?
+                self.process_verify_and_auth_message_api(fields)
             }
             Some(IncomingMessageIds::RerouteMktDepthReq) => {
-                self.process_reroute_market_depth_request(fields)?
+                self.process_reroute_market_depth_request(fields)
+            }
+            Some(IncomingMessageIds::ReplaceFaEnd) => self.process_replace_fa_end(fields),
+            #[cfg(feature = "wsh")]
+            Some(IncomingMessageIds::WshMetaData) => self.process_wsh_meta_data(fields),
+            #[cfg(feature = "wsh")]
+            Some(IncomingMessageIds::WshEventData) => self.process_wsh_event_data(fields),
+            Some(IncomingMessageIds::HistoricalSchedule) => {
+                self.process_historical_schedule(fields)
+            }
+            Some(IncomingMessageIds::UserInfo) => self.process_user_info(fields),
+            // Only sent on request, which needs the wsh feature
+            #[cfg(not(feature = "wsh"))]
+            Some(IncomingMessageIds::WshMetaData | IncomingMessageIds::WshEventData) => {
+                Err(DecodeError::at(fields.len(), DecodeErrorReason::UnknownMessageId).into())
             }
 
-            _ => panic!("Received unkown message id!!  Exiting..."),
-        }
-        Ok(())
+            None => Err(DecodeError::at(fields.len(), DecodeErrorReason::UnknownMessageId).into()),
+        };
+        result.map_err(|err| match err {
+            IbkrError::Decode(err) => err.locate(msg_id, fields).into(),
+            err => err,
+        })
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_price(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_price(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -286,9 +651,9 @@ where
         fields_itr.next();
 
         let request_id = decode_i32(&mut fields_itr)?;
-        let tick_type: i32 = decode_i32(&mut fields_itr)?;
+        let tick_type: TickType = decode_enum(&mut fields_itr)?;
         let price: f64 = decode_f64(&mut fields_itr)?;
-        let size = decode_i32(&mut fields_itr)?;
+        let size = decode_size(&mut fields_itr)?;
         let attr_mask: i32 = decode_i32(&mut fields_itr)?;
         let mut tick_arrtibute = TickAttrib::new(false, false, false);
 
@@ -301,40 +666,38 @@ where
         if self.server_version >= MIN_SERVER_VER_PRE_OPEN_BID_ASK {
             tick_arrtibute.pre_open = attr_mask & 4 != 0;
         }
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_price(
-                request_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
-                price,
-                tick_arrtibute,
-            );
+        self.conflate_tick(ConflatedTick::Price {
+            request_id,
+            tick_type,
+            price,
+            attrib: tick_arrtibute,
+        });
 
         // Process ver 2 fields.
 
-        let size_tick_type = match FromPrimitive::from_i32(tick_type) {
-            Some(TickType::Bid) => TickType::BidSize,
-            Some(TickType::Ask) => TickType::AskSize,
-            Some(TickType::Last) => TickType::LastSize,
-            Some(TickType::DelayedBid) => TickType::DelayedBidSize,
-            Some(TickType::DelayedAsk) => TickType::DelayedAskSize,
-            Some(TickType::DelayedLast) => TickType::DelayedLastSize,
+        let size_tick_type = match tick_type {
+            TickType::Bid => TickType::BidSize,
+            TickType::Ask => TickType::AskSize,
+            TickType::Last => TickType::LastSize,
+            TickType::DelayedBid => TickType::DelayedBidSize,
+            TickType::DelayedAsk => TickType::DelayedAskSize,
+            TickType::DelayedLast => TickType::DelayedLastSize,
             _ => TickType::NotSet,
         };
 
         if size_tick_type as i32 != TickType::NotSet as i32 {
-            self.wrapper
-                .lock()
-                .expect(WRAPPER_POISONED_MUTEX)
-                .tick_size(request_id, size_tick_type, size);
+            self.conflate_tick(ConflatedTick::Size {
+                request_id,
+                tick_type: size_tick_type,
+                size,
+            });
         }
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_string(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_string(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -343,22 +706,18 @@ where
         fields_itr.next();
 
         let request_id: i32 = decode_i32(&mut fields_itr)?;
-        let tick_type: i32 = decode_i32(&mut fields_itr)?;
+        let tick_type: TickType = decode_enum(&mut fields_itr)?;
         let value = decode_string(&mut fields_itr)?;
 
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .tick_string(
-                request_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
-                value.as_ref(),
-            );
+            .tick_string(request_id, tick_type, value.as_ref());
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_summary(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_summary(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -366,21 +725,38 @@ where
         // Throw away version.
         fields_itr.next();
 
-        self.wrapper
+        let request_id = decode_i32(&mut fields_itr)?;
+        let account = decode_string(&mut fields_itr)?;
+        let tag = decode_string(&mut fields_itr)?;
+        let value = decode_string(&mut fields_itr)?;
+        let currency = decode_string(&mut fields_itr)?;
+
+        let event =
+            AccountSummaryEvent::Value(AccountSummaryValue::new(&account, &tag, &value, &currency));
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_summary(
-                decode_i32(&mut fields_itr)?,
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-            );
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_summaries
+            .send(request_id, event);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .account_summary(
+                    request_id,
+                    account.as_ref(),
+                    tag.as_ref(),
+                    value.as_ref(),
+                    currency.as_ref(),
+                );
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_summary_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_summary_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -388,15 +764,25 @@ where
         // Throw away version.
         fields_itr.next();
 
-        self.wrapper
+        let request_id = decode_i32(&mut fields_itr)?;
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_summary_end(decode_i32(&mut fields_itr)?);
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_summaries
+            .send(request_id, AccountSummaryEvent::End);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .account_summary_end(request_id);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_update_multi(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_update_multi(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -411,25 +797,36 @@ where
         let value = decode_string(&mut fields_itr)?;
         let currency = decode_string(&mut fields_itr)?;
 
-        self.wrapper
+        let event = AccountUpdateMultiEvent::Value(ModelAccountValue::new(
+            &account,
+            &model_code,
+            AccountValue::new(&key, &value, &currency),
+        ));
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_update_multi(
-                request_id,
-                account.as_ref(),
-                model_code.as_ref(),
-                key.as_ref(),
-                value.as_ref(),
-                currency.as_ref(),
-            );
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_updates_multi
+            .send(request_id, event);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .account_update_multi(
+                    request_id,
+                    account.as_ref(),
+                    model_code.as_ref(),
+                    key.as_ref(),
+                    value.as_ref(),
+                    currency.as_ref(),
+                );
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_update_multi_end(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_account_update_multi_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -439,15 +836,24 @@ where
 
         let request_id: i32 = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_update_multi_end(request_id);
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_updates_multi
+            .send(request_id, AccountUpdateMultiEvent::End);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .account_update_multi_end(request_id);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_download_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_download_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -455,15 +861,25 @@ where
         // Throw away version.
         fields_itr.next();
 
-        self.wrapper
+        let account = decode_string(&mut fields_itr)?;
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .account_download_end(decode_string(&mut fields_itr)?.as_ref());
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_updates
+            .send(NO_VALID_ID, AccountUpdateEvent::End(account));
+
+        if let Err(AccountUpdateEvent::End(account)) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .account_download_end(account.as_ref());
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_update_time(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_update_time(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -471,15 +887,25 @@ where
         // Throw away version.
         fields_itr.next();
 
-        self.wrapper
+        let time_stamp = decode_string(&mut fields_itr)?;
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .update_account_time(decode_string(&mut fields_itr)?.as_ref());
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_updates
+            .send(NO_VALID_ID, AccountUpdateEvent::Time(time_stamp));
+
+        if let Err(AccountUpdateEvent::Time(time_stamp)) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .update_account_time(time_stamp.as_ref());
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_account_value(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_account_value(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -487,26 +913,52 @@ where
         // Throw away version.
         fields_itr.next();
 
-        self.wrapper
+        let key = decode_string(&mut fields_itr)?;
+        let value = decode_string(&mut fields_itr)?;
+        let currency = decode_string(&mut fields_itr)?;
+        let account = decode_string(&mut fields_itr)?;
+
+        let account_value = AccountValue::new(&key, &value, &currency);
+        self.alert(|alerts| alerts.apply_account_value(&account, &account_value));
+        let event = AccountUpdateEvent::Value {
+            account: account.clone(),
+            value: account_value,
+        };
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .update_account_value(
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-                decode_string(&mut fields_itr)?.as_ref(),
-            );
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_updates
+            .send(NO_VALID_ID, event);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .update_account_value(
+                    key.as_ref(),
+                    value.as_ref(),
+                    currency.as_ref(),
+                    account.as_ref(),
+                );
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_bond_contract_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_bond_contract_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
-        let version: i32 = decode_i32(&mut fields_itr)?;
+        // Servers from MIN_SERVER_VER_SIZE_RULES on send all fields and no
+        // version
+        let version = if self.server_version < MIN_SERVER_VER_SIZE_RULES {
+            decode_i32(&mut fields_itr)?
+        } else {
+            IncomingMessageIds::BondContractData.version()
+        };
 
         let mut request_id = -1;
         if version >= 3 {
@@ -516,17 +968,17 @@ where
         let mut contract = ContractDetails::default();
 
         contract.contract.symbol = decode_string(&mut fields_itr)?;
-        contract.contract.sec_type = decode_string(&mut fields_itr)?;
+        contract.contract.sec_type = decode_string(&mut fields_itr)?.into();
         contract.cusip = decode_string(&mut fields_itr)?;
         contract.coupon = decode_f64(&mut fields_itr)?;
-        self.read_last_trade_date(&mut contract, true, fields_itr.next().unwrap())?;
+        self.read_last_trade_date(&mut contract, true, decode_field(&mut fields_itr)?)?;
         contract.issue_date = decode_string(&mut fields_itr)?;
         contract.ratings = decode_string(&mut fields_itr)?;
         contract.bond_type = decode_string(&mut fields_itr)?;
         contract.coupon_type = decode_string(&mut fields_itr)?;
-        contract.convertible = i32::from_str(fields_itr.next().unwrap().as_ref())? != 0;
-        contract.callable = i32::from_str(fields_itr.next().unwrap().as_ref())? != 0;
-        contract.putable = i32::from_str(fields_itr.next().unwrap().as_ref())? != 0;
+        contract.convertible = decode_bool(&mut fields_itr)?;
+        contract.callable = decode_bool(&mut fields_itr)?;
+        contract.putable = decode_bool(&mut fields_itr)?;
         contract.desc_append = decode_string(&mut fields_itr)?;
         contract.contract.exchange = decode_string(&mut fields_itr)?;
         contract.contract.currency = decode_string(&mut fields_itr)?;
@@ -534,7 +986,9 @@ where
         contract.contract.trading_class = decode_string(&mut fields_itr)?;
         contract.contract.con_id = decode_i32(&mut fields_itr)?;
         contract.min_tick = decode_f64(&mut fields_itr)?;
-        if self.server_version >= MIN_SERVER_VER_MD_SIZE_MULTIPLIER {
+        if self.server_version >= MIN_SERVER_VER_MD_SIZE_MULTIPLIER
+            && self.server_version < MIN_SERVER_VER_SIZE_RULES
+        {
             contract.md_size_multiplier = decode_i32(&mut fields_itr)?;
         }
         contract.order_types = decode_string(&mut fields_itr)?;
@@ -558,8 +1012,8 @@ where
                 contract.sec_id_list = vec![];
                 for _ in 0..sec_id_list_count {
                     contract.sec_id_list.push(TagValue::new(
-                        fields_itr.next().unwrap().parse().unwrap(),
-                        fields_itr.next().unwrap().parse().unwrap(),
+                        decode_string(&mut fields_itr)?,
+                        decode_string(&mut fields_itr)?,
                     ));
                 }
             }
@@ -570,16 +1024,34 @@ where
         if self.server_version >= MIN_SERVER_VER_MARKET_RULES {
             contract.market_rule_ids = decode_string(&mut fields_itr)?;
         }
+        if self.server_version >= MIN_SERVER_VER_SIZE_RULES {
+            contract.min_size = decode_size(&mut fields_itr)?;
+            contract.size_increment = decode_size(&mut fields_itr)?;
+            contract.suggested_size_increment = decode_size(&mut fields_itr)?;
+        }
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .bond_contract_details(request_id, contract.clone());
+            .expect(STREAMS_POISONED_MUTEX)
+            .contract_details
+            .send(
+                request_id,
+                ContractDetailsEvent::Details(Box::new(contract.clone())),
+            );
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .bond_contract_details(request_id, contract.clone());
+        }
+
+        self.fetch_market_rules(contract.market_rule_ids.as_str())?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_commission_report(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_commission_report(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
         // Throw away message_id.
         fields_itr.next();
@@ -587,9 +1059,9 @@ where
         fields_itr.next();
 
         let commission_report = CommissionReport {
-            exec_id: fields_itr.next().unwrap().to_string(),
+            exec_id: decode_string(&mut fields_itr)?,
             commission: decode_f64(&mut fields_itr)?,
-            currency: fields_itr.next().unwrap().to_string(),
+            currency: decode_string(&mut fields_itr)?,
 
             realized_pnl: decode_f64(&mut fields_itr)?,
 
@@ -606,7 +1078,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_completed_order(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_completed_order(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -634,7 +1106,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_complete_orders_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_complete_orders_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -647,13 +1119,19 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_contract_details(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_contract_details(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
-        let version: i32 = decode_i32(&mut fields_itr)?;
+        // Servers from MIN_SERVER_VER_SIZE_RULES on send all fields and no
+        // version
+        let version = if self.server_version < MIN_SERVER_VER_SIZE_RULES {
+            decode_i32(&mut fields_itr)?
+        } else {
+            IncomingMessageIds::ContractData.version()
+        };
 
         let mut request_id = -1;
         if version >= 3 {
@@ -663,10 +1141,10 @@ where
         let mut contract = ContractDetails::default();
 
         contract.contract.symbol = decode_string(&mut fields_itr)?;
-        contract.contract.sec_type = decode_string(&mut fields_itr)?;
-        self.read_last_trade_date(&mut contract, false, fields_itr.next().unwrap())?;
+        contract.contract.sec_type = decode_string(&mut fields_itr)?.into();
+        self.read_last_trade_date(&mut contract, false, decode_field(&mut fields_itr)?)?;
         contract.contract.strike = decode_f64(&mut fields_itr)?;
-        contract.contract.right = decode_string(&mut fields_itr)?;
+        contract.contract.right = decode_right(&mut fields_itr)?;
         contract.contract.exchange = decode_string(&mut fields_itr)?;
         contract.contract.currency = decode_string(&mut fields_itr)?;
         contract.contract.local_symbol = decode_string(&mut fields_itr)?;
@@ -674,7 +1152,9 @@ where
         contract.contract.trading_class = decode_string(&mut fields_itr)?;
         contract.contract.con_id = decode_i32(&mut fields_itr)?;
         contract.min_tick = decode_f64(&mut fields_itr)?;
-        if self.server_version >= MIN_SERVER_VER_MD_SIZE_MULTIPLIER {
+        if self.server_version >= MIN_SERVER_VER_MD_SIZE_MULTIPLIER
+            && self.server_version < MIN_SERVER_VER_SIZE_RULES
+        {
             contract.md_size_multiplier = decode_i32(&mut fields_itr)?;
         }
         contract.contract.multiplier = decode_string(&mut fields_itr)?;
@@ -686,6 +1166,9 @@ where
         }
         if version >= 5 {
             contract.long_name = decode_string(&mut fields_itr)?;
+            if self.server_version >= MIN_SERVER_VER_ENCODE_MSG_ASCII7 {
+                contract.long_name = unescape_unicode(&contract.long_name);
+            }
             contract.contract.primary_exchange = decode_string(&mut fields_itr)?;
         }
 
@@ -730,16 +1213,43 @@ where
         if self.server_version >= MIN_SERVER_VER_REAL_EXPIRATION_DATE {
             contract.real_expiration_date = decode_string(&mut fields_itr)?;
         }
+        if self.server_version >= MIN_SERVER_VER_STOCK_TYPE {
+            contract.stock_type = decode_string(&mut fields_itr)?;
+        }
+        if self.server_version >= MIN_SERVER_VER_FRACTIONAL_SIZE_SUPPORT
+            && self.server_version < MIN_SERVER_VER_SIZE_RULES
+        {
+            // The size min tick was replaced by the size rules
+            decode_size(&mut fields_itr)?;
+        }
+        if self.server_version >= MIN_SERVER_VER_SIZE_RULES {
+            contract.min_size = decode_size(&mut fields_itr)?;
+            contract.size_increment = decode_size(&mut fields_itr)?;
+            contract.suggested_size_increment = decode_size(&mut fields_itr)?;
+        }
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .contract_details(request_id, contract.clone());
+            .expect(STREAMS_POISONED_MUTEX)
+            .contract_details
+            .send(
+                request_id,
+                ContractDetailsEvent::Details(Box::new(contract.clone())),
+            );
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .contract_details(request_id, contract.clone());
+        }
+
+        self.fetch_market_rules(contract.market_rule_ids.as_str())?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_contract_details_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_contract_details_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -749,15 +1259,23 @@ where
 
         let request_id = decode_i32(&mut fields_itr)?;
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .contract_details_end(request_id);
+            .expect(STREAMS_POISONED_MUTEX)
+            .contract_details
+            .send(request_id, ContractDetailsEvent::End);
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .contract_details_end(request_id);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_current_time(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_current_time(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -773,10 +1291,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_delta_neutral_validation(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_delta_neutral_validation(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -800,7 +1315,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_display_group_list(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_display_group_list(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -810,17 +1325,17 @@ where
 
         let request_id = decode_i32(&mut fields_itr)?;
 
-        let groups = decode_string(&mut fields_itr)?;
+        let groups = parse_display_groups(decode_string(&mut fields_itr)?.as_str());
 
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .display_group_list(request_id, groups.as_ref());
+            .display_group_list(request_id, groups);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_display_group_updated(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_display_group_updated(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -832,14 +1347,28 @@ where
 
         let contract_info = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .display_group_updated(request_id, contract_info.as_ref());
+        match DisplayGroupContract::parse(contract_info.as_str()) {
+            Some(contract) => self
+                .wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .display_group_updated(request_id, contract),
+            None => self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).error(
+                request_id,
+                TwsError::BadMessage.code(),
+                format!(
+                    "{} Invalid display group contract: {}",
+                    TwsError::BadMessage.message(),
+                    contract_info
+                )
+                .as_str(),
+                "",
+            ),
+        }
         Ok(())
     }
 
-    fn process_error_message(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_error_message(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -847,105 +1376,194 @@ where
         // Throw away version.
         fields_itr.next();
 
-        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).error(
-            decode_i32(&mut fields_itr)?,
-            decode_i32(&mut fields_itr)?,
-            decode_string(&mut fields_itr)?.as_ref(),
-        );
-        Ok(())
-    }
-
-    //----------------------------------------------------------------------------------------------
-    fn process_execution_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
-        let mut fields_itr = fields.iter();
-
-        // Throw away message_id.
-        fields_itr.next();
-
-        let mut version = self.server_version;
-
-        if self.server_version < MIN_SERVER_VER_LAST_LIQUIDITY {
-            version = decode_i32(&mut fields_itr)?;
-        }
-
-        let mut request_id = -1;
-
-        if version >= 7 {
-            request_id = decode_i32(&mut fields_itr)?;
-        }
-
-        let order_id = decode_i32(&mut fields_itr)?;
-
-        // decode contract fields
-        let mut contract = Contract {
-            con_id: decode_i32(&mut fields_itr)?, // ver 5 field
-            symbol: decode_string(&mut fields_itr)?,
-            sec_type: decode_string(&mut fields_itr)?,
-            last_trade_date_or_contract_month: decode_string(&mut fields_itr)?,
-            strike: decode_f64(&mut fields_itr)?,
-            right: decode_string(&mut fields_itr)?,
-            exchange: decode_string(&mut fields_itr)?,
-            currency: decode_string(&mut fields_itr)?,
-            local_symbol: decode_string(&mut fields_itr)?,
-            ..Default::default()
-        };
-
-        if version >= 9 {
-            contract.multiplier = decode_string(&mut fields_itr)?;
+        let request_id = decode_i32(&mut fields_itr)?;
+        let error_code = decode_i32(&mut fields_itr)?;
+        let mut error_string = decode_string(&mut fields_itr)?;
+        if self.server_version >= MIN_SERVER_VER_ENCODE_MSG_ASCII7 {
+            error_string = unescape_unicode(&error_string);
         }
-
-        if version >= 10 {
-            contract.trading_class = decode_string(&mut fields_itr)?;
+        let mut advanced_order_reject_json = "".to_string();
+        if self.server_version >= MIN_SERVER_VER_ADVANCED_ORDER_REJECT {
+            advanced_order_reject_json = unescape_unicode(&decode_string(&mut fields_itr)?);
         }
 
-        // decode execution fields
-        let mut execution = Execution {
-            order_id,
-            exec_id: decode_string(&mut fields_itr)?,
-            time: decode_string(&mut fields_itr)?,
-            acct_number: decode_string(&mut fields_itr)?,
-            exchange: decode_string(&mut fields_itr)?,
-            side: decode_string(&mut fields_itr)?,
-            ..Default::default()
-        };
-
-        if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
-            execution.shares = decode_f64(&mut fields_itr)?;
-        } else {
-            execution.shares = decode_i32(&mut fields_itr)? as f64;
+        if request_id > 0
+            && is_retryable_historical_error(error_code, &error_string)
+            && self.retry_historical_data(request_id, error_code, &error_string)
+        {
+            return Ok(());
         }
 
-        execution.price = decode_f64(&mut fields_itr)?;
-        execution.perm_id = decode_i32(&mut fields_itr)?; // ver 2 field
-        execution.client_id = decode_i32(&mut fields_itr)?; // ver 3 field
-        execution.liquidation = decode_i32(&mut fields_itr)?; // ver 4 field
+        self.alert(|alerts| alerts.apply_error(request_id, error_code, &error_string));
 
-        if version >= 6 {
-            execution.cum_qty = decode_f64(&mut fields_itr)?;
-            execution.avg_price = decode_f64(&mut fields_itr)?;
+        // An error ends a streamed historical data, ticks, contract details,
+        // option chain, matching symbols, account summary, positions, PnL or
+        // what-if request
+        if request_id > 0 {
+            let mut streams = self.streams.lock().expect(STREAMS_POISONED_MUTEX);
+            let event = HistoricalDataEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.historical_data.send(request_id, event).is_ok() {
+                streams.remove_historical_data(request_id);
+                return Ok(());
+            }
+            // Warnings do not end a request
+            if !is_warning(error_code) {
+                streams.bar_times.remove(&request_id);
+                streams.historical_retries.remove(&request_id);
+            }
+            let event = HistoricalTicksEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.historical_ticks.send(request_id, event).is_ok() {
+                streams.historical_ticks.remove(request_id);
+                return Ok(());
+            }
+            let event = ContractDetailsEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.contract_details.send(request_id, event).is_ok() {
+                streams.contract_details.remove(request_id);
+                return Ok(());
+            }
+            let event = OptionChainEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.option_chains.send(request_id, event).is_ok() {
+                streams.option_chains.remove(request_id);
+                return Ok(());
+            }
+            let event = SymbolSamplesEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.symbol_samples.send(request_id, event).is_ok() {
+                streams.symbol_samples.remove(request_id);
+                return Ok(());
+            }
+            let event = AccountSummaryEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.account_summaries.send(request_id, event).is_ok() {
+                streams.account_summaries.remove(request_id);
+                return Ok(());
+            }
+            let event = PositionEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.positions_multi.send(request_id, event).is_ok() {
+                streams.positions_multi.remove(request_id);
+                return Ok(());
+            }
+            let event = AccountUpdateMultiEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams
+                .account_updates_multi
+                .send(request_id, event)
+                .is_ok()
+            {
+                streams.account_updates_multi.remove(request_id);
+                return Ok(());
+            }
+            let event = PnlEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.pnl.send(request_id, event).is_ok() {
+                streams.pnl.remove(request_id);
+                return Ok(());
+            }
+            let event = PnlSingleEvent::Error {
+                code: error_code,
+                message: error_string.clone(),
+            };
+            if streams.pnl_single.send(request_id, event).is_ok() {
+                streams.pnl_single.remove(request_id);
+                return Ok(());
+            }
+            // Order message warnings do not end a what-if check
+            if error_code != ORDER_MESSAGE {
+                let event = WhatIfEvent::Error {
+                    code: error_code,
+                    message: error_string.clone(),
+                };
+                if streams.what_if.send(request_id, event).is_ok() {
+                    streams.what_if.remove(request_id);
+                    return Ok(());
+                }
+            }
         }
 
-        if version >= 8 {
-            execution.order_ref = decode_string(&mut fields_itr)?;
+        let mut wrapper = self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX);
+        wrapper.error(
+            request_id,
+            error_code,
+            error_string.as_ref(),
+            advanced_order_reject_json.as_ref(),
+        );
+        if let Some(status) = DataFarmStatus::from_error(error_code, error_string.as_ref()) {
+            wrapper.data_farm_status(&status);
         }
+        Ok(())
+    }
 
-        if version >= 9 {
-            execution.ev_rule = decode_string(&mut fields_itr)?;
+    //----------------------------------------------------------------------------------------------
+    /// Sends a historical data request that failed with a pacing violation
+    /// or a timeout again after the delay of its retry policy. Returns false
+    /// if the request is not retried, e.g. because all attempts are used up.
+    fn retry_historical_data(&mut self, request_id: i32, error_code: i32, error: &str) -> bool {
+        let mut streams = self.streams.lock().expect(STREAMS_POISONED_MUTEX);
+        let retry = match streams.historical_retries.get_mut(&request_id) {
+            Some(retry) if retry.attempts < retry.policy.max_attempts => retry,
+            _ => return false,
+        };
+        let delay = retry.policy.delay(retry.attempts);
+        retry.attempts += 1;
+        let attempts = retry.attempts;
+        warn!(
+            "Retrying historical data request {} in {:?} after error {}: {}",
+            request_id, delay, error_code, error
+        );
 
-            let tmp_ev_mult = (&mut fields_itr).peekable().peek().unwrap().as_str();
-            if !tmp_ev_mult.is_empty() {
-                execution.ev_multiplier = decode_f64(&mut fields_itr)?;
-            } else {
-                execution.ev_multiplier = 1.0;
+        let streams = self.streams.clone();
+        let request_sender = self.request_sender.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            // The request may have been cancelled in the meantime
+            let message = match streams
+                .lock()
+                .expect(STREAMS_POISONED_MUTEX)
+                .historical_retries
+                .get(&request_id)
+            {
+                Some(retry) if retry.attempts == attempts => retry.message.clone(),
+                _ => return,
+            };
+            if let Err(err) = request_sender.send_message(&message) {
+                error!(
+                    "Failed to retry historical data request {}: {}",
+                    request_id, err
+                );
             }
-        }
+        });
+        true
+    }
 
-        if self.server_version >= MIN_SERVER_VER_MODELS_SUPPORT {
-            execution.model_code = decode_string(&mut fields_itr)?;
-        }
-        if self.server_version >= MIN_SERVER_VER_LAST_LIQUIDITY {
-            execution.last_liquidity = decode_i32(&mut fields_itr)?;
-        }
+    //----------------------------------------------------------------------------------------------
+    fn process_execution_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
+        let (request_id, contract, execution) = decode_execution_data(fields, self.server_version)?;
+
+        self.write_journal(JournalEntry::Fill(JournalFill::new(&execution)));
+        self.alert(|alerts| alerts.apply_execution(&contract, &execution));
 
         self.wrapper
             .lock()
@@ -955,7 +1573,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_execution_data_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_execution_data_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -973,20 +1591,21 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_family_codes(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_family_codes(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
         let family_codes_count = decode_i32(&mut fields_itr)?;
-        let family_codes: Vec<FamilyCode> = vec![
-            FamilyCode {
-                account_id: decode_string(&mut fields_itr)?,
-                family_code_str: decode_string(&mut fields_itr)?,
-            };
-            family_codes_count as _
-        ];
+        let family_codes = (0..family_codes_count)
+            .map(|_| {
+                Ok(FamilyCode {
+                    account_id: decode_string(&mut fields_itr)?,
+                    family_code_str: decode_string(&mut fields_itr)?,
+                })
+            })
+            .collect::<Result<Vec<_>, IbkrError>>()?;
 
         self.wrapper
             .lock()
@@ -996,7 +1615,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_fundamental_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_fundamental_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1014,7 +1633,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_head_timestamp(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_head_timestamp(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1030,7 +1649,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_histogram_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_histogram_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1039,13 +1658,14 @@ where
         let request_id = decode_i32(&mut fields_itr)?;
         let num_points = decode_i32(&mut fields_itr)?;
 
-        let histogram = vec![
-            HistogramData {
-                price: decode_f64(&mut fields_itr)?,
-                count: decode_i32(&mut fields_itr)?,
-            };
-            num_points as _
-        ];
+        let histogram = (0..num_points)
+            .map(|_| {
+                Ok(HistogramData {
+                    price: decode_f64(&mut fields_itr)?,
+                    count: decode_size(&mut fields_itr)?,
+                })
+            })
+            .collect::<Result<Vec<_>, IbkrError>>()?;
 
         self.wrapper
             .lock()
@@ -1055,7 +1675,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
         // Throw away message_id.
         fields_itr.next();
@@ -1068,10 +1688,14 @@ where
         let start_date = decode_string(&mut fields_itr)?; // ver 2 field
         let end_date = decode_string(&mut fields_itr)?; // ver 2 field
 
-        let _peek = *(fields_itr.clone()).peekable().peek().unwrap();
-
         let bar_count = decode_i32(&mut fields_itr)?;
 
+        let bar_time_parser = self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .bar_time_parser(request_id);
+
         for _ in 0..bar_count {
             let mut bar = BarData {
                 date: decode_string(&mut fields_itr)?,
@@ -1079,11 +1703,7 @@ where
                 high: decode_f64(&mut fields_itr)?,
                 low: decode_f64(&mut fields_itr)?,
                 close: decode_f64(&mut fields_itr)?,
-                volume: if self.server_version < MIN_SERVER_VER_SYNT_REALTIME_BARS {
-                    decode_i32(&mut fields_itr)? as i64
-                } else {
-                    decode_i64(&mut fields_itr)?
-                },
+                volume: decode_size(&mut fields_itr)?,
                 average: decode_f64(&mut fields_itr)?,
                 ..Default::default()
             };
@@ -1093,23 +1713,45 @@ where
             }
 
             bar.bar_count = decode_i32(&mut fields_itr)?; // ver 3 field
+            bar.time = bar_time_parser.parse(&bar.date);
 
-            self.wrapper
+            if let Err(HistoricalDataEvent::Bar(bar)) = self
+                .streams
                 .lock()
-                .expect(WRAPPER_POISONED_MUTEX)
-                .historical_data(request_id, bar);
+                .expect(STREAMS_POISONED_MUTEX)
+                .historical_data
+                .send(request_id, HistoricalDataEvent::Bar(bar))
+            {
+                self.wrapper
+                    .lock()
+                    .expect(WRAPPER_POISONED_MUTEX)
+                    .historical_data(request_id, bar);
+            }
         }
 
         // send end of dataset marker
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_data_end(request_id, start_date.as_ref(), end_date.as_ref());
+        let end = HistoricalDataEvent::End {
+            start: start_date,
+            end: end_date,
+        };
+        let mut streams = self.streams.lock().expect(STREAMS_POISONED_MUTEX);
+        if matches!(streams.bar_times.get(&request_id), Some((_, false))) {
+            streams.bar_times.remove(&request_id);
+            streams.historical_retries.remove(&request_id);
+        }
+        let end = streams.historical_data.send(request_id, end);
+        drop(streams);
+        if let Err(HistoricalDataEvent::End { start, end }) = end {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .historical_data_end(request_id, start.as_ref(), end.as_ref());
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_data_update(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_data_update(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1117,7 +1759,7 @@ where
 
         let request_id = decode_i32(&mut fields_itr)?;
 
-        let bar = BarData {
+        let mut bar = BarData {
             bar_count: decode_i32(&mut fields_itr)?,
             date: decode_string(&mut fields_itr)?,
             open: decode_f64(&mut fields_itr)?,
@@ -1125,18 +1767,27 @@ where
             high: decode_f64(&mut fields_itr)?,
             low: decode_f64(&mut fields_itr)?,
             average: decode_f64(&mut fields_itr)?,
-            volume: decode_i64(&mut fields_itr)?,
+            volume: decode_size(&mut fields_itr)?,
+            time: None,
         };
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_data_update(request_id, bar);
+        let mut streams = self.streams.lock().expect(STREAMS_POISONED_MUTEX);
+        bar.time = streams.bar_time_parser(request_id).parse(&bar.date);
+        let update = streams
+            .historical_data
+            .send(request_id, HistoricalDataEvent::Update(bar));
+        drop(streams);
+        if let Err(HistoricalDataEvent::Update(bar)) = update {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .historical_data_update(request_id, bar);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_news(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_news(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1144,24 +1795,22 @@ where
 
         let request_id = decode_i32(&mut fields_itr)?;
         let time = decode_string(&mut fields_itr)?;
-        let provider_code = decode_string(&mut fields_itr)?;
-        let article_id = decode_string(&mut fields_itr)?;
-        let headline = decode_string(&mut fields_itr)?;
+        let headline = NewsHeadline::new(
+            parse_historical_news_time(time.as_ref()).unwrap_or_default(),
+            decode_string(&mut fields_itr)?,
+            decode_string(&mut fields_itr)?,
+            decode_string(&mut fields_itr)?,
+            "".to_string(),
+        );
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .historical_news(
-                request_id,
-                time.as_ref(),
-                provider_code.as_ref(),
-                article_id.as_ref(),
-                headline.as_ref(),
-            );
+            .historical_news(request_id, headline);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_news_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_news_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1178,7 +1827,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_ticks(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_ticks(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1196,24 +1845,33 @@ where
             };
             fields_itr.next(); // for consistency
             historical_tick.price = decode_f64(&mut fields_itr)?;
-            historical_tick.size = decode_i32(&mut fields_itr)?;
+            historical_tick.size = decode_size(&mut fields_itr)?;
             ticks.push(historical_tick);
         }
 
         let done = decode_bool(&mut fields_itr)?;
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_ticks(request_id, ticks, done);
+            .expect(STREAMS_POISONED_MUTEX)
+            .historical_ticks
+            .send(
+                request_id,
+                HistoricalTicksEvent::Ticks(HistoricalTicks::Midpoint(ticks)),
+            );
+
+        if let Err(HistoricalTicksEvent::Ticks(HistoricalTicks::Midpoint(ticks))) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .historical_ticks(request_id, ticks, done);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_ticks_bid_ask(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_historical_ticks_bid_ask(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1237,22 +1895,34 @@ where
             historical_tick_bid_ask.tick_attrib_bid_ask = tick_attrib_bid_ask;
             historical_tick_bid_ask.price_bid = decode_f64(&mut fields_itr)?;
             historical_tick_bid_ask.price_ask = decode_f64(&mut fields_itr)?;
-            historical_tick_bid_ask.size_bid = decode_i32(&mut fields_itr)?;
-            historical_tick_bid_ask.size_ask = decode_i32(&mut fields_itr)?;
+            historical_tick_bid_ask.size_bid = decode_size(&mut fields_itr)?;
+            historical_tick_bid_ask.size_ask = decode_size(&mut fields_itr)?;
             ticks.push(historical_tick_bid_ask);
         }
 
         let done = decode_bool(&mut fields_itr)?;
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_ticks_bid_ask(request_id, ticks, done);
+            .expect(STREAMS_POISONED_MUTEX)
+            .historical_ticks
+            .send(
+                request_id,
+                HistoricalTicksEvent::Ticks(HistoricalTicks::BidAsk(ticks)),
+            );
+
+        if let Err(HistoricalTicksEvent::Ticks(HistoricalTicks::BidAsk(ticks))) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .historical_ticks_bid_ask(request_id, ticks, done);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_historical_ticks_last(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_historical_ticks_last(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1275,7 +1945,7 @@ where
             };
             historical_tick_last.tick_attrib_last = tick_attrib_last;
             historical_tick_last.price = decode_f64(&mut fields_itr)?;
-            historical_tick_last.size = decode_i32(&mut fields_itr)?;
+            historical_tick_last.size = decode_size(&mut fields_itr)?;
             historical_tick_last.exchange = decode_string(&mut fields_itr)?;
             historical_tick_last.special_conditions = decode_string(&mut fields_itr)?;
             ticks.push(historical_tick_last);
@@ -1283,15 +1953,27 @@ where
 
         let done = decode_bool(&mut fields_itr)?;
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .historical_ticks_last(request_id, ticks, done);
+            .expect(STREAMS_POISONED_MUTEX)
+            .historical_ticks
+            .send(
+                request_id,
+                HistoricalTicksEvent::Ticks(HistoricalTicks::Trades(ticks)),
+            );
+
+        if let Err(HistoricalTicksEvent::Ticks(HistoricalTicks::Trades(ticks))) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .historical_ticks_last(request_id, ticks, done);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_managed_accounts(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_managed_accounts(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1300,6 +1982,10 @@ where
         fields_itr.next();
 
         let accounts_list = decode_string(&mut fields_itr)?;
+        self.accounts
+            .lock()
+            .expect(ACCOUNTS_POISONED_MUTEX)
+            .update(&accounts_list);
         info!("calling managed_accounts");
         self.wrapper
             .lock()
@@ -1310,7 +1996,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_data_type(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_data_type(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
         // Throw away message_id.
         fields_itr.next();
@@ -1326,7 +2012,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_depth(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_depth(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
         // Throw away message_id.
         fields_itr.next();
@@ -1339,17 +2025,39 @@ where
         let operation = decode_i32(&mut fields_itr)?;
         let side = decode_i32(&mut fields_itr)?;
         let price = decode_f64(&mut fields_itr)?;
-        let size = decode_i32(&mut fields_itr)?;
+        let size = decode_size(&mut fields_itr)?;
+
+        self.streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .update_order_book(
+                request_id,
+                &DepthUpdate {
+                    position,
+                    market_maker: "",
+                    operation,
+                    side,
+                    price,
+                    size,
+                },
+            );
 
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .update_market_depth(request_id, position, operation, side, price, size);
+            .update_market_depth(
+                request_id,
+                position,
+                operation,
+                side,
+                price,
+                size_to_decimal(size),
+            );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_depth_l2(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_depth_l2(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1364,13 +2072,28 @@ where
         let operation = decode_i32(&mut fields_itr)?;
         let side = decode_i32(&mut fields_itr)?;
         let price = decode_f64(&mut fields_itr)?;
-        let size = decode_i32(&mut fields_itr)?;
+        let size = decode_size(&mut fields_itr)?;
         let mut is_smart_depth = false;
 
         if self.server_version >= MIN_SERVER_VER_SMART_DEPTH {
             is_smart_depth = decode_bool(&mut fields_itr)?;
         }
 
+        self.streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .update_order_book(
+                request_id,
+                &DepthUpdate {
+                    position,
+                    market_maker: &market_maker,
+                    operation,
+                    side,
+                    price,
+                    size,
+                },
+            );
+
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
@@ -1381,14 +2104,14 @@ where
                 operation,
                 side,
                 price,
-                size,
+                size_to_decimal(size),
                 is_smart_depth,
             );
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_rule(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_rule(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1397,13 +2120,20 @@ where
         let market_rule_id = decode_i32(&mut fields_itr)?;
 
         let price_increments_count = decode_i32(&mut fields_itr)?;
-        let price_increments = vec![
-            PriceIncrement {
+        // Each increment takes two fields
+        let mut price_increments =
+            Vec::with_capacity((price_increments_count.max(0) as usize).min(fields.len() / 2));
+        for _ in 0..price_increments_count {
+            price_increments.push(PriceIncrement {
                 low_edge: decode_f64(&mut fields_itr)?,
                 increment: decode_f64(&mut fields_itr)?,
-            };
-            price_increments_count as _
-        ];
+            });
+        }
+
+        self.market_rules
+            .lock()
+            .expect(MARKET_RULES_POISONED_MUTEX)
+            .insert(MarketRule::new(market_rule_id, price_increments.clone()));
 
         self.wrapper
             .lock()
@@ -1413,7 +2143,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_market_depth_exchanges(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_market_depth_exchanges(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1446,24 +2176,26 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_news_article(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_news_article(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
         let request_id = decode_i32(&mut fields_itr)?;
-        let article_type = decode_i32(&mut fields_itr)?;
-        let article_text = decode_string(&mut fields_itr)?;
+        let article = NewsArticle::new(
+            FromPrimitive::from_i32(decode_i32(&mut fields_itr)?).unwrap_or(NewsArticleType::Text),
+            decode_string(&mut fields_itr)?,
+        );
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .news_article(request_id, article_type, article_text.as_ref());
+            .news_article(request_id, article);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_news_bulletins(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_news_bulletins(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1471,38 +2203,38 @@ where
         // Throw away version.
         fields_itr.next();
 
-        let news_msg_id = decode_i32(&mut fields_itr)?;
-        let news_msg_type = decode_i32(&mut fields_itr)?;
-        let news_message = decode_string(&mut fields_itr)?;
-        let originating_exch = decode_string(&mut fields_itr)?;
+        let bulletin = NewsBulletin::new(
+            decode_i32(&mut fields_itr)?,
+            FromPrimitive::from_i32(decode_i32(&mut fields_itr)?)
+                .unwrap_or(NewsBulletinType::Regular),
+            decode_string(&mut fields_itr)?,
+            decode_string(&mut fields_itr)?,
+        );
 
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .update_news_bulletin(
-                news_msg_id,
-                news_msg_type,
-                news_message.as_ref(),
-                originating_exch.as_ref(),
-            );
+            .update_news_bulletin(bulletin);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_news_providers(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_news_providers(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
         let news_providers_count = decode_i32(&mut fields_itr)?;
-        let news_providers = vec![
-            NewsProvider {
+        // Each provider takes two fields
+        let mut news_providers =
+            Vec::with_capacity((news_providers_count.max(0) as usize).min(fields.len() / 2));
+        for _ in 0..news_providers_count {
+            news_providers.push(NewsProvider {
                 code: decode_string(&mut fields_itr)?,
                 name: decode_string(&mut fields_itr)?,
-            };
-            news_providers_count as _
-        ];
+            });
+        }
 
         self.wrapper
             .lock()
@@ -1512,7 +2244,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_next_valid_id(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_next_valid_id(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1529,30 +2261,23 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_open_order(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
-        let mut fields_itr = fields.iter();
-        //info!("Processing open order");
-        // Throw away message_id.
-        fields_itr.next();
+    fn process_open_order(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
+        let (contract, order, order_state) = decode_open_order(fields, self.server_version)?;
 
-        let mut order = Order::default();
-        let mut contract = Contract::default();
-        let mut order_state = OrderState::default();
-
-        let mut version = self.server_version;
-        if self.server_version < MIN_SERVER_VER_ORDER_CONTAINER {
-            version = decode_i32(&mut fields_itr)?;
+        if order.what_if {
+            let mut streams = self.streams.lock().expect(STREAMS_POISONED_MUTEX);
+            let event = WhatIfEvent::Result(Box::new(order_state.clone()));
+            if streams.what_if.send(order.order_id, event).is_ok() {
+                streams.what_if.remove(order.order_id);
+                return Ok(());
+            }
         }
 
-        let mut order_decoder = OrderDecoder::new(
-            &mut contract,
-            &mut order,
-            &mut order_state,
-            version,
-            self.server_version,
-        );
-
-        order_decoder.decode_open(&mut fields_itr)?;
+        if !order_state.status.is_empty() {
+            let mut orders = self.orders.lock().expect(ORDERS_POISONED_MUTEX);
+            orders.update(order.order_id, &order_state.status);
+            orders.set_order_ref(order.order_id, &order.order_ref);
+        }
 
         self.wrapper
             .lock()
@@ -1562,7 +2287,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_open_order_end(&mut self, _fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_open_order_end(&mut self, _fields: &[&str]) -> Result<(), IbkrError> {
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
@@ -1571,7 +2296,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_order_bound(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_order_bound(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1589,7 +2314,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_order_status(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_order_status(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1605,17 +2330,17 @@ where
 
         let filled;
         if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
-            filled = decode_f64(&mut fields_itr)?;
+            filled = decode_decimal(&mut fields_itr)?;
         } else {
-            filled = decode_i32(&mut fields_itr)? as f64;
+            filled = BigDecimal::from(decode_i32(&mut fields_itr)?);
         }
 
         let remaining;
 
         if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
-            remaining = decode_f64(&mut fields_itr)?;
+            remaining = decode_decimal(&mut fields_itr)?;
         } else {
-            remaining = decode_i32(&mut fields_itr)? as f64;
+            remaining = BigDecimal::from(decode_i32(&mut fields_itr)?);
         }
 
         let avg_fill_price = decode_f64(&mut fields_itr)?;
@@ -1631,6 +2356,18 @@ where
             market_cap_price = decode_f64(&mut fields_itr)?;
         }
 
+        self.orders
+            .lock()
+            .expect(ORDERS_POISONED_MUTEX)
+            .update(order_id, &status);
+        self.write_journal(JournalEntry::Status {
+            order_id,
+            status: status.clone(),
+            filled: decimal_to_f64(&filled),
+            remaining: decimal_to_f64(&remaining),
+            avg_fill_price,
+        });
+
         self.wrapper
             .try_lock()
             .expect(WRAPPER_POISONED_MUTEX)
@@ -1651,7 +2388,26 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_pnl(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    /// What TWS reports is journaled after the fact, so a journal that
+    /// cannot be written does not stop the message
+    fn write_journal(&self, entry: JournalEntry) {
+        if let Some(journal) = self.journal.lock().expect(JOURNAL_POISONED_MUTEX).as_mut() {
+            if let Err(err) = journal.append(entry) {
+                warn!("Could not write to the order journal: {}", err);
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls the alert hooks, if there are any
+    fn alert<F: FnOnce(&mut AlertHooks) -> usize>(&self, apply: F) {
+        if let Some(alerts) = self.alerts.lock().expect(ALERTS_POISONED_MUTEX).as_mut() {
+            apply(alerts);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn process_pnl(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1670,22 +2426,32 @@ where
             realized_pnl = decode_f64(&mut fields_itr)?;
         }
 
-        self.wrapper
+        let event = PnlEvent::Update(ProfitAndLoss::new(daily_pnl, unrealized_pnl, realized_pnl));
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .profit_and_loss(request_id, daily_pnl, unrealized_pnl, realized_pnl);
+            .expect(STREAMS_POISONED_MUTEX)
+            .pnl
+            .send(request_id, event);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .profit_and_loss(request_id, daily_pnl, unrealized_pnl, realized_pnl);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_pnl_single(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_pnl_single(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
         let request_id = decode_i32(&mut fields_itr)?;
-        let pos = decode_i32(&mut fields_itr)?;
+        let pos = decode_string(&mut fields_itr)?;
         let daily_pnl = decode_f64(&mut fields_itr)?;
         let mut unrealized_pnl = 0.0;
         let mut realized_pnl = 0.0;
@@ -1700,22 +2466,38 @@ where
 
         let value = decode_f64(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .profit_and_loss_single(
-                request_id,
-                pos,
-                daily_pnl,
-                unrealized_pnl,
-                realized_pnl,
-                value,
-            );
+        let event = PnlSingleEvent::Update(SingleProfitAndLoss::new(
+            BigDecimal::from_str(pos.trim()).unwrap_or_default(),
+            daily_pnl,
+            unrealized_pnl,
+            realized_pnl,
+            value,
+        ));
+        let sent = self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .pnl_single
+            .send(request_id, event);
+
+        if let Err(PnlSingleEvent::Update(pnl)) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .profit_and_loss_single(
+                    request_id,
+                    pnl.position,
+                    daily_pnl,
+                    unrealized_pnl,
+                    realized_pnl,
+                    value,
+                );
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_portfolio_value(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_portfolio_value(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1727,10 +2509,10 @@ where
         let mut contract = Contract {
             con_id: decode_i32(&mut fields_itr)?, // ver 6 field
             symbol: decode_string(&mut fields_itr)?,
-            sec_type: decode_string(&mut fields_itr)?,
+            sec_type: decode_string(&mut fields_itr)?.into(),
             last_trade_date_or_contract_month: decode_string(&mut fields_itr)?,
             strike: decode_f64(&mut fields_itr)?,
-            right: decode_string(&mut fields_itr)?,
+            right: decode_right(&mut fields_itr)?,
             ..Default::default()
         };
 
@@ -1745,16 +2527,15 @@ where
             contract.trading_class = decode_string(&mut fields_itr)?;
         }
 
-        let position;
-        if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
-            position = decode_f64(&mut fields_itr)?;
+        let position = if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
+            decode_decimal(&mut fields_itr)?
         } else {
-            position = decode_i32(&mut fields_itr)? as f64;
-        }
+            BigDecimal::from(decode_i32(&mut fields_itr)?)
+        };
 
         let market_price = decode_f64(&mut fields_itr)?;
-        let market_value = decode_f64(&mut fields_itr)?;
-        let average_cost = decode_f64(&mut fields_itr)?; // ver 3 field
+        let market_value = decode_decimal(&mut fields_itr)?;
+        let average_cost = decode_decimal(&mut fields_itr)?; // ver 3 field
         let unrealized_pnl = decode_f64(&mut fields_itr)?; // ver 3 field
         let realized_pnl = decode_f64(&mut fields_itr)?; // ver 3 field
 
@@ -1764,10 +2545,9 @@ where
             contract.primary_exchange = decode_string(&mut fields_itr)?;
         }
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .update_portfolio(
+        let event = AccountUpdateEvent::Portfolio {
+            account: account_name,
+            item: Box::new(PortfolioItem::new(
                 contract,
                 position,
                 market_price,
@@ -1775,13 +2555,35 @@ where
                 average_cost,
                 unrealized_pnl,
                 realized_pnl,
-                account_name.as_ref(),
-            );
+            )),
+        };
+        let sent = self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .account_updates
+            .send(NO_VALID_ID, event);
+
+        if let Err(AccountUpdateEvent::Portfolio { account, item }) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .update_portfolio(
+                    item.contract,
+                    item.position,
+                    item.market_price,
+                    decimal_to_f64(&item.market_value),
+                    decimal_to_f64(&item.average_cost),
+                    item.unrealized_pnl,
+                    item.realized_pnl,
+                    account.as_ref(),
+                );
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_position_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1795,10 +2597,10 @@ where
         let mut contract = Contract {
             con_id: decode_i32(&mut fields_itr)?,
             symbol: decode_string(&mut fields_itr)?,
-            sec_type: decode_string(&mut fields_itr)?,
+            sec_type: decode_string(&mut fields_itr)?.into(),
             last_trade_date_or_contract_month: decode_string(&mut fields_itr)?,
             strike: decode_f64(&mut fields_itr)?,
-            right: decode_string(&mut fields_itr)?,
+            right: decode_right(&mut fields_itr)?,
             multiplier: decode_string(&mut fields_itr)?,
             exchange: decode_string(&mut fields_itr)?,
             currency: decode_string(&mut fields_itr)?,
@@ -1809,38 +2611,58 @@ where
             contract.trading_class = decode_string(&mut fields_itr)?;
         }
 
-        let position;
-        if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
-            position = decode_f64(&mut fields_itr)?;
+        let size = if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
+            decode_decimal(&mut fields_itr)?
         } else {
-            position = decode_i32(&mut fields_itr)? as f64;
-        }
+            BigDecimal::from(decode_i32(&mut fields_itr)?)
+        };
 
         let mut avg_cost = 0.0;
         if version >= 3 {
             avg_cost = decode_f64(&mut fields_itr)?;
         }
 
-        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).position(
-            account.as_ref(),
-            contract,
-            position,
-            avg_cost,
-        );
+        let event = PositionEvent::Position(Box::new(Position::new(
+            &account, contract, size, avg_cost, "",
+        )));
+        let sent = self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .positions
+            .send(NO_VALID_ID, event);
+
+        if let Err(PositionEvent::Position(position)) = sent {
+            self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).position(
+                account.as_ref(),
+                position.contract,
+                position.size,
+                avg_cost,
+            );
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_end(&mut self, _fields: &[String]) -> Result<(), IBKRApiLibError> {
-        self.wrapper
+    fn process_position_end(&mut self, _fields: &[&str]) -> Result<(), IbkrError> {
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .position_end();
+            .expect(STREAMS_POISONED_MUTEX)
+            .positions
+            .send(NO_VALID_ID, PositionEvent::End);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .position_end();
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_multi(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_position_multi(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1856,10 +2678,10 @@ where
         let contract = Contract {
             con_id: decode_i32(&mut fields_itr)?,
             symbol: decode_string(&mut fields_itr)?,
-            sec_type: decode_string(&mut fields_itr)?,
+            sec_type: decode_string(&mut fields_itr)?.into(),
             last_trade_date_or_contract_month: decode_string(&mut fields_itr)?,
             strike: decode_f64(&mut fields_itr)?,
-            right: decode_string(&mut fields_itr)?,
+            right: decode_right(&mut fields_itr)?,
             multiplier: decode_string(&mut fields_itr)?,
             exchange: decode_string(&mut fields_itr)?,
             currency: decode_string(&mut fields_itr)?,
@@ -1868,27 +2690,43 @@ where
             ..Default::default()
         };
 
-        let position = decode_f64(&mut fields_itr)?;
+        let size = decode_decimal(&mut fields_itr)?;
         let avg_cost = decode_f64(&mut fields_itr)?;
         let model_code = decode_string(&mut fields_itr)?;
 
-        self.wrapper
+        let event = PositionEvent::Position(Box::new(Position::new(
+            &account,
+            contract,
+            size,
+            avg_cost,
+            &model_code,
+        )));
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .position_multi(
-                request_id,
-                account.as_ref(),
-                model_code.as_ref(),
-                contract,
-                position,
-                avg_cost,
-            );
+            .expect(STREAMS_POISONED_MUTEX)
+            .positions_multi
+            .send(request_id, event);
+
+        if let Err(PositionEvent::Position(position)) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .position_multi(
+                    request_id,
+                    account.as_ref(),
+                    model_code.as_ref(),
+                    position.contract,
+                    position.size,
+                    avg_cost,
+                );
+        }
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_position_multi_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_position_multi_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1897,15 +2735,24 @@ where
         fields_itr.next();
 
         let request_id = decode_i32(&mut fields_itr)?;
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .position_multi_end(request_id);
+            .expect(STREAMS_POISONED_MUTEX)
+            .positions_multi
+            .send(request_id, PositionEvent::End);
+
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .position_multi_end(request_id);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_real_time_bars(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_real_time_bars(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1916,28 +2763,37 @@ where
         let request_id = decode_i32(&mut fields_itr)?;
 
         let bar = RealTimeBar {
-            date_time: decode_string(&mut fields_itr)?,
+            date_time: Utc
+                .timestamp_opt(decode_i64(&mut fields_itr)?, 0)
+                .single()
+                .unwrap_or_default(),
             open: decode_f64(&mut fields_itr)?,
             high: decode_f64(&mut fields_itr)?,
             low: decode_f64(&mut fields_itr)?,
             close: decode_f64(&mut fields_itr)?,
-            volume: decode_i64(&mut fields_itr)?,
+            volume: decode_size(&mut fields_itr)?,
             wap: decode_f64(&mut fields_itr)?,
             count: decode_i32(&mut fields_itr)?,
         };
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .realtime_bar(request_id, bar);
+            .expect(STREAMS_POISONED_MUTEX)
+            .real_time_bars
+            .send(request_id, bar);
+
+        if let Err(bar) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .realtime_bar(request_id, bar);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_receive_financial_advisor(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_receive_financial_advisor(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1945,24 +2801,29 @@ where
         // Throw away version.
         fields_itr.next();
 
-        let fa_data_type = decode_i32(&mut fields_itr)?;
+        let fa_data_type: FaDataType = decode_enum(&mut fields_itr)?;
         let xml = decode_string(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .receive_financial_advisor(
-                FromPrimitive::from_i32(fa_data_type).unwrap(),
-                xml.as_ref(),
-            );
+        let sent = {
+            let mut streams = self.streams.lock().expect(STREAMS_POISONED_MUTEX);
+            let sent = streams.fa_data.send(fa_data_type as i32, xml);
+            if sent.is_ok() {
+                streams.fa_data.remove(fa_data_type as i32);
+            }
+            sent
+        };
+
+        if let Err(xml) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .receive_financial_advisor(fa_data_type, xml.as_ref());
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_reroute_market_data_request(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_reroute_market_data_request(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -1980,10 +2841,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_reroute_market_depth_request(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_reroute_market_depth_request(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2001,7 +2859,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_scanner_data(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_scanner_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2012,6 +2870,7 @@ where
         let request_id = decode_i32(&mut fields_itr)?;
 
         let number_of_elements = decode_i32(&mut fields_itr)?;
+        let mut scan_data = Vec::with_capacity(number_of_elements.max(0) as usize);
 
         for _ in 0..number_of_elements {
             let mut data = ScanData {
@@ -2023,10 +2882,10 @@ where
                 contract: Contract {
                     con_id: decode_i32(&mut fields_itr)?, // ver 3 field
                     symbol: decode_string(&mut fields_itr)?,
-                    sec_type: decode_string(&mut fields_itr)?,
+                    sec_type: decode_string(&mut fields_itr)?.into(),
                     last_trade_date_or_contract_month: decode_string(&mut fields_itr)?,
                     strike: decode_f64(&mut fields_itr)?,
-                    right: decode_string(&mut fields_itr)?,
+                    right: decode_right(&mut fields_itr)?,
                     exchange: decode_string(&mut fields_itr)?,
                     currency: decode_string(&mut fields_itr)?,
                     local_symbol: decode_string(&mut fields_itr)?,
@@ -2042,29 +2901,43 @@ where
             data.projection = decode_string(&mut fields_itr)?;
             data.legs = decode_string(&mut fields_itr)?;
 
+            scan_data.push(data);
+        }
+
+        scan_data.sort_by_key(|data| data.rank);
+        let sent = self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .scanner_data
+            .send(request_id, scan_data);
+
+        if let Err(scan_data) = sent {
+            for data in scan_data {
+                self.wrapper
+                    .lock()
+                    .expect(WRAPPER_POISONED_MUTEX)
+                    .scanner_data(
+                        request_id,
+                        data.rank,
+                        data.contract,
+                        data.distance.as_ref(),
+                        data.benchmark.as_ref(),
+                        data.projection.as_ref(),
+                        data.legs.as_ref(),
+                    );
+            }
+
             self.wrapper
                 .lock()
                 .expect(WRAPPER_POISONED_MUTEX)
-                .scanner_data(
-                    request_id,
-                    data.rank,
-                    data.contract,
-                    data.distance.as_ref(),
-                    data.benchmark.as_ref(),
-                    data.projection.as_ref(),
-                    data.legs.as_ref(),
-                );
+                .scanner_data_end(request_id);
         }
-
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .scanner_data_end(request_id);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_scanner_parameters(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_scanner_parameters(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2083,8 +2956,8 @@ where
     //----------------------------------------------------------------------------------------------
     fn process_security_definition_option_parameter(
         &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+        fields: &[&str],
+    ) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2105,13 +2978,37 @@ where
         }
 
         let strike_count = decode_i32(&mut fields_itr)?;
-        let mut strikes = HashSet::new();
+        let mut strike_values = vec![];
         for _ in 0..strike_count {
-            let strike = decode_f64(&mut fields_itr)?;
-            let big_strike = BigDecimal::from_f64(strike).unwrap();
-            strikes.insert(big_strike);
+            strike_values.push(decode_f64(&mut fields_itr)?);
+        }
+
+        let parameters = OptionChainParameters::new(
+            exchange.clone(),
+            underlying_con_id,
+            trading_class.clone(),
+            multiplier.clone(),
+            &expirations.iter().cloned().collect::<Vec<String>>(),
+            &strike_values,
+        );
+        let sent = self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .option_chains
+            .send(
+                request_id,
+                OptionChainEvent::Parameters(Box::new(parameters)),
+            );
+        if sent.is_ok() {
+            return Ok(());
         }
 
+        // Strikes that are not finite have no decimal
+        let strikes = strike_values
+            .into_iter()
+            .filter_map(BigDecimal::from_f64)
+            .collect();
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
@@ -2130,23 +3027,31 @@ where
     //----------------------------------------------------------------------------------------------
     fn process_security_definition_option_parameter_end(
         &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+        fields: &[&str],
+    ) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
         let request_id = decode_i32(&mut fields_itr)?;
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .security_definition_option_parameter_end(request_id);
+            .expect(STREAMS_POISONED_MUTEX)
+            .option_chains
+            .send(request_id, OptionChainEvent::End);
+        if sent.is_err() {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .security_definition_option_parameter_end(request_id);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_smart_components(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_smart_components(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2156,14 +3061,22 @@ where
 
         let count = decode_i32(&mut fields_itr)?;
 
-        let smart_components = vec![
-            SmartComponent {
+        // Each component takes three fields
+        let mut smart_components =
+            Vec::with_capacity((count.max(0) as usize).min(fields.len() / 3));
+        for _ in 0..count {
+            smart_components.push(SmartComponent {
                 bit_number: decode_i32(&mut fields_itr)?,
                 exchange: decode_string(&mut fields_itr)?,
                 exchange_letter: decode_string(&mut fields_itr)?,
-            };
-            count as _
-        ];
+            });
+        }
+
+        let smart_components = self
+            .smart_components
+            .lock()
+            .expect(SMART_COMPONENTS_POISONED_MUTEX)
+            .complete_request(request_id, SmartComponentMap::new(smart_components));
 
         self.wrapper
             .lock()
@@ -2173,7 +3086,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_soft_dollar_tiers(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_soft_dollar_tiers(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2183,14 +3096,25 @@ where
 
         let count = decode_i32(&mut fields_itr)?;
 
-        let tiers = vec![
-            SoftDollarTier {
+        let mut tiers = Vec::with_capacity(count.max(0) as usize);
+        for _ in 0..count {
+            tiers.push(SoftDollarTier {
                 name: decode_string(&mut fields_itr)?,
                 val: decode_string(&mut fields_itr)?,
                 display_name: decode_string(&mut fields_itr)?,
-            };
-            count as _
-        ];
+            });
+        }
+
+        let tiers = match self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .soft_dollar_tiers
+            .send(request_id, tiers)
+        {
+            Ok(()) => return Ok(()),
+            Err(tiers) => tiers,
+        };
 
         self.wrapper
             .lock()
@@ -2200,7 +3124,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_symbol_samples(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_symbol_samples(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2215,7 +3139,7 @@ where
                 contract: Contract {
                     con_id: decode_i32(&mut fields_itr)?,
                     symbol: decode_string(&mut fields_itr)?,
-                    sec_type: decode_string(&mut fields_itr)?,
+                    sec_type: decode_string(&mut fields_itr)?.into(),
                     primary_exchange: decode_string(&mut fields_itr)?,
                     currency: decode_string(&mut fields_itr)?,
                     ..Default::default()
@@ -2227,22 +3151,34 @@ where
             con_desc.derivative_sec_types = vec![];
             for _ in 0..derivative_sec_types_cnt {
                 let deriv_sec_type = decode_string(&mut fields_itr)?;
-                con_desc.derivative_sec_types.push(deriv_sec_type);
+                con_desc.derivative_sec_types.push(deriv_sec_type.into());
             }
 
             contract_descriptions.push(con_desc);
         }
 
-        self.wrapper
+        let sent = self
+            .streams
             .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .symbol_samples(request_id, contract_descriptions);
+            .expect(STREAMS_POISONED_MUTEX)
+            .symbol_samples
+            .send(
+                request_id,
+                SymbolSamplesEvent::Descriptions(contract_descriptions),
+            );
+
+        if let Err(SymbolSamplesEvent::Descriptions(contract_descriptions)) = sent {
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .symbol_samples(request_id, contract_descriptions);
+        }
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_by_tick(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_by_tick(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2250,16 +3186,14 @@ where
 
         let request_id = decode_i32(&mut fields_itr)?;
 
-        let tick_type = decode_i32(&mut fields_itr)?;
+        let tick_type: TickByTickType = decode_enum(&mut fields_itr)?;
         let time = decode_i64(&mut fields_itr)?;
 
         match tick_type {
-            0 => return Ok(()), // None
-            1..=2 =>
-            // Last (1) or AllLast (2)
-            {
+            TickByTickType::None => return Ok(()),
+            TickByTickType::Last | TickByTickType::AllLast => {
                 let price = decode_f64(&mut fields_itr)?;
-                let size = decode_i32(&mut fields_itr)?;
+                let size = decode_size(&mut fields_itr)?;
                 let mask = decode_i32(&mut fields_itr)?;
                 let tick_attrib_last = TickAttribLast {
                     past_limit: mask & 1 != 0,
@@ -2272,22 +3206,20 @@ where
                     .expect(WRAPPER_POISONED_MUTEX)
                     .tick_by_tick_all_last(
                         request_id,
-                        FromPrimitive::from_i32(tick_type).unwrap(),
+                        tick_type,
                         time,
                         price,
-                        size,
+                        size_to_decimal(size),
                         tick_attrib_last,
                         exchange.as_ref(),
                         special_conditions.as_ref(),
                     );
             }
-            3 =>
-            // BidAsk
-            {
+            TickByTickType::BidAsk => {
                 let bid_price = decode_f64(&mut fields_itr)?;
                 let ask_price = decode_f64(&mut fields_itr)?;
-                let bid_size = decode_i32(&mut fields_itr)?;
-                let ask_size = decode_i32(&mut fields_itr)?;
+                let bid_size = decode_size(&mut fields_itr)?;
+                let ask_size = decode_size(&mut fields_itr)?;
                 let mask = decode_i32(&mut fields_itr)?;
                 let tick_attrib_bid_ask = TickAttribBidAsk {
                     bid_past_low: mask & 1 != 0,
@@ -2301,31 +3233,25 @@ where
                         time,
                         bid_price,
                         ask_price,
-                        bid_size,
-                        ask_size,
+                        size_to_decimal(bid_size),
+                        size_to_decimal(ask_size),
                         tick_attrib_bid_ask,
                     );
             }
-            4 =>
-            // MidPoint
-            {
+            TickByTickType::MidPoint => {
                 let mid_point = decode_f64(&mut fields_itr)?;
                 self.wrapper
                     .lock()
                     .expect(WRAPPER_POISONED_MUTEX)
                     .tick_by_tick_mid_point(request_id, time, mid_point);
             }
-            _ => return Ok(()),
         }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn process_tick_exchange_for_physical(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_tick_exchange_for_physical(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2334,7 +3260,7 @@ where
         fields_itr.next();
 
         let ticker_id = decode_i32(&mut fields_itr)?;
-        let tick_type = decode_i32(&mut fields_itr)?;
+        let tick_type: TickType = decode_enum(&mut fields_itr)?;
         let basis_points = decode_f64(&mut fields_itr)?;
         let formatted_basis_points = decode_string(&mut fields_itr)?;
         let implied_futures_price = decode_f64(&mut fields_itr)?;
@@ -2347,7 +3273,7 @@ where
             .expect(WRAPPER_POISONED_MUTEX)
             .tick_exchange_for_physical(
                 ticker_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
+                tick_type,
                 basis_points,
                 formatted_basis_points.as_ref(),
                 implied_futures_price,
@@ -2360,7 +3286,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_generic(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_generic(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2369,59 +3295,74 @@ where
         fields_itr.next();
 
         let ticker_id = decode_i32(&mut fields_itr)?;
-        let tick_type = decode_i32(&mut fields_itr)?;
+        let tick_type: TickType = decode_enum(&mut fields_itr)?;
         let value = decode_f64(&mut fields_itr)?;
 
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .tick_generic(
-                ticker_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
-                value,
-            );
+            .tick_generic(ticker_id, tick_type, value);
+
+        if tick_type == TickType::Halted || tick_type == TickType::DelayedHalted {
+            let trading_status = TradingStatus::from_tick_value(value);
+            self.quotes
+                .lock()
+                .expect(QUOTES_POISONED_MUTEX)
+                .update_trading_status(ticker_id, trading_status);
+            self.wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .trading_status(ticker_id, trading_status);
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_news(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_news(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
         let ticker_id = decode_i32(&mut fields_itr)?;
-        let time_stamp = decode_i32(&mut fields_itr)?;
-        let provider_code = decode_string(&mut fields_itr)?;
-        let article_id = decode_string(&mut fields_itr)?;
-        let headline = decode_string(&mut fields_itr)?;
-        let extra_data = decode_string(&mut fields_itr)?;
+        // Milliseconds since the epoch
+        let time_stamp = decode_i64(&mut fields_itr)?;
+        let headline = NewsHeadline::new(
+            Utc.timestamp_millis_opt(time_stamp)
+                .single()
+                .unwrap_or_default(),
+            decode_string(&mut fields_itr)?,
+            decode_string(&mut fields_itr)?,
+            decode_string(&mut fields_itr)?,
+            decode_string(&mut fields_itr)?,
+        );
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
-            .tick_news(
-                ticker_id,
-                time_stamp,
-                provider_code.as_ref(),
-                article_id.as_ref(),
-                headline.as_ref(),
-                extra_data.as_ref(),
-            );
+            .tick_news(ticker_id, headline);
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_option_computation(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_tick_option_computation(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
         fields_itr.next();
 
-        let version = decode_i32(&mut fields_itr)?;
+        // Servers from MIN_SERVER_VER_PRICE_BASED_VOLATILITY on send all
+        // fields and no version
+        let version = if self.server_version < MIN_SERVER_VER_PRICE_BASED_VOLATILITY {
+            decode_i32(&mut fields_itr)?
+        } else {
+            IncomingMessageIds::TickOptionComputation.version()
+        };
         let ticker_id = decode_i32(&mut fields_itr)?;
-        let tick_type = decode_i32(&mut fields_itr)?;
+        let tick_type: TickType = decode_enum(&mut fields_itr)?;
+        let tick_attrib = if self.server_version >= MIN_SERVER_VER_PRICE_BASED_VOLATILITY {
+            decode_i32(&mut fields_itr)?
+        } else {
+            UNSET_INTEGER
+        };
         let mut implied_vol = decode_f64(&mut fields_itr)?;
         if approx_eq!(f64, implied_vol, -1.0, ulps = 2) {
             // -1 is the "not yet computed" indicator
@@ -2440,8 +3381,8 @@ where
         let mut theta = f64::max_value();
         let mut und_price = f64::max_value();
         if version >= 6
-            || tick_type == TickType::ModelOption as i32
-            || tick_type == TickType::DelayedModelOption as i32
+            || tick_type == TickType::ModelOption
+            || tick_type == TickType::DelayedModelOption
         {
             // introduced in version == 5
             opt_price = decode_f64(&mut fields_itr)?;
@@ -2483,7 +3424,8 @@ where
             .expect(WRAPPER_POISONED_MUTEX)
             .tick_option_computation(
                 ticker_id,
-                FromPrimitive::from_i32(tick_type).unwrap(),
+                tick_type,
+                tick_attrib,
                 implied_vol,
                 delta,
                 opt_price,
@@ -2497,10 +3439,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_request_parameters(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_tick_request_parameters(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2510,6 +3449,12 @@ where
         let min_tick = decode_f64(&mut fields_itr)?;
         let bbo_exchange = decode_string(&mut fields_itr)?;
         let snapshot_permissions = decode_i32(&mut fields_itr)?;
+
+        self.smart_components
+            .lock()
+            .expect(SMART_COMPONENTS_POISONED_MUTEX)
+            .set_bbo_exchange(ticker_id, bbo_exchange.as_ref());
+
         self.wrapper
             .lock()
             .expect(WRAPPER_POISONED_MUTEX)
@@ -2523,7 +3468,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_size(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_size(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2532,18 +3477,19 @@ where
         fields_itr.next();
 
         let ticker_id = decode_i32(&mut fields_itr)?;
-        let tick_type = decode_i32(&mut fields_itr)?;
-        let size = decode_i32(&mut fields_itr)?;
+        let tick_type: TickType = decode_enum(&mut fields_itr)?;
+        let size = decode_size(&mut fields_itr)?;
 
-        self.wrapper
-            .lock()
-            .expect(WRAPPER_POISONED_MUTEX)
-            .tick_size(ticker_id, FromPrimitive::from_i32(tick_type).unwrap(), size);
+        self.conflate_tick(ConflatedTick::Size {
+            request_id: ticker_id,
+            tick_type,
+            size,
+        });
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_tick_snapshot_end(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_tick_snapshot_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2561,10 +3507,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_verify_and_auth_completed(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_verify_and_auth_completed(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2583,10 +3526,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_verify_and_auth_message_api(
-        &mut self,
-        fields: &[String],
-    ) -> Result<(), IBKRApiLibError> {
+    fn process_verify_and_auth_message_api(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
 
         // Throw away message_id.
@@ -2605,7 +3545,7 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_verify_completed(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_verify_completed(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
         // Throw away message_id.
         fields_itr.next();
@@ -2625,7 +3565,7 @@ where
 
     //----------------------------------------------------------------------------------------------
     #[allow(dead_code)]
-    fn process_verify_message_api(&mut self, fields: &[String]) -> Result<(), IBKRApiLibError> {
+    fn process_verify_message_api(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
         let mut fields_itr = fields.iter();
         // Throw away message_id.
         fields_itr.next();
@@ -2641,13 +3581,305 @@ where
         Ok(())
     }
 
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "wsh")]
+    fn process_wsh_meta_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
+        let mut fields_itr = fields.iter();
+
+        // Throw away message_id.
+        fields_itr.next();
+
+        let request_id = decode_i32(&mut fields_itr)?;
+        let data_json = decode_string(&mut fields_itr)?;
+
+        match serde_json::from_str(data_json.as_str()) {
+            Ok(meta_data) => self
+                .wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .wsh_meta_data(request_id, meta_data),
+            Err(err) => self.report_bad_json(request_id, "WSH meta data", &err),
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "wsh")]
+    fn process_wsh_event_data(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
+        let mut fields_itr = fields.iter();
+
+        // Throw away message_id.
+        fields_itr.next();
+
+        let request_id = decode_i32(&mut fields_itr)?;
+        let data_json = decode_string(&mut fields_itr)?;
+
+        match WshEvent::parse_all(data_json.as_str()) {
+            Ok(events) => self
+                .wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .wsh_event_data(request_id, events),
+            Err(err) => self.report_bad_json(request_id, "WSH event data", &err),
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn process_replace_fa_end(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
+        let mut fields_itr = fields.iter();
+
+        // Throw away message_id.
+        fields_itr.next();
+
+        let request_id = decode_i32(&mut fields_itr)?;
+        let text = decode_string(&mut fields_itr)?;
+
+        self.wrapper
+            .lock()
+            .expect(WRAPPER_POISONED_MUTEX)
+            .replace_fa_end(request_id, text.as_str());
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn process_user_info(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
+        let mut fields_itr = fields.iter();
+
+        // Throw away message_id.
+        fields_itr.next();
+
+        let request_id = decode_i32(&mut fields_itr)?;
+        let white_branding_id = decode_string(&mut fields_itr)?;
+
+        let white_branding_id = match self
+            .streams
+            .lock()
+            .expect(STREAMS_POISONED_MUTEX)
+            .user_info
+            .send(request_id, white_branding_id)
+        {
+            Ok(()) => return Ok(()),
+            Err(white_branding_id) => white_branding_id,
+        };
+
+        self.wrapper
+            .lock()
+            .expect(WRAPPER_POISONED_MUTEX)
+            .user_info(request_id, white_branding_id.as_str());
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn process_historical_schedule(&mut self, fields: &[&str]) -> Result<(), IbkrError> {
+        let mut fields_itr = fields.iter();
+
+        // Throw away message_id.
+        fields_itr.next();
+
+        let request_id = decode_i32(&mut fields_itr)?;
+        let start_date_time = decode_string(&mut fields_itr)?;
+        let end_date_time = decode_string(&mut fields_itr)?;
+        let time_zone = decode_string(&mut fields_itr)?;
+        let session_count = decode_i32(&mut fields_itr)?;
+
+        let mut sessions = Vec::with_capacity(session_count.max(0) as usize);
+        for _ in 0..session_count {
+            sessions.push((
+                decode_string(&mut fields_itr)?,
+                decode_string(&mut fields_itr)?,
+                decode_string(&mut fields_itr)?,
+            ));
+        }
+
+        match HistoricalSchedule::parse(
+            start_date_time.as_str(),
+            end_date_time.as_str(),
+            time_zone.as_str(),
+            sessions.as_slice(),
+        ) {
+            Some(schedule) => self
+                .wrapper
+                .lock()
+                .expect(WRAPPER_POISONED_MUTEX)
+                .historical_schedule(request_id, schedule),
+            None => self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).error(
+                request_id,
+                TwsError::BadMessage.code(),
+                format!(
+                    "{} Invalid historical schedule: {} - {} {}",
+                    TwsError::BadMessage.message(),
+                    start_date_time,
+                    end_date_time,
+                    time_zone
+                )
+                .as_str(),
+                "",
+            ),
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Reports a JSON payload that could not be parsed through the `Wrapper`
+    /// instead of ending the message loop
+    #[cfg(feature = "wsh")]
+    fn report_bad_json(&mut self, request_id: i32, what: &str, err: &serde_json::Error) {
+        self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).error(
+            request_id,
+            TwsError::BadMessage.code(),
+            format!(
+                "{} Invalid {}: {}",
+                TwsError::BadMessage.message(),
+                what,
+                err
+            )
+            .as_str(),
+            "",
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the market rules referenced by a contract that are neither
+    /// cached nor requested yet
+    fn fetch_market_rules(&mut self, market_rule_ids: &str) -> Result<(), IbkrError> {
+        if self.server_version < MIN_SERVER_VER_MARKET_RULES || !self.request_sender.is_connected()
+        {
+            return Ok(());
+        }
+
+        let missing_rules = self
+            .market_rules
+            .lock()
+            .expect(MARKET_RULES_POISONED_MUTEX)
+            .missing_rules(market_rule_ids);
+
+        let mut msg = Vec::new();
+        for market_rule_id in missing_rules {
+            begin_message(&mut msg);
+            write_field(&mut msg, &(OutgoingMessageIds::ReqMarketRule as i32))?;
+            write_field(&mut msg, &market_rule_id)?;
+            self.request_sender.send_message(finish_message(&mut msg))?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Passes a price or size tick through the conflator and delivers
+    /// whatever is due to the wrapper
+    fn conflate_tick(&mut self, tick: ConflatedTick) {
+        // The cache always holds the latest values, independent of conflation
+        match &tick {
+            ConflatedTick::Price {
+                request_id,
+                tick_type,
+                price,
+                ..
+            } => self
+                .quotes
+                .lock()
+                .expect(QUOTES_POISONED_MUTEX)
+                .update_tick_price(*request_id, *tick_type, *price),
+            ConflatedTick::Size {
+                request_id,
+                tick_type,
+                size,
+            } => self
+                .quotes
+                .lock()
+                .expect(QUOTES_POISONED_MUTEX)
+                .update_tick_size(*request_id, *tick_type, *size),
+        }
+        let ticks = self
+            .tick_conflator
+            .lock()
+            .expect(CONFLATOR_POISONED_MUTEX)
+            .offer(tick, Instant::now());
+        self.deliver_ticks(ticks);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn deliver_ticks(&mut self, ticks: Vec<ConflatedTick>) {
+        for tick in ticks {
+            let (request_id, tick_type) = match tick {
+                ConflatedTick::Price {
+                    request_id,
+                    tick_type,
+                    price,
+                    attrib,
+                } => {
+                    self.wrapper
+                        .lock()
+                        .expect(WRAPPER_POISONED_MUTEX)
+                        .tick_price(request_id, tick_type, price, attrib);
+                    (request_id, tick_type)
+                }
+                ConflatedTick::Size {
+                    request_id,
+                    tick_type,
+                    size,
+                } => {
+                    self.wrapper
+                        .lock()
+                        .expect(WRAPPER_POISONED_MUTEX)
+                        .tick_size(request_id, tick_type, size_to_decimal(size));
+                    (request_id, tick_type)
+                }
+            };
+
+            if is_auction_tick(tick_type) {
+                let auction_state = self
+                    .quotes
+                    .lock()
+                    .expect(QUOTES_POISONED_MUTEX)
+                    .auction_state(request_id)
+                    .cloned();
+                if let Some(auction_state) = auction_state {
+                    self.wrapper
+                        .lock()
+                        .expect(WRAPPER_POISONED_MUTEX)
+                        .auction_state(request_id, auction_state);
+                }
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Waits for the next message, waking up in between to deliver conflated
+    /// ticks that became due
+    fn next_message(&mut self) -> Result<Result<String, DecodeError>, RecvError> {
+        loop {
+            let next_due = self
+                .tick_conflator
+                .lock()
+                .expect(CONFLATOR_POISONED_MUTEX)
+                .next_due(Instant::now());
+
+            match next_due {
+                Some(timeout) => match self.msg_queue.recv_timeout(timeout) {
+                    Ok(val) => return Ok(val),
+                    Err(RecvTimeoutError::Timeout) => {
+                        let ticks = self
+                            .tick_conflator
+                            .lock()
+                            .expect(CONFLATOR_POISONED_MUTEX)
+                            .drain_due(Instant::now());
+                        self.deliver_ticks(ticks);
+                    }
+                    Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+                },
+                None => return self.msg_queue.recv(),
+            }
+        }
+    }
+
     //----------------------------------------------------------------------------------------------
     fn read_last_trade_date(
         &self,
         contract: &mut ContractDetails,
         is_bond: bool,
         read_date: &str,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         if !read_date.is_empty() {
             let splitted = read_date.split_whitespace().collect::<Vec<&str>>();
             if !splitted.is_empty() {
@@ -2669,7 +3901,17 @@ where
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn run(&mut self) -> Result<(), IBKRApiLibError> {
+    /// Reports a message that was dropped, as it could not be read or decoded
+    fn decode_error(&mut self, err: &DecodeError) {
+        warn!("Dropping message: {}", err);
+        self.wrapper
+            .lock()
+            .expect(WRAPPER_POISONED_MUTEX)
+            .decode_error(err);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn run(&mut self) -> Result<(), IbkrError> {
         //This is the function that has the message loop.
         const CONN_STATE_POISONED: &str = "Connection state mutex was poisoned";
         info!("Starting run...");
@@ -2677,17 +3919,20 @@ where
         loop {
             // debug!("Client waiting for message...");
 
-            let text = self.msg_queue.recv();
+            let text = self.next_message();
             match text {
-                Result::Ok(val) => {
+                Result::Ok(Err(err)) => self.decode_error(&err),
+                Result::Ok(Ok(val)) => {
                     if val.len() > MAX_MSG_LEN as usize {
                         self.wrapper.lock().expect(WRAPPER_POISONED_MUTEX).error(
                             NO_VALID_ID,
                             TwsError::NotConnected.code(),
                             format!("{}:{}:{}", TwsError::NotConnected.message(), val.len(), val)
                                 .as_str(),
+                            "",
                         );
                         error!("Error receiving message.  Disconnected: Message too big");
+                        self.alert(|alerts| alerts.apply_connection_lost("Message too big"));
                         self.wrapper
                             .lock()
                             .expect(WRAPPER_POISONED_MUTEX)
@@ -2697,9 +3942,15 @@ where
                         error!("Error receiving message.  Invalid size.  Disconnected.");
                         return Ok(());
                     } else {
-                        let fields = read_fields((&val).as_ref());
-
-                        self.interpret(fields.as_slice())?;
+                        // The fields borrow from the message, none is copied
+                        let fields = split_fields(&val);
+
+                        // The message is dropped, the next one starts at its
+                        // own size prefix
+                        match self.dispatch(&fields) {
+                            Err(IbkrError::Decode(err)) => self.decode_error(&err),
+                            result => result?,
+                        }
                     }
                 }
                 Result::Err(err) => {
@@ -2707,6 +3958,9 @@ where
                         != ConnStatus::DISCONNECTED as i32
                     {
                         info!("Error receiving message.  Disconnected: {:?}", err);
+                        self.alert(|alerts| {
+                            alerts.apply_connection_lost("Socket closed or broken")
+                        });
                         self.wrapper
                             .lock()
                             .expect(WRAPPER_POISONED_MUTEX)
@@ -0,0 +1,81 @@
+//! Types for linking the API client to TWS window groups
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+//==================================================================================================
+/// The contract selected in a TWS display group, encoded by TWS as `none`,
+/// `combo` or `<con_id>@<exchange>`, e.g. `8314@SMART`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DisplayGroupContract {
+    /// Nothing is selected
+    None,
+    /// A single, non-combination contract
+    Contract { con_id: i32, exchange: String },
+    /// A combination contract is selected
+    Combo,
+}
+
+impl DisplayGroupContract {
+    pub fn new(con_id: i32, exchange: &str) -> Self {
+        DisplayGroupContract::Contract {
+            con_id,
+            exchange: exchange.to_string(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Parses the contract info of `Wrapper::display_group_updated`. Returns
+    /// `None` if the info can not be parsed.
+    pub fn parse(contract_info: &str) -> Option<Self> {
+        let contract_info = contract_info.trim();
+        match contract_info {
+            "" | "none" => Some(DisplayGroupContract::None),
+            "combo" => Some(DisplayGroupContract::Combo),
+            _ => {
+                let (con_id, exchange) = match contract_info.split_once('@') {
+                    Some((con_id, exchange)) => (con_id, exchange),
+                    None => (contract_info, ""),
+                };
+                con_id
+                    .parse()
+                    .ok()
+                    .map(|con_id| DisplayGroupContract::new(con_id, exchange))
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn con_id(&self) -> Option<i32> {
+        match self {
+            DisplayGroupContract::Contract { con_id, .. } => Some(*con_id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DisplayGroupContract {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayGroupContract::None => write!(f, "none"),
+            DisplayGroupContract::Contract { con_id, exchange } if exchange.is_empty() => {
+                write!(f, "{}", con_id)
+            }
+            DisplayGroupContract::Contract { con_id, exchange } => {
+                write!(f, "{}@{}", con_id, exchange)
+            }
+            DisplayGroupContract::Combo => write!(f, "combo"),
+        }
+    }
+}
+
+//==================================================================================================
+/// Parses the `|` separated group ids of `Wrapper::display_group_list`
+pub fn parse_display_groups(groups: &str) -> Vec<i32> {
+    groups
+        .split('|')
+        .filter_map(|group| group.trim().parse().ok())
+        .collect()
+}
@@ -0,0 +1,160 @@
+//! Catalog of the error codes TWS sends
+//!
+//! TWS reports errors, warnings and notifications through the `error`
+//! callback with a numeric code. The codes this crate knows are listed here
+//! as constants, together with their category and the message TWS sends
+//! with them, so code can match on `ORDER_REJECTED` instead of `201` and look
+//! up what an unfamiliar code means. The codes of errors raised by the client
+//! itself, 501 to 530, are those of `TwsError`.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+//==================================================================================================
+/// What an error code is about
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ErrorCategory {
+    /// The connection between TWS and the IB servers
+    Connectivity,
+    /// Too many messages or requests
+    Pacing,
+    Order,
+    MarketData,
+    HistoricalData,
+    Contract,
+    Account,
+    /// A request that could not be validated or processed
+    Request,
+    /// A warning or notification that does not end a request
+    Warning,
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+//==================================================================================================
+/// A known error code
+///
+/// name - the name of the constant of the code
+/// message - the message TWS sends with the code, or the start of it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorCodeInfo {
+    pub code: i32,
+    pub name: &'static str,
+    pub category: ErrorCategory,
+    pub message: &'static str,
+}
+
+impl fmt::Display for ErrorCodeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {}): {}",
+            self.code, self.name, self.category, self.message
+        )
+    }
+}
+
+// Declares a constant per code and the catalog of all of them
+macro_rules! error_codes {
+    ($($name:ident = $code:literal, $category:ident, $message:literal;)*) => {
+        $(
+            #[doc = $message]
+            pub const $name: i32 = $code;
+        )*
+
+        /// The known codes, in ascending order
+        pub const ERROR_CODES: &[ErrorCodeInfo] = &[
+            $(
+                ErrorCodeInfo {
+                    code: $code,
+                    name: stringify!($name),
+                    category: ErrorCategory::$category,
+                    message: $message,
+                },
+            )*
+        ];
+    };
+}
+
+error_codes! {
+    MAX_MESSAGE_RATE_EXCEEDED = 100, Pacing, "Max rate of messages per second has been exceeded.";
+    MAX_TICKERS_REACHED = 101, Pacing, "Max number of tickers has been reached.";
+    DUPLICATE_TICKER_ID = 102, Request, "Duplicate ticker ID.";
+    DUPLICATE_ORDER_ID = 103, Order, "Duplicate order ID.";
+    CANT_MODIFY_FILLED_ORDER = 104, Order, "Can't modify a filled order.";
+    ORDER_MODIFY_MISMATCH = 105, Order, "Order being modified does not match original order.";
+    CANT_TRANSMIT_ORDER = 106, Order, "Can't transmit order ID:";
+    CANT_TRANSMIT_INCOMPLETE_ORDER = 107, Order, "Cannot transmit incomplete order.";
+    PRICE_OUT_OF_RANGE = 109, Order, "Price is out of the range defined by the Percentage setting at order defaults frame.";
+    PRICE_INCREMENT_VIOLATION = 110, Order, "The price does not conform to the minimum price variation for this contract.";
+    ORDER_NOT_FOUND = 135, Order, "Can't find order with ID =";
+    ORDER_NOT_CANCELLABLE = 136, Order, "This order cannot be cancelled.";
+    CANCEL_NOT_IN_CANCELLABLE_STATE = 161, Order, "Cancel attempted when order is not in a cancellable state.";
+    HISTORICAL_DATA_ERROR = 162, HistoricalData, "Historical market data Service error message.";
+    HISTORICAL_DATA_QUERY_MESSAGE = 165, HistoricalData, "Historical market Data Service query message.";
+    HISTORICAL_DATA_EXPIRED_CONTRACT = 166, HistoricalData, "HMDS Expired Contract Violation.";
+    NO_SECURITY_DEFINITION = 200, Contract, "No security definition has been found for the request.";
+    ORDER_REJECTED = 201, Order, "Order rejected - Reason:";
+    ORDER_CANCELLED = 202, Order, "Order cancelled - Reason:";
+    SECURITY_NOT_ALLOWED = 203, Contract, "The security is not available or allowed for this account.";
+    TICKER_ID_NOT_FOUND = 300, Request, "Can't find EId with ticker Id:";
+    MAX_MARKET_DEPTH_REQUESTS = 309, MarketData, "Max number of market depth requests has been reached.";
+    MARKET_DEPTH_RESET = 317, MarketData, "Market depth data has been RESET. Please empty deep book contents before applying any new entries.";
+    VALIDATION_ERROR = 321, Request, "Error validating request.";
+    PROCESSING_ERROR = 322, Request, "Error processing request.";
+    MARKET_DATA_NOT_SUBSCRIBED = 354, MarketData, "Requested market data is not subscribed.";
+    NO_HISTORICAL_DATA_QUERY = 366, HistoricalData, "No historical data query found for ticker id:";
+    ORDER_MESSAGE = 399, Warning, "Order message error";
+    SHORT_SALE_NOT_AVAILABLE = 404, Order, "Shares for this order are not immediately available for short sale.";
+    INVALID_REAL_TIME_QUERY = 420, MarketData, "Invalid real-time query.";
+    CONNECTIVITY_LOST = 1100, Connectivity, "Connectivity between IB and the TWS has been lost.";
+    CONNECTIVITY_RESTORED_DATA_LOST = 1101, Connectivity, "Connectivity between IB and TWS has been restored - data lost.";
+    CONNECTIVITY_RESTORED = 1102, Connectivity, "Connectivity between IB and TWS has been restored - data maintained.";
+    SOCKET_PORT_RESET = 1300, Connectivity, "TWS socket port has been reset and this connection is being dropped.";
+    ACCOUNT_DATA_UNSUBSCRIBED = 2100, Warning, "New account data requested from TWS. API client has been unsubscribed from account data.";
+    ACCOUNT_SUBSCRIPTION_REFUSED = 2101, Warning, "Unable to subscribe to account as the following clients are subscribed to a different account.";
+    ORDER_STILL_PROCESSING = 2102, Warning, "Unable to modify this order as it is still being processed.";
+    MARKET_DATA_FARM_BROKEN = 2103, Warning, "Market data farm connection is broken:";
+    MARKET_DATA_FARM_OK = 2104, Warning, "Market data farm connection is OK:";
+    HISTORICAL_DATA_FARM_BROKEN = 2105, Warning, "HMDS data farm connection is broken:";
+    HISTORICAL_DATA_FARM_OK = 2106, Warning, "HMDS data farm connection is OK:";
+    HISTORICAL_DATA_FARM_INACTIVE = 2107, Warning, "HMDS data farm connection is inactive but should be available upon demand.";
+    MARKET_DATA_FARM_INACTIVE = 2108, Warning, "Market data farm connection is inactive but should be available upon demand.";
+    OUTSIDE_RTH_IGNORED = 2109, Warning, "Order Event Warning: Attribute 'Outside Regular Trading Hours' is ignored based on the order type and destination.";
+    TWS_CONNECTIVITY_BROKEN = 2110, Warning, "Connectivity between TWS and server is broken. It will be restored automatically.";
+    MARKET_DATA_FARM_CONNECTING = 2119, Warning, "Market data farm is connecting:";
+    CLOSING_QUANTITY_EXCEEDS_POSITION = 2137, Warning, "The closing order quantity is greater than your current position.";
+    SEC_DEF_FARM_BROKEN = 2157, Warning, "Sec-def data farm connection is broken:";
+    SEC_DEF_FARM_OK = 2158, Warning, "Sec-def data farm connection is OK:";
+    PART_OF_MARKET_DATA_NOT_SUBSCRIBED = 10090, MarketData, "Part of requested market data is not subscribed.";
+    ORDER_TO_CANCEL_NOT_FOUND = 10147, Order, "OrderId that needs to be cancelled is not found.";
+    ORDER_TO_CANCEL_NOT_CANCELLABLE = 10148, Order, "OrderId that needs to be cancelled cannot be cancelled, state:";
+    DELAYED_MARKET_DATA = 10167, MarketData, "Requested market data is not subscribed. Displaying delayed market data.";
+    DELAYED_MARKET_DATA_NOT_ENABLED = 10168, MarketData, "Requested market data is not subscribed. Delayed market data is not enabled.";
+    LIVE_UPDATES_FAILED = 10182, HistoricalData, "Failed to request live updates (disconnected).";
+    COMPETING_LIVE_SESSION = 10197, MarketData, "No market data during competing live session.";
+    BUST_EVENT = 10225, MarketData, "Bust event occurred, current subscription is deactivated.";
+    ETRADE_ONLY_NOT_SUPPORTED = 10268, Order, "The 'EtradeOnly' order attribute is not supported.";
+}
+
+//==================================================================================================
+/// The catalog entry of a code, if it is known
+pub fn lookup(code: i32) -> Option<&'static ErrorCodeInfo> {
+    ERROR_CODES
+        .binary_search_by_key(&code, |info| info.code)
+        .ok()
+        .map(|index| &ERROR_CODES[index])
+}
+
+//==================================================================================================
+/// Whether a code is a warning or notification, which does not end the
+/// request it is sent for
+pub fn is_warning(code: i32) -> bool {
+    (2100..2200).contains(&code) || code == ORDER_MESSAGE
+}
@@ -1,7 +1,13 @@
 //! Error types
+use std::io;
 use std::num::{ParseFloatError, ParseIntError};
 use std::sync::mpsc::{RecvError, RecvTimeoutError};
-use std::{error, fmt, io};
+
+use thiserror::Error;
+
+use crate::core::common::NO_VALID_ID;
+use crate::core::contract::Contract;
+use crate::core::error_codes::{self, ErrorCodeInfo};
 
 const ALREADY_CONNECTED: (i32, &str) = (501, "Already connected.");
 const CONNECT_FAIL: (i32, &str) = (502, "Couldn't connect to TWS. Confirm that \"Enable ActiveX and Socket EClients\"
@@ -19,7 +25,10 @@ const SOCKET_EXCEPTION: (i32, &str) = (509, "Exception caught while reading sock
 const FAIL_CREATE_SOCK: (i32, &str) = (520, "Failed to create socket.");
 const SSL_FAIL: (i32, &str) = (530, "SSL specific TwsError.");
 
-#[derive(Clone, Debug)]
+//==================================================================================================
+/// The errors raised by the client itself, with the codes of the TWS API
+#[derive(Error, Clone, Copy, Debug, PartialEq, Eq)]
+#[error("Code: {}, Message: {}", self.code(), self.message())]
 pub enum TwsError {
     AlreadyConnected,
     ConnectFail,
@@ -68,132 +77,296 @@ impl TwsError {
     }
 }
 
-impl error::Error for TwsError {}
+//==================================================================================================
+/// The errors of this crate
+#[derive(Error, Debug)]
+pub enum IbkrError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Parse error: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("Parse error: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("Receive error: {0}")]
+    RecvError(#[from] RecvError),
+    #[error("Receive error: {0}")]
+    RecvTimeoutError(#[from] RecvTimeoutError),
+    /// The connection is not in a state to send the request, e.g. it is
+    /// not connected or the server version is too old for it
+    #[error("Connection error: {0}")]
+    Connection(#[from] ConnectionError),
+    /// A message could not be encoded
+    #[error("Encode error: {0}")]
+    Encode(#[from] EncodeError),
+    /// A message received could not be decoded
+    #[error("Decode error: {0}")]
+    Decode(#[from] DecodeError),
+    /// The client refused to send a request, e.g. because an argument is
+    /// not valid
+    #[error("Request {request_id} rejected: {message}")]
+    Rejected { request_id: i32, message: String },
+    /// TWS answered a request with an error
+    #[error("TWS error: {0}")]
+    Server(#[from] ServerError),
+    /// TWS did not answer a request in time
+    #[error("Request {request_id} timed out waiting for {waiting_for}")]
+    Timeout {
+        request_id: i32,
+        waiting_for: String,
+    },
+}
 
-impl fmt::Display for TwsError {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "Code: {}, Message: {}", self.code(), self.message())
+/// The former name of `IbkrError`
+#[deprecated(note = "use IbkrError")]
+pub type IBKRApiLibError = IbkrError;
+
+impl IbkrError {
+    /// A request the client refused to send
+    pub fn rejected(request_id: i32, message: &str) -> Self {
+        IbkrError::Rejected {
+            request_id,
+            message: message.to_string(),
+        }
     }
-}
 
-// #[derive(Debug)]
-pub enum IBKRApiLibError {
-    Io(io::Error),
-    ParseFloat(ParseFloatError),
-    ParseInt(ParseIntError),
-    RecvError(RecvError),
-    RecvTimeoutError(RecvTimeoutError),
-    ApiError(TwsApiReportableError),
-}
+    //----------------------------------------------------------------------------------------------
+    /// A request that TWS did not answer in time
+    ///
+    /// # Arguments
+    /// * waiting_for - what was not received, e.g. "the positions"
+    pub fn timeout(request_id: i32, waiting_for: &str) -> Self {
+        IbkrError::Timeout {
+            request_id,
+            waiting_for: waiting_for.to_string(),
+        }
+    }
 
-impl fmt::Display for IBKRApiLibError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    //----------------------------------------------------------------------------------------------
+    /// The id of the request the error belongs to, if it belongs to one
+    pub fn request_id(&self) -> Option<i32> {
         match self {
-            // Both underlying errors already impl `Display`, so we defer to
-            // their implementations.
-            IBKRApiLibError::Io(ref err) => write!(f, "IO error: {}", err),
-            IBKRApiLibError::ParseFloat(ref err) => write!(f, "Parse error: {}", err),
-            IBKRApiLibError::ParseInt(ref err) => write!(f, "Parse error: {}", err),
-            IBKRApiLibError::RecvError(ref err) => write!(f, "Recieve error: {}", err),
-            IBKRApiLibError::RecvTimeoutError(ref err) => write!(f, "Reader Send error {}", err),
-            IBKRApiLibError::ApiError(ref err) => write!(f, "TWS Error: {}", err),
+            IbkrError::Connection(err) => Some(err.request_id),
+            IbkrError::Rejected { request_id, .. } | IbkrError::Timeout { request_id, .. } => {
+                Some(*request_id)
+            }
+            IbkrError::Server(err) => Some(err.request_id),
+            _ => None,
         }
+        .filter(|request_id| *request_id != NO_VALID_ID)
     }
-}
 
-impl fmt::Debug for IBKRApiLibError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    //----------------------------------------------------------------------------------------------
+    /// The TWS API error code: the code TWS sent, or the code of a
+    /// `TwsError`
+    pub fn code(&self) -> Option<i32> {
         match self {
-            // Both underlying errors already impl `Display`, so we defer to
-            // their implementations.
-            IBKRApiLibError::Io(ref err) => write!(f, "IO error: {}", err),
-            IBKRApiLibError::ParseFloat(ref err) => write!(f, "Parse error: {}", err),
-            IBKRApiLibError::ParseInt(ref err) => write!(f, "Parse error: {}", err),
-            IBKRApiLibError::RecvError(ref err) => write!(f, "Recieve error: {}", err),
-            IBKRApiLibError::RecvTimeoutError(ref err) => write!(f, "Reader Send error {}", err),
-            IBKRApiLibError::ApiError(ref err) => write!(f, "TWS Error: {}", err),
+            IbkrError::Connection(err) => Some(err.kind.code()),
+            IbkrError::Server(err) => Some(err.code),
+            _ => None,
         }
     }
 }
 
-impl error::Error for IBKRApiLibError {
-    fn cause(&self) -> Option<&dyn error::Error> {
-        match self {
-            // N.B. Both of these implicitly cast `err` from their concrete
-            // types (either `&io::Error` or `&num::ParseIntError`)
-            // to a trait object `&Error`. This works because both error types
-            // implement `Error`.
-            IBKRApiLibError::Io(ref err) => Some(err),
-            IBKRApiLibError::ParseFloat(ref err) => Some(err),
-            IBKRApiLibError::ParseInt(ref err) => Some(err),
-            IBKRApiLibError::RecvError(ref err) => Some(err),
-            IBKRApiLibError::RecvTimeoutError(ref err) => Some(err),
-            IBKRApiLibError::ApiError(ref err) => Some(err),
+//==================================================================================================
+/// A `TwsError` raised for a request
+///
+/// detail - what the error is about, e.g. the feature the server does not
+///   support, empty if the message of the kind says it all
+#[derive(Error, Clone, Debug, PartialEq)]
+#[error("{}", self.description())]
+pub struct ConnectionError {
+    pub request_id: i32,
+    pub kind: TwsError,
+    pub detail: String,
+}
+
+impl ConnectionError {
+    pub fn new(request_id: i32, kind: TwsError, detail: &str) -> Self {
+        ConnectionError {
+            request_id,
+            kind,
+            detail: detail.to_string(),
         }
     }
-}
 
-impl From<io::Error> for IBKRApiLibError {
-    fn from(err: io::Error) -> IBKRApiLibError {
-        IBKRApiLibError::Io(err)
+    //----------------------------------------------------------------------------------------------
+    /// The message of the kind followed by the detail, as reported to
+    /// `Wrapper::error`
+    pub fn description(&self) -> String {
+        if self.detail.is_empty() {
+            self.kind.message().to_string()
+        } else {
+            format!("{} {}", self.kind.message(), self.detail)
+        }
     }
 }
 
-impl From<ParseIntError> for IBKRApiLibError {
-    fn from(err: ParseIntError) -> IBKRApiLibError {
-        IBKRApiLibError::ParseInt(err)
-    }
+//==================================================================================================
+/// Why a message could not be encoded
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum EncodeError {
+    /// TWS only accepts ASCII text
+    #[error("not ASCII: {0:?}")]
+    NonAscii(String),
 }
 
-impl From<ParseFloatError> for IBKRApiLibError {
-    fn from(err: ParseFloatError) -> IBKRApiLibError {
-        IBKRApiLibError::ParseFloat(err)
-    }
+//==================================================================================================
+/// An error TWS sent for a request
+///
+/// request_id - the id of the request, order or ticker, NO_VALID_ID if the
+///   error does not belong to one
+/// code - one of the codes in `error_codes`, or a code this crate does not
+///   know yet
+#[derive(Error, Clone, Debug, PartialEq)]
+#[error("request_id = {request_id}, code = {code}, message = {message}")]
+pub struct ServerError {
+    pub request_id: i32,
+    pub code: i32,
+    pub message: String,
 }
 
-impl From<RecvError> for IBKRApiLibError {
-    fn from(err: RecvError) -> IBKRApiLibError {
-        IBKRApiLibError::RecvError(err)
+impl ServerError {
+    pub fn new(request_id: i32, code: i32, message: &str) -> Self {
+        ServerError {
+            request_id,
+            code,
+            message: message.to_string(),
+        }
     }
-}
 
-impl From<RecvTimeoutError> for IBKRApiLibError {
-    fn from(err: RecvTimeoutError) -> IBKRApiLibError {
-        IBKRApiLibError::RecvTimeoutError(err)
+    //----------------------------------------------------------------------------------------------
+    /// The catalog entry of the code, if it is known
+    pub fn info(&self) -> Option<&'static ErrorCodeInfo> {
+        error_codes::lookup(self.code)
     }
-}
 
-impl From<TwsApiReportableError> for IBKRApiLibError {
-    fn from(err: TwsApiReportableError) -> IBKRApiLibError {
-        IBKRApiLibError::ApiError(err)
+    //----------------------------------------------------------------------------------------------
+    pub fn is_warning(&self) -> bool {
+        error_codes::is_warning(self.code)
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct TwsApiReportableError {
-    pub request_id: i32,
-    pub code: String,
-    pub description: String,
+//==================================================================================================
+/// Why a message could not be decoded
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum DecodeErrorReason {
+    /// The message ends before the field, e.g. because it was truncated
+    #[error("missing field")]
+    MissingField,
+    /// The field is not a number
+    #[error("invalid number '{0}'")]
+    InvalidNumber(String),
+    /// The field holds a value this crate does not know, e.g. a tick type
+    /// added by a newer TWS
+    #[error("unknown value '{0}'")]
+    UnknownValue(String),
+    /// The message id is not known
+    #[error("unknown message id")]
+    UnknownMessageId,
+    /// The field holds bytes that are not UTF-8, see `InvalidUtf8Policy`
+    #[error("invalid UTF-8")]
+    InvalidUtf8,
 }
 
-impl TwsApiReportableError {
-    pub fn new(request_id: i32, code: String, description: String) -> Self {
-        Self {
-            request_id,
-            code,
-            description,
+//==================================================================================================
+/// A message that could not be decoded. The message is dropped, the
+/// messages after it are decoded as usual.
+///
+/// msg_type - the message id, NO_VALID_ID if it is not a number
+/// field - the index of the field in the message, the message id being 0
+/// raw_frame - the fields of the message, each terminated by NULL
+#[derive(Error, Clone, Debug, PartialEq)]
+#[error("message {msg_type}, field {field}: {reason}. Frame: {raw_frame:?}")]
+pub struct DecodeError {
+    pub msg_type: i32,
+    pub field: usize,
+    pub reason: DecodeErrorReason,
+    pub raw_frame: String,
+    // The number of fields from the field on, until the message is known
+    remaining: usize,
+}
+
+impl DecodeError {
+    pub fn new(msg_type: i32, field: usize, reason: DecodeErrorReason, raw_frame: &str) -> Self {
+        DecodeError {
+            msg_type,
+            field,
+            reason,
+            raw_frame: raw_frame.to_string(),
+            remaining: 0,
         }
     }
-}
 
-impl fmt::Display for TwsApiReportableError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "TWS Error: request_id = {}. code = {}. description = {}",
-            self.request_id, self.code, self.description
-        )
+    //----------------------------------------------------------------------------------------------
+    /// An error at a field of a message that is being decoded, counting the
+    /// fields from the end as the message is not known yet. `remaining` is
+    /// the number of fields from the field on, 0 if it is missing.
+    pub(crate) fn at(remaining: usize, reason: DecodeErrorReason) -> Self {
+        DecodeError {
+            msg_type: NO_VALID_ID,
+            field: 0,
+            reason,
+            raw_frame: "".to_string(),
+            remaining,
+        }
     }
+
+    //----------------------------------------------------------------------------------------------
+    /// Fills in the message of an error created with at()
+    pub(crate) fn locate(mut self, msg_type: i32, fields: &[&str]) -> Self {
+        self.msg_type = msg_type;
+        self.field = fields.len().saturating_sub(self.remaining);
+        self.raw_frame = fields.iter().fold(String::new(), |mut frame, field| {
+            frame.push_str(field);
+            frame.push('\0');
+            frame
+        });
+        self.remaining = 0;
+        self
+    }
+}
+
+//==================================================================================================
+/// The reasons `EClient::resolve_contract` can fail
+#[derive(Error, Debug)]
+pub enum ResolveContractError {
+    /// No contract matches the query
+    #[error(
+        "No contract matches {} {} {} {}",
+        .0.symbol,
+        .0.sec_type,
+        .0.exchange,
+        .0.currency
+    )]
+    NotFound(Box<Contract>),
+    /// More than one contract matches the query. Set more fields of the
+    /// query, e.g. `primary_exchange`, `currency` or `trading_class`, to pick
+    /// one of the candidates.
+    #[error("{} contracts match: {}", .0.len(), describe_candidates(.0))]
+    Ambiguous(Vec<Contract>),
+    /// The security id of the query is not valid for its type
+    #[error("Invalid security id: {0}")]
+    InvalidSecId(String),
+    /// The request failed
+    #[error(transparent)]
+    Api(#[from] IbkrError),
 }
 
-impl error::Error for TwsApiReportableError {}
+//==================================================================================================
+fn describe_candidates(candidates: &[Contract]) -> String {
+    candidates
+        .iter()
+        .map(|contract| {
+            format!(
+                "{} {} {} {} {} (con_id {})",
+                contract.local_symbol,
+                contract.sec_type,
+                contract.primary_exchange,
+                contract.currency,
+                contract.trading_class,
+                contract.con_id
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
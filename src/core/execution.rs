@@ -2,10 +2,20 @@
 //! Types related to executions
 use std::fmt::{Display, Error, Formatter};
 
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+use crate::core::common::Action;
+use crate::core::contract::SecType;
+
+/// The format of `ExecutionFilter::time`, in UTC
+pub const EXECUTION_FILTER_TIME_FORMAT: &str = "%Y%m%d-%H:%M:%S";
+
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Execution {
     pub exec_id: String,
     pub time: String,
@@ -116,13 +126,32 @@ impl Display for Execution {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+/// Parses the time of an execution, e.g. `20230103  09:30:00` or
+/// `20230103 09:30:00 US/Eastern`. Times without a time zone are in
+/// `default_time_zone`, which should be the time zone set in TWS.
+pub fn parse_execution_time(time: &str, default_time_zone: Tz) -> Option<DateTime<Utc>> {
+    let mut parts = time.split_whitespace();
+    let date_time = format!("{} {}", parts.next()?, parts.next()?);
+    let time_zone = match parts.next() {
+        Some(time_zone) => time_zone.parse::<Tz>().ok()?,
+        None => default_time_zone,
+    };
+    let date_time = NaiveDateTime::parse_from_str(&date_time, "%Y%m%d %H:%M:%S").ok()?;
+    time_zone
+        .from_local_datetime(&date_time)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+//==================================================================================================
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ExecutionFilter {
     pub client_id: i32,
     pub acct_code: String,
     pub time: String,
     pub symbol: String,
-    pub sec_type: String,
+    pub sec_type: SecType,
     pub exchange: String,
     pub side: String,
 }
@@ -133,7 +162,7 @@ impl ExecutionFilter {
         acct_code: String,
         time: String,
         symbol: String,
-        sec_type: String,
+        sec_type: SecType,
         exchange: String,
         side: String,
     ) -> Self {
@@ -147,4 +176,106 @@ impl ExecutionFilter {
             side,
         }
     }
+    //----------------------------------------------------------------------------------------------
+    /// Starts building a filter that matches all executions
+    pub fn builder() -> ExecutionFilterBuilder {
+        ExecutionFilterBuilder::new()
+    }
+}
+
+//==================================================================================================
+/// Builds an `ExecutionFilter` from typed fields. Unset fields match all
+/// executions.
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionFilterBuilder {
+    filter: ExecutionFilter,
+}
+
+impl ExecutionFilterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only the executions of orders placed by a client
+    pub fn client_id(mut self, client_id: i32) -> Self {
+        self.filter.client_id = client_id;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn account(mut self, account: &str) -> Self {
+        self.filter.acct_code = account.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn symbol(mut self, symbol: &str) -> Self {
+        self.filter.symbol = symbol.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn sec_type(mut self, sec_type: SecType) -> Self {
+        self.filter.sec_type = sec_type;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.filter.exchange = exchange.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only buys or only sells. Short sales are sells.
+    pub fn side(mut self, side: Action) -> Self {
+        self.filter.side = match side {
+            Action::Buy => "BUY",
+            Action::Sell | Action::Sshort => "SELL",
+        }
+        .to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only the executions after `time`, sent in UTC
+    pub fn since<Z: TimeZone>(mut self, time: &DateTime<Z>) -> Self {
+        self.filter.time = time
+            .with_timezone(&Utc)
+            .format(EXECUTION_FILTER_TIME_FORMAT)
+            .to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only the executions since the start of a day in a time zone, e.g.
+    /// the time zone of the exchange
+    pub fn since_date(self, date: NaiveDate, time_zone: &Tz) -> Self {
+        match time_zone
+            .from_local_datetime(&date.and_time(Default::default()))
+            .earliest()
+        {
+            Some(midnight) => self.since(&midnight),
+            None => self,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only the executions since the start of today in the local time zone
+    pub fn today(mut self) -> Self {
+        let today = Local::now().date_naive();
+        if let Some(midnight) = Local
+            .from_local_datetime(&today.and_time(Default::default()))
+            .earliest()
+        {
+            self = self.since(&midnight);
+        }
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn build(self) -> ExecutionFilter {
+        self.filter
+    }
 }
@@ -0,0 +1,461 @@
+//! Execution quality of fills, for transaction cost analysis
+//!
+//! `ArrivalQuotes` set with `EClient::set_arrival_quotes` keeps the quote of
+//! the client's `QuoteCache` for the contract of every order when the order
+//! is sent, so subscribe to the market data of the contracts with their
+//! `con_id` before placing orders. `ExecutionQuality` takes these arrival
+//! quotes and the executions of the orders and computes per fill, per order
+//! and per trading day:
+//!
+//! * the slippage against the arrival mid
+//! * the slippage against the VWAP of the market over the life of the order,
+//!   if it was given with `set_vwap`, e.g. from `bars_vwap`
+//! * the effective spread: twice the distance of the fill from the arrival
+//!   mid
+//!
+//! Slippage is a cost: positive if a buy filled above or a sell below the
+//! benchmark. Amounts are per share and in basis points of the benchmark;
+//! multipliers are not applied.
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::BarData;
+use crate::core::execution::Execution;
+use crate::core::quote_cache::Quote;
+
+const BASIS_POINTS: f64 = 10_000.0;
+
+//==================================================================================================
+/// The quote of the contract of an order when it was sent
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ArrivalQuote {
+    pub order_id: i32,
+    pub con_id: i32,
+    pub time: DateTime<Utc>,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+}
+
+impl ArrivalQuote {
+    pub fn new(order_id: i32, con_id: i32, time: DateTime<Utc>, quote: &Quote) -> Self {
+        ArrivalQuote {
+            order_id,
+            con_id,
+            time,
+            bid: quote.bid,
+            ask: quote.ask,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The midpoint of bid and ask, if both are known
+    pub fn mid(&self) -> Option<f64> {
+        self.bid.zip(self.ask).map(|(bid, ask)| (bid + ask) / 2.0)
+    }
+}
+
+impl fmt::Display for ArrivalQuote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, con_id: {}, time: {}, bid: {:?}, ask: {:?}",
+            self.order_id,
+            self.con_id,
+            self.time.to_rfc3339(),
+            self.bid,
+            self.ask
+        )
+    }
+}
+
+//==================================================================================================
+/// The arrival quotes of orders by order id. Modifying an order keeps the
+/// quote of when it was first sent.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ArrivalQuotes {
+    quotes: BTreeMap<i32, ArrivalQuote>,
+}
+
+impl ArrivalQuotes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records the arrival quote of an order, unless it has one already
+    pub fn record(&mut self, arrival: ArrivalQuote) {
+        self.quotes.entry(arrival.order_id).or_insert(arrival);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn get(&self, order_id: i32) -> Option<&ArrivalQuote> {
+        self.quotes.get(&order_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn iter(&self) -> impl Iterator<Item = &ArrivalQuote> {
+        self.quotes.values()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        self.quotes.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.quotes.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear(&mut self) {
+        self.quotes.clear();
+    }
+}
+
+//==================================================================================================
+/// The volume weighted average price of bars, from their average prices.
+/// None if the bars have no volume.
+pub fn bars_vwap(bars: &[BarData]) -> Option<f64> {
+    let volume: f64 = bars.iter().map(|bar| bar.volume).sum();
+    (volume > 0.0).then(|| bars.iter().map(|bar| bar.average * bar.volume).sum::<f64>() / volume)
+}
+
+//==================================================================================================
+/// The cost of trading at `price` against `benchmark`, per share
+fn slippage(buy: bool, price: f64, benchmark: f64) -> f64 {
+    if buy {
+        price - benchmark
+    } else {
+        benchmark - price
+    }
+}
+
+//==================================================================================================
+fn basis_points(amount: f64, benchmark: f64) -> Option<f64> {
+    (benchmark != 0.0).then(|| amount / benchmark * BASIS_POINTS)
+}
+
+//==================================================================================================
+/// The quality of one fill
+///
+/// date - the trading day, from the time of the execution
+/// arrival_slippage, vwap_slippage - per share; None without a benchmark
+/// effective_spread - twice the distance of the price from the arrival mid,
+///   per share
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FillQuality {
+    pub order_id: i32,
+    pub exec_id: String,
+    pub date: Option<NaiveDate>,
+    pub buy: bool,
+    pub shares: f64,
+    pub price: f64,
+    pub arrival_mid: Option<f64>,
+    pub arrival_slippage: Option<f64>,
+    pub arrival_slippage_bps: Option<f64>,
+    pub vwap: Option<f64>,
+    pub vwap_slippage: Option<f64>,
+    pub vwap_slippage_bps: Option<f64>,
+    pub effective_spread: Option<f64>,
+}
+
+impl fmt::Display for FillQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, exec_id: {}, {} {} at {}, arrival_slippage_bps: {:?}, \
+             vwap_slippage_bps: {:?}, effective_spread: {:?}",
+            self.order_id,
+            self.exec_id,
+            if self.buy { "BOT" } else { "SLD" },
+            self.shares,
+            self.price,
+            self.arrival_slippage_bps,
+            self.vwap_slippage_bps,
+            self.effective_spread
+        )
+    }
+}
+
+//==================================================================================================
+/// The quality of the fills of one order, weighted by their shares
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrderQuality {
+    pub order_id: i32,
+    pub buy: bool,
+    pub fills: usize,
+    pub shares: f64,
+    pub avg_price: f64,
+    pub arrival_mid: Option<f64>,
+    pub arrival_slippage: Option<f64>,
+    pub arrival_slippage_bps: Option<f64>,
+    pub vwap: Option<f64>,
+    pub vwap_slippage: Option<f64>,
+    pub vwap_slippage_bps: Option<f64>,
+    pub effective_spread: Option<f64>,
+}
+
+impl fmt::Display for OrderQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, {} {} at {} in {} fills, arrival_slippage_bps: {:?}, \
+             vwap_slippage_bps: {:?}, effective_spread: {:?}",
+            self.order_id,
+            if self.buy { "BOT" } else { "SLD" },
+            self.shares,
+            self.avg_price,
+            self.fills,
+            self.arrival_slippage_bps,
+            self.vwap_slippage_bps,
+            self.effective_spread
+        )
+    }
+}
+
+//==================================================================================================
+/// The quality of the fills of one trading day
+///
+/// notional - the traded value of all fills
+/// arrival_cost, vwap_cost - the slippage times the shares of the fills with
+///   a benchmark
+/// arrival_slippage_bps, vwap_slippage_bps, effective_spread_bps - weighted
+///   by the notional of the fills with a benchmark
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DailyQuality {
+    pub date: NaiveDate,
+    pub orders: usize,
+    pub fills: usize,
+    pub shares: f64,
+    pub notional: f64,
+    pub arrival_cost: f64,
+    pub arrival_slippage_bps: Option<f64>,
+    pub vwap_cost: f64,
+    pub vwap_slippage_bps: Option<f64>,
+    pub effective_spread_bps: Option<f64>,
+}
+
+impl fmt::Display for DailyQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "date: {}, orders: {}, fills: {}, shares: {}, notional: {}, arrival_cost: {}, \
+             arrival_slippage_bps: {:?}, vwap_cost: {}, vwap_slippage_bps: {:?}, \
+             effective_spread_bps: {:?}",
+            self.date,
+            self.orders,
+            self.fills,
+            self.shares,
+            self.notional,
+            self.arrival_cost,
+            self.arrival_slippage_bps,
+            self.vwap_cost,
+            self.vwap_slippage_bps,
+            self.effective_spread_bps
+        )
+    }
+}
+
+//==================================================================================================
+/// A weighted average that is None without weight
+#[derive(Default)]
+struct WeightedMean {
+    sum: f64,
+    weight: f64,
+}
+
+impl WeightedMean {
+    fn add(&mut self, value: Option<f64>, weight: f64) {
+        if let Some(value) = value {
+            self.sum += value * weight;
+            self.weight += weight;
+        }
+    }
+
+    fn mean(&self) -> Option<f64> {
+        (self.weight > 0.0).then(|| self.sum / self.weight)
+    }
+}
+
+//==================================================================================================
+/// Computes the execution quality of fills from their arrival quotes
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionQuality {
+    arrivals: ArrivalQuotes,
+    vwaps: BTreeMap<i32, f64>,
+    executions: Vec<Execution>,
+    exec_ids: HashSet<String>,
+}
+
+impl ExecutionQuality {
+    pub fn new(arrivals: ArrivalQuotes) -> Self {
+        ExecutionQuality {
+            arrivals,
+            ..Self::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the VWAP of the market over the life of an order
+    pub fn set_vwap(&mut self, order_id: i32, vwap: f64) {
+        self.vwaps.insert(order_id, vwap);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a fill. Returns false if the execution was added before.
+    pub fn add_execution(&mut self, execution: &Execution) -> bool {
+        if !self.exec_ids.insert(execution.exec_id.clone()) {
+            return false;
+        }
+        self.executions.push(execution.clone());
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The quality of the fills, in the order they were added
+    pub fn fills(&self) -> Vec<FillQuality> {
+        self.executions
+            .iter()
+            .map(|execution| {
+                let buy = execution.side != "SLD";
+                let arrival_mid = self
+                    .arrivals
+                    .get(execution.order_id)
+                    .and_then(ArrivalQuote::mid);
+                let vwap = self.vwaps.get(&execution.order_id).copied();
+                let arrival_slippage = arrival_mid.map(|mid| slippage(buy, execution.price, mid));
+                let vwap_slippage = vwap.map(|vwap| slippage(buy, execution.price, vwap));
+                FillQuality {
+                    order_id: execution.order_id,
+                    exec_id: execution.exec_id.clone(),
+                    date: execution
+                        .time
+                        .split_whitespace()
+                        .next()
+                        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok()),
+                    buy,
+                    shares: execution.shares,
+                    price: execution.price,
+                    arrival_mid,
+                    arrival_slippage,
+                    arrival_slippage_bps: arrival_slippage
+                        .zip(arrival_mid)
+                        .and_then(|(slippage, mid)| basis_points(slippage, mid)),
+                    vwap,
+                    vwap_slippage,
+                    vwap_slippage_bps: vwap_slippage
+                        .zip(vwap)
+                        .and_then(|(slippage, vwap)| basis_points(slippage, vwap)),
+                    effective_spread: arrival_mid.map(|mid| 2.0 * (execution.price - mid).abs()),
+                }
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The quality of the orders, by order id
+    pub fn orders(&self) -> Vec<OrderQuality> {
+        let mut by_order: BTreeMap<i32, Vec<FillQuality>> = BTreeMap::new();
+        for fill in self.fills() {
+            by_order.entry(fill.order_id).or_default().push(fill);
+        }
+        by_order
+            .into_iter()
+            .map(|(order_id, fills)| {
+                let shares: f64 = fills.iter().map(|fill| fill.shares).sum();
+                let mut price = WeightedMean::default();
+                let mut arrival_slippage = WeightedMean::default();
+                let mut vwap_slippage = WeightedMean::default();
+                let mut effective_spread = WeightedMean::default();
+                for fill in &fills {
+                    price.add(Some(fill.price), fill.shares);
+                    arrival_slippage.add(fill.arrival_slippage, fill.shares);
+                    vwap_slippage.add(fill.vwap_slippage, fill.shares);
+                    effective_spread.add(fill.effective_spread, fill.shares);
+                }
+                let arrival_mid = fills[0].arrival_mid;
+                let vwap = fills[0].vwap;
+                let arrival_slippage = arrival_slippage.mean();
+                let vwap_slippage = vwap_slippage.mean();
+                OrderQuality {
+                    order_id,
+                    buy: fills[0].buy,
+                    fills: fills.len(),
+                    shares,
+                    avg_price: price.mean().unwrap_or_default(),
+                    arrival_mid,
+                    arrival_slippage,
+                    arrival_slippage_bps: arrival_slippage
+                        .zip(arrival_mid)
+                        .and_then(|(slippage, mid)| basis_points(slippage, mid)),
+                    vwap,
+                    vwap_slippage,
+                    vwap_slippage_bps: vwap_slippage
+                        .zip(vwap)
+                        .and_then(|(slippage, vwap)| basis_points(slippage, vwap)),
+                    effective_spread: effective_spread.mean(),
+                }
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The quality of the trading days, by date. Fills without a date are
+    /// left out.
+    pub fn daily(&self) -> Vec<DailyQuality> {
+        let mut by_date: BTreeMap<NaiveDate, Vec<FillQuality>> = BTreeMap::new();
+        for fill in self.fills() {
+            if let Some(date) = fill.date {
+                by_date.entry(date).or_default().push(fill);
+            }
+        }
+        by_date
+            .into_iter()
+            .map(|(date, fills)| {
+                let mut arrival_slippage_bps = WeightedMean::default();
+                let mut vwap_slippage_bps = WeightedMean::default();
+                let mut effective_spread_bps = WeightedMean::default();
+                for fill in &fills {
+                    let notional = fill.shares * fill.price;
+                    arrival_slippage_bps.add(fill.arrival_slippage_bps, notional);
+                    vwap_slippage_bps.add(fill.vwap_slippage_bps, notional);
+                    effective_spread_bps.add(
+                        fill.effective_spread
+                            .zip(fill.arrival_mid)
+                            .and_then(|(spread, mid)| basis_points(spread, mid)),
+                        notional,
+                    );
+                }
+                let orders: HashSet<i32> = fills.iter().map(|fill| fill.order_id).collect();
+                let cost = |slippage: fn(&FillQuality) -> Option<f64>| -> f64 {
+                    fills
+                        .iter()
+                        .filter_map(|fill| slippage(fill).map(|slippage| slippage * fill.shares))
+                        .sum()
+                };
+                DailyQuality {
+                    date,
+                    orders: orders.len(),
+                    fills: fills.len(),
+                    shares: fills.iter().map(|fill| fill.shares).sum(),
+                    notional: fills.iter().map(|fill| fill.shares * fill.price).sum(),
+                    arrival_cost: cost(|fill| fill.arrival_slippage),
+                    arrival_slippage_bps: arrival_slippage_bps.mean(),
+                    vwap_cost: cost(|fill| fill.vwap_slippage),
+                    vwap_slippage_bps: vwap_slippage_bps.mean(),
+                    effective_spread_bps: effective_spread_bps.mean(),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,565 @@
+//! Typed view of the financial advisor configuration. Requires the
+//! `fa-config` feature.
+//!
+//! TWS exchanges the allocation groups, allocation profiles and account
+//! aliases of an FA login as XML through `request_fa`/`replace_fa`. The
+//! functions here parse that XML into `FaGroup`, `FaProfile` and `FaAlias`,
+//! serialize them back and change the configuration of TWS with a fetch,
+//! modify and replace.
+use std::error;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use roxmltree::{Document, Node};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::{FaDataType, NO_VALID_ID};
+use crate::core::errors::IbkrError;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// Why an FA configuration could not be read or changed
+#[derive(Debug)]
+pub enum FaConfigError {
+    /// The XML of TWS could not be parsed
+    Xml(roxmltree::Error),
+    /// There is no group with this name
+    UnknownGroup(String),
+    /// There is no profile with this name
+    UnknownProfile(String),
+    /// The request failed
+    Api(IbkrError),
+}
+
+impl fmt::Display for FaConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaConfigError::Xml(err) => write!(f, "Invalid FA configuration: {}", err),
+            FaConfigError::UnknownGroup(name) => write!(f, "Unknown FA group: {}", name),
+            FaConfigError::UnknownProfile(name) => write!(f, "Unknown FA profile: {}", name),
+            FaConfigError::Api(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for FaConfigError {
+    fn cause(&self) -> Option<&dyn error::Error> {
+        match self {
+            FaConfigError::Xml(err) => Some(err),
+            FaConfigError::Api(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<roxmltree::Error> for FaConfigError {
+    fn from(err: roxmltree::Error) -> FaConfigError {
+        FaConfigError::Xml(err)
+    }
+}
+
+impl From<IbkrError> for FaConfigError {
+    fn from(err: IbkrError) -> FaConfigError {
+        FaConfigError::Api(err)
+    }
+}
+
+//==================================================================================================
+/// An allocation group: orders for the group are allocated to its accounts
+/// with the default method, e.g. `EqualQuantity`, `NetLiq`,
+/// `AvailableEquity` or `PctChange`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FaGroup {
+    pub name: String,
+    pub accounts: Vec<String>,
+    pub default_method: String,
+}
+
+impl FaGroup {
+    pub fn new(name: &str, accounts: Vec<String>, default_method: &str) -> Self {
+        FaGroup {
+            name: name.to_string(),
+            accounts,
+            default_method: default_method.to_string(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds an account to the group. Returns whether it was not in the group
+    /// yet.
+    pub fn add_account(&mut self, account: &str) -> bool {
+        if self.accounts.iter().any(|a| a == account) {
+            return false;
+        }
+        self.accounts.push(account.to_string());
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Removes an account from the group. Returns whether it was in the
+    /// group.
+    pub fn remove_account(&mut self, account: &str) -> bool {
+        let len = self.accounts.len();
+        self.accounts.retain(|a| a != account);
+        self.accounts.len() != len
+    }
+}
+
+impl fmt::Display for FaGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "name: {}, accounts: {}, default_method: {}",
+            self.name,
+            self.accounts.join(","),
+            self.default_method
+        )
+    }
+}
+
+//==================================================================================================
+/// How the amounts of the allocations of a profile are interpreted
+#[repr(i32)]
+#[derive(Clone, Copy, FromPrimitive, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FaProfileType {
+    #[default]
+    Percentages = 1,
+    Ratios = 2,
+    Shares = 3,
+}
+
+impl fmt::Display for FaProfileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaProfileType::Percentages => write!(f, "Percentages"),
+            FaProfileType::Ratios => write!(f, "Ratios"),
+            FaProfileType::Shares => write!(f, "Shares"),
+        }
+    }
+}
+
+//==================================================================================================
+/// The share of an account in the orders of a profile
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FaAllocation {
+    pub account: String,
+    pub amount: f64,
+}
+
+impl FaAllocation {
+    pub fn new(account: &str, amount: f64) -> Self {
+        FaAllocation {
+            account: account.to_string(),
+            amount,
+        }
+    }
+}
+
+//==================================================================================================
+/// An allocation profile: orders for the profile are allocated to its
+/// accounts by fixed amounts
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FaProfile {
+    pub name: String,
+    pub profile_type: FaProfileType,
+    pub allocations: Vec<FaAllocation>,
+}
+
+impl FaProfile {
+    pub fn new(name: &str, profile_type: FaProfileType, allocations: Vec<FaAllocation>) -> Self {
+        FaProfile {
+            name: name.to_string(),
+            profile_type,
+            allocations,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the amount allocated to an account, adding the account if it is
+    /// not in the profile yet
+    pub fn set_allocation(&mut self, account: &str, amount: f64) {
+        match self.allocations.iter_mut().find(|a| a.account == account) {
+            Some(allocation) => allocation.amount = amount,
+            None => self.allocations.push(FaAllocation::new(account, amount)),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Removes an account from the profile. Returns whether it was in the
+    /// profile.
+    pub fn remove_allocation(&mut self, account: &str) -> bool {
+        let len = self.allocations.len();
+        self.allocations.retain(|a| a.account != account);
+        self.allocations.len() != len
+    }
+}
+
+impl fmt::Display for FaProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let allocations: Vec<String> = self
+            .allocations
+            .iter()
+            .map(|allocation| format!("{}:{}", allocation.account, allocation.amount))
+            .collect();
+        write!(
+            f,
+            "name: {}, profile_type: {}, allocations: {}",
+            self.name,
+            self.profile_type,
+            allocations.join(",")
+        )
+    }
+}
+
+//==================================================================================================
+/// The alias TWS shows for an account
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FaAlias {
+    pub account: String,
+    pub alias: String,
+}
+
+impl FaAlias {
+    pub fn new(account: &str, alias: &str) -> Self {
+        FaAlias {
+            account: account.to_string(),
+            alias: alias.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FaAlias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "account: {}, alias: {}", self.account, self.alias)
+    }
+}
+
+//==================================================================================================
+/// Parses the `GROUPS` XML
+pub fn parse_groups(xml: &str) -> Result<Vec<FaGroup>, roxmltree::Error> {
+    let document = Document::parse(xml)?;
+    Ok(document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("Group"))
+        .map(|node| FaGroup {
+            name: child_text(&node, "name"),
+            accounts: node
+                .children()
+                .filter(|list| list.has_tag_name("ListOfAccts"))
+                .flat_map(|list| list.children().filter(|a| a.has_tag_name("String")))
+                .map(|account| account.text().unwrap_or("").trim().to_string())
+                .collect(),
+            default_method: child_text(&node, "defaultMethod"),
+        })
+        .collect())
+}
+
+//==================================================================================================
+/// Parses the `PROFILES` XML. Unknown profile types are read as
+/// percentages.
+pub fn parse_profiles(xml: &str) -> Result<Vec<FaProfile>, roxmltree::Error> {
+    let document = Document::parse(xml)?;
+    Ok(document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("AllocationProfile"))
+        .map(|node| FaProfile {
+            name: child_text(&node, "name"),
+            profile_type: child_text(&node, "type")
+                .parse()
+                .ok()
+                .and_then(FaProfileType::from_i32)
+                .unwrap_or_default(),
+            allocations: node
+                .children()
+                .filter(|list| list.has_tag_name("ListOfAllocations"))
+                .flat_map(|list| list.children().filter(|a| a.has_tag_name("Allocation")))
+                .map(|allocation| FaAllocation {
+                    account: child_text(&allocation, "acct"),
+                    amount: child_text(&allocation, "amount").parse().unwrap_or(0.0),
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+//==================================================================================================
+/// Parses the `ALIASES` XML
+pub fn parse_aliases(xml: &str) -> Result<Vec<FaAlias>, roxmltree::Error> {
+    let document = Document::parse(xml)?;
+    Ok(document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("AccountAlias"))
+        .map(|node| FaAlias {
+            account: child_text(&node, "account"),
+            alias: child_text(&node, "alias"),
+        })
+        .collect())
+}
+
+//==================================================================================================
+/// Serializes groups into the XML `replace_fa` expects for `GROUPS`
+pub fn groups_to_xml(groups: &[FaGroup]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ListOfGroups>\n");
+    for group in groups {
+        xml.push_str("  <Group>\n");
+        xml.push_str(&format!("    <name>{}</name>\n", escape(&group.name)));
+        xml.push_str("    <ListOfAccts varName=\"list\">\n");
+        for account in &group.accounts {
+            xml.push_str(&format!("      <String>{}</String>\n", escape(account)));
+        }
+        xml.push_str("    </ListOfAccts>\n");
+        xml.push_str(&format!(
+            "    <defaultMethod>{}</defaultMethod>\n",
+            escape(&group.default_method)
+        ));
+        xml.push_str("  </Group>\n");
+    }
+    xml.push_str("</ListOfGroups>\n");
+    xml
+}
+
+//==================================================================================================
+/// Serializes profiles into the XML `replace_fa` expects for `PROFILES`
+pub fn profiles_to_xml(profiles: &[FaProfile]) -> String {
+    let mut xml =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ListOfAllocationProfiles>\n");
+    for profile in profiles {
+        xml.push_str("  <AllocationProfile>\n");
+        xml.push_str(&format!("    <name>{}</name>\n", escape(&profile.name)));
+        xml.push_str(&format!(
+            "    <type>{}</type>\n",
+            profile.profile_type as i32
+        ));
+        xml.push_str("    <ListOfAllocations varName=\"listOfAllocations\">\n");
+        for allocation in &profile.allocations {
+            xml.push_str("      <Allocation>\n");
+            xml.push_str(&format!(
+                "        <acct>{}</acct>\n",
+                escape(&allocation.account)
+            ));
+            xml.push_str(&format!("        <amount>{}</amount>\n", allocation.amount));
+            xml.push_str("      </Allocation>\n");
+        }
+        xml.push_str("    </ListOfAllocations>\n");
+        xml.push_str("  </AllocationProfile>\n");
+    }
+    xml.push_str("</ListOfAllocationProfiles>\n");
+    xml
+}
+
+//==================================================================================================
+/// Serializes aliases into the XML `replace_fa` expects for `ALIASES`
+pub fn aliases_to_xml(aliases: &[FaAlias]) -> String {
+    let mut xml =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<ListOfAccountAliases>\n");
+    for alias in aliases {
+        xml.push_str("  <AccountAlias>\n");
+        xml.push_str(&format!(
+            "    <account>{}</account>\n",
+            escape(&alias.account)
+        ));
+        xml.push_str(&format!("    <alias>{}</alias>\n", escape(&alias.alias)));
+        xml.push_str("  </AccountAlias>\n");
+    }
+    xml.push_str("</ListOfAccountAliases>\n");
+    xml
+}
+
+//==================================================================================================
+/// Fetches the allocation groups from TWS
+pub fn fetch_groups<T>(
+    client: &Mutex<EClient<T>>,
+    timeout: Duration,
+) -> Result<Vec<FaGroup>, FaConfigError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let xml = client
+        .lock()
+        .expect(POISONED_MUTEX)
+        .fetch_fa(FaDataType::GROUPS, timeout)?;
+    Ok(parse_groups(&xml)?)
+}
+
+//==================================================================================================
+/// Fetches the allocation profiles from TWS
+pub fn fetch_profiles<T>(
+    client: &Mutex<EClient<T>>,
+    timeout: Duration,
+) -> Result<Vec<FaProfile>, FaConfigError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let xml = client
+        .lock()
+        .expect(POISONED_MUTEX)
+        .fetch_fa(FaDataType::PROFILES, timeout)?;
+    Ok(parse_profiles(&xml)?)
+}
+
+//==================================================================================================
+/// Fetches the account aliases from TWS
+pub fn fetch_aliases<T>(
+    client: &Mutex<EClient<T>>,
+    timeout: Duration,
+) -> Result<Vec<FaAlias>, FaConfigError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let xml = client
+        .lock()
+        .expect(POISONED_MUTEX)
+        .fetch_fa(FaDataType::ALIASES, timeout)?;
+    Ok(parse_aliases(&xml)?)
+}
+
+//==================================================================================================
+/// Replaces all allocation groups of TWS. TWS confirms it with
+/// `Wrapper::replace_fa_end` for `NO_VALID_ID`.
+pub fn replace_groups<T>(client: &Mutex<EClient<T>>, groups: &[FaGroup]) -> Result<(), IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    client.lock().expect(POISONED_MUTEX).replace_fa(
+        NO_VALID_ID,
+        FaDataType::GROUPS,
+        &groups_to_xml(groups),
+    )
+}
+
+//==================================================================================================
+/// Replaces all allocation profiles of TWS
+pub fn replace_profiles<T>(
+    client: &Mutex<EClient<T>>,
+    profiles: &[FaProfile],
+) -> Result<(), IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    client.lock().expect(POISONED_MUTEX).replace_fa(
+        NO_VALID_ID,
+        FaDataType::PROFILES,
+        &profiles_to_xml(profiles),
+    )
+}
+
+//==================================================================================================
+/// Replaces all account aliases of TWS
+pub fn replace_aliases<T>(client: &Mutex<EClient<T>>, aliases: &[FaAlias]) -> Result<(), IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    client.lock().expect(POISONED_MUTEX).replace_fa(
+        NO_VALID_ID,
+        FaDataType::ALIASES,
+        &aliases_to_xml(aliases),
+    )
+}
+
+//==================================================================================================
+/// Adds an account to an allocation group of TWS. The groups are only
+/// replaced if the account was not in the group yet. Returns whether it was
+/// added.
+pub fn add_account_to_group<T>(
+    client: &Mutex<EClient<T>>,
+    group: &str,
+    account: &str,
+    timeout: Duration,
+) -> Result<bool, FaConfigError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    modify_group(client, group, timeout, |g| g.add_account(account))
+}
+
+//==================================================================================================
+/// Removes an account from an allocation group of TWS. Returns whether it
+/// was in the group.
+pub fn remove_account_from_group<T>(
+    client: &Mutex<EClient<T>>,
+    group: &str,
+    account: &str,
+    timeout: Duration,
+) -> Result<bool, FaConfigError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    modify_group(client, group, timeout, |g| g.remove_account(account))
+}
+
+//==================================================================================================
+/// Sets the amount allocated to an account by an allocation profile of TWS
+pub fn set_profile_allocation<T>(
+    client: &Mutex<EClient<T>>,
+    profile: &str,
+    account: &str,
+    amount: f64,
+    timeout: Duration,
+) -> Result<(), FaConfigError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut profiles = fetch_profiles(client, timeout)?;
+    profiles
+        .iter_mut()
+        .find(|p| p.name == profile)
+        .ok_or_else(|| FaConfigError::UnknownProfile(profile.to_string()))?
+        .set_allocation(account, amount);
+    Ok(replace_profiles(client, &profiles)?)
+}
+
+//==================================================================================================
+fn modify_group<T, F>(
+    client: &Mutex<EClient<T>>,
+    group: &str,
+    timeout: Duration,
+    modify: F,
+) -> Result<bool, FaConfigError>
+where
+    T: Wrapper + Send + Sync + 'static,
+    F: FnOnce(&mut FaGroup) -> bool,
+{
+    let mut groups = fetch_groups(client, timeout)?;
+    let changed = modify(
+        groups
+            .iter_mut()
+            .find(|g| g.name == group)
+            .ok_or_else(|| FaConfigError::UnknownGroup(group.to_string()))?,
+    );
+    if changed {
+        replace_groups(client, &groups)?;
+    }
+    Ok(changed)
+}
+
+//==================================================================================================
+fn child_text(node: &Node, name: &str) -> String {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .unwrap_or("")
+        .trim()
+        .to_string()
+}
+
+//==================================================================================================
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
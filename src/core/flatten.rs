@@ -0,0 +1,435 @@
+//! Closing orders that flatten positions
+//!
+//! `flatten` and `flatten_all` fetch the current positions from TWS and
+//! place one order per position that brings it to zero. The orders are
+//! market orders or marketable limit orders priced off the quotes of the
+//! client's market data subscriptions, so subscribe to the contracts with
+//! their `con_id` before using limit orders.
+//!
+//! Quantities are in contracts: an option position of 3 is closed with an
+//! order for 3, whatever its multiplier. A combo is closed with one order
+//! on the combo for the number of units its leg positions add up to.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::NO_VALID_ID;
+use crate::core::contract::{Contract, SecType};
+use crate::core::errors::IbkrError;
+use crate::core::order::Order;
+use crate::core::portfolio::contract_multiplier;
+use crate::core::positions::Position;
+use crate::core::quote_cache::{Quote, QuoteCache};
+use crate::core::reconciliation::SIZE_TOLERANCE;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// How closing orders are priced
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FlattenStyle {
+    Market,
+    /// A limit order at the bid for sells and at the ask for buys, moved
+    /// by `offset` into the market. Falls back to the last price if the
+    /// side of the quote is missing. Keep the offset a multiple of the
+    /// price increment of the contracts.
+    MarketableLimit {
+        offset: f64,
+    },
+}
+
+//==================================================================================================
+/// Selects the positions `flatten_all` closes. An empty filter selects all
+/// positions.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FlattenFilter {
+    pub account: Option<String>,
+    pub sec_type: Option<SecType>,
+    pub symbol: Option<String>,
+}
+
+impl FlattenFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn account(mut self, account: &str) -> Self {
+        self.account = Some(account.to_string());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn sec_type(mut self, sec_type: SecType) -> Self {
+        self.sec_type = Some(sec_type);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn symbol(mut self, symbol: &str) -> Self {
+        self.symbol = Some(symbol.to_string());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn matches(&self, position: &Position) -> bool {
+        self.account
+            .as_ref()
+            .is_none_or(|account| *account == position.account)
+            && self
+                .sec_type
+                .as_ref()
+                .is_none_or(|sec_type| *sec_type == position.contract.sec_type)
+            && self
+                .symbol
+                .as_ref()
+                .is_none_or(|symbol| *symbol == position.contract.symbol)
+    }
+}
+
+//==================================================================================================
+/// An order that closes a position
+///
+/// contract - the contract to place the order on, routed to its primary
+///            exchange or SMART if the position did not carry an exchange
+/// order - the order, with its `order_id` set once it has been placed
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClosingOrder {
+    pub account: String,
+    pub contract: Contract,
+    pub order: Order,
+}
+
+impl ClosingOrder {
+    /// The value the order trades at `price`, including the multiplier of
+    /// the contract
+    pub fn notional(&self, price: f64) -> f64 {
+        self.order.total_quantity * price * contract_multiplier(&self.contract)
+    }
+}
+
+impl fmt::Display for ClosingOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, local_symbol: {}, order_id: {}, action: {}, quantity: {}, order_type: {}, \
+             limit_price: {}",
+            self.account,
+            self.contract.local_symbol,
+            self.order.order_id,
+            self.order.action,
+            self.order.total_quantity,
+            self.order.order_type,
+            self.order.lmt_price
+        )
+    }
+}
+
+//==================================================================================================
+pub(crate) fn closing_contract(contract: &Contract) -> Contract {
+    let mut contract = contract.clone();
+    if contract.exchange.is_empty() {
+        contract.exchange = if contract.primary_exchange.is_empty() {
+            "SMART".to_string()
+        } else {
+            contract.primary_exchange.clone()
+        };
+    }
+    contract
+}
+
+//==================================================================================================
+/// The price to trade at right away: the bid when selling, the ask when
+/// buying, else the last price
+fn marketable_price(quote: Option<&Quote>, selling: bool) -> Option<f64> {
+    quote.and_then(|quote| {
+        if selling { quote.bid } else { quote.ask }
+            .or(quote.last)
+            .filter(|price| *price > 0.0)
+    })
+}
+
+//==================================================================================================
+fn closing_order_for(
+    account: &str,
+    contract: &Contract,
+    size: f64,
+    price: impl Fn(bool) -> Option<f64>,
+    style: FlattenStyle,
+) -> Result<ClosingOrder, String> {
+    if size.abs() <= SIZE_TOLERANCE {
+        return Err(format!(
+            "The position of {} in {} is already flat.",
+            account, contract.local_symbol
+        ));
+    }
+    let selling = size > 0.0;
+    let action = if selling { "SELL" } else { "BUY" };
+    let order = match style {
+        FlattenStyle::Market => Order::market_order(account, action, size.abs()),
+        FlattenStyle::MarketableLimit { offset } => {
+            let price = price(selling).ok_or_else(|| {
+                format!(
+                    "No quote to price the closing order of {} in {}.",
+                    account, contract.local_symbol
+                )
+            })?;
+            let limit_price = if selling {
+                price - offset
+            } else {
+                price + offset
+            };
+            Order::limit_order(account, action, size.abs(), limit_price)
+        }
+    };
+    Ok(ClosingOrder {
+        account: account.to_string(),
+        contract: closing_contract(contract),
+        order,
+    })
+}
+
+//==================================================================================================
+/// The order that closes a position
+///
+/// # Arguments
+/// * position - a position of a single contract, e.g. from
+///   `EClient::fetch_positions`
+/// * style - how to price the order
+/// * quotes - the quotes to price marketable limit orders with
+pub fn closing_order(
+    position: &Position,
+    style: FlattenStyle,
+    quotes: &QuoteCache,
+) -> Result<ClosingOrder, String> {
+    let size: f64 = position.size.to_string().parse().unwrap_or(0.0);
+    let quote = quotes.contract_quote(position.contract.con_id);
+    closing_order_for(
+        &position.account,
+        &position.contract,
+        size,
+        |selling| marketable_price(quote, selling),
+        style,
+    )
+}
+
+//==================================================================================================
+/// The number of units of a combo an account holds, negative if short.
+/// Every leg must be held in the direction and ratio of the combo; legs
+/// held in a larger ratio count as far as the combo goes.
+///
+/// # Arguments
+/// * combo - a BAG contract, e.g. built with `ComboBuilder`
+/// * account - the account holding the legs
+/// * positions - the positions of the legs
+pub fn combo_size(combo: &Contract, account: &str, positions: &[Position]) -> Result<f64, String> {
+    if combo.combo_legs.is_empty() {
+        return Err("The combo has no legs.".to_string());
+    }
+    let mut sizes: BTreeMap<i32, f64> = BTreeMap::new();
+    for position in positions
+        .iter()
+        .filter(|position| position.account == account)
+    {
+        *sizes.entry(position.contract.con_id).or_insert(0.0) +=
+            position.size.to_string().parse().unwrap_or(0.0);
+    }
+
+    let mut units: Option<f64> = None;
+    for leg in &combo.combo_legs {
+        let direction = if leg.action == "BUY" { 1.0 } else { -1.0 };
+        let leg_units = sizes.get(&leg.con_id).copied().unwrap_or(0.0) / (leg.ratio * direction);
+        if leg_units.abs() <= SIZE_TOLERANCE {
+            return Ok(0.0);
+        }
+        units = Some(match units {
+            None => leg_units,
+            Some(units) if units.signum() != leg_units.signum() => {
+                return Err(format!(
+                    "The positions of {} do not form the combo: leg {} is not held in its \
+                     direction.",
+                    account, leg.con_id
+                ));
+            }
+            Some(units) if units.abs() <= leg_units.abs() => units,
+            Some(_) => leg_units,
+        });
+    }
+    // Legs can only be traded in whole units of the combo
+    Ok(units.map_or(0.0, f64::trunc))
+}
+
+//==================================================================================================
+/// The order that closes the position of an account in a combo. Marketable
+/// limit orders are priced at the ratio weighted sum of the leg quotes, as
+/// combos of options are.
+///
+/// # Arguments
+/// * combo - a BAG contract, e.g. built with `ComboBuilder`
+/// * account - the account holding the legs
+/// * positions - the positions of the legs
+/// * style - how to price the order
+/// * quotes - the quotes of the legs to price marketable limit orders with
+pub fn closing_combo_order(
+    combo: &Contract,
+    account: &str,
+    positions: &[Position],
+    style: FlattenStyle,
+    quotes: &QuoteCache,
+) -> Result<ClosingOrder, String> {
+    let size = combo_size(combo, account, positions)?;
+    let price = |selling: bool| {
+        combo.combo_legs.iter().try_fold(0.0, |price, leg| {
+            let bought = leg.action == "BUY";
+            // Closing sells the legs the combo buys and buys the others back
+            let leg_price = marketable_price(quotes.contract_quote(leg.con_id), selling == bought)?;
+            Some(if bought {
+                price + leg.ratio * leg_price
+            } else {
+                price - leg.ratio * leg_price
+            })
+        })
+    };
+    closing_order_for(account, combo, size, price, style)
+}
+
+//==================================================================================================
+/// The orders that close the positions `filter` selects, ordered as the
+/// positions. Flat positions are skipped.
+pub fn closing_orders(
+    positions: &[Position],
+    filter: &FlattenFilter,
+    style: FlattenStyle,
+    quotes: &QuoteCache,
+) -> Result<Vec<ClosingOrder>, String> {
+    positions
+        .iter()
+        .filter(|position| filter.matches(position) && !position.is_closed())
+        .map(|position| closing_order(position, style, quotes))
+        .collect()
+}
+
+//==================================================================================================
+fn place_closing_orders<T>(
+    client: &mut EClient<T>,
+    closing_orders: Vec<ClosingOrder>,
+    next_order_id: &mut i32,
+) -> Result<Vec<ClosingOrder>, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut placed = vec![];
+    for mut closing_order in closing_orders {
+        closing_order.order.order_id = *next_order_id;
+        client.place_order(
+            closing_order.order.order_id,
+            &closing_order.contract,
+            &closing_order.order,
+        )?;
+        *next_order_id += 1;
+        placed.push(closing_order);
+    }
+    Ok(placed)
+}
+
+//==================================================================================================
+fn flatten_error(message: String) -> IbkrError {
+    IbkrError::rejected(NO_VALID_ID, &message)
+}
+
+//==================================================================================================
+/// Closes the positions of all accounts in a contract. A combo closes the
+/// units of it each account holds.
+///
+/// Returns the placed orders. No order is placed if one of them cannot be
+/// built, e.g. for lack of a quote.
+///
+/// # Arguments
+/// * client - a connected client that is not streaming positions
+/// * contract - the contract to close, with its `con_id` set
+/// * style - how to price the orders
+/// * next_order_id - the next valid order id, advanced past the ids used
+/// * timeout - how long to wait for each of the positions
+pub fn flatten<T>(
+    client: &Mutex<EClient<T>>,
+    contract: &Contract,
+    style: FlattenStyle,
+    next_order_id: &mut i32,
+    timeout: Duration,
+) -> Result<Vec<ClosingOrder>, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut client = client.lock().expect(POISONED_MUTEX);
+    let positions = client.fetch_positions(timeout)?;
+    let closing_orders = {
+        let quotes = client.quotes.lock().expect(POISONED_MUTEX);
+        if contract.sec_type == SecType::Combo {
+            let accounts: BTreeSet<&str> = positions
+                .iter()
+                .filter(|position| {
+                    contract
+                        .combo_legs
+                        .iter()
+                        .any(|leg| leg.con_id == position.contract.con_id)
+                })
+                .map(|position| position.account.as_str())
+                .collect();
+            accounts
+                .into_iter()
+                .filter(|account| {
+                    combo_size(contract, account, &positions)
+                        .map_or(true, |size| size.abs() > SIZE_TOLERANCE)
+                })
+                .map(|account| closing_combo_order(contract, account, &positions, style, &quotes))
+                .collect::<Result<Vec<_>, _>>()
+        } else {
+            let positions: Vec<Position> = positions
+                .into_iter()
+                .filter(|position| position.contract.con_id == contract.con_id)
+                .collect();
+            closing_orders(&positions, &FlattenFilter::new(), style, &quotes)
+        }
+    }
+    .map_err(flatten_error)?;
+    place_closing_orders(&mut client, closing_orders, next_order_id)
+}
+
+//==================================================================================================
+/// Closes all positions `filter` selects, one order per position
+///
+/// Returns the placed orders. No order is placed if one of them cannot be
+/// built, e.g. for lack of a quote.
+///
+/// # Arguments
+/// * client - a connected client that is not streaming positions
+/// * filter - the positions to close
+/// * style - how to price the orders
+/// * next_order_id - the next valid order id, advanced past the ids used
+/// * timeout - how long to wait for each of the positions
+pub fn flatten_all<T>(
+    client: &Mutex<EClient<T>>,
+    filter: &FlattenFilter,
+    style: FlattenStyle,
+    next_order_id: &mut i32,
+    timeout: Duration,
+) -> Result<Vec<ClosingOrder>, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut client = client.lock().expect(POISONED_MUTEX);
+    let positions = client.fetch_positions(timeout)?;
+    let closing_orders = {
+        let quotes = client.quotes.lock().expect(POISONED_MUTEX);
+        closing_orders(&positions, filter, style, &quotes)
+    }
+    .map_err(flatten_error)?;
+    place_closing_orders(&mut client, closing_orders, next_order_id)
+}
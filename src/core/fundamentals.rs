@@ -0,0 +1,487 @@
+//! Typed views of the fundamental data XML returned by
+//! `Wrapper::fundamental_data`. Requires the `fundamentals` feature.
+//!
+//! Values that are missing or can not be parsed are left out, so a report
+//! of a company that lacks some data still parses.
+use std::collections::BTreeMap;
+use std::fmt::{Display, Error, Formatter};
+
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const REPORT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+//==================================================================================================
+/// The report types of `EClient::request_fundamental_data`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FundamentalReportType {
+    /// Company overview, parsed by `CompanySnapshot`
+    Snapshot,
+    /// Financial summary, parsed by `FinancialSummary`
+    FinancialSummary,
+    /// Financial ratios
+    Ratios,
+    /// Financial statements, parsed by `FinancialStatements`
+    FinancialStatements,
+    /// Analyst estimates, parsed by `Estimates`
+    Estimates,
+    /// Company calendar
+    Calendar,
+}
+
+impl Display for FundamentalReportType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match *self {
+            FundamentalReportType::Snapshot => write!(f, "ReportSnapshot"),
+            FundamentalReportType::FinancialSummary => write!(f, "ReportsFinSummary"),
+            FundamentalReportType::Ratios => write!(f, "ReportRatios"),
+            FundamentalReportType::FinancialStatements => write!(f, "ReportsFinStatements"),
+            FundamentalReportType::Estimates => write!(f, "RESC"),
+            FundamentalReportType::Calendar => write!(f, "CalendarReport"),
+        }
+    }
+}
+
+//==================================================================================================
+/// The company overview of a `ReportSnapshot`
+///
+/// ratios - the ratios by their field name, e.g. `NPRICE` or `PEEXCLXOR`
+/// forecasts - the current consensus values by their field name, e.g.
+///             `ConsRecom` or `TargetPrice`
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompanySnapshot {
+    pub company_name: String,
+    pub ticker: String,
+    pub exchange: String,
+    pub reporting_currency: String,
+    pub employees: Option<i64>,
+    pub shares_outstanding: Option<f64>,
+    pub business_summary: String,
+    pub ratios: BTreeMap<String, f64>,
+    pub forecasts: BTreeMap<String, f64>,
+}
+
+impl CompanySnapshot {
+    pub fn parse(xml: &str) -> Result<Self, roxmltree::Error> {
+        let document = Document::parse(xml)?;
+        let root = document.root_element();
+        let mut snapshot = CompanySnapshot::default();
+
+        for node in root.descendants().filter(|node| node.is_element()) {
+            match node.tag_name().name() {
+                "CoID" if node.attribute("Type") == Some("CompanyName") => {
+                    snapshot.company_name = text(&node);
+                }
+                // The first issue is the common stock
+                "IssueID"
+                    if node.attribute("Type") == Some("Ticker") && snapshot.ticker.is_empty() =>
+                {
+                    snapshot.ticker = text(&node);
+                }
+                "Exchange" if snapshot.exchange.is_empty() => {
+                    snapshot.exchange = node.attribute("Code").unwrap_or_default().to_string();
+                }
+                "ReportingCurrency" => {
+                    snapshot.reporting_currency =
+                        node.attribute("Code").unwrap_or_default().to_string();
+                }
+                "Employees" => snapshot.employees = text(&node).parse().ok(),
+                "SharesOut" => snapshot.shares_outstanding = number(&node),
+                "Text" if node.attribute("Type") == Some("Business Summary") => {
+                    snapshot.business_summary = text(&node);
+                }
+                "Ratio" => {
+                    let field_name = node.attribute("FieldName").unwrap_or_default().to_string();
+                    let in_forecasts = node
+                        .ancestors()
+                        .any(|ancestor| ancestor.has_tag_name("ForecastData"));
+                    if in_forecasts {
+                        let value = node
+                            .children()
+                            .find(|value| {
+                                value.has_tag_name("Value")
+                                    && value.attribute("PeriodType") == Some("CURR")
+                            })
+                            .and_then(|value| number(&value));
+                        if let Some(value) = value {
+                            snapshot.forecasts.insert(field_name, value);
+                        }
+                    } else if let Some(value) = number(&node) {
+                        snapshot.ratios.insert(field_name, value);
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(snapshot)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn ratio(&self, field_name: &str) -> Option<f64> {
+        self.ratios.get(field_name).copied()
+    }
+}
+
+//==================================================================================================
+/// A value of a `FinancialSummary`
+///
+/// report_type - `A` for audited, `P` for preliminary, `R` for restated or
+///               `TTM` for trailing twelve months
+/// period - the length of the period, e.g. `3M` or `12M`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SummaryValue {
+    pub as_of: NaiveDate,
+    pub report_type: String,
+    pub period: String,
+    pub value: f64,
+}
+
+//==================================================================================================
+/// The earnings, revenues and dividends of a `ReportsFinSummary`, each
+/// ordered by date
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FinancialSummary {
+    pub currency: String,
+    pub eps: Vec<SummaryValue>,
+    pub revenues: Vec<SummaryValue>,
+    pub dividends_per_share: Vec<SummaryValue>,
+}
+
+impl FinancialSummary {
+    pub fn parse(xml: &str) -> Result<Self, roxmltree::Error> {
+        let document = Document::parse(xml)?;
+        let root = document.root_element();
+        let mut summary = FinancialSummary::default();
+
+        for node in root.descendants().filter(|node| node.is_element()) {
+            let values = match node.tag_name().name() {
+                "EPS" => &mut summary.eps,
+                "TotalRevenue" => &mut summary.revenues,
+                "DividendPerShare" => &mut summary.dividends_per_share,
+                "TotalRevenues" => {
+                    summary.currency = node.attribute("currency").unwrap_or_default().to_string();
+                    continue;
+                }
+                _ => continue,
+            };
+            let as_of = node
+                .attribute("asofDate")
+                .and_then(|date| NaiveDate::parse_from_str(date, REPORT_DATE_FORMAT).ok());
+            if let (Some(as_of), Some(value)) = (as_of, number(&node)) {
+                values.push(SummaryValue {
+                    as_of,
+                    report_type: node.attribute("reportType").unwrap_or_default().to_string(),
+                    period: node.attribute("period").unwrap_or_default().to_string(),
+                    value,
+                });
+            }
+        }
+        for values in [
+            &mut summary.eps,
+            &mut summary.revenues,
+            &mut summary.dividends_per_share,
+        ] {
+            values.sort_by_key(|value| value.as_of);
+        }
+        Ok(summary)
+    }
+}
+
+//==================================================================================================
+/// The kind of a financial statement
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum StatementType {
+    /// `INC`
+    Income,
+    /// `BAL`
+    BalanceSheet,
+    /// `CAS`
+    CashFlow,
+    Other(String),
+}
+
+impl From<&str> for StatementType {
+    fn from(statement_type: &str) -> Self {
+        match statement_type {
+            "INC" => StatementType::Income,
+            "BAL" => StatementType::BalanceSheet,
+            "CAS" => StatementType::CashFlow,
+            other => StatementType::Other(other.to_string()),
+        }
+    }
+}
+
+impl Display for StatementType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            StatementType::Income => write!(f, "INC"),
+            StatementType::BalanceSheet => write!(f, "BAL"),
+            StatementType::CashFlow => write!(f, "CAS"),
+            StatementType::Other(statement_type) => write!(f, "{}", statement_type),
+        }
+    }
+}
+
+//==================================================================================================
+/// A line of a financial statement, e.g. `SREV` named `Revenue`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LineItem {
+    pub code: String,
+    pub name: String,
+    pub value: f64,
+}
+
+//==================================================================================================
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Statement {
+    pub statement_type: StatementType,
+    pub line_items: Vec<LineItem>,
+}
+
+impl Statement {
+    /// The value of the line with a code, e.g. `SREV` for the revenue
+    pub fn value(&self, code: &str) -> Option<f64> {
+        self.line_items
+            .iter()
+            .find(|line_item| line_item.code == code)
+            .map(|line_item| line_item.value)
+    }
+}
+
+//==================================================================================================
+/// The statements of a fiscal year or, for interim periods, a quarter or
+/// half year
+///
+/// period_number - the number of an interim period in its fiscal year
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FiscalPeriod {
+    pub end_date: NaiveDate,
+    pub fiscal_year: i32,
+    pub period_number: Option<i32>,
+    pub statements: Vec<Statement>,
+}
+
+impl FiscalPeriod {
+    pub fn statement(&self, statement_type: &StatementType) -> Option<&Statement> {
+        self.statements
+            .iter()
+            .find(|statement| statement.statement_type == *statement_type)
+    }
+}
+
+//==================================================================================================
+/// The income statements, balance sheets and cash flow statements of a
+/// `ReportsFinStatements`, latest period first as sent by TWS
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FinancialStatements {
+    pub currency: String,
+    pub annual: Vec<FiscalPeriod>,
+    pub interim: Vec<FiscalPeriod>,
+}
+
+impl FinancialStatements {
+    pub fn parse(xml: &str) -> Result<Self, roxmltree::Error> {
+        let document = Document::parse(xml)?;
+        let root = document.root_element();
+        let mut statements = FinancialStatements::default();
+
+        let line_names: BTreeMap<&str, String> = root
+            .descendants()
+            .filter(|node| node.has_tag_name("mapItem"))
+            .filter_map(|node| Some((node.attribute("coaItem")?, text(&node))))
+            .collect();
+
+        for node in root.descendants().filter(|node| node.is_element()) {
+            match node.tag_name().name() {
+                "ReportingCurrency" => {
+                    statements.currency = node.attribute("Code").unwrap_or_default().to_string();
+                }
+                "FiscalPeriod" => {
+                    let period = match fiscal_period(&node, &line_names) {
+                        Some(period) => period,
+                        None => continue,
+                    };
+                    if node.attribute("Type") == Some("Interim") {
+                        statements.interim.push(period);
+                    } else {
+                        statements.annual.push(period);
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(statements)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The latest annual statement of a kind
+    pub fn latest_annual(&self, statement_type: &StatementType) -> Option<&Statement> {
+        self.annual
+            .iter()
+            .max_by_key(|period| period.end_date)
+            .and_then(|period| period.statement(statement_type))
+    }
+}
+
+//==================================================================================================
+fn fiscal_period(node: &Node, line_names: &BTreeMap<&str, String>) -> Option<FiscalPeriod> {
+    let end_date =
+        NaiveDate::parse_from_str(node.attribute("EndDate")?, REPORT_DATE_FORMAT).ok()?;
+    let statements = node
+        .children()
+        .filter(|statement| statement.has_tag_name("Statement"))
+        .map(|statement| Statement {
+            statement_type: statement.attribute("Type").unwrap_or_default().into(),
+            line_items: statement
+                .children()
+                .filter(|line_item| line_item.has_tag_name("lineItem"))
+                .filter_map(|line_item| {
+                    let code = line_item.attribute("coaCode")?;
+                    Some(LineItem {
+                        code: code.to_string(),
+                        name: line_names.get(code).cloned().unwrap_or_default(),
+                        value: number(&line_item)?,
+                    })
+                })
+                .collect(),
+        })
+        .collect();
+    Some(FiscalPeriod {
+        end_date,
+        fiscal_year: node.attribute("FiscalYear")?.parse().ok()?,
+        period_number: node
+            .attribute("FiscalPeriodNumber")
+            .and_then(|number| number.parse().ok()),
+        statements,
+    })
+}
+
+//==================================================================================================
+/// The consensus estimates of a measure for a fiscal year
+///
+/// measure - what is estimated, e.g. `EPS` or `REVENUE`
+/// period_type - `A` for annual, `Q` for quarterly estimates
+/// values - the current values by their type, e.g. `Mean` or `NumOfEst`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FiscalYearEstimate {
+    pub measure: String,
+    pub unit: String,
+    pub period_type: String,
+    pub fiscal_year: i32,
+    pub end_month: Option<u32>,
+    pub values: BTreeMap<String, f64>,
+}
+
+impl FiscalYearEstimate {
+    pub fn mean(&self) -> Option<f64> {
+        self.values.get("Mean").copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn high(&self) -> Option<f64> {
+        self.values.get("High").copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn low(&self) -> Option<f64> {
+        self.values.get("Low").copied()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The number of analysts whose estimates make up the consensus
+    pub fn number_of_estimates(&self) -> Option<i32> {
+        self.values.get("NumOfEst").map(|count| *count as i32)
+    }
+}
+
+//==================================================================================================
+/// The analyst consensus estimates of a `RESC` report
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Estimates {
+    pub fiscal_years: Vec<FiscalYearEstimate>,
+}
+
+impl Estimates {
+    pub fn parse(xml: &str) -> Result<Self, roxmltree::Error> {
+        let document = Document::parse(xml)?;
+        let root = document.root_element();
+        let mut estimates = Estimates::default();
+
+        for estimate in root
+            .descendants()
+            .filter(|node| node.has_tag_name("FYEstimate"))
+        {
+            for period in estimate
+                .children()
+                .filter(|node| node.has_tag_name("FYPeriod"))
+            {
+                let fiscal_year = match period.attribute("fYear").and_then(|year| year.parse().ok())
+                {
+                    Some(fiscal_year) => fiscal_year,
+                    None => continue,
+                };
+                let values = period
+                    .children()
+                    .filter(|node| node.has_tag_name("ConsEstimate"))
+                    .filter_map(|node| {
+                        let value = node
+                            .children()
+                            .find(|value| {
+                                value.has_tag_name("ConsValue")
+                                    && value.attribute("dateType") == Some("CURR")
+                            })
+                            .and_then(|value| number(&value))?;
+                        Some((node.attribute("type")?.to_string(), value))
+                    })
+                    .collect();
+                estimates.fiscal_years.push(FiscalYearEstimate {
+                    measure: estimate.attribute("type").unwrap_or_default().to_string(),
+                    unit: estimate.attribute("unit").unwrap_or_default().to_string(),
+                    period_type: period
+                        .attribute("periodType")
+                        .unwrap_or_default()
+                        .to_string(),
+                    fiscal_year,
+                    end_month: period
+                        .attribute("endMonth")
+                        .and_then(|month| month.parse().ok()),
+                    values,
+                });
+            }
+        }
+        Ok(estimates)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The annual estimate of a measure for a fiscal year
+    pub fn annual(&self, measure: &str, fiscal_year: i32) -> Option<&FiscalYearEstimate> {
+        self.fiscal_years.iter().find(|estimate| {
+            estimate.measure == measure
+                && estimate.fiscal_year == fiscal_year
+                && estimate.period_type == "A"
+        })
+    }
+}
+
+//==================================================================================================
+fn text(node: &Node) -> String {
+    node.text().unwrap_or_default().trim().to_string()
+}
+
+//==================================================================================================
+fn number(node: &Node) -> Option<f64> {
+    node.text()?.trim().parse().ok()
+}
@@ -0,0 +1,258 @@
+//! The active expiries of a futures root and rolls between them
+//!
+//! Dates passed as `today` should be the current date at the exchange, e.g.
+//! `Utc::now().with_timezone(&tz).date_naive()` with the time zone of
+//! `ContractDetails::time_zone`, as expiries are dates at the exchange.
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::Action;
+use crate::core::continuous_futures::parse_expiry;
+use crate::core::contract::{Contract, ContractDetails, SecType};
+use crate::core::errors::IbkrError;
+use crate::core::order::Order;
+use crate::core::spreads::ComboBuilder;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// The number of days from `today` to `expiry`; 0 on the day of expiry and
+/// negative once expired
+pub fn days_to_expiry(expiry: NaiveDate, today: NaiveDate) -> i64 {
+    (expiry - today).num_days()
+}
+
+//==================================================================================================
+/// One expiry of a futures root
+///
+/// expiry - the last trading day, from the real expiration date if TWS sends
+///          one
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FuturesExpiry {
+    pub expiry: NaiveDate,
+    pub details: ContractDetails,
+}
+
+impl FuturesExpiry {
+    pub fn new(expiry: NaiveDate, details: ContractDetails) -> Self {
+        FuturesExpiry { expiry, details }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The expiry of the contract of `details`. Returns `None` if the
+    /// expiry can not be parsed.
+    pub fn from_contract_details(details: ContractDetails) -> Option<Self> {
+        let expiry = parse_expiry(&details.real_expiration_date)
+            .or_else(|| parse_expiry(&details.contract.last_trade_date_or_contract_month))?;
+        Some(FuturesExpiry::new(expiry, details))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn contract(&self) -> &Contract {
+        &self.details.contract
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn days_to_expiry(&self, today: NaiveDate) -> i64 {
+        days_to_expiry(self.expiry, today)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the contract expires within `roll_days` days of `today` but
+    /// has not expired yet
+    pub fn in_roll_window(&self, today: NaiveDate, roll_days: i64) -> bool {
+        (0..=roll_days).contains(&self.days_to_expiry(today))
+    }
+}
+
+impl fmt::Display for FuturesExpiry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expiry: {}, local_symbol: {}",
+            self.expiry, self.details.contract.local_symbol
+        )
+    }
+}
+
+//==================================================================================================
+/// The expiries of a futures root, ordered by expiry
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FuturesChain {
+    pub root: Contract,
+    pub expiries: Vec<FuturesExpiry>,
+}
+
+impl FuturesChain {
+    /// Orders the details by expiry and drops duplicates and details
+    /// without a valid expiry
+    pub fn new(root: Contract, details: Vec<ContractDetails>) -> Self {
+        let mut expiries: Vec<FuturesExpiry> = details
+            .into_iter()
+            .filter_map(FuturesExpiry::from_contract_details)
+            .collect();
+        expiries.sort_by_key(|expiry| (expiry.expiry, expiry.details.contract.con_id));
+        expiries.dedup_by_key(|expiry| expiry.details.contract.con_id);
+        FuturesChain { root, expiries }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Requests the contract details of all expiries of a root that are
+    /// still trading
+    ///
+    /// # Arguments
+    /// * client - a connected client
+    /// * request_id - the id of the contract details request
+    /// * root - the symbol, exchange and currency of the futures, e.g. ES on
+    ///   CME in USD
+    /// * timeout - how long to wait for each of the details
+    pub fn fetch<T>(
+        client: &Mutex<EClient<T>>,
+        request_id: i32,
+        root: &Contract,
+        timeout: Duration,
+    ) -> Result<Self, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        let mut query = root.clone();
+        query.sec_type = SecType::Future;
+        query.last_trade_date_or_contract_month = "".to_string();
+        query.include_expired = false;
+
+        let details = client
+            .lock()
+            .expect(POISONED_MUTEX)
+            .fetch_contract_details(request_id, &query, timeout)?;
+        Ok(FuturesChain::new(root.clone(), details))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The expiries that have not expired on `today`
+    pub fn active(&self, today: NaiveDate) -> impl Iterator<Item = &FuturesExpiry> {
+        self.expiries
+            .iter()
+            .filter(move |expiry| expiry.expiry >= today)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The first expiry that has not expired on `today`
+    pub fn front_month(&self, today: NaiveDate) -> Option<&FuturesExpiry> {
+        self.active(today).next()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The expiry after the front month
+    pub fn back_month(&self, today: NaiveDate) -> Option<&FuturesExpiry> {
+        self.active(today).nth(1)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The expiries that are inside the roll window on `today`, see
+    /// `FuturesExpiry::in_roll_window`
+    pub fn rolling(&self, today: NaiveDate, roll_days: i64) -> Vec<&FuturesExpiry> {
+        self.active(today)
+            .filter(|expiry| expiry.in_roll_window(today, roll_days))
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The expiry to hold on `today`: the front month, or the next expiry
+    /// once the front month is inside the roll window
+    pub fn current(&self, today: NaiveDate, roll_days: i64) -> Option<&FuturesExpiry> {
+        self.active(today)
+            .find(|expiry| !expiry.in_roll_window(today, roll_days))
+    }
+}
+
+//==================================================================================================
+/// The price of a roll order
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RollPrice {
+    Market,
+    /// The price of the target expiry minus the price of the expiring one
+    Limit(f64),
+}
+
+impl fmt::Display for RollPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RollPrice::Market => write!(f, "Market"),
+            RollPrice::Limit(price) => write!(f, "Limit({})", price),
+        }
+    }
+}
+
+//==================================================================================================
+/// A calendar spread that moves a position to a later expiry and the order
+/// to place on it
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FuturesRoll {
+    pub combo: Contract,
+    pub order: Order,
+}
+
+//==================================================================================================
+/// The roll of a position from an expiring future to a later expiry of the
+/// same root. The combo sells the expiring and buys the target contract;
+/// the order buys it to roll a long position and sells it to roll a short
+/// one, in the size of the position.
+///
+/// # Arguments
+/// * expiring - the contract of the position, with its `con_id` set
+/// * target - the later expiry, with its `con_id` set
+/// * position - the position in the expiring contract, negative if short
+/// * price - the order type and, for limit orders, the spread price
+/// * account - the account of the position, empty for the default account
+pub fn roll(
+    expiring: &Contract,
+    target: &Contract,
+    position: f64,
+    price: &RollPrice,
+    account: &str,
+) -> Result<FuturesRoll, String> {
+    if expiring.sec_type != SecType::Future || target.sec_type != SecType::Future {
+        return Err("Only futures can be rolled".to_string());
+    }
+    if expiring.last_trade_date_or_contract_month >= target.last_trade_date_or_contract_month {
+        return Err(format!(
+            "The target {} must expire after {}",
+            target.last_trade_date_or_contract_month, expiring.last_trade_date_or_contract_month
+        ));
+    }
+    if position == 0.0 {
+        return Err("There is no position to roll".to_string());
+    }
+
+    let combo = ComboBuilder::new(&expiring.exchange)
+        .leg(expiring, Action::Sell, 1.0)
+        .leg(target, Action::Buy, 1.0)
+        .build()?;
+    let action = if position > 0.0 {
+        Action::Buy
+    } else {
+        Action::Sell
+    };
+    let order = match price {
+        RollPrice::Market => {
+            Order::combo_market_order(account, &action.to_string(), position.abs(), false)
+        }
+        RollPrice::Limit(limit_price) => Order::combo_limit_order(
+            account,
+            &action.to_string(),
+            position.abs(),
+            *limit_price,
+            false,
+        ),
+    };
+    Ok(FuturesRoll { combo, order })
+}
@@ -0,0 +1,134 @@
+//! Checks and conversions for IDEALPRO FX and CFD orders
+//!
+//! A currency pair like EUR.USD has the base currency EUR as its `symbol`
+//! and the quote currency USD as its `currency`. The `total_quantity` of an
+//! FX order is in the base currency, its `cash_qty` in the quote currency.
+//! Rates are the price of the pair, i.e. quote currency per unit of base
+//! currency.
+use crate::core::common::UNSET_DOUBLE;
+use crate::core::contract::{Contract, Exchange, SecType};
+use crate::core::order::Order;
+
+/// Orders on IDEALPRO below this value in USD are odd lots, which are
+/// routed to a different venue and filled at worse prices
+pub const IDEALPRO_MINIMUM_USD: f64 = 25_000.0;
+
+/// The time in force of FX orders; empty means DAY
+pub const FX_TIFS: [&str; 4] = ["DAY", "GTC", "IOC", "FOK"];
+
+/// The time in force of CFD orders; empty means DAY
+pub const CFD_TIFS: [&str; 4] = ["DAY", "GTC", "GTD", "IOC"];
+
+//==================================================================================================
+/// The amount in the quote currency of `quantity` units of the base
+/// currency
+pub fn base_to_quote(quantity: f64, rate: f64) -> f64 {
+    quantity * rate
+}
+
+//==================================================================================================
+/// The units of the base currency worth `amount` in the quote currency
+pub fn quote_to_base(amount: f64, rate: f64) -> f64 {
+    amount / rate
+}
+
+//==================================================================================================
+/// The cash quantity of an order, if it is set
+pub fn cash_quantity(order: &Order) -> Option<f64> {
+    if order.cash_qty != UNSET_DOUBLE && order.cash_qty > 0.0 {
+        Some(order.cash_qty)
+    } else {
+        None
+    }
+}
+
+//==================================================================================================
+/// The quantity of an FX order in the base currency, converting a cash
+/// quantity at `rate`
+pub fn base_quantity(order: &Order, rate: f64) -> f64 {
+    match cash_quantity(order) {
+        Some(cash_qty) => quote_to_base(cash_qty, rate),
+        None => order.total_quantity,
+    }
+}
+
+//==================================================================================================
+fn check_tif(order: &Order, tifs: &[&str]) -> Result<(), String> {
+    if order.tif.is_empty() || tifs.contains(&order.tif.to_uppercase().as_str()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "The time in force {} is not one of {}",
+            order.tif,
+            tifs.join(", ")
+        ))
+    }
+}
+
+//==================================================================================================
+/// Checks an FX order before it is placed: the contract must be a currency
+/// pair, the order must have either a quantity or a cash quantity, the
+/// latter only on market and limit orders, and a time in force of
+/// `FX_TIFS`. Orders on IDEALPRO must be worth `IDEALPRO_MINIMUM_USD`.
+///
+/// # Arguments
+/// * rate - the current rate of the pair, to convert cash quantities
+/// * usd_per_base - the value of one unit of the base currency in USD, to
+///   check the IDEALPRO minimum; 1 if the base currency is USD
+pub fn validate_fx_order(
+    contract: &Contract,
+    order: &Order,
+    rate: f64,
+    usd_per_base: f64,
+) -> Result<(), String> {
+    if contract.sec_type != SecType::Forex {
+        return Err(format!("{} is not a currency pair", contract.symbol));
+    }
+    check_tif(order, &FX_TIFS)?;
+    match cash_quantity(order) {
+        Some(_) if order.total_quantity > 0.0 => {
+            return Err("An order has either a quantity or a cash quantity".to_string());
+        }
+        Some(_) if order.order_type != "MKT" && order.order_type != "LMT" => {
+            return Err(format!(
+                "Cash quantities are not supported for {} orders",
+                order.order_type
+            ));
+        }
+        None if order.total_quantity <= 0.0 => {
+            return Err("The order has no quantity".to_string());
+        }
+        _ => {}
+    }
+    if contract.exchange == Exchange::IDEALPRO {
+        let usd = base_quantity(order, rate) * usd_per_base;
+        if usd < IDEALPRO_MINIMUM_USD {
+            return Err(format!(
+                "The order is worth {:.2} USD, below the IDEALPRO minimum of {} USD",
+                usd, IDEALPRO_MINIMUM_USD
+            ));
+        }
+    }
+    Ok(())
+}
+
+//==================================================================================================
+/// Checks a CFD order before it is placed: the contract must be a CFD, the
+/// order must have a whole positive quantity, no cash quantity and a time in
+/// force of `CFD_TIFS`.
+pub fn validate_cfd_order(contract: &Contract, order: &Order) -> Result<(), String> {
+    if contract.sec_type != SecType::Cfd {
+        return Err(format!("{} is not a CFD", contract.symbol));
+    }
+    check_tif(order, &CFD_TIFS)?;
+    if cash_quantity(order).is_some() {
+        return Err("CFD orders do not support cash quantities".to_string());
+    }
+    if order.total_quantity <= 0.0 || order.total_quantity.fract() != 0.0 {
+        return Err(format!(
+            "The quantity {} is not a whole number of CFDs",
+            order.total_quantity
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,284 @@
+//! Hedging of the FX exposure of cash balances
+//!
+//! `FxHedgeMonitor` watches the per currency `CashBalance` and
+//! `ExchangeRate` values of the account updates. When the balance of a
+//! currency other than the base currency of the account is worth more than
+//! the threshold in the base currency, it returns the IDEALPRO market order
+//! that exchanges the balance into the base currency. The order is checked
+//! with `fx::validate_fx_order`, so balances worth less than the IDEALPRO
+//! minimum are not hedged.
+//!
+//! A currency is hedged once per breach: no further order is returned for
+//! it until its exposure fell back within the threshold, e.g. after the
+//! hedge filled. `place_fx_hedge` places a hedge unless the monitor is a
+//! dry run, in which case the hedge is only logged.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Mutex;
+
+use log::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::account_updates::{AccountUpdateEvent, AccountValue, AccountValueKey};
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::contract::Contract;
+use crate::core::currency::{fx_contract, fx_pair};
+use crate::core::errors::IbkrError;
+use crate::core::fx::validate_fx_order;
+use crate::core::order::Order;
+use crate::core::wrapper::Wrapper;
+
+/// The currency of the per currency values summed up over all currencies
+const BASE: &str = "BASE";
+
+//==================================================================================================
+/// The cash balance of an account in a currency other than its base
+/// currency
+///
+/// balance - the balance in `currency`, negative if it is owed
+/// exposure - the balance in the base currency
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FxExposure {
+    pub account: String,
+    pub currency: String,
+    pub balance: f64,
+    pub exposure: f64,
+}
+
+impl fmt::Display for FxExposure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, currency: {}, balance: {}, exposure: {}",
+            self.account, self.currency, self.balance, self.exposure
+        )
+    }
+}
+
+//==================================================================================================
+/// The order that hedges an exposure
+///
+/// order - the order, with its `order_id` set once it has been placed
+/// dry_run - whether the order is only to be logged
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FxHedge {
+    pub exposure: FxExposure,
+    pub contract: Contract,
+    pub order: Order,
+    pub dry_run: bool,
+}
+
+impl fmt::Display for FxHedge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, order_id: {}, {} {} {}.{}{}",
+            self.exposure,
+            self.order.order_id,
+            self.order.action,
+            self.order.total_quantity,
+            self.contract.symbol,
+            self.contract.currency,
+            if self.dry_run { " (dry run)" } else { "" }
+        )
+    }
+}
+
+//==================================================================================================
+/// Watches the cash balances of accounts and hedges the ones in foreign
+/// currencies that exceed a threshold
+#[derive(Clone, Debug)]
+pub struct FxHedgeMonitor {
+    base_currency: String,
+    threshold: f64,
+    currencies: HashSet<String>,
+    dry_run: bool,
+    balances: HashMap<(String, String), f64>,
+    rates: HashMap<(String, String), f64>,
+    hedged: HashSet<(String, String)>,
+}
+
+impl FxHedgeMonitor {
+    /// A monitor of accounts with the base currency `base_currency` that
+    /// hedges balances worth more than `threshold` in it
+    pub fn new(base_currency: &str, threshold: f64) -> Self {
+        FxHedgeMonitor {
+            base_currency: base_currency.to_uppercase(),
+            threshold,
+            currencies: HashSet::new(),
+            dry_run: false,
+            balances: HashMap::new(),
+            rates: HashMap::new(),
+            hedged: HashSet::new(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Hedges only the balances in `currency`; by default all foreign
+    /// currencies are hedged
+    pub fn currency(mut self, currency: &str) -> Self {
+        self.currencies.insert(currency.to_uppercase());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only logs the hedges instead of placing them
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The exposure of an account in a currency, if its balance and the rate
+    /// into the base currency have been received
+    pub fn exposure(&self, account: &str, currency: &str) -> Option<FxExposure> {
+        let key = (account.to_string(), currency.to_string());
+        let balance = *self.balances.get(&key)?;
+        let rate = *self.rates.get(&key)?;
+        Some(FxExposure {
+            account: account.to_string(),
+            currency: currency.to_string(),
+            balance,
+            exposure: balance * rate,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an account value and returns the hedge of its currency, if
+    /// its exposure exceeds the threshold for the first time
+    pub fn apply_value(&mut self, account: &str, value: &AccountValue) -> Option<FxHedge> {
+        let currency = value.currency.to_uppercase();
+        if currency == BASE || currency == self.base_currency || currency.is_empty() {
+            return None;
+        }
+        let number = value.value?;
+        let key = (account.to_string(), currency.clone());
+        match value.key {
+            AccountValueKey::CashBalance => self.balances.insert(key.clone(), number),
+            AccountValueKey::ExchangeRate => self.rates.insert(key.clone(), number),
+            _ => return None,
+        };
+        if !self.currencies.is_empty() && !self.currencies.contains(&currency) {
+            return None;
+        }
+
+        let exposure = self.exposure(account, &currency)?;
+        if exposure.exposure.abs() <= self.threshold {
+            self.hedged.remove(&key);
+            return None;
+        }
+        if !self.hedged.insert(key) {
+            return None;
+        }
+        match self.hedge(exposure) {
+            Ok(hedge) => Some(hedge),
+            Err(message) => {
+                warn!(
+                    "Cannot hedge the {} balance of {}: {}",
+                    currency, account, message
+                );
+                None
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an event of `EClient::stream_account_updates`
+    pub fn apply(&mut self, event: &AccountUpdateEvent) -> Option<FxHedge> {
+        match event {
+            AccountUpdateEvent::Value { account, value } => self.apply_value(account, value),
+            _ => None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The order that exchanges the balance of an exposure into the base
+    /// currency, in whole units of the base currency of the pair
+    pub fn hedge(&self, exposure: FxExposure) -> Result<FxHedge, String> {
+        let (base, quote) = fx_pair(&exposure.currency, &self.base_currency);
+        let contract = fx_contract(&base, &quote);
+        // Selling a long balance, or buying the base currency of the
+        // account with it if that is the base currency of the pair
+        let (quantity, sell) = if base == exposure.currency {
+            (exposure.balance.abs(), exposure.balance > 0.0)
+        } else {
+            (exposure.exposure.abs(), exposure.balance < 0.0)
+        };
+        let order = Order::market_order(
+            &exposure.account,
+            if sell { "SELL" } else { "BUY" },
+            quantity.round(),
+        );
+
+        let rate_into_base = |currency: &str| {
+            if currency == self.base_currency {
+                return Ok(1.0);
+            }
+            self.rates
+                .get(&(exposure.account.clone(), currency.to_string()))
+                .copied()
+                .ok_or_else(|| format!("The exchange rate of {} is not known.", currency))
+        };
+        let rate = rate_into_base(&base)? / rate_into_base(&quote)?;
+        let usd_per_base = rate_into_base(&base)? / rate_into_base("USD")?;
+        validate_fx_order(&contract, &order, rate, usd_per_base)?;
+        Ok(FxHedge {
+            exposure,
+            contract,
+            order,
+            dry_run: self.dry_run,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Hedges a currency again on its next update above the threshold, e.g.
+    /// after its hedge was rejected
+    pub fn rearm(&mut self, account: &str, currency: &str) {
+        self.hedged
+            .remove(&(account.to_string(), currency.to_uppercase()));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forgets the values received and the hedges, e.g. on reconnect
+    pub fn clear(&mut self) {
+        self.balances.clear();
+        self.rates.clear();
+        self.hedged.clear();
+    }
+}
+
+//==================================================================================================
+/// Places a hedge, unless it is a dry run. Returns whether it was placed.
+///
+/// # Arguments
+/// * next_order_id - the next valid order id, advanced past the id used
+pub fn place_fx_hedge<T>(
+    client: &Mutex<EClient<T>>,
+    hedge: &mut FxHedge,
+    next_order_id: &mut i32,
+) -> Result<bool, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    if hedge.dry_run {
+        info!("FX hedge: {}", hedge);
+        return Ok(false);
+    }
+    hedge.order.order_id = *next_order_id;
+    client.lock().expect(POISONED_MUTEX).place_order(
+        hedge.order.order_id,
+        &hedge.contract,
+        &hedge.order,
+    )?;
+    *next_order_id += 1;
+    info!("FX hedge: {}", hedge);
+    Ok(true)
+}
@@ -0,0 +1,778 @@
+//! Stream handles and helpers for historical data requests
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{
+    BarData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, WhatToShow,
+};
+use crate::core::error_codes::HISTORICAL_DATA_ERROR;
+use crate::core::errors::{IbkrError, ServerError};
+use crate::core::server_versions::MIN_SERVER_VER_HISTORICAL_TICKS;
+use crate::core::subscription::Subscription;
+
+/// The maximum number of ticks returned by a historical ticks request
+pub const MAX_HISTORICAL_TICKS: i32 = 1000;
+
+/// Format of the UTC times of a historical ticks request
+pub const HISTORICAL_TICKS_TIME_FORMAT: &str = "%Y%m%d-%H:%M:%S";
+
+/// Format of the dates of intraday bars requested with `format_date` 1
+pub const BAR_TIME_FORMAT: &str = "%Y%m%d %H:%M:%S";
+
+/// Format of the dates of daily and longer bars
+pub const BAR_DATE_FORMAT: &str = "%Y%m%d";
+
+/// Format of the UTC end times of historical data requests
+pub const END_DATE_TIME_UTC_FORMAT: &str = "%Y%m%d-%H:%M:%S";
+
+/// Format of the end times of historical data requests, followed by their
+/// time zone
+pub const END_DATE_TIME_FORMAT: &str = "%Y%m%d %H:%M:%S";
+
+/// Format of the times of a `HistoricalSchedule` as sent by TWS
+pub const SCHEDULE_TIME_FORMAT: &str = "%Y%m%d-%H:%M:%S";
+
+/// Format of the reference dates of a `HistoricalSchedule` as sent by TWS
+pub const SCHEDULE_DATE_FORMAT: &str = "%Y%m%d";
+
+//==================================================================================================
+/// An event of a historical data request as delivered to a
+/// `HistoricalDataStream`
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HistoricalDataEvent {
+    /// A bar of the requested range
+    Bar(BarData),
+    /// All bars of the requested range have been sent
+    End { start: String, end: String },
+    /// An update of the latest bar of a `keep_up_to_date` request
+    Update(BarData),
+    /// TWS rejected or aborted the request
+    Error { code: i32, message: String },
+}
+
+impl fmt::Display for HistoricalDataEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HistoricalDataEvent::Bar(bar) => write!(f, "Bar({})", bar),
+            HistoricalDataEvent::End { start, end } => {
+                write!(f, "End(start: {}, end: {})", start, end)
+            }
+            HistoricalDataEvent::Update(bar) => write!(f, "Update({})", bar),
+            HistoricalDataEvent::Error { code, message } => {
+                write!(f, "Error(code: {}, message: {})", code, message)
+            }
+        }
+    }
+}
+
+//==================================================================================================
+/// Parses the dates of the bars of a historical data request into times
+///
+/// format_date - the `format_date` the request was made with. With 1
+///               intraday bars are dated `yyyyMMdd HH:mm:ss`, optionally
+///               followed by a time zone, with 2 in seconds since the epoch.
+///               Daily and longer bars are dated `yyyyMMdd` with both.
+/// time_zone - the time zone of dates without one, i.e. the time zone set in
+///             TWS. Times since the epoch are converted to it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BarTimeParser {
+    pub format_date: i32,
+    pub time_zone: Tz,
+}
+
+impl BarTimeParser {
+    pub fn new(format_date: i32, time_zone: Tz) -> Self {
+        BarTimeParser {
+            format_date,
+            time_zone,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The time of a bar date. Daily and longer bars are dated at midnight.
+    /// `None` if the date does not match the format of the request.
+    pub fn parse(&self, date: &str) -> Option<DateTime<Tz>> {
+        let date = date.trim();
+        if date.len() == 8 {
+            let date = NaiveDate::parse_from_str(date, BAR_DATE_FORMAT).ok()?;
+            return local_time(&date.and_hms_opt(0, 0, 0)?, &self.time_zone);
+        }
+        if self.format_date == 2 {
+            return self.time_zone.timestamp_opt(date.parse().ok()?, 0).single();
+        }
+
+        let mut parts = date.split_whitespace();
+        let date_time = format!("{} {}", parts.next()?, parts.next()?);
+        let date_time = NaiveDateTime::parse_from_str(&date_time, BAR_TIME_FORMAT).ok()?;
+        let time_zone = match parts.next() {
+            Some(time_zone) => time_zone.parse().ok()?,
+            None => self.time_zone,
+        };
+        local_time(&date_time, &time_zone)
+    }
+}
+
+impl Default for BarTimeParser {
+    fn default() -> Self {
+        BarTimeParser::new(1, Tz::UTC)
+    }
+}
+
+impl fmt::Display for BarTimeParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "format_date: {}, time_zone: {}",
+            self.format_date, self.time_zone
+        )
+    }
+}
+
+//==================================================================================================
+/// How historical data requests that failed with a pacing violation or a
+/// timeout of the historical data farm are sent again, see
+/// `EClient::set_historical_retry_policy`
+///
+/// max_attempts - how often a request is sent again before the error is
+///                reported; 0 disables retrying
+/// initial_delay - the delay before the first retry
+/// multiplier - the factor the delay grows by with every retry
+/// max_delay - the longest delay
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HistoricalRetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+}
+
+impl HistoricalRetryPolicy {
+    pub fn new(
+        max_attempts: u32,
+        initial_delay: Duration,
+        multiplier: f64,
+        max_delay: Duration,
+    ) -> Self {
+        HistoricalRetryPolicy {
+            max_attempts,
+            initial_delay,
+            multiplier,
+            max_delay,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A policy that reports every error
+    pub fn none() -> Self {
+        HistoricalRetryPolicy::new(0, Duration::ZERO, 1.0, Duration::ZERO)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 0
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The delay before retry `attempt`, counting from 0
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let delay = self.initial_delay.as_secs_f64() * self.multiplier.powi(attempt as i32);
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+}
+
+impl Default for HistoricalRetryPolicy {
+    /// Three retries after 15, 30 and 60 seconds. Identical requests within
+    /// 15 seconds are a pacing violation themselves.
+    fn default() -> Self {
+        HistoricalRetryPolicy::new(3, Duration::from_secs(15), 2.0, Duration::from_secs(120))
+    }
+}
+
+impl fmt::Display for HistoricalRetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max_attempts: {}, initial_delay: {:?}, multiplier: {}, max_delay: {:?}",
+            self.max_attempts, self.initial_delay, self.multiplier, self.max_delay
+        )
+    }
+}
+
+//==================================================================================================
+/// Whether an error of a historical data request is a pacing violation or a
+/// timeout of the historical data farm, i.e. the request may succeed when
+/// sent again later
+pub fn is_retryable_historical_error(code: i32, message: &str) -> bool {
+    let message = message.to_lowercase();
+    code == HISTORICAL_DATA_ERROR
+        && (message.contains("pacing violation")
+            || message.contains("timeout")
+            || message.contains("timed out"))
+}
+
+//==================================================================================================
+/// Receiving end of `EClient::stream_historical_data`.
+///
+/// Iterating yields the bars of the requested range and completes on the end
+/// marker. If TWS reports an error for the request, the error is yielded as
+/// the last item. Requests made with `keep_up_to_date` continue to receive
+/// bar updates after the end marker, see `next_update`.
+///
+/// Dropping the handle cancels the request unless it has already completed.
+pub struct HistoricalDataStream {
+    subscription: Subscription<HistoricalDataEvent>,
+    keep_up_to_date: bool,
+    active: Arc<AtomicBool>,
+    range: Option<(String, String)>,
+    finished: bool,
+}
+
+impl HistoricalDataStream {
+    pub(crate) fn new(
+        subscription: Subscription<HistoricalDataEvent>,
+        keep_up_to_date: bool,
+        active: Arc<AtomicBool>,
+    ) -> Self {
+        HistoricalDataStream {
+            subscription,
+            keep_up_to_date,
+            active,
+            range: None,
+            finished: false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The request id the data was requested with
+    pub fn request_id(&self) -> i32 {
+        self.subscription.request_id()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The start and end of the returned range as reported with the end
+    /// marker, once it has been received
+    pub fn range(&self) -> Option<(&str, &str)> {
+        self.range
+            .as_ref()
+            .map(|(start, end)| (start.as_str(), end.as_str()))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the end marker or an error has been received
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Blocks until all bars of the requested range have been received
+    pub fn collect_bars(&mut self) -> Result<Vec<BarData>, IbkrError> {
+        self.by_ref().collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as `collect_bars` but fails with `RecvTimeoutError::Timeout` if
+    /// the end marker has not arrived within `timeout`
+    pub fn collect_bars_timeout(&mut self, timeout: Duration) -> Result<Vec<BarData>, IbkrError> {
+        let deadline = Instant::now() + timeout;
+        let mut bars = vec![];
+        while self.range.is_none() && !self.finished {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.next_event(Some(remaining)) {
+                Some(HistoricalDataEvent::Bar(bar)) => bars.push(bar),
+                Some(HistoricalDataEvent::Error { code, message }) => {
+                    return Err(self.error(code, message))
+                }
+                Some(_) => (),
+                None => return Err(IbkrError::RecvTimeoutError(RecvTimeoutError::Timeout)),
+            }
+        }
+        Ok(bars)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Blocks until the next update of a `keep_up_to_date` request arrives.
+    /// Bars of the initial range that have not been consumed yet are
+    /// skipped. Returns `None` once the request has ended.
+    pub fn next_update(&mut self) -> Option<Result<BarData, IbkrError>> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            match self.next_event(None)? {
+                HistoricalDataEvent::Update(bar) => return Some(Ok(bar)),
+                HistoricalDataEvent::Error { code, message } => {
+                    return Some(Err(self.error(code, message)))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Same as `next_update` but gives up after `timeout`
+    pub fn next_update_timeout(&mut self, timeout: Duration) -> Option<Result<BarData, IbkrError>> {
+        if self.finished {
+            return None;
+        }
+        loop {
+            match self.next_event(Some(timeout))? {
+                HistoricalDataEvent::Update(bar) => return Some(Ok(bar)),
+                HistoricalDataEvent::Error { code, message } => {
+                    return Some(Err(self.error(code, message)))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn next_event(&mut self, timeout: Option<Duration>) -> Option<HistoricalDataEvent> {
+        let event = match timeout {
+            Some(timeout) => self.subscription.recv_timeout(timeout).ok()?,
+            None => match self.subscription.recv() {
+                Ok(event) => event,
+                Err(_) => {
+                    // The client disconnected
+                    self.finish();
+                    return None;
+                }
+            },
+        };
+        match &event {
+            HistoricalDataEvent::End { start, end } => {
+                self.range = Some((start.clone(), end.clone()));
+                if !self.keep_up_to_date {
+                    self.finish();
+                }
+            }
+            HistoricalDataEvent::Error { .. } => self.finish(),
+            _ => (),
+        }
+        Some(event)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn finish(&mut self) {
+        self.finished = true;
+        self.active.store(false, Ordering::Release);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn error(&self, code: i32, message: String) -> IbkrError {
+        IbkrError::Server(ServerError::new(self.request_id(), code, &message))
+    }
+}
+
+impl Iterator for HistoricalDataStream {
+    type Item = Result<BarData, IbkrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.range.is_some() || self.finished {
+            return None;
+        }
+        loop {
+            match self.next_event(None)? {
+                HistoricalDataEvent::Bar(bar) => return Some(Ok(bar)),
+                HistoricalDataEvent::End { .. } => return None,
+                HistoricalDataEvent::Error { code, message } => {
+                    return Some(Err(self.error(code, message)))
+                }
+                HistoricalDataEvent::Update(_) => (),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for HistoricalDataStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "HistoricalDataStream {{ request_id: {}, keep_up_to_date: {}, finished: {} }}",
+            self.request_id(),
+            self.keep_up_to_date,
+            self.finished
+        )
+    }
+}
+
+//==================================================================================================
+/// The ticks of one historical ticks request. The kind depends on the
+/// `what_to_show` of the request.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HistoricalTicks {
+    /// `TRADES` ticks, including exchange and special conditions
+    Trades(Vec<HistoricalTickLast>),
+    /// `BID_ASK` ticks
+    BidAsk(Vec<HistoricalTickBidAsk>),
+    /// `MIDPOINT` ticks
+    Midpoint(Vec<HistoricalTick>),
+}
+
+impl HistoricalTicks {
+    /// No ticks of the kind that matches `what_to_show`
+    pub fn empty(what_to_show: &WhatToShow) -> Self {
+        match what_to_show {
+            WhatToShow::Trades => HistoricalTicks::Trades(vec![]),
+            WhatToShow::BidAsk => HistoricalTicks::BidAsk(vec![]),
+            _ => HistoricalTicks::Midpoint(vec![]),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        match self {
+            HistoricalTicks::Trades(ticks) => ticks.len(),
+            HistoricalTicks::BidAsk(ticks) => ticks.len(),
+            HistoricalTicks::Midpoint(ticks) => ticks.len(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The time of the last tick in seconds since the epoch
+    pub fn last_time(&self) -> Option<i32> {
+        match self {
+            HistoricalTicks::Trades(ticks) => ticks.last().map(|tick| tick.time),
+            HistoricalTicks::BidAsk(ticks) => ticks.last().map(|tick| tick.time),
+            HistoricalTicks::Midpoint(ticks) => ticks.last().map(|tick| tick.time),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Removes the ticks after `time`, given in seconds since the epoch
+    pub fn truncate_after(&mut self, time: i32) {
+        match self {
+            HistoricalTicks::Trades(ticks) => ticks.retain(|tick| tick.time <= time),
+            HistoricalTicks::BidAsk(ticks) => ticks.retain(|tick| tick.time <= time),
+            HistoricalTicks::Midpoint(ticks) => ticks.retain(|tick| tick.time <= time),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Appends the ticks of another page of the same kind. Ticks of a
+    /// different kind are ignored.
+    pub fn append(&mut self, other: HistoricalTicks) {
+        match (self, other) {
+            (HistoricalTicks::Trades(ticks), HistoricalTicks::Trades(mut other)) => {
+                ticks.append(&mut other)
+            }
+            (HistoricalTicks::BidAsk(ticks), HistoricalTicks::BidAsk(mut other)) => {
+                ticks.append(&mut other)
+            }
+            (HistoricalTicks::Midpoint(ticks), HistoricalTicks::Midpoint(mut other)) => {
+                ticks.append(&mut other)
+            }
+            _ => (),
+        }
+    }
+}
+
+impl fmt::Display for HistoricalTicks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (kind, ticks): (&str, Vec<String>) = match self {
+            HistoricalTicks::Trades(ticks) => (
+                "Trades",
+                ticks.iter().map(|tick| tick.to_string()).collect(),
+            ),
+            HistoricalTicks::BidAsk(ticks) => (
+                "BidAsk",
+                ticks.iter().map(|tick| tick.to_string()).collect(),
+            ),
+            HistoricalTicks::Midpoint(ticks) => (
+                "Midpoint",
+                ticks.iter().map(|tick| tick.to_string()).collect(),
+            ),
+        };
+        write!(f, "{}([{}])", kind, ticks.join("; "))
+    }
+}
+
+//==================================================================================================
+/// An event of a historical ticks request as delivered to the channel used
+/// by `EClient::fetch_historical_ticks`
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HistoricalTicksEvent {
+    Ticks(HistoricalTicks),
+    /// TWS rejected the request
+    Error {
+        code: i32,
+        message: String,
+    },
+}
+
+//==================================================================================================
+/// A range of historical ticks. TWS returns at most 1000 ticks per request
+/// (plus all further ticks of the last second), so longer ranges are fetched
+/// page by page, see `next_page`.
+///
+/// what_to_show - one of `WhatToShow::Trades`, `WhatToShow::BidAsk` or
+///                `WhatToShow::Midpoint`
+/// start_date_time - the time of the first tick
+/// end_date_time - the time of the last tick; if `None`, a single page is
+///                 fetched
+/// number_of_ticks - the number of ticks per request, at most 1000
+/// regular_trading_hours_only - only return ticks within the regular trading
+///                              hours
+/// ignore_size - skip `BID_ASK` ticks that only change the size
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HistoricalTicksQuery {
+    pub what_to_show: WhatToShow,
+    pub start_date_time: DateTime<Utc>,
+    pub end_date_time: Option<DateTime<Utc>>,
+    pub number_of_ticks: i32,
+    pub regular_trading_hours_only: bool,
+    pub ignore_size: bool,
+}
+
+impl HistoricalTicksQuery {
+    pub fn new(
+        what_to_show: WhatToShow,
+        start_date_time: DateTime<Utc>,
+        end_date_time: Option<DateTime<Utc>>,
+    ) -> Self {
+        HistoricalTicksQuery {
+            what_to_show,
+            start_date_time,
+            end_date_time,
+            number_of_ticks: MAX_HISTORICAL_TICKS,
+            regular_trading_hours_only: false,
+            ignore_size: false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The start time in the form sent to TWS
+    pub fn start_date_time_param(&self) -> String {
+        self.start_date_time
+            .format(HISTORICAL_TICKS_TIME_FORMAT)
+            .to_string()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Removes the ticks after the end of the range from a page and returns
+    /// the query for the following page, if the range is not complete yet
+    pub fn next_page(&self, page: &mut HistoricalTicks) -> Option<Self> {
+        let end = self.end_date_time?.timestamp();
+        let last_time = page.last_time()? as i64;
+
+        if last_time > end {
+            page.truncate_after(end as i32);
+            return None;
+        }
+        // A full page ends with all ticks of its last second
+        if (page.len() as i32) < self.number_of_ticks || last_time == end {
+            return None;
+        }
+        Some(HistoricalTicksQuery {
+            start_date_time: Utc.timestamp_opt(last_time + 1, 0).single()?,
+            ..self.clone()
+        })
+    }
+}
+
+//==================================================================================================
+/// A trading session of a `HistoricalSchedule`
+///
+/// start, end - the start and end of the session
+/// ref_date - the trading day the session belongs to, which can differ from
+///            the calendar date of its start, e.g. for futures sessions that
+///            start in the evening
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HistoricalSession {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub ref_date: NaiveDate,
+}
+
+impl HistoricalSession {
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>, ref_date: NaiveDate) -> Self {
+        HistoricalSession {
+            start,
+            end,
+            ref_date,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether `time` falls into the session; the end is exclusive
+    pub fn contains(&self, time: &DateTime<Utc>) -> bool {
+        self.start <= *time && *time < self.end
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn duration(&self) -> chrono::Duration {
+        self.end - self.start
+    }
+}
+
+impl fmt::Display for HistoricalSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "start: {}, end: {}, ref_date: {}",
+            self.start, self.end, self.ref_date
+        )
+    }
+}
+
+//==================================================================================================
+/// The trading sessions of a contract as returned by a historical data
+/// request with `WhatToShow::Schedule`, see
+/// `EClient::request_historical_schedule`
+///
+/// start_date_time, end_date_time - the range the schedule covers
+/// time_zone - the time zone of the exchange
+/// sessions - the sessions, ordered by their start
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HistoricalSchedule {
+    pub start_date_time: DateTime<Utc>,
+    pub end_date_time: DateTime<Utc>,
+    pub time_zone: Tz,
+    pub sessions: Vec<HistoricalSession>,
+}
+
+impl HistoricalSchedule {
+    /// Builds the schedule from the fields sent by TWS. The times are given
+    /// as `YYYYMMDD-hh:mm:ss` in the time zone of the schedule, the
+    /// reference dates as `YYYYMMDD`. Returns `None` if any field can not be
+    /// parsed.
+    pub fn parse(
+        start_date_time: &str,
+        end_date_time: &str,
+        time_zone: &str,
+        sessions: &[(String, String, String)],
+    ) -> Option<Self> {
+        let time_zone: Tz = time_zone.trim().parse().ok()?;
+        let mut schedule = HistoricalSchedule {
+            start_date_time: parse_schedule_time(start_date_time, &time_zone)?,
+            end_date_time: parse_schedule_time(end_date_time, &time_zone)?,
+            time_zone,
+            sessions: Vec::with_capacity(sessions.len()),
+        };
+        for (start, end, ref_date) in sessions {
+            schedule.sessions.push(HistoricalSession {
+                start: parse_schedule_time(start, &time_zone)?,
+                end: parse_schedule_time(end, &time_zone)?,
+                ref_date: NaiveDate::parse_from_str(ref_date.trim(), SCHEDULE_DATE_FORMAT).ok()?,
+            });
+        }
+        schedule.sessions.sort_by_key(|session| session.start);
+        Some(schedule)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The session `time` falls into, if any
+    pub fn session_at(&self, time: &DateTime<Utc>) -> Option<&HistoricalSession> {
+        self.sessions.iter().find(|session| session.contains(time))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The first session that starts after `time`
+    pub fn next_session(&self, time: &DateTime<Utc>) -> Option<&HistoricalSession> {
+        self.sessions.iter().find(|session| session.start > *time)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sessions of a trading day
+    pub fn sessions_for(&self, ref_date: NaiveDate) -> Vec<&HistoricalSession> {
+        self.sessions
+            .iter()
+            .filter(|session| session.ref_date == ref_date)
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The trading days of the schedule in ascending order
+    pub fn trading_days(&self) -> Vec<NaiveDate> {
+        let mut days: Vec<NaiveDate> = self
+            .sessions
+            .iter()
+            .map(|session| session.ref_date)
+            .collect();
+        days.sort_unstable();
+        days.dedup();
+        days
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A time converted to the time zone of the schedule
+    pub fn local_time(&self, time: &DateTime<Utc>) -> DateTime<Tz> {
+        time.with_timezone(&self.time_zone)
+    }
+}
+
+impl fmt::Display for HistoricalSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sessions: Vec<String> = self
+            .sessions
+            .iter()
+            .map(|session| session.to_string())
+            .collect();
+        write!(
+            f,
+            "start_date_time: {}, end_date_time: {}, time_zone: {}, sessions: [{}]",
+            self.start_date_time,
+            self.end_date_time,
+            self.time_zone,
+            sessions.join("; ")
+        )
+    }
+}
+
+//==================================================================================================
+/// Formats the end time of a historical data, head time stamp or schedule
+/// request, see `EClient::format_end_date_time`. Times in UTC are sent as
+/// `yyyyMMdd-HH:mm:ss`, others as `yyyyMMdd HH:mm:ss` followed by the name
+/// of their time zone, e.g. `20230103 16:00:00 US/Eastern`. Servers older
+/// than `MIN_SERVER_VER_HISTORICAL_TICKS` only know the latter form and get
+/// UTC times as `yyyyMMdd HH:mm:ss GMT`.
+pub fn format_end_date_time(time: &DateTime<Tz>, server_version: i32) -> String {
+    let utc = matches!(
+        time.timezone(),
+        Tz::UTC | Tz::Etc__UTC | Tz::GMT | Tz::Etc__GMT | Tz::Etc__UCT | Tz::UCT | Tz::Zulu
+    );
+    if utc && server_version >= MIN_SERVER_VER_HISTORICAL_TICKS {
+        time.format(END_DATE_TIME_UTC_FORMAT).to_string()
+    } else if utc {
+        format!("{} GMT", time.format(END_DATE_TIME_FORMAT))
+    } else {
+        format!(
+            "{} {}",
+            time.format(END_DATE_TIME_FORMAT),
+            time.timezone().name()
+        )
+    }
+}
+
+//==================================================================================================
+fn local_time(date_time: &NaiveDateTime, time_zone: &Tz) -> Option<DateTime<Tz>> {
+    // Times that fall into a daylight saving gap or overlap are resolved to
+    // the earlier offset
+    time_zone.from_local_datetime(date_time).earliest()
+}
+
+//==================================================================================================
+fn parse_schedule_time(time: &str, time_zone: &Tz) -> Option<DateTime<Utc>> {
+    let time = NaiveDateTime::parse_from_str(time.trim(), SCHEDULE_TIME_FORMAT).ok()?;
+    local_time(&time, time_zone).map(|time| time.with_timezone(&Utc))
+}
@@ -0,0 +1,498 @@
+//! Downloads long ranges of historical bars in chunks that comply with the
+//! historical data limitations of TWS
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+use log::*;
+
+#[cfg(feature = "serde")]
+use crate::core::bar_cache::{BarCache, BarCacheKey};
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::{BarData, BarSize, HistoricalDuration, WhatToShow};
+use crate::core::contract::Contract;
+use crate::core::error_codes::{
+    CONNECTIVITY_LOST, HISTORICAL_DATA_ERROR, HISTORICAL_DATA_QUERY_MESSAGE,
+    NO_HISTORICAL_DATA_QUERY, PROCESSING_ERROR,
+};
+use crate::core::errors::IbkrError;
+use crate::core::historical::format_end_date_time;
+use crate::core::wrapper::Wrapper;
+
+// Requests bar dates as seconds since the epoch
+const FORMAT_DATE_EPOCH: i32 = 2;
+
+//==================================================================================================
+/// One request of a `HistoricalDownloader`
+///
+/// start, end - the range covered by the request
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoricalChunk {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl HistoricalChunk {
+    /// The end time in the form sent to a server of `server_version`
+    pub fn end_date_time_param(&self, server_version: i32) -> String {
+        format_end_date_time(&self.end.with_timezone(&Tz::UTC), server_version)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The duration of the request for the chunk. Ranges shorter than a day
+    /// are given in seconds, longer ones in whole days. Chunks shorter than
+    /// a bar request one bar.
+    pub fn duration(&self, bar_size: &BarSize) -> HistoricalDuration {
+        let seconds = (self.end - self.start)
+            .num_seconds()
+            .max(bar_size.seconds())
+            .max(1);
+        if seconds < 86400 {
+            HistoricalDuration::Seconds(seconds as u32)
+        } else {
+            HistoricalDuration::Days(((seconds + 86399) / 86400) as u32)
+        }
+    }
+}
+
+impl fmt::Display for HistoricalChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "start: {}, end: {}", self.start, self.end)
+    }
+}
+
+//==================================================================================================
+/// Progress of a download, passed to the progress callback after each chunk
+///
+/// completed_chunks - the number of chunks downloaded so far
+/// total_chunks - the number of chunks of the download
+/// bars - the number of bars received so far, including duplicates
+/// chunk - the chunk that has just been downloaded
+#[derive(Clone, Debug)]
+pub struct DownloadProgress {
+    pub completed_chunks: usize,
+    pub total_chunks: usize,
+    pub bars: usize,
+    pub chunk: HistoricalChunk,
+}
+
+impl DownloadProgress {
+    /// The completed part of the download, from 0 to 1
+    pub fn fraction(&self) -> f64 {
+        if self.total_chunks == 0 {
+            1.0
+        } else {
+            self.completed_chunks as f64 / self.total_chunks as f64
+        }
+    }
+}
+
+impl fmt::Display for DownloadProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "completed_chunks: {}, total_chunks: {}, bars: {}, chunk: {}",
+            self.completed_chunks, self.total_chunks, self.bars, self.chunk
+        )
+    }
+}
+
+//==================================================================================================
+/// Keeps track of the historical data requests sent, so that no more than
+/// 60 requests are made within ten minutes and no more than five within two
+/// seconds
+#[derive(Debug, Default)]
+pub struct HistoricalPacer {
+    requests: VecDeque<Instant>,
+}
+
+impl HistoricalPacer {
+    const WINDOW: Duration = Duration::from_secs(600);
+    const MAX_REQUESTS_PER_WINDOW: usize = 60;
+    const BURST_WINDOW: Duration = Duration::from_secs(2);
+    const MAX_REQUESTS_PER_BURST: usize = 5;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How long to wait at `now` before the next request can be sent
+    pub fn delay(&self, now: Instant) -> Duration {
+        let mut delay = Duration::ZERO;
+        let in_window = self
+            .requests
+            .iter()
+            .filter(|time| now.duration_since(**time) < Self::WINDOW)
+            .count();
+        if in_window >= Self::MAX_REQUESTS_PER_WINDOW {
+            let oldest = self.requests[self.requests.len() - in_window];
+            delay = delay.max((oldest + Self::WINDOW).saturating_duration_since(now));
+        }
+
+        let in_burst = self
+            .requests
+            .iter()
+            .filter(|time| now.duration_since(**time) < Self::BURST_WINDOW)
+            .count();
+        if in_burst >= Self::MAX_REQUESTS_PER_BURST {
+            let oldest = self.requests[self.requests.len() - in_burst];
+            delay = delay.max((oldest + Self::BURST_WINDOW).saturating_duration_since(now));
+        }
+        delay
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records a request sent at `now`
+    pub fn record(&mut self, now: Instant) {
+        while matches!(self.requests.front(), Some(time) if now.duration_since(*time) >= Self::WINDOW)
+        {
+            self.requests.pop_front();
+        }
+        self.requests.push_back(now);
+    }
+}
+
+//==================================================================================================
+/// Downloads the bars of a range that is too long for a single historical
+/// data request.
+///
+/// The range is split into chunks no longer than TWS allows for the bar
+/// size. The chunks are requested one after the other under the pacing
+/// limits of TWS; chunks failing with a transient error, e.g. a pacing
+/// violation or a timeout, are retried. The bars of all chunks are returned
+/// as one series, ordered by time and without duplicates.
+#[derive(Debug)]
+pub struct HistoricalDownloader {
+    contract: Contract,
+    bar_size: BarSize,
+    what_to_show: WhatToShow,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    use_rth: bool,
+    max_retries: u32,
+    retry_delay: Duration,
+    timeout: Duration,
+    pacer: HistoricalPacer,
+}
+
+impl HistoricalDownloader {
+    pub fn new(
+        contract: Contract,
+        bar_size: BarSize,
+        what_to_show: WhatToShow,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Self {
+        HistoricalDownloader {
+            contract,
+            bar_size,
+            what_to_show,
+            start,
+            end,
+            use_rth: false,
+            max_retries: 3,
+            // Identical requests within 15 seconds are a pacing violation
+            retry_delay: Duration::from_secs(15),
+            timeout: Duration::from_secs(60),
+            pacer: HistoricalPacer::new(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Only download bars within the regular trading hours
+    pub fn use_rth(mut self, use_rth: bool) -> Self {
+        self.use_rth = use_rth;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How often a chunk is retried after a transient error. Defaults to 3.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How long to wait before a chunk is retried. Defaults to 15 seconds.
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How long to wait for the bars of a chunk. Defaults to 60 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The chunks the range is split into, ordered by time
+    pub fn chunks(&self) -> Vec<HistoricalChunk> {
+        self.chunks_between(self.start, self.end)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The number of request ids `download` may use: one for each attempt of
+    /// each chunk
+    pub fn request_ids(&self) -> i32 {
+        self.chunks().len() as i32 * (self.max_retries as i32 + 1)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Downloads all chunks, blocking until they have arrived. The client is
+    /// only locked while a request is sent, not while waiting for its bars.
+    ///
+    /// # Arguments
+    /// * client - a connected client
+    /// * first_request_id - the id of the first request; each request,
+    ///   including retries, uses the next id
+    /// * progress - called after each chunk
+    pub fn download<T, F>(
+        &mut self,
+        client: &Mutex<EClient<T>>,
+        first_request_id: i32,
+        mut progress: F,
+    ) -> Result<Vec<BarData>, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+        F: FnMut(&DownloadProgress),
+    {
+        let chunks = self.chunks();
+        let bars = self.download_chunks(client, first_request_id, &chunks, &mut progress)?;
+        Ok(merge_bars(bars, &self.start, &self.end))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Like `download`, but only requests the parts of the range before the
+    /// first and from the last bar stored in the cache. The last stored bar
+    /// is requested again, as it may have been incomplete. The downloaded
+    /// bars are added to the cache.
+    #[cfg(feature = "serde")]
+    pub fn download_cached<T, F>(
+        &mut self,
+        client: &Mutex<EClient<T>>,
+        cache: &BarCache,
+        first_request_id: i32,
+        mut progress: F,
+    ) -> Result<Vec<BarData>, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+        F: FnMut(&DownloadProgress),
+    {
+        let key = self.cache_key();
+        let chunks = match cache.range(&key)? {
+            Some((first, last)) => {
+                let mut chunks = self.chunks_between(self.start, first.min(self.end));
+                chunks.extend(self.chunks_between(last.max(self.start), self.end));
+                chunks
+            }
+            None => self.chunks(),
+        };
+        let bars = self.download_chunks(client, first_request_id, &chunks, &mut progress)?;
+        let bars = cache.store(&key, bars)?;
+        Ok(merge_bars(bars, &self.start, &self.end))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The key the bars of this download are stored under in a `BarCache`
+    #[cfg(feature = "serde")]
+    pub fn cache_key(&self) -> BarCacheKey {
+        BarCacheKey::new(
+            &self.contract,
+            &self.bar_size,
+            &self.what_to_show,
+            self.use_rth,
+        )
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn chunks_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<HistoricalChunk> {
+        let max_length = max_chunk_length(&self.bar_size);
+        let mut chunks = vec![];
+        let mut start = start;
+        while start < end {
+            let chunk_end = (start + max_length).min(end);
+            chunks.push(HistoricalChunk {
+                start,
+                end: chunk_end,
+            });
+            start = chunk_end;
+        }
+        chunks
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn download_chunks<T, F>(
+        &mut self,
+        client: &Mutex<EClient<T>>,
+        first_request_id: i32,
+        chunks: &[HistoricalChunk],
+        progress: &mut F,
+    ) -> Result<Vec<BarData>, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+        F: FnMut(&DownloadProgress),
+    {
+        let mut request_id = first_request_id;
+        let mut bars = vec![];
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut retries = 0;
+            loop {
+                thread::sleep(self.pacer.delay(Instant::now()));
+                self.pacer.record(Instant::now());
+
+                match self.download_chunk(client, request_id, chunk) {
+                    Ok(mut chunk_bars) => {
+                        bars.append(&mut chunk_bars);
+                        break;
+                    }
+                    Err(err) if is_no_data_error(&err) => break,
+                    Err(err) if retries < self.max_retries && is_transient_error(&err) => {
+                        warn!(
+                            "Retrying historical data chunk {} after error: {}",
+                            chunk, err
+                        );
+                        retries += 1;
+                        thread::sleep(self.retry_delay);
+                    }
+                    Err(err) => return Err(err),
+                }
+                request_id += 1;
+            }
+            request_id += 1;
+
+            progress(&DownloadProgress {
+                completed_chunks: index + 1,
+                total_chunks: chunks.len(),
+                bars: bars.len(),
+                chunk: chunk.clone(),
+            });
+        }
+        Ok(bars)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn download_chunk<T>(
+        &self,
+        client: &Mutex<EClient<T>>,
+        request_id: i32,
+        chunk: &HistoricalChunk,
+    ) -> Result<Vec<BarData>, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        let mut client = client.lock().expect(POISONED_MUTEX);
+        let end_date_time = chunk.end_date_time_param(client.server_version());
+        let mut stream = client.stream_historical_data(
+            request_id,
+            &self.contract,
+            end_date_time.as_str(),
+            chunk.duration(&self.bar_size),
+            self.bar_size.clone(),
+            self.what_to_show.clone(),
+            self.use_rth as i32,
+            FORMAT_DATE_EPOCH,
+            false,
+            &[],
+        )?;
+        drop(client);
+        stream.collect_bars_timeout(self.timeout)
+    }
+}
+
+//==================================================================================================
+/// The longest range TWS returns for a single request of a bar size
+fn max_chunk_length(bar_size: &BarSize) -> chrono::Duration {
+    match bar_size {
+        BarSize::_1Secs => chrono::Duration::seconds(1800),
+        BarSize::_5Secs => chrono::Duration::seconds(3600),
+        BarSize::_10Secs | BarSize::_15Secs => chrono::Duration::seconds(14400),
+        BarSize::_30Secs => chrono::Duration::seconds(28800),
+        BarSize::_1Min => chrono::Duration::days(1),
+        BarSize::_2Mins => chrono::Duration::days(2),
+        BarSize::_3Mins
+        | BarSize::_5Mins
+        | BarSize::_10Mins
+        | BarSize::_15Mins
+        | BarSize::_20Mins => chrono::Duration::weeks(1),
+        BarSize::_30Mins | BarSize::_1Hour | BarSize::_4Hours => chrono::Duration::days(30),
+        BarSize::_1Day | BarSize::_1Week | BarSize::_1Month => chrono::Duration::days(365),
+    }
+}
+
+//==================================================================================================
+/// TWS reports a range without any bars as an error
+fn is_no_data_error(err: &IbkrError) -> bool {
+    match err {
+        IbkrError::Server(err) => {
+            err.code == HISTORICAL_DATA_ERROR && err.message.contains("returned no data")
+        }
+        _ => false,
+    }
+}
+
+//==================================================================================================
+/// Whether a failed chunk is worth retrying: timeouts, pacing violations,
+/// cancelled queries and lost connectivity
+pub fn is_transient_error(err: &IbkrError) -> bool {
+    match err {
+        IbkrError::RecvTimeoutError(_) => true,
+        _ => matches!(
+            err.code(),
+            Some(
+                HISTORICAL_DATA_ERROR
+                    | HISTORICAL_DATA_QUERY_MESSAGE
+                    | PROCESSING_ERROR
+                    | NO_HISTORICAL_DATA_QUERY
+                    | CONNECTIVITY_LOST
+            )
+        ),
+    }
+}
+
+//==================================================================================================
+/// The time of a bar, see `BarData::time`. Bars without a time must have been
+/// requested with dates as seconds since the epoch; daily and longer bars
+/// are then taken to be dated at midnight UTC.
+pub(crate) fn bar_time(bar: &BarData) -> Option<DateTime<Utc>> {
+    if let Some(time) = &bar.time {
+        return Some(time.with_timezone(&Utc));
+    }
+    let date = bar.date.trim();
+    if date.len() == 8 {
+        let date = NaiveDate::parse_from_str(date, "%Y%m%d").ok()?;
+        return Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?));
+    }
+    Utc.timestamp_opt(date.parse().ok()?, 0).single()
+}
+
+//==================================================================================================
+/// Orders the bars of all chunks by time, removes duplicates and the bars
+/// outside of the range
+pub fn merge_bars(bars: Vec<BarData>, start: &DateTime<Utc>, end: &DateTime<Utc>) -> Vec<BarData> {
+    let start_day = start.date_naive();
+    let mut bars: Vec<(DateTime<Utc>, BarData)> = bars
+        .into_iter()
+        .filter_map(|bar| bar_time(&bar).map(|time| (time, bar)))
+        .filter(|(time, bar)| {
+            // Daily bars are dated at midnight of their day
+            let date = bar.date.trim();
+            let after_start = if date.len() == 8 {
+                NaiveDate::parse_from_str(date, "%Y%m%d").is_ok_and(|date| date >= start_day)
+            } else {
+                time >= start
+            };
+            after_start && time < end
+        })
+        .collect();
+    bars.sort_by_key(|(time, _)| *time);
+    bars.dedup_by_key(|(time, _)| *time);
+    bars.into_iter().map(|(_, bar)| bar).collect()
+}
@@ -0,0 +1,450 @@
+//! Technical indicators computed on streaming bars
+//!
+//! An `IndicatorPipeline` holds any number of `Indicator`s: the SMA, EMA,
+//! RSI and ATR of this module or indicators of your own implementing the
+//! trait. Each bar fed to the pipeline updates all of them and comes back
+//! with their values in an `IndicatorUpdate`, so signal code gets the bar
+//! and its indicators together.
+//!
+//! Real time bars of `EClient::stream_real_time_bars` are passed through a
+//! pipeline with `IndicatorStream`; bars of a `BarAggregator` or of
+//! historical data with `IndicatorPipeline::update_bars`. Indicators return
+//! None until they have seen enough bars.
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, TryRecvError};
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::{BarData, RealTimeBar};
+use crate::core::subscription::Subscription;
+
+//==================================================================================================
+/// The prices and volume of a bar, as indicators see it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Ohlcv {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+impl From<&RealTimeBar> for Ohlcv {
+    fn from(bar: &RealTimeBar) -> Self {
+        Ohlcv {
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+        }
+    }
+}
+
+impl From<&BarData> for Ohlcv {
+    fn from(bar: &BarData) -> Self {
+        Ohlcv {
+            open: bar.open,
+            high: bar.high,
+            low: bar.low,
+            close: bar.close,
+            volume: bar.volume,
+        }
+    }
+}
+
+//==================================================================================================
+/// An indicator updated bar by bar
+pub trait Indicator: Send {
+    /// The name of the indicator in `IndicatorUpdate`, e.g. `SMA(20)`
+    fn name(&self) -> String;
+
+    /// Adds a bar and returns the value of the indicator after it, None
+    /// while the indicator has not seen enough bars
+    fn update(&mut self, bar: &Ohlcv) -> Option<f64>;
+
+    /// Forgets the bars seen, e.g. at the start of a new session
+    fn reset(&mut self);
+}
+
+//==================================================================================================
+/// Simple moving average of the closes
+#[derive(Clone, Debug)]
+pub struct Sma {
+    period: usize,
+    closes: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        Sma {
+            period: period.max(1),
+            closes: VecDeque::new(),
+            sum: 0.0,
+        }
+    }
+}
+
+impl Indicator for Sma {
+    fn name(&self) -> String {
+        format!("SMA({})", self.period)
+    }
+
+    fn update(&mut self, bar: &Ohlcv) -> Option<f64> {
+        self.closes.push_back(bar.close);
+        self.sum += bar.close;
+        if self.closes.len() > self.period {
+            self.sum -= self.closes.pop_front().unwrap_or_default();
+        }
+        (self.closes.len() == self.period).then(|| self.sum / self.period as f64)
+    }
+
+    fn reset(&mut self) {
+        self.closes.clear();
+        self.sum = 0.0;
+    }
+}
+
+//==================================================================================================
+/// Exponential moving average of the closes with a smoothing of
+/// 2 / (period + 1), starting at the simple average of the first period
+#[derive(Clone, Debug)]
+pub struct Ema {
+    period: usize,
+    seed: Sma,
+    ema: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        Ema {
+            period: period.max(1),
+            seed: Sma::new(period),
+            ema: None,
+        }
+    }
+}
+
+impl Indicator for Ema {
+    fn name(&self) -> String {
+        format!("EMA({})", self.period)
+    }
+
+    fn update(&mut self, bar: &Ohlcv) -> Option<f64> {
+        self.ema = match self.ema {
+            Some(ema) => {
+                let alpha = 2.0 / (self.period as f64 + 1.0);
+                Some(ema + alpha * (bar.close - ema))
+            }
+            None => self.seed.update(bar),
+        };
+        self.ema
+    }
+
+    fn reset(&mut self) {
+        self.seed.reset();
+        self.ema = None;
+    }
+}
+
+//==================================================================================================
+/// Wilder's smoothing: the simple average of the first period values, then
+/// (average * (period - 1) + value) / period
+#[derive(Clone, Debug)]
+struct WilderAverage {
+    period: usize,
+    count: usize,
+    average: f64,
+}
+
+impl WilderAverage {
+    fn new(period: usize) -> Self {
+        WilderAverage {
+            period: period.max(1),
+            count: 0,
+            average: 0.0,
+        }
+    }
+
+    fn update(&mut self, value: f64) -> Option<f64> {
+        let period = self.period as f64;
+        if self.count < self.period {
+            self.count += 1;
+            self.average += value / period;
+        } else {
+            self.average = (self.average * (period - 1.0) + value) / period;
+        }
+        (self.count == self.period).then_some(self.average)
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+        self.average = 0.0;
+    }
+}
+
+//==================================================================================================
+/// Wilder's relative strength index of the closes, from 0 to 100. The first
+/// value comes with the bar after the first period.
+#[derive(Clone, Debug)]
+pub struct Rsi {
+    period: usize,
+    previous: Option<f64>,
+    gains: WilderAverage,
+    losses: WilderAverage,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        Rsi {
+            period: period.max(1),
+            previous: None,
+            gains: WilderAverage::new(period),
+            losses: WilderAverage::new(period),
+        }
+    }
+}
+
+impl Indicator for Rsi {
+    fn name(&self) -> String {
+        format!("RSI({})", self.period)
+    }
+
+    fn update(&mut self, bar: &Ohlcv) -> Option<f64> {
+        let previous = self.previous.replace(bar.close)?;
+        let change = bar.close - previous;
+        let gain = self.gains.update(change.max(0.0));
+        let loss = self.losses.update((-change).max(0.0));
+        let (gain, loss) = gain.zip(loss)?;
+        Some(if loss == 0.0 {
+            if gain == 0.0 {
+                50.0
+            } else {
+                100.0
+            }
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        })
+    }
+
+    fn reset(&mut self) {
+        self.previous = None;
+        self.gains.reset();
+        self.losses.reset();
+    }
+}
+
+//==================================================================================================
+/// Wilder's average true range. The true range of the first bar is its
+/// high less its low.
+#[derive(Clone, Debug)]
+pub struct Atr {
+    period: usize,
+    previous_close: Option<f64>,
+    average: WilderAverage,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        Atr {
+            period: period.max(1),
+            previous_close: None,
+            average: WilderAverage::new(period),
+        }
+    }
+}
+
+impl Indicator for Atr {
+    fn name(&self) -> String {
+        format!("ATR({})", self.period)
+    }
+
+    fn update(&mut self, bar: &Ohlcv) -> Option<f64> {
+        let range = bar.high - bar.low;
+        let true_range = match self.previous_close.replace(bar.close) {
+            Some(close) => range
+                .max((bar.high - close).abs())
+                .max((bar.low - close).abs()),
+            None => range,
+        };
+        self.average.update(true_range)
+    }
+
+    fn reset(&mut self) {
+        self.previous_close = None;
+        self.average.reset();
+    }
+}
+
+//==================================================================================================
+/// A bar with the values of the indicators of a pipeline after it, in the
+/// order the indicators were added
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IndicatorUpdate<B> {
+    pub bar: B,
+    pub values: Vec<(String, Option<f64>)>,
+}
+
+impl<B> IndicatorUpdate<B> {
+    /// The value of an indicator by its name
+    pub fn value(&self, name: &str) -> Option<f64> {
+        self.values
+            .iter()
+            .find(|(indicator, _)| indicator == name)
+            .and_then(|(_, value)| *value)
+    }
+}
+
+impl<B: fmt::Display> fmt::Display for IndicatorUpdate<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.bar)?;
+        for (name, value) in &self.values {
+            match value {
+                Some(value) => write!(f, ", {}: {}", name, value)?,
+                None => write!(f, ", {}: -", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+//==================================================================================================
+/// Indicators updated together by the same bars
+#[derive(Default)]
+pub struct IndicatorPipeline {
+    indicators: Vec<Box<dyn Indicator>>,
+}
+
+impl IndicatorPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds an indicator
+    pub fn with<I: Indicator + 'static>(mut self, indicator: I) -> Self {
+        self.add(Box::new(indicator));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn add(&mut self, indicator: Box<dyn Indicator>) {
+        self.indicators.push(indicator);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The names of the indicators, in the order they were added
+    pub fn names(&self) -> Vec<String> {
+        self.indicators
+            .iter()
+            .map(|indicator| indicator.name())
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Updates the indicators with a bar and returns their values
+    pub fn update(&mut self, bar: &Ohlcv) -> Vec<(String, Option<f64>)> {
+        self.indicators
+            .iter_mut()
+            .map(|indicator| (indicator.name(), indicator.update(bar)))
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn update_real_time_bar(&mut self, bar: RealTimeBar) -> IndicatorUpdate<RealTimeBar> {
+        let values = self.update(&Ohlcv::from(&bar));
+        IndicatorUpdate { bar, values }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Updates the indicators with bars in time order, e.g. those completed
+    /// by a `BarAggregator`
+    pub fn update_bars(&mut self, bars: Vec<BarData>) -> Vec<IndicatorUpdate<BarData>> {
+        bars.into_iter()
+            .map(|bar| {
+                let values = self.update(&Ohlcv::from(&bar));
+                IndicatorUpdate { bar, values }
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn reset(&mut self) {
+        for indicator in self.indicators.iter_mut() {
+            indicator.reset();
+        }
+    }
+}
+
+impl fmt::Debug for IndicatorPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IndicatorPipeline {{ indicators: {:?} }}", self.names())
+    }
+}
+
+//==================================================================================================
+/// A real time bar subscription whose bars come with the values of a
+/// pipeline. Dropping it cancels the subscription.
+#[derive(Debug)]
+pub struct IndicatorStream {
+    subscription: Subscription<RealTimeBar>,
+    pipeline: IndicatorPipeline,
+}
+
+impl IndicatorStream {
+    pub fn new(subscription: Subscription<RealTimeBar>, pipeline: IndicatorPipeline) -> Self {
+        IndicatorStream {
+            subscription,
+            pipeline,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn request_id(&self) -> i32 {
+        self.subscription.request_id()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Blocks until the next bar arrives
+    pub fn recv(&mut self) -> Result<IndicatorUpdate<RealTimeBar>, RecvError> {
+        let bar = self.subscription.recv()?;
+        Ok(self.pipeline.update_real_time_bar(bar))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn try_recv(&mut self) -> Result<IndicatorUpdate<RealTimeBar>, TryRecvError> {
+        let bar = self.subscription.try_recv()?;
+        Ok(self.pipeline.update_real_time_bar(bar))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn recv_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<IndicatorUpdate<RealTimeBar>, RecvTimeoutError> {
+        let bar = self.subscription.recv_timeout(timeout)?;
+        Ok(self.pipeline.update_real_time_bar(bar))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn pipeline(&self) -> &IndicatorPipeline {
+        &self.pipeline
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn pipeline_mut(&mut self) -> &mut IndicatorPipeline {
+        &mut self.pipeline
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels the subscription and hands back the pipeline
+    pub fn into_pipeline(self) -> IndicatorPipeline {
+        self.pipeline
+    }
+}
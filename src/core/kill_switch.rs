@@ -0,0 +1,69 @@
+//! Options and outcome of `EClient::kill_switch`
+//!
+//! The kill switch cancels all orders with a global cancel, waits until TWS
+//! confirms the cancellation of the orders the client tracks, optionally
+//! flattens positions and blocks `EClient::place_order` until
+//! `EClient::rearm` is called. Orders of other clients are cancelled too
+//! but only the client's own orders are confirmed.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::flatten::{ClosingOrder, FlattenFilter, FlattenStyle};
+
+//==================================================================================================
+/// The positions the kill switch closes after the orders are cancelled
+///
+/// next_order_id - the order id of the first closing order; the ids that
+///                 follow are used for the other closing orders
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KillSwitchFlatten {
+    pub filter: FlattenFilter,
+    pub style: FlattenStyle,
+    pub next_order_id: i32,
+}
+
+impl KillSwitchFlatten {
+    pub fn new(filter: FlattenFilter, style: FlattenStyle, next_order_id: i32) -> Self {
+        KillSwitchFlatten {
+            filter,
+            style,
+            next_order_id,
+        }
+    }
+}
+
+//==================================================================================================
+/// What the kill switch did
+///
+/// cancelled - the tracked orders whose cancellation or fill TWS confirmed
+/// unconfirmed - the tracked orders still working when the timeout expired
+/// closing_orders - the orders placed to flatten positions
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KillSwitchReport {
+    pub cancelled: Vec<i32>,
+    pub unconfirmed: Vec<i32>,
+    pub closing_orders: Vec<ClosingOrder>,
+}
+
+impl KillSwitchReport {
+    /// Whether TWS confirmed that none of the tracked orders is working
+    pub fn is_confirmed(&self) -> bool {
+        self.unconfirmed.is_empty()
+    }
+}
+
+impl fmt::Display for KillSwitchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cancelled: {:?}, unconfirmed: {:?}, closing_orders: {}",
+            self.cancelled,
+            self.unconfirmed,
+            self.closing_orders.len()
+        )
+    }
+}
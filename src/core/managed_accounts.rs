@@ -0,0 +1,124 @@
+//! The accounts a login manages
+//!
+//! TWS sends the comma separated list of managed accounts right after the
+//! connection is established and on `request_managed_accts`. The client
+//! keeps it in `ManagedAccounts`, together with an optional default account
+//! that is applied to orders and requests without one.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+//==================================================================================================
+/// Splits the list of `Wrapper::managed_accounts` into account codes
+pub fn parse_managed_accounts(accounts_list: &str) -> Vec<String> {
+    accounts_list
+        .split(',')
+        .map(str::trim)
+        .filter(|account| !account.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+//==================================================================================================
+/// The managed accounts and the default account of a client. As long as the
+/// list has not been received, any account is accepted.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ManagedAccounts {
+    accounts: Vec<String>,
+    default_account: Option<String>,
+}
+
+impl ManagedAccounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replaces the managed accounts with the list TWS sent. The default
+    /// account is dropped if it is no longer managed.
+    pub fn update(&mut self, accounts_list: &str) {
+        self.accounts = parse_managed_accounts(accounts_list);
+        if let Some(default_account) = &self.default_account {
+            if !self.is_managed(default_account) {
+                self.default_account = None;
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn accounts(&self) -> &[String] {
+        &self.accounts
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the list of managed accounts has been received
+    pub fn is_known(&self) -> bool {
+        !self.accounts.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_managed(&self, account: &str) -> bool {
+        self.accounts.iter().any(|managed| managed == account)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn default_account(&self) -> Option<&str> {
+        self.default_account.as_deref()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the account applied to orders and requests without one. Fails if
+    /// the account is not managed by the login.
+    pub fn set_default_account(&mut self, account: &str) -> Result<(), String> {
+        if account.is_empty() {
+            return Err("The default account can not be empty.".to_string());
+        }
+        self.validate(account)?;
+        self.default_account = Some(account.to_string());
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear_default_account(&mut self) {
+        self.default_account = None;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks that an account is managed by the login. An empty account, or
+    /// any account before the list has been received, is accepted.
+    pub fn validate(&self, account: &str) -> Result<(), String> {
+        if account.is_empty() || !self.is_known() || self.is_managed(account) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Account {} is not managed by this login, only {} are.",
+                account,
+                self.accounts.join(",")
+            ))
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The account a request is made for: the account itself after checking
+    /// it, or the default account if it is empty
+    pub fn resolve(&self, account: &str) -> Result<String, String> {
+        if account.is_empty() {
+            return Ok(self.default_account.clone().unwrap_or_default());
+        }
+        self.validate(account)?;
+        Ok(account.to_string())
+    }
+}
+
+impl fmt::Display for ManagedAccounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "accounts: {}, default_account: {}",
+            self.accounts.join(","),
+            self.default_account.as_deref().unwrap_or("")
+        )
+    }
+}
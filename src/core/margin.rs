@@ -0,0 +1,234 @@
+//! Margin impact of what-if orders
+//!
+//! TWS answers an order with the `what_if` flag set with an `OrderState`
+//! that holds the account's margin before the order, its change and the
+//! margin after it, as strings. `MarginImpact` parses them and
+//! `what_if_basket` checks a list of orders one after the other and adds up
+//! their impact in a `BasketMarginProjection`.
+use std::fmt;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::contract::Contract;
+use crate::core::errors::IbkrError;
+use crate::core::order::{Order, OrderState};
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// What a what-if order streamed by `EClient::fetch_what_if` ends with
+#[derive(Clone, Debug)]
+pub enum WhatIfEvent {
+    Result(Box<OrderState>),
+    Error { code: i32, message: String },
+}
+
+//==================================================================================================
+/// Parses a margin value of an `OrderState`. TWS sends an empty string or
+/// the maximum double for values it did not calculate.
+pub fn parse_margin_value(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok().and_then(calculated)
+}
+
+//==================================================================================================
+fn calculated(value: f64) -> Option<f64> {
+    if value.is_finite() && value.abs() < f64::MAX / 2.0 {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+//==================================================================================================
+/// The margin and commission impact of a what-if order
+///
+/// init_margin_before - the initial margin requirement of the account before
+///   the order
+/// init_margin_change - how much the order changes the initial margin
+/// commission - None if TWS did not calculate it
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MarginImpact {
+    pub init_margin_before: Option<f64>,
+    pub init_margin_change: Option<f64>,
+    pub init_margin_after: Option<f64>,
+    pub maint_margin_before: Option<f64>,
+    pub maint_margin_change: Option<f64>,
+    pub maint_margin_after: Option<f64>,
+    pub equity_with_loan_before: Option<f64>,
+    pub equity_with_loan_change: Option<f64>,
+    pub equity_with_loan_after: Option<f64>,
+    pub commission: Option<f64>,
+    pub commission_currency: String,
+    pub warning_text: String,
+}
+
+impl MarginImpact {
+    pub fn from_order_state(order_state: &OrderState) -> Self {
+        MarginImpact {
+            init_margin_before: parse_margin_value(&order_state.init_margin_before),
+            init_margin_change: parse_margin_value(&order_state.init_margin_change),
+            init_margin_after: parse_margin_value(&order_state.init_margin_after),
+            maint_margin_before: parse_margin_value(&order_state.maint_margin_before),
+            maint_margin_change: parse_margin_value(&order_state.maint_margin_change),
+            maint_margin_after: parse_margin_value(&order_state.maint_margin_after),
+            equity_with_loan_before: parse_margin_value(&order_state.equity_with_loan_before),
+            equity_with_loan_change: parse_margin_value(&order_state.equity_with_loan_change),
+            equity_with_loan_after: parse_margin_value(&order_state.equity_with_loan_after),
+            commission: calculated(order_state.commission),
+            commission_currency: order_state.commission_currency.clone(),
+            warning_text: order_state.warning_text.clone(),
+        }
+    }
+}
+
+impl fmt::Display for MarginImpact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "init_margin_change: {:?}, maint_margin_change: {:?}, equity_with_loan_change: {:?}, \
+             commission: {:?} {}, warning_text: {}",
+            self.init_margin_change,
+            self.maint_margin_change,
+            self.equity_with_loan_change,
+            self.commission,
+            self.commission_currency,
+            self.warning_text
+        )
+    }
+}
+
+//==================================================================================================
+/// The combined margin impact of a list of orders
+///
+/// Each order is checked on its own against the current account, so the
+/// changes of orders that offset each other, e.g. the legs of a hedge, add
+/// up to more than the margin the orders need together.
+///
+/// legs - the impact of each order, in the order they were checked
+/// init_margin_before - the account's initial margin before the orders, from
+///   the first order that reported it
+/// init_margin_change - the sum of the changes of the orders that reported
+///   one
+/// warnings - the warning texts of the orders, with the index of the order
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BasketMarginProjection {
+    pub legs: Vec<MarginImpact>,
+    pub init_margin_before: Option<f64>,
+    pub init_margin_change: f64,
+    pub maint_margin_before: Option<f64>,
+    pub maint_margin_change: f64,
+    pub equity_with_loan_before: Option<f64>,
+    pub equity_with_loan_change: f64,
+    pub commission: f64,
+    pub warnings: Vec<(usize, String)>,
+}
+
+impl BasketMarginProjection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds the impact of another order
+    pub fn add(&mut self, impact: MarginImpact) {
+        self.init_margin_before = self.init_margin_before.or(impact.init_margin_before);
+        self.maint_margin_before = self.maint_margin_before.or(impact.maint_margin_before);
+        self.equity_with_loan_before = self
+            .equity_with_loan_before
+            .or(impact.equity_with_loan_before);
+        self.init_margin_change += impact.init_margin_change.unwrap_or(0.0);
+        self.maint_margin_change += impact.maint_margin_change.unwrap_or(0.0);
+        self.equity_with_loan_change += impact.equity_with_loan_change.unwrap_or(0.0);
+        self.commission += impact.commission.unwrap_or(0.0);
+        if !impact.warning_text.is_empty() {
+            self.warnings
+                .push((self.legs.len(), impact.warning_text.clone()));
+        }
+        self.legs.push(impact);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The projected initial margin after all orders
+    pub fn init_margin_after(&self) -> Option<f64> {
+        self.init_margin_before
+            .map(|before| before + self.init_margin_change)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The projected maintenance margin after all orders
+    pub fn maint_margin_after(&self) -> Option<f64> {
+        self.maint_margin_before
+            .map(|before| before + self.maint_margin_change)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The projected equity with loan value after all orders
+    pub fn equity_with_loan_after(&self) -> Option<f64> {
+        self.equity_with_loan_before
+            .map(|before| before + self.equity_with_loan_change)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The projected equity with loan value left over the initial margin,
+    /// negative if the account could not open the orders
+    pub fn excess_after(&self) -> Option<f64> {
+        Some(self.equity_with_loan_after()? - self.init_margin_after()?)
+    }
+}
+
+impl fmt::Display for BasketMarginProjection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "legs: {}, init_margin_change: {}, maint_margin_change: {}, \
+             equity_with_loan_change: {}, commission: {}, excess_after: {:?}",
+            self.legs.len(),
+            self.init_margin_change,
+            self.maint_margin_change,
+            self.equity_with_loan_change,
+            self.commission,
+            self.excess_after()
+        )
+    }
+}
+
+//==================================================================================================
+/// Checks the margin impact of a list of orders as what-if orders, one after
+/// the other, and adds it up. Stops at the first order TWS rejects.
+///
+/// # Arguments
+/// * next_order_id - the next unused order id, advanced by the ids used
+/// * pacing - the time to wait between two orders, so the what-if checks do
+///   not count against the order rate limit all at once
+/// * timeout - how long to wait for each result
+pub fn what_if_basket<T>(
+    client: &Mutex<EClient<T>>,
+    basket: &[(Contract, Order)],
+    next_order_id: &mut i32,
+    pacing: Duration,
+    timeout: Duration,
+) -> Result<BasketMarginProjection, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut projection = BasketMarginProjection::new();
+    for (index, (contract, order)) in basket.iter().enumerate() {
+        if index > 0 {
+            thread::sleep(pacing);
+        }
+        let order_id = *next_order_id;
+        *next_order_id += 1;
+        let order_state = client
+            .lock()
+            .expect(POISONED_MUTEX)
+            .fetch_what_if(order_id, contract, order, timeout)?;
+        projection.add(MarginImpact::from_order_state(&order_state));
+    }
+    Ok(projection)
+}
@@ -0,0 +1,214 @@
+//! Market rules and a cache of their price increment tables
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::PriceIncrement;
+use crate::core::contract::ContractDetails;
+
+//==================================================================================================
+/// The price increments of a market rule, ordered by their low edge
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MarketRule {
+    pub market_rule_id: i32,
+    pub price_increments: Vec<PriceIncrement>,
+}
+
+impl MarketRule {
+    pub fn new(market_rule_id: i32, mut price_increments: Vec<PriceIncrement>) -> Self {
+        price_increments.sort_by(|a, b| {
+            a.low_edge
+                .partial_cmp(&b.low_edge)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        MarketRule {
+            market_rule_id,
+            price_increments,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The minimum price increment that applies at `price`
+    pub fn increment(&self, price: f64) -> Option<f64> {
+        let magnitude = price.abs();
+        self.price_increments
+            .iter()
+            .rev()
+            .find(|price_increment| magnitude >= price_increment.low_edge)
+            .or_else(|| self.price_increments.first())
+            .map(|price_increment| price_increment.increment)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Rounds `price` to the nearest valid price. Prices are returned
+    /// unchanged if the rule has no increments.
+    pub fn snap(&self, price: f64) -> f64 {
+        self.snap_with(price, f64::round)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Rounds `price` down to the next valid price
+    pub fn snap_down(&self, price: f64) -> f64 {
+        self.snap_with(price, f64::floor)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Rounds `price` up to the next valid price
+    pub fn snap_up(&self, price: f64) -> f64 {
+        self.snap_with(price, f64::ceil)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The number of decimals needed to display prices at `price`
+    pub fn decimals(&self, price: f64) -> usize {
+        match self.increment(price) {
+            Some(increment) if increment > 0.0 => {
+                let mut decimals = 0;
+                let mut scaled = increment;
+                while decimals < 10 && (scaled - scaled.round()).abs() > 1e-9 {
+                    scaled *= 10.0;
+                    decimals += 1;
+                }
+                decimals
+            }
+            _ => 0,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Formats `price` with the number of decimals of its price increment
+    pub fn format_price(&self, price: f64) -> String {
+        format!("{:.*}", self.decimals(price), price)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn snap_with(&self, price: f64, round: fn(f64) -> f64) -> f64 {
+        match self.increment(price) {
+            Some(increment) if increment > 0.0 => {
+                // Round the quotient first to get rid of representation
+                // errors such as 100.35 / 0.05 = 2006.9999999999998
+                let steps = (price / increment * 1e9).round() / 1e9;
+                let snapped = round(steps) * increment;
+                let decimals = self.decimals(price) as i32;
+                let scale = 10f64.powi(decimals);
+                (snapped * scale).round() / scale
+            }
+            _ => price,
+        }
+    }
+}
+
+impl fmt::Display for MarketRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let price_increments: Vec<String> = self
+            .price_increments
+            .iter()
+            .map(|price_increment| price_increment.to_string())
+            .collect();
+        write!(
+            f,
+            "market_rule_id: {}, price_increments: [{}]",
+            self.market_rule_id,
+            price_increments.join("; ")
+        )
+    }
+}
+
+//==================================================================================================
+/// Parses the comma separated `market_rule_ids` of a `ContractDetails`
+pub fn parse_market_rule_ids(market_rule_ids: &str) -> Vec<i32> {
+    market_rule_ids
+        .split(',')
+        .filter_map(|market_rule_id| market_rule_id.trim().parse().ok())
+        .collect()
+}
+
+//==================================================================================================
+/// Caches the market rules received from TWS so that tick sizes can be looked
+/// up synchronously. Keeps track of the rules that have been requested but
+/// not yet received so that each rule is only requested once.
+#[derive(Debug)]
+pub struct MarketRuleCache {
+    rules: HashMap<i32, MarketRule>,
+    requested: HashSet<i32>,
+    auto_fetch: bool,
+}
+
+impl Default for MarketRuleCache {
+    fn default() -> Self {
+        MarketRuleCache {
+            rules: HashMap::new(),
+            requested: HashSet::new(),
+            auto_fetch: true,
+        }
+    }
+}
+
+impl MarketRuleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn insert(&mut self, market_rule: MarketRule) {
+        self.requested.remove(&market_rule.market_rule_id);
+        self.rules.insert(market_rule.market_rule_id, market_rule);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn get(&self, market_rule_id: i32) -> Option<&MarketRule> {
+        self.rules.get(&market_rule_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The market rule that applies to a contract on one of its valid
+    /// exchanges. `market_rule_ids` lists one rule per entry of
+    /// `valid_exchanges`.
+    pub fn rule_for_exchange(
+        &self,
+        contract_details: &ContractDetails,
+        exchange: &str,
+    ) -> Option<&MarketRule> {
+        contract_details
+            .valid_exchanges
+            .split(',')
+            .zip(contract_details.market_rule_ids.split(','))
+            .find(|(valid_exchange, _)| valid_exchange.trim() == exchange)
+            .and_then(|(_, market_rule_id)| market_rule_id.trim().parse().ok())
+            .and_then(|market_rule_id| self.get(market_rule_id))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Marks a rule as requested. Returns `false` if the rule is cached or
+    /// already on its way and hence does not need to be requested again.
+    pub fn mark_requested(&mut self, market_rule_id: i32) -> bool {
+        !self.rules.contains_key(&market_rule_id) && self.requested.insert(market_rule_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Marks all rules referenced by `market_rule_ids` that are neither cached
+    /// nor requested yet as requested and returns them. Returns nothing if
+    /// automatic fetching is turned off.
+    pub fn missing_rules(&mut self, market_rule_ids: &str) -> Vec<i32> {
+        if !self.auto_fetch {
+            return vec![];
+        }
+        parse_market_rule_ids(market_rule_ids)
+            .into_iter()
+            .filter(|market_rule_id| self.mark_requested(*market_rule_id))
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn set_auto_fetch(&mut self, auto_fetch: bool) {
+        self.auto_fetch = auto_fetch;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn auto_fetch(&self) -> bool {
+        self.auto_fetch
+    }
+}
@@ -1,19 +1,45 @@
 //! Functions for processing messages
 use std::any::Any;
-use std::convert::TryInto;
-use std::io::Write;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::Utf8Error;
 use std::string::String;
+use std::sync::{Arc, Mutex};
 
 use std::vec::Vec;
 
 use ascii;
 use ascii::AsAsciiStr;
+use bigdecimal::BigDecimal;
 
 use log::*;
 use num_derive::FromPrimitive;
 
-use crate::core::common::{UNSET_DOUBLE, UNSET_INTEGER};
-use crate::core::errors::IBKRApiLibError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::POISONED_MUTEX;
+use crate::core::common::{NO_VALID_ID, UNSET_DOUBLE, UNSET_INTEGER, UNSET_LONG};
+use crate::core::errors::{DecodeError, DecodeErrorReason, EncodeError, IbkrError};
+use crate::core::server_versions::{
+    MIN_SERVER_VER_ACCOUNT_SUMMARY, MIN_SERVER_VER_API_BIND_ORDER,
+    MIN_SERVER_VER_CANCEL_HEADTIMESTAMP, MIN_SERVER_VER_CFD_REROUTE,
+    MIN_SERVER_VER_COMPLETED_ORDERS, MIN_SERVER_VER_DELTA_NEUTRAL, MIN_SERVER_VER_FUNDAMENTAL_DATA,
+    MIN_SERVER_VER_HISTORICAL_SCHEDULE, MIN_SERVER_VER_HISTORICAL_TICKS, MIN_SERVER_VER_LINKING,
+    MIN_SERVER_VER_LINKING_AUTH, MIN_SERVER_VER_MARKET_RULES, MIN_SERVER_VER_MODELS_SUPPORT,
+    MIN_SERVER_VER_PNL, MIN_SERVER_VER_POSITIONS, MIN_SERVER_VER_REPLACE_FA_END,
+    MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT, MIN_SERVER_VER_REQ_CALC_OPTION_PRICE,
+    MIN_SERVER_VER_REQ_FAMILY_CODES, MIN_SERVER_VER_REQ_GLOBAL_CANCEL,
+    MIN_SERVER_VER_REQ_HEAD_TIMESTAMP, MIN_SERVER_VER_REQ_HISTOGRAM,
+    MIN_SERVER_VER_REQ_HISTORICAL_NEWS, MIN_SERVER_VER_REQ_MARKET_DATA_TYPE,
+    MIN_SERVER_VER_REQ_MATCHING_SYMBOLS, MIN_SERVER_VER_REQ_MKT_DEPTH_EXCHANGES,
+    MIN_SERVER_VER_REQ_NEWS_ARTICLE, MIN_SERVER_VER_REQ_NEWS_PROVIDERS,
+    MIN_SERVER_VER_REQ_SMART_COMPONENTS, MIN_SERVER_VER_SEC_DEF_OPT_PARAMS_REQ,
+    MIN_SERVER_VER_SOFT_DOLLAR_TIER, MIN_SERVER_VER_SYNT_REALTIME_BARS,
+    MIN_SERVER_VER_TICK_BY_TICK, MIN_SERVER_VER_TICK_NEWS, MIN_SERVER_VER_USER_INFO,
+    MIN_SERVER_VER_WSHE_CALENDAR,
+};
 
 //==================================================================================================
 trait EClientMsgSink {
@@ -30,179 +56,252 @@ pub enum FAMessageDataTypes {
 }
 
 //==================================================================================================
-/// incoming msg id's
-#[derive(FromPrimitive)]
-#[repr(i32)]
-pub enum IncomingMessageIds {
-    TickPrice = 1,
-    TickSize = 2,
-    OrderStatus = 3,
-    ErrMsg = 4,
-    OpenOrder = 5,
-    AcctValue = 6,
-    PortfolioValue = 7,
-    AcctUpdateTime = 8,
-    NextValidId = 9,
-    ContractData = 10,
-    ExecutionData = 11,
-    MarketDepth = 12,
-    MarketDepthL2 = 13,
-    NewsBulletins = 14,
-    ManagedAccts = 15,
-    ReceiveFa = 16,
-    HistoricalData = 17,
-    BondContractData = 18,
-    ScannerParameters = 19,
-    ScannerData = 20,
-    TickOptionComputation = 21,
-    TickGeneric = 45,
-    TickString = 46,
-    TickEfp = 47,
-    CurrentTime = 49,
-    RealTimeBars = 50,
-    FundamentalData = 51,
-    ContractDataEnd = 52,
-    OpenOrderEnd = 53,
-    AcctDownloadEnd = 54,
-    ExecutionDataEnd = 55,
-    DeltaNeutralValidation = 56,
-    TickSnapshotEnd = 57,
-    MarketDataType = 58,
-    CommissionReport = 59,
-    PositionData = 61,
-    PositionEnd = 62,
-    AccountSummary = 63,
-    AccountSummaryEnd = 64,
-    VerifyMessageApi = 65,
-    VerifyCompleted = 66,
-    DisplayGroupList = 67,
-    DisplayGroupUpdated = 68,
-    VerifyAndAuthMessageApi = 69,
-    VerifyAndAuthCompleted = 70,
-    PositionMulti = 71,
-    PositionMultiEnd = 72,
-    AccountUpdateMulti = 73,
-    AccountUpdateMultiEnd = 74,
-    SecurityDefinitionOptionParameter = 75,
-    SecurityDefinitionOptionParameterEnd = 76,
-    SoftDollarTiers = 77,
-    FamilyCodes = 78,
-    SymbolSamples = 79,
-    MktDepthExchanges = 80,
-    TickReqParams = 81,
-    SmartComponents = 82,
-    NewsArticle = 83,
-    TickNews = 84,
-    NewsProviders = 85,
-    HistoricalNews = 86,
-    HistoricalNewsEnd = 87,
-    HeadTimestamp = 88,
-    HistogramData = 89,
-    HistoricalDataUpdate = 90,
-    RerouteMktDataReq = 91,
-    RerouteMktDepthReq = 92,
-    MarketRule = 93,
-    Pnl = 94,
-    PnlSingle = 95,
-    HistoricalTicks = 96,
-    HistoricalTicksBidAsk = 97,
-    HistoricalTicksLast = 98,
-    TickByTick = 99,
-    OrderBound = 100,
-    CompletedOrder = 101,
-    CompletedOrdersEnd = 102,
+/// The id, version and minimum server version of a message
+///
+/// version - the version an outgoing message is sent with, or the version an
+///   incoming message is decoded as once servers stop sending it; 0 if the
+///   message has none
+/// min_server_version - the first server version that knows the message, 0
+///   if every supported server does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageInfo {
+    pub id: i32,
+    pub name: &'static str,
+    pub version: i32,
+    pub min_server_version: i32,
 }
 
-//==================================================================================================
-/// Outgoing msg id's
-#[derive(FromPrimitive)]
-#[repr(i32)]
-pub enum OutgoingMessageIds {
-    ReqMktData = 1,
-    CancelMktData = 2,
-    PlaceOrder = 3,
-    CancelOrder = 4,
-    ReqOpenOrders = 5,
-    ReqAcctData = 6,
-    ReqExecutions = 7,
-    ReqIds = 8,
-    ReqContractData = 9,
-    ReqMktDepth = 10,
-    CancelMktDepth = 11,
-    ReqNewsBulletins = 12,
-    CancelNewsBulletins = 13,
-    SetServerLoglevel = 14,
-    ReqAutoOpenOrders = 15,
-    ReqAllOpenOrders = 16,
-    ReqManagedAccts = 17,
-    ReqFa = 18,
-    ReplaceFa = 19,
-    ReqHistoricalData = 20,
-    ExerciseOptions = 21,
-    ReqScannerSubscription = 22,
-    CancelScannerSubscription = 23,
-    ReqScannerParameters = 24,
-    CancelHistoricalData = 25,
-    ReqCurrentTime = 49,
-    ReqRealTimeBars = 50,
-    CancelRealTimeBars = 51,
-    ReqFundamentalData = 52,
-    CancelFundamentalData = 53,
-    ReqCalcImpliedVolat = 54,
-    ReqCalcOptionPrice = 55,
-    CancelCalcImpliedVolat = 56,
-    CancelCalcOptionPrice = 57,
-    ReqGlobalCancel = 58,
-    ReqMarketDataType = 59,
-    ReqPositions = 61,
-    ReqAccountSummary = 62,
-    CancelAccountSummary = 63,
-    CancelPositions = 64,
-    VerifyRequest = 65,
-    VerifyMessage = 66,
-    QueryDisplayGroups = 67,
-    SubscribeToGroupEvents = 68,
-    UpdateDisplayGroup = 69,
-    UnsubscribeFromGroupEvents = 70,
-    StartApi = 71,
-    VerifyAndAuthRequest = 72,
-    VerifyAndAuthMessage = 73,
-    ReqPositionsMulti = 74,
-    CancelPositionsMulti = 75,
-    ReqAccountUpdatesMulti = 76,
-    CancelAccountUpdatesMulti = 77,
-    ReqSecDefOptParams = 78,
-    ReqSoftDollarTiers = 79,
-    ReqFamilyCodes = 80,
-    ReqMatchingSymbols = 81,
-    ReqMktDepthExchanges = 82,
-    ReqSmartComponents = 83,
-    ReqNewsArticle = 84,
-    ReqNewsProviders = 85,
-    ReqHistoricalNews = 86,
-    ReqHeadTimestamp = 87,
-    ReqHistogramData = 88,
-    CancelHistogramData = 89,
-    CancelHeadTimestamp = 90,
-    ReqMarketRule = 91,
-    ReqPnl = 92,
-    CancelPnl = 93,
-    ReqPnlSingle = 94,
-    CancelPnlSingle = 95,
-    ReqHistoricalTicks = 96,
-    ReqTickByTickData = 97,
-    CancelTickByTickData = 98,
-    ReqCompletedOrders = 99,
+// Declares an enum of message ids and the table of their versions, so ids,
+// versions and minimum server versions are kept in one place
+macro_rules! message_table {
+    (
+        $(#[$attr:meta])*
+        $enum:ident, $table:ident {
+            $($name:ident = $id:literal, $version:literal, $min_server_version:expr;)*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(FromPrimitive, Clone, Copy, Debug, PartialEq, Eq)]
+        #[repr(i32)]
+        pub enum $enum {
+            $($name = $id,)*
+        }
+
+        impl $enum {
+            pub const fn info(self) -> MessageInfo {
+                match self {
+                    $(
+                        $enum::$name => MessageInfo {
+                            id: $id,
+                            name: stringify!($name),
+                            version: $version,
+                            min_server_version: $min_server_version,
+                        },
+                    )*
+                }
+            }
+
+            //--------------------------------------------------------------------------------------
+            pub const fn version(self) -> i32 {
+                self.info().version
+            }
+
+            //--------------------------------------------------------------------------------------
+            pub const fn min_server_version(self) -> i32 {
+                self.info().min_server_version
+            }
+        }
+
+        /// The messages in ascending order of their ids
+        pub const $table: &[MessageInfo] = &[$($enum::$name.info(),)*];
+    };
+}
+
+message_table! {
+    /// incoming msg id's
+    IncomingMessageIds, INCOMING_MESSAGES {
+        TickPrice = 1, 0, 0;
+        TickSize = 2, 0, 0;
+        OrderStatus = 3, 0, 0;
+        ErrMsg = 4, 0, 0;
+        OpenOrder = 5, 34, 0;
+        AcctValue = 6, 0, 0;
+        PortfolioValue = 7, 0, 0;
+        AcctUpdateTime = 8, 0, 0;
+        NextValidId = 9, 0, 0;
+        ContractData = 10, 8, 0;
+        ExecutionData = 11, 10, 0;
+        MarketDepth = 12, 0, 0;
+        MarketDepthL2 = 13, 0, 0;
+        NewsBulletins = 14, 0, 0;
+        ManagedAccts = 15, 0, 0;
+        ReceiveFa = 16, 0, 0;
+        HistoricalData = 17, 0, 0;
+        BondContractData = 18, 6, 0;
+        ScannerParameters = 19, 0, 0;
+        ScannerData = 20, 0, 0;
+        TickOptionComputation = 21, 6, 0;
+        TickGeneric = 45, 0, 0;
+        TickString = 46, 0, 0;
+        TickEfp = 47, 0, 0;
+        CurrentTime = 49, 0, 0;
+        RealTimeBars = 50, 0, 0;
+        FundamentalData = 51, 0, MIN_SERVER_VER_FUNDAMENTAL_DATA;
+        ContractDataEnd = 52, 0, 0;
+        OpenOrderEnd = 53, 0, 0;
+        AcctDownloadEnd = 54, 0, 0;
+        ExecutionDataEnd = 55, 0, 0;
+        DeltaNeutralValidation = 56, 0, MIN_SERVER_VER_DELTA_NEUTRAL;
+        TickSnapshotEnd = 57, 0, 0;
+        MarketDataType = 58, 0, MIN_SERVER_VER_REQ_MARKET_DATA_TYPE;
+        CommissionReport = 59, 0, 0;
+        PositionData = 61, 0, MIN_SERVER_VER_POSITIONS;
+        PositionEnd = 62, 0, MIN_SERVER_VER_POSITIONS;
+        AccountSummary = 63, 0, MIN_SERVER_VER_ACCOUNT_SUMMARY;
+        AccountSummaryEnd = 64, 0, MIN_SERVER_VER_ACCOUNT_SUMMARY;
+        VerifyMessageApi = 65, 0, MIN_SERVER_VER_LINKING;
+        VerifyCompleted = 66, 0, MIN_SERVER_VER_LINKING;
+        DisplayGroupList = 67, 0, MIN_SERVER_VER_LINKING;
+        DisplayGroupUpdated = 68, 0, MIN_SERVER_VER_LINKING;
+        VerifyAndAuthMessageApi = 69, 0, MIN_SERVER_VER_LINKING_AUTH;
+        VerifyAndAuthCompleted = 70, 0, MIN_SERVER_VER_LINKING_AUTH;
+        PositionMulti = 71, 0, MIN_SERVER_VER_MODELS_SUPPORT;
+        PositionMultiEnd = 72, 0, MIN_SERVER_VER_MODELS_SUPPORT;
+        AccountUpdateMulti = 73, 0, MIN_SERVER_VER_MODELS_SUPPORT;
+        AccountUpdateMultiEnd = 74, 0, MIN_SERVER_VER_MODELS_SUPPORT;
+        SecurityDefinitionOptionParameter = 75, 0, MIN_SERVER_VER_SEC_DEF_OPT_PARAMS_REQ;
+        SecurityDefinitionOptionParameterEnd = 76, 0, MIN_SERVER_VER_SEC_DEF_OPT_PARAMS_REQ;
+        SoftDollarTiers = 77, 0, MIN_SERVER_VER_SOFT_DOLLAR_TIER;
+        FamilyCodes = 78, 0, MIN_SERVER_VER_REQ_FAMILY_CODES;
+        SymbolSamples = 79, 0, MIN_SERVER_VER_REQ_MATCHING_SYMBOLS;
+        MktDepthExchanges = 80, 0, MIN_SERVER_VER_REQ_MKT_DEPTH_EXCHANGES;
+        TickReqParams = 81, 0, 0;
+        SmartComponents = 82, 0, MIN_SERVER_VER_REQ_SMART_COMPONENTS;
+        NewsArticle = 83, 0, MIN_SERVER_VER_REQ_NEWS_ARTICLE;
+        TickNews = 84, 0, MIN_SERVER_VER_TICK_NEWS;
+        NewsProviders = 85, 0, MIN_SERVER_VER_REQ_NEWS_PROVIDERS;
+        HistoricalNews = 86, 0, MIN_SERVER_VER_REQ_HISTORICAL_NEWS;
+        HistoricalNewsEnd = 87, 0, MIN_SERVER_VER_REQ_HISTORICAL_NEWS;
+        HeadTimestamp = 88, 0, MIN_SERVER_VER_REQ_HEAD_TIMESTAMP;
+        HistogramData = 89, 0, MIN_SERVER_VER_REQ_HISTOGRAM;
+        HistoricalDataUpdate = 90, 0, MIN_SERVER_VER_SYNT_REALTIME_BARS;
+        RerouteMktDataReq = 91, 0, MIN_SERVER_VER_CFD_REROUTE;
+        RerouteMktDepthReq = 92, 0, MIN_SERVER_VER_CFD_REROUTE;
+        MarketRule = 93, 0, MIN_SERVER_VER_MARKET_RULES;
+        Pnl = 94, 0, MIN_SERVER_VER_PNL;
+        PnlSingle = 95, 0, MIN_SERVER_VER_PNL;
+        HistoricalTicks = 96, 0, MIN_SERVER_VER_HISTORICAL_TICKS;
+        HistoricalTicksBidAsk = 97, 0, MIN_SERVER_VER_HISTORICAL_TICKS;
+        HistoricalTicksLast = 98, 0, MIN_SERVER_VER_HISTORICAL_TICKS;
+        TickByTick = 99, 0, MIN_SERVER_VER_TICK_BY_TICK;
+        OrderBound = 100, 0, MIN_SERVER_VER_API_BIND_ORDER;
+        CompletedOrder = 101, 0, MIN_SERVER_VER_COMPLETED_ORDERS;
+        CompletedOrdersEnd = 102, 0, MIN_SERVER_VER_COMPLETED_ORDERS;
+        ReplaceFaEnd = 103, 0, MIN_SERVER_VER_REPLACE_FA_END;
+        WshMetaData = 104, 0, MIN_SERVER_VER_WSHE_CALENDAR;
+        WshEventData = 105, 0, MIN_SERVER_VER_WSHE_CALENDAR;
+        HistoricalSchedule = 106, 0, MIN_SERVER_VER_HISTORICAL_SCHEDULE;
+        UserInfo = 107, 0, MIN_SERVER_VER_USER_INFO;
+    }
+}
+
+message_table! {
+    /// Outgoing msg id's
+    OutgoingMessageIds, OUTGOING_MESSAGES {
+        ReqMktData = 1, 11, 0;
+        CancelMktData = 2, 2, 0;
+        PlaceOrder = 3, 45, 0;
+        CancelOrder = 4, 2, 0;
+        ReqOpenOrders = 5, 1, 0;
+        ReqAcctData = 6, 2, 0;
+        ReqExecutions = 7, 3, 0;
+        ReqIds = 8, 1, 0;
+        ReqContractData = 9, 8, 0;
+        ReqMktDepth = 10, 5, 0;
+        CancelMktDepth = 11, 1, 0;
+        ReqNewsBulletins = 12, 1, 0;
+        CancelNewsBulletins = 13, 1, 0;
+        SetServerLoglevel = 14, 1, 0;
+        ReqAutoOpenOrders = 15, 1, 0;
+        ReqAllOpenOrders = 16, 1, 0;
+        ReqManagedAccts = 17, 1, 0;
+        ReqFa = 18, 1, 0;
+        ReplaceFa = 19, 1, 0;
+        ReqHistoricalData = 20, 6, 0;
+        ExerciseOptions = 21, 2, 0;
+        ReqScannerSubscription = 22, 4, 0;
+        CancelScannerSubscription = 23, 1, 0;
+        ReqScannerParameters = 24, 1, 0;
+        CancelHistoricalData = 25, 1, 0;
+        ReqCurrentTime = 49, 2, 0;
+        ReqRealTimeBars = 50, 3, 0;
+        CancelRealTimeBars = 51, 1, 0;
+        ReqFundamentalData = 52, 2, MIN_SERVER_VER_FUNDAMENTAL_DATA;
+        CancelFundamentalData = 53, 1, MIN_SERVER_VER_FUNDAMENTAL_DATA;
+        ReqCalcImpliedVolat = 54, 3, MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT;
+        ReqCalcOptionPrice = 55, 3, MIN_SERVER_VER_REQ_CALC_OPTION_PRICE;
+        CancelCalcImpliedVolat = 56, 1, MIN_SERVER_VER_REQ_CALC_IMPLIED_VOLAT;
+        CancelCalcOptionPrice = 57, 1, MIN_SERVER_VER_REQ_CALC_OPTION_PRICE;
+        ReqGlobalCancel = 58, 1, MIN_SERVER_VER_REQ_GLOBAL_CANCEL;
+        ReqMarketDataType = 59, 1, MIN_SERVER_VER_REQ_MARKET_DATA_TYPE;
+        ReqPositions = 61, 1, MIN_SERVER_VER_POSITIONS;
+        ReqAccountSummary = 62, 2, MIN_SERVER_VER_ACCOUNT_SUMMARY;
+        CancelAccountSummary = 63, 1, MIN_SERVER_VER_ACCOUNT_SUMMARY;
+        CancelPositions = 64, 1, MIN_SERVER_VER_POSITIONS;
+        VerifyRequest = 65, 1, MIN_SERVER_VER_LINKING;
+        VerifyMessage = 66, 1, MIN_SERVER_VER_LINKING;
+        QueryDisplayGroups = 67, 1, MIN_SERVER_VER_LINKING;
+        SubscribeToGroupEvents = 68, 1, MIN_SERVER_VER_LINKING;
+        UpdateDisplayGroup = 69, 1, MIN_SERVER_VER_LINKING;
+        UnsubscribeFromGroupEvents = 70, 1, MIN_SERVER_VER_LINKING;
+        StartApi = 71, 2, 0;
+        VerifyAndAuthRequest = 72, 1, MIN_SERVER_VER_LINKING_AUTH;
+        VerifyAndAuthMessage = 73, 1, MIN_SERVER_VER_LINKING_AUTH;
+        ReqPositionsMulti = 74, 1, MIN_SERVER_VER_MODELS_SUPPORT;
+        CancelPositionsMulti = 75, 1, MIN_SERVER_VER_MODELS_SUPPORT;
+        ReqAccountUpdatesMulti = 76, 1, MIN_SERVER_VER_MODELS_SUPPORT;
+        CancelAccountUpdatesMulti = 77, 1, MIN_SERVER_VER_MODELS_SUPPORT;
+        ReqSecDefOptParams = 78, 0, MIN_SERVER_VER_SEC_DEF_OPT_PARAMS_REQ;
+        ReqSoftDollarTiers = 79, 0, MIN_SERVER_VER_SOFT_DOLLAR_TIER;
+        ReqFamilyCodes = 80, 0, MIN_SERVER_VER_REQ_FAMILY_CODES;
+        ReqMatchingSymbols = 81, 0, MIN_SERVER_VER_REQ_MATCHING_SYMBOLS;
+        ReqMktDepthExchanges = 82, 0, MIN_SERVER_VER_REQ_MKT_DEPTH_EXCHANGES;
+        ReqSmartComponents = 83, 0, MIN_SERVER_VER_REQ_SMART_COMPONENTS;
+        ReqNewsArticle = 84, 0, MIN_SERVER_VER_REQ_NEWS_ARTICLE;
+        ReqNewsProviders = 85, 0, MIN_SERVER_VER_REQ_NEWS_PROVIDERS;
+        ReqHistoricalNews = 86, 0, MIN_SERVER_VER_REQ_HISTORICAL_NEWS;
+        ReqHeadTimestamp = 87, 0, MIN_SERVER_VER_REQ_HEAD_TIMESTAMP;
+        ReqHistogramData = 88, 0, MIN_SERVER_VER_REQ_HISTOGRAM;
+        CancelHistogramData = 89, 0, MIN_SERVER_VER_REQ_HISTOGRAM;
+        CancelHeadTimestamp = 90, 0, MIN_SERVER_VER_CANCEL_HEADTIMESTAMP;
+        ReqMarketRule = 91, 0, MIN_SERVER_VER_MARKET_RULES;
+        ReqPnl = 92, 0, MIN_SERVER_VER_PNL;
+        CancelPnl = 93, 0, MIN_SERVER_VER_PNL;
+        ReqPnlSingle = 94, 0, MIN_SERVER_VER_PNL;
+        CancelPnlSingle = 95, 0, MIN_SERVER_VER_PNL;
+        ReqHistoricalTicks = 96, 0, MIN_SERVER_VER_HISTORICAL_TICKS;
+        ReqTickByTickData = 97, 0, MIN_SERVER_VER_TICK_BY_TICK;
+        CancelTickByTickData = 98, 0, MIN_SERVER_VER_TICK_BY_TICK;
+        ReqCompletedOrders = 99, 0, MIN_SERVER_VER_COMPLETED_ORDERS;
+        ReqWshMetaData = 100, 0, MIN_SERVER_VER_WSHE_CALENDAR;
+        CancelWshMetaData = 101, 0, MIN_SERVER_VER_WSHE_CALENDAR;
+        ReqWshEventData = 102, 0, MIN_SERVER_VER_WSHE_CALENDAR;
+        CancelWshEventData = 103, 0, MIN_SERVER_VER_WSHE_CALENDAR;
+        ReqUserInfo = 104, 0, MIN_SERVER_VER_USER_INFO;
+    }
 }
 
 //==================================================================================================
-pub fn make_message(msg: &str) -> Result<Vec<u8>, IBKRApiLibError> {
+pub fn make_message(msg: &str) -> Result<Vec<u8>, IbkrError> {
     //let mut buffer = ByteBuffer::new();
     let mut buffer: Vec<u8> = Vec::new();
 
     buffer.extend_from_slice(&i32::to_be_bytes(msg.len() as i32));
 
-    buffer.write_all(msg.as_ascii_str().unwrap().as_bytes())?;
+    let msg = msg
+        .as_ascii_str()
+        .map_err(|_| EncodeError::NonAscii(msg.to_string()))?;
+    buffer.write_all(msg.as_bytes())?;
     let tmp = buffer.clone();
     //debug!("Message after create: {:?}", buffer);
 
@@ -213,7 +312,7 @@ pub fn make_message(msg: &str) -> Result<Vec<u8>, IBKRApiLibError> {
 }
 
 //==================================================================================================
-pub fn read_msg(buf: &[u8]) -> Result<(usize, String, Vec<u8>), IBKRApiLibError> {
+pub fn read_msg(buf: &[u8]) -> Result<(usize, String, Vec<u8>), IbkrError> {
     // first the size prefix and then the corresponding msg payload ""
 
     if buf.len() < 4 {
@@ -221,11 +320,12 @@ pub fn read_msg(buf: &[u8]) -> Result<(usize, String, Vec<u8>), IBKRApiLibError>
         return Ok((0, String::new(), buf.to_vec()));
     }
 
-    let size = i32::from_be_bytes(buf[0..4].try_into().unwrap()) as usize;
+    let size = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as usize;
     //debug!("read_msg: Message size: {:?}", size);
 
     if buf.len() - 4 >= size {
-        let text = String::from_utf8(buf[4..4 + size].to_vec()).unwrap();
+        let text = String::from_utf8(buf[4..4 + size].to_vec())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
         //debug!("read_msg: text in read message: {:?}", text);
         Ok((size, text, buf[4 + size..].to_vec()))
     } else {
@@ -234,22 +334,209 @@ pub fn read_msg(buf: &[u8]) -> Result<(usize, String, Vec<u8>), IBKRApiLibError>
 }
 
 //==================================================================================================
-pub fn read_fields(buf: &str) -> Vec<String> {
-    //msg payload is made of fields terminated/separated by NULL chars
-    let a = '\u{0}';
-    let mut fields: Vec<&str> = buf.split(a).collect::<Vec<&str>>();
-    //debug!("fields.len() in read_fields: {}", fields.len());
-    //last one is empty
-    fields.remove(fields.len() - 1);
+/// What to do with a message that is not valid UTF-8, e.g. because the
+/// description of a bond or the name of an exchange holds Latin-1 text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InvalidUtf8Policy {
+    /// Drop the message with an `InvalidUtf8` decode error, which is passed
+    /// to `Wrapper::decode_error`. The messages after it are read as usual.
+    #[default]
+    Error,
+    /// Replace the invalid bytes with U+FFFD and pass the message on
+    Lossy,
+    /// Like `Lossy`, and keep the raw bytes of the fields that had invalid
+    /// bytes in a `RawFieldLog`
+    LossyKeepRaw,
+}
+
+//==================================================================================================
+/// A field that was not valid UTF-8, as it was received
+///
+/// message_id - the id of the message, NO_VALID_ID if it is not a number
+/// field - the index of the field in the message, the message id being 0
+/// text - the field as it was passed on, with U+FFFD for the invalid bytes
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RawField {
+    pub message_id: i32,
+    pub field: usize,
+    pub text: String,
+    pub bytes: Vec<u8>,
+}
+
+impl fmt::Display for RawField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "message_id: {}, field: {}, text: {}, bytes: {:?}",
+            self.message_id, self.field, self.text, self.bytes
+        )
+    }
+}
+
+//==================================================================================================
+/// The raw fields kept by `InvalidUtf8Policy::LossyKeepRaw`, shared between
+/// the reader of a connection and the client. Only the latest
+/// `MAX_RAW_FIELDS` are kept.
+#[derive(Clone, Debug, Default)]
+pub struct RawFieldLog {
+    fields: Arc<Mutex<VecDeque<RawField>>>,
+}
+
+/// The number of raw fields a `RawFieldLog` keeps
+pub const MAX_RAW_FIELDS: usize = 1000;
+
+impl RawFieldLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn push(&self, field: RawField) {
+        let mut fields = self.fields.lock().expect(POISONED_MUTEX);
+        if fields.len() == MAX_RAW_FIELDS {
+            fields.pop_front();
+        }
+        fields.push_back(field);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes out the fields kept so far, oldest first
+    pub fn take(&self) -> Vec<RawField> {
+        self.fields
+            .lock()
+            .expect(POISONED_MUTEX)
+            .drain(..)
+            .collect()
+    }
+}
+
+//==================================================================================================
+/// Splits the bytes read from the socket into messages. Bytes are appended
+/// as they arrive and every message is taken out as soon as it is complete,
+/// so large responses are dispatched message by message instead of after
+/// the whole packet was read.
+#[derive(Debug, Default)]
+pub struct MessageBuffer {
+    buf: Vec<u8>,
+    start: usize,
+    utf8_policy: InvalidUtf8Policy,
+    raw_fields: RawFieldLog,
+}
+
+impl MessageBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets what is done with messages that are not valid UTF-8 and where
+    /// their raw fields are kept
+    pub fn set_utf8_policy(&mut self, policy: InvalidUtf8Policy, raw_fields: RawFieldLog) {
+        self.utf8_policy = policy;
+        self.raw_fields = raw_fields;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Appends bytes read from the socket
+    pub fn extend(&mut self, bytes: &[u8]) {
+        // Drop the messages taken out before, once per read instead of once
+        // per message
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes out the next complete message, without its size prefix
+    pub fn next_message(&mut self) -> Result<Option<String>, IbkrError> {
+        let pending = &self.buf[self.start..];
+        if pending.len() < 4 {
+            return Ok(None);
+        }
+        let size = u32::from_be_bytes([pending[0], pending[1], pending[2], pending[3]]) as usize;
+        if pending.len() - 4 < size {
+            return Ok(None);
+        }
+
+        let payload = &pending[4..4 + size];
+        let text = match std::str::from_utf8(payload) {
+            Ok(text) => Ok(text.to_string()),
+            Err(err) => self.invalid_utf8(payload, err),
+        };
+        // A message that is dropped is taken out too, or it would block the
+        // ones after it
+        self.start += 4 + size;
+        text.map(Some)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn invalid_utf8(&self, payload: &[u8], err: Utf8Error) -> Result<String, IbkrError> {
+        let text = String::from_utf8_lossy(payload).into_owned();
+        let message_id = payload
+            .split(|byte| *byte == 0)
+            .next()
+            .and_then(|id| std::str::from_utf8(id).ok())
+            .and_then(|id| id.parse().ok())
+            .unwrap_or(NO_VALID_ID);
+        match self.utf8_policy {
+            InvalidUtf8Policy::Error => {
+                let field = memchr::memchr_iter(0, &payload[..err.valid_up_to()]).count();
+                Err(
+                    DecodeError::new(message_id, field, DecodeErrorReason::InvalidUtf8, &text)
+                        .into(),
+                )
+            }
+            InvalidUtf8Policy::Lossy => Ok(text),
+            InvalidUtf8Policy::LossyKeepRaw => {
+                for (field, bytes) in payload.split(|byte| *byte == 0).enumerate() {
+                    if std::str::from_utf8(bytes).is_err() {
+                        self.raw_fields.push(RawField {
+                            message_id,
+                            field,
+                            text: String::from_utf8_lossy(bytes).into_owned(),
+                            bytes: bytes.to_vec(),
+                        });
+                    }
+                }
+                Ok(text)
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The number of bytes of messages that are not complete yet
+    pub fn pending(&self) -> usize {
+        self.buf.len() - self.start
+    }
+}
 
+//==================================================================================================
+/// Splits a message into its fields without copying them
+pub fn split_fields(buf: &str) -> Vec<&str> {
+    //msg payload is made of fields terminated by NULL chars, anything after
+    //the last one is not a field. NULL is ASCII, so the positions memchr
+    //finds are always char boundaries.
+    let bytes = buf.as_bytes();
+    let mut fields = Vec::with_capacity(memchr::memchr_iter(0, bytes).count());
+    let mut start = 0;
+    for end in memchr::memchr_iter(0, bytes) {
+        fields.push(&buf[start..end]);
+        start = end + 1;
+    }
     fields
-        .iter()
-        .map(|x| String::from(*x))
-        .collect::<Vec<String>>()
 }
 
 //==================================================================================================
-pub fn make_field(val: &dyn Any) -> Result<String, IBKRApiLibError> {
+pub fn read_fields(buf: &str) -> Vec<String> {
+    split_fields(buf).into_iter().map(String::from).collect()
+}
+
+//==================================================================================================
+pub fn make_field(val: &dyn Any) -> Result<String, IbkrError> {
     // debug!("CALLING make_field!!");
     // adds the NULL string terminator
     let mut field = "\0".to_string();
@@ -280,7 +567,7 @@ pub fn make_field(val: &dyn Any) -> Result<String, IBKRApiLibError> {
 }
 
 //==================================================================================================
-pub fn make_field_handle_empty(val: &dyn Any) -> Result<String, IBKRApiLibError> {
+pub fn make_field_handle_empty(val: &dyn Any) -> Result<String, IbkrError> {
     if let Some(stringval) = val.downcast_ref::<f64>() {
         if UNSET_DOUBLE == *stringval {
             return make_field(&"");
@@ -293,3 +580,130 @@ pub fn make_field_handle_empty(val: &dyn Any) -> Result<String, IBKRApiLibError>
 
     make_field(val)
 }
+
+//==================================================================================================
+/// A value that can be written as a field of an outgoing message
+///
+/// Fields are written straight into the message buffer, terminated by NULL.
+/// Unset values, i.e. `UNSET_INTEGER`, `UNSET_DOUBLE` and `None`, are written
+/// as empty fields. Text that is not ASCII is rejected, as TWS only accepts
+/// ASCII.
+pub trait EncodeField {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError>;
+}
+
+impl EncodeField for bool {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        // bool type is encoded as int
+        buf.push(if *self { b'1' } else { b'0' });
+        buf.push(0);
+        Ok(())
+    }
+}
+
+impl EncodeField for i32 {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        if *self != UNSET_INTEGER {
+            let _ = write!(buf, "{}", self);
+        }
+        buf.push(0);
+        Ok(())
+    }
+}
+
+impl EncodeField for i64 {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        if *self != UNSET_LONG {
+            let _ = write!(buf, "{}", self);
+        }
+        buf.push(0);
+        Ok(())
+    }
+}
+
+impl EncodeField for usize {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let _ = write!(buf, "{}", self);
+        buf.push(0);
+        Ok(())
+    }
+}
+
+impl EncodeField for f64 {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        if *self == f64::INFINITY {
+            buf.extend_from_slice(b"Infinity");
+        } else if *self != UNSET_DOUBLE {
+            let _ = write!(buf, "{}", self);
+        }
+        buf.push(0);
+        Ok(())
+    }
+}
+
+impl EncodeField for BigDecimal {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let _ = write!(buf, "{}", self);
+        buf.push(0);
+        Ok(())
+    }
+}
+
+impl EncodeField for str {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let text = self
+            .as_ascii_str()
+            .map_err(|_| EncodeError::NonAscii(self.to_string()))?;
+        buf.extend_from_slice(text.as_bytes());
+        buf.push(0);
+        Ok(())
+    }
+}
+
+impl EncodeField for String {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        self.as_str().write_field(buf)
+    }
+}
+
+impl<T: EncodeField + ?Sized> EncodeField for &T {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        (**self).write_field(buf)
+    }
+}
+
+impl<T: EncodeField> EncodeField for Option<T> {
+    fn write_field(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        match self {
+            Some(val) => val.write_field(buf),
+            None => {
+                buf.push(0);
+                Ok(())
+            }
+        }
+    }
+}
+
+//==================================================================================================
+/// Appends a field to a message started with begin_message()
+pub fn write_field<F: EncodeField + ?Sized>(buf: &mut Vec<u8>, val: &F) -> Result<(), EncodeError> {
+    val.write_field(buf)
+}
+
+//==================================================================================================
+/// Starts a message in a buffer, leaving room for the size prefix. The
+/// buffer keeps its capacity, so it can be reused for every message of a
+/// connection.
+pub fn begin_message(buf: &mut Vec<u8>) {
+    buf.clear();
+    buf.extend_from_slice(&[0; 4]);
+}
+
+//==================================================================================================
+/// Fills in the size prefix of a message started with begin_message() and
+/// returns the bytes to send
+pub fn finish_message(buf: &mut [u8]) -> &[u8] {
+    let size = (buf.len() - 4) as i32;
+    buf[..4].copy_from_slice(&size.to_be_bytes());
+    buf
+}
@@ -1,18 +1,92 @@
 //! Core structs, enums, and functions
+pub mod account_alerts;
 pub mod account_summary_tags;
+pub mod account_updates;
+pub mod alert_hooks;
 pub mod algo_params;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod audit_log;
+pub mod backtest;
+#[cfg(feature = "arrow-export")]
+pub mod arrow_export;
+pub mod bar_aggregator;
+#[cfg(feature = "serde")]
+pub mod bar_cache;
+pub mod bar_integrity;
+pub mod blotter;
 pub mod client;
 pub mod common;
+pub mod conflation;
+pub mod continuous_futures;
 pub mod contract;
+pub mod contract_cache;
+pub mod currency;
+#[cfg(feature = "csv-export")]
+pub mod csv_export;
 pub mod decoder;
+pub mod display_groups;
+pub mod error_codes;
 pub mod errors;
 pub mod execution;
+pub mod execution_quality;
+#[cfg(feature = "fa-config")]
+pub mod fa_config;
+pub mod flatten;
+#[cfg(feature = "fundamentals")]
+pub mod fundamentals;
+pub mod futures_chain;
+pub mod fx;
+pub mod fx_hedge;
+pub mod historical;
+pub mod historical_downloader;
+pub mod indicators;
+pub mod kill_switch;
+pub mod managed_accounts;
+pub mod margin;
+pub mod market_rules;
 pub mod messages;
+pub mod model;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod news;
+pub mod option_chain;
 pub mod order;
 pub mod order_condition;
+pub mod order_book;
 pub mod order_decoder;
+pub mod order_journal;
+pub mod order_throttle;
+pub mod order_tracker;
+pub mod pnl;
+pub mod portfolio;
+pub mod positions;
+pub mod price_averages;
+pub mod protective_bracket;
+pub mod quote_cache;
 pub mod reader;
+pub mod rebalance;
+pub mod reconciliation;
+pub mod risk;
+pub mod round_trip;
 pub mod scanner;
+#[cfg(feature = "scanner-params")]
+pub mod scanner_parameters;
 pub mod server_versions;
+pub mod session_gate;
+pub mod smart_components;
+pub mod spreads;
+pub mod strategy;
 pub mod streamer;
+pub mod subscription;
+pub mod tick_recorder;
+pub mod trade_report;
+#[cfg(feature = "sqlite")]
+pub mod trade_store;
+pub mod trading_hours;
+pub mod trading_mode;
+pub mod vol_surface;
+pub mod volume_profile;
 pub mod wrapper;
+#[cfg(feature = "wsh")]
+pub mod wsh;
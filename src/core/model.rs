@@ -0,0 +1,80 @@
+//! Model portfolios
+//!
+//! An advisor can split an account into models, e.g. one per strategy. TWS
+//! keeps positions, account values and PnL per model when orders carry the
+//! model code. A `Model` names the account and model code once, so orders
+//! and requests of a model can not end up in the account at large.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::execution::Execution;
+use crate::core::order::Order;
+use crate::core::positions::Position;
+
+//==================================================================================================
+/// A model of an account
+///
+/// account - the account of the model, empty for the default account
+/// code - the model code
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Model {
+    pub account: String,
+    pub code: String,
+}
+
+impl Model {
+    pub fn new(account: &str, code: &str) -> Self {
+        Model {
+            account: account.to_string(),
+            code: code.to_string(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Places an order in the model by setting its account and model code
+    pub fn apply(&self, order: &mut Order) {
+        order.account = self.account.clone();
+        order.model_code = self.code.clone();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A copy of an order placed in the model
+    pub fn order(&self, order: &Order) -> Order {
+        let mut order = order.clone();
+        self.apply(&mut order);
+        order
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether a position is held by the model. Positions of all accounts
+    /// only carry the model code if they were requested per model.
+    pub fn holds(&self, position: &Position) -> bool {
+        position.model_code == self.code
+            && (self.account.is_empty() || position.account == self.account)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether an execution was made for the model
+    pub fn executed(&self, execution: &Execution) -> bool {
+        execution.model_code == self.code
+            && (self.account.is_empty() || execution.acct_number == self.account)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The positions held by the model
+    pub fn positions<'a>(&self, positions: &'a [Position]) -> Vec<&'a Position> {
+        positions
+            .iter()
+            .filter(|position| self.holds(position))
+            .collect()
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "account: {}, code: {}", self.account, self.code)
+    }
+}
@@ -0,0 +1,103 @@
+//! Compact binary serialization of the serde types as MessagePack
+//!
+//! Values are written as maps keyed by field name, so records stay readable
+//! when fields are added to a type, and are still a fraction of the size of
+//! their JSON. `MsgpackWriter` and `MsgpackReader` frame each value with its
+//! length as a 4 byte big endian integer, like the messages of the TWS
+//! protocol, so a stream of them can be stored as a recorded session or
+//! passed through a pipe or socket to another process.
+use std::io::{self, Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::core::errors::IbkrError;
+
+//==================================================================================================
+pub fn to_msgpack<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, IbkrError> {
+    Ok(rmp_serde::to_vec_named(value)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?)
+}
+
+//==================================================================================================
+pub fn from_msgpack<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, IbkrError> {
+    Ok(rmp_serde::from_slice(bytes)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?)
+}
+
+//==================================================================================================
+/// Writes values as length prefixed MessagePack frames
+#[derive(Debug)]
+pub struct MsgpackWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> MsgpackWriter<W> {
+    pub fn new(writer: W) -> Self {
+        MsgpackWriter { writer }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn write<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), IbkrError> {
+        let bytes = to_msgpack(value)?;
+        self.writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+        self.writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn flush(&mut self) -> Result<(), IbkrError> {
+        Ok(self.writer.flush()?)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+//==================================================================================================
+/// Reads the values written by a `MsgpackWriter`
+#[derive(Debug)]
+pub struct MsgpackReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> MsgpackReader<R> {
+    pub fn new(reader: R) -> Self {
+        MsgpackReader { reader }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The next value, None at the end of the stream. A stream that ends
+    /// inside a frame is an error.
+    pub fn read<T: DeserializeOwned>(&mut self) -> Result<Option<T>, IbkrError> {
+        let mut size = [0; 4];
+        let mut filled = 0;
+        while filled < size.len() {
+            match self.reader.read(&mut size[filled..])? {
+                0 if filled == 0 => return Ok(None),
+                0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                read => filled += read,
+            }
+        }
+        let mut bytes = vec![0; u32::from_be_bytes(size) as usize];
+        self.reader.read_exact(&mut bytes)?;
+        from_msgpack(&bytes).map(Some)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Reads all values up to the end of the stream
+    pub fn read_all<T: DeserializeOwned>(&mut self) -> Result<Vec<T>, IbkrError> {
+        let mut values = vec![];
+        while let Some(value) = self.read()? {
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
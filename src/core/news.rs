@@ -0,0 +1,286 @@
+//! Typed news headlines, articles, bulletins and historical news queries
+use std::fmt;
+
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use num_derive::FromPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Format of the date times used by `EClient::request_historical_news`
+pub const HISTORICAL_NEWS_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+//==================================================================================================
+/// Type of a news article body
+#[repr(i32)]
+#[derive(Clone, Copy, FromPrimitive, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewsArticleType {
+    // #[default]
+    /// Plain text or HTML
+    Text = 0,
+    /// Binary data, e.g. a PDF, encoded as base64
+    Binary = 1,
+}
+
+impl fmt::Display for NewsArticleType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NewsArticleType::Text => write!(f, "Text"),
+            NewsArticleType::Binary => write!(f, "Binary"),
+        }
+    }
+}
+
+//==================================================================================================
+/// article_type - whether the article is text/HTML or base64 encoded binary
+///                data
+/// text - the article body
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NewsArticle {
+    pub article_type: NewsArticleType,
+    pub text: String,
+}
+
+impl NewsArticle {
+    pub fn new(article_type: NewsArticleType, text: String) -> Self {
+        NewsArticle { article_type, text }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether a text article is formatted as HTML
+    pub fn is_html(&self) -> bool {
+        if self.article_type != NewsArticleType::Text {
+            return false;
+        }
+        self.text.trim_start().starts_with('<')
+    }
+}
+
+impl fmt::Display for NewsArticle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "article_type: {}, text: {}",
+            self.article_type, self.text
+        )
+    }
+}
+
+//==================================================================================================
+/// time - when the headline was published
+/// provider_code - the news provider, e.g. BRFG
+/// article_id - pass to `EClient::request_news_article` to fetch the body
+/// headline - the headline, possibly prefixed with metadata in braces, e.g.
+///            `{A:800015:L:en:K:0.87:C:0.95}`
+/// extra_data - additional data sent with live headlines
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NewsHeadline {
+    pub time: DateTime<Utc>,
+    pub provider_code: String,
+    pub article_id: String,
+    pub headline: String,
+    pub extra_data: String,
+}
+
+impl NewsHeadline {
+    pub fn new(
+        time: DateTime<Utc>,
+        provider_code: String,
+        article_id: String,
+        headline: String,
+        extra_data: String,
+    ) -> Self {
+        NewsHeadline {
+            time,
+            provider_code,
+            article_id,
+            headline,
+            extra_data,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The headline without its metadata prefix
+    pub fn text(&self) -> &str {
+        match self.split_metadata() {
+            Some((_, text)) => text,
+            None => self.headline.as_str(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The metadata prefix of the headline without the braces, if any
+    pub fn metadata(&self) -> Option<&str> {
+        self.split_metadata().map(|(metadata, _)| metadata)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A value of the metadata prefix, e.g. `"L"` for the language or `"K"`
+    /// for the sentiment score
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        let mut parts = self.metadata()?.split(':');
+        while let Some(part) = parts.next() {
+            let value = parts.next()?;
+            if part == key {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn split_metadata(&self) -> Option<(&str, &str)> {
+        if !self.headline.starts_with('{') {
+            return None;
+        }
+        self.headline
+            .find('}')
+            .map(|end| (&self.headline[1..end], &self.headline[end + 1..]))
+    }
+}
+
+impl fmt::Display for NewsHeadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time: {}, provider_code: {}, article_id: {}, headline: {}, extra_data: {}",
+            self.time, self.provider_code, self.article_id, self.headline, self.extra_data
+        )
+    }
+}
+
+//==================================================================================================
+/// Type of a news bulletin
+#[repr(i32)]
+#[derive(Clone, Copy, FromPrimitive, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NewsBulletinType {
+    // #[default]
+    Regular = 1,
+    /// The exchange of the bulletin is no longer available for trading
+    ExchangeUnavailable = 2,
+    /// The exchange of the bulletin is available for trading again
+    ExchangeAvailable = 3,
+}
+
+impl fmt::Display for NewsBulletinType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NewsBulletinType::Regular => write!(f, "Regular"),
+            NewsBulletinType::ExchangeUnavailable => write!(f, "ExchangeUnavailable"),
+            NewsBulletinType::ExchangeAvailable => write!(f, "ExchangeAvailable"),
+        }
+    }
+}
+
+//==================================================================================================
+/// msg_id - the bulletin's identifier
+/// msg_type - regular bulletin or a change of an exchange's availability
+/// message - the message
+/// origin_exchange - the exchange the message comes from
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NewsBulletin {
+    pub msg_id: i32,
+    pub msg_type: NewsBulletinType,
+    pub message: String,
+    pub origin_exchange: String,
+}
+
+impl NewsBulletin {
+    pub fn new(
+        msg_id: i32,
+        msg_type: NewsBulletinType,
+        message: String,
+        origin_exchange: String,
+    ) -> Self {
+        NewsBulletin {
+            msg_id,
+            msg_type,
+            message,
+            origin_exchange,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the bulletin reports a change of an exchange's availability
+    /// rather than regular news
+    pub fn is_exchange_status(&self) -> bool {
+        self.msg_type != NewsBulletinType::Regular
+    }
+}
+
+impl fmt::Display for NewsBulletin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "msg_id: {}, msg_type: {}, message: {}, origin_exchange: {}",
+            self.msg_id, self.msg_type, self.message, self.origin_exchange
+        )
+    }
+}
+
+//==================================================================================================
+/// Parses the time of a historical news headline, e.g. `2019-06-27
+/// 15:48:21.0`
+pub fn parse_historical_news_time(time: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(time.trim(), HISTORICAL_NEWS_TIME_FORMAT)
+        .ok()
+        .map(|time| Utc.from_utc_datetime(&time))
+}
+
+//==================================================================================================
+/// Formats a time for `EClient::request_historical_news`
+pub fn format_historical_news_time(time: &DateTime<Utc>) -> String {
+    format!(
+        "{}.{}",
+        time.format("%Y-%m-%d %H:%M:%S"),
+        time.timestamp_subsec_millis() / 100
+    )
+}
+
+//==================================================================================================
+/// The parameters of a historical news request. Each request returns at most
+/// `total_results` headlines, newest first; use `next_page` to page back in
+/// time while `Wrapper::historical_news_end` reports `has_more`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HistoricalNewsQuery {
+    pub con_id: i32,
+    pub provider_codes: Vec<String>,
+    pub start_date_time: Option<DateTime<Utc>>,
+    pub end_date_time: Option<DateTime<Utc>>,
+    pub total_results: i32,
+}
+
+impl HistoricalNewsQuery {
+    pub fn new(con_id: i32, provider_codes: &[&str], total_results: i32) -> Self {
+        HistoricalNewsQuery {
+            con_id,
+            provider_codes: provider_codes.iter().map(|code| code.to_string()).collect(),
+            start_date_time: None,
+            end_date_time: None,
+            total_results,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The query for the headlines published before the oldest headline of
+    /// the current page
+    pub fn next_page(&self, oldest_headline: &NewsHeadline) -> Self {
+        HistoricalNewsQuery {
+            // The end of the range is inclusive and has a resolution of one
+            // tenth of a second
+            end_date_time: Some(oldest_headline.time - Duration::milliseconds(100)),
+            ..self.clone()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The provider codes in the `'+'` separated form expected by TWS
+    pub fn provider_codes_param(&self) -> String {
+        self.provider_codes.join("+")
+    }
+}
@@ -0,0 +1,393 @@
+//! Option chains as returned by `EClient::request_sec_def_opt_params`
+//!
+//! `EClient::fetch_option_chain` collects the expiries and strikes of all
+//! exchanges and trading classes into an `OptionChain`, which can be
+//! narrowed with an `OptionChainFilter` and resolved to the option
+//! contracts that exist.
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::continuous_futures::parse_expiry;
+use crate::core::contract::{Contract, ContractDetails, Right, SecType};
+use crate::core::errors::IbkrError;
+use crate::core::wrapper::Wrapper;
+
+const EXPIRY_FORMAT: &str = "%Y%m%d";
+
+//==================================================================================================
+/// The expiries and strikes of the options of one exchange and trading class
+///
+/// expirations - ascending
+/// strikes - ascending
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OptionChainParameters {
+    pub exchange: String,
+    pub underlying_con_id: i32,
+    pub trading_class: String,
+    pub multiplier: String,
+    pub expirations: Vec<NaiveDate>,
+    pub strikes: Vec<f64>,
+}
+
+impl OptionChainParameters {
+    /// Sorts the expiries and strikes; expiries that are not `YYYYMMDD` are
+    /// dropped
+    pub fn new(
+        exchange: String,
+        underlying_con_id: i32,
+        trading_class: String,
+        multiplier: String,
+        expirations: &[String],
+        strikes: &[f64],
+    ) -> Self {
+        let mut expirations: Vec<NaiveDate> = expirations
+            .iter()
+            .filter_map(|expiry| parse_expiry(expiry))
+            .collect();
+        expirations.sort_unstable();
+        expirations.dedup();
+        let mut strikes = strikes.to_vec();
+        strikes.sort_by(f64::total_cmp);
+        strikes.dedup();
+        OptionChainParameters {
+            exchange,
+            underlying_con_id,
+            trading_class,
+            multiplier,
+            expirations,
+            strikes,
+        }
+    }
+}
+
+impl fmt::Display for OptionChainParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "exchange: {}, underlying_con_id: {}, trading_class: {}, multiplier: {}, \
+             expirations: {}, strikes: {}",
+            self.exchange,
+            self.underlying_con_id,
+            self.trading_class,
+            self.multiplier,
+            self.expirations.len(),
+            self.strikes.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// Event of a request for option chain parameters fetched through a channel
+/// instead of the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum OptionChainEvent {
+    /// The parameters of one exchange and trading class
+    Parameters(Box<OptionChainParameters>),
+    /// All parameters have been received
+    End,
+    /// The request failed
+    Error { code: i32, message: String },
+}
+
+//==================================================================================================
+/// Narrows an `OptionChain`. Bounds are inclusive, unset ones do not
+/// filter.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OptionChainFilter {
+    pub exchange: Option<String>,
+    pub trading_class: Option<String>,
+    pub first_expiry: Option<NaiveDate>,
+    pub last_expiry: Option<NaiveDate>,
+    pub min_strike: Option<f64>,
+    pub max_strike: Option<f64>,
+}
+
+impl OptionChainFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keeps the options of one exchange, e.g. `"SMART"`
+    pub fn exchange(mut self, exchange: &str) -> Self {
+        self.exchange = Some(exchange.to_string());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn trading_class(mut self, trading_class: &str) -> Self {
+        self.trading_class = Some(trading_class.to_string());
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keeps the expiries from `first` to `last`
+    pub fn expiries(mut self, first: NaiveDate, last: NaiveDate) -> Self {
+        self.first_expiry = Some(first);
+        self.last_expiry = Some(last);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keeps the strikes from `min` to `max`
+    pub fn strikes(mut self, min: f64, max: f64) -> Self {
+        self.min_strike = Some(min);
+        self.max_strike = Some(max);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Keeps the strikes within `band` of the spot price, e.g. a band of
+    /// `0.1` keeps the strikes from 90% to 110% of the spot price, see
+    /// `EClient::fetch_spot_price`
+    pub fn strikes_around(self, spot: f64, band: f64) -> Self {
+        self.strikes(spot * (1.0 - band), spot * (1.0 + band))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn keeps_expiry(&self, expiry: &NaiveDate) -> bool {
+        self.first_expiry.is_none_or(|first| *expiry >= first)
+            && self.last_expiry.is_none_or(|last| *expiry <= last)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn keeps_strike(&self, strike: f64) -> bool {
+        self.min_strike.is_none_or(|min| strike >= min)
+            && self.max_strike.is_none_or(|max| strike <= max)
+    }
+}
+
+//==================================================================================================
+/// The option chain of an underlying
+///
+/// underlying - the contract the chain was requested for
+/// parameters - the expiries and strikes by exchange and trading class
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OptionChain {
+    pub underlying: Contract,
+    pub parameters: Vec<OptionChainParameters>,
+}
+
+impl OptionChain {
+    pub fn new(underlying: Contract, parameters: Vec<OptionChainParameters>) -> Self {
+        OptionChain {
+            underlying,
+            parameters,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn exchanges(&self) -> Vec<String> {
+        self.distinct(|parameters| parameters.exchange.clone())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn trading_classes(&self) -> Vec<String> {
+        self.distinct(|parameters| parameters.trading_class.clone())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn multipliers(&self) -> Vec<String> {
+        self.distinct(|parameters| parameters.multiplier.clone())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The expiries of all exchanges and trading classes, ascending
+    pub fn expirations(&self) -> Vec<NaiveDate> {
+        let mut expirations: Vec<NaiveDate> = self
+            .parameters
+            .iter()
+            .flat_map(|parameters| parameters.expirations.iter().cloned())
+            .collect();
+        expirations.sort_unstable();
+        expirations.dedup();
+        expirations
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The strikes of all exchanges and trading classes, ascending
+    pub fn strikes(&self) -> Vec<f64> {
+        let mut strikes: Vec<f64> = self
+            .parameters
+            .iter()
+            .flat_map(|parameters| parameters.strikes.iter().cloned())
+            .collect();
+        strikes.sort_by(f64::total_cmp);
+        strikes.dedup();
+        strikes
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The chain narrowed by `filter`. Exchanges and trading classes left
+    /// without expiries or strikes are dropped.
+    pub fn filter(&self, filter: &OptionChainFilter) -> OptionChain {
+        let parameters = self
+            .parameters
+            .iter()
+            .filter(|parameters| {
+                filter
+                    .exchange
+                    .as_ref()
+                    .is_none_or(|exchange| parameters.exchange.eq_ignore_ascii_case(exchange))
+                    && filter
+                        .trading_class
+                        .as_ref()
+                        .is_none_or(|trading_class| parameters.trading_class == *trading_class)
+            })
+            .map(|parameters| OptionChainParameters {
+                expirations: parameters
+                    .expirations
+                    .iter()
+                    .filter(|expiry| filter.keeps_expiry(expiry))
+                    .cloned()
+                    .collect(),
+                strikes: parameters
+                    .strikes
+                    .iter()
+                    .filter(|strike| filter.keeps_strike(**strike))
+                    .cloned()
+                    .collect(),
+                ..parameters.clone()
+            })
+            .filter(|parameters| {
+                !parameters.expirations.is_empty() && !parameters.strikes.is_empty()
+            })
+            .collect();
+        OptionChain::new(self.underlying.clone(), parameters)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The option contracts of every expiry and strike of the chain with
+    /// the given rights. Not every combination exists; use `resolve` to
+    /// get only those that do. Filter to a single exchange first, otherwise
+    /// there is one contract per exchange.
+    pub fn contracts(&self, rights: &[Right]) -> Vec<Contract> {
+        let mut contracts = vec![];
+        for parameters in &self.parameters {
+            for expiry in &parameters.expirations {
+                for strike in &parameters.strikes {
+                    for right in rights {
+                        let mut contract = self.option(parameters, expiry, *right);
+                        contract.strike = *strike;
+                        contracts.push(contract);
+                    }
+                }
+            }
+        }
+        contracts
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The details of the option contracts of the chain that exist, ordered
+    /// by expiry, right and strike. Sends one contract details request per
+    /// exchange, trading class, expiry and right.
+    ///
+    /// # Arguments
+    /// * client - a connected client
+    /// * first_request_id - the id of the first request, the following ones
+    ///   use the next ids
+    /// * rights - the rights to resolve, e.g. `[Right::Call, Right::Put]`
+    /// * timeout - how long to wait for each of the contract details
+    pub fn resolve<T>(
+        &self,
+        client: &Mutex<EClient<T>>,
+        first_request_id: i32,
+        rights: &[Right],
+        timeout: Duration,
+    ) -> Result<Vec<ContractDetails>, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        let mut request_id = first_request_id;
+        let mut resolved = vec![];
+        for parameters in &self.parameters {
+            for expiry in &parameters.expirations {
+                for right in rights {
+                    let query = self.option(parameters, expiry, *right);
+                    let details = client
+                        .lock()
+                        .expect(POISONED_MUTEX)
+                        .fetch_contract_details(request_id, &query, timeout)?;
+                    request_id += 1;
+                    resolved.extend(details.into_iter().filter(|details| {
+                        parameters
+                            .strikes
+                            .iter()
+                            .any(|strike| (strike - details.contract.strike).abs() < 1e-9)
+                    }));
+                }
+            }
+        }
+        resolved.sort_by(|a, b| {
+            let key = |details: &ContractDetails| {
+                (
+                    details.contract.last_trade_date_or_contract_month.clone(),
+                    details.contract.right.to_string(),
+                )
+            };
+            key(a)
+                .cmp(&key(b))
+                .then(a.contract.strike.total_cmp(&b.contract.strike))
+        });
+        Ok(resolved)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// An option of the chain without a strike
+    fn option(
+        &self,
+        parameters: &OptionChainParameters,
+        expiry: &NaiveDate,
+        right: Right,
+    ) -> Contract {
+        Contract {
+            symbol: self.underlying.symbol.clone(),
+            sec_type: if self.underlying.sec_type == SecType::Future {
+                SecType::FuturesOption
+            } else {
+                SecType::Option
+            },
+            last_trade_date_or_contract_month: expiry.format(EXPIRY_FORMAT).to_string(),
+            right,
+            multiplier: parameters.multiplier.clone(),
+            exchange: parameters.exchange.clone(),
+            currency: self.underlying.currency.clone(),
+            trading_class: parameters.trading_class.clone(),
+            ..Default::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn distinct<F: Fn(&OptionChainParameters) -> String>(&self, field: F) -> Vec<String> {
+        let mut values: Vec<String> = self.parameters.iter().map(field).collect();
+        values.sort_unstable();
+        values.dedup();
+        values
+    }
+}
+
+impl fmt::Display for OptionChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .map(|parameters| parameters.to_string())
+            .collect();
+        write!(
+            f,
+            "underlying: {} {}, parameters: [{}]",
+            self.underlying.symbol,
+            self.underlying.con_id,
+            parameters.join("; ")
+        )
+    }
+}
@@ -1,20 +1,27 @@
 #![allow(clippy::too_many_arguments)]
 //! Types related to orders
 use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Error, Formatter};
 
 use crate::core::common::{TagValue, UNSET_DOUBLE, UNSET_INTEGER};
+use crate::core::contract::SecType;
 use crate::core::order::AuctionStrategy::AuctionUnset;
 use crate::core::order::Origin::Customer;
 use crate::core::order_condition::{
     create_condition, Condition, ConditionType, ExecutionCondition, MarginCondition,
-    OrderConditionEnum, PercentChangeCondition, PriceCondition, TimeCondition, VolumeCondition,
+    OrderConditionEnum, PercentChangeCondition, PriceCondition, TimeCondition, TriggerMethod,
+    VolumeCondition,
 };
 
+/// The `compete_against_best_offset` of a PEG BEST order that competes up
+/// to the midpoint
+pub const COMPETE_AGAINST_BEST_OFFSET_UP_TO_MID: f64 = f64::INFINITY;
+
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, Debug, FromPrimitive, Copy)]
+#[derive(Clone, Debug, FromPrimitive, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Origin {
     // #[default]
     Customer = 0,
@@ -31,7 +38,8 @@ impl Default for Origin {
 // enum AuctionStrategy
 
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, Debug, FromPrimitive, Copy)]
+#[derive(Clone, Debug, FromPrimitive, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum AuctionStrategy {
     // #[default]
     AuctionUnset = 0,
@@ -46,7 +54,8 @@ impl Default for AuctionStrategy {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SoftDollarTier {
     pub name: String,
     pub val: String,
@@ -75,8 +84,9 @@ impl Display for SoftDollarTier {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-#[serde(default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct OrderState {
     pub status: String,
     pub init_margin_before: String,
@@ -193,8 +203,9 @@ impl Display for OrderState {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-#[serde(default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct OrderComboLeg {
     pub price: f64, // type: float
 }
@@ -211,8 +222,9 @@ impl Display for OrderComboLeg {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(default)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Order {
     pub soft_dollar_tier: SoftDollarTier,
     // order identifier
@@ -441,6 +453,15 @@ pub struct Order {
     pub parent_perm_id: i32,
 
     pub use_price_mgmt_algo: bool,
+    pub duration: i32,
+    pub post_to_ats: i32,
+    pub advanced_error_override: String,
+    pub manual_order_time: String,
+    pub min_trade_qty: i32,
+    pub min_compete_size: i32,
+    pub compete_against_best_offset: f64,
+    pub mid_offset_at_whole: f64,
+    pub mid_offset_at_half: f64,
 }
 
 impl Order {
@@ -575,6 +596,15 @@ impl Order {
         route_marketable_to_bbo: bool,
         parent_perm_id: i32,
         use_price_mgmt_algo: bool,
+        duration: i32,
+        post_to_ats: i32,
+        advanced_error_override: String,
+        manual_order_time: String,
+        min_trade_qty: i32,
+        min_compete_size: i32,
+        compete_against_best_offset: f64,
+        mid_offset_at_whole: f64,
+        mid_offset_at_half: f64,
     ) -> Self {
         Order {
             soft_dollar_tier,
@@ -707,9 +737,29 @@ impl Order {
             route_marketable_to_bbo,
             parent_perm_id,
             use_price_mgmt_algo,
+            duration,
+            post_to_ats,
+            advanced_error_override,
+            manual_order_time,
+            min_trade_qty,
+            min_compete_size,
+            compete_against_best_offset,
+            mid_offset_at_whole,
+            mid_offset_at_half,
         }
     }
 
+    /// Whether the order is a PEG BEST order, which takes the minimum compete
+    /// size and the offset to compete against the best price
+    pub fn is_peg_best_order(&self) -> bool {
+        self.order_type == "PEG BEST" || self.order_type == "PEGBEST"
+    }
+
+    /// Whether the order is a PEG MID order, which takes the midpoint offsets
+    pub fn is_peg_mid_order(&self) -> bool {
+        self.order_type == "PEG MID" || self.order_type == "PEGMID"
+    }
+
     /// An auction order is entered into the electronic trading system during
     /// the pre-market opening period for execution at the Calculated
     /// Opening Price (COP). If your order is not filled on the open, the
@@ -1767,7 +1817,7 @@ impl Order {
             // in the reference contract...
             reference_contract_id: reference_con_id,
             // being traded at...
-            reference_exchange_id: reference_exchange.parse().unwrap(),
+            reference_exchange_id: reference_exchange.to_string(),
             //starting reference price is...
             stock_ref_price: stock_reference_price,
             // Keep order active as long as reference contract trades between...
@@ -1874,7 +1924,7 @@ impl Order {
     }
 
     pub fn price_condition_order(
-        trigger_method: i32,
+        trigger_method: TriggerMethod,
         con_id: i32,
         exchange: &str,
         price: f64,
@@ -1892,7 +1942,7 @@ impl Order {
             .contract_condition
             .operator_condition
             .is_more = is_more;
-        price_condition.trigger_method = FromPrimitive::from_i32(trigger_method).unwrap();
+        price_condition.trigger_method = trigger_method;
         // this quantity
         price_condition.price = price;
         // AND | OR next condition (will be ignored if no more conditions are added)
@@ -1907,7 +1957,7 @@ impl Order {
 
     pub fn execution_condition_order(
         symbol: &str,
-        sec_type: &str,
+        sec_type: SecType,
         exchange: &str,
         is_conjunction: bool,
     ) -> ExecutionCondition {
@@ -1918,7 +1968,7 @@ impl Order {
         // at exchange
         exec_condition.exchange = exchange.to_string();
         // for this sec_type
-        exec_condition.sec_type = sec_type.to_string();
+        exec_condition.sec_type = sec_type;
         // AND | OR next condition (will be ignored if no more conditions are added)
         exec_condition.order_condition.is_conjunction_connection = is_conjunction;
         exec_condition.order_condition.cond_type = ConditionType::Execution;
@@ -2047,6 +2097,15 @@ impl Order {
 
 impl Display for Order {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        let conditions = self
+            .conditions
+            .iter()
+            .map(|x| {
+                x.make_fields()
+                    .map(|fields| format!("{}|", fields.join(",")))
+            })
+            .collect::<Result<String, _>>()
+            .map_err(|_| Error)?;
         write!(
             f,
             "order_id = {}\n\
@@ -2094,14 +2153,7 @@ impl Display for Order {
             } else {
                 String::new()
             },
-            if !self.conditions.is_empty() {
-                self.conditions
-                    .iter()
-                    .map(|x| format!("{}|", x.make_fields().unwrap().as_slice().join(",")))
-                    .collect::<String>()
-            } else {
-                String::new()
-            },
+            conditions,
         )
     }
 }
@@ -2301,6 +2353,15 @@ impl Default for Order {
             parent_perm_id: 0,
 
             use_price_mgmt_algo: false,
+            duration: UNSET_INTEGER,
+            post_to_ats: UNSET_INTEGER,
+            advanced_error_override: String::new(),
+            manual_order_time: String::new(),
+            min_trade_qty: UNSET_INTEGER,
+            min_compete_size: UNSET_INTEGER,
+            compete_against_best_offset: UNSET_DOUBLE,
+            mid_offset_at_whole: UNSET_DOUBLE,
+            mid_offset_at_half: UNSET_DOUBLE,
         }
     }
 }
@@ -0,0 +1,264 @@
+//! Order books built from market depth and their microstructure metrics
+//!
+//! An `OrderBook` applies the rows TWS sends with `update_market_depth` and
+//! `update_market_depth_l2`. `OrderBook::metrics` computes what execution
+//! algorithms look at: the imbalance between bid and ask size, the mid
+//! weighted by the sizes at the touch, the depth within a number of ticks of
+//! the touch and the slope of each side of the book.
+//!
+//! `EClient::stream_book_metrics` requests market depth, keeps the book and
+//! delivers its metrics after every change through a `Subscription`, while
+//! the rows still go to the `Wrapper`.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const INSERT: i32 = 0;
+const UPDATE: i32 = 1;
+const DELETE: i32 = 2;
+const ASK: i32 = 0;
+const BID: i32 = 1;
+
+//==================================================================================================
+/// A row of market depth as TWS sends it with `update_market_depth` and
+/// `update_market_depth_l2`
+///
+/// position - the row
+/// market_maker - empty for market depth without market makers
+/// operation - 0 inserts, 1 updates and 2 deletes the row
+/// side - 0 for asks, 1 for bids
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthUpdate<'a> {
+    pub position: i32,
+    pub market_maker: &'a str,
+    pub operation: i32,
+    pub side: i32,
+    pub price: f64,
+    pub size: f64,
+}
+
+//==================================================================================================
+/// A row of an order book
+///
+/// market_maker - the exchange or market maker holding the order, empty for
+///   market depth without market makers
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BookLevel {
+    pub price: f64,
+    pub size: f64,
+    pub market_maker: String,
+}
+
+impl fmt::Display for BookLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "price: {}, size: {}, market_maker: {}",
+            self.price, self.size, self.market_maker
+        )
+    }
+}
+
+//==================================================================================================
+/// What the metrics of a book are computed over
+///
+/// levels - the number of rows of each side the imbalance and the slope
+///   take into account
+/// tick_size - the minimum price increment of the contract
+/// depth_ticks - how many ticks from the touch the depth reaches
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BookMetricsConfig {
+    pub levels: usize,
+    pub tick_size: f64,
+    pub depth_ticks: u32,
+}
+
+impl BookMetricsConfig {
+    pub fn new(levels: usize, tick_size: f64, depth_ticks: u32) -> Self {
+        BookMetricsConfig {
+            levels,
+            tick_size,
+            depth_ticks,
+        }
+    }
+}
+
+impl Default for BookMetricsConfig {
+    fn default() -> Self {
+        Self::new(5, 0.01, 5)
+    }
+}
+
+//==================================================================================================
+/// The microstructure of a book at one point in time
+///
+/// imbalance - the bid size less the ask size over their sum, within the
+///   configured levels, from -1 for only asks to 1 for only bids
+/// weighted_mid - the prices at the touch weighted by the size on the other
+///   side, which leans towards the side that is likely to trade through
+/// bid_depth, ask_depth - the size within the configured ticks of the touch
+/// bid_slope, ask_slope - the size within the configured levels per unit of
+///   price away from the mid; a steep side takes more size to move
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BookMetrics {
+    pub request_id: i32,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    pub spread: Option<f64>,
+    pub mid: Option<f64>,
+    pub weighted_mid: Option<f64>,
+    pub imbalance: Option<f64>,
+    pub bid_depth: f64,
+    pub ask_depth: f64,
+    pub bid_slope: Option<f64>,
+    pub ask_slope: Option<f64>,
+}
+
+impl fmt::Display for BookMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "request_id: {}, best_bid: {:?}, best_ask: {:?}, spread: {:?}, mid: {:?}, \
+             weighted_mid: {:?}, imbalance: {:?}, bid_depth: {}, ask_depth: {}, bid_slope: {:?}, \
+             ask_slope: {:?}",
+            self.request_id,
+            self.best_bid,
+            self.best_ask,
+            self.spread,
+            self.mid,
+            self.weighted_mid,
+            self.imbalance,
+            self.bid_depth,
+            self.ask_depth,
+            self.bid_slope,
+            self.ask_slope
+        )
+    }
+}
+
+//==================================================================================================
+/// The rows of both sides of a book, best first
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrderBook {
+    bids: Vec<BookLevel>,
+    asks: Vec<BookLevel>,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies a row as sent by TWS. Returns false if the row does not fit
+    /// the book, e.g. the deletion of a row the book does not have.
+    pub fn apply(&mut self, update: &DepthUpdate) -> bool {
+        let rows = match update.side {
+            ASK => &mut self.asks,
+            BID => &mut self.bids,
+            _ => return false,
+        };
+        let Ok(position) = usize::try_from(update.position) else {
+            return false;
+        };
+        let level = BookLevel {
+            price: update.price,
+            size: update.size,
+            market_maker: update.market_maker.to_string(),
+        };
+        match update.operation {
+            INSERT if position <= rows.len() => rows.insert(position, level),
+            // Updates of the row after the last one add it
+            UPDATE if position < rows.len() => rows[position] = level,
+            UPDATE if position == rows.len() => rows.push(level),
+            DELETE if position < rows.len() => {
+                rows.remove(position);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn bids(&self) -> &[BookLevel] {
+        &self.bids
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn asks(&self) -> &[BookLevel] {
+        &self.asks
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn best_bid(&self) -> Option<&BookLevel> {
+        self.bids.first()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn best_ask(&self) -> Option<&BookLevel> {
+        self.asks.first()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The metrics of the book
+    pub fn metrics(&self, request_id: i32, config: &BookMetricsConfig) -> BookMetrics {
+        let best_bid = self.best_bid();
+        let best_ask = self.best_ask();
+        let mid = best_bid
+            .zip(best_ask)
+            .map(|(bid, ask)| (bid.price + ask.price) / 2.0);
+        let weighted_mid = best_bid.zip(best_ask).and_then(|(bid, ask)| {
+            let size = bid.size + ask.size;
+            (size > 0.0).then(|| (bid.price * ask.size + ask.price * bid.size) / size)
+        });
+        let bid_size: f64 = self.bids.iter().take(config.levels).map(|l| l.size).sum();
+        let ask_size: f64 = self.asks.iter().take(config.levels).map(|l| l.size).sum();
+        let imbalance =
+            (bid_size + ask_size > 0.0).then(|| (bid_size - ask_size) / (bid_size + ask_size));
+        let slope = |rows: &[BookLevel], size: f64| {
+            let last = rows[..rows.len().min(config.levels)].last()?;
+            let distance = (last.price - mid?).abs();
+            (distance > 0.0).then(|| size / distance)
+        };
+        BookMetrics {
+            request_id,
+            best_bid: best_bid.map(|level| level.price),
+            best_ask: best_ask.map(|level| level.price),
+            spread: best_bid
+                .zip(best_ask)
+                .map(|(bid, ask)| ask.price - bid.price),
+            mid,
+            weighted_mid,
+            imbalance,
+            bid_depth: depth_within(&self.bids, config.tick_size, config.depth_ticks),
+            ask_depth: depth_within(&self.asks, config.tick_size, config.depth_ticks),
+            bid_slope: slope(&self.bids, bid_size),
+            ask_slope: slope(&self.asks, ask_size),
+        }
+    }
+}
+
+//==================================================================================================
+/// The size within `ticks` ticks of the touch of one side of a book
+fn depth_within(rows: &[BookLevel], tick_size: f64, ticks: u32) -> f64 {
+    let Some(best) = rows.first() else {
+        return 0.0;
+    };
+    // Half a tick of slack for prices that are not exact multiples
+    let reach = (ticks as f64 + 0.5) * tick_size;
+    rows.iter()
+        .filter(|level| (level.price - best.price).abs() <= reach)
+        .map(|level| level.size)
+        .sum()
+}
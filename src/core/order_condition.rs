@@ -3,17 +3,20 @@ use std::fmt::{Debug, Display, Error, Formatter};
 use std::slice::Iter;
 
 use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::core::decoder::{decode_bool, decode_f64, decode_i32, decode_string};
-use crate::core::errors::IBKRApiLibError;
+use crate::core::common::NO_VALID_ID;
+use crate::core::contract::SecType;
+use crate::core::decoder::{decode_bool, decode_enum, decode_f64, decode_i32, decode_string};
+use crate::core::errors::IbkrError;
 use crate::core::messages::make_field;
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Copy)]
+#[derive(Clone, FromPrimitive, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ConditionType {
     Price = 1,
     Time = 3,
@@ -50,7 +53,8 @@ impl Debug for ConditionType {
 
 //==================================================================================================
 #[repr(i32)]
-#[derive(Serialize, Deserialize, Clone, FromPrimitive, Copy)]
+#[derive(Clone, FromPrimitive, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TriggerMethod {
     Default = 0,
     DoubleBidAsk = 1,
@@ -92,7 +96,8 @@ impl Debug for TriggerMethod {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum OrderConditionEnum {
     Price(PriceCondition),
     Time(TimeCondition),
@@ -103,7 +108,7 @@ pub enum OrderConditionEnum {
 }
 
 impl Condition for OrderConditionEnum {
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         match self {
             OrderConditionEnum::Execution(s) => s.decode(fields_iter),
             OrderConditionEnum::Price(p) => p.decode(fields_iter),
@@ -115,7 +120,7 @@ impl Condition for OrderConditionEnum {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         match self {
             OrderConditionEnum::Execution(s) => s.make_fields(),
             OrderConditionEnum::Price(p) => p.make_fields(),
@@ -139,7 +144,7 @@ impl Condition for OrderConditionEnum {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn set_value_from_string(&mut self, text: String) {
+    fn set_value_from_string(&mut self, text: String) -> Result<(), IbkrError> {
         match self {
             OrderConditionEnum::Execution(s) => s.set_value_from_string(text),
             OrderConditionEnum::Price(p) => p.set_value_from_string(text),
@@ -187,16 +192,17 @@ impl Debug for OrderConditionEnum {
 }
 
 //==================================================================================================
-pub trait Condition: Display + Debug + Serialize {
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError>;
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError>;
+pub trait Condition: Display + Debug {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError>;
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError>;
     fn value_to_string(&self) -> String;
-    fn set_value_from_string(&mut self, text: String);
+    fn set_value_from_string(&mut self, text: String) -> Result<(), IbkrError>;
     fn get_type(&self) -> ConditionType;
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Copy, Default)]
+#[derive(Clone, Debug, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OrderCondition {
     pub cond_type: ConditionType,
     pub is_conjunction_connection: bool,
@@ -228,14 +234,14 @@ impl OrderCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    pub fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         let connector = decode_string(fields_iter)?;
         self.is_conjunction_connection = connector == "a";
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    pub fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = vec![];
         let val = if self.is_conjunction_connection {
             "a"
@@ -247,16 +253,17 @@ impl OrderCondition {
     }
 }
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ExecutionCondition {
-    pub sec_type: String,
+    pub sec_type: SecType,
     pub exchange: String,
     pub symbol: String,
     pub order_condition: OrderCondition,
 }
 
 impl ExecutionCondition {
-    pub fn new(sec_type: String, exchange: String, symbol: String) -> Self {
+    pub fn new(sec_type: SecType, exchange: String, symbol: String) -> Self {
         ExecutionCondition {
             sec_type,
             exchange,
@@ -268,18 +275,18 @@ impl ExecutionCondition {
 
 impl Condition for ExecutionCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order_condition.decode(fields_iter)?;
-        self.sec_type = decode_string(fields_iter)?;
+        self.sec_type = decode_string(fields_iter)?.into();
         self.exchange = decode_string(fields_iter)?;
         self.symbol = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self.order_condition.make_fields()?;
-        flds.push(make_field(&self.sec_type)?);
+        flds.push(make_field(&self.sec_type.to_string())?);
         flds.push(make_field(&self.exchange)?);
         flds.push(make_field(&self.symbol)?);
         Ok(flds)
@@ -294,8 +301,11 @@ impl Condition for ExecutionCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn set_value_from_string(&mut self, _text: String) {
-        unimplemented!()
+    fn set_value_from_string(&mut self, _text: String) -> Result<(), IbkrError> {
+        Err(IbkrError::rejected(
+            NO_VALID_ID,
+            &format!("{} conditions have no value", self.get_type()),
+        ))
     }
 
     //----------------------------------------------------------------------------------------------
@@ -326,7 +336,8 @@ impl From<OrderConditionEnum> for ExecutionCondition {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Copy, Default)]
+#[derive(Clone, Debug, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct OperatorCondition {
     pub order_condition: OrderCondition,
     pub is_more: bool,
@@ -341,24 +352,14 @@ impl OperatorCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn value_to_string(&self) -> String {
-        unimplemented!();
-    }
-
-    //----------------------------------------------------------------------------------------------
-    pub fn set_value_from_string(&self, _text: &str) {
-        unimplemented!();
-    }
-
-    //----------------------------------------------------------------------------------------------
-    pub fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    pub fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order_condition.decode(fields_iter)?;
         self.is_more = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self.order_condition.make_fields()?;
         flds.push(make_field(&self.is_more)?);
         Ok(flds)
@@ -366,7 +367,8 @@ impl OperatorCondition {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[derive(Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MarginCondition {
     pub operator_condition: OperatorCondition,
     pub percent: f64,
@@ -384,14 +386,14 @@ impl MarginCondition {
 
 impl Condition for MarginCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.operator_condition.decode(fields_iter)?;
-        self.percent = decode_f64(fields_iter).unwrap();
+        self.percent = decode_f64(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self.operator_condition.make_fields()?;
         flds.push(make_field(&self.percent)?);
         Ok(flds)
@@ -401,8 +403,9 @@ impl Condition for MarginCondition {
         self.percent.to_string()
     }
 
-    fn set_value_from_string(&mut self, text: String) {
-        self.percent = text.parse().unwrap();
+    fn set_value_from_string(&mut self, text: String) -> Result<(), IbkrError> {
+        self.percent = text.parse()?;
+        Ok(())
     }
 
     fn get_type(&self) -> ConditionType {
@@ -431,7 +434,8 @@ impl From<OrderConditionEnum> for MarginCondition {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ContractCondition {
     pub operator_condition: OperatorCondition,
     pub con_id: i32,
@@ -447,19 +451,25 @@ impl ContractCondition {
             exchange: exchange.to_string(),
         }
     }
-}
 
-impl Condition for ContractCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
-        self.operator_condition.decode(fields_iter)?;
+    /// Decodes the contract of a condition, which follows its value
+    fn decode_contract(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.con_id = decode_i32(fields_iter)?;
         self.exchange = decode_string(fields_iter)?;
         Ok(())
     }
+}
+
+impl Condition for ContractCondition {
+    //----------------------------------------------------------------------------------------------
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        self.operator_condition.decode(fields_iter)?;
+        self.decode_contract(fields_iter)
+    }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self.operator_condition.make_fields()?;
         flds.push(make_field(&self.con_id)?);
         flds.push(make_field(&self.exchange)?);
@@ -472,8 +482,11 @@ impl Condition for ContractCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn set_value_from_string(&mut self, _text: String) {
-        unimplemented!()
+    fn set_value_from_string(&mut self, _text: String) -> Result<(), IbkrError> {
+        Err(IbkrError::rejected(
+            NO_VALID_ID,
+            &format!("{} conditions have no value", self.get_type()),
+        ))
     }
 
     //----------------------------------------------------------------------------------------------
@@ -503,7 +516,8 @@ impl From<OrderConditionEnum> for ContractCondition {
     }
 }
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TimeCondition {
     pub operator_condition: OperatorCondition,
     pub time: String,
@@ -521,14 +535,14 @@ impl TimeCondition {
 
 impl Condition for TimeCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.operator_condition.decode(fields_iter)?;
-        self.time = decode_string(fields_iter).unwrap();
+        self.time = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self.operator_condition.make_fields()?;
         flds.push(make_field(&self.time)?);
         Ok(flds)
@@ -540,8 +554,9 @@ impl Condition for TimeCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn set_value_from_string(&mut self, text: String) {
-        self.time = text
+    fn set_value_from_string(&mut self, text: String) -> Result<(), IbkrError> {
+        self.time = text;
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -574,7 +589,8 @@ impl From<OrderConditionEnum> for TimeCondition {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PriceCondition {
     pub contract_condition: ContractCondition,
     pub price: f64,
@@ -605,15 +621,18 @@ impl PriceCondition {
 
 impl Condition for PriceCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        self.contract_condition
+            .operator_condition
+            .decode(fields_iter)?;
         self.price = decode_f64(fields_iter)?;
-        self.contract_condition.decode(fields_iter)?;
-        self.trigger_method = FromPrimitive::from_i32(decode_i32(fields_iter)?).unwrap();
+        self.contract_condition.decode_contract(fields_iter)?;
+        self.trigger_method = decode_enum(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self
             .contract_condition
             .operator_condition
@@ -636,8 +655,9 @@ impl Condition for PriceCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn set_value_from_string(&mut self, text: String) {
-        self.price = text.parse().unwrap();
+    fn set_value_from_string(&mut self, text: String) -> Result<(), IbkrError> {
+        self.price = text.parse()?;
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -669,7 +689,8 @@ impl From<OrderConditionEnum> for PriceCondition {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PercentChangeCondition {
     pub contract_condition: ContractCondition,
     pub change_percent: f64,
@@ -692,14 +713,17 @@ impl PercentChangeCondition {
 
 impl Condition for PercentChangeCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        self.contract_condition
+            .operator_condition
+            .decode(fields_iter)?;
         self.change_percent = decode_f64(fields_iter)?;
-        self.contract_condition.decode(fields_iter)?;
+        self.contract_condition.decode_contract(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self
             .contract_condition
             .operator_condition
@@ -721,8 +745,9 @@ impl Condition for PercentChangeCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn set_value_from_string(&mut self, text: String) {
-        self.change_percent = text.parse().unwrap();
+    fn set_value_from_string(&mut self, text: String) -> Result<(), IbkrError> {
+        self.change_percent = text.parse()?;
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -755,7 +780,8 @@ impl From<OrderConditionEnum> for PercentChangeCondition {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Clone, Default)]
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct VolumeCondition {
     pub contract_condition: ContractCondition,
     pub volume: i32,
@@ -778,15 +804,17 @@ impl VolumeCondition {
 
 impl Condition for VolumeCondition {
     //----------------------------------------------------------------------------------------------
-    fn decode(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
-        self.contract_condition.decode(fields_iter)?;
+    fn decode(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        self.contract_condition
+            .operator_condition
+            .decode(fields_iter)?;
         self.volume = decode_i32(fields_iter)?;
-
+        self.contract_condition.decode_contract(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn make_fields(&self) -> Result<Vec<String>, IBKRApiLibError> {
+    fn make_fields(&self) -> Result<Vec<String>, IbkrError> {
         let mut flds = self
             .contract_condition
             .operator_condition
@@ -807,8 +835,9 @@ impl Condition for VolumeCondition {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn set_value_from_string(&mut self, text: String) {
-        self.volume = text.parse().unwrap();
+    fn set_value_from_string(&mut self, text: String) -> Result<(), IbkrError> {
+        self.volume = text.parse()?;
+        Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
@@ -842,15 +871,43 @@ impl From<OrderConditionEnum> for VolumeCondition {
 }
 
 //----------------------------------------------------------------------------------------------
+/// An empty condition of a type, to decode it into
 pub fn create_condition(cond_type: ConditionType) -> OrderConditionEnum {
     match cond_type {
-        ConditionType::Execution => OrderConditionEnum::Execution(ExecutionCondition::default()),
-        ConditionType::Margin => OrderConditionEnum::Margin(MarginCondition::default()),
-        ConditionType::PercentChange => {
-            OrderConditionEnum::PercentChange(PercentChangeCondition::default())
-        }
-        ConditionType::Price => OrderConditionEnum::Price(PriceCondition::default()),
-        ConditionType::Time => OrderConditionEnum::Time(TimeCondition::default()),
-        ConditionType::Volume => OrderConditionEnum::Volume(VolumeCondition::default()),
+        ConditionType::Execution => OrderConditionEnum::Execution(ExecutionCondition::new(
+            SecType::default(),
+            String::new(),
+            String::new(),
+        )),
+        ConditionType::Margin => OrderConditionEnum::Margin(MarginCondition::new(false, 0.0)),
+        ConditionType::PercentChange => OrderConditionEnum::PercentChange(
+            PercentChangeCondition::new(0, String::new(), false, 0.0),
+        ),
+        ConditionType::Price => OrderConditionEnum::Price(PriceCondition::new(
+            TriggerMethod::default(),
+            0,
+            "",
+            false,
+            0.0,
+        )),
+        ConditionType::Time => OrderConditionEnum::Time(TimeCondition::new(false, String::new())),
+        ConditionType::Volume => OrderConditionEnum::Volume(VolumeCondition::new(0, "", false, 0)),
     }
 }
+
+//----------------------------------------------------------------------------------------------
+/// The fields of a condition as they are sent with an order: its type
+/// followed by its own fields
+pub fn encode_condition(condition: &OrderConditionEnum) -> Result<Vec<String>, IbkrError> {
+    let mut fields = vec![make_field(&(condition.get_type() as i32))?];
+    fields.extend(condition.make_fields()?);
+    Ok(fields)
+}
+
+//----------------------------------------------------------------------------------------------
+/// Decodes a condition of an order from its type and its own fields
+pub fn decode_condition(fields_iter: &mut Iter<&str>) -> Result<OrderConditionEnum, IbkrError> {
+    let mut condition = create_condition(decode_enum(fields_iter)?);
+    condition.decode(fields_iter)?;
+    Ok(condition)
+}
@@ -1,23 +1,22 @@
 //! Helper types and functions related to decoding order type messages
 use std::slice::Iter;
 
-use num_traits::FromPrimitive;
-
 use crate::core::common::{TagValue, UNSET_DOUBLE};
 use crate::core::contract::{ComboLeg, Contract, DeltaNeutralContract};
 use crate::core::decoder::{
-    decode_bool, decode_f64, decode_f64_show_unset, decode_i32, decode_i32_show_unset,
-    decode_string,
+    decode_bool, decode_enum, decode_f64, decode_f64_show_unset, decode_i32, decode_i32_show_unset,
+    decode_right, decode_string,
 };
-use crate::core::errors::IBKRApiLibError;
+use crate::core::errors::IbkrError;
 use crate::core::order::{Order, OrderComboLeg, OrderState, SoftDollarTier};
-use crate::core::order_condition::{create_condition, Condition};
+use crate::core::order_condition::decode_condition;
 use crate::core::server_versions::{
-    MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE, MIN_SERVER_VER_CASH_QTY, MIN_SERVER_VER_D_PEG_ORDERS,
+    MIN_CLIENT_VER, MIN_SERVER_VER_AUTO_CANCEL_PARENT, MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE,
+    MIN_SERVER_VER_CASH_QTY, MIN_SERVER_VER_DURATION, MIN_SERVER_VER_D_PEG_ORDERS,
     MIN_SERVER_VER_FRACTIONAL_POSITIONS, MIN_SERVER_VER_MODELS_SUPPORT,
-    MIN_SERVER_VER_ORDER_CONTAINER, MIN_SERVER_VER_PEGGED_TO_BENCHMARK,
-    MIN_SERVER_VER_PRICE_MGMT_ALGO, MIN_SERVER_VER_SOFT_DOLLAR_TIER, MIN_SERVER_VER_SSHORTX_OLD,
-    MIN_SERVER_VER_WHAT_IF_EXT_FIELDS,
+    MIN_SERVER_VER_ORDER_CONTAINER, MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS,
+    MIN_SERVER_VER_PEGGED_TO_BENCHMARK, MIN_SERVER_VER_POST_TO_ATS, MIN_SERVER_VER_PRICE_MGMT_ALGO,
+    MIN_SERVER_VER_SOFT_DOLLAR_TIER, MIN_SERVER_VER_SSHORTX_OLD, MIN_SERVER_VER_WHAT_IF_EXT_FIELDS,
 };
 
 //==================================================================================================
@@ -49,8 +48,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     pub(crate) fn decode_completed(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         // read contract fields
         self.decode_contract_fields(fields_iter)?;
 
@@ -108,19 +107,20 @@ impl<'a> OrderDecoder<'a> {
         self.decode_auto_cancel_date(fields_iter)?;
         self.decode_filled_quantity(fields_iter)?;
         self.decode_ref_futures_con_id(fields_iter)?;
-        self.decode_auto_cancel_parent(fields_iter)?;
+        self.decode_auto_cancel_parent(fields_iter, MIN_CLIENT_VER)?;
         self.decode_shareholder(fields_iter)?;
         self.decode_imbalance_only(fields_iter)?;
         self.decode_route_marketable_to_bbo(fields_iter)?;
         self.decode_parent_perm_id(fields_iter)?;
         self.decode_completed_time(fields_iter)?;
         self.decode_completed_status(fields_iter)?;
+        self.decode_peg_best_peg_mid_order_attributes(fields_iter)?;
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn decode_open(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    pub fn decode_open(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.decode_order_id(fields_iter)?;
 
         // read contract fields
@@ -190,27 +190,28 @@ impl<'a> OrderDecoder<'a> {
         self.decode_is_oms_containers(fields_iter)?;
         self.decode_discretionary_up_to_limit_price(fields_iter)?;
         self.decode_use_price_mgmt_algo(fields_iter)?;
+        self.decode_duration(fields_iter)?;
+        self.decode_post_to_ats(fields_iter)?;
+        self.decode_auto_cancel_parent(fields_iter, MIN_SERVER_VER_AUTO_CANCEL_PARENT)?;
+        self.decode_peg_best_peg_mid_order_attributes(fields_iter)?;
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_order_id(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_order_id(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.order_id = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_contract_fields(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_contract_fields(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.contract.con_id = decode_i32(fields_iter)?;
         self.contract.symbol = decode_string(fields_iter)?;
-        self.contract.sec_type = decode_string(fields_iter)?;
+        self.contract.sec_type = decode_string(fields_iter)?.into();
         self.contract.last_trade_date_or_contract_month = decode_string(fields_iter)?;
         self.contract.strike = decode_f64(fields_iter)?;
-        self.contract.right = decode_string(fields_iter)?;
+        self.contract.right = decode_right(fields_iter)?;
         if self.version >= 32 {
             self.contract.multiplier = decode_string(fields_iter)?;
         }
@@ -225,16 +226,13 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_action(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_action(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.action = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_total_quantity(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_total_quantity(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
             self.order.total_quantity = decode_f64(fields_iter)?;
         } else {
@@ -244,13 +242,13 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_order_type(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_order_type(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.order_type = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_lmt_price(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_lmt_price(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.version < 29 {
             self.order.lmt_price = decode_f64(fields_iter)?;
         } else {
@@ -260,7 +258,7 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_aux_price(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_aux_price(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.version < 30 {
             self.order.aux_price = decode_f64(fields_iter)?;
         } else {
@@ -271,99 +269,87 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_tif(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_tif(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.tif = decode_string(fields_iter)?;
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_oca_group(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_oca_group(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.oca_group = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_account(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_account(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.account = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_open_close(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_open_close(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.open_close = decode_string(fields_iter)?;
 
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_origin(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
-        self.order.origin = FromPrimitive::from_i32(decode_i32(fields_iter)?).unwrap();
+    fn decode_origin(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        self.order.origin = decode_enum(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_order_ref(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_order_ref(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.order_ref = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_client_id(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_client_id(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.client_id = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_perm_id(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_perm_id(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.perm_id = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_outside_rth(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_outside_rth(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.outside_rth = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_hidden(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_hidden(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.hidden = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_discretionary_amt(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_discretionary_amt(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.discretionary_amt = decode_f64(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_good_after_time(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_good_after_time(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.good_after_time = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn skip_shares_allocation(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn skip_shares_allocation(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         decode_string(fields_iter)?; // deprecated
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_faparams(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_faparams(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.fa_group = decode_string(fields_iter)?;
         self.order.fa_method = decode_string(fields_iter)?;
         self.order.fa_percentage = decode_string(fields_iter)?;
@@ -372,7 +358,7 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_model_code(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_model_code(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_MODELS_SUPPORT {
             self.order.model_code = decode_string(fields_iter)?;
         }
@@ -380,42 +366,30 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_good_till_date(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_good_till_date(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.good_till_date = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_rule80a(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_rule80a(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.rule80a = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_percent_offset(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_percent_offset(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.percent_offset = decode_f64_show_unset(fields_iter)?;
         Ok(())
     }
 
-    fn decode_settling_firm(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_settling_firm(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.settling_firm = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_short_sale_params(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_short_sale_params(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.short_sale_slot = decode_i32(fields_iter)?;
         self.order.designated_location = decode_string(fields_iter)?;
         if self.server_version == MIN_SERVER_VER_SSHORTX_OLD {
@@ -427,19 +401,13 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_auction_strategy(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
-        self.order.auction_strategy = FromPrimitive::from_i32(decode_i32(fields_iter)?).unwrap();
+    fn decode_auction_strategy(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        self.order.auction_strategy = decode_enum(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_box_order_params(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_box_order_params(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.starting_price = decode_f64_show_unset(fields_iter)?;
         self.order.stock_ref_price = decode_f64_show_unset(fields_iter)?;
         self.order.delta = decode_f64_show_unset(fields_iter)?;
@@ -449,109 +417,85 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_peg_to_stk_or_vol_order_params(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         self.order.stock_range_lower = decode_f64_show_unset(fields_iter)?;
         self.order.stock_range_upper = decode_f64_show_unset(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_display_size(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_display_size(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.display_size = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_block_order(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_block_order(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.block_order = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
 
-    fn decode_sweep_to_fill(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_sweep_to_fill(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.sweep_to_fill = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_all_or_none(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_all_or_none(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.all_or_none = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_min_qty(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_min_qty(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.min_qty = decode_i32_show_unset(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_oca_type(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_oca_type(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.oca_type = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_etrade_only(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_etrade_only(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.e_trade_only = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_firm_quote_only(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_firm_quote_only(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.firm_quote_only = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_nbbo_price_cap(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_nbbo_price_cap(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.nbbo_price_cap = decode_f64_show_unset(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_parent_id(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_parent_id(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.parent_id = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_trigger_method(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_trigger_method(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.trigger_method = decode_i32(fields_iter)?;
         Ok(())
     }
 
     fn decode_vol_order_params(
         &mut self,
-        fields_iter: &mut Iter<String>,
+        fields_iter: &mut Iter<&str>,
         read_open_order_attribs: bool,
-    ) -> Result<(), IBKRApiLibError> {
+    ) -> Result<(), IbkrError> {
         self.order.volatility = decode_f64_show_unset(fields_iter)?;
         self.order.volatility_type = decode_i32(fields_iter)?;
         self.order.delta_neutral_order_type = decode_string(fields_iter)?;
@@ -581,10 +525,7 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_trail_params(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_trail_params(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.trail_stop_price = decode_f64_show_unset(fields_iter)?;
         if self.version >= 30 {
             self.order.trailing_percent = decode_f64_show_unset(fields_iter)?;
@@ -593,44 +534,43 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_basis_points(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_basis_points(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.basis_points = decode_f64_show_unset(fields_iter)?;
         self.order.basis_points_type = decode_i32_show_unset(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_combo_legs(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_combo_legs(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.contract.combo_legs_descrip = decode_string(fields_iter)?;
 
         if self.version >= 29 {
             let combo_legs_count = decode_i32(fields_iter)?;
 
-            self.contract.combo_legs = vec![
-                ComboLeg {
-                    con_id: decode_i32(fields_iter)?,
-                    ratio: decode_f64(fields_iter)?,
-                    action: decode_string(fields_iter)?,
-                    exchange: decode_string(fields_iter)?,
-                    open_close: FromPrimitive::from_i32(decode_i32(fields_iter)?).unwrap(),
-                    short_sale_slot: decode_i32(fields_iter)?,
-                    designated_location: decode_string(fields_iter)?,
-                    exempt_code: decode_i32(fields_iter)?,
-                };
-                combo_legs_count as _
-            ];
+            self.contract.combo_legs = (0..combo_legs_count)
+                .map(|_| {
+                    Ok(ComboLeg {
+                        con_id: decode_i32(fields_iter)?,
+                        ratio: decode_f64(fields_iter)?,
+                        action: decode_string(fields_iter)?,
+                        exchange: decode_string(fields_iter)?,
+                        open_close: decode_enum(fields_iter)?,
+                        short_sale_slot: decode_i32(fields_iter)?,
+                        designated_location: decode_string(fields_iter)?,
+                        exempt_code: decode_i32(fields_iter)?,
+                    })
+                })
+                .collect::<Result<_, IbkrError>>()?;
         }
         let order_combo_legs_count = decode_i32(fields_iter)?;
 
-        self.order.order_combo_legs = vec![
-            OrderComboLeg {
-                price: decode_f64_show_unset(fields_iter)?,
-            };
-            order_combo_legs_count as _
-        ];
+        self.order.order_combo_legs = (0..order_combo_legs_count)
+            .map(|_| {
+                Ok(OrderComboLeg {
+                    price: decode_f64_show_unset(fields_iter)?,
+                })
+            })
+            .collect::<Result<_, IbkrError>>()?;
 
         Ok(())
     }
@@ -638,27 +578,19 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_smart_combo_routing_params(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.version >= 26 {
             let smart_combo_routing_params_count = decode_i32(fields_iter)?;
 
-            self.order.smart_combo_routing_params = vec![
-                TagValue {
-                    tag: decode_string(fields_iter)?,
-                    value: decode_string(fields_iter)?,
-                };
-                smart_combo_routing_params_count as _
-            ];
+            self.order.smart_combo_routing_params =
+                decode_tag_values(fields_iter, smart_combo_routing_params_count)?;
         }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_scale_order_params(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_scale_order_params(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.scale_init_level_size = decode_i32_show_unset(fields_iter)?;
 
         if self.version >= 20 {
@@ -683,10 +615,7 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_hedge_params(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_hedge_params(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.version >= 24 {
             self.order.hedge_type = decode_string(fields_iter)?;
         }
@@ -699,8 +628,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_opt_out_smart_routing(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.version >= 25 {
             self.order.opt_out_smart_routing = decode_bool(fields_iter)?;
         }
@@ -708,17 +637,14 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_clearing_params(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_clearing_params(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.clearing_account = decode_string(fields_iter)?;
         self.order.clearing_intent = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_not_held(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_not_held(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.version >= 22 {
             self.order.not_held = decode_bool(fields_iter)?;
         }
@@ -726,52 +652,34 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_delta_neutral(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_delta_neutral(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.version >= 20 {
             let delta_neutral_contract_present = decode_bool(fields_iter)?;
             if delta_neutral_contract_present {
-                self.contract.delta_neutral_contract =
-                    Option::from(DeltaNeutralContract::default());
-                self.contract
-                    .delta_neutral_contract
-                    .as_mut()
-                    .unwrap()
-                    .con_id = decode_i32(fields_iter)?;
-                self.contract.delta_neutral_contract.as_mut().unwrap().delta =
-                    decode_f64(fields_iter)?;
-                self.contract.delta_neutral_contract.as_mut().unwrap().price =
-                    decode_f64(fields_iter)?;
+                self.contract.delta_neutral_contract = Some(DeltaNeutralContract::new(
+                    decode_i32(fields_iter)?,
+                    decode_f64(fields_iter)?,
+                    decode_f64(fields_iter)?,
+                ));
             }
         }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_algo_params(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_algo_params(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.version >= 21 {
             self.order.algo_strategy = decode_string(fields_iter)?;
             if !self.order.algo_strategy.is_empty() {
                 let algo_params_count = decode_i32(fields_iter)?;
-                self.order.algo_params = vec![
-                    TagValue {
-                        tag: decode_string(fields_iter)?,
-                        value: decode_string(fields_iter)?,
-                    };
-                    algo_params_count as _
-                ];
+                self.order.algo_params = decode_tag_values(fields_iter, algo_params_count)?;
             }
         }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_solicited(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_solicited(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.version >= 33 {
             self.order.solicited = decode_bool(fields_iter)?;
         }
@@ -779,10 +687,7 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_order_status(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_order_status(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order_state.status = decode_string(fields_iter)?;
         Ok(())
     }
@@ -790,8 +695,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_what_if_info_and_commission(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         self.order.what_if = decode_bool(fields_iter)?;
         self.decode_order_status(fields_iter)?;
         if self.server_version >= MIN_SERVER_VER_WHAT_IF_EXT_FIELDS {
@@ -818,8 +723,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_vol_randomize_flags(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.version >= 34 {
             self.order.randomize_size = decode_bool(fields_iter)?;
             self.order.randomize_price = decode_bool(fields_iter)?;
@@ -830,8 +735,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_peg_to_bench_params(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_PEGGED_TO_BENCHMARK
             && self.order.order_type == "PEG BENCH"
         {
@@ -845,19 +750,14 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_conditions(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_conditions(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_PEGGED_TO_BENCHMARK {
             let conditions_size = decode_i32(fields_iter)?;
 
             if conditions_size > 0 {
                 self.order.conditions = vec![];
                 for _ in 0..conditions_size {
-                    let condition_type = FromPrimitive::from_i32(decode_i32(fields_iter)?).unwrap();
-
-                    let mut condition = create_condition(condition_type);
-                    condition.decode(fields_iter)?;
-
-                    self.order.conditions.push(condition);
+                    self.order.conditions.push(decode_condition(fields_iter)?);
                 }
                 self.order.conditions_ignore_rth = decode_bool(fields_iter)?;
                 self.order.conditions_cancel_order = decode_bool(fields_iter)?;
@@ -869,8 +769,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_adjusted_order_params(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_PEGGED_TO_BENCHMARK {
             self.order.adjusted_order_type = decode_string(fields_iter)?;
             self.order.trigger_price = decode_f64(fields_iter)?;
@@ -886,18 +786,15 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_stop_price_and_lmt_price_offset(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         self.order.trail_stop_price = decode_f64(fields_iter)?;
         self.order.lmt_price_offset = decode_f64(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_soft_dollar_tier(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_soft_dollar_tier(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_SOFT_DOLLAR_TIER {
             let name = decode_string(fields_iter)?;
             let value = decode_string(fields_iter)?;
@@ -908,7 +805,7 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_cash_qty(&mut self, fields_iter: &mut Iter<String>) -> Result<(), IBKRApiLibError> {
+    fn decode_cash_qty(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_CASH_QTY {
             self.order.cash_qty = decode_f64(fields_iter)?;
         }
@@ -918,8 +815,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_dont_use_auto_price_for_hedge(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE {
             self.order.dont_use_auto_price_for_hedge = decode_bool(fields_iter)?;
         }
@@ -927,10 +824,7 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_is_oms_containers(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_is_oms_containers(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_ORDER_CONTAINER {
             self.order.is_oms_container = decode_bool(fields_iter)?;
         }
@@ -940,8 +834,8 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_discretionary_up_to_limit_price(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_D_PEG_ORDERS {
             self.order.discretionary_up_to_limit_price = decode_bool(fields_iter)?;
         }
@@ -949,55 +843,45 @@ impl<'a> OrderDecoder<'a> {
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_auto_cancel_date(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_auto_cancel_date(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.auto_cancel_date = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_filled_quantity(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_filled_quantity(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.filled_quantity = decode_f64(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_ref_futures_con_id(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_ref_futures_con_id(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.ref_futures_con_id = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
+    /// Completed orders always carry the flag, open orders only from
+    /// `MIN_SERVER_VER_AUTO_CANCEL_PARENT` on
     fn decode_auto_cancel_parent(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
-        self.order.auto_cancel_parent = decode_bool(fields_iter)?;
+        fields_iter: &mut Iter<&str>,
+        min_server_version: i32,
+    ) -> Result<(), IbkrError> {
+        if self.server_version >= min_server_version {
+            self.order.auto_cancel_parent = decode_bool(fields_iter)?;
+        }
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_shareholder(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_shareholder(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.shareholder = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_imbalance_only(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_imbalance_only(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.imbalance_only = decode_bool(fields_iter)?;
         Ok(())
     }
@@ -1005,35 +889,26 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_route_marketable_to_bbo(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         self.order.route_marketable_to_bbo = decode_bool(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_parent_perm_id(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_parent_perm_id(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order.parent_perm_id = decode_i32(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_completed_time(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_completed_time(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order_state.completed_time = decode_string(fields_iter)?;
         Ok(())
     }
 
     //----------------------------------------------------------------------------------------------
-    fn decode_completed_status(
-        &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+    fn decode_completed_status(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
         self.order_state.completed_status = decode_string(fields_iter)?;
         Ok(())
     }
@@ -1041,11 +916,55 @@ impl<'a> OrderDecoder<'a> {
     //----------------------------------------------------------------------------------------------
     fn decode_use_price_mgmt_algo(
         &mut self,
-        fields_iter: &mut Iter<String>,
-    ) -> Result<(), IBKRApiLibError> {
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
         if self.server_version >= MIN_SERVER_VER_PRICE_MGMT_ALGO {
             self.order.use_price_mgmt_algo = decode_bool(fields_iter)?;
         }
         Ok(())
     }
+
+    //----------------------------------------------------------------------------------------------
+    fn decode_duration(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        if self.server_version >= MIN_SERVER_VER_DURATION {
+            self.order.duration = decode_i32_show_unset(fields_iter)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn decode_post_to_ats(&mut self, fields_iter: &mut Iter<&str>) -> Result<(), IbkrError> {
+        if self.server_version >= MIN_SERVER_VER_POST_TO_ATS {
+            self.order.post_to_ats = decode_i32_show_unset(fields_iter)?;
+        }
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn decode_peg_best_peg_mid_order_attributes(
+        &mut self,
+        fields_iter: &mut Iter<&str>,
+    ) -> Result<(), IbkrError> {
+        if self.server_version >= MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS {
+            self.order.min_trade_qty = decode_i32_show_unset(fields_iter)?;
+            self.order.min_compete_size = decode_i32_show_unset(fields_iter)?;
+            self.order.compete_against_best_offset = decode_f64_show_unset(fields_iter)?;
+            self.order.mid_offset_at_whole = decode_f64_show_unset(fields_iter)?;
+            self.order.mid_offset_at_half = decode_f64_show_unset(fields_iter)?;
+        }
+        Ok(())
+    }
+}
+
+//==================================================================================================
+/// Decodes `count` tag and value pairs
+fn decode_tag_values(fields_iter: &mut Iter<&str>, count: i32) -> Result<Vec<TagValue>, IbkrError> {
+    (0..count)
+        .map(|_| {
+            Ok(TagValue {
+                tag: decode_string(fields_iter)?,
+                value: decode_string(fields_iter)?,
+            })
+        })
+        .collect()
 }
@@ -0,0 +1,672 @@
+//! Write-ahead journal of orders and executions
+//!
+//! An `OrderJournal` set with `EClient::set_order_journal` gets a record of
+//! every order the client is about to send, before it is sent, of every
+//! cancel request and of every order status and execution TWS reports. Each
+//! record is a 4 byte big endian length followed by null terminated fields,
+//! like the messages on the wire, and is synced to disk before the client
+//! goes on. The journal only ever appends; a record cut short by a crash is
+//! cut off when the journal is opened again.
+//!
+//! `read_journal` reads the records back and `JournalState` replays them into
+//! the last known state of every order, to recover after a crash or to look
+//! into what happened afterwards. Unlike the trade store this needs no
+//! database.
+//!
+//! Orders held back by the order throttle are written when they are sent.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::core::common::UNSET_DOUBLE;
+use crate::core::contract::Contract;
+use crate::core::errors::IbkrError;
+use crate::core::execution::Execution;
+use crate::core::order::Order;
+use crate::core::order_tracker::is_final_status;
+
+//==================================================================================================
+/// What an order was placed with
+///
+/// lmt_price, aux_price - None if the order has none
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrderIntent {
+    pub order_id: i32,
+    pub con_id: i32,
+    pub symbol: String,
+    pub sec_type: String,
+    pub exchange: String,
+    pub currency: String,
+    pub action: String,
+    pub total_quantity: f64,
+    pub order_type: String,
+    pub lmt_price: Option<f64>,
+    pub aux_price: Option<f64>,
+    pub tif: String,
+    pub account: String,
+    pub order_ref: String,
+}
+
+impl OrderIntent {
+    pub fn new(order_id: i32, contract: &Contract, order: &Order) -> Self {
+        let price = |price: f64| (price != UNSET_DOUBLE).then_some(price);
+        OrderIntent {
+            order_id,
+            con_id: contract.con_id,
+            symbol: contract.symbol.clone(),
+            sec_type: contract.sec_type.to_string(),
+            exchange: contract.exchange.clone(),
+            currency: contract.currency.clone(),
+            action: order.action.clone(),
+            total_quantity: order.total_quantity,
+            order_type: order.order_type.clone(),
+            lmt_price: price(order.lmt_price),
+            aux_price: price(order.aux_price),
+            tif: order.tif.clone(),
+            account: order.account.clone(),
+            order_ref: order.order_ref.clone(),
+        }
+    }
+}
+
+impl fmt::Display for OrderIntent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, {} {} {} {} {}, lmt_price: {:?}, aux_price: {:?}, order_ref: {}",
+            self.order_id,
+            self.action,
+            self.total_quantity,
+            self.symbol,
+            self.sec_type,
+            self.order_type,
+            self.lmt_price,
+            self.aux_price,
+            self.order_ref
+        )
+    }
+}
+
+//==================================================================================================
+/// An execution of an order
+///
+/// side - `BOT` or `SLD`
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalFill {
+    pub order_id: i32,
+    pub exec_id: String,
+    pub time: String,
+    pub side: String,
+    pub shares: f64,
+    pub price: f64,
+}
+
+impl JournalFill {
+    pub fn new(execution: &Execution) -> Self {
+        JournalFill {
+            order_id: execution.order_id,
+            exec_id: execution.exec_id.clone(),
+            time: execution.time.clone(),
+            side: execution.side.clone(),
+            shares: execution.shares,
+            price: execution.price,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The shares, negative if sold
+    pub fn signed_shares(&self) -> f64 {
+        if self.side == "SLD" {
+            -self.shares
+        } else {
+            self.shares
+        }
+    }
+}
+
+impl fmt::Display for JournalFill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, exec_id: {}, time: {}, {} {} at {}",
+            self.order_id, self.exec_id, self.time, self.side, self.shares, self.price
+        )
+    }
+}
+
+//==================================================================================================
+/// What a journal record is about
+///
+/// Intent - an order is about to be placed or modified
+/// Status - TWS reported the status of an order
+/// Fill - TWS reported an execution
+/// Cancel - the cancellation of an order is about to be requested
+/// GlobalCancel - the cancellation of all orders is about to be requested
+#[derive(Clone, Debug, PartialEq)]
+pub enum JournalEntry {
+    Intent(OrderIntent),
+    Status {
+        order_id: i32,
+        status: String,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+    },
+    Fill(JournalFill),
+    Cancel {
+        order_id: i32,
+    },
+    GlobalCancel,
+}
+
+impl JournalEntry {
+    /// The order the entry is about, None for a global cancel
+    pub fn order_id(&self) -> Option<i32> {
+        match self {
+            JournalEntry::Intent(intent) => Some(intent.order_id),
+            JournalEntry::Fill(fill) => Some(fill.order_id),
+            JournalEntry::Status { order_id, .. } | JournalEntry::Cancel { order_id } => {
+                Some(*order_id)
+            }
+            JournalEntry::GlobalCancel => None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn fields(&self) -> Vec<String> {
+        match self {
+            JournalEntry::Intent(intent) => vec![
+                "INTENT".to_string(),
+                intent.order_id.to_string(),
+                intent.con_id.to_string(),
+                intent.symbol.clone(),
+                intent.sec_type.clone(),
+                intent.exchange.clone(),
+                intent.currency.clone(),
+                intent.action.clone(),
+                intent.total_quantity.to_string(),
+                intent.order_type.clone(),
+                intent
+                    .lmt_price
+                    .map(|price| price.to_string())
+                    .unwrap_or_default(),
+                intent
+                    .aux_price
+                    .map(|price| price.to_string())
+                    .unwrap_or_default(),
+                intent.tif.clone(),
+                intent.account.clone(),
+                intent.order_ref.clone(),
+            ],
+            JournalEntry::Status {
+                order_id,
+                status,
+                filled,
+                remaining,
+                avg_fill_price,
+            } => vec![
+                "STATUS".to_string(),
+                order_id.to_string(),
+                status.clone(),
+                filled.to_string(),
+                remaining.to_string(),
+                avg_fill_price.to_string(),
+            ],
+            JournalEntry::Fill(fill) => vec![
+                "FILL".to_string(),
+                fill.order_id.to_string(),
+                fill.exec_id.clone(),
+                fill.time.clone(),
+                fill.side.clone(),
+                fill.shares.to_string(),
+                fill.price.to_string(),
+            ],
+            JournalEntry::Cancel { order_id } => {
+                vec!["CANCEL".to_string(), order_id.to_string()]
+            }
+            JournalEntry::GlobalCancel => vec!["GLOBAL_CANCEL".to_string()],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn from_fields(fields: &mut Fields) -> io::Result<Self> {
+        let kind = fields.string()?;
+        Ok(match kind.as_str() {
+            "INTENT" => JournalEntry::Intent(OrderIntent {
+                order_id: fields.parse()?,
+                con_id: fields.parse()?,
+                symbol: fields.string()?,
+                sec_type: fields.string()?,
+                exchange: fields.string()?,
+                currency: fields.string()?,
+                action: fields.string()?,
+                total_quantity: fields.parse()?,
+                order_type: fields.string()?,
+                lmt_price: fields.optional()?,
+                aux_price: fields.optional()?,
+                tif: fields.string()?,
+                account: fields.string()?,
+                order_ref: fields.string()?,
+            }),
+            "STATUS" => JournalEntry::Status {
+                order_id: fields.parse()?,
+                status: fields.string()?,
+                filled: fields.parse()?,
+                remaining: fields.parse()?,
+                avg_fill_price: fields.parse()?,
+            },
+            "FILL" => JournalEntry::Fill(JournalFill {
+                order_id: fields.parse()?,
+                exec_id: fields.string()?,
+                time: fields.string()?,
+                side: fields.string()?,
+                shares: fields.parse()?,
+                price: fields.parse()?,
+            }),
+            "CANCEL" => JournalEntry::Cancel {
+                order_id: fields.parse()?,
+            },
+            "GLOBAL_CANCEL" => JournalEntry::GlobalCancel,
+            _ => return Err(invalid_data(format!("unknown journal entry {}", kind))),
+        })
+    }
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JournalEntry::Intent(intent) => write!(f, "intent {}", intent),
+            JournalEntry::Status {
+                order_id,
+                status,
+                filled,
+                remaining,
+                avg_fill_price,
+            } => write!(
+                f,
+                "status order_id: {}, status: {}, filled: {}, remaining: {}, avg_fill_price: {}",
+                order_id, status, filled, remaining, avg_fill_price
+            ),
+            JournalEntry::Fill(fill) => write!(f, "fill {}", fill),
+            JournalEntry::Cancel { order_id } => write!(f, "cancel order_id: {}", order_id),
+            JournalEntry::GlobalCancel => write!(f, "global cancel"),
+        }
+    }
+}
+
+//==================================================================================================
+/// An entry of the journal
+///
+/// sequence - counts up by one from the first record of a journal, starting
+///   at 1
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalRecord {
+    pub sequence: u64,
+    pub time: DateTime<Utc>,
+    pub entry: JournalEntry,
+}
+
+impl JournalRecord {
+    /// The record as written to the journal, with its length prefix
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        let fields = [
+            self.sequence.to_string(),
+            self.time.timestamp_millis().to_string(),
+        ];
+        for field in fields.into_iter().chain(self.entry.fields()) {
+            payload.extend_from_slice(field.as_bytes());
+            payload.push(0);
+        }
+        let mut buf = (payload.len() as u32).to_be_bytes().to_vec();
+        buf.append(&mut payload);
+        buf
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Decodes the payload of a record, without its length prefix
+    pub fn decode(payload: &[u8]) -> Result<Self, IbkrError> {
+        let payload = std::str::from_utf8(payload).map_err(invalid_data)?;
+        let mut fields = Fields(payload.strip_suffix('\0').unwrap_or(payload).split('\0'));
+        let sequence = fields.parse()?;
+        let millis: i64 = fields.parse()?;
+        let time = Utc
+            .timestamp_millis_opt(millis)
+            .single()
+            .ok_or_else(|| invalid_data(format!("invalid journal time {}", millis)))?;
+        let entry = JournalEntry::from_fields(&mut fields)?;
+        Ok(JournalRecord {
+            sequence,
+            time,
+            entry,
+        })
+    }
+}
+
+impl fmt::Display for JournalRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sequence: {}, time: {}, {}",
+            self.sequence,
+            self.time.to_rfc3339(),
+            self.entry
+        )
+    }
+}
+
+//==================================================================================================
+struct Fields<'a>(std::str::Split<'a, char>);
+
+impl Fields<'_> {
+    fn string(&mut self) -> io::Result<String> {
+        self.0
+            .next()
+            .map(String::from)
+            .ok_or_else(|| invalid_data("journal record is missing fields"))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn parse<T>(&mut self) -> io::Result<T>
+    where
+        T: std::str::FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.string()?.parse().map_err(invalid_data)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn optional(&mut self) -> io::Result<Option<f64>> {
+        match self.string()? {
+            field if field.is_empty() => Ok(None),
+            field => field.parse().map(Some).map_err(invalid_data),
+        }
+    }
+}
+
+fn invalid_data<E>(err: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+//==================================================================================================
+/// Reads the records of a journal one by one. A record cut short at the end
+/// ends the records like the end of the data does.
+pub struct JournalReader<R: Read> {
+    reader: R,
+    position: u64,
+}
+
+impl<R: Read> JournalReader<R> {
+    pub fn new(reader: R) -> Self {
+        JournalReader {
+            reader,
+            position: 0,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The number of bytes of the whole records read so far
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The next record, None at the end of the journal
+    pub fn next_record(&mut self) -> Result<Option<JournalRecord>, IbkrError> {
+        let mut len = [0u8; 4];
+        if !self.read_whole(&mut len)? {
+            return Ok(None);
+        }
+        let mut payload = vec![0u8; u32::from_be_bytes(len) as usize];
+        if !self.read_whole(&mut payload)? {
+            return Ok(None);
+        }
+        let record = JournalRecord::decode(&payload)?;
+        self.position += (len.len() + payload.len()) as u64;
+        Ok(Some(record))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn read_whole(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        match self.reader.read_exact(buf) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl<R: Read> Iterator for JournalReader<R> {
+    type Item = Result<JournalRecord, IbkrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+//==================================================================================================
+/// The records of a journal file, none if the file does not exist
+pub fn read_journal(path: impl AsRef<Path>) -> Result<Vec<JournalRecord>, IbkrError> {
+    match File::open(path) {
+        Ok(file) => JournalReader::new(BufReader::new(file)).collect(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err.into()),
+    }
+}
+
+//==================================================================================================
+/// Appends records to a journal file and syncs them to disk
+pub struct OrderJournal {
+    file: File,
+    last_sequence: u64,
+}
+
+impl OrderJournal {
+    /// Opens a journal for appending, creating it if it does not exist. The
+    /// numbering continues from the last record in it and a record cut short
+    /// at the end is cut off.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IbkrError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut reader = JournalReader::new(BufReader::new(&mut file));
+        let mut last_sequence = 0;
+        while let Some(record) = reader.next_record()? {
+            last_sequence = record.sequence;
+        }
+        let len = reader.position();
+        file.set_len(len)?;
+        Ok(OrderJournal {
+            file,
+            last_sequence,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sequence number of the last record appended
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Appends a record of an entry and syncs it to disk. The sequence number
+    /// is only used up if the record was written.
+    pub fn append(&mut self, entry: JournalEntry) -> Result<JournalRecord, IbkrError> {
+        let record = JournalRecord {
+            sequence: self.last_sequence + 1,
+            time: Utc::now(),
+            entry,
+        };
+        self.file.write_all(&record.encode())?;
+        self.file.sync_data()?;
+        self.last_sequence = record.sequence;
+        Ok(record)
+    }
+}
+
+impl fmt::Debug for OrderJournal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderJournal")
+            .field("last_sequence", &self.last_sequence)
+            .finish()
+    }
+}
+
+//==================================================================================================
+/// The state of an order replayed from a journal
+///
+/// intent - None if the journal holds no intent for the order, e.g. for an
+///   order placed by another client
+/// status - `PendingSubmit` until TWS reports a status
+/// fills - the fills by execution id, so an execution reported twice counts
+///   once
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct JournaledOrder {
+    pub intent: Option<OrderIntent>,
+    pub status: String,
+    pub filled: f64,
+    pub remaining: f64,
+    pub avg_fill_price: f64,
+    pub cancel_requested: bool,
+    pub fills: BTreeMap<String, JournalFill>,
+}
+
+impl JournaledOrder {
+    /// Whether the order can still fill
+    pub fn is_working(&self) -> bool {
+        !is_final_status(&self.status)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The quantity of the fills, signed by side
+    pub fn filled_position(&self) -> f64 {
+        self.fills.values().map(JournalFill::signed_shares).sum()
+    }
+}
+
+impl fmt::Display for JournaledOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "status: {}, filled: {}, remaining: {}, avg_fill_price: {}, cancel_requested: {}, \
+             fills: {}",
+            self.status,
+            self.filled,
+            self.remaining,
+            self.avg_fill_price,
+            self.cancel_requested,
+            self.fills.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// The orders of a journal as of its last record
+#[derive(Clone, Debug, Default)]
+pub struct JournalState {
+    orders: BTreeMap<i32, JournaledOrder>,
+    last_sequence: u64,
+}
+
+impl JournalState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replays records in the order they were written
+    pub fn replay<'a>(records: impl IntoIterator<Item = &'a JournalRecord>) -> Self {
+        let mut state = Self::new();
+        for record in records {
+            state.apply(record);
+        }
+        state
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replays a journal file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, IbkrError> {
+        Ok(Self::replay(&read_journal(path)?))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn apply(&mut self, record: &JournalRecord) {
+        self.last_sequence = record.sequence;
+        match &record.entry {
+            JournalEntry::Intent(intent) => {
+                self.order_mut(intent.order_id).intent = Some(intent.clone());
+            }
+            JournalEntry::Status {
+                order_id,
+                status,
+                filled,
+                remaining,
+                avg_fill_price,
+            } => {
+                let order = self.order_mut(*order_id);
+                order.status = status.clone();
+                order.filled = *filled;
+                order.remaining = *remaining;
+                order.avg_fill_price = *avg_fill_price;
+            }
+            JournalEntry::Fill(fill) => {
+                self.order_mut(fill.order_id)
+                    .fills
+                    .insert(fill.exec_id.clone(), fill.clone());
+            }
+            JournalEntry::Cancel { order_id } => {
+                self.order_mut(*order_id).cancel_requested = true;
+            }
+            JournalEntry::GlobalCancel => {
+                for order in self.orders.values_mut().filter(|order| order.is_working()) {
+                    order.cancel_requested = true;
+                }
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn order(&self, order_id: i32) -> Option<&JournaledOrder> {
+        self.orders.get(&order_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn orders(&self) -> &BTreeMap<i32, JournaledOrder> {
+        &self.orders
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ids of the orders that could still fill when the journal ended
+    pub fn working_orders(&self) -> Vec<i32> {
+        self.orders
+            .iter()
+            .filter(|(_, order)| order.is_working())
+            .map(|(order_id, _)| *order_id)
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The sequence number of the last record replayed
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn order_mut(&mut self, order_id: i32) -> &mut JournaledOrder {
+        self.orders
+            .entry(order_id)
+            .or_insert_with(|| JournaledOrder {
+                status: "PendingSubmit".to_string(),
+                ..JournaledOrder::default()
+            })
+    }
+}
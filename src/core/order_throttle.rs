@@ -0,0 +1,334 @@
+//! A cap on the rate of orders a client places
+//!
+//! Independent of the pacing of messages on the wire, an `OrderThrottle` set
+//! with `EClient::set_order_throttle` limits how many orders `place_order`
+//! sends per second and per minute, so that a strategy stuck in a loop cannot
+//! flood TWS with orders. Orders over the limit are either rejected or
+//! queued, see `ThrottleMode`. Queued orders go out, in the order they were
+//! placed, when `EClient::release_throttled_orders` is called after the
+//! limit allows it; cancelling a queued order takes it off the queue and a
+//! global cancel empties the queue. Every order held back or released is
+//! reported as a `ThrottleEvent` to the hook of the throttle.
+//!
+//! What-if orders and the closing orders of the kill switch are not
+//! throttled.
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::contract::Contract;
+use crate::core::order::Order;
+
+//==================================================================================================
+/// What happens to orders over the limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ThrottleMode {
+    /// `place_order` fails
+    #[default]
+    Reject,
+    /// The order is queued until the limit allows it
+    Queue,
+}
+
+//==================================================================================================
+/// What the throttle did with an order
+///
+/// Throttled - the order was over the limit; `queued` tells whether it was
+///   queued or rejected, `wait` how long until the limit allows the next order
+/// Released - a queued order was sent
+/// Dropped - a queued order was cancelled before it was sent
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ThrottleEvent {
+    Throttled {
+        order_id: i32,
+        queued: bool,
+        wait: Duration,
+    },
+    Released {
+        order_id: i32,
+    },
+    Dropped {
+        order_id: i32,
+    },
+}
+
+impl ThrottleEvent {
+    pub fn order_id(&self) -> i32 {
+        match self {
+            ThrottleEvent::Throttled { order_id, .. }
+            | ThrottleEvent::Released { order_id }
+            | ThrottleEvent::Dropped { order_id } => *order_id,
+        }
+    }
+}
+
+impl fmt::Display for ThrottleEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThrottleEvent::Throttled {
+                order_id,
+                queued,
+                wait,
+            } => write!(
+                f,
+                "order {} throttled, {} for {:?}",
+                order_id,
+                if *queued { "queued" } else { "rejected" },
+                wait
+            ),
+            ThrottleEvent::Released { order_id } => write!(f, "order {} released", order_id),
+            ThrottleEvent::Dropped { order_id } => write!(f, "order {} dropped", order_id),
+        }
+    }
+}
+
+//==================================================================================================
+/// An order waiting for the throttle
+#[derive(Clone, Debug)]
+pub struct QueuedOrder {
+    pub order_id: i32,
+    pub contract: Contract,
+    pub order: Order,
+}
+
+//==================================================================================================
+/// The id of the last of queued orders with an order ref
+pub(crate) fn queued_by_ref<'a, I>(queue: I, order_ref: &str) -> Option<i32>
+where
+    I: DoubleEndedIterator<Item = &'a QueuedOrder>,
+{
+    if order_ref.is_empty() {
+        return None;
+    }
+    queue
+        .rev()
+        .find(|queued| queued.order.order_ref == order_ref)
+        .map(|queued| queued.order_id)
+}
+
+//==================================================================================================
+type ThrottleHook = Box<dyn FnMut(&ThrottleEvent) + Send>;
+
+/// Counts the orders sent within the last second and minute and holds back
+/// those over the limits
+#[derive(Default)]
+pub struct OrderThrottle {
+    max_per_second: Option<usize>,
+    max_per_minute: Option<usize>,
+    mode: ThrottleMode,
+    sent: VecDeque<Instant>,
+    queue: VecDeque<QueuedOrder>,
+    hook: Option<ThrottleHook>,
+}
+
+impl OrderThrottle {
+    const SECOND: Duration = Duration::from_secs(1);
+    const MINUTE: Duration = Duration::from_secs(60);
+
+    /// A throttle without limits, see max_per_second() and max_per_minute()
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Limits the orders within any second. A limit of 0 counts as 1.
+    pub fn max_per_second(mut self, max_orders: usize) -> Self {
+        self.max_per_second = Some(max_orders);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Limits the orders within any minute. A limit of 0 counts as 1.
+    pub fn max_per_minute(mut self, max_orders: usize) -> Self {
+        self.max_per_minute = Some(max_orders);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn mode(mut self, mode: ThrottleMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn throttle_mode(&self) -> ThrottleMode {
+        self.mode
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `hook` with every event from now on
+    pub fn set_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&ThrottleEvent) + Send + 'static,
+    {
+        self.hook = Some(Box::new(hook));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// How long to wait at `now` before the next order can be sent
+    pub fn delay(&self, now: Instant) -> Duration {
+        [
+            (self.max_per_second, Self::SECOND),
+            (self.max_per_minute, Self::MINUTE),
+        ]
+        .into_iter()
+        .filter_map(|(max_orders, window)| max_orders.map(|max_orders| (max_orders, window)))
+        .map(|(max_orders, window)| {
+            let in_window = self
+                .sent
+                .iter()
+                .filter(|time| now.duration_since(**time) < window)
+                .count();
+            if in_window < max_orders.max(1) {
+                Duration::ZERO
+            } else {
+                let oldest = self.sent[self.sent.len() - in_window];
+                (oldest + window).saturating_duration_since(now)
+            }
+        })
+        .max()
+        .unwrap_or(Duration::ZERO)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records an order sent at `now`
+    pub fn record(&mut self, now: Instant) {
+        while matches!(self.sent.front(), Some(time) if now.duration_since(*time) >= Self::MINUTE) {
+            self.sent.pop_front();
+        }
+        self.sent.push_back(now);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Decides at `now` whether an order can be sent. If it can, it is
+    /// recorded as sent. Otherwise it is queued or rejected, as the mode
+    /// says, and the event is returned. Orders are queued as long as others
+    /// are waiting, so they go out in order. An order with the id of a
+    /// queued order replaces it in its place.
+    pub fn admit(
+        &mut self,
+        now: Instant,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> Result<(), ThrottleEvent> {
+        let wait = self.delay(now);
+        if wait.is_zero() && self.queue.is_empty() {
+            self.record(now);
+            return Ok(());
+        }
+        let queued = self.mode == ThrottleMode::Queue;
+        if queued {
+            let queued_order = QueuedOrder {
+                order_id,
+                contract: contract.clone(),
+                order: order.clone(),
+            };
+            match self
+                .queue
+                .iter_mut()
+                .find(|queued| queued.order_id == order_id)
+            {
+                Some(queued) => *queued = queued_order,
+                None => self.queue.push_back(queued_order),
+            }
+        }
+        let event = ThrottleEvent::Throttled {
+            order_id,
+            queued,
+            wait,
+        };
+        self.notify(&event);
+        Err(event)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes the queued orders that can be sent at `now` off the queue, and
+    /// records them as sent
+    pub fn release(&mut self, now: Instant) -> Vec<QueuedOrder> {
+        let mut released = vec![];
+        while !self.queue.is_empty() && self.delay(now).is_zero() {
+            if let Some(order) = self.queue.pop_front() {
+                self.record(now);
+                self.notify(&ThrottleEvent::Released {
+                    order_id: order.order_id,
+                });
+                released.push(order);
+            }
+        }
+        released
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Puts released orders that could not be sent back at the front of the
+    /// queue, in their order, and no longer counts them as sent
+    pub fn requeue(&mut self, orders: Vec<QueuedOrder>) {
+        for queued in orders.into_iter().rev() {
+            self.sent.pop_back();
+            self.queue.push_front(queued);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes a queued order off the queue. Returns false if it is not queued.
+    pub fn remove(&mut self, order_id: i32) -> bool {
+        match self
+            .queue
+            .iter()
+            .position(|queued| queued.order_id == order_id)
+        {
+            Some(index) => {
+                self.queue.remove(index);
+                self.notify(&ThrottleEvent::Dropped { order_id });
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Empties the queue
+    pub fn clear(&mut self) {
+        while let Some(queued) = self.queue.pop_front() {
+            self.notify(&ThrottleEvent::Dropped {
+                order_id: queued.order_id,
+            });
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ids of the queued orders, first to go out first
+    pub fn queued(&self) -> Vec<i32> {
+        self.queue.iter().map(|queued| queued.order_id).collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The queued order with an order ref, the last one if there are several.
+    /// None for an empty ref.
+    pub fn queued_by_ref(&self, order_ref: &str) -> Option<i32> {
+        queued_by_ref(self.queue.iter(), order_ref)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn notify(&mut self, event: &ThrottleEvent) {
+        if let Some(hook) = self.hook.as_mut() {
+            hook(event);
+        }
+    }
+}
+
+impl fmt::Debug for OrderThrottle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OrderThrottle")
+            .field("max_per_second", &self.max_per_second)
+            .field("max_per_minute", &self.max_per_minute)
+            .field("mode", &self.mode)
+            .field("queued", &self.queued())
+            .finish()
+    }
+}
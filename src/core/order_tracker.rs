@@ -0,0 +1,135 @@
+//! The latest status of the orders of a client
+//!
+//! `EClient::place_order` starts tracking an order and the decoder updates
+//! it from every `order_status` message, so the client knows which of its
+//! orders are still working without a `Wrapper` having to keep track.
+//!
+//! The tracker also keeps the `order_ref` of the orders, from `place_order`
+//! and from the open orders TWS sends, e.g. after a reconnect. With a
+//! `DuplicateRefPolicy` other than `Allow`, `place_order` does not submit an
+//! order a second time while an order with the same ref is working, which
+//! makes retrying a submission after an error or a reconnect safe.
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+//==================================================================================================
+/// Whether an order with this status can no longer fill
+pub fn is_final_status(status: &str) -> bool {
+    matches!(status, "ApiCancelled" | "Cancelled" | "Filled" | "Inactive")
+}
+
+//==================================================================================================
+/// What `place_order` does with an order whose `order_ref` is that of a
+/// working order with another order id. Orders without a ref are always
+/// placed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DuplicateRefPolicy {
+    /// Places the order
+    #[default]
+    Allow,
+    /// Fails
+    Reject,
+    /// Places it with the order id of the working order, which modifies it
+    Modify,
+}
+
+impl fmt::Display for DuplicateRefPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicateRefPolicy::Allow => write!(f, "allow"),
+            DuplicateRefPolicy::Reject => write!(f, "reject"),
+            DuplicateRefPolicy::Modify => write!(f, "modify"),
+        }
+    }
+}
+
+//==================================================================================================
+/// The statuses and order refs of orders by order id
+#[derive(Clone, Debug, Default)]
+pub struct OrderTracker {
+    statuses: BTreeMap<i32, String>,
+    order_refs: BTreeMap<i32, String>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Tracks an order that has just been placed. An order that is modified
+    /// keeps its status until TWS reports a new one.
+    pub fn track(&mut self, order_id: i32) {
+        self.statuses
+            .entry(order_id)
+            .or_insert_with(|| "PendingSubmit".to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records the status of an order, tracking it if it is not yet
+    pub fn update(&mut self, order_id: i32, status: &str) {
+        self.statuses.insert(order_id, status.to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records the order ref of an order. An empty ref is not recorded.
+    pub fn set_order_ref(&mut self, order_id: i32, order_ref: &str) {
+        if order_ref.is_empty() {
+            self.order_refs.remove(&order_id);
+        } else {
+            self.order_refs.insert(order_id, order_ref.to_string());
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn order_ref(&self, order_id: i32) -> Option<&str> {
+        self.order_refs.get(&order_id).map(String::as_str)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The working order with an order ref, the most recent one if there are
+    /// several. None for an empty ref.
+    pub fn working_order_by_ref(&self, order_ref: &str) -> Option<i32> {
+        if order_ref.is_empty() {
+            return None;
+        }
+        self.order_refs
+            .iter()
+            .rev()
+            .filter(|(_, tracked_ref)| *tracked_ref == order_ref)
+            .map(|(order_id, _)| *order_id)
+            .find(|order_id| self.is_working(*order_id))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn status(&self, order_id: i32) -> Option<&str> {
+        self.statuses.get(&order_id).map(String::as_str)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether an order is tracked and can still fill
+    pub fn is_working(&self, order_id: i32) -> bool {
+        self.status(order_id)
+            .is_some_and(|status| !is_final_status(status))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ids of the orders that can still fill, in ascending order
+    pub fn working_orders(&self) -> Vec<i32> {
+        self.statuses
+            .iter()
+            .filter(|(_, status)| !is_final_status(status))
+            .map(|(order_id, _)| *order_id)
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear(&mut self) {
+        self.statuses.clear();
+        self.order_refs.clear();
+    }
+}
@@ -0,0 +1,132 @@
+//! Typed profit and loss of `EClient::request_profit_and_loss` and
+//! `EClient::request_profit_and_loss_single`
+//!
+//! TWS sends `UNSET_DOUBLE` for values it does not know, e.g. the daily PnL
+//! of a position opened today before the first close. They are `None` here.
+use std::fmt;
+
+use bigdecimal::BigDecimal;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::UNSET_DOUBLE;
+
+//==================================================================================================
+/// A value as sent by TWS, `None` if it is unset
+pub fn pnl_value(value: f64) -> Option<f64> {
+    if value == UNSET_DOUBLE || !value.is_finite() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+//==================================================================================================
+fn fmt_value(value: &Option<f64>) -> String {
+    value.map_or("-".to_string(), |value| value.to_string())
+}
+
+//==================================================================================================
+/// The profit and loss of an account or model
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProfitAndLoss {
+    pub daily: Option<f64>,
+    pub unrealized: Option<f64>,
+    pub realized: Option<f64>,
+}
+
+impl ProfitAndLoss {
+    pub fn new(daily: f64, unrealized: f64, realized: f64) -> Self {
+        ProfitAndLoss {
+            daily: pnl_value(daily),
+            unrealized: pnl_value(unrealized),
+            realized: pnl_value(realized),
+        }
+    }
+}
+
+impl fmt::Display for ProfitAndLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "daily: {}, unrealized: {}, realized: {}",
+            fmt_value(&self.daily),
+            fmt_value(&self.unrealized),
+            fmt_value(&self.realized)
+        )
+    }
+}
+
+//==================================================================================================
+/// The profit and loss of one position
+///
+/// position - the size of the position, negative if short
+/// value - the market value of the position
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SingleProfitAndLoss {
+    pub position: BigDecimal,
+    pub daily: Option<f64>,
+    pub unrealized: Option<f64>,
+    pub realized: Option<f64>,
+    pub value: Option<f64>,
+}
+
+impl SingleProfitAndLoss {
+    pub fn new(
+        position: BigDecimal,
+        daily: f64,
+        unrealized: f64,
+        realized: f64,
+        value: f64,
+    ) -> Self {
+        SingleProfitAndLoss {
+            position,
+            daily: pnl_value(daily),
+            unrealized: pnl_value(unrealized),
+            realized: pnl_value(realized),
+            value: pnl_value(value),
+        }
+    }
+}
+
+impl fmt::Display for SingleProfitAndLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "position: {}, daily: {}, unrealized: {}, realized: {}, value: {}",
+            self.position,
+            fmt_value(&self.daily),
+            fmt_value(&self.unrealized),
+            fmt_value(&self.realized),
+            fmt_value(&self.value)
+        )
+    }
+}
+
+//==================================================================================================
+/// Event of a profit and loss subscription streamed through a channel
+/// instead of the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum PnlEvent {
+    /// The current profit and loss, sent about once a second
+    Update(ProfitAndLoss),
+    Error {
+        code: i32,
+        message: String,
+    },
+}
+
+//==================================================================================================
+/// Event of a profit and loss subscription of one position streamed through
+/// a channel instead of the `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum PnlSingleEvent {
+    /// The current profit and loss, sent about once a second
+    Update(SingleProfitAndLoss),
+    Error {
+        code: i32,
+        message: String,
+    },
+}
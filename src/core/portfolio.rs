@@ -0,0 +1,354 @@
+//! A local portfolio maintained from executions and positions
+//!
+//! `Portfolio` nets the executions of `Wrapper::exec_details` into one
+//! position per account and contract, with the average cost and the
+//! realized PnL of the closed part. Positions received from TWS, e.g. on
+//! startup, replace the netted ones. Marking the positions with prices, for
+//! example from the `QuoteCache`, gives their market value and unrealized
+//! PnL. A `CurrencyConverter` sums them up in the base currency.
+//!
+//! Prices and average costs are per unit of the contract; PnL and values
+//! are in the currency of the contract and include the multiplier.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::CommissionReport;
+use crate::core::contract::Contract;
+use crate::core::currency::{Conversion, CurrencyConverter};
+use crate::core::execution::Execution;
+use crate::core::positions::Position;
+use crate::core::quote_cache::QuoteCache;
+
+//==================================================================================================
+/// The multiplier of a contract, 1 if it has none
+pub fn contract_multiplier(contract: &Contract) -> f64 {
+    contract
+        .multiplier
+        .trim()
+        .parse()
+        .ok()
+        .filter(|multiplier: &f64| *multiplier > 0.0)
+        .unwrap_or(1.0)
+}
+
+//==================================================================================================
+/// The position of an account in one contract
+///
+/// quantity - the size of the position, negative if short
+/// average_cost - the average price the position was opened at
+/// realized_pnl - the PnL of the closed parts of the position, without
+///                commissions
+/// commissions - the commissions of the executions of the position
+/// market_price - the price the position was last marked with
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PortfolioPosition {
+    pub account: String,
+    pub contract: Contract,
+    pub quantity: f64,
+    pub average_cost: f64,
+    pub realized_pnl: f64,
+    pub commissions: f64,
+    pub market_price: Option<f64>,
+}
+
+impl PortfolioPosition {
+    pub fn new(account: &str, contract: Contract) -> Self {
+        PortfolioPosition {
+            account: account.to_string(),
+            contract,
+            quantity: 0.0,
+            average_cost: 0.0,
+            realized_pnl: 0.0,
+            commissions: 0.0,
+            market_price: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn multiplier(&self) -> f64 {
+        contract_multiplier(&self.contract)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_flat(&self) -> bool {
+        self.quantity == 0.0
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// What the position cost to open
+    pub fn cost_basis(&self) -> f64 {
+        self.quantity * self.average_cost * self.multiplier()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The value of the position at the market price
+    pub fn market_value(&self) -> Option<f64> {
+        self.market_price
+            .map(|price| self.quantity * price * self.multiplier())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The PnL of the open position at the market price
+    pub fn unrealized_pnl(&self) -> Option<f64> {
+        self.market_value()
+            .map(|market_value| market_value - self.cost_basis())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a fill to the position. Fills against the position close it
+    /// first and realize the PnL of the closed part; what is left opens a
+    /// new position at the fill price.
+    ///
+    /// # Arguments
+    /// * quantity - the size of the fill, negative for sells
+    /// * price - the price of the fill
+    pub fn fill(&mut self, quantity: f64, price: f64) {
+        if quantity == 0.0 {
+            return;
+        }
+        if self.quantity == 0.0 || self.quantity.signum() == quantity.signum() {
+            let total = self.quantity + quantity;
+            self.average_cost = (self.quantity * self.average_cost + quantity * price) / total;
+            self.quantity = total;
+            return;
+        }
+
+        let closed = quantity.abs().min(self.quantity.abs()) * self.quantity.signum();
+        self.realized_pnl += closed * (price - self.average_cost) * self.multiplier();
+        self.quantity += quantity;
+        if self.quantity == 0.0 {
+            self.average_cost = 0.0;
+        } else if self.quantity.signum() == quantity.signum() {
+            // The fill reversed the position
+            self.average_cost = price;
+        }
+    }
+}
+
+impl fmt::Display for PortfolioPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, local_symbol: {}, quantity: {}, average_cost: {}, realized_pnl: {}, \
+             commissions: {}, market_price: {:?}",
+            self.account,
+            self.contract.local_symbol,
+            self.quantity,
+            self.average_cost,
+            self.realized_pnl,
+            self.commissions,
+            self.market_price
+        )
+    }
+}
+
+//==================================================================================================
+/// The positions of all accounts, netted from executions and positions
+///
+/// Executions are applied once per `exec_id`, so the same executions can be
+/// fed from `request_executions` and from the live `exec_details`
+/// callbacks.
+#[derive(Clone, Debug, Default)]
+pub struct Portfolio {
+    positions: BTreeMap<(String, i32), PortfolioPosition>,
+    executions: HashMap<String, (String, i32)>,
+    commissions: HashSet<String>,
+}
+
+impl Portfolio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Nets an execution into the position of its account and contract.
+    /// Returns false if the execution has already been applied.
+    pub fn apply_execution(&mut self, contract: &Contract, execution: &Execution) -> bool {
+        if self.executions.contains_key(&execution.exec_id) {
+            return false;
+        }
+        let quantity = match execution.side.as_str() {
+            "BOT" => execution.shares,
+            "SLD" => -execution.shares,
+            _ => return false,
+        };
+        let key = (execution.acct_number.clone(), contract.con_id);
+        self.executions
+            .insert(execution.exec_id.clone(), key.clone());
+        self.positions
+            .entry(key)
+            .or_insert_with(|| PortfolioPosition::new(&execution.acct_number, contract.clone()))
+            .fill(quantity, execution.price);
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds the commission of an execution to its position. Returns false
+    /// for reports of unknown executions and reports that have already been
+    /// applied, which are ignored.
+    pub fn apply_commission(&mut self, report: &CommissionReport) -> bool {
+        let key = match self.executions.get(&report.exec_id) {
+            Some(key) => key,
+            None => return false,
+        };
+        if !self.commissions.insert(report.exec_id.clone()) {
+            return false;
+        }
+        match self.positions.get_mut(key) {
+            Some(position) => {
+                position.commissions += report.commission;
+                true
+            }
+            None => false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Replaces the quantity and average cost of a position with the ones
+    /// reported by TWS, keeping its realized PnL, commissions and price
+    pub fn set_position(&mut self, position: &Position) {
+        let key = (position.account.clone(), position.contract.con_id);
+        let entry = self.positions.entry(key).or_insert_with(|| {
+            PortfolioPosition::new(&position.account, position.contract.clone())
+        });
+        entry.quantity = position.size.to_string().parse().unwrap_or(0.0);
+        // TWS includes the multiplier in the average cost
+        entry.average_cost = if entry.quantity == 0.0 {
+            0.0
+        } else {
+            position.avg_cost / contract_multiplier(&position.contract)
+        };
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the market price of the positions in a contract
+    pub fn mark(&mut self, con_id: i32, price: f64) {
+        for position in self
+            .positions
+            .values_mut()
+            .filter(|position| position.contract.con_id == con_id)
+        {
+            position.market_price = Some(price);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Marks the positions with the last price of the quotes of their
+    /// contracts, or the midpoint if there is no last price. Positions
+    /// without a quote keep their price.
+    pub fn mark_with_quotes(&mut self, quotes: &QuoteCache) {
+        for position in self.positions.values_mut() {
+            if let Some(price) = quotes
+                .contract_quote(position.contract.con_id)
+                .and_then(|quote| quote.last.or_else(|| quote.midpoint()))
+            {
+                position.market_price = Some(price);
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn position(&self, account: &str, con_id: i32) -> Option<&PortfolioPosition> {
+        self.positions.get(&(account.to_string(), con_id))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// All positions, including flat ones with realized PnL, ordered by
+    /// account and contract
+    pub fn positions(&self) -> impl Iterator<Item = &PortfolioPosition> {
+        self.positions.values()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The positions of an account that are not flat
+    pub fn open_positions<'a>(
+        &'a self,
+        account: &'a str,
+    ) -> impl Iterator<Item = &'a PortfolioPosition> + 'a {
+        self.positions
+            .values()
+            .filter(move |position| position.account == account && !position.is_flat())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The realized PnL of an account per currency, without commissions
+    pub fn realized_pnl(&self, account: &str) -> BTreeMap<String, f64> {
+        self.sum_by_currency(account, |position| Some(position.realized_pnl))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The unrealized PnL of the marked positions of an account per
+    /// currency
+    pub fn unrealized_pnl(&self, account: &str) -> BTreeMap<String, f64> {
+        self.sum_by_currency(account, |position| position.unrealized_pnl())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The net exposure of an account per currency: the market value of its
+    /// positions, or their cost basis if they are not marked
+    pub fn exposure(&self, account: &str) -> BTreeMap<String, f64> {
+        self.sum_by_currency(account, |position| {
+            Some(
+                position
+                    .market_value()
+                    .unwrap_or_else(|| position.cost_basis()),
+            )
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The net exposure of an account in one currency, e.g. the base
+    /// currency of the account. Fails with the currencies whose rate is not
+    /// known.
+    pub fn base_exposure(
+        &self,
+        account: &str,
+        converter: &CurrencyConverter,
+        base_currency: &str,
+    ) -> Result<Conversion, Vec<String>> {
+        converter.convert_sum(&self.exposure(account), base_currency)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The unrealized PnL of the marked positions of an account in one
+    /// currency
+    pub fn base_unrealized_pnl(
+        &self,
+        account: &str,
+        converter: &CurrencyConverter,
+        base_currency: &str,
+    ) -> Result<Conversion, Vec<String>> {
+        converter.convert_sum(&self.unrealized_pnl(account), base_currency)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.executions.clear();
+        self.commissions.clear();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn sum_by_currency<F>(&self, account: &str, value: F) -> BTreeMap<String, f64>
+    where
+        F: Fn(&PortfolioPosition) -> Option<f64>,
+    {
+        let mut sums = BTreeMap::new();
+        for position in self
+            .positions
+            .values()
+            .filter(|position| position.account == account)
+        {
+            if let Some(value) = value(position) {
+                *sums
+                    .entry(position.contract.currency.clone())
+                    .or_insert(0.0) += value;
+            }
+        }
+        sums
+    }
+}
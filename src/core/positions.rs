@@ -0,0 +1,130 @@
+//! Typed positions of `EClient::request_positions` and
+//! `EClient::request_positions_multi`
+use std::fmt;
+use std::time::Duration;
+
+use bigdecimal::{BigDecimal, Zero};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::contract::Contract;
+use crate::core::errors::{IbkrError, ServerError};
+use crate::core::subscription::Subscription;
+
+//==================================================================================================
+/// A position of an account
+///
+/// size - the size of the position, negative if short
+/// avg_cost - the average cost per contract, including the multiplier
+/// model_code - the model the position belongs to, empty for positions of
+///              `request_positions`
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Position {
+    pub account: String,
+    pub contract: Contract,
+    pub size: BigDecimal,
+    pub avg_cost: f64,
+    pub model_code: String,
+}
+
+impl Position {
+    pub fn new(
+        account: &str,
+        contract: Contract,
+        size: BigDecimal,
+        avg_cost: f64,
+        model_code: &str,
+    ) -> Self {
+        Position {
+            account: account.to_string(),
+            contract,
+            size,
+            avg_cost,
+            model_code: model_code.to_string(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the position has been closed. TWS keeps sending closed
+    /// positions until the end of the day.
+    pub fn is_closed(&self) -> bool {
+        self.size.is_zero()
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, local_symbol: {}, size: {}, avg_cost: {}, model_code: {}",
+            self.account, self.contract.local_symbol, self.size, self.avg_cost, self.model_code
+        )
+    }
+}
+
+//==================================================================================================
+/// Event of a positions request streamed through a channel instead of the
+/// `Wrapper` callbacks
+#[derive(Clone, Debug)]
+pub enum PositionEvent {
+    Position(Box<Position>),
+    /// All positions have been sent once; changes follow as they happen
+    End,
+    /// The request failed; only sent for `request_positions_multi`
+    Error {
+        code: i32,
+        message: String,
+    },
+}
+
+//==================================================================================================
+/// Replaces the position of the same account, model and contract in
+/// `positions`, or adds it
+pub fn update_positions(positions: &mut Vec<Position>, position: Position) {
+    match positions.iter_mut().find(|old| {
+        old.account == position.account
+            && old.model_code == position.model_code
+            && old.contract.con_id == position.contract.con_id
+    }) {
+        Some(old) => *old = position,
+        None => positions.push(position),
+    }
+}
+
+//==================================================================================================
+/// Receives the positions of a subscription until TWS has sent all of them.
+/// Positions sent again replace the earlier ones and closed positions are
+/// left out.
+///
+/// # Arguments
+/// * subscription - a subscription of `EClient::stream_positions` or
+///   `EClient::stream_positions_multi`
+/// * timeout - how long to wait for each of the positions
+pub fn collect_positions(
+    subscription: &Subscription<PositionEvent>,
+    timeout: Duration,
+) -> Result<Vec<Position>, IbkrError> {
+    let mut positions = vec![];
+    loop {
+        match subscription.recv_timeout(timeout) {
+            Ok(PositionEvent::Position(position)) => update_positions(&mut positions, *position),
+            Ok(PositionEvent::End) => break,
+            Ok(PositionEvent::Error { code, message }) => {
+                return Err(IbkrError::Server(ServerError::new(
+                    subscription.request_id(),
+                    code,
+                    &message,
+                )));
+            }
+            Err(_) => {
+                return Err(IbkrError::timeout(
+                    subscription.request_id(),
+                    "the positions",
+                ));
+            }
+        }
+    }
+    positions.retain(|position| !position.is_closed());
+    Ok(positions)
+}
@@ -0,0 +1,143 @@
+//! Streaming VWAP and TWAP computations over trades and bars
+use std::collections::VecDeque;
+
+use crate::core::common::{BarData, RealTimeBar};
+
+//==================================================================================================
+/// Volume weighted average price and cumulative volume of a session
+#[derive(Clone, Debug, Default)]
+pub struct SessionVwap {
+    notional: f64,
+    volume: f64,
+}
+
+impl SessionVwap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a trade, e.g. from a tick-by-tick `AllLast` stream
+    pub fn update_trade(&mut self, price: f64, size: f64) {
+        if size > 0.0 {
+            self.notional += price * size;
+            self.volume += size;
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a real time bar. Uses the bar's WAP and falls back to its typical
+    /// price (high + low + close) / 3 if TWS did not provide one.
+    pub fn update_real_time_bar(&mut self, bar: &RealTimeBar) {
+        let price = if bar.wap > 0.0 {
+            bar.wap
+        } else {
+            (bar.high + bar.low + bar.close) / 3.0
+        };
+        self.update_trade(price, bar.volume);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a historical bar. Uses the bar's average price and falls back to
+    /// its typical price (high + low + close) / 3 if TWS did not provide one.
+    pub fn update_bar(&mut self, bar: &BarData) {
+        let price = if bar.average > 0.0 {
+            bar.average
+        } else {
+            (bar.high + bar.low + bar.close) / 3.0
+        };
+        self.update_trade(price, bar.volume);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The VWAP so far, `None` before the first trade
+    pub fn vwap(&self) -> Option<f64> {
+        if self.volume > 0.0 {
+            Some(self.notional / self.volume)
+        } else {
+            None
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn cumulative_volume(&self) -> f64 {
+        self.volume
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Starts a new session
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+//==================================================================================================
+/// Time weighted average price over a rolling window. Each price is weighted
+/// by the time until the next price was observed.
+#[derive(Clone, Debug)]
+pub struct RollingTwap {
+    window: i64,
+    // (time in seconds since the epoch, price), oldest first
+    samples: VecDeque<(i64, f64)>,
+}
+
+impl RollingTwap {
+    /// # Arguments
+    /// * window - length of the window in seconds
+    pub fn new(window: i64) -> Self {
+        RollingTwap {
+            window: window.max(1),
+            samples: VecDeque::new(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a price observed at `time` (seconds since the epoch)
+    pub fn update(&mut self, time: i64, price: f64) {
+        self.samples.push_back((time, price));
+        self.expire(time);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds the close of a real time bar at the bar's end
+    pub fn update_real_time_bar(&mut self, bar: &RealTimeBar) {
+        self.update(bar.date_time.timestamp() + 5, bar.close);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The TWAP over the window ending at `now`, `None` before the first
+    /// price
+    pub fn twap(&self, now: i64) -> Option<f64> {
+        let start = now - self.window;
+        let mut weighted = 0.0;
+        let mut duration = 0;
+
+        for (index, (time, price)) in self.samples.iter().enumerate() {
+            let from = (*time).max(start);
+            let until = match self.samples.get(index + 1) {
+                Some((next_time, _)) => (*next_time).min(now),
+                None => now,
+            };
+            if until > from {
+                weighted += price * (until - from) as f64;
+                duration += until - from;
+            }
+        }
+
+        if duration > 0 {
+            Some(weighted / duration as f64)
+        } else {
+            self.samples.back().map(|(_, price)| *price)
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    // Drops the samples that no longer affect the window, keeping the one
+    // whose price is still in effect at the window's start
+    fn expire(&mut self, now: i64) {
+        let start = now - self.window;
+        while self.samples.len() > 1 && self.samples[1].0 <= start {
+            self.samples.pop_front();
+        }
+    }
+}
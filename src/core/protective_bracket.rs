@@ -0,0 +1,285 @@
+//! Protective brackets for existing positions
+//!
+//! `protective_bracket` builds the take-profit limit order and the stop order
+//! that protect a position, as a one-cancels-all pair on the closing side:
+//! selling for long positions and buying for short ones. Both are sized to
+//! the part of the position the working orders do not protect yet, so a
+//! position that already has a take-profit but no stop only gets the stop,
+//! which joins the OCA group of the take-profit. Working orders on the
+//! opening side, e.g. left over from before the position flipped, are
+//! reported as stale rather than counted.
+//!
+//! `attach_protective_bracket` fetches the position from TWS and places the
+//! orders. The orders are GTC and reduce the other order of the pair when
+//! they fill, with overfill protection.
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::NO_VALID_ID;
+use crate::core::contract::Contract;
+use crate::core::errors::IbkrError;
+use crate::core::flatten::closing_contract;
+use crate::core::order::Order;
+use crate::core::positions::Position;
+use crate::core::reconciliation::SIZE_TOLERANCE;
+use crate::core::wrapper::Wrapper;
+
+/// Remaining orders are reduced in size with block, see `Order::oca_type`
+const OCA_REDUCE_WITH_BLOCK: i32 = 2;
+
+//==================================================================================================
+/// The prices of a protective bracket. For a long position the take-profit
+/// must be above the stop, for a short position below it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BracketPrices {
+    pub take_profit: f64,
+    pub stop_loss: f64,
+}
+
+impl BracketPrices {
+    pub fn new(take_profit: f64, stop_loss: f64) -> Self {
+        BracketPrices {
+            take_profit,
+            stop_loss,
+        }
+    }
+}
+
+//==================================================================================================
+/// The orders that protect a position
+///
+/// take_profit, stop_loss - None if the working orders already protect the
+///   whole position on that side; the order ids are set once they have been
+///   placed
+/// oca_group - the group of the new orders, that of a working protective
+///   order if there is one
+/// stale_orders - the ids of working orders on the opening side of the
+///   position
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProtectiveBracket {
+    pub account: String,
+    pub contract: Contract,
+    pub position: f64,
+    pub take_profit: Option<Order>,
+    pub stop_loss: Option<Order>,
+    pub oca_group: String,
+    pub stale_orders: Vec<i32>,
+}
+
+impl ProtectiveBracket {
+    /// The new orders, take-profit first
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.take_profit.iter().chain(self.stop_loss.iter())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the working orders already protect the whole position
+    pub fn is_complete(&self) -> bool {
+        self.take_profit.is_none() && self.stop_loss.is_none()
+    }
+}
+
+impl fmt::Display for ProtectiveBracket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quantity = |order: &Option<Order>| order.as_ref().map(|order| order.total_quantity);
+        write!(
+            f,
+            "account: {}, local_symbol: {}, position: {}, take_profit: {:?}, stop_loss: {:?}, \
+             oca_group: {}, stale_orders: {:?}",
+            self.account,
+            self.contract.local_symbol,
+            self.position,
+            quantity(&self.take_profit),
+            quantity(&self.stop_loss),
+            self.oca_group,
+            self.stale_orders
+        )
+    }
+}
+
+//==================================================================================================
+fn is_stop(order: &Order) -> bool {
+    matches!(
+        order.order_type.as_str(),
+        "STP" | "STP LMT" | "TRAIL" | "TRAIL LIMIT"
+    )
+}
+
+//==================================================================================================
+/// The bracket that protects a position, taking the working orders into
+/// account
+///
+/// # Arguments
+/// * position - a position of a single contract, e.g. from
+///   `EClient::fetch_positions`
+/// * working_orders - the working orders with their contracts, e.g. from
+///   `Wrapper::open_order`; orders of other contracts or accounts are
+///   ignored
+/// * prices - the prices of the new orders
+/// * oca_group - the group of the new orders if no working order has one
+pub fn protective_bracket(
+    position: &Position,
+    working_orders: &[(Contract, Order)],
+    prices: BracketPrices,
+    oca_group: &str,
+) -> Result<ProtectiveBracket, String> {
+    let size: f64 = position.size.to_string().parse().unwrap_or(0.0);
+    let contract = &position.contract;
+    if size.abs() <= SIZE_TOLERANCE {
+        return Err(format!(
+            "The position of {} in {} is flat.",
+            position.account, contract.local_symbol
+        ));
+    }
+    let long = size > 0.0;
+    if (long && prices.take_profit <= prices.stop_loss)
+        || (!long && prices.take_profit >= prices.stop_loss)
+    {
+        return Err(format!(
+            "The take-profit {} and the stop {} are on the wrong sides for a {} position.",
+            prices.take_profit,
+            prices.stop_loss,
+            if long { "long" } else { "short" }
+        ));
+    }
+    let closing_action = if long { "SELL" } else { "BUY" };
+
+    let mut take_profit_quantity = 0.0;
+    let mut stop_quantity = 0.0;
+    let mut working_group = None;
+    let mut stale_orders = vec![];
+    for (_, order) in working_orders.iter().filter(|(working_contract, order)| {
+        working_contract.con_id == contract.con_id
+            && (order.account.is_empty() || order.account == position.account)
+    }) {
+        if order.action != closing_action {
+            stale_orders.push(order.order_id);
+            continue;
+        }
+        if is_stop(order) {
+            stop_quantity += order.total_quantity;
+        } else if order.order_type == "LMT" {
+            take_profit_quantity += order.total_quantity;
+        } else {
+            continue;
+        }
+        if working_group.is_none() && !order.oca_group.is_empty() {
+            working_group = Some(order.oca_group.clone());
+        }
+    }
+    let oca_group = working_group.unwrap_or_else(|| oca_group.to_string());
+
+    let open_quantity = |covered: f64| {
+        let quantity = size.abs() - covered;
+        (quantity > SIZE_TOLERANCE).then_some(quantity)
+    };
+    let protective_order = |mut order: Order| {
+        order.oca_group = oca_group.clone();
+        order.oca_type = OCA_REDUCE_WITH_BLOCK;
+        order.tif = "GTC".to_string();
+        order
+    };
+    let take_profit = open_quantity(take_profit_quantity).map(|quantity| {
+        protective_order(Order::limit_order(
+            &position.account,
+            closing_action,
+            quantity,
+            prices.take_profit,
+        ))
+    });
+    let stop_loss = open_quantity(stop_quantity).map(|quantity| {
+        protective_order(Order::stop_order(
+            &position.account,
+            closing_action,
+            quantity,
+            prices.stop_loss,
+        ))
+    });
+
+    Ok(ProtectiveBracket {
+        account: position.account.clone(),
+        contract: closing_contract(contract),
+        position: size,
+        take_profit,
+        stop_loss,
+        oca_group,
+        stale_orders,
+    })
+}
+
+//==================================================================================================
+/// Fetches the position of an account in a contract and places the orders
+/// that protect it
+///
+/// Returns the bracket with the order ids of the placed orders.
+///
+/// # Arguments
+/// * client - a connected client that is not streaming positions
+/// * contract - the contract of the position, with its `con_id` set
+/// * account - the account holding the position; may be empty if only one
+///   account holds the contract
+/// * working_orders - the working orders with their contracts, see
+///   `protective_bracket`
+/// * prices - the prices of the new orders
+/// * next_order_id - the next valid order id, advanced past the ids used
+/// * timeout - how long to wait for each of the positions
+pub fn attach_protective_bracket<T>(
+    client: &Mutex<EClient<T>>,
+    contract: &Contract,
+    account: &str,
+    working_orders: &[(Contract, Order)],
+    prices: BracketPrices,
+    next_order_id: &mut i32,
+    timeout: Duration,
+) -> Result<ProtectiveBracket, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut client = client.lock().expect(POISONED_MUTEX);
+    let positions: Vec<Position> = client
+        .fetch_positions(timeout)?
+        .into_iter()
+        .filter(|position| {
+            position.contract.con_id == contract.con_id
+                && (account.is_empty() || position.account == account)
+        })
+        .collect();
+    let position = match positions.as_slice() {
+        [position] => position,
+        [] => {
+            return Err(IbkrError::rejected(
+                NO_VALID_ID,
+                &format!("There is no position in {}.", contract.con_id),
+            ))
+        }
+        _ => {
+            return Err(IbkrError::rejected(
+                NO_VALID_ID,
+                &format!(
+                    "Several accounts hold {}; name the account to protect.",
+                    contract.con_id
+                ),
+            ))
+        }
+    };
+    let oca_group = format!("protect-{}-{}", position.account, contract.con_id);
+    let mut bracket = protective_bracket(position, working_orders, prices, &oca_group)
+        .map_err(|message| IbkrError::rejected(NO_VALID_ID, &message))?;
+    for order in bracket
+        .take_profit
+        .iter_mut()
+        .chain(bracket.stop_loss.iter_mut())
+    {
+        order.order_id = *next_order_id;
+        client.place_order(order.order_id, &bracket.contract, order)?;
+        *next_order_id += 1;
+    }
+    Ok(bracket)
+}
@@ -0,0 +1,295 @@
+//! Latest quotes, trading status and auction state of market data
+//! subscriptions
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::TickType;
+
+//==================================================================================================
+/// Trading status of an instrument as reported by the `TickType::Halted` and
+/// `TickType::DelayedHalted` ticks.
+///
+/// Limit-up/limit-down (LULD) trading pauses are reported as
+/// `VolatilityHalt`.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TradingStatus {
+    /// No halt status has been received
+    #[default]
+    NotAvailable = -1,
+    NotHalted = 0,
+    /// Regulatory or news pending halt
+    Halted = 1,
+    /// Volatility pause, e.g. after the price left the LULD band
+    VolatilityHalt = 2,
+}
+
+impl TradingStatus {
+    /// Converts the value of a halted tick, unknown values are mapped to
+    /// `NotAvailable`
+    pub fn from_tick_value(value: f64) -> Self {
+        match value as i32 {
+            0 => TradingStatus::NotHalted,
+            1 => TradingStatus::Halted,
+            2 => TradingStatus::VolatilityHalt,
+            _ => TradingStatus::NotAvailable,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether trading in the instrument is halted or paused
+    pub fn is_halted(&self) -> bool {
+        matches!(self, TradingStatus::Halted | TradingStatus::VolatilityHalt)
+    }
+}
+
+impl fmt::Display for TradingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradingStatus::NotAvailable => write!(f, "NotAvailable"),
+            TradingStatus::NotHalted => write!(f, "NotHalted"),
+            TradingStatus::Halted => write!(f, "Halted"),
+            TradingStatus::VolatilityHalt => write!(f, "VolatilityHalt"),
+        }
+    }
+}
+
+//==================================================================================================
+/// The state of the next auction of an instrument as reported by the
+/// auction ticks, which are requested with the generic tick 225.
+///
+/// price - the indicative price of the auction
+/// volume - the number of shares that would trade in the auction
+/// imbalance - the number of unmatched shares
+/// regulatory_imbalance - the imbalance used to determine whether the
+///                        auction is delayed by the exchange
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AuctionState {
+    pub price: Option<f64>,
+    pub volume: Option<f64>,
+    pub imbalance: Option<f64>,
+    pub regulatory_imbalance: Option<f64>,
+}
+
+impl AuctionState {
+    /// Whether any auction tick has been received
+    pub fn is_available(&self) -> bool {
+        self.price.is_some()
+            || self.volume.is_some()
+            || self.imbalance.is_some()
+            || self.regulatory_imbalance.is_some()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The imbalance relative to the auction volume, if both are known
+    pub fn imbalance_ratio(&self) -> Option<f64> {
+        match (self.imbalance, self.volume) {
+            (Some(imbalance), Some(volume)) if volume != 0.0 => Some(imbalance / volume),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AuctionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "price: {:?}, volume: {:?}, imbalance: {:?}, regulatory_imbalance: {:?}",
+            self.price, self.volume, self.imbalance, self.regulatory_imbalance
+        )
+    }
+}
+
+//==================================================================================================
+/// Whether a tick updates the `AuctionState`
+pub fn is_auction_tick(tick_type: TickType) -> bool {
+    matches!(
+        tick_type,
+        TickType::AuctionPrice
+            | TickType::AuctionVolume
+            | TickType::AuctionImbalance
+            | TickType::RegulatoryImbalance
+    )
+}
+
+//==================================================================================================
+/// The latest values received for a market data subscription. Delayed ticks
+/// update the same fields as their real time counterparts.
+///
+/// con_id - the contract of the subscription, if known
+/// trading_status - the latest halt status
+/// auction - the state of the next auction
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Quote {
+    pub con_id: Option<i32>,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub last: Option<f64>,
+    pub bid_size: Option<f64>,
+    pub ask_size: Option<f64>,
+    pub last_size: Option<f64>,
+    pub volume: Option<f64>,
+    pub trading_status: TradingStatus,
+    pub auction: AuctionState,
+}
+
+impl Quote {
+    /// The midpoint of bid and ask, if both are known
+    pub fn midpoint(&self) -> Option<f64> {
+        match (self.bid, self.ask) {
+            (Some(bid), Some(ask)) => Some((bid + ask) / 2.0),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Quote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "con_id: {:?}, bid: {:?}, ask: {:?}, last: {:?}, bid_size: {:?}, ask_size: {:?}, \
+             last_size: {:?}, volume: {:?}, trading_status: {}, auction: {}",
+            self.con_id,
+            self.bid,
+            self.ask,
+            self.last,
+            self.bid_size,
+            self.ask_size,
+            self.last_size,
+            self.volume,
+            self.trading_status,
+            self.auction
+        )
+    }
+}
+
+//==================================================================================================
+/// Keeps the latest quote of every market data subscription, keyed by ticker
+/// id. Order routing code can check `is_halted` or `is_contract_halted`
+/// before submitting orders.
+#[derive(Debug, Default)]
+pub struct QuoteCache {
+    quotes: HashMap<i32, Quote>,
+}
+
+impl QuoteCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Starts tracking a market data subscription
+    pub fn add_ticker(&mut self, ticker_id: i32, con_id: Option<i32>) {
+        self.quotes.insert(
+            ticker_id,
+            Quote {
+                con_id,
+                ..Quote::default()
+            },
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn remove_ticker(&mut self, ticker_id: i32) {
+        self.quotes.remove(&ticker_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn update_tick_price(&mut self, ticker_id: i32, tick_type: TickType, price: f64) {
+        let quote = self.quotes.entry(ticker_id).or_default();
+        match tick_type {
+            TickType::Bid | TickType::DelayedBid => quote.bid = Some(price),
+            TickType::Ask | TickType::DelayedAsk => quote.ask = Some(price),
+            TickType::Last | TickType::DelayedLast => quote.last = Some(price),
+            TickType::AuctionPrice => quote.auction.price = Some(price),
+            _ => (),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn update_tick_size(&mut self, ticker_id: i32, tick_type: TickType, size: f64) {
+        let quote = self.quotes.entry(ticker_id).or_default();
+        match tick_type {
+            TickType::BidSize | TickType::DelayedBidSize => quote.bid_size = Some(size),
+            TickType::AskSize | TickType::DelayedAskSize => quote.ask_size = Some(size),
+            TickType::LastSize | TickType::DelayedLastSize => quote.last_size = Some(size),
+            TickType::Volume | TickType::DelayedVolume => quote.volume = Some(size),
+            TickType::AuctionVolume => quote.auction.volume = Some(size),
+            TickType::AuctionImbalance => quote.auction.imbalance = Some(size),
+            TickType::RegulatoryImbalance => quote.auction.regulatory_imbalance = Some(size),
+            _ => (),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn update_trading_status(&mut self, ticker_id: i32, trading_status: TradingStatus) {
+        self.quotes.entry(ticker_id).or_default().trading_status = trading_status;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn quote(&self, ticker_id: i32) -> Option<&Quote> {
+        self.quotes.get(&ticker_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn auction_state(&self, ticker_id: i32) -> Option<&AuctionState> {
+        self.quotes.get(&ticker_id).map(|quote| &quote.auction)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The auction state of the first subscription of the contract that
+    /// received any auction tick
+    pub fn contract_auction_state(&self, con_id: i32) -> Option<&AuctionState> {
+        self.quotes
+            .values()
+            .filter(|quote| quote.con_id == Some(con_id))
+            .map(|quote| &quote.auction)
+            .find(|auction| auction.is_available())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The quote of the first subscription of the contract that received a
+    /// last price or both bid and ask
+    pub fn contract_quote(&self, con_id: i32) -> Option<&Quote> {
+        self.quotes.values().find(|quote| {
+            quote.con_id == Some(con_id) && (quote.last.is_some() || quote.midpoint().is_some())
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_halted(&self, ticker_id: i32) -> bool {
+        matches!(self.quotes.get(&ticker_id), Some(quote) if quote.trading_status.is_halted())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether any subscription of the contract reports a halt
+    pub fn is_contract_halted(&self, con_id: i32) -> bool {
+        self.quotes
+            .values()
+            .any(|quote| quote.con_id == Some(con_id) && quote.trading_status.is_halted())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ticker ids of all halted subscriptions
+    pub fn halted_tickers(&self) -> Vec<i32> {
+        let mut tickers: Vec<i32> = self
+            .quotes
+            .iter()
+            .filter(|(_, quote)| quote.trading_status.is_halted())
+            .map(|(ticker_id, _)| *ticker_id)
+            .collect();
+        tickers.sort_unstable();
+        tickers
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn clear(&mut self) {
+        self.quotes.clear();
+    }
+}
@@ -1,5 +1,5 @@
 //! Reads and processes messages from the TCP socket
-use std::io::Read;
+use std::io::{ErrorKind, Read};
 use std::net::Shutdown;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
@@ -8,21 +8,27 @@ use std::sync::Arc;
 use log::*;
 
 use super::streamer::Streamer;
-use crate::core::errors::IBKRApiLibError;
-use crate::core::messages::read_msg;
+use crate::core::errors::{DecodeError, IbkrError};
+use crate::core::messages::{InvalidUtf8Policy, MessageBuffer, RawFieldLog};
+
+/// The number of bytes read from the socket at once
+const READ_SIZE: usize = 4096;
 
 //==================================================================================================
 pub struct Reader {
     stream: Box<dyn Streamer + 'static>,
-    messages: Sender<String>,
+    /// The messages, or the errors of those that could not be read, for the
+    /// decoder to report
+    messages: Sender<Result<String, DecodeError>>,
     disconnect_requested: Arc<AtomicBool>,
     is_connected: bool,
+    buffer: MessageBuffer,
 }
 
 impl Reader {
     pub fn new(
         stream: Box<impl Streamer + 'static>,
-        messages: Sender<String>,
+        messages: Sender<Result<String, DecodeError>>,
         disconnect_requested: Arc<AtomicBool>,
     ) -> Self {
         Reader {
@@ -30,75 +36,89 @@ impl Reader {
             messages,
             disconnect_requested,
             is_connected: true,
+            buffer: MessageBuffer::new(),
         }
     }
 
     //----------------------------------------------------------------------------------------------
-    pub fn recv_packet(&mut self) -> Result<Vec<u8>, IBKRApiLibError> {
-        //debug!("_recv_all_msg");
-        let buf = self._recv_all_msg()?;
-        // receiving 0 bytes outside a timeout means the connection is either
-        // closed or broken
-        if buf.is_empty() && !self.disconnect_requested.load(Ordering::Acquire) {
-            info!("socket either closed or broken, disconnecting");
-            self.stream.shutdown(Shutdown::Both)?;
-            self.is_connected = false;
-        }
-        Ok(buf)
+    /// Sets what is done with messages that are not valid UTF-8, see
+    /// `MessageBuffer::set_utf8_policy`
+    pub fn set_utf8_policy(&mut self, policy: InvalidUtf8Policy, raw_fields: RawFieldLog) {
+        self.buffer.set_utf8_policy(policy, raw_fields);
     }
 
     //----------------------------------------------------------------------------------------------
-    fn _recv_all_msg(&mut self) -> Result<Vec<u8>, IBKRApiLibError> {
-        let mut cont = true;
-        let mut allbuf: Vec<u8> = Vec::new();
-        const NUM_BYTES: usize = 4096;
-
-        while cont {
-            let mut buf: [u8; NUM_BYTES] = [0; NUM_BYTES];
-
-            let bytes_read = self
-                .stream
-                .read(&mut buf)
-                .expect("Couldnt read from reader...");
-            allbuf.extend_from_slice(&buf[0..bytes_read]);
-            //logger.debug("len %d raw:%s|", len(buf), buf)
-
-            if bytes_read < NUM_BYTES {
-                cont = false;
+    /// Reads the next complete message, blocking until it has arrived.
+    /// Returns None if the connection was closed.
+    pub fn next_message(&mut self) -> Result<Option<String>, IbkrError> {
+        loop {
+            if let Some(msg) = self.buffer.next_message()? {
+                return Ok(Some(msg));
+            }
+            if !self.fill_buffer()? {
+                return Ok(None);
             }
         }
-        Ok(allbuf)
     }
 
     //----------------------------------------------------------------------------------------------
-    fn process_reader_msgs(&mut self) -> Result<(), IBKRApiLibError> {
-        // Grab a packet of messages from the socket.
-        let mut message_packet = self.recv_packet()?;
-        //debug!(" recvd size {}", message_packet.len());
+    /// Reads what the socket has and appends it to the buffer. Returns false
+    /// if the connection was closed.
+    fn fill_buffer(&mut self) -> Result<bool, IbkrError> {
+        let mut buf = [0; READ_SIZE];
+        let bytes_read = match self.stream.read(&mut buf) {
+            Ok(bytes_read) => bytes_read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => return Ok(true),
+            Err(err) => {
+                self.is_connected = false;
+                return Err(err.into());
+            }
+        };
 
-        // Read messages from the packet until there are no more.
-        // When this loop ends, break into the outer loop and grab another packet.
-        // Repeat until the connection is closed
-        //
-        let _msg = String::new();
-        while !message_packet.is_empty() {
-            // Read a message from the packet then add it to the message queue below.
-            let (_size, msg, remaining_messages) = read_msg(message_packet.as_slice())?;
+        // receiving 0 bytes outside a timeout means the connection is either
+        // closed or broken
+        if bytes_read == 0 {
+            if !self.disconnect_requested.load(Ordering::Acquire) {
+                info!("socket either closed or broken, disconnecting");
+                self.stream.shutdown(Shutdown::Both)?;
+            }
+            self.is_connected = false;
+            return Ok(false);
+        }
 
-            // Clear the Vec that holds the bytes from the packet and reload with the bytes
-            // that haven't been read. The variable remaining_messages only
-            // holds the unread messages left in the packet
-            message_packet.clear();
-            message_packet.extend_from_slice(remaining_messages.as_slice());
+        self.buffer.extend(&buf[..bytes_read]);
+        Ok(true)
+    }
 
-            if msg.as_str() != "" {
-                self.messages.send(msg).expect("READER CANNOT SEND MESSAGE");
-            } else {
-                // Break to the outer loop in run and get another packet of messages.
-                debug!("more incoming packet(s) are needed ");
-                break;
+    //----------------------------------------------------------------------------------------------
+    fn process_reader_msgs(&mut self) -> Result<(), IbkrError> {
+        if !self.fill_buffer()? {
+            return Ok(());
+        }
+
+        // Pass on every message that is complete. The rest stays in the
+        // buffer until the next read completes it.
+        loop {
+            let msg = match self.buffer.next_message() {
+                Ok(Some(msg)) => Ok(msg),
+                Ok(None) => break,
+                // The message was dropped, the ones after it are passed on
+                // and the decoder reports it to the wrapper
+                Err(IbkrError::Decode(err)) => Err(err),
+                Err(err) => {
+                    error!("{}", err);
+                    continue;
+                }
+            };
+            if self.messages.send(msg).is_err() {
+                error!("The decoder has stopped, stopping the reader");
+                self.is_connected = false;
+                return Ok(());
             }
         }
+        if self.buffer.pending() > 0 {
+            debug!("more incoming packet(s) are needed ");
+        }
 
         Ok(())
     }
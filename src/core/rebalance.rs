@@ -0,0 +1,500 @@
+//! Rebalancing of an account to target weights
+//!
+//! A `Rebalancer` holds the weight of the net liquidation of an account
+//! each contract should make up. `Rebalancer::plan` compares the targets
+//! with the positions of the account and returns the orders that bring the
+//! account to them, with sells before buys so the cash they free is there
+//! for the buys. Target quantities are rounded to the lot size of each
+//! contract and limit prices to its minimum tick; trades smaller than one
+//! lot, the minimum size or the minimum trade value are skipped.
+//!
+//! Contracts are valued at the midpoint or the last price of the quotes of
+//! the client's market data subscriptions, so subscribe to the contracts
+//! with their `con_id` first. The weight is of the market value, including
+//! the multiplier of the contract.
+//!
+//! `rebalance` fetches the net liquidation and the positions from TWS and
+//! either checks the margin impact of the orders as what-if orders or
+//! places them.
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::account_summary_tags::{AccountSummaryTagSet, AccountSummaryTags};
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::{PriceIncrement, NO_VALID_ID, UNSET_DOUBLE};
+use crate::core::contract::{Contract, ContractDetails};
+use crate::core::errors::IbkrError;
+use crate::core::flatten::closing_contract;
+use crate::core::margin::{what_if_basket, BasketMarginProjection};
+use crate::core::market_rules::MarketRule;
+use crate::core::order::Order;
+use crate::core::portfolio::contract_multiplier;
+use crate::core::positions::Position;
+use crate::core::quote_cache::QuoteCache;
+use crate::core::reconciliation::SIZE_TOLERANCE;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// How rebalancing orders are priced
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RebalanceStyle {
+    #[default]
+    Market,
+    /// A limit order at the bid for sells and at the ask for buys, moved
+    /// by `ticks` minimum ticks into the market. Falls back to the last
+    /// price if the side of the quote is missing.
+    MarketableLimit { ticks: u32 },
+}
+
+//==================================================================================================
+/// The weight a contract should make up of the net liquidation of an
+/// account
+///
+/// weight - the market value of the position over the net liquidation,
+///   negative for short positions
+/// lot_size - the quantity orders must be a multiple of
+/// min_size - the smallest quantity of an order
+/// min_tick - the minimum price increment of limit prices
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RebalanceTarget {
+    pub contract: Contract,
+    pub weight: f64,
+    pub lot_size: f64,
+    pub min_size: f64,
+    pub min_tick: f64,
+}
+
+impl RebalanceTarget {
+    /// A target traded in whole units with a minimum tick of 0.01
+    pub fn new(contract: &Contract, weight: f64) -> Self {
+        RebalanceTarget {
+            contract: contract.clone(),
+            weight,
+            lot_size: 1.0,
+            min_size: 0.0,
+            min_tick: 0.01,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A target with the size increment, minimum size and minimum tick of
+    /// the details of its contract, where TWS sent them
+    pub fn from_details(details: &ContractDetails, weight: f64) -> Self {
+        let sent = |value: f64| (value > 0.0 && value != UNSET_DOUBLE).then_some(value);
+        let mut target = Self::new(&details.contract, weight);
+        if let Some(lot_size) = sent(details.size_increment) {
+            target.lot_size = lot_size;
+        }
+        if let Some(min_size) = sent(details.min_size) {
+            target.min_size = min_size;
+        }
+        if let Some(min_tick) = sent(details.min_tick) {
+            target.min_tick = min_tick;
+        }
+        target
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn lot_size(mut self, lot_size: f64) -> Self {
+        self.lot_size = lot_size;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn min_size(mut self, min_size: f64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn min_tick(mut self, min_tick: f64) -> Self {
+        self.min_tick = min_tick;
+        self
+    }
+}
+
+impl fmt::Display for RebalanceTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "con_id: {}, symbol: {}, weight: {}, lot_size: {}, min_size: {}, min_tick: {}",
+            self.contract.con_id,
+            self.contract.symbol,
+            self.weight,
+            self.lot_size,
+            self.min_size,
+            self.min_tick
+        )
+    }
+}
+
+//==================================================================================================
+/// An order that moves a position towards its target
+///
+/// current, target - the position before and after the order
+/// price - the price the contract was valued at
+/// order - the order, with its `order_id` set once it has been placed
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RebalanceOrder {
+    pub contract: Contract,
+    pub weight: f64,
+    pub current: f64,
+    pub target: f64,
+    pub price: Option<f64>,
+    pub order: Order,
+}
+
+impl fmt::Display for RebalanceOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "local_symbol: {}, weight: {}, current: {}, target: {}, order_id: {}, action: {}, \
+             quantity: {}, order_type: {}, limit_price: {}",
+            self.contract.local_symbol,
+            self.weight,
+            self.current,
+            self.target,
+            self.order.order_id,
+            self.order.action,
+            self.order.total_quantity,
+            self.order.order_type,
+            self.order.lmt_price
+        )
+    }
+}
+
+//==================================================================================================
+/// The orders that rebalance an account
+///
+/// orders - sells first, then buys
+/// skipped - the contracts whose trade was too small, with the reason
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RebalancePlan {
+    pub account: String,
+    pub net_liquidation: f64,
+    pub orders: Vec<RebalanceOrder>,
+    pub skipped: Vec<(i32, String)>,
+}
+
+impl RebalancePlan {
+    /// The contracts and orders, e.g. for `margin::what_if_basket`
+    pub fn basket(&self) -> Vec<(Contract, Order)> {
+        self.orders
+            .iter()
+            .map(|order| (order.contract.clone(), order.order.clone()))
+            .collect()
+    }
+}
+
+impl fmt::Display for RebalancePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "account: {}, net_liquidation: {}, orders: {}, skipped: {}",
+            self.account,
+            self.net_liquidation,
+            self.orders.len(),
+            self.skipped.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// The target weights of an account
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rebalancer {
+    targets: Vec<RebalanceTarget>,
+    style: RebalanceStyle,
+    close_untargeted: bool,
+    min_trade_value: f64,
+}
+
+impl Rebalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a target, replacing that of the same contract
+    pub fn target(mut self, target: RebalanceTarget) -> Self {
+        self.targets
+            .retain(|existing| existing.contract.con_id != target.contract.con_id);
+        self.targets.push(target);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn style(mut self, style: RebalanceStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Closes the positions of the account in contracts without a target.
+    /// By default they are left alone.
+    pub fn close_untargeted(mut self, close_untargeted: bool) -> Self {
+        self.close_untargeted = close_untargeted;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Skips trades worth less than `min_trade_value`
+    pub fn min_trade_value(mut self, min_trade_value: f64) -> Self {
+        self.min_trade_value = min_trade_value;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn targets(&self) -> &[RebalanceTarget] {
+        &self.targets
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The orders that bring an account to the target weights
+    ///
+    /// # Arguments
+    /// * account - the account to rebalance
+    /// * net_liquidation - the net liquidation of the account
+    /// * positions - the positions, e.g. from `EClient::fetch_positions`;
+    ///   positions of other accounts are ignored
+    /// * quotes - the quotes to value the contracts and price the orders with
+    pub fn plan(
+        &self,
+        account: &str,
+        net_liquidation: f64,
+        positions: &[Position],
+        quotes: &QuoteCache,
+    ) -> Result<RebalancePlan, String> {
+        if !net_liquidation.is_finite() || net_liquidation <= 0.0 {
+            return Err(format!(
+                "The net liquidation {} of {} cannot be rebalanced.",
+                net_liquidation, account
+            ));
+        }
+        let current = |con_id: i32| -> f64 {
+            positions
+                .iter()
+                .filter(|position| {
+                    position.account == account && position.contract.con_id == con_id
+                })
+                .map(|position| position.size.to_string().parse().unwrap_or(0.0))
+                .sum()
+        };
+
+        let mut targets = self.targets.clone();
+        if self.close_untargeted {
+            let targeted: BTreeSet<i32> = targets
+                .iter()
+                .map(|target| target.contract.con_id)
+                .collect();
+            for position in positions.iter().filter(|position| {
+                position.account == account
+                    && !position.is_closed()
+                    && !targeted.contains(&position.contract.con_id)
+            }) {
+                targets.push(RebalanceTarget::new(&position.contract, 0.0));
+            }
+        }
+
+        let mut plan = RebalancePlan {
+            account: account.to_string(),
+            net_liquidation,
+            ..Default::default()
+        };
+        for target in &targets {
+            if !target.weight.is_finite() || target.lot_size <= 0.0 {
+                return Err(format!(
+                    "The target of {} is not valid.",
+                    target.contract.symbol
+                ));
+            }
+            let con_id = target.contract.con_id;
+            let quote = quotes.contract_quote(con_id);
+            let price = quote
+                .and_then(|quote| quote.midpoint().or(quote.last))
+                .filter(|price| *price > 0.0);
+            let current = current(con_id);
+            let target_quantity = if target.weight == 0.0 {
+                0.0
+            } else {
+                let price = price.ok_or_else(|| {
+                    format!(
+                        "No quote to value {} ({}) with.",
+                        target.contract.symbol, con_id
+                    )
+                })?;
+                let quantity = target.weight * net_liquidation
+                    / (price * contract_multiplier(&target.contract));
+                (quantity / target.lot_size).round() * target.lot_size
+            };
+            let quantity = (target_quantity - current).abs();
+            if quantity <= SIZE_TOLERANCE {
+                continue;
+            }
+            // Closing a position takes all of it, whatever the lot size
+            let closing = target_quantity == 0.0;
+            if !closing
+                && (quantity < target.lot_size - SIZE_TOLERANCE || quantity < target.min_size)
+            {
+                plan.skipped.push((
+                    con_id,
+                    format!(
+                        "The trade of {} is below the lot or minimum size.",
+                        quantity
+                    ),
+                ));
+                continue;
+            }
+            let value = price.map(|price| quantity * price * contract_multiplier(&target.contract));
+            if value.is_some_and(|value| value < self.min_trade_value) {
+                plan.skipped.push((
+                    con_id,
+                    format!(
+                        "The trade of {} is below the minimum trade value.",
+                        quantity
+                    ),
+                ));
+                continue;
+            }
+
+            let selling = target_quantity < current;
+            let action = if selling { "SELL" } else { "BUY" };
+            let order = match self.style {
+                RebalanceStyle::Market => Order::market_order(account, action, quantity),
+                RebalanceStyle::MarketableLimit { ticks } => {
+                    let price = quote
+                        .and_then(|quote| {
+                            if selling { quote.bid } else { quote.ask }.or(quote.last)
+                        })
+                        .filter(|price| *price > 0.0)
+                        .ok_or_else(|| {
+                            format!(
+                                "No quote to price the order of {} with.",
+                                target.contract.symbol
+                            )
+                        })?;
+                    let offset = ticks as f64 * target.min_tick;
+                    let rule = MarketRule::new(0, vec![PriceIncrement::new(0.0, target.min_tick)]);
+                    let limit_price = if selling {
+                        rule.snap_down(price - offset)
+                    } else {
+                        rule.snap_up(price + offset)
+                    };
+                    Order::limit_order(account, action, quantity, limit_price)
+                }
+            };
+            plan.orders.push(RebalanceOrder {
+                contract: closing_contract(&target.contract),
+                weight: target.weight,
+                current,
+                target: target_quantity,
+                price,
+                order,
+            });
+        }
+        // The sort is stable, so the orders keep the order of the targets
+        plan.orders
+            .sort_by_key(|order| order.order.action != "SELL");
+        Ok(plan)
+    }
+}
+
+//==================================================================================================
+/// What `rebalance` did
+///
+/// margin - the margin impact of the orders, if they were checked as what-if
+///   orders instead of placed
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RebalanceOutcome {
+    pub plan: RebalancePlan,
+    pub margin: Option<BasketMarginProjection>,
+}
+
+//==================================================================================================
+/// Fetches the net liquidation and the positions of an account and either
+/// checks the orders that rebalance it as what-if orders or places them
+///
+/// Returns the plan with the order ids of the orders used. No order is sent
+/// if the plan cannot be built, e.g. for lack of a quote.
+///
+/// # Arguments
+/// * client - a connected client that is not streaming positions
+/// * request_id - the id of the account summary request
+/// * account - the account to rebalance
+/// * rebalancer - the target weights
+/// * what_if - checks the margin impact of the orders instead of placing
+///   them
+/// * next_order_id - the next valid order id, advanced past the ids used
+/// * timeout - how long to wait for each of the account values, positions
+///   and what-if results
+pub fn rebalance<T>(
+    client: &Mutex<EClient<T>>,
+    request_id: i32,
+    account: &str,
+    rebalancer: &Rebalancer,
+    what_if: bool,
+    next_order_id: &mut i32,
+    timeout: Duration,
+) -> Result<RebalanceOutcome, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let mut plan = {
+        let mut client = client.lock().expect(POISONED_MUTEX);
+        let net_liquidation = client
+            .fetch_account_summary(
+                request_id,
+                "All",
+                &AccountSummaryTagSet::new().tag(AccountSummaryTags::NetLiquidation),
+                timeout,
+            )?
+            .value(account, AccountSummaryTags::NetLiquidation)
+            .ok_or_else(|| {
+                IbkrError::rejected(
+                    request_id,
+                    &format!("There is no net liquidation of {}.", account),
+                )
+            })?;
+        let positions = client.fetch_positions(timeout)?;
+        let quotes = client.quotes.lock().expect(POISONED_MUTEX);
+        rebalancer
+            .plan(account, net_liquidation, &positions, &quotes)
+            .map_err(|message| IbkrError::rejected(NO_VALID_ID, &message))?
+    };
+
+    if what_if {
+        let margin = what_if_basket(
+            client,
+            &plan.basket(),
+            next_order_id,
+            Duration::ZERO,
+            timeout,
+        )?;
+        return Ok(RebalanceOutcome {
+            plan,
+            margin: Some(margin),
+        });
+    }
+    let mut client = client.lock().expect(POISONED_MUTEX);
+    for rebalance_order in plan.orders.iter_mut() {
+        rebalance_order.order.order_id = *next_order_id;
+        client.place_order(
+            rebalance_order.order.order_id,
+            &rebalance_order.contract,
+            &rebalance_order.order,
+        )?;
+        *next_order_id += 1;
+    }
+    Ok(RebalanceOutcome { plan, margin: None })
+}
@@ -0,0 +1,240 @@
+//! Reconciliation of the local `Portfolio` with the positions reported by
+//! TWS
+//!
+//! Run on startup, before trading on the local state, and periodically to
+//! catch missed or duplicated executions.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::contract::Contract;
+use crate::core::errors::IbkrError;
+use crate::core::portfolio::Portfolio;
+use crate::core::positions::Position;
+use crate::core::wrapper::Wrapper;
+
+/// Sizes closer than this are equal, to allow for the rounding of
+/// fractional sizes
+pub const SIZE_TOLERANCE: f64 = 1e-6;
+
+//==================================================================================================
+/// A difference between the local portfolio and TWS
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Discrepancy {
+    /// TWS reports a position the local portfolio does not have
+    Missing {
+        account: String,
+        contract: Contract,
+        broker_size: f64,
+    },
+    /// The local portfolio has a position TWS does not report
+    Extra {
+        account: String,
+        contract: Contract,
+        local_size: f64,
+    },
+    /// Both have the position but in different sizes
+    SizeMismatch {
+        account: String,
+        contract: Contract,
+        local_size: f64,
+        broker_size: f64,
+    },
+}
+
+impl Discrepancy {
+    pub fn account(&self) -> &str {
+        match self {
+            Discrepancy::Missing { account, .. }
+            | Discrepancy::Extra { account, .. }
+            | Discrepancy::SizeMismatch { account, .. } => account,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn contract(&self) -> &Contract {
+        match self {
+            Discrepancy::Missing { contract, .. }
+            | Discrepancy::Extra { contract, .. }
+            | Discrepancy::SizeMismatch { contract, .. } => contract,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The size to add to the local position to match TWS
+    pub fn difference(&self) -> f64 {
+        match self {
+            Discrepancy::Missing { broker_size, .. } => *broker_size,
+            Discrepancy::Extra { local_size, .. } => -local_size,
+            Discrepancy::SizeMismatch {
+                local_size,
+                broker_size,
+                ..
+            } => broker_size - local_size,
+        }
+    }
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discrepancy::Missing {
+                account,
+                contract,
+                broker_size,
+            } => write!(
+                f,
+                "Missing: account: {}, con_id: {}, broker_size: {}",
+                account, contract.con_id, broker_size
+            ),
+            Discrepancy::Extra {
+                account,
+                contract,
+                local_size,
+            } => write!(
+                f,
+                "Extra: account: {}, con_id: {}, local_size: {}",
+                account, contract.con_id, local_size
+            ),
+            Discrepancy::SizeMismatch {
+                account,
+                contract,
+                local_size,
+                broker_size,
+            } => write!(
+                f,
+                "SizeMismatch: account: {}, con_id: {}, local_size: {}, broker_size: {}",
+                account, contract.con_id, local_size, broker_size
+            ),
+        }
+    }
+}
+
+//==================================================================================================
+/// The differences between the local portfolio and TWS, ordered by account
+/// and `con_id`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReconciliationReport {
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconciliationReport {
+    /// Whether the local portfolio matches TWS
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn account_discrepancies<'a>(
+        &'a self,
+        account: &'a str,
+    ) -> impl Iterator<Item = &'a Discrepancy> + 'a {
+        self.discrepancies
+            .iter()
+            .filter(move |discrepancy| discrepancy.account() == account)
+    }
+}
+
+impl fmt::Display for ReconciliationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "No discrepancies");
+        }
+        let discrepancies: Vec<String> = self
+            .discrepancies
+            .iter()
+            .map(|discrepancy| discrepancy.to_string())
+            .collect();
+        write!(f, "{}", discrepancies.join("\n"))
+    }
+}
+
+//==================================================================================================
+/// Compares the open positions of the local portfolio with the positions
+/// reported by TWS, e.g. by `EClient::fetch_positions`. Flat positions on
+/// either side count as no position.
+pub fn reconcile(portfolio: &Portfolio, positions: &[Position]) -> ReconciliationReport {
+    let mut broker: BTreeMap<(String, i32), (f64, &Contract)> = BTreeMap::new();
+    for position in positions {
+        let size: f64 = position.size.to_string().parse().unwrap_or(0.0);
+        let entry = broker
+            .entry((position.account.clone(), position.contract.con_id))
+            .or_insert((0.0, &position.contract));
+        // Positions multi can report one position per model
+        entry.0 += size;
+    }
+
+    let mut discrepancies = vec![];
+    for local in portfolio.positions() {
+        let key = (local.account.clone(), local.contract.con_id);
+        let broker_size = broker.remove(&key).map_or(0.0, |(size, _)| size);
+        let differs = (local.quantity - broker_size).abs() > SIZE_TOLERANCE;
+        if !differs {
+            continue;
+        }
+        let discrepancy = if broker_size.abs() <= SIZE_TOLERANCE {
+            Discrepancy::Extra {
+                account: local.account.clone(),
+                contract: local.contract.clone(),
+                local_size: local.quantity,
+            }
+        } else if local.is_flat() {
+            Discrepancy::Missing {
+                account: local.account.clone(),
+                contract: local.contract.clone(),
+                broker_size,
+            }
+        } else {
+            Discrepancy::SizeMismatch {
+                account: local.account.clone(),
+                contract: local.contract.clone(),
+                local_size: local.quantity,
+                broker_size,
+            }
+        };
+        discrepancies.push(discrepancy);
+    }
+    for ((account, _), (broker_size, contract)) in broker {
+        if broker_size.abs() > SIZE_TOLERANCE {
+            discrepancies.push(Discrepancy::Missing {
+                account,
+                contract: contract.clone(),
+                broker_size,
+            });
+        }
+    }
+    discrepancies.sort_by(|a, b| {
+        (a.account(), a.contract().con_id).cmp(&(b.account(), b.contract().con_id))
+    });
+    ReconciliationReport { discrepancies }
+}
+
+//==================================================================================================
+/// Fetches the positions of all accounts from TWS and reconciles the local
+/// portfolio with them
+///
+/// # Arguments
+/// * client - a connected client that is not streaming positions
+/// * portfolio - the local portfolio
+/// * timeout - how long to wait for each of the positions
+pub fn reconcile_with_tws<T>(
+    client: &Mutex<EClient<T>>,
+    portfolio: &Portfolio,
+    timeout: Duration,
+) -> Result<ReconciliationReport, IbkrError>
+where
+    T: Wrapper + Send + Sync + 'static,
+{
+    let positions = client
+        .lock()
+        .expect(POISONED_MUTEX)
+        .fetch_positions(timeout)?;
+    Ok(reconcile(portfolio, &positions))
+}
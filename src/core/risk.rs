@@ -0,0 +1,487 @@
+//! Pre-trade risk checks
+//!
+//! `RiskChecks` set with `EClient::set_risk_checks` are evaluated by
+//! `EClient::place_order` before an order is sent. Each `RiskCheck` is added
+//! with a `RiskAction`: a violation of a `Block` check rejects the order, a
+//! violation of a `Warn` check is logged and handed to the warning hook, and
+//! the order is sent.
+//!
+//! `RiskChecks::from_limits` builds the default rule set from `RiskLimits`:
+//! a maximum order notional, a maximum position per symbol and a maximum
+//! daily loss, both of which read the fills and commission reports of a
+//! shared `Blotter`, and a list of restricted symbols. Orders that reduce a
+//! position pass the position and loss limits.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::blotter::{Blotter, BlotterEvent};
+use crate::core::client::POISONED_MUTEX;
+use crate::core::common::UNSET_DOUBLE;
+use crate::core::contract::Contract;
+use crate::core::order::Order;
+use crate::core::portfolio::contract_multiplier;
+
+//==================================================================================================
+/// An order about to be placed
+///
+/// price - the price to value the order at: its limit or stop price, or the
+///   latest quote of the contract for market orders. None if there is none.
+#[derive(Clone, Copy, Debug)]
+pub struct RiskOrder<'a> {
+    pub order_id: i32,
+    pub contract: &'a Contract,
+    pub order: &'a Order,
+    pub price: Option<f64>,
+}
+
+impl RiskOrder<'_> {
+    /// The quantity of the order, negative for sells
+    pub fn signed_quantity(&self) -> f64 {
+        if self.order.action == "BUY" {
+            self.order.total_quantity
+        } else {
+            -self.order.total_quantity
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The value the order trades, including the multiplier of the contract
+    pub fn notional(&self) -> Option<f64> {
+        self.price.map(|price| {
+            self.order.total_quantity * price.abs() * contract_multiplier(self.contract)
+        })
+    }
+}
+
+//==================================================================================================
+/// The price an order is valued at by its own terms: the limit price of
+/// limit orders, the stop price of stop orders, None otherwise. The
+/// auxiliary price of other order types, e.g. the trailing amount of `TRAIL`
+/// or the offset of `REL` orders, is not a price.
+pub fn order_price(order: &Order) -> Option<f64> {
+    let price = match order.order_type.as_str() {
+        "LMT" | "LOC" | "LOO" | "LIT" | "STP LMT" => order.lmt_price,
+        "STP" | "STP PRT" | "MIT" => order.aux_price,
+        _ => return None,
+    };
+    (price != UNSET_DOUBLE && price != 0.0).then_some(price)
+}
+
+//==================================================================================================
+/// A pre-trade control
+pub trait RiskCheck: Send {
+    fn name(&self) -> &str;
+
+    //----------------------------------------------------------------------------------------------
+    /// None if the order passes, else why it does not
+    fn check(&self, order: &RiskOrder) -> Option<String>;
+}
+
+//==================================================================================================
+/// What happens to an order that violates a check
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RiskAction {
+    Block,
+    Warn,
+}
+
+impl fmt::Display for RiskAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskAction::Block => write!(f, "block"),
+            RiskAction::Warn => write!(f, "warn"),
+        }
+    }
+}
+
+//==================================================================================================
+/// A check an order did not pass
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RiskViolation {
+    pub order_id: i32,
+    pub check: String,
+    pub action: RiskAction,
+    pub message: String,
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, check: {}, action: {}, message: {}",
+            self.order_id, self.check, self.action, self.message
+        )
+    }
+}
+
+//==================================================================================================
+/// Rejects orders whose notional exceeds a limit, and orders that cannot be
+/// valued for lack of a price
+#[derive(Clone, Debug)]
+pub struct MaxOrderNotional {
+    pub limit: f64,
+}
+
+impl RiskCheck for MaxOrderNotional {
+    fn name(&self) -> &str {
+        "max order notional"
+    }
+
+    fn check(&self, order: &RiskOrder) -> Option<String> {
+        match order.notional() {
+            None => Some("There is no price to value the order at.".to_string()),
+            Some(notional) if notional > self.limit => Some(format!(
+                "The notional {:.2} exceeds the limit of {:.2}.",
+                notional, self.limit
+            )),
+            Some(_) => None,
+        }
+    }
+}
+
+//==================================================================================================
+/// The net position of a symbol from the fills of a blotter
+fn blotter_position(blotter: &Blotter, symbol: &str) -> f64 {
+    blotter
+        .entries()
+        .iter()
+        .filter(|entry| entry.contract.symbol == symbol)
+        .filter_map(|entry| match &entry.event {
+            BlotterEvent::Fill(execution) if execution.side == "SLD" => Some(-execution.shares),
+            BlotterEvent::Fill(execution) => Some(execution.shares),
+            _ => None,
+        })
+        .sum()
+}
+
+//==================================================================================================
+/// Rejects orders that take the position in a symbol beyond a limit, long
+/// or short. The position is the starting position plus the fills in the
+/// blotter.
+pub struct MaxPosition {
+    pub limit: f64,
+    blotter: Arc<Mutex<Blotter>>,
+    start_positions: HashMap<String, f64>,
+}
+
+impl MaxPosition {
+    pub fn new(limit: f64, blotter: Arc<Mutex<Blotter>>) -> Self {
+        MaxPosition {
+            limit,
+            blotter,
+            start_positions: HashMap::new(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the position held before the first fill in the blotter
+    pub fn start_position(mut self, symbol: &str, position: f64) -> Self {
+        self.start_positions.insert(symbol.to_string(), position);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn position(&self, symbol: &str) -> f64 {
+        self.start_positions.get(symbol).copied().unwrap_or(0.0)
+            + blotter_position(&self.blotter.lock().expect(POISONED_MUTEX), symbol)
+    }
+}
+
+impl RiskCheck for MaxPosition {
+    fn name(&self) -> &str {
+        "max position"
+    }
+
+    fn check(&self, order: &RiskOrder) -> Option<String> {
+        let position = self.position(&order.contract.symbol);
+        let projected = position + order.signed_quantity();
+        if projected.abs() > self.limit && projected.abs() > position.abs() {
+            Some(format!(
+                "The position in {} would be {}, the limit is {}.",
+                order.contract.symbol, projected, self.limit
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Debug for MaxPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaxPosition")
+            .field("limit", &self.limit)
+            .field("start_positions", &self.start_positions)
+            .finish()
+    }
+}
+
+//==================================================================================================
+/// Rejects orders that open or add to positions once the realized loss of
+/// the day, net of commissions, reaches a limit. The day starts at midnight
+/// UTC unless another start is set.
+pub struct MaxDailyLoss {
+    pub limit: f64,
+    blotter: Arc<Mutex<Blotter>>,
+    day_start: Option<DateTime<Utc>>,
+}
+
+impl MaxDailyLoss {
+    pub fn new(limit: f64, blotter: Arc<Mutex<Blotter>>) -> Self {
+        MaxDailyLoss {
+            limit,
+            blotter,
+            day_start: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Counts the loss from `day_start` on, e.g. the start of the session
+    pub fn day_start(mut self, day_start: DateTime<Utc>) -> Self {
+        self.day_start = Some(day_start);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The realized profit of the day net of commissions, negative for a
+    /// loss
+    pub fn realized_pnl(&self) -> f64 {
+        let day_start = self.day_start.unwrap_or_else(|| {
+            Utc::now()
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .unwrap_or_default()
+                .and_utc()
+        });
+        self.blotter
+            .lock()
+            .expect(POISONED_MUTEX)
+            .entries()
+            .iter()
+            .filter(|entry| entry.time >= day_start)
+            .filter_map(|entry| match &entry.event {
+                BlotterEvent::Commission(report) => {
+                    let realized_pnl = if report.realized_pnl == UNSET_DOUBLE {
+                        0.0
+                    } else {
+                        report.realized_pnl
+                    };
+                    let commission = if report.commission == UNSET_DOUBLE {
+                        0.0
+                    } else {
+                        report.commission
+                    };
+                    Some(realized_pnl - commission)
+                }
+                _ => None,
+            })
+            .sum()
+    }
+}
+
+impl RiskCheck for MaxDailyLoss {
+    fn name(&self) -> &str {
+        "max daily loss"
+    }
+
+    fn check(&self, order: &RiskOrder) -> Option<String> {
+        let realized_pnl = self.realized_pnl();
+        if realized_pnl > -self.limit {
+            return None;
+        }
+        let position = blotter_position(
+            &self.blotter.lock().expect(POISONED_MUTEX),
+            &order.contract.symbol,
+        );
+        if (position + order.signed_quantity()).abs() < position.abs() {
+            return None;
+        }
+        Some(format!(
+            "The loss of the day is {:.2}, the limit is {:.2}.",
+            -realized_pnl, self.limit
+        ))
+    }
+}
+
+impl fmt::Debug for MaxDailyLoss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MaxDailyLoss")
+            .field("limit", &self.limit)
+            .field("day_start", &self.day_start)
+            .finish()
+    }
+}
+
+//==================================================================================================
+/// Rejects orders on symbols that must not be traded
+#[derive(Clone, Debug, Default)]
+pub struct RestrictedList {
+    symbols: HashSet<String>,
+}
+
+impl RestrictedList {
+    pub fn new<I, S>(symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        RestrictedList {
+            symbols: symbols
+                .into_iter()
+                .map(|symbol| symbol.as_ref().to_uppercase())
+                .collect(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.symbols.contains(&symbol.to_uppercase())
+    }
+}
+
+impl RiskCheck for RestrictedList {
+    fn name(&self) -> &str {
+        "restricted list"
+    }
+
+    fn check(&self, order: &RiskOrder) -> Option<String> {
+        if self.contains(&order.contract.symbol) {
+            Some(format!(
+                "{} is on the restricted list.",
+                order.contract.symbol
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+//==================================================================================================
+/// The limits of the default rule set. Limits that are None are not
+/// checked.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RiskLimits {
+    pub max_order_notional: Option<f64>,
+    pub max_position: Option<f64>,
+    pub max_daily_loss: Option<f64>,
+    pub restricted: Vec<String>,
+}
+
+//==================================================================================================
+type RiskWarningHook = Box<dyn FnMut(&RiskViolation) + Send>;
+
+/// The checks `place_order` evaluates, in the order they were added
+#[derive(Default)]
+pub struct RiskChecks {
+    checks: Vec<(Box<dyn RiskCheck>, RiskAction)>,
+    warning_hook: Option<RiskWarningHook>,
+}
+
+impl RiskChecks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The default rule set: the checks of the limits that are set, all
+    /// with `action`
+    pub fn from_limits(
+        limits: &RiskLimits,
+        blotter: Arc<Mutex<Blotter>>,
+        action: RiskAction,
+    ) -> Self {
+        let mut checks = RiskChecks::new();
+        if !limits.restricted.is_empty() {
+            checks = checks.add(RestrictedList::new(&limits.restricted), action);
+        }
+        if let Some(limit) = limits.max_order_notional {
+            checks = checks.add(MaxOrderNotional { limit }, action);
+        }
+        if let Some(limit) = limits.max_position {
+            checks = checks.add(MaxPosition::new(limit, blotter.clone()), action);
+        }
+        if let Some(limit) = limits.max_daily_loss {
+            checks = checks.add(MaxDailyLoss::new(limit, blotter), action);
+        }
+        checks
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn add<C: RiskCheck + 'static>(mut self, check: C, action: RiskAction) -> Self {
+        self.checks.push((Box::new(check), action));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `hook` with every violation of a `Warn` check
+    pub fn set_warning_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&RiskViolation) + Send + 'static,
+    {
+        self.warning_hook = Some(Box::new(hook));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        self.checks.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The checks the order violates
+    pub fn evaluate(&self, order: &RiskOrder) -> Vec<RiskViolation> {
+        self.checks
+            .iter()
+            .filter_map(|(check, action)| {
+                check.check(order).map(|message| RiskViolation {
+                    order_id: order.order_id,
+                    check: check.name().to_string(),
+                    action: *action,
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Evaluates the checks, hands the warnings to the hook and fails with
+    /// the first blocking violation
+    pub fn enforce(&mut self, order: &RiskOrder) -> Result<Vec<RiskViolation>, RiskViolation> {
+        let violations = self.evaluate(order);
+        if let Some(violation) = violations
+            .iter()
+            .find(|violation| violation.action == RiskAction::Block)
+        {
+            return Err(violation.clone());
+        }
+        if let Some(hook) = self.warning_hook.as_mut() {
+            for violation in violations.iter() {
+                hook(violation);
+            }
+        }
+        Ok(violations)
+    }
+}
+
+impl fmt::Debug for RiskChecks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.checks
+                    .iter()
+                    .map(|(check, action)| format!("{}: {}", check.name(), action)),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,412 @@
+//! Encode → decode round trips of orders, contracts, conditions and
+//! executions
+//!
+//! Each function takes a value through the same encoding and decoding the
+//! client uses and returns what came back, so tests can check that nothing
+//! is lost or reordered on the way. Executions and open orders are only ever
+//! sent by TWS, `encode_execution_data` and `encode_open_order` write them the
+//! way TWS does. Orders and contracts also go through their serde form, which
+//! is what caches and stores use, if the `serde` feature is on.
+//!
+//! With the `arbitrary` feature, `core::arbitrary` provides proptest
+//! strategies to feed these functions with generated values.
+#[cfg(feature = "serde")]
+use std::io;
+
+#[cfg(feature = "serde")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::core::common::UNSET_DOUBLE;
+use crate::core::contract::Contract;
+use crate::core::decoder::{decode_execution_data, decode_open_order};
+use crate::core::errors::IbkrError;
+use crate::core::execution::Execution;
+use crate::core::messages::{make_field, split_fields, IncomingMessageIds};
+use crate::core::order::{Order, OrderState};
+use crate::core::order_condition::{decode_condition, encode_condition, OrderConditionEnum};
+use crate::core::server_versions::{
+    MIN_SERVER_VER_AUTO_CANCEL_PARENT, MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE,
+    MIN_SERVER_VER_CASH_QTY, MIN_SERVER_VER_DURATION, MIN_SERVER_VER_D_PEG_ORDERS,
+    MIN_SERVER_VER_FRACTIONAL_POSITIONS, MIN_SERVER_VER_LAST_LIQUIDITY,
+    MIN_SERVER_VER_MODELS_SUPPORT, MIN_SERVER_VER_ORDER_CONTAINER,
+    MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS, MIN_SERVER_VER_PEGGED_TO_BENCHMARK,
+    MIN_SERVER_VER_POST_TO_ATS, MIN_SERVER_VER_PRICE_MGMT_ALGO, MIN_SERVER_VER_SOFT_DOLLAR_TIER,
+    MIN_SERVER_VER_SSHORTX_OLD, MIN_SERVER_VER_WHAT_IF_EXT_FIELDS,
+};
+
+//==================================================================================================
+/// Encodes an execution details message the way a server of `server_version`
+/// sends it, without the size prefix
+pub fn encode_execution_data(
+    request_id: i32,
+    contract: &Contract,
+    execution: &Execution,
+    server_version: i32,
+) -> Result<String, IbkrError> {
+    let message = IncomingMessageIds::ExecutionData;
+    let mut fields = vec![make_field(&(message as i32))?];
+    if server_version < MIN_SERVER_VER_LAST_LIQUIDITY {
+        fields.push(make_field(&message.version())?);
+    }
+    fields.push(make_field(&request_id)?);
+    fields.push(make_field(&execution.order_id)?);
+
+    fields.push(make_field(&contract.con_id)?);
+    fields.push(make_field(&contract.symbol)?);
+    fields.push(make_field(&contract.sec_type.to_string())?);
+    fields.push(make_field(&contract.last_trade_date_or_contract_month)?);
+    fields.push(make_field(&contract.strike)?);
+    fields.push(make_field(&contract.right.to_string())?);
+    fields.push(make_field(&contract.exchange)?);
+    fields.push(make_field(&contract.currency)?);
+    fields.push(make_field(&contract.local_symbol)?);
+    fields.push(make_field(&contract.multiplier)?);
+    fields.push(make_field(&contract.trading_class)?);
+
+    fields.push(make_field(&execution.exec_id)?);
+    fields.push(make_field(&execution.time)?);
+    fields.push(make_field(&execution.acct_number)?);
+    fields.push(make_field(&execution.exchange)?);
+    fields.push(make_field(&execution.side)?);
+    if server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
+        fields.push(make_field(&execution.shares)?);
+    } else {
+        fields.push(make_field(&(execution.shares as i32))?);
+    }
+    fields.push(make_field(&execution.price)?);
+    fields.push(make_field(&execution.perm_id)?);
+    fields.push(make_field(&execution.client_id)?);
+    fields.push(make_field(&execution.liquidation)?);
+    fields.push(make_field(&execution.cum_qty)?);
+    fields.push(make_field(&execution.avg_price)?);
+    fields.push(make_field(&execution.order_ref)?);
+    fields.push(make_field(&execution.ev_rule)?);
+    fields.push(make_field(&execution.ev_multiplier)?);
+    if server_version >= MIN_SERVER_VER_MODELS_SUPPORT {
+        fields.push(make_field(&execution.model_code)?);
+    }
+    if server_version >= MIN_SERVER_VER_LAST_LIQUIDITY {
+        fields.push(make_field(&execution.last_liquidity)?);
+    }
+    Ok(fields.concat())
+}
+
+//==================================================================================================
+/// Encodes an execution as a server of `server_version` would and decodes it
+/// again into the request id, the contract and the execution
+///
+/// Only the contract fields that are part of the message come back; an unset
+/// `ev_multiplier` comes back as 1, like an empty one from TWS.
+pub fn execution_round_trip(
+    request_id: i32,
+    contract: &Contract,
+    execution: &Execution,
+    server_version: i32,
+) -> Result<(i32, Contract, Execution), IbkrError> {
+    let text = encode_execution_data(request_id, contract, execution, server_version)?;
+    decode_execution_data(&split_fields(&text), server_version)
+}
+
+//==================================================================================================
+/// Encodes an open order message the way a server of `server_version` sends
+/// it for an order placed with `EClient::place_order`, without the size
+/// prefix
+pub fn encode_open_order(
+    contract: &Contract,
+    order: &Order,
+    order_state: &OrderState,
+    server_version: i32,
+) -> Result<String, IbkrError> {
+    let message = IncomingMessageIds::OpenOrder;
+    let version = message.version();
+    let mut fields = vec![make_field(&(message as i32))?];
+    if server_version < MIN_SERVER_VER_ORDER_CONTAINER {
+        fields.push(make_field(&version)?);
+    }
+    fields.push(make_field(&order.order_id)?);
+
+    fields.push(make_field(&contract.con_id)?);
+    fields.push(make_field(&contract.symbol)?);
+    fields.push(make_field(&contract.sec_type.to_string())?);
+    fields.push(make_field(&contract.last_trade_date_or_contract_month)?);
+    fields.push(make_field(&contract.strike)?);
+    fields.push(make_field(&contract.right.to_string())?);
+    fields.push(make_field(&contract.multiplier)?);
+    fields.push(make_field(&contract.exchange)?);
+    fields.push(make_field(&contract.currency)?);
+    fields.push(make_field(&contract.local_symbol)?);
+    fields.push(make_field(&contract.trading_class)?);
+
+    fields.push(make_field(&order.action)?);
+    if server_version >= MIN_SERVER_VER_FRACTIONAL_POSITIONS {
+        fields.push(make_field(&order.total_quantity)?);
+    } else {
+        fields.push(make_field(&(order.total_quantity as i32))?);
+    }
+    fields.push(make_field(&order.order_type)?);
+    fields.push(make_field(&order.lmt_price)?);
+    fields.push(make_field(&order.aux_price)?);
+    fields.push(make_field(&order.tif)?);
+    fields.push(make_field(&order.oca_group)?);
+    fields.push(make_field(&order.account)?);
+    fields.push(make_field(&order.open_close)?);
+    fields.push(make_field(&(order.origin as i32))?);
+    fields.push(make_field(&order.order_ref)?);
+    fields.push(make_field(&order.client_id)?);
+    fields.push(make_field(&order.perm_id)?);
+    fields.push(make_field(&order.outside_rth)?);
+    fields.push(make_field(&order.hidden)?);
+    fields.push(make_field(&order.discretionary_amt)?);
+    fields.push(make_field(&order.good_after_time)?);
+    fields.push(make_field(&"")?); // deprecated shares allocation
+    fields.push(make_field(&order.fa_group)?);
+    fields.push(make_field(&order.fa_method)?);
+    fields.push(make_field(&order.fa_percentage)?);
+    fields.push(make_field(&order.fa_profile)?);
+    if server_version >= MIN_SERVER_VER_MODELS_SUPPORT {
+        fields.push(make_field(&order.model_code)?);
+    }
+    fields.push(make_field(&order.good_till_date)?);
+    fields.push(make_field(&order.rule80a)?);
+    fields.push(make_field(&order.percent_offset)?);
+    fields.push(make_field(&order.settling_firm)?);
+    fields.push(make_field(&order.short_sale_slot)?);
+    fields.push(make_field(&order.designated_location)?);
+    if server_version == MIN_SERVER_VER_SSHORTX_OLD {
+        fields.push(make_field(&0)?);
+    } else {
+        fields.push(make_field(&order.exempt_code)?);
+    }
+    fields.push(make_field(&(order.auction_strategy as i32))?);
+    fields.push(make_field(&order.starting_price)?);
+    fields.push(make_field(&order.stock_ref_price)?);
+    fields.push(make_field(&order.delta)?);
+    fields.push(make_field(&order.stock_range_lower)?);
+    fields.push(make_field(&order.stock_range_upper)?);
+    fields.push(make_field(&order.display_size)?);
+    fields.push(make_field(&order.block_order)?);
+    fields.push(make_field(&order.sweep_to_fill)?);
+    fields.push(make_field(&order.all_or_none)?);
+    fields.push(make_field(&order.min_qty)?);
+    fields.push(make_field(&order.oca_type)?);
+    fields.push(make_field(&order.e_trade_only)?);
+    fields.push(make_field(&order.firm_quote_only)?);
+    fields.push(make_field(&order.nbbo_price_cap)?);
+    fields.push(make_field(&order.parent_id)?);
+    fields.push(make_field(&order.trigger_method)?);
+
+    fields.push(make_field(&order.volatility)?);
+    fields.push(make_field(&order.volatility_type)?);
+    fields.push(make_field(&order.delta_neutral_order_type)?);
+    fields.push(make_field(&order.delta_neutral_aux_price)?);
+    if !order.delta_neutral_order_type.is_empty() {
+        fields.push(make_field(&order.delta_neutral_con_id)?);
+        fields.push(make_field(&order.delta_neutral_settling_firm)?);
+        fields.push(make_field(&order.delta_neutral_clearing_account)?);
+        fields.push(make_field(&order.delta_neutral_clearing_intent)?);
+        fields.push(make_field(&order.delta_neutral_open_close)?);
+        fields.push(make_field(&order.delta_neutral_short_sale)?);
+        fields.push(make_field(&order.delta_neutral_short_sale_slot)?);
+        fields.push(make_field(&order.delta_neutral_designated_location)?);
+    }
+    fields.push(make_field(&order.continuous_update)?);
+    fields.push(make_field(&order.reference_price_type)?);
+
+    fields.push(make_field(&order.trail_stop_price)?);
+    fields.push(make_field(&order.trailing_percent)?);
+    fields.push(make_field(&order.basis_points)?);
+    fields.push(make_field(&order.basis_points_type)?);
+
+    fields.push(make_field(&contract.combo_legs_descrip)?);
+    fields.push(make_field(&contract.combo_legs.len())?);
+    for combo_leg in &contract.combo_legs {
+        fields.push(make_field(&combo_leg.con_id)?);
+        fields.push(make_field(&combo_leg.ratio)?);
+        fields.push(make_field(&combo_leg.action)?);
+        fields.push(make_field(&combo_leg.exchange)?);
+        fields.push(make_field(&(combo_leg.open_close as i32))?);
+        fields.push(make_field(&combo_leg.short_sale_slot)?);
+        fields.push(make_field(&combo_leg.designated_location)?);
+        fields.push(make_field(&combo_leg.exempt_code)?);
+    }
+    fields.push(make_field(&order.order_combo_legs.len())?);
+    for order_combo_leg in &order.order_combo_legs {
+        fields.push(make_field(&order_combo_leg.price)?);
+    }
+    fields.push(make_field(&order.smart_combo_routing_params.len())?);
+    for tag_value in &order.smart_combo_routing_params {
+        fields.push(make_field(&tag_value.tag)?);
+        fields.push(make_field(&tag_value.value)?);
+    }
+
+    fields.push(make_field(&order.scale_init_level_size)?);
+    fields.push(make_field(&order.scale_subs_level_size)?);
+    fields.push(make_field(&order.scale_price_increment)?);
+    if order.scale_price_increment != UNSET_DOUBLE && order.scale_price_increment > 0.0 {
+        fields.push(make_field(&order.scale_price_adjust_value)?);
+        fields.push(make_field(&order.scale_price_adjust_interval)?);
+        fields.push(make_field(&order.scale_profit_offset)?);
+        fields.push(make_field(&order.scale_auto_reset)?);
+        fields.push(make_field(&order.scale_init_position)?);
+        fields.push(make_field(&order.scale_init_fill_qty)?);
+        fields.push(make_field(&order.scale_random_percent)?);
+    }
+
+    fields.push(make_field(&order.hedge_type)?);
+    if !order.hedge_type.is_empty() {
+        fields.push(make_field(&order.hedge_param)?);
+    }
+    fields.push(make_field(&order.opt_out_smart_routing)?);
+    fields.push(make_field(&order.clearing_account)?);
+    fields.push(make_field(&order.clearing_intent)?);
+    fields.push(make_field(&order.not_held)?);
+
+    match &contract.delta_neutral_contract {
+        Some(delta_neutral_contract) => {
+            fields.push(make_field(&true)?);
+            fields.push(make_field(&delta_neutral_contract.con_id)?);
+            fields.push(make_field(&delta_neutral_contract.delta)?);
+            fields.push(make_field(&delta_neutral_contract.price)?);
+        }
+        None => fields.push(make_field(&false)?),
+    }
+
+    fields.push(make_field(&order.algo_strategy)?);
+    if !order.algo_strategy.is_empty() {
+        fields.push(make_field(&order.algo_params.len())?);
+        for tag_value in &order.algo_params {
+            fields.push(make_field(&tag_value.tag)?);
+            fields.push(make_field(&tag_value.value)?);
+        }
+    }
+    fields.push(make_field(&order.solicited)?);
+
+    fields.push(make_field(&order.what_if)?);
+    fields.push(make_field(&order_state.status)?);
+    if server_version >= MIN_SERVER_VER_WHAT_IF_EXT_FIELDS {
+        fields.push(make_field(&order_state.init_margin_before)?);
+        fields.push(make_field(&order_state.maint_margin_before)?);
+        fields.push(make_field(&order_state.equity_with_loan_before)?);
+        fields.push(make_field(&order_state.init_margin_change)?);
+        fields.push(make_field(&order_state.maint_margin_change)?);
+        fields.push(make_field(&order_state.equity_with_loan_change)?);
+    }
+    fields.push(make_field(&order_state.init_margin_after)?);
+    fields.push(make_field(&order_state.maint_margin_after)?);
+    fields.push(make_field(&order_state.equity_with_loan_after)?);
+    fields.push(make_field(&order_state.commission)?);
+    fields.push(make_field(&order_state.min_commission)?);
+    fields.push(make_field(&order_state.max_commission)?);
+    fields.push(make_field(&order_state.commission_currency)?);
+    fields.push(make_field(&order_state.warning_text)?);
+
+    fields.push(make_field(&order.randomize_size)?);
+    fields.push(make_field(&order.randomize_price)?);
+
+    if server_version >= MIN_SERVER_VER_PEGGED_TO_BENCHMARK {
+        if order.order_type == "PEG BENCH" {
+            fields.push(make_field(&order.reference_contract_id)?);
+            fields.push(make_field(&order.is_pegged_change_amount_decrease)?);
+            fields.push(make_field(&order.pegged_change_amount)?);
+            fields.push(make_field(&order.reference_change_amount)?);
+            fields.push(make_field(&order.reference_exchange_id)?);
+        }
+
+        fields.push(make_field(&order.conditions.len())?);
+        if !order.conditions.is_empty() {
+            for condition in &order.conditions {
+                fields.extend(encode_condition(condition)?);
+            }
+            fields.push(make_field(&order.conditions_ignore_rth)?);
+            fields.push(make_field(&order.conditions_cancel_order)?);
+        }
+
+        fields.push(make_field(&order.adjusted_order_type)?);
+        fields.push(make_field(&order.trigger_price)?);
+        fields.push(make_field(&order.trail_stop_price)?);
+        fields.push(make_field(&order.lmt_price_offset)?);
+        fields.push(make_field(&order.adjusted_stop_price)?);
+        fields.push(make_field(&order.adjusted_stop_limit_price)?);
+        fields.push(make_field(&order.adjusted_trailing_amount)?);
+        fields.push(make_field(&order.adjustable_trailing_unit)?);
+    }
+    if server_version >= MIN_SERVER_VER_SOFT_DOLLAR_TIER {
+        fields.push(make_field(&order.soft_dollar_tier.name)?);
+        fields.push(make_field(&order.soft_dollar_tier.val)?);
+        fields.push(make_field(&order.soft_dollar_tier.display_name)?);
+    }
+    if server_version >= MIN_SERVER_VER_CASH_QTY {
+        fields.push(make_field(&order.cash_qty)?);
+    }
+    if server_version >= MIN_SERVER_VER_AUTO_PRICE_FOR_HEDGE {
+        fields.push(make_field(&order.dont_use_auto_price_for_hedge)?);
+    }
+    if server_version >= MIN_SERVER_VER_ORDER_CONTAINER {
+        fields.push(make_field(&order.is_oms_container)?);
+    }
+    if server_version >= MIN_SERVER_VER_D_PEG_ORDERS {
+        fields.push(make_field(&order.discretionary_up_to_limit_price)?);
+    }
+    if server_version >= MIN_SERVER_VER_PRICE_MGMT_ALGO {
+        fields.push(make_field(&order.use_price_mgmt_algo)?);
+    }
+    if server_version >= MIN_SERVER_VER_DURATION {
+        fields.push(make_field(&order.duration)?);
+    }
+    if server_version >= MIN_SERVER_VER_POST_TO_ATS {
+        fields.push(make_field(&order.post_to_ats)?);
+    }
+    if server_version >= MIN_SERVER_VER_AUTO_CANCEL_PARENT {
+        fields.push(make_field(&order.auto_cancel_parent)?);
+    }
+    if server_version >= MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS {
+        fields.push(make_field(&order.min_trade_qty)?);
+        fields.push(make_field(&order.min_compete_size)?);
+        fields.push(make_field(&order.compete_against_best_offset)?);
+        fields.push(make_field(&order.mid_offset_at_whole)?);
+        fields.push(make_field(&order.mid_offset_at_half)?);
+    }
+    Ok(fields.concat())
+}
+
+//==================================================================================================
+/// Encodes an order as a server of `server_version` sends it back as an open
+/// order and decodes it again into the contract, the order and its state
+///
+/// Only the contract fields that are part of the message come back, and the
+/// order fields TWS does not send back keep their defaults. Fields TWS sends
+/// without marking them unset, e.g. `trigger_price`, come back as 0 if they
+/// were unset.
+pub fn order_round_trip(
+    contract: &Contract,
+    order: &Order,
+    order_state: &OrderState,
+    server_version: i32,
+) -> Result<(Contract, Order, OrderState), IbkrError> {
+    let text = encode_open_order(contract, order, order_state, server_version)?;
+    decode_open_order(&split_fields(&text), server_version)
+}
+
+//==================================================================================================
+/// Encodes a condition as it is sent with an order and decodes it again, as
+/// it comes back with an open order
+pub fn condition_round_trip(
+    condition: &OrderConditionEnum,
+) -> Result<OrderConditionEnum, IbkrError> {
+    let text = encode_condition(condition)?.concat();
+    let fields = split_fields(&text);
+    decode_condition(&mut fields.iter())
+}
+
+//==================================================================================================
+/// Serializes a value, e.g. an `Order` or a `Contract`, to JSON and reads it
+/// back
+#[cfg(feature = "serde")]
+pub fn json_round_trip<T: Serialize + DeserializeOwned>(value: &T) -> Result<T, IbkrError> {
+    let json = serde_json::to_string(value)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok(serde_json::from_str(&json)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?)
+}
@@ -1,13 +1,19 @@
 #![allow(clippy::too_many_arguments)]
 //! Types for dealing with scanner data and scanner subscriptions
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Error, Formatter};
 
+use crate::core::common::{TagValue, UNSET_DOUBLE, UNSET_INTEGER};
 use crate::core::contract::ContractDetails;
 
+/// Number of rows of a scanner subscription that lets TWS use its default
+pub const NO_ROW_NUMBER_SPECIFIED: i32 = -1;
+
 //==================================================================================================
 
-#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ScanData {
     pub contract: ContractDetails,
     pub rank: i32,
@@ -46,7 +52,8 @@ impl Display for ScanData {
 }
 
 //==================================================================================================
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ScannerSubscription {
     pub number_of_rows: i32,
     pub instrument: String,
@@ -69,6 +76,10 @@ pub struct ScannerSubscription {
     pub average_option_volume_above: i32,
     pub scanner_setting_pairs: String,
     pub stock_type_filter: String,
+    /// Generic filters, sent along with the filter options passed to
+    /// `EClient::request_scanner_subscription`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub filter_options: Vec<TagValue>,
 }
 
 impl ScannerSubscription {
@@ -117,8 +128,16 @@ impl ScannerSubscription {
             average_option_volume_above,
             scanner_setting_pairs,
             stock_type_filter,
+            filter_options: vec![],
         }
     }
+
+    //----------------------------------------------------------------------------------------------
+    /// Starts building a subscription for a scan code. Unlike `default()`
+    /// the builder leaves all legacy filter fields unset.
+    pub fn builder(scan_code: ScanCode) -> ScannerSubscriptionBuilder {
+        ScannerSubscriptionBuilder::new(scan_code)
+    }
 }
 
 impl Display for ScannerSubscription {
@@ -130,3 +149,278 @@ impl Display for ScannerSubscription {
         )
     }
 }
+
+//==================================================================================================
+/// The scan code of a scanner subscription, i.e. what the results are ranked
+/// by. See `EClient::request_scanner_parameters` for the codes available.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScanCode {
+    TopPercGain,
+    TopPercLose,
+    TopOpenPercGain,
+    TopOpenPercLose,
+    MostActive,
+    MostActiveUsd,
+    HotByVolume,
+    HotByPrice,
+    TopTradeCount,
+    TopTradeRate,
+    TopVolumeRate,
+    HighOptImpVolat,
+    LowOptImpVolat,
+    HighOptVolumePutCallRatio,
+    OptVolumeMostActive,
+    HighVs52WeekHigh,
+    LowVs52WeekLow,
+    Halted,
+    ComboLatestTrade,
+    /// Any other code listed in the scanner parameters
+    Other(String),
+}
+
+impl Display for ScanCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            ScanCode::TopPercGain => write!(f, "TOP_PERC_GAIN"),
+            ScanCode::TopPercLose => write!(f, "TOP_PERC_LOSE"),
+            ScanCode::TopOpenPercGain => write!(f, "TOP_OPEN_PERC_GAIN"),
+            ScanCode::TopOpenPercLose => write!(f, "TOP_OPEN_PERC_LOSE"),
+            ScanCode::MostActive => write!(f, "MOST_ACTIVE"),
+            ScanCode::MostActiveUsd => write!(f, "MOST_ACTIVE_USD"),
+            ScanCode::HotByVolume => write!(f, "HOT_BY_VOLUME"),
+            ScanCode::HotByPrice => write!(f, "HOT_BY_PRICE"),
+            ScanCode::TopTradeCount => write!(f, "TOP_TRADE_COUNT"),
+            ScanCode::TopTradeRate => write!(f, "TOP_TRADE_RATE"),
+            ScanCode::TopVolumeRate => write!(f, "TOP_VOLUME_RATE"),
+            ScanCode::HighOptImpVolat => write!(f, "HIGH_OPT_IMP_VOLAT"),
+            ScanCode::LowOptImpVolat => write!(f, "LOW_OPT_IMP_VOLAT"),
+            ScanCode::HighOptVolumePutCallRatio => write!(f, "HIGH_OPT_VOLUME_PUT_CALL_RATIO"),
+            ScanCode::OptVolumeMostActive => write!(f, "OPT_VOLUME_MOST_ACTIVE"),
+            ScanCode::HighVs52WeekHigh => write!(f, "HIGH_VS_52W_HL"),
+            ScanCode::LowVs52WeekLow => write!(f, "LOW_VS_52W_HL"),
+            ScanCode::Halted => write!(f, "HALTED"),
+            ScanCode::ComboLatestTrade => write!(f, "COMBO_LATEST_TRADE"),
+            ScanCode::Other(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+//==================================================================================================
+/// The kind of instruments a scanner subscription covers
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScanInstrument {
+    Stock,
+    StockEu,
+    StockHk,
+    Etf,
+    FutureUs,
+    FutureEu,
+    IndexUs,
+    Bond,
+    NativeCombo,
+    /// Any other instrument listed in the scanner parameters
+    Other(String),
+}
+
+impl Display for ScanInstrument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            ScanInstrument::Stock => write!(f, "STK"),
+            ScanInstrument::StockEu => write!(f, "STOCK.EU"),
+            ScanInstrument::StockHk => write!(f, "STOCK.HK"),
+            ScanInstrument::Etf => write!(f, "ETF.EQ.US"),
+            ScanInstrument::FutureUs => write!(f, "FUT.US"),
+            ScanInstrument::FutureEu => write!(f, "FUT.EU"),
+            ScanInstrument::IndexUs => write!(f, "IND.US"),
+            ScanInstrument::Bond => write!(f, "BOND"),
+            ScanInstrument::NativeCombo => write!(f, "NATCOMB"),
+            ScanInstrument::Other(instrument) => write!(f, "{}", instrument),
+        }
+    }
+}
+
+//==================================================================================================
+/// The markets a scanner subscription covers
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScanLocation {
+    StockUsMajor,
+    StockUs,
+    StockNasdaq,
+    StockNyse,
+    StockAmex,
+    StockArca,
+    StockEuIbis,
+    StockEuLse,
+    StockHkSehk,
+    FutureUs,
+    FutureEuSoffex,
+    IndexUs,
+    /// Any other location listed in the scanner parameters
+    Other(String),
+}
+
+impl Display for ScanLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            ScanLocation::StockUsMajor => write!(f, "STK.US.MAJOR"),
+            ScanLocation::StockUs => write!(f, "STK.US"),
+            ScanLocation::StockNasdaq => write!(f, "STK.NASDAQ"),
+            ScanLocation::StockNyse => write!(f, "STK.NYSE"),
+            ScanLocation::StockAmex => write!(f, "STK.AMEX"),
+            ScanLocation::StockArca => write!(f, "STK.ARCA"),
+            ScanLocation::StockEuIbis => write!(f, "STK.EU.IBIS"),
+            ScanLocation::StockEuLse => write!(f, "STK.EU.LSE"),
+            ScanLocation::StockHkSehk => write!(f, "STK.HK.SEHK"),
+            ScanLocation::FutureUs => write!(f, "FUT.US"),
+            ScanLocation::FutureEuSoffex => write!(f, "FUT.EU.SOFFEX"),
+            ScanLocation::IndexUs => write!(f, "IND.US"),
+            ScanLocation::Other(location) => write!(f, "{}", location),
+        }
+    }
+}
+
+//==================================================================================================
+/// A generic scanner filter, sent as a filter option of the subscription
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScanFilter {
+    PriceAbove(f64),
+    PriceBelow(f64),
+    VolumeAbove(i64),
+    AvgVolumeAbove(i64),
+    /// Market capitalization in millions of USD
+    MarketCapAbove(f64),
+    /// Market capitalization in millions of USD
+    MarketCapBelow(f64),
+    ChangePercAbove(f64),
+    ChangePercBelow(f64),
+    ImpVolatAbove(f64),
+    ImpVolatBelow(f64),
+    OptVolumeAbove(i64),
+    /// Any other filter listed in the scanner parameters
+    Other(String, String),
+}
+
+impl ScanFilter {
+    /// The filter's code as listed in the scanner parameters
+    pub fn tag(&self) -> &str {
+        match self {
+            ScanFilter::PriceAbove(_) => "priceAbove",
+            ScanFilter::PriceBelow(_) => "priceBelow",
+            ScanFilter::VolumeAbove(_) => "volumeAbove",
+            ScanFilter::AvgVolumeAbove(_) => "avgVolumeAbove",
+            ScanFilter::MarketCapAbove(_) => "marketCapAbove1e6",
+            ScanFilter::MarketCapBelow(_) => "marketCapBelow1e6",
+            ScanFilter::ChangePercAbove(_) => "changePercAbove",
+            ScanFilter::ChangePercBelow(_) => "changePercBelow",
+            ScanFilter::ImpVolatAbove(_) => "impVolatAbove",
+            ScanFilter::ImpVolatBelow(_) => "impVolatBelow",
+            ScanFilter::OptVolumeAbove(_) => "optVolumeAbove",
+            ScanFilter::Other(tag, _) => tag.as_str(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn value(&self) -> String {
+        match self {
+            ScanFilter::PriceAbove(value)
+            | ScanFilter::PriceBelow(value)
+            | ScanFilter::MarketCapAbove(value)
+            | ScanFilter::MarketCapBelow(value)
+            | ScanFilter::ChangePercAbove(value)
+            | ScanFilter::ChangePercBelow(value)
+            | ScanFilter::ImpVolatAbove(value)
+            | ScanFilter::ImpVolatBelow(value) => value.to_string(),
+            ScanFilter::VolumeAbove(value)
+            | ScanFilter::AvgVolumeAbove(value)
+            | ScanFilter::OptVolumeAbove(value) => value.to_string(),
+            ScanFilter::Other(_, value) => value.clone(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn to_tag_value(&self) -> TagValue {
+        TagValue::new(self.tag().to_string(), self.value())
+    }
+}
+
+impl Display for ScanFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{}={}", self.tag(), self.value())
+    }
+}
+
+//==================================================================================================
+/// Builds a `ScannerSubscription` from typed scan codes, instruments,
+/// locations and filters. Scans US major stocks by default.
+#[derive(Clone, Debug)]
+pub struct ScannerSubscriptionBuilder {
+    subscription: ScannerSubscription,
+}
+
+impl ScannerSubscriptionBuilder {
+    pub fn new(scan_code: ScanCode) -> Self {
+        ScannerSubscriptionBuilder {
+            subscription: ScannerSubscription {
+                number_of_rows: NO_ROW_NUMBER_SPECIFIED,
+                instrument: ScanInstrument::Stock.to_string(),
+                location_code: ScanLocation::StockUsMajor.to_string(),
+                scan_code: scan_code.to_string(),
+                above_price: UNSET_DOUBLE,
+                below_price: UNSET_DOUBLE,
+                above_volume: UNSET_INTEGER,
+                market_cap_above: UNSET_DOUBLE,
+                market_cap_below: UNSET_DOUBLE,
+                coupon_rate_above: UNSET_DOUBLE,
+                coupon_rate_below: UNSET_DOUBLE,
+                average_option_volume_above: UNSET_INTEGER,
+                ..Default::default()
+            },
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn instrument(mut self, instrument: ScanInstrument) -> Self {
+        self.subscription.instrument = instrument.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn location(mut self, location: ScanLocation) -> Self {
+        self.subscription.location_code = location.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The maximum number of results, at most 50
+    pub fn number_of_rows(mut self, number_of_rows: i32) -> Self {
+        self.subscription.number_of_rows = number_of_rows;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Restricts stock scans to a stock type, e.g. `"CORP"` or `"ETF"`
+    pub fn stock_type_filter(mut self, stock_type_filter: &str) -> Self {
+        self.subscription.stock_type_filter = stock_type_filter.to_string();
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a filter. A filter of the same kind added before is replaced.
+    pub fn filter(mut self, filter: ScanFilter) -> Self {
+        let tag_value = filter.to_tag_value();
+        self.subscription
+            .filter_options
+            .retain(|option| option.tag != tag_value.tag);
+        self.subscription.filter_options.push(tag_value);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn build(self) -> ScannerSubscription {
+        self.subscription
+    }
+}
@@ -0,0 +1,276 @@
+//! Typed view of the scanner parameters XML returned by
+//! `Wrapper::scanner_parameters`. Requires the `scanner-params` feature.
+use std::fmt::{Display, Error, Formatter};
+
+use roxmltree::{Document, Node};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::scanner::ScannerSubscription;
+
+//==================================================================================================
+/// An instrument that can be scanned, e.g. `STK` for US stocks
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanInstrumentInfo {
+    pub name: String,
+    pub instrument_type: String,
+    /// The ids of the filters that apply to the instrument
+    pub filters: Vec<String>,
+}
+
+//==================================================================================================
+/// A location that can be scanned, e.g. `STK.US.MAJOR`. Locations are nested
+/// in the XML; `parent_code` is the code of the enclosing location.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanLocationInfo {
+    pub display_name: String,
+    pub location_code: String,
+    pub instruments: Vec<String>,
+    pub parent_code: Option<String>,
+}
+
+//==================================================================================================
+/// A scan code and the instruments it can be used with
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanTypeInfo {
+    pub display_name: String,
+    pub scan_code: String,
+    pub instruments: Vec<String>,
+}
+
+//==================================================================================================
+/// A single value of a filter, e.g. `priceAbove`
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanFilterField {
+    pub code: String,
+    pub display_name: String,
+}
+
+//==================================================================================================
+/// A filter, e.g. `PRICE` with the fields `priceAbove` and `priceBelow`
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanFilterInfo {
+    pub id: String,
+    pub category: String,
+    /// Whether the filter has a lower and an upper bound
+    pub is_range: bool,
+    pub fields: Vec<ScanFilterField>,
+}
+
+//==================================================================================================
+/// Why a scanner subscription does not match the scanner parameters
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScanValidationError {
+    UnknownInstrument(String),
+    UnknownLocation(String),
+    UnknownScanCode(String),
+    /// The scan code can not be used with the instrument
+    ScanCodeNotForInstrument(String, String),
+    UnknownFilter(String),
+    /// The filter can not be used with the instrument
+    FilterNotForInstrument(String, String),
+}
+
+impl Display for ScanValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self {
+            ScanValidationError::UnknownInstrument(instrument) => {
+                write!(f, "Unknown instrument: {}", instrument)
+            }
+            ScanValidationError::UnknownLocation(location) => {
+                write!(f, "Unknown location: {}", location)
+            }
+            ScanValidationError::UnknownScanCode(scan_code) => {
+                write!(f, "Unknown scan code: {}", scan_code)
+            }
+            ScanValidationError::ScanCodeNotForInstrument(scan_code, instrument) => write!(
+                f,
+                "Scan code {} is not available for {}",
+                scan_code, instrument
+            ),
+            ScanValidationError::UnknownFilter(filter) => write!(f, "Unknown filter: {}", filter),
+            ScanValidationError::FilterNotForInstrument(filter, instrument) => {
+                write!(f, "Filter {} is not available for {}", filter, instrument)
+            }
+        }
+    }
+}
+
+//==================================================================================================
+/// The instruments, locations, scan codes and filters available to market
+/// scanners
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScannerParameters {
+    pub instruments: Vec<ScanInstrumentInfo>,
+    pub locations: Vec<ScanLocationInfo>,
+    pub scan_types: Vec<ScanTypeInfo>,
+    pub filters: Vec<ScanFilterInfo>,
+}
+
+impl ScannerParameters {
+    /// Parses the XML received by `Wrapper::scanner_parameters`
+    pub fn parse(xml: &str) -> Result<Self, roxmltree::Error> {
+        let document = Document::parse(xml)?;
+        let root = document.root_element();
+        let mut parameters = ScannerParameters::default();
+
+        for node in root.descendants().filter(|node| node.is_element()) {
+            match node.tag_name().name() {
+                "Instrument" => parameters.instruments.push(ScanInstrumentInfo {
+                    name: child_text(&node, "name"),
+                    instrument_type: child_text(&node, "type"),
+                    filters: split_list(&child_text(&node, "filters")),
+                }),
+                "Location" => parameters.locations.push(ScanLocationInfo {
+                    display_name: child_text(&node, "displayName"),
+                    location_code: child_text(&node, "locationCode"),
+                    instruments: split_list(&child_text(&node, "instruments")),
+                    parent_code: node
+                        .ancestors()
+                        .skip(1)
+                        .find(|ancestor| ancestor.has_tag_name("Location"))
+                        .map(|parent| child_text(&parent, "locationCode")),
+                }),
+                "ScanType" => parameters.scan_types.push(ScanTypeInfo {
+                    display_name: child_text(&node, "displayName"),
+                    scan_code: child_text(&node, "scanCode"),
+                    instruments: split_list(&child_text(&node, "instruments")),
+                }),
+                "RangeFilter" | "SimpleFilter" => parameters.filters.push(ScanFilterInfo {
+                    id: child_text(&node, "id"),
+                    category: child_text(&node, "category"),
+                    is_range: node.has_tag_name("RangeFilter"),
+                    fields: node
+                        .children()
+                        .filter(|field| field.has_tag_name("AbstractField"))
+                        .map(|field| ScanFilterField {
+                            code: child_text(&field, "code"),
+                            display_name: child_text(&field, "displayName"),
+                        })
+                        .collect(),
+                }),
+                _ => (),
+            }
+        }
+        Ok(parameters)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn instrument(&self, instrument_type: &str) -> Option<&ScanInstrumentInfo> {
+        self.instruments
+            .iter()
+            .find(|instrument| instrument.instrument_type == instrument_type)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn location(&self, location_code: &str) -> Option<&ScanLocationInfo> {
+        self.locations
+            .iter()
+            .find(|location| location.location_code == location_code)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn scan_type(&self, scan_code: &str) -> Option<&ScanTypeInfo> {
+        self.scan_types
+            .iter()
+            .find(|scan_type| scan_type.scan_code == scan_code)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The filter one of whose fields has the code `field_code`, e.g.
+    /// `priceAbove`
+    pub fn filter_for_field(&self, field_code: &str) -> Option<&ScanFilterInfo> {
+        self.filters
+            .iter()
+            .find(|filter| filter.fields.iter().any(|field| field.code == field_code))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The scan codes that can be used with an instrument
+    pub fn scan_types_for_instrument(&self, instrument_type: &str) -> Vec<&ScanTypeInfo> {
+        self.scan_types
+            .iter()
+            .filter(|scan_type| scan_type.instruments.iter().any(|i| i == instrument_type))
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Checks the instrument, location, scan code and filter options of a
+    /// subscription against the parameters and returns all problems found
+    pub fn validate(&self, subscription: &ScannerSubscription) -> Vec<ScanValidationError> {
+        let mut errors = vec![];
+        let instrument_type = subscription.instrument.as_str();
+
+        let instrument = self.instrument(instrument_type);
+        if instrument.is_none() {
+            errors.push(ScanValidationError::UnknownInstrument(
+                instrument_type.to_string(),
+            ));
+        }
+
+        if self.location(&subscription.location_code).is_none() {
+            errors.push(ScanValidationError::UnknownLocation(
+                subscription.location_code.clone(),
+            ));
+        }
+
+        match self.scan_type(&subscription.scan_code) {
+            None => errors.push(ScanValidationError::UnknownScanCode(
+                subscription.scan_code.clone(),
+            )),
+            Some(scan_type) => {
+                if instrument.is_some()
+                    && !scan_type.instruments.iter().any(|i| i == instrument_type)
+                {
+                    errors.push(ScanValidationError::ScanCodeNotForInstrument(
+                        subscription.scan_code.clone(),
+                        instrument_type.to_string(),
+                    ));
+                }
+            }
+        }
+
+        for option in subscription.filter_options.iter() {
+            match self.filter_for_field(&option.tag) {
+                None => errors.push(ScanValidationError::UnknownFilter(option.tag.clone())),
+                Some(filter) => {
+                    if let Some(instrument) = instrument {
+                        if !instrument.filters.contains(&filter.id) {
+                            errors.push(ScanValidationError::FilterNotForInstrument(
+                                option.tag.clone(),
+                                instrument_type.to_string(),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+//==================================================================================================
+fn child_text(node: &Node, name: &str) -> String {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+//==================================================================================================
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
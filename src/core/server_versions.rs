@@ -92,9 +92,31 @@ pub const MIN_SERVER_VER_D_PEG_ORDERS: i32 = 148;
 pub const MIN_SERVER_VER_MKT_DEPTH_PRIM_EXCHANGE: i32 = 149;
 pub const MIN_SERVER_VER_COMPLETED_ORDERS: i32 = 150;
 pub const MIN_SERVER_VER_PRICE_MGMT_ALGO: i32 = 151;
+pub const MIN_SERVER_VER_STOCK_TYPE: i32 = 152;
+pub const MIN_SERVER_VER_ENCODE_MSG_ASCII7: i32 = 153;
+pub const MIN_SERVER_VER_SEND_ALL_FAMILY_CODES: i32 = 154;
+pub const MIN_SERVER_VER_NO_DEFAULT_OPEN_CLOSE: i32 = 155;
+pub const MIN_SERVER_VER_PRICE_BASED_VOLATILITY: i32 = 156;
+pub const MIN_SERVER_VER_REPLACE_FA_END: i32 = 157;
+pub const MIN_SERVER_VER_DURATION: i32 = 158;
+pub const MIN_SERVER_VER_MARKET_DATA_IN_SHARES: i32 = 159;
+pub const MIN_SERVER_VER_POST_TO_ATS: i32 = 160;
+pub const MIN_SERVER_VER_WSHE_CALENDAR: i32 = 161;
+pub const MIN_SERVER_VER_AUTO_CANCEL_PARENT: i32 = 162;
+pub const MIN_SERVER_VER_FRACTIONAL_SIZE_SUPPORT: i32 = 163;
+pub const MIN_SERVER_VER_SIZE_RULES: i32 = 164;
+pub const MIN_SERVER_VER_HISTORICAL_SCHEDULE: i32 = 165;
+pub const MIN_SERVER_VER_ADVANCED_ORDER_REJECT: i32 = 166;
+pub const MIN_SERVER_VER_USER_INFO: i32 = 167;
+pub const MIN_SERVER_VER_CRYPTO_AGGREGATED_TRADES: i32 = 168;
+pub const MIN_SERVER_VER_MANUAL_ORDER_TIME: i32 = 169;
+pub const MIN_SERVER_VER_PEGBEST_PEGMID_OFFSETS: i32 = 170;
+pub const MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS: i32 = 171;
+pub const MIN_SERVER_VER_IPO_PRICES: i32 = 172;
+pub const MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS_DATE: i32 = 173;
 
 // 100+ messaging */
 // 100 = enhanced handshake, msg length prefixes
 
 pub const MIN_CLIENT_VER: i32 = 100;
-pub const MAX_CLIENT_VER: i32 = MIN_SERVER_VER_PRICE_MGMT_ALGO;
+pub const MAX_CLIENT_VER: i32 = MIN_SERVER_VER_WSH_EVENT_DATA_FILTERS_DATE;
@@ -0,0 +1,238 @@
+//! Gating of orders by the trading sessions of their contracts
+//!
+//! A `SessionGate` set with `EClient::set_session_gate` knows the sessions
+//! of the contracts added to it from their `ContractDetails`, the liquid
+//! hours by default. `place_order` holds back orders for a contract outside
+//! its sessions, so that an order is not submitted overnight by accident.
+//! Orders with `outside_rth` set and what-if orders pass.
+//!
+//! Orders held back are either rejected or queued until the market opens,
+//! see `SessionGateMode`. Queued orders go out when
+//! `EClient::release_session_orders` is called after the market opened;
+//! cancelling a queued order takes it off the queue and a global cancel
+//! empties the queue. Orders for contracts the gate has no sessions of are
+//! rejected, unless `allow_unknown` is set.
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::contract::{Contract, ContractDetails};
+use crate::core::order::Order;
+use crate::core::order_throttle::{queued_by_ref, QueuedOrder};
+use crate::core::trading_hours::{next_session, session_at, Session};
+
+//==================================================================================================
+/// Which hours of a contract the gate goes by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SessionHours {
+    /// `liquid_hours`, i.e. the regular trading hours
+    #[default]
+    Liquid,
+    /// `trading_hours`, including the extended hours
+    Trading,
+}
+
+//==================================================================================================
+/// What happens to orders outside the sessions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SessionGateMode {
+    /// `place_order` fails
+    #[default]
+    Reject,
+    /// The order is queued until the next session opens. Orders for which
+    /// no next session is known are rejected.
+    Queue,
+}
+
+//==================================================================================================
+/// What the gate did with an order
+///
+/// Open - the order can be sent
+/// Queued - the order waits for the session opening at `opens`
+/// Rejected - the order must not be sent, for the reason given
+#[derive(Clone, Debug, PartialEq)]
+pub enum GateDecision {
+    Open,
+    Queued { opens: DateTime<Tz> },
+    Rejected(String),
+}
+
+impl fmt::Display for GateDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GateDecision::Open => write!(f, "open"),
+            GateDecision::Queued { opens } => write!(f, "queued until {}", opens),
+            GateDecision::Rejected(reason) => write!(f, "rejected: {}", reason),
+        }
+    }
+}
+
+//==================================================================================================
+/// Holds back orders for contracts outside their sessions
+#[derive(Clone, Debug, Default)]
+pub struct SessionGate {
+    hours: SessionHours,
+    mode: SessionGateMode,
+    allow_unknown: bool,
+    sessions: HashMap<i32, Vec<Session>>,
+    queue: Vec<QueuedOrder>,
+}
+
+impl SessionGate {
+    /// A gate that goes by the liquid hours and rejects orders outside them
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn hours(mut self, hours: SessionHours) -> Self {
+        self.hours = hours;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn mode(mut self, mode: SessionGateMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Lets orders for contracts without known sessions pass
+    pub fn allow_unknown(mut self, allow_unknown: bool) -> Self {
+        self.allow_unknown = allow_unknown;
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn gate_mode(&self) -> SessionGateMode {
+        self.mode
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds the sessions of a contract from its details, by its `con_id`.
+    /// Only the days sent with the details, usually about a week, are known,
+    /// so the details should be added again from time to time.
+    pub fn add_contract_details(&mut self, details: &ContractDetails) -> Result<(), String> {
+        let sessions = match self.hours {
+            SessionHours::Liquid => details.liquid_sessions()?,
+            SessionHours::Trading => details.trading_sessions()?,
+        };
+        self.set_sessions(details.contract.con_id, sessions);
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the sessions of a contract, ordered by their open
+    pub fn set_sessions(&mut self, con_id: i32, sessions: Vec<Session>) {
+        self.sessions.insert(con_id, sessions);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn sessions(&self, con_id: i32) -> Option<&[Session]> {
+        self.sessions.get(&con_id).map(Vec::as_slice)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Decides at `now` whether an order can be sent, without queuing it
+    pub fn check(&self, now: &DateTime<Utc>, contract: &Contract, order: &Order) -> GateDecision {
+        if order.outside_rth || order.what_if {
+            return GateDecision::Open;
+        }
+        let Some(sessions) = self.sessions(contract.con_id) else {
+            return if self.allow_unknown {
+                GateDecision::Open
+            } else {
+                GateDecision::Rejected(format!(
+                    "The sessions of contract {} are not known.",
+                    contract.con_id
+                ))
+            };
+        };
+        if session_at(sessions, now).is_some() {
+            return GateDecision::Open;
+        }
+        match (self.mode, next_session(sessions, now)) {
+            (SessionGateMode::Queue, Some(session)) => GateDecision::Queued {
+                opens: session.open,
+            },
+            _ => GateDecision::Rejected(format!(
+                "The market of {} is closed. Set outside_rth to place the order.",
+                contract.symbol
+            )),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Decides at `now` whether an order can be sent and queues it if the
+    /// decision is to
+    pub fn admit(
+        &mut self,
+        now: &DateTime<Utc>,
+        order_id: i32,
+        contract: &Contract,
+        order: &Order,
+    ) -> GateDecision {
+        let decision = self.check(now, contract, order);
+        if let GateDecision::Queued { .. } = decision {
+            self.remove(order_id);
+            self.queue.push(QueuedOrder {
+                order_id,
+                contract: contract.clone(),
+                order: order.clone(),
+            });
+        }
+        decision
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes the queued orders whose market is open at `now` off the queue
+    pub fn release(&mut self, now: &DateTime<Utc>) -> Vec<QueuedOrder> {
+        let (released, queue) = self.queue.drain(..).partition(|queued| {
+            self.sessions
+                .get(&queued.contract.con_id)
+                .is_some_and(|sessions| session_at(sessions, now).is_some())
+        });
+        self.queue = queue;
+        released
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Puts released orders that could not be sent back at the front of the
+    /// queue, in their order
+    pub fn requeue(&mut self, orders: Vec<QueuedOrder>) {
+        self.queue.splice(0..0, orders);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Takes a queued order off the queue. Returns false if it is not queued.
+    pub fn remove(&mut self, order_id: i32) -> bool {
+        let len = self.queue.len();
+        self.queue.retain(|queued| queued.order_id != order_id);
+        self.queue.len() != len
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Empties the queue
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The ids of the queued orders, in the order they were placed
+    pub fn queued(&self) -> Vec<i32> {
+        self.queue.iter().map(|queued| queued.order_id).collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The queued order with an order ref, the last one if there are several.
+    /// None for an empty ref.
+    pub fn queued_by_ref(&self, order_ref: &str) -> Option<i32> {
+        queued_by_ref(self.queue.iter(), order_ref)
+    }
+}
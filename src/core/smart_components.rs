@@ -0,0 +1,147 @@
+//! Typed mapping of SMART component exchanges as returned by
+//! `EClient::request_smart_components`
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::SmartComponent;
+
+//==================================================================================================
+/// Mapping of bit numbers to the exchange name and single letter code of the
+/// components of a SMART routed quote
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SmartComponentMap {
+    pub components: BTreeMap<i32, SmartComponent>,
+}
+
+impl SmartComponentMap {
+    pub fn new(components: Vec<SmartComponent>) -> Self {
+        SmartComponentMap {
+            components: components
+                .into_iter()
+                .map(|component| (component.bit_number, component))
+                .collect(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn get(&self, bit_number: i32) -> Option<&SmartComponent> {
+        self.components.get(&bit_number)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Translates the single letter code used in the bid/ask exchange ticks of
+    /// a SMART quote into the name of the exchange.
+    pub fn exchange_for_letter(&self, exchange_letter: &str) -> Option<&str> {
+        self.components
+            .values()
+            .find(|component| component.exchange_letter == exchange_letter)
+            .map(|component| component.exchange.as_str())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Translates a string of exchange letters, e.g. the value of a
+    /// `TickType::BidExch` tick, into exchange names. Unknown letters are
+    /// returned as they are.
+    pub fn exchanges_for_letters(&self, exchange_letters: &str) -> Vec<String> {
+        exchange_letters
+            .chars()
+            .map(|letter| {
+                let letter = letter.to_string();
+                self.exchange_for_letter(letter.as_str())
+                    .map(|exchange| exchange.to_string())
+                    .unwrap_or(letter)
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+}
+
+impl fmt::Display for SmartComponentMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components: Vec<String> = self
+            .components
+            .values()
+            .map(|component| component.to_string())
+            .collect();
+        write!(f, "[{}]", components.join("; "))
+    }
+}
+
+//==================================================================================================
+/// Keeps track of the `bbo_exchange` mapping identifiers reported for market
+/// data subscriptions and of the component maps received for them
+#[derive(Debug, Default)]
+pub struct SmartComponentRegistry {
+    bbo_exchanges: HashMap<i32, String>,
+    pending_requests: HashMap<i32, String>,
+    maps: HashMap<String, SmartComponentMap>,
+}
+
+impl SmartComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records the `bbo_exchange` of a market data subscription as reported
+    /// by `Wrapper::tick_request_parameters`
+    pub fn set_bbo_exchange(&mut self, ticker_id: i32, bbo_exchange: &str) {
+        self.bbo_exchanges
+            .insert(ticker_id, bbo_exchange.to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn bbo_exchange(&self, ticker_id: i32) -> Option<&str> {
+        self.bbo_exchanges.get(&ticker_id).map(|bbo| bbo.as_str())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn remove_ticker(&mut self, ticker_id: i32) {
+        self.bbo_exchanges.remove(&ticker_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub(crate) fn add_request(&mut self, request_id: i32, bbo_exchange: &str) {
+        self.pending_requests
+            .insert(request_id, bbo_exchange.to_string());
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Stores the map received for a request and returns it
+    pub(crate) fn complete_request(
+        &mut self,
+        request_id: i32,
+        map: SmartComponentMap,
+    ) -> SmartComponentMap {
+        if let Some(bbo_exchange) = self.pending_requests.remove(&request_id) {
+            self.maps.insert(bbo_exchange, map.clone());
+        }
+        map
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn map(&self, bbo_exchange: &str) -> Option<&SmartComponentMap> {
+        self.maps.get(bbo_exchange)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The component map of a market data subscription, if both its
+    /// `bbo_exchange` and the map for it have been received
+    pub fn map_for_ticker(&self, ticker_id: i32) -> Option<&SmartComponentMap> {
+        self.bbo_exchange(ticker_id)
+            .and_then(|bbo_exchange| self.map(bbo_exchange))
+    }
+}
@@ -0,0 +1,291 @@
+//! Builders of option spreads as BAG contracts
+//!
+//! The legs are option contracts with their `con_id` set, e.g. as returned
+//! by `EClient::fetch_contract_details`. The builders check that the legs
+//! form the structure and return a combo whose legs carry the actions of
+//! buying it; place a SELL order on the combo to take the other side.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::Action;
+use crate::core::contract::{ComboLeg, Contract, Right, SecType};
+
+//==================================================================================================
+/// A leg of a `ComboBuilder`
+///
+/// ratio - the number of contracts of the leg per unit of the combo
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpreadLeg {
+    pub contract: Contract,
+    pub action: Action,
+    pub ratio: f64,
+}
+
+impl SpreadLeg {
+    pub fn new(contract: Contract, action: Action, ratio: f64) -> Self {
+        SpreadLeg {
+            contract,
+            action,
+            ratio,
+        }
+    }
+}
+
+impl fmt::Display for SpreadLeg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "con_id: {}, action: {}, ratio: {}",
+            self.contract.con_id, self.action, self.ratio
+        )
+    }
+}
+
+//==================================================================================================
+/// Builds a BAG contract from arbitrary legs on the same underlying
+#[derive(Clone, Debug)]
+pub struct ComboBuilder {
+    exchange: String,
+    legs: Vec<SpreadLeg>,
+}
+
+impl ComboBuilder {
+    /// # Arguments
+    /// * exchange - the exchange of the combo and its legs, usually `"SMART"`
+    pub fn new(exchange: &str) -> Self {
+        ComboBuilder {
+            exchange: exchange.to_string(),
+            legs: vec![],
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn leg(mut self, contract: &Contract, action: Action, ratio: f64) -> Self {
+        self.legs
+            .push(SpreadLeg::new(contract.clone(), action, ratio));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The combo contract. Fails if there are no legs, a leg has no
+    /// `con_id` or a ratio that is not positive, or the legs have different
+    /// symbols or currencies.
+    pub fn build(&self) -> Result<Contract, String> {
+        let first = &self.legs.first().ok_or("A combo needs legs")?.contract;
+        for leg in &self.legs {
+            if leg.contract.con_id <= 0 {
+                return Err(format!(
+                    "The leg {} {} has no con_id",
+                    leg.contract.symbol, leg.contract.local_symbol
+                ));
+            }
+            if leg.ratio <= 0.0 {
+                return Err(format!("The leg {} has a ratio of {}", leg, leg.ratio));
+            }
+            if leg.contract.symbol != first.symbol || leg.contract.currency != first.currency {
+                return Err("All legs must have the same symbol and currency".to_string());
+            }
+        }
+
+        Ok(Contract {
+            symbol: first.symbol.clone(),
+            sec_type: SecType::Combo,
+            currency: first.currency.clone(),
+            exchange: self.exchange.clone(),
+            combo_legs: self
+                .legs
+                .iter()
+                .map(|leg| ComboLeg {
+                    con_id: leg.contract.con_id,
+                    ratio: leg.ratio,
+                    action: leg.action.to_string(),
+                    exchange: self.exchange.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        })
+    }
+}
+
+//==================================================================================================
+/// The right of an option leg
+fn right(contract: &Contract) -> Result<Right, String> {
+    match contract.right {
+        Right::None => Err(format!("The leg {} is not an option", contract.symbol)),
+        right => Ok(right),
+    }
+}
+
+//==================================================================================================
+fn expiry(contract: &Contract) -> &str {
+    contract.last_trade_date_or_contract_month.as_str()
+}
+
+//==================================================================================================
+fn check(condition: bool, message: &str) -> Result<(), String> {
+    if condition {
+        Ok(())
+    } else {
+        Err(message.to_string())
+    }
+}
+
+//==================================================================================================
+/// Buys `long` and sells `short`, options of the same right and expiry with
+/// different strikes. A bull call spread buys the lower strike, a bear put
+/// spread the higher one.
+pub fn vertical(long: &Contract, short: &Contract, exchange: &str) -> Result<Contract, String> {
+    check(
+        right(long)? == right(short)? && expiry(long) == expiry(short),
+        "The legs of a vertical spread must have the same right and expiry",
+    )?;
+    check(
+        long.strike != short.strike,
+        "The legs of a vertical spread must have different strikes",
+    )?;
+    ComboBuilder::new(exchange)
+        .leg(long, Action::Buy, 1.0)
+        .leg(short, Action::Sell, 1.0)
+        .build()
+}
+
+//==================================================================================================
+/// Sells the `near` and buys the `far` option of the same right and strike
+pub fn calendar(near: &Contract, far: &Contract, exchange: &str) -> Result<Contract, String> {
+    check(
+        right(near)? == right(far)? && near.strike == far.strike,
+        "The legs of a calendar spread must have the same right and strike",
+    )?;
+    check(
+        expiry(near) < expiry(far),
+        "The near leg of a calendar spread must expire first",
+    )?;
+    ComboBuilder::new(exchange)
+        .leg(near, Action::Sell, 1.0)
+        .leg(far, Action::Buy, 1.0)
+        .build()
+}
+
+//==================================================================================================
+/// Sells the `near` and buys the `far` option of the same right with
+/// different strikes
+pub fn diagonal(near: &Contract, far: &Contract, exchange: &str) -> Result<Contract, String> {
+    check(
+        right(near)? == right(far)?,
+        "The legs of a diagonal spread must have the same right",
+    )?;
+    check(
+        expiry(near) < expiry(far) && near.strike != far.strike,
+        "The near leg of a diagonal spread must expire first at another strike",
+    )?;
+    ComboBuilder::new(exchange)
+        .leg(near, Action::Sell, 1.0)
+        .leg(far, Action::Buy, 1.0)
+        .build()
+}
+
+//==================================================================================================
+/// Buys a call and a put of the same strike and expiry
+pub fn straddle(call: &Contract, put: &Contract, exchange: &str) -> Result<Contract, String> {
+    check(
+        right(call)? == Right::Call && right(put)? == Right::Put,
+        "A straddle needs a call and a put",
+    )?;
+    check(
+        call.strike == put.strike && expiry(call) == expiry(put),
+        "The legs of a straddle must have the same strike and expiry",
+    )?;
+    ComboBuilder::new(exchange)
+        .leg(call, Action::Buy, 1.0)
+        .leg(put, Action::Buy, 1.0)
+        .build()
+}
+
+//==================================================================================================
+/// Buys an out of the money call and put of the same expiry, i.e. the put
+/// has the lower strike
+pub fn strangle(call: &Contract, put: &Contract, exchange: &str) -> Result<Contract, String> {
+    check(
+        right(call)? == Right::Call && right(put)? == Right::Put,
+        "A strangle needs a call and a put",
+    )?;
+    check(
+        put.strike < call.strike && expiry(call) == expiry(put),
+        "The put of a strangle must have the lower strike and the same expiry",
+    )?;
+    ComboBuilder::new(exchange)
+        .leg(call, Action::Buy, 1.0)
+        .leg(put, Action::Buy, 1.0)
+        .build()
+}
+
+//==================================================================================================
+/// Buys the `lower` and the `upper` wing and sells two of the `middle`
+/// option, all of the same right and expiry with equally spaced strikes
+pub fn butterfly(
+    lower: &Contract,
+    middle: &Contract,
+    upper: &Contract,
+    exchange: &str,
+) -> Result<Contract, String> {
+    let right = right(lower)?;
+    check(
+        [middle, upper]
+            .iter()
+            .all(|leg| leg.right == right && expiry(leg) == expiry(lower)),
+        "The legs of a butterfly must have the same right and expiry",
+    )?;
+    check(
+        lower.strike < middle.strike
+            && middle.strike < upper.strike
+            && (middle.strike - lower.strike - (upper.strike - middle.strike)).abs() < 1e-9,
+        "The strikes of a butterfly must be ascending and equally spaced",
+    )?;
+    ComboBuilder::new(exchange)
+        .leg(lower, Action::Buy, 1.0)
+        .leg(middle, Action::Sell, 2.0)
+        .leg(upper, Action::Buy, 1.0)
+        .build()
+}
+
+//==================================================================================================
+/// Buys `long_put`, sells `short_put` and `short_call` and buys `long_call`,
+/// all of the same expiry and in ascending order of their strikes. Buying
+/// the combo receives a credit, so TWS quotes it at a negative price.
+pub fn iron_condor(
+    long_put: &Contract,
+    short_put: &Contract,
+    short_call: &Contract,
+    long_call: &Contract,
+    exchange: &str,
+) -> Result<Contract, String> {
+    check(
+        right(long_put)? == Right::Put
+            && right(short_put)? == Right::Put
+            && right(short_call)? == Right::Call
+            && right(long_call)? == Right::Call,
+        "An iron condor needs two puts and two calls",
+    )?;
+    check(
+        [short_put, short_call, long_call]
+            .iter()
+            .all(|leg| expiry(leg) == expiry(long_put)),
+        "The legs of an iron condor must have the same expiry",
+    )?;
+    check(
+        long_put.strike < short_put.strike
+            && short_put.strike <= short_call.strike
+            && short_call.strike < long_call.strike,
+        "The strikes of an iron condor must be ascending from the long put",
+    )?;
+    ComboBuilder::new(exchange)
+        .leg(long_put, Action::Buy, 1.0)
+        .leg(short_put, Action::Sell, 1.0)
+        .leg(short_call, Action::Sell, 1.0)
+        .leg(long_call, Action::Buy, 1.0)
+        .build()
+}
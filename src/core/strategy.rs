@@ -0,0 +1,553 @@
+//! A skeleton for trading strategies
+//!
+//! Implement `Strategy` and hand it to a `StrategyRunner`. The runner calls
+//! the hooks of the strategy as quotes change, real time bars arrive and the
+//! orders the strategy placed change status, and at a fixed interval if a
+//! timer is set. Hooks do not talk to the client directly: they queue
+//! subscriptions, orders and cancels on the `StrategyContext` they are
+//! given, and the runner sends them once the hook returns.
+//!
+//! `StrategyRunner::dispatch` feeds a strategy events without a client, e.g.
+//! recorded ones, and leaves the queued commands to the caller.
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use crate::core::client::{EClient, POISONED_MUTEX};
+use crate::core::common::{RealTimeBar, WhatToShow};
+use crate::core::contract::Contract;
+use crate::core::errors::IbkrError;
+use crate::core::order::Order;
+use crate::core::quote_cache::Quote;
+use crate::core::subscription::Subscription;
+use crate::core::wrapper::Wrapper;
+
+//==================================================================================================
+/// A change to an order placed by a strategy
+///
+/// Status - the order status as reported by `Wrapper::order_status`, e.g.
+///   `Submitted` or `Filled`
+/// Rejected - the order or its cancel could not be sent
+#[derive(Clone, Debug, PartialEq)]
+pub enum OrderEvent {
+    Status { order_id: i32, status: String },
+    Rejected { order_id: i32, reason: String },
+}
+
+impl OrderEvent {
+    pub fn order_id(&self) -> i32 {
+        match self {
+            OrderEvent::Status { order_id, .. } | OrderEvent::Rejected { order_id, .. } => {
+                *order_id
+            }
+        }
+    }
+}
+
+impl fmt::Display for OrderEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderEvent::Status { order_id, status } => {
+                write!(f, "order_id: {}, status: {}", order_id, status)
+            }
+            OrderEvent::Rejected { order_id, reason } => {
+                write!(f, "order_id: {}, rejected: {}", order_id, reason)
+            }
+        }
+    }
+}
+
+//==================================================================================================
+/// An event a strategy is called with
+#[derive(Clone, Debug)]
+pub enum StrategyEvent {
+    Tick { ticker_id: i32, quote: Quote },
+    Bar { request_id: i32, bar: RealTimeBar },
+    Order(OrderEvent),
+    Timer,
+}
+
+impl fmt::Display for StrategyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StrategyEvent::Tick { ticker_id, quote } => {
+                write!(f, "tick {}: {}", ticker_id, quote)
+            }
+            StrategyEvent::Bar { request_id, bar } => write!(f, "bar {}: {}", request_id, bar),
+            StrategyEvent::Order(event) => write!(f, "order {}", event),
+            StrategyEvent::Timer => write!(f, "timer"),
+        }
+    }
+}
+
+//==================================================================================================
+/// A request a strategy queued on its context
+#[derive(Clone, Debug)]
+pub enum StrategyCommand {
+    SubscribeMarketData {
+        ticker_id: i32,
+        contract: Box<Contract>,
+    },
+    SubscribeBars {
+        request_id: i32,
+        contract: Box<Contract>,
+        what_to_show: WhatToShow,
+    },
+    Unsubscribe(i32),
+    PlaceOrder {
+        order_id: i32,
+        contract: Box<Contract>,
+        order: Box<Order>,
+    },
+    CancelOrder(i32),
+}
+
+//==================================================================================================
+/// What a strategy hook can do. Request and order ids are handed out by the
+/// context, so they never collide with each other.
+#[derive(Clone, Debug)]
+pub struct StrategyContext {
+    next_order_id: i32,
+    next_request_id: i32,
+    commands: Vec<StrategyCommand>,
+    stopping: bool,
+    clock: Option<DateTime<Utc>>,
+}
+
+impl StrategyContext {
+    /// # Arguments
+    /// * next_order_id - the next valid order id, from `Wrapper::next_valid_id`
+    /// * first_request_id - the first id to use for subscriptions
+    pub fn new(next_order_id: i32, first_request_id: i32) -> Self {
+        StrategyContext {
+            next_order_id,
+            next_request_id: first_request_id,
+            commands: vec![],
+            stopping: false,
+            clock: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes to market data. Returns the ticker id `on_tick` is called
+    /// with.
+    pub fn subscribe_market_data(&mut self, contract: &Contract) -> i32 {
+        let ticker_id = self.request_id();
+        self.commands.push(StrategyCommand::SubscribeMarketData {
+            ticker_id,
+            contract: Box::new(contract.clone()),
+        });
+        ticker_id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Subscribes to 5 second real time bars. Returns the request id
+    /// `on_bar` is called with.
+    pub fn subscribe_bars(&mut self, contract: &Contract, what_to_show: WhatToShow) -> i32 {
+        let request_id = self.request_id();
+        self.commands.push(StrategyCommand::SubscribeBars {
+            request_id,
+            contract: Box::new(contract.clone()),
+            what_to_show,
+        });
+        request_id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Ends a subscription made with `subscribe_market_data` or
+    /// `subscribe_bars`
+    pub fn unsubscribe(&mut self, request_id: i32) {
+        self.commands.push(StrategyCommand::Unsubscribe(request_id));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Places an order. Returns its order id, which `on_order_event` is
+    /// called with as the status of the order changes.
+    pub fn place_order(&mut self, contract: &Contract, order: &Order) -> i32 {
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        let mut order = order.clone();
+        order.order_id = order_id;
+        self.commands.push(StrategyCommand::PlaceOrder {
+            order_id,
+            contract: Box::new(contract.clone()),
+            order: Box::new(order),
+        });
+        order_id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn cancel_order(&mut self, order_id: i32) {
+        self.commands.push(StrategyCommand::CancelOrder(order_id));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Asks the runner to stop once the current hook returns
+    pub fn stop(&mut self) {
+        self.stopping = true;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_stopping(&self) -> bool {
+        self.stopping
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The current time. Use it instead of `Utc::now()` so that the strategy
+    /// sees the simulated time in a backtest.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.unwrap_or_else(Utc::now)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the time `now` returns from here on
+    pub fn set_clock(&mut self, time: DateTime<Utc>) {
+        self.clock = Some(time);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The commands queued since the last call, oldest first
+    pub fn take_commands(&mut self) -> Vec<StrategyCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn request_id(&mut self) -> i32 {
+        let request_id = self.next_request_id;
+        self.next_request_id += 1;
+        request_id
+    }
+}
+
+//==================================================================================================
+/// The hooks of a strategy. All of them do nothing unless implemented.
+pub trait Strategy {
+    /// Called once before any other hook, e.g. to make the subscriptions
+    fn on_start(&mut self, _context: &mut StrategyContext) {}
+
+    //----------------------------------------------------------------------------------------------
+    /// Called when the quote of a market data subscription changed
+    fn on_tick(&mut self, _context: &mut StrategyContext, _ticker_id: i32, _quote: &Quote) {}
+
+    //----------------------------------------------------------------------------------------------
+    fn on_bar(&mut self, _context: &mut StrategyContext, _request_id: i32, _bar: &RealTimeBar) {}
+
+    //----------------------------------------------------------------------------------------------
+    fn on_order_event(&mut self, _context: &mut StrategyContext, _event: &OrderEvent) {}
+
+    //----------------------------------------------------------------------------------------------
+    /// Called every timer interval of the runner
+    fn on_timer(&mut self, _context: &mut StrategyContext) {}
+
+    //----------------------------------------------------------------------------------------------
+    /// Called once when the runner stops. Orders placed here are still sent
+    /// before the subscriptions are cancelled.
+    fn on_stop(&mut self, _context: &mut StrategyContext) {}
+}
+
+//==================================================================================================
+/// Runs a strategy against a client
+///
+/// `run` polls the client for changed quotes, new bars and changed order
+/// statuses, calls the hooks of the strategy with them and sends what the
+/// strategy queued. Market data arrives through the client's quotes, so do
+/// not consume the ticks of the strategy's subscriptions elsewhere.
+pub struct StrategyRunner<S: Strategy> {
+    strategy: S,
+    context: StrategyContext,
+    timer_interval: Option<Duration>,
+    last_timer: Instant,
+    market_data: HashMap<i32, Option<Quote>>,
+    bars: HashMap<i32, Subscription<RealTimeBar>>,
+    orders: HashMap<i32, Option<String>>,
+    started: bool,
+}
+
+impl<S: Strategy> StrategyRunner<S> {
+    pub fn new(strategy: S, context: StrategyContext) -> Self {
+        StrategyRunner {
+            strategy,
+            context,
+            timer_interval: None,
+            last_timer: Instant::now(),
+            market_data: HashMap::new(),
+            bars: HashMap::new(),
+            orders: HashMap::new(),
+            started: false,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `on_timer` every `interval`, measured from the start
+    pub fn timer_interval(mut self, interval: Duration) -> Self {
+        self.timer_interval = Some(interval);
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn strategy_mut(&mut self) -> &mut S {
+        &mut self.strategy
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn context(&mut self) -> &mut StrategyContext {
+        &mut self.context
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn into_strategy(self) -> S {
+        self.strategy
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `on_start` unless it has been called before
+    pub fn start_strategy(&mut self) {
+        if !self.started {
+            self.started = true;
+            self.last_timer = Instant::now();
+            self.strategy.on_start(&mut self.context);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `on_stop` if the strategy has been started and not stopped
+    pub fn stop_strategy(&mut self) {
+        if self.started {
+            self.started = false;
+            self.strategy.on_stop(&mut self.context);
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls the hook of an event. The commands the strategy queued stay on
+    /// the context.
+    pub fn dispatch(&mut self, event: &StrategyEvent) {
+        let context = &mut self.context;
+        match event {
+            StrategyEvent::Tick { ticker_id, quote } => {
+                self.strategy.on_tick(context, *ticker_id, quote)
+            }
+            StrategyEvent::Bar { request_id, bar } => {
+                self.strategy.on_bar(context, *request_id, bar)
+            }
+            StrategyEvent::Order(event) => self.strategy.on_order_event(context, event),
+            StrategyEvent::Timer => self.strategy.on_timer(context),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `on_start` and sends what it queued
+    pub fn start<T>(&mut self, client: &Mutex<EClient<T>>) -> Result<(), IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        self.start_strategy();
+        self.send_commands(client)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Collects the events since the last poll, dispatches them and sends
+    /// what the strategy queued. Returns false once the strategy asked to
+    /// stop.
+    pub fn poll<T>(&mut self, client: &Mutex<EClient<T>>) -> Result<bool, IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        for event in self.collect_events(client) {
+            self.dispatch(&event);
+            self.send_commands(client)?;
+            if self.context.stopping {
+                return Ok(false);
+            }
+        }
+        Ok(!self.context.stopping)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Calls `on_stop`, sends what it queued and cancels the subscriptions.
+    /// Orders the strategy placed are left working.
+    pub fn stop<T>(&mut self, client: &Mutex<EClient<T>>) -> Result<(), IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        self.stop_strategy();
+        let sent = self.send_commands(client);
+
+        self.bars.clear();
+        let mut client = client.lock().expect(POISONED_MUTEX);
+        for (ticker_id, _) in self.market_data.drain() {
+            client.cancel_market_data(ticker_id)?;
+        }
+        sent
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Starts the strategy and polls every `poll_interval` until it asks to
+    /// stop or an error occurs, then stops it
+    pub fn run<T>(
+        &mut self,
+        client: &Mutex<EClient<T>>,
+        poll_interval: Duration,
+    ) -> Result<(), IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        let result = self.start(client).and_then(|_| loop {
+            if !self.poll(client)? {
+                break Ok(());
+            }
+            thread::sleep(poll_interval);
+        });
+        let stopped = self.stop(client);
+        result.and(stopped)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn collect_events<T>(&mut self, client: &Mutex<EClient<T>>) -> Vec<StrategyEvent>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        let mut events = vec![];
+        {
+            let client = client.lock().expect(POISONED_MUTEX);
+            for (ticker_id, last) in self.market_data.iter_mut() {
+                let quote = client.quote(*ticker_id);
+                if quote.is_some() && quote != *last {
+                    events.push(StrategyEvent::Tick {
+                        ticker_id: *ticker_id,
+                        quote: quote.clone().unwrap_or_default(),
+                    });
+                    *last = quote;
+                }
+            }
+            for (order_id, last) in self.orders.iter_mut() {
+                let status = client.order_status(*order_id);
+                if status.is_some() && status != *last {
+                    events.push(StrategyEvent::Order(OrderEvent::Status {
+                        order_id: *order_id,
+                        status: status.clone().unwrap_or_default(),
+                    }));
+                    *last = status;
+                }
+            }
+        }
+        for (request_id, bars) in &self.bars {
+            events.extend(bars.try_iter().map(|bar| StrategyEvent::Bar {
+                request_id: *request_id,
+                bar,
+            }));
+        }
+        if let Some(interval) = self.timer_interval {
+            if self.last_timer.elapsed() >= interval {
+                self.last_timer += interval;
+                events.push(StrategyEvent::Timer);
+            }
+        }
+        events
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sends the queued commands. Orders and cancels that fail are reported
+    /// to the strategy as rejected, failed subscriptions end the run.
+    fn send_commands<T>(&mut self, client: &Mutex<EClient<T>>) -> Result<(), IbkrError>
+    where
+        T: Wrapper + Send + Sync + 'static,
+    {
+        loop {
+            let commands = self.context.take_commands();
+            if commands.is_empty() {
+                return Ok(());
+            }
+            let mut rejected = vec![];
+            {
+                let mut client = client.lock().expect(POISONED_MUTEX);
+                for command in commands {
+                    match command {
+                        StrategyCommand::SubscribeMarketData {
+                            ticker_id,
+                            contract,
+                        } => {
+                            client.request_market_data(
+                                ticker_id,
+                                &contract,
+                                "",
+                                false,
+                                false,
+                                &[],
+                            )?;
+                            self.market_data.insert(ticker_id, None);
+                        }
+                        StrategyCommand::SubscribeBars {
+                            request_id,
+                            contract,
+                            what_to_show,
+                        } => {
+                            let bars = client.stream_real_time_bars(
+                                request_id,
+                                &contract,
+                                what_to_show,
+                                false,
+                                &[],
+                            )?;
+                            self.bars.insert(request_id, bars);
+                        }
+                        StrategyCommand::Unsubscribe(request_id) => {
+                            if self.market_data.remove(&request_id).is_some() {
+                                client.cancel_market_data(request_id)?;
+                            }
+                            self.bars.remove(&request_id);
+                        }
+                        StrategyCommand::PlaceOrder {
+                            order_id,
+                            contract,
+                            order,
+                        } => match client.place_order(order_id, &contract, &order) {
+                            Ok(()) => {
+                                self.orders.entry(order_id).or_insert(None);
+                            }
+                            Err(err) => rejected.push(OrderEvent::Rejected {
+                                order_id,
+                                reason: err.to_string(),
+                            }),
+                        },
+                        StrategyCommand::CancelOrder(order_id) => {
+                            if let Err(err) = client.cancel_order(order_id) {
+                                rejected.push(OrderEvent::Rejected {
+                                    order_id,
+                                    reason: err.to_string(),
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+            // Rejections can queue new commands, which are sent on the next
+            // pass
+            for event in rejected {
+                self.strategy.on_order_event(&mut self.context, &event);
+            }
+        }
+    }
+}
+
+impl<S: Strategy> fmt::Debug for StrategyRunner<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StrategyRunner")
+            .field("context", &self.context)
+            .field("timer_interval", &self.timer_interval)
+            .field("market_data", &self.market_data.keys())
+            .field("bars", &self.bars.keys())
+            .field("orders", &self.orders.keys())
+            .field("started", &self.started)
+            .finish()
+    }
+}
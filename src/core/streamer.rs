@@ -3,12 +3,16 @@ use std::net::{SocketAddr, TcpStream};
 use std::{
     io::{self, Read, Write},
     net::Shutdown,
+    sync::{Arc, Mutex},
 };
 
+use crate::core::errors::IbkrError;
+use crate::core::messages::make_message;
+
 //----------------------------------------------------------------------------------------------
 pub trait Streamer: Read + Write + Send + Sync {
     fn shutdown(&mut self, how: Shutdown) -> io::Result<()>;
-    fn connect(&mut self, addr: &SocketAddr);
+    fn connect(&mut self, addr: &SocketAddr) -> io::Result<()>;
 }
 //----------------------------------------------------------------------------------------------
 #[derive(Debug)]
@@ -27,14 +31,16 @@ impl Streamer for TcpStreamer {
         self.stream.shutdown(how)
     }
 
-    fn connect(&mut self, addr: &SocketAddr) {
-        self.stream = TcpStream::connect(addr).expect("Cannot connect!!");
+    fn connect(&mut self, addr: &SocketAddr) -> io::Result<()> {
+        self.stream = TcpStream::connect(addr)?;
+        Ok(())
     }
 }
 
-impl Clone for TcpStreamer {
-    fn clone(&self) -> Self {
-        TcpStreamer::new(self.stream.try_clone().unwrap())
+impl TcpStreamer {
+    /// A streamer on the same connection
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(TcpStreamer::new(self.stream.try_clone()?))
     }
 }
 
@@ -54,6 +60,65 @@ impl Write for TcpStreamer {
     }
 }
 
+//----------------------------------------------------------------------------------------------
+/// A shareable handle to a connection's stream that lets parts of the API
+/// other than `EClient`, e.g. the decoder or subscription handles, send
+/// requests of their own
+#[derive(Clone, Default)]
+pub struct RequestSender {
+    stream: Arc<Mutex<Option<Box<dyn Streamer>>>>,
+}
+
+impl RequestSender {
+    pub fn new(stream: Option<Box<dyn Streamer>>) -> Self {
+        RequestSender {
+            stream: Arc::new(Mutex::new(stream)),
+        }
+    }
+
+    pub(crate) fn set_stream(&self, stream: Option<Box<dyn Streamer>>) {
+        *self
+            .stream
+            .lock()
+            .expect("Request stream mutex was poisoned") = stream;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.stream
+            .lock()
+            .expect("Request stream mutex was poisoned")
+            .is_some()
+    }
+
+    /// Sends a request. Does nothing if there is no stream, e.g. because the
+    /// client has disconnected.
+    pub fn send_request(&self, request: &str) -> Result<(), IbkrError> {
+        let bytes = make_message(request)?;
+        if let Some(stream) = self
+            .stream
+            .lock()
+            .expect("Request stream mutex was poisoned")
+            .as_mut()
+        {
+            stream.write_all(bytes.as_slice())?;
+        }
+        Ok(())
+    }
+
+    /// Sends a message written with begin_message() and finish_message()
+    pub fn send_message(&self, message: &[u8]) -> Result<(), IbkrError> {
+        if let Some(stream) = self
+            .stream
+            .lock()
+            .expect("Request stream mutex was poisoned")
+            .as_mut()
+        {
+            stream.write_all(message)?;
+        }
+        Ok(())
+    }
+}
+
 //----------------------------------------------------------------------------------------------
 pub struct TestStreamer {
     stream: ByteBuffer,
@@ -78,7 +143,9 @@ impl Streamer for TestStreamer {
         Ok(())
     }
 
-    fn connect(&mut self, _addr: &SocketAddr) {}
+    fn connect(&mut self, _addr: &SocketAddr) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 impl Read for TestStreamer {
@@ -93,10 +160,7 @@ impl Read for TestStreamer {
         while cont {
             let mut buf: [u8; NUM_BYTES] = [0; NUM_BYTES];
 
-            let bytes_read = self
-                .stream
-                .read(&mut buf)
-                .expect("Couldnt read from reader...");
+            let bytes_read = self.stream.read(&mut buf)?;
             allbuf.extend_from_slice(&buf[0..bytes_read]);
 
             if bytes_read < NUM_BYTES {
@@ -0,0 +1,293 @@
+//! Stream handles for subscriptions whose data is delivered through a
+//! channel instead of the `Wrapper` callbacks
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, RecvError, RecvTimeoutError, Sender, TryRecvError};
+use std::time::Duration;
+
+use crate::core::account_summary_tags::AccountSummaryEvent;
+use crate::core::account_updates::{AccountUpdateEvent, AccountUpdateMultiEvent};
+use crate::core::common::RealTimeBar;
+use crate::core::contract::{ContractDetailsEvent, SymbolSamplesEvent};
+use crate::core::historical::{
+    BarTimeParser, HistoricalDataEvent, HistoricalRetryPolicy, HistoricalTicksEvent,
+};
+use crate::core::margin::WhatIfEvent;
+use crate::core::option_chain::OptionChainEvent;
+use crate::core::order::SoftDollarTier;
+use crate::core::order_book::{BookMetrics, BookMetricsConfig, DepthUpdate, OrderBook};
+use crate::core::pnl::{PnlEvent, PnlSingleEvent};
+use crate::core::positions::PositionEvent;
+use crate::core::scanner::ScanData;
+
+//==================================================================================================
+/// Receiving end of a streaming subscription. Dropping the handle cancels
+/// the subscription with TWS.
+pub struct Subscription<E> {
+    request_id: i32,
+    receiver: Receiver<E>,
+    cancel: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl<E> Subscription<E> {
+    pub(crate) fn new<F>(request_id: i32, receiver: Receiver<E>, cancel: F) -> Self
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        Subscription {
+            request_id,
+            receiver,
+            cancel: Some(Box::new(cancel)),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The request id the subscription was made with
+    pub fn request_id(&self) -> i32 {
+        self.request_id
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Blocks until the next event arrives. Fails once the subscription has
+    /// ended, e.g. because the client disconnected.
+    pub fn recv(&self) -> Result<E, RecvError> {
+        self.receiver.recv()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn try_recv(&self) -> Result<E, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<E, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Iterates over the events, blocking until each one arrives
+    pub fn iter(&self) -> mpsc::Iter<'_, E> {
+        self.receiver.iter()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Iterates over the events that have already arrived
+    pub fn try_iter(&self) -> mpsc::TryIter<'_, E> {
+        self.receiver.try_iter()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Cancels the subscription. Same as dropping the handle.
+    pub fn cancel(self) {}
+}
+
+impl<E> Drop for Subscription<E> {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel();
+        }
+    }
+}
+
+impl<'a, E> IntoIterator for &'a Subscription<E> {
+    type Item = E;
+    type IntoIter = mpsc::Iter<'a, E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<E> fmt::Debug for Subscription<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Subscription {{ request_id: {} }}", self.request_id)
+    }
+}
+
+//==================================================================================================
+/// Sending ends of the subscriptions of one event type, keyed by request id
+pub(crate) struct Subscriptions<E> {
+    senders: HashMap<i32, Sender<E>>,
+}
+
+impl<E> Default for Subscriptions<E> {
+    fn default() -> Self {
+        Subscriptions {
+            senders: HashMap::new(),
+        }
+    }
+}
+
+impl<E> Subscriptions<E> {
+    //----------------------------------------------------------------------------------------------
+    pub(crate) fn add(&mut self, request_id: i32) -> Receiver<E> {
+        let (sender, receiver) = mpsc::channel();
+        self.senders.insert(request_id, sender);
+        receiver
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub(crate) fn remove(&mut self, request_id: i32) {
+        self.senders.remove(&request_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub(crate) fn clear(&mut self) {
+        self.senders.clear();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub(crate) fn contains(&self, request_id: i32) -> bool {
+        self.senders.contains_key(&request_id)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sends an event to the subscription of `request_id`. Hands the event
+    /// back if there is no such subscription, so that it can be delivered
+    /// to the `Wrapper` instead.
+    pub(crate) fn send(&mut self, request_id: i32, event: E) -> Result<(), E> {
+        match self.senders.get(&request_id) {
+            Some(sender) => {
+                if sender.send(event).is_err() {
+                    // The handle is gone, its cancel is on the way
+                    self.senders.remove(&request_id);
+                }
+                Ok(())
+            }
+            None => Err(event),
+        }
+    }
+}
+
+//==================================================================================================
+/// A historical data request that is sent again if it fails with a pacing
+/// violation or a timeout
+///
+/// message - the request as sent
+/// attempts - the number of retries so far
+pub(crate) struct HistoricalRetry {
+    pub(crate) message: Vec<u8>,
+    pub(crate) policy: HistoricalRetryPolicy,
+    pub(crate) attempts: u32,
+}
+
+impl HistoricalRetry {
+    pub(crate) fn new(message: Vec<u8>, policy: HistoricalRetryPolicy) -> Self {
+        HistoricalRetry {
+            message,
+            policy,
+            attempts: 0,
+        }
+    }
+}
+
+//==================================================================================================
+/// All streaming subscriptions of a client. Shared between `EClient`, which
+/// registers them, and `Decoder`, which feeds them. Requests without a
+/// request id, like the account updates, are keyed by `NO_VALID_ID`.
+///
+/// fa_data - the FA configuration XML, keyed by the `FaDataType` code
+/// what_if - the results of what-if orders, keyed by order id
+/// user_info - the white branding ids of the user info requests
+/// bar_times - the parsers of the bar dates of the historical data requests,
+///             with whether the request is kept up to date
+/// historical_retries - the historical data requests that are retried
+/// order_books - the books of the market depth requests of `book_metrics`
+#[derive(Default)]
+pub struct StreamRegistry {
+    pub(crate) real_time_bars: Subscriptions<RealTimeBar>,
+    pub(crate) scanner_data: Subscriptions<Vec<ScanData>>,
+    pub(crate) historical_data: Subscriptions<HistoricalDataEvent>,
+    pub(crate) historical_ticks: Subscriptions<HistoricalTicksEvent>,
+    pub(crate) contract_details: Subscriptions<ContractDetailsEvent>,
+    pub(crate) option_chains: Subscriptions<OptionChainEvent>,
+    pub(crate) symbol_samples: Subscriptions<SymbolSamplesEvent>,
+    pub(crate) account_summaries: Subscriptions<AccountSummaryEvent>,
+    pub(crate) account_updates: Subscriptions<AccountUpdateEvent>,
+    pub(crate) account_updates_multi: Subscriptions<AccountUpdateMultiEvent>,
+    pub(crate) positions: Subscriptions<PositionEvent>,
+    pub(crate) positions_multi: Subscriptions<PositionEvent>,
+    pub(crate) pnl: Subscriptions<PnlEvent>,
+    pub(crate) pnl_single: Subscriptions<PnlSingleEvent>,
+    pub(crate) fa_data: Subscriptions<String>,
+    pub(crate) what_if: Subscriptions<WhatIfEvent>,
+    pub(crate) soft_dollar_tiers: Subscriptions<Vec<SoftDollarTier>>,
+    pub(crate) user_info: Subscriptions<String>,
+    pub(crate) book_metrics: Subscriptions<BookMetrics>,
+    pub(crate) order_books: HashMap<i32, (OrderBook, BookMetricsConfig)>,
+    pub(crate) bar_times: HashMap<i32, (BarTimeParser, bool)>,
+    pub(crate) historical_retries: HashMap<i32, HistoricalRetry>,
+}
+
+impl StreamRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Ends all subscriptions, e.g. on disconnect
+    pub(crate) fn clear(&mut self) {
+        self.real_time_bars.clear();
+        self.scanner_data.clear();
+        self.historical_data.clear();
+        self.historical_ticks.clear();
+        self.contract_details.clear();
+        self.option_chains.clear();
+        self.symbol_samples.clear();
+        self.account_summaries.clear();
+        self.account_updates.clear();
+        self.account_updates_multi.clear();
+        self.positions.clear();
+        self.positions_multi.clear();
+        self.pnl.clear();
+        self.pnl_single.clear();
+        self.fa_data.clear();
+        self.what_if.clear();
+        self.soft_dollar_tiers.clear();
+        self.user_info.clear();
+        self.book_metrics.clear();
+        self.order_books.clear();
+        self.bar_times.clear();
+        self.historical_retries.clear();
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies a row of market depth to the book of a book metrics
+    /// subscription and sends the metrics if the book changed
+    pub(crate) fn update_order_book(&mut self, request_id: i32, update: &DepthUpdate) {
+        let Some((book, config)) = self.order_books.get_mut(&request_id) else {
+            return;
+        };
+        if book.apply(update) {
+            let metrics = book.metrics(request_id, config);
+            if self.book_metrics.send(request_id, metrics).is_err() {
+                self.order_books.remove(&request_id);
+            }
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forgets a book metrics subscription, e.g. when it is cancelled
+    pub(crate) fn remove_book_metrics(&mut self, request_id: i32) {
+        self.book_metrics.remove(request_id);
+        self.order_books.remove(&request_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forgets a historical data request, e.g. when it is cancelled
+    pub(crate) fn remove_historical_data(&mut self, request_id: i32) {
+        self.historical_data.remove(request_id);
+        self.bar_times.remove(&request_id);
+        self.historical_retries.remove(&request_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The parser of the bar dates of a historical data request. The default
+    /// parser if the request is unknown.
+    pub(crate) fn bar_time_parser(&self, request_id: i32) -> BarTimeParser {
+        self.bar_times
+            .get(&request_id)
+            .map(|(parser, _)| *parser)
+            .unwrap_or_default()
+    }
+}
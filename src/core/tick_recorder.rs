@@ -0,0 +1,111 @@
+//! Recording of live market data ticks for later export
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::TickType;
+
+//==================================================================================================
+/// A market data tick as received by `Wrapper::tick_price` or
+/// `Wrapper::tick_size`, stamped with the time it arrived
+///
+/// price - set for price ticks
+/// size - set for size ticks
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RecordedTick {
+    pub time: DateTime<Utc>,
+    pub ticker_id: i32,
+    pub tick_type: TickType,
+    pub price: Option<f64>,
+    pub size: Option<f64>,
+}
+
+impl RecordedTick {
+    pub fn price(time: DateTime<Utc>, ticker_id: i32, tick_type: TickType, price: f64) -> Self {
+        RecordedTick {
+            time,
+            ticker_id,
+            tick_type,
+            price: Some(price),
+            size: None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn size(time: DateTime<Utc>, ticker_id: i32, tick_type: TickType, size: f64) -> Self {
+        RecordedTick {
+            time,
+            ticker_id,
+            tick_type,
+            price: None,
+            size: Some(size),
+        }
+    }
+}
+
+impl fmt::Display for RecordedTick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time: {}, ticker_id: {}, tick_type: {}, price: {:?}, size: {:?}",
+            self.time, self.ticker_id, self.tick_type, self.price, self.size
+        )
+    }
+}
+
+//==================================================================================================
+/// Buffers the ticks received by a `Wrapper`, e.g. to write them out in
+/// batches
+#[derive(Debug, Default)]
+pub struct TickRecorder {
+    ticks: Vec<RecordedTick>,
+}
+
+impl TickRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records a tick of `Wrapper::tick_price`, stamped with the current time
+    pub fn record_price(&mut self, ticker_id: i32, tick_type: TickType, price: f64) {
+        self.ticks
+            .push(RecordedTick::price(Utc::now(), ticker_id, tick_type, price));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Records a tick of `Wrapper::tick_size`, stamped with the current time
+    pub fn record_size(&mut self, ticker_id: i32, tick_type: TickType, size: f64) {
+        self.ticks
+            .push(RecordedTick::size(Utc::now(), ticker_id, tick_type, size));
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn record(&mut self, tick: RecordedTick) {
+        self.ticks.push(tick);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn ticks(&self) -> &[RecordedTick] {
+        &self.ticks
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Removes and returns all recorded ticks
+    pub fn drain(&mut self) -> Vec<RecordedTick> {
+        std::mem::take(&mut self.ticks)
+    }
+}
@@ -0,0 +1,201 @@
+//! End-of-day summaries of executions and commissions
+//!
+//! `TradeReport` aggregates executions, e.g. the day's executions from
+//! `request_executions` or a `TradeStore`, with their commission reports
+//! per symbol and per strategy, i.e. per `order_ref`. Amounts are in the
+//! currency of the contracts, so summaries are kept per currency.
+//!
+//! The slippage of an execution is measured against the arrival price of
+//! its order, the price of the market when the order was placed, which the
+//! caller records by order id. Positive slippage is a cost.
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::CommissionReport;
+use crate::core::contract::Contract;
+use crate::core::execution::Execution;
+use crate::core::pnl::pnl_value;
+use crate::core::portfolio::contract_multiplier;
+
+//==================================================================================================
+/// The executions of a symbol or strategy in one currency
+///
+/// name - the symbol, or the `order_ref` of the strategy
+/// bought - the quantity bought
+/// sold - the quantity sold
+/// notional - the traded value, including the multipliers
+/// realized_pnl - the realized PnL TWS reports with the commissions
+/// slippage - the cost of the executions against the arrival prices
+/// arrival_notional - the traded value at the arrival prices of the
+///                    executions with one
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TradeSummary {
+    pub name: String,
+    pub currency: String,
+    pub executions: usize,
+    pub bought: f64,
+    pub sold: f64,
+    pub notional: f64,
+    pub realized_pnl: f64,
+    pub commissions: f64,
+    pub slippage: f64,
+    pub arrival_notional: f64,
+}
+
+impl TradeSummary {
+    pub fn new(name: &str, currency: &str) -> Self {
+        TradeSummary {
+            name: name.to_string(),
+            currency: currency.to_string(),
+            ..Default::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The quantity traded in both directions
+    pub fn volume(&self) -> f64 {
+        self.bought + self.sold
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The realized PnL after commissions
+    pub fn net_pnl(&self) -> f64 {
+        self.realized_pnl - self.commissions
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The slippage in basis points of the value traded at the arrival
+    /// prices, if any execution had one
+    pub fn slippage_bps(&self) -> Option<f64> {
+        if self.arrival_notional > 0.0 {
+            Some(self.slippage / self.arrival_notional * 10_000.0)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for TradeSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "name: {}, currency: {}, executions: {}, bought: {}, sold: {}, notional: {}, \
+             realized_pnl: {}, commissions: {}, slippage: {}",
+            self.name,
+            self.currency,
+            self.executions,
+            self.bought,
+            self.sold,
+            self.notional,
+            self.realized_pnl,
+            self.commissions,
+            self.slippage
+        )
+    }
+}
+
+//==================================================================================================
+/// Summaries of executions per symbol and per strategy, ordered by name and
+/// currency. Executions without an `order_ref` are summarized under an
+/// empty strategy name.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TradeReport {
+    pub by_symbol: Vec<TradeSummary>,
+    pub by_strategy: Vec<TradeSummary>,
+}
+
+impl TradeReport {
+    /// Aggregates executions and their commission reports. Executions are
+    /// counted once per `exec_id`.
+    ///
+    /// # Arguments
+    /// * executions - the executions and their contracts
+    /// * commission_reports - the commission reports of the executions
+    /// * arrival_prices - the arrival prices of the orders by order id
+    pub fn new(
+        executions: &[(Contract, Execution)],
+        commission_reports: &[CommissionReport],
+        arrival_prices: &HashMap<i32, f64>,
+    ) -> Self {
+        let reports: HashMap<&str, &CommissionReport> = commission_reports
+            .iter()
+            .map(|report| (report.exec_id.as_str(), report))
+            .collect();
+
+        let mut by_symbol: BTreeMap<(String, String), TradeSummary> = BTreeMap::new();
+        let mut by_strategy: BTreeMap<(String, String), TradeSummary> = BTreeMap::new();
+        let mut seen = HashSet::new();
+        for (contract, execution) in executions {
+            if !seen.insert(execution.exec_id.as_str()) {
+                continue;
+            }
+            let direction = match execution.side.as_str() {
+                "BOT" => 1.0,
+                "SLD" => -1.0,
+                _ => continue,
+            };
+            let multiplier = contract_multiplier(contract);
+            let arrival_price = arrival_prices.get(&execution.order_id);
+            let report = reports.get(execution.exec_id.as_str());
+
+            for (summaries, name) in [
+                (&mut by_symbol, &contract.symbol),
+                (&mut by_strategy, &execution.order_ref),
+            ] {
+                let summary = summaries
+                    .entry((name.clone(), contract.currency.clone()))
+                    .or_insert_with(|| TradeSummary::new(name, &contract.currency));
+                summary.executions += 1;
+                if direction > 0.0 {
+                    summary.bought += execution.shares;
+                } else {
+                    summary.sold += execution.shares;
+                }
+                summary.notional += execution.shares * execution.price * multiplier;
+                if let Some(arrival_price) = arrival_price {
+                    summary.slippage += direction
+                        * (execution.price - arrival_price)
+                        * execution.shares
+                        * multiplier;
+                    summary.arrival_notional += execution.shares * arrival_price * multiplier;
+                }
+                if let Some(report) = report {
+                    summary.commissions += report.commission;
+                    summary.realized_pnl += pnl_value(report.realized_pnl).unwrap_or(0.0);
+                }
+            }
+        }
+
+        TradeReport {
+            by_symbol: by_symbol.into_values().collect(),
+            by_strategy: by_strategy.into_values().collect(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn symbol(&self, symbol: &str) -> impl Iterator<Item = &TradeSummary> {
+        let symbol = symbol.to_string();
+        self.by_symbol
+            .iter()
+            .filter(move |summary| summary.name == symbol)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn strategy(&self, order_ref: &str) -> impl Iterator<Item = &TradeSummary> {
+        let order_ref = order_ref.to_string();
+        self.by_strategy
+            .iter()
+            .filter(move |summary| summary.name == order_ref)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
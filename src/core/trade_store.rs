@@ -0,0 +1,556 @@
+#![allow(clippy::too_many_arguments)]
+//! Persistence of executions, commission reports and orders in SQLite
+//!
+//! Feed a `TradeStore` from the `Wrapper` callbacks: `exec_details`,
+//! `commission_report`, `open_order` and `order_status`. Everything is
+//! written as it arrives, so the store survives restarts and can be
+//! queried for audits and end-of-day reports.
+//!
+//! The schema is stable within a `SCHEMA_VERSION`, which is stored as the
+//! `user_version` of the database. Contracts and orders are stored as JSON
+//! next to the columns that are queried. Times are RFC 3339 UTC timestamps
+//! with milliseconds, e.g. `2023-01-03T14:30:00.000Z`, which sort in time
+//! order.
+use std::fmt;
+use std::path::Path;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use rusqlite::types::Type;
+use rusqlite::{ffi, params, Connection, OptionalExtension, Row};
+use serde::de::DeserializeOwned;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::CommissionReport;
+use crate::core::contract::Contract;
+use crate::core::execution::{parse_execution_time, Execution};
+use crate::core::order::Order;
+
+/// The version of the schema, incremented on incompatible changes
+pub const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS executions (
+    exec_id TEXT PRIMARY KEY,
+    time TEXT NOT NULL,
+    time_utc TEXT,
+    account TEXT NOT NULL,
+    con_id INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    sec_type TEXT NOT NULL,
+    local_symbol TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    exchange TEXT NOT NULL,
+    side TEXT NOT NULL,
+    shares REAL NOT NULL,
+    price REAL NOT NULL,
+    perm_id INTEGER NOT NULL,
+    client_id INTEGER NOT NULL,
+    order_id INTEGER NOT NULL,
+    liquidation INTEGER NOT NULL,
+    cum_qty REAL NOT NULL,
+    avg_price REAL NOT NULL,
+    order_ref TEXT NOT NULL,
+    ev_rule TEXT NOT NULL,
+    ev_multiplier REAL NOT NULL,
+    model_code TEXT NOT NULL,
+    last_liquidity INTEGER NOT NULL,
+    contract TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS executions_time_utc ON executions (time_utc);
+CREATE TABLE IF NOT EXISTS commission_reports (
+    exec_id TEXT PRIMARY KEY,
+    commission REAL NOT NULL,
+    currency TEXT NOT NULL,
+    realized_pnl REAL NOT NULL,
+    yield REAL NOT NULL,
+    yield_redemption_date TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS orders (
+    order_id INTEGER PRIMARY KEY,
+    perm_id INTEGER NOT NULL,
+    client_id INTEGER NOT NULL,
+    account TEXT NOT NULL,
+    con_id INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    action TEXT NOT NULL,
+    order_type TEXT NOT NULL,
+    total_quantity REAL NOT NULL,
+    order_ref TEXT NOT NULL,
+    updated_utc TEXT NOT NULL,
+    contract TEXT NOT NULL,
+    order_json TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS order_states (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id INTEGER NOT NULL,
+    time_utc TEXT NOT NULL,
+    status TEXT NOT NULL,
+    filled REAL NOT NULL,
+    remaining REAL NOT NULL,
+    avg_fill_price REAL NOT NULL,
+    perm_id INTEGER NOT NULL,
+    parent_id INTEGER NOT NULL,
+    last_fill_price REAL NOT NULL,
+    client_id INTEGER NOT NULL,
+    why_held TEXT NOT NULL,
+    market_cap_price REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS order_states_order_id ON order_states (order_id);
+";
+
+const EXECUTION_COLUMNS: &str = "contract, exec_id, time, account, exchange, side, shares, price, \
+                                 perm_id, client_id, order_id, liquidation, cum_qty, avg_price, \
+                                 order_ref, ev_rule, ev_multiplier, model_code, last_liquidity";
+
+const ORDER_STATE_COLUMNS: &str = "order_id, time_utc, status, filled, remaining, avg_fill_price, \
+                                   perm_id, parent_id, last_fill_price, client_id, why_held, \
+                                   market_cap_price";
+
+//==================================================================================================
+/// Formats a time as stored
+pub fn format_store_time(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+//==================================================================================================
+fn to_json<T: Serialize>(value: &T) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|err| rusqlite::Error::ToSqlConversionFailure(err.into()))
+}
+
+//==================================================================================================
+fn from_json<T: DeserializeOwned>(row: &Row, index: usize) -> rusqlite::Result<T> {
+    let json: String = row.get(index)?;
+    serde_json::from_str(&json)
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, err.into()))
+}
+
+//==================================================================================================
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let time: String = row.get(index)?;
+    DateTime::parse_from_rfc3339(&time)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, err.into()))
+}
+
+//==================================================================================================
+/// A status of an order as reported by `Wrapper::order_status`
+///
+/// time - when the status was received
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrderStatusRecord {
+    pub order_id: i32,
+    pub time: DateTime<Utc>,
+    pub status: String,
+    pub filled: f64,
+    pub remaining: f64,
+    pub avg_fill_price: f64,
+    pub perm_id: i32,
+    pub parent_id: i32,
+    pub last_fill_price: f64,
+    pub client_id: i32,
+    pub why_held: String,
+    pub market_cap_price: f64,
+}
+
+impl OrderStatusRecord {
+    /// A status received now, with the arguments of `Wrapper::order_status`
+    pub fn new(
+        order_id: i32,
+        status: &str,
+        filled: f64,
+        remaining: f64,
+        avg_fill_price: f64,
+        perm_id: i32,
+        parent_id: i32,
+        last_fill_price: f64,
+        client_id: i32,
+        why_held: &str,
+        market_cap_price: f64,
+    ) -> Self {
+        OrderStatusRecord {
+            order_id,
+            time: Utc::now(),
+            status: status.to_string(),
+            filled,
+            remaining,
+            avg_fill_price,
+            perm_id,
+            parent_id,
+            last_fill_price,
+            client_id,
+            why_held: why_held.to_string(),
+            market_cap_price,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(OrderStatusRecord {
+            order_id: row.get(0)?,
+            time: parse_time(row, 1)?,
+            status: row.get(2)?,
+            filled: row.get(3)?,
+            remaining: row.get(4)?,
+            avg_fill_price: row.get(5)?,
+            perm_id: row.get(6)?,
+            parent_id: row.get(7)?,
+            last_fill_price: row.get(8)?,
+            client_id: row.get(9)?,
+            why_held: row.get(10)?,
+            market_cap_price: row.get(11)?,
+        })
+    }
+}
+
+impl fmt::Display for OrderStatusRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order_id: {}, time: {}, status: {}, filled: {}, remaining: {}, avg_fill_price: {}",
+            self.order_id,
+            format_store_time(&self.time),
+            self.status,
+            self.filled,
+            self.remaining,
+            self.avg_fill_price
+        )
+    }
+}
+
+//==================================================================================================
+/// A SQLite database of executions, commission reports, orders and order
+/// status transitions
+pub struct TradeStore {
+    connection: Connection,
+    time_zone: Tz,
+}
+
+impl TradeStore {
+    /// Opens or creates a database file
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Creates a database that only lives as long as the store
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn with_connection(connection: Connection) -> rusqlite::Result<Self> {
+        let version: i32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(rusqlite::Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_MISMATCH),
+                Some(format!(
+                    "The database has schema version {}, this version supports up to {}.",
+                    version, SCHEMA_VERSION
+                )),
+            ));
+        }
+        connection.execute_batch(SCHEMA)?;
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(TradeStore {
+            connection,
+            time_zone: Tz::UTC,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the time zone of execution times without one, i.e. the time zone
+    /// set in TWS. Defaults to UTC.
+    pub fn set_time_zone(&mut self, time_zone: Tz) {
+        self.time_zone = time_zone;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The underlying connection, for queries the store has no helper for
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Stores an execution. Returns false if it was already stored, e.g.
+    /// when the executions are requested again after a restart.
+    pub fn record_execution(
+        &self,
+        contract: &Contract,
+        execution: &Execution,
+    ) -> rusqlite::Result<bool> {
+        let time_utc = parse_execution_time(&execution.time, self.time_zone)
+            .map(|time| format_store_time(&time));
+        let inserted = self.connection.execute(
+            "INSERT OR IGNORE INTO executions (exec_id, time, time_utc, account, con_id, symbol, \
+             sec_type, local_symbol, currency, exchange, side, shares, price, perm_id, client_id, \
+             order_id, liquidation, cum_qty, avg_price, order_ref, ev_rule, ev_multiplier, \
+             model_code, last_liquidity, contract) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, \
+             ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25)",
+            params![
+                execution.exec_id,
+                execution.time,
+                time_utc,
+                execution.acct_number,
+                contract.con_id,
+                contract.symbol,
+                contract.sec_type.to_string(),
+                contract.local_symbol,
+                contract.currency,
+                execution.exchange,
+                execution.side,
+                execution.shares,
+                execution.price,
+                execution.perm_id,
+                execution.client_id,
+                execution.order_id,
+                execution.liquidation,
+                execution.cum_qty,
+                execution.avg_price,
+                execution.order_ref,
+                execution.ev_rule,
+                execution.ev_multiplier,
+                execution.model_code,
+                execution.last_liquidity,
+                to_json(contract)?,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Stores a commission report, replacing an earlier one of the same
+    /// execution
+    pub fn record_commission_report(&self, report: &CommissionReport) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO commission_reports (exec_id, commission, currency, \
+             realized_pnl, yield, yield_redemption_date) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                report.exec_id,
+                report.commission,
+                report.currency,
+                report.realized_pnl,
+                report.yield_,
+                report.yield_redemption_date,
+            ],
+        )?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Stores an order as received by `Wrapper::open_order`, replacing the
+    /// earlier version of a modified order
+    pub fn record_order(&self, contract: &Contract, order: &Order) -> rusqlite::Result<()> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO orders (order_id, perm_id, client_id, account, con_id, symbol, \
+             action, order_type, total_quantity, order_ref, updated_utc, contract, order_json) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                order.order_id,
+                order.perm_id,
+                order.client_id,
+                order.account,
+                contract.con_id,
+                contract.symbol,
+                order.action,
+                order.order_type,
+                order.total_quantity,
+                order.order_ref,
+                format_store_time(&Utc::now()),
+                to_json(contract)?,
+                to_json(order)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Stores a status of an order if it is a transition, i.e. its status or
+    /// filled quantity differs from the last one stored. TWS often repeats
+    /// statuses. Returns whether the status was stored.
+    pub fn record_order_status(&self, record: &OrderStatusRecord) -> rusqlite::Result<bool> {
+        let last = self.last_order_state(record.order_id)?;
+        if last.is_some_and(|last| last.status == record.status && last.filled == record.filled) {
+            return Ok(false);
+        }
+        self.connection.execute(
+            &format!(
+                "INSERT INTO order_states ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, \
+                 ?11, ?12)",
+                ORDER_STATE_COLUMNS
+            ),
+            params![
+                record.order_id,
+                format_store_time(&record.time),
+                record.status,
+                record.filled,
+                record.remaining,
+                record.avg_fill_price,
+                record.perm_id,
+                record.parent_id,
+                record.last_fill_price,
+                record.client_id,
+                record.why_held,
+                record.market_cap_price,
+            ],
+        )?;
+        Ok(true)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn execution_from_row(row: &Row) -> rusqlite::Result<(Contract, Execution)> {
+        Ok((
+            from_json(row, 0)?,
+            Execution {
+                exec_id: row.get(1)?,
+                time: row.get(2)?,
+                acct_number: row.get(3)?,
+                exchange: row.get(4)?,
+                side: row.get(5)?,
+                shares: row.get(6)?,
+                price: row.get(7)?,
+                perm_id: row.get(8)?,
+                client_id: row.get(9)?,
+                order_id: row.get(10)?,
+                liquidation: row.get(11)?,
+                cum_qty: row.get(12)?,
+                avg_price: row.get(13)?,
+                order_ref: row.get(14)?,
+                ev_rule: row.get(15)?,
+                ev_multiplier: row.get(16)?,
+                model_code: row.get(17)?,
+                last_liquidity: row.get(18)?,
+            },
+        ))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn execution(&self, exec_id: &str) -> rusqlite::Result<Option<(Contract, Execution)>> {
+        self.connection
+            .query_row(
+                &format!(
+                    "SELECT {} FROM executions WHERE exec_id = ?1",
+                    EXECUTION_COLUMNS
+                ),
+                [exec_id],
+                Self::execution_from_row,
+            )
+            .optional()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The executions from `from` up to but excluding `to`, in time order.
+    /// Executions whose time could not be parsed are left out.
+    pub fn executions_between(
+        &self,
+        from: &DateTime<Utc>,
+        to: &DateTime<Utc>,
+    ) -> rusqlite::Result<Vec<(Contract, Execution)>> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {} FROM executions WHERE time_utc >= ?1 AND time_utc < ?2 \
+             ORDER BY time_utc, exec_id",
+            EXECUTION_COLUMNS
+        ))?;
+        let executions = statement.query_map(
+            [format_store_time(from), format_store_time(to)],
+            Self::execution_from_row,
+        )?;
+        executions.collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn commission_report_from_row(row: &Row) -> rusqlite::Result<CommissionReport> {
+        Ok(CommissionReport {
+            exec_id: row.get(0)?,
+            commission: row.get(1)?,
+            currency: row.get(2)?,
+            realized_pnl: row.get(3)?,
+            yield_: row.get(4)?,
+            yield_redemption_date: row.get(5)?,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn commission_report(&self, exec_id: &str) -> rusqlite::Result<Option<CommissionReport>> {
+        self.connection
+            .query_row(
+                "SELECT exec_id, commission, currency, realized_pnl, yield, \
+                 yield_redemption_date FROM commission_reports WHERE exec_id = ?1",
+                [exec_id],
+                Self::commission_report_from_row,
+            )
+            .optional()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The commission reports of the executions from `from` up to but
+    /// excluding `to`, in the time order of the executions
+    pub fn commission_reports_between(
+        &self,
+        from: &DateTime<Utc>,
+        to: &DateTime<Utc>,
+    ) -> rusqlite::Result<Vec<CommissionReport>> {
+        let mut statement = self.connection.prepare(
+            "SELECT c.exec_id, c.commission, c.currency, c.realized_pnl, c.yield, \
+             c.yield_redemption_date FROM commission_reports c \
+             JOIN executions e ON e.exec_id = c.exec_id \
+             WHERE e.time_utc >= ?1 AND e.time_utc < ?2 ORDER BY e.time_utc, e.exec_id",
+        )?;
+        let reports = statement.query_map(
+            [format_store_time(from), format_store_time(to)],
+            Self::commission_report_from_row,
+        )?;
+        reports.collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The latest version of an order
+    pub fn order(&self, order_id: i32) -> rusqlite::Result<Option<(Contract, Order)>> {
+        self.connection
+            .query_row(
+                "SELECT contract, order_json FROM orders WHERE order_id = ?1",
+                [order_id],
+                |row| Ok((from_json(row, 0)?, from_json(row, 1)?)),
+            )
+            .optional()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The status transitions of an order, oldest first
+    pub fn order_states(&self, order_id: i32) -> rusqlite::Result<Vec<OrderStatusRecord>> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {} FROM order_states WHERE order_id = ?1 ORDER BY id",
+            ORDER_STATE_COLUMNS
+        ))?;
+        let records = statement.query_map([order_id], OrderStatusRecord::from_row)?;
+        records.collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn last_order_state(&self, order_id: i32) -> rusqlite::Result<Option<OrderStatusRecord>> {
+        self.connection
+            .query_row(
+                &format!(
+                    "SELECT {} FROM order_states WHERE order_id = ?1 ORDER BY id DESC LIMIT 1",
+                    ORDER_STATE_COLUMNS
+                ),
+                [order_id],
+                OrderStatusRecord::from_row,
+            )
+            .optional()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The last status of every order, by order id
+    pub fn last_order_states(&self) -> rusqlite::Result<Vec<OrderStatusRecord>> {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT {} FROM order_states WHERE id IN \
+             (SELECT MAX(id) FROM order_states GROUP BY order_id) ORDER BY order_id",
+            ORDER_STATE_COLUMNS
+        ))?;
+        let records = statement.query_map([], OrderStatusRecord::from_row)?;
+        records.collect()
+    }
+}
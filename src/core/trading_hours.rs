@@ -0,0 +1,193 @@
+//! Parsing of the trading and liquid hours of `ContractDetails`
+//!
+//! TWS sends the hours as days separated by `;`, each either a range like
+//! `20240102:0930-20240102:1600` or closed, like `20240103:CLOSED`. Older
+//! versions send the times of a day without the date of their end, e.g.
+//! `20090507:0700-1830,1830-2330`; ranges that end before they start end on
+//! the next day. The times are in the time zone of `time_zone_id`.
+use std::fmt;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+const HOURS_DATE_FORMAT: &str = "%Y%m%d";
+const HOURS_TIME_FORMAT: &str = "%H%M";
+
+//==================================================================================================
+/// A range of time the market of a contract is open; the close is exclusive
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Session {
+    #[cfg_attr(feature = "serde", serde(with = "session_time_serde"))]
+    pub open: DateTime<Tz>,
+    #[cfg_attr(feature = "serde", serde(with = "session_time_serde"))]
+    pub close: DateTime<Tz>,
+}
+
+impl Session {
+    pub fn new(open: DateTime<Tz>, close: DateTime<Tz>) -> Self {
+        Session { open, close }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether `time`, in any time zone, falls into the session
+    pub fn contains<T: TimeZone>(&self, time: &DateTime<T>) -> bool {
+        self.open <= *time && self.close > *time
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn duration(&self) -> Duration {
+        self.close - self.open
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "open: {}, close: {}", self.open, self.close)
+    }
+}
+
+//==================================================================================================
+/// (De)serializes a session time as seconds since the epoch and the name of
+/// its time zone, as chrono can not deserialize a `DateTime<Tz>`
+#[cfg(feature = "serde")]
+mod session_time_serde {
+    use chrono::{DateTime, TimeZone};
+    use chrono_tz::Tz;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &DateTime<Tz>, serializer: S) -> Result<S::Ok, S::Error> {
+        (time.timestamp(), time.timezone()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Tz>, D::Error> {
+        let (seconds, time_zone) = <(i64, Tz)>::deserialize(deserializer)?;
+        time_zone
+            .timestamp_opt(seconds, 0)
+            .single()
+            .ok_or_else(|| D::Error::custom("Invalid session time"))
+    }
+}
+
+//==================================================================================================
+/// The time zone of a `time_zone_id` of `ContractDetails`. Besides the names
+/// of the tz database TWS sends abbreviations like `EST` or `JST`, which are
+/// mapped to the zone of the exchanges that use them, i.e. with daylight
+/// saving time where it applies. Text after the first word, as in
+/// `EST (Eastern Standard Time)`, is ignored.
+pub fn parse_time_zone_id(time_zone_id: &str) -> Option<Tz> {
+    let time_zone_id = time_zone_id.split_whitespace().next()?;
+    match time_zone_id.to_uppercase().as_str() {
+        "EST" | "EDT" | "EST5EDT" => Some(Tz::US__Eastern),
+        "CST" | "CDT" | "CST6CDT" => Some(Tz::US__Central),
+        "MST" | "MDT" | "MST7MDT" => Some(Tz::US__Mountain),
+        "PST" | "PDT" | "PST8PDT" => Some(Tz::US__Pacific),
+        "JST" => Some(Tz::Asia__Tokyo),
+        "HKT" => Some(Tz::Asia__Hong_Kong),
+        "AET" | "AEST" | "AEDT" => Some(Tz::Australia__Sydney),
+        "IST" => Some(Tz::Asia__Kolkata),
+        _ => time_zone_id.parse().ok(),
+    }
+}
+
+//==================================================================================================
+/// Parses trading or liquid hours into sessions ordered by their open
+///
+/// # Arguments
+/// * hours - the hours as sent by TWS, see the module documentation
+/// * time_zone - the time zone of the hours, see `parse_time_zone_id`
+pub fn parse_trading_hours(hours: &str, time_zone: &Tz) -> Result<Vec<Session>, String> {
+    let mut sessions = vec![];
+    for day in hours
+        .split(';')
+        .map(str::trim)
+        .filter(|day| !day.is_empty())
+    {
+        let (date, ranges) = day
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid trading hours: {}", day))?;
+        if ranges.trim().eq_ignore_ascii_case("CLOSED") {
+            continue;
+        }
+        let date = parse_date(date)?;
+        for range in ranges
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+        {
+            let (open, close) = range
+                .split_once('-')
+                .ok_or_else(|| format!("Invalid trading hours: {}", range))?;
+            let (open, _) = parse_time(open, date)?;
+            let (mut close, dated) = parse_time(close, open.date())?;
+            if !dated && close <= open {
+                close += Duration::days(1);
+            }
+            sessions.push(Session::new(
+                local_time(&open, time_zone)?,
+                local_time(&close, time_zone)?,
+            ));
+        }
+    }
+    sessions.sort_by_key(|session| session.open);
+    Ok(sessions)
+}
+
+//==================================================================================================
+/// The session `time` falls into, if any
+///
+/// # Arguments
+/// * sessions - sessions ordered by their open, as returned by
+///   `parse_trading_hours`
+pub fn session_at<'a, T: TimeZone>(
+    sessions: &'a [Session],
+    time: &DateTime<T>,
+) -> Option<&'a Session> {
+    sessions.iter().find(|session| session.contains(time))
+}
+
+//==================================================================================================
+/// The first session that opens after `time`
+pub fn next_session<'a, T: TimeZone>(
+    sessions: &'a [Session],
+    time: &DateTime<T>,
+) -> Option<&'a Session> {
+    sessions.iter().find(|session| session.open > *time)
+}
+
+//==================================================================================================
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), HOURS_DATE_FORMAT)
+        .map_err(|_| format!("Invalid trading hours date: {}", date))
+}
+
+//==================================================================================================
+/// Parses `YYYYMMDD:hhmm` or `hhmm` on `date`, and whether the time had a
+/// date of its own. `2400` is midnight at the end of the day.
+fn parse_time(time: &str, date: NaiveDate) -> Result<(NaiveDateTime, bool), String> {
+    let (date, time, dated) = match time.split_once(':') {
+        Some((date, time)) => (parse_date(date)?, time, true),
+        None => (date, time, false),
+    };
+    if time.trim() == "2400" {
+        return Ok((date.and_time(NaiveTime::MIN) + Duration::days(1), dated));
+    }
+    let time = NaiveTime::parse_from_str(time.trim(), HOURS_TIME_FORMAT)
+        .map_err(|_| format!("Invalid trading hours time: {}", time))?;
+    Ok((date.and_time(time), dated))
+}
+
+//==================================================================================================
+fn local_time(time: &NaiveDateTime, time_zone: &Tz) -> Result<DateTime<Tz>, String> {
+    // Times that fall into a daylight saving gap or overlap are resolved to
+    // the earlier offset
+    time_zone
+        .from_local_datetime(time)
+        .earliest()
+        .ok_or_else(|| format!("Invalid local time {} in {}", time, time_zone))
+}
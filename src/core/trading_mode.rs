@@ -0,0 +1,98 @@
+//! Telling paper trading sessions from live ones
+//!
+//! Paper trading accounts have codes starting with `D`, e.g. `DU1234567`
+//! for an individual account or `DF1234567` for an advisor master, and TWS
+//! and IB Gateway listen on different default ports for paper and live
+//! sessions. `EClient::trading_mode` goes by the managed accounts once they
+//! have arrived and by the port before that, unless a mode has been set with
+//! `EClient::set_trading_mode`.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::MarketDataTypeEnum;
+
+/// Default port of TWS for live sessions
+pub const TWS_LIVE_PORT: u32 = 7496;
+/// Default port of TWS for paper sessions
+pub const TWS_PAPER_PORT: u32 = 7497;
+/// Default port of IB Gateway for live sessions
+pub const GATEWAY_LIVE_PORT: u32 = 4001;
+/// Default port of IB Gateway for paper sessions
+pub const GATEWAY_PAPER_PORT: u32 = 4002;
+
+//==================================================================================================
+/// Whether a session trades with real money
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TradingMode {
+    Paper,
+    Live,
+    #[default]
+    Unknown,
+}
+
+impl TradingMode {
+    /// The mode of the default port of TWS or IB Gateway, Unknown for other
+    /// ports
+    pub fn from_port(port: u32) -> Self {
+        match port {
+            TWS_PAPER_PORT | GATEWAY_PAPER_PORT => TradingMode::Paper,
+            TWS_LIVE_PORT | GATEWAY_LIVE_PORT => TradingMode::Live,
+            _ => TradingMode::Unknown,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The mode of the managed accounts, Unknown if there are none. A login
+    /// never mixes paper and live accounts, so the first one decides.
+    pub fn from_accounts(accounts: &[String]) -> Self {
+        match accounts.first() {
+            Some(account) if account.starts_with('D') => TradingMode::Paper,
+            Some(_) => TradingMode::Live,
+            None => TradingMode::Unknown,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The mode of the managed accounts if they are known, of the port
+    /// otherwise
+    pub fn detect(port: u32, accounts: &[String]) -> Self {
+        match TradingMode::from_accounts(accounts) {
+            TradingMode::Unknown => TradingMode::from_port(port),
+            mode => mode,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_paper(&self) -> bool {
+        *self == TradingMode::Paper
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_live(&self) -> bool {
+        *self == TradingMode::Live
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The market data type to request by default. Paper accounts often
+    /// lack market data subscriptions, so they ask for delayed data, which
+    /// TWS only sends where there is no real time data.
+    pub fn default_market_data_type(&self) -> MarketDataTypeEnum {
+        match self {
+            TradingMode::Paper => MarketDataTypeEnum::Delayed,
+            TradingMode::Live | TradingMode::Unknown => MarketDataTypeEnum::Realtime,
+        }
+    }
+}
+
+impl fmt::Display for TradingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TradingMode::Paper => write!(f, "paper"),
+            TradingMode::Live => write!(f, "live"),
+            TradingMode::Unknown => write!(f, "unknown"),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Implied volatility surfaces of option chains and their snapshots
+//!
+//! A `VolSurface` collects the implied volatilities TWS computes for the
+//! options of one underlying, as received with
+//! `Wrapper::tick_option_computation` for the market data requests of the
+//! options tracked with `VolSurface::track`, e.g. those of
+//! `OptionChain::resolve`. The model computations are used, since those
+//! are sent for options without quotes too.
+//!
+//! `VolSurface::snapshot` captures the whole surface with the time and the
+//! spot of the underlying, so surfaces can be archived, e.g. at the end of
+//! the day, as JSON with `VolSurfaceSnapshot::write_json` or as Parquet with
+//! `arrow_export::write_vol_surface_parquet`.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::io;
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::TickType;
+use crate::core::continuous_futures::parse_expiry;
+use crate::core::contract::{Contract, Right};
+
+//==================================================================================================
+/// TWS sends these for values it could not compute, e.g. -1 or -2 for the
+/// implied volatility and the maximum double
+fn computed(value: f64) -> Option<f64> {
+    (0.0..f64::MAX / 2.0).contains(&value).then_some(value)
+}
+
+//==================================================================================================
+/// The implied volatility of one option of a surface
+///
+/// implied_vol - annualized, e.g. 0.25 for 25%
+/// und_price - the price of the underlying the volatility was computed at
+/// time - when the volatility was received
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VolPoint {
+    pub expiry: NaiveDate,
+    pub strike: f64,
+    pub right: Right,
+    pub implied_vol: f64,
+    pub delta: Option<f64>,
+    pub und_price: Option<f64>,
+    pub time: DateTime<Utc>,
+}
+
+impl fmt::Display for VolPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expiry: {}, strike: {}, right: {}, implied_vol: {}, delta: {:?}, und_price: {:?}, \
+             time: {}",
+            self.expiry.format("%Y%m%d"),
+            self.strike,
+            self.right,
+            self.implied_vol,
+            self.delta,
+            self.und_price,
+            self.time.to_rfc3339()
+        )
+    }
+}
+
+//==================================================================================================
+/// A surface as it was at one time
+///
+/// spot - the price of the underlying, the one set with `VolSurface::set_spot`
+///   or else the latest one the volatilities were computed at
+/// points - ordered by expiry, strike and right
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VolSurfaceSnapshot {
+    pub underlying: String,
+    pub time: DateTime<Utc>,
+    pub spot: Option<f64>,
+    pub points: Vec<VolPoint>,
+}
+
+impl VolSurfaceSnapshot {
+    /// The expiries of the surface, ascending
+    pub fn expiries(&self) -> Vec<NaiveDate> {
+        let mut expiries: Vec<NaiveDate> = self.points.iter().map(|point| point.expiry).collect();
+        expiries.dedup();
+        expiries
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The points of one expiry, the smile
+    pub fn smile(&self, expiry: NaiveDate) -> impl Iterator<Item = &VolPoint> {
+        self.points
+            .iter()
+            .filter(move |point| point.expiry == expiry)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Writes the snapshot to a JSON file, replacing the file if it exists
+    #[cfg(feature = "serde")]
+    pub fn write_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = self
+            .to_json()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::write(path, json)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    #[cfg(feature = "serde")]
+    pub fn read_json<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+impl fmt::Display for VolSurfaceSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "underlying: {}, time: {}, spot: {:?}, points: {}",
+            self.underlying,
+            self.time.to_rfc3339(),
+            self.spot,
+            self.points.len()
+        )
+    }
+}
+
+//==================================================================================================
+/// The expiry, the strike in thousandths and the right of an option,
+/// ordering the points of a surface
+type PointKey = (NaiveDate, i64, String);
+
+//==================================================================================================
+fn point_key(expiry: NaiveDate, strike: f64, right: Right) -> PointKey {
+    (expiry, (strike * 1000.0).round() as i64, right.to_string())
+}
+
+//==================================================================================================
+/// The implied volatilities of the options of one underlying
+#[derive(Clone, Debug, Default)]
+pub struct VolSurface {
+    underlying: String,
+    options: HashMap<i32, (NaiveDate, f64, Right)>,
+    points: BTreeMap<PointKey, VolPoint>,
+    spot: Option<f64>,
+    und_price: Option<f64>,
+}
+
+impl VolSurface {
+    pub fn new(underlying: &str) -> Self {
+        VolSurface {
+            underlying: underlying.to_string(),
+            ..Self::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn underlying(&self) -> &str {
+        &self.underlying
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Tracks the market data request of an option. Returns false for
+    /// contracts that are not options with an expiry `YYYYMMDD`.
+    pub fn track(&mut self, ticker_id: i32, contract: &Contract) -> bool {
+        let Some(expiry) = parse_expiry(&contract.last_trade_date_or_contract_month) else {
+            return false;
+        };
+        if contract.right == Right::None {
+            return false;
+        }
+        self.options
+            .insert(ticker_id, (expiry, contract.strike, contract.right));
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn untrack(&mut self, ticker_id: i32) {
+        self.options.remove(&ticker_id);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Applies an option computation of a tracked request, as received with
+    /// `Wrapper::tick_option_computation`. Returns whether it changed the
+    /// surface; computations other than the model's and those without an
+    /// implied volatility are ignored.
+    pub fn update(
+        &mut self,
+        ticker_id: i32,
+        tick_type: TickType,
+        implied_vol: f64,
+        delta: f64,
+        und_price: f64,
+        time: DateTime<Utc>,
+    ) -> bool {
+        if !matches!(
+            tick_type,
+            TickType::ModelOption | TickType::DelayedModelOption
+        ) {
+            return false;
+        }
+        let Some(&(expiry, strike, right)) = self.options.get(&ticker_id) else {
+            return false;
+        };
+        let Some(implied_vol) = computed(implied_vol) else {
+            return false;
+        };
+        let und_price = computed(und_price);
+        self.und_price = und_price.or(self.und_price);
+        self.set_point(VolPoint {
+            expiry,
+            strike,
+            right,
+            implied_vol,
+            delta: (delta.is_finite() && delta.abs() <= 1.0).then_some(delta),
+            und_price,
+            time,
+        });
+        true
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets a point, replacing that of the same option
+    pub fn set_point(&mut self, point: VolPoint) {
+        self.points
+            .insert(point_key(point.expiry, point.strike, point.right), point);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Sets the price of the underlying, e.g. from its quote
+    pub fn set_spot(&mut self, spot: f64) {
+        self.spot = Some(spot);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The point of an option, if its volatility has been received
+    pub fn point(&self, expiry: NaiveDate, strike: f64, right: Right) -> Option<&VolPoint> {
+        self.points.get(&point_key(expiry, strike, right))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The points, ordered by expiry, strike and right
+    pub fn points(&self) -> impl Iterator<Item = &VolPoint> {
+        self.points.values()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Captures the surface at `time`
+    pub fn snapshot(&self, time: DateTime<Utc>) -> VolSurfaceSnapshot {
+        VolSurfaceSnapshot {
+            underlying: self.underlying.clone(),
+            time,
+            spot: self.spot.or(self.und_price),
+            points: self.points.values().cloned().collect(),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Forgets the points and prices, keeping the tracked requests
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.spot = None;
+        self.und_price = None;
+    }
+}
@@ -0,0 +1,216 @@
+//! Volume-at-price profiles built from trade ticks
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{FixedOffset, NaiveTime, Timelike};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::core::common::HistoricalTickLast;
+
+//==================================================================================================
+/// The traded volume at one price bucket of a `VolumeProfile`
+///
+/// price - the price of the bucket, a multiple of the bucket size
+/// volume - the volume traded in the bucket
+/// count - the number of trades in the bucket
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct VolumeAtPrice {
+    pub price: f64,
+    pub volume: f64,
+    pub count: i32,
+}
+
+impl fmt::Display for VolumeAtPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "price: {}, volume: {}, count: {}",
+            self.price, self.volume, self.count
+        )
+    }
+}
+
+//==================================================================================================
+/// Builds a volume-at-price profile from the trades of a tick-by-tick
+/// (`AllLast`/`Last`) stream or from historical `TRADES` ticks.
+///
+/// Prices are rounded to the nearest multiple of the bucket size. If a
+/// session start is set, the profile resets with the first trade of each new
+/// session.
+#[derive(Clone, Debug)]
+pub struct VolumeProfile {
+    bucket_size: f64,
+    session_start: Option<(NaiveTime, FixedOffset)>,
+    session: Option<i64>,
+    // (volume, count), keyed by the price in multiples of the bucket size
+    buckets: BTreeMap<i64, (f64, i32)>,
+    total_volume: f64,
+}
+
+impl VolumeProfile {
+    pub fn new(bucket_size: f64) -> Self {
+        VolumeProfile {
+            bucket_size: if bucket_size > 0.0 { bucket_size } else { 0.01 },
+            session_start: None,
+            session: None,
+            buckets: BTreeMap::new(),
+            total_volume: 0.0,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Resets the profile whenever a trade falls into a new session. Sessions
+    /// start daily at `time` in the time zone given by `offset`, e.g. 09:30 at
+    /// UTC-5 for the US equity regular trading hours in winter.
+    pub fn with_session_start(mut self, time: NaiveTime, offset: FixedOffset) -> Self {
+        self.session_start = Some((time, offset));
+        self
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn bucket_size(&self) -> f64 {
+        self.bucket_size
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a trade
+    ///
+    /// # Arguments
+    /// * time - time of the trade in seconds since the epoch
+    /// * price - trade price
+    /// * size - trade size
+    pub fn update_trade(&mut self, time: i64, price: f64, size: f64) {
+        if let Some(session) = self.session_of(time) {
+            if matches!(self.session, Some(current) if current != session) {
+                self.reset();
+            }
+            self.session = Some(session);
+        }
+        if size <= 0.0 || !price.is_finite() {
+            return;
+        }
+
+        let bucket = self
+            .buckets
+            .entry((price / self.bucket_size).round() as i64)
+            .or_insert((0.0, 0));
+        bucket.0 += size;
+        bucket.1 += 1;
+        self.total_volume += size;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Adds a historical trade as received by `Wrapper::historical_ticks_last`
+    pub fn update_historical_tick_last(&mut self, tick: &HistoricalTickLast) {
+        self.update_trade(tick.time as i64, tick.price, tick.size);
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The buckets with any volume, ordered by price
+    pub fn levels(&self) -> Vec<VolumeAtPrice> {
+        self.buckets
+            .iter()
+            .map(|(bucket, (volume, count))| VolumeAtPrice {
+                price: self.bucket_price(*bucket),
+                volume: *volume,
+                count: *count,
+            })
+            .collect()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn volume_at(&self, price: f64) -> f64 {
+        self.buckets
+            .get(&((price / self.bucket_size).round() as i64))
+            .map_or(0.0, |(volume, _)| *volume)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    pub fn total_volume(&self) -> f64 {
+        self.total_volume
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The price with the highest volume; the lowest such price on ties
+    pub fn point_of_control(&self) -> Option<f64> {
+        self.point_of_control_bucket()
+            .map(|bucket| self.bucket_price(bucket))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The low and high price of the range around the point of control that
+    /// holds `fraction` of the total volume, e.g. 0.7 for the customary 70%
+    /// value area. The range grows towards the side with the higher volume.
+    pub fn value_area(&self, fraction: f64) -> Option<(f64, f64)> {
+        let levels: Vec<(i64, f64)> = self
+            .buckets
+            .iter()
+            .map(|(bucket, (volume, _))| (*bucket, *volume))
+            .collect();
+        let poc = self.point_of_control_bucket()?;
+        let mut low = levels.iter().position(|(bucket, _)| *bucket == poc)?;
+        let mut high = low;
+        let target = self.total_volume * fraction.clamp(0.0, 1.0);
+        let mut volume = levels[low].1;
+
+        while volume < target && (low > 0 || high + 1 < levels.len()) {
+            let below = if low > 0 { levels[low - 1].1 } else { -1.0 };
+            let above = if high + 1 < levels.len() {
+                levels[high + 1].1
+            } else {
+                -1.0
+            };
+            if above >= below {
+                high += 1;
+                volume += above;
+            } else {
+                low -= 1;
+                volume += below;
+            }
+        }
+        Some((
+            self.bucket_price(levels[low].0),
+            self.bucket_price(levels[high].0),
+        ))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Clears the profile, e.g. at the start of a session
+    pub fn reset(&mut self) {
+        self.buckets.clear();
+        self.total_volume = 0.0;
+        self.session = None;
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn point_of_control_bucket(&self) -> Option<i64> {
+        self.buckets
+            .iter()
+            .fold(
+                None,
+                |best: Option<(i64, f64)>, (bucket, (volume, _))| match best {
+                    Some((_, best_volume)) if best_volume >= *volume => best,
+                    _ => Some((*bucket, *volume)),
+                },
+            )
+            .map(|(bucket, _)| bucket)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    fn bucket_price(&self, bucket: i64) -> f64 {
+        // Round to get rid of representation errors such as 3 * 0.1
+        (bucket as f64 * self.bucket_size * 1e9).round() / 1e9
+    }
+
+    //----------------------------------------------------------------------------------------------
+    // The number of the session a trade falls into, counted in days since
+    // the epoch in the session's time zone
+    fn session_of(&self, time: i64) -> Option<i64> {
+        let (start, offset) = self.session_start?;
+        let seconds =
+            time + offset.local_minus_utc() as i64 - start.num_seconds_from_midnight() as i64;
+        Some(seconds.div_euclid(86400))
+    }
+}
@@ -8,22 +8,56 @@ use bigdecimal::BigDecimal;
 
 use crate::core::common::RealTimeBar;
 use crate::core::common::{
-    BarData, CommissionReport, DepthMktDataDescription, FaDataType, FamilyCode, HistogramData,
-    HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider, PriceIncrement,
-    SmartComponent, TickAttrib, TickAttribBidAsk, TickAttribLast, TickByTickType, TickType,
+    BarData, CommissionReport, DataFarmStatus, DepthMktDataDescription, FaDataType, FamilyCode,
+    HistogramData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider,
+    PriceIncrement, TickAttrib, TickAttribBidAsk, TickAttribLast, TickByTickType, TickType,
 };
 use crate::core::contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract};
+use crate::core::display_groups::DisplayGroupContract;
+use crate::core::errors::DecodeError;
 use crate::core::execution::Execution;
+use crate::core::historical::HistoricalSchedule;
+use crate::core::news::{NewsArticle, NewsBulletin, NewsHeadline};
 use crate::core::order::{Order, OrderState, SoftDollarTier};
+use crate::core::quote_cache::{AuctionState, TradingStatus};
+use crate::core::smart_components::SmartComponentMap;
+#[cfg(feature = "wsh")]
+use crate::core::wsh::WshEvent;
 
 /// A trait that clients will implement that declares callback functions that
 /// get called when the application receives messages from the Trader
 /// WorkStation or IB Gateway.
+///
+/// Sizes, of ticks, depth rows, orders and positions alike, are passed as
+/// `BigDecimal`, like the `Position` and `PortfolioItem` types.
 pub trait Wrapper: Send + Sync {
     //----------------------------------------------------------------------------------------------
     /// This event is called when there is an error with the
     /// communication or when TWS wants to send a message to the core.
-    fn error(&mut self, request_id: i32, error_code: i32, error_string: &str);
+    ///
+    /// # Arguments
+    /// * advanced_order_reject_json - the details of an order rejection as
+    ///   JSON, empty for other errors and for servers before
+    ///   `MIN_SERVER_VER_ADVANCED_ORDER_REJECT`
+    fn error(
+        &mut self,
+        request_id: i32,
+        error_code: i32,
+        error_string: &str,
+        advanced_order_reject_json: &str,
+    );
+
+    //----------------------------------------------------------------------------------------------
+    /// Called when TWS reports a change in the connectivity of a market data,
+    /// historical data (HMDS) or security definition farm. The same message
+    /// is also passed to `error` with its original code.
+    fn data_farm_status(&mut self, status: &DataFarmStatus);
+
+    //----------------------------------------------------------------------------------------------
+    /// Called when a message could not be decoded, e.g. because it was
+    /// truncated, sent by a newer TWS or, with `InvalidUtf8Policy::Error`,
+    /// not valid UTF-8. The message is dropped and the connection stays up.
+    fn decode_error(&mut self, error: &DecodeError);
 
     //----------------------------------------------------------------------------------------------
     fn win_error(&mut self, text: &str, last_error: i32);
@@ -47,7 +81,7 @@ pub trait Wrapper: Send + Sync {
 
     //----------------------------------------------------------------------------------------------
     ///Market data tick size callback. Handles all size-related ticks.
-    fn tick_size(&mut self, request_id: i32, tick_type: TickType, size: i32);
+    fn tick_size(&mut self, request_id: i32, tick_type: TickType, size: BigDecimal);
 
     //----------------------------------------------------------------------------------------------
     /// When requesting market data snapshots, this market will indicate the
@@ -57,6 +91,27 @@ pub trait Wrapper: Send + Sync {
     //----------------------------------------------------------------------------------------------
     fn tick_generic(&mut self, request_id: i32, tick_type: TickType, value: f64);
 
+    //----------------------------------------------------------------------------------------------
+    /// Called after `tick_generic` for the `TickType::Halted` and
+    /// `TickType::DelayedHalted` ticks with the decoded trading status. The
+    /// status is also kept by the client, see `EClient::is_halted`.
+    ///
+    /// # Arguments
+    /// * request_id - The request's identifier
+    /// * trading_status - Whether trading is halted or paused
+    fn trading_status(&mut self, request_id: i32, trading_status: TradingStatus);
+
+    //----------------------------------------------------------------------------------------------
+    /// Called after `tick_price` or `tick_size` for the auction price,
+    /// volume, imbalance and regulatory imbalance ticks with the updated
+    /// auction state of the subscription. Request the generic tick 225 to
+    /// receive them.
+    ///
+    /// # Arguments
+    /// * request_id - The request's identifier
+    /// * auction_state - The latest values of all auction ticks
+    fn auction_state(&mut self, request_id: i32, auction_state: AuctionState);
+
     //----------------------------------------------------------------------------------------------
     fn tick_string(&mut self, request_id: i32, tick_type: TickType, value: &str);
 
@@ -151,8 +206,8 @@ pub trait Wrapper: Send + Sync {
         &mut self,
         order_id: i32,
         status: &str,
-        filled: f64,
-        remaining: f64,
+        filled: BigDecimal,
+        remaining: BigDecimal,
         avg_fill_price: f64,
         perm_id: i32,
         parent_id: i32,
@@ -202,7 +257,7 @@ pub trait Wrapper: Send + Sync {
     fn update_portfolio(
         &mut self,
         contract: Contract,
-        position: f64,
+        position: BigDecimal,
         market_price: f64,
         market_value: f64,
         average_cost: f64,
@@ -274,7 +329,7 @@ pub trait Wrapper: Send + Sync {
         operation: i32,
         side: i32,
         price: f64,
-        size: i32,
+        size: BigDecimal,
     );
 
     //----------------------------------------------------------------------------------------------
@@ -302,28 +357,17 @@ pub trait Wrapper: Send + Sync {
         operation: i32,
         side: i32,
         price: f64,
-        size: i32,
+        size: BigDecimal,
         is_smart_depth: bool,
     );
 
     //----------------------------------------------------------------------------------------------
-    /// provides IB's bulletins
+    /// provides IB's bulletins, see EClient::request_news_bulletins
     ///
     /// # Arguments
-    /// * msg_id - the bulletin's identifier
-    /// * msg_type - one of:
-    ///     * 1 - Regular news bulletin
-    ///     * 2 - Exchange no longer available for trading
-    ///     * 3 - Exchange is available for trading
-    /// * news_message - the message
-    /// * origin_exch -    the exchange where the message comes from.
-    fn update_news_bulletin(
-        &mut self,
-        msg_id: i32,
-        msg_type: i32,
-        news_message: &str,
-        origin_exch: &str,
-    );
+    /// * bulletin - the bulletin's identifier, type, message and originating
+    ///   exchange
+    fn update_news_bulletin(&mut self, bulletin: NewsBulletin);
 
     //----------------------------------------------------------------------------------------------
     /// Receives a comma-separated string with the managed account ids.
@@ -343,6 +387,14 @@ pub trait Wrapper: Send + Sync {
     ///     * faXmlData -  the xml-formatted configuration
     fn receive_financial_advisor(&mut self, fa_data: FaDataType, cxml: &str);
 
+    //----------------------------------------------------------------------------------------------
+    /// Confirms a change of the FA configuration by EClient::replace_fa
+    ///
+    /// # Arguments
+    /// * request_id - the id passed to replace_fa
+    /// * text - the result of the change
+    fn replace_fa_end(&mut self, request_id: i32, text: &str);
+
     //----------------------------------------------------------------------------------------------
     ///  returns the requested historical data bars
     ///
@@ -357,7 +409,9 @@ pub trait Wrapper: Send + Sync {
 
     //----------------------------------------------------------------------------------------------
     /// Provides the xml-formatted parameters available to create a market
-    /// scanner.
+    /// scanner. With the `scanner-params` feature, ScannerParameters::parse
+    /// turns the XML into typed lists of instruments, locations, scan codes
+    /// and filters.
     ///
     /// # Arguments
     /// * xml -   the xml-formatted string with the available parameters.
@@ -430,7 +484,7 @@ pub trait Wrapper: Send + Sync {
     //----------------------------------------------------------------------------------------------
     /// This event returns real-time positions for all accounts in
     /// response to the reqPositions() method.
-    fn position(&mut self, account: &str, contract: Contract, position: f64, avg_cost: f64);
+    fn position(&mut self, account: &str, contract: Contract, position: BigDecimal, avg_cost: f64);
 
     //----------------------------------------------------------------------------------------------
     /// This is called once all position data for a given request are
@@ -475,11 +529,10 @@ pub trait Wrapper: Send + Sync {
     ///
     /// # Arguments
     /// * request_id - The requestId specified in query_display_groups().
-    /// * groups - A list of integers representing visible group ID's separated
-    ///   by the | character, and sorted by most used group first. This list
-    ///   will not change during TWS session (in other words, user cannot add a
-    ///   new group; sorting can change though).
-    fn display_group_list(&mut self, request_id: i32, groups: &str);
+    /// * groups - The ids of the visible groups, sorted by most used group
+    ///   first. This list will not change during TWS session (in other words,
+    ///   user cannot add a new group; sorting can change though).
+    fn display_group_list(&mut self, request_id: i32, groups: Vec<i32>);
 
     //----------------------------------------------------------------------------------------------
     /// This is sent by TWS to the API core once after receiving
@@ -489,13 +542,10 @@ pub trait Wrapper: Send + Sync {
     ///
     /// # Arguments
     /// * request_id - The requestId specified in subscribe_to_group_events().
-    /// * contract_info - The encoded value that uniquely represents the
-    ///   contract in IB. Possible values include: none = empty selection
-    ///   contractID@exchange = any non-combination contract.
-    ///
-    ///                 //Examples: 8314@SMART for IBM SMART; 8314@ARCA for IBM
-    /// @ARCA.                 //combo = if any combo is selected.
-    fn display_group_updated(&mut self, request_id: i32, contract_info: &str);
+    /// * contract_info - The contract selected in the group: nothing, a
+    ///   single contract on an exchange, e.g. 8314@SMART for IBM SMART, or a
+    ///   combo.
+    fn display_group_updated(&mut self, request_id: i32, contract_info: DisplayGroupContract);
 
     //----------------------------------------------------------------------------------------------
     /// same as position() except it can be for a certain account/model
@@ -505,7 +555,7 @@ pub trait Wrapper: Send + Sync {
         account: &str,
         model_code: &str,
         contract: Contract,
-        pos: f64,
+        pos: BigDecimal,
         avg_cost: f64,
     );
 
@@ -537,10 +587,16 @@ pub trait Wrapper: Send + Sync {
     /// underlier moves. TWS's option model volatilities, prices, and
     /// deltas, along with the present value of dividends expected on that
     /// options underlier are received.
+    ///
+    /// # Arguments
+    /// * tick_attrib - 0 if the implied volatility is return based, 1 if it
+    ///   is price based, `UNSET_INTEGER` for servers before
+    ///   `MIN_SERVER_VER_PRICE_BASED_VOLATILITY`
     fn tick_option_computation(
         &mut self,
         request_id: i32,
         tick_type: TickType,
+        tick_attrib: i32,
         implied_vol: f64,
         delta: f64,
         opt_price: f64,
@@ -610,20 +666,13 @@ pub trait Wrapper: Send + Sync {
     );
 
     //----------------------------------------------------------------------------------------------
-    /// returns news headlines
-    fn tick_news(
-        &mut self,
-        ticker_id: i32,
-        time_stamp: i32,
-        provider_code: &str,
-        article_id: &str,
-        headline: &str,
-        extra_data: &str,
-    );
+    /// returns news headlines of a news tick subscription, see
+    /// EClient::request_news_ticks
+    fn tick_news(&mut self, ticker_id: i32, headline: NewsHeadline);
 
     //----------------------------------------------------------------------------------------------
-    /// returns exchange component mapping
-    fn smart_components(&mut self, request_id: i32, smart_components: Vec<SmartComponent>);
+    /// returns exchange component mapping, keyed by bit number
+    fn smart_components(&mut self, request_id: i32, smart_components: SmartComponentMap);
 
     //----------------------------------------------------------------------------------------------
     /// returns exchange map of a particular contract
@@ -641,18 +690,11 @@ pub trait Wrapper: Send + Sync {
 
     //----------------------------------------------------------------------------------------------
     /// returns body of news article
-    fn news_article(&mut self, request_id: i32, article_type: i32, article_text: &str);
+    fn news_article(&mut self, request_id: i32, article: NewsArticle);
 
     //----------------------------------------------------------------------------------------------
     /// returns historical news headlines
-    fn historical_news(
-        &mut self,
-        request_id: i32,
-        time: &str,
-        provider_code: &str,
-        article_id: &str,
-        headline: &str,
-    );
+    fn historical_news(&mut self, request_id: i32, headline: NewsHeadline);
 
     //----------------------------------------------------------------------------------------------
     /// signals end of historical news
@@ -671,6 +713,15 @@ pub trait Wrapper: Send + Sync {
     /// returns updates in real time when keepUpToDate is set to True
     fn historical_data_update(&mut self, request_id: i32, bar: BarData);
 
+    //----------------------------------------------------------------------------------------------
+    /// Returns the trading sessions requested with
+    /// `EClient::request_historical_schedule`
+    ///
+    /// # Arguments
+    /// * request_id - the request's identifier
+    /// * schedule - the sessions and the time zone of the exchange
+    fn historical_schedule(&mut self, request_id: i32, schedule: HistoricalSchedule);
+
     //----------------------------------------------------------------------------------------------
     /// returns reroute cfd contract information for market data request
     fn reroute_market_data_request(&mut self, request_id: i32, con_id: i32, exchange: &str);
@@ -699,7 +750,7 @@ pub trait Wrapper: Send + Sync {
     fn profit_and_loss_single(
         &mut self,
         request_id: i32,
-        pos: i32,
+        pos: BigDecimal,
         daily_pn_l: f64,
         unrealized_pn_l: f64,
         realized_pn_l: f64,
@@ -736,7 +787,7 @@ pub trait Wrapper: Send + Sync {
         tick_type: TickByTickType,
         time: i64,
         price: f64,
-        size: i32,
+        size: BigDecimal,
         tick_attrib_last: TickAttribLast,
         exchange: &str,
         special_conditions: &str,
@@ -750,8 +801,8 @@ pub trait Wrapper: Send + Sync {
         time: i64,
         bid_price: f64,
         ask_price: f64,
-        bid_size: i32,
-        ask_size: i32,
+        bid_size: BigDecimal,
+        ask_size: BigDecimal,
         tick_attrib_bid_ask: TickAttribBidAsk,
     );
 
@@ -776,4 +827,34 @@ pub trait Wrapper: Send + Sync {
     //----------------------------------------------------------------------------------------------
     /// This is called at the end of a given request for completed orders.
     fn completed_orders_end(&mut self);
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the meta data of the Wall Street Horizon corporate event
+    /// calendar, see EClient::request_wsh_meta_data
+    ///
+    /// # Arguments
+    /// * request_id - the request's identifier
+    /// * meta_data - the available event types and filters as JSON
+    #[cfg(feature = "wsh")]
+    fn wsh_meta_data(&mut self, request_id: i32, meta_data: serde_json::Value);
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns Wall Street Horizon corporate events, see
+    /// EClient::request_wsh_event_data
+    ///
+    /// # Arguments
+    /// * request_id - the request's identifier
+    /// * events - the events, e.g. earnings dates or dividends
+    #[cfg(feature = "wsh")]
+    fn wsh_event_data(&mut self, request_id: i32, events: Vec<WshEvent>);
+
+    //----------------------------------------------------------------------------------------------
+    /// Returns the user information requested with
+    /// EClient::request_user_info
+    ///
+    /// # Arguments
+    /// * request_id - the request's identifier
+    /// * white_branding_id - the white branding id of the introducing broker,
+    ///   empty if the login is not white branded
+    fn user_info(&mut self, request_id: i32, white_branding_id: &str);
 }
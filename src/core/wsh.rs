@@ -0,0 +1,209 @@
+//! Wall Street Horizon (WSH) corporate event data
+use std::fmt;
+
+use chrono::NaiveDate;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Format of the dates of a `WshEventData` request
+pub const WSH_DATE_FORMAT: &str = "%Y%m%d";
+
+//==================================================================================================
+/// The parameters of a WSH event data request, see
+/// `EClient::request_wsh_event_data`. Events are selected either by
+/// `con_id` or by `filter`.
+///
+/// con_id - the contract to request the events of
+/// filter - JSON filter built from the filters listed in the WSH meta data,
+///          e.g. `{"country": "All", "wshe_ed": "true"}`
+/// fill_watchlist - add the events of the contracts on the TWS watchlists
+/// fill_portfolio - add the events of the contracts in the portfolio
+/// fill_competitors - add the events of the competitors of the contract
+/// start_date, end_date - the range of the event dates
+/// total_limit - the maximum number of events returned
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WshEventData {
+    pub con_id: Option<i32>,
+    pub filter: Option<Value>,
+    pub fill_watchlist: bool,
+    pub fill_portfolio: bool,
+    pub fill_competitors: bool,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub total_limit: Option<i32>,
+}
+
+impl WshEventData {
+    /// The events of a single contract
+    pub fn new(con_id: i32) -> Self {
+        WshEventData {
+            con_id: Some(con_id),
+            ..Self::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The events selected by a JSON filter
+    pub fn with_filter(filter: Value) -> Self {
+        WshEventData {
+            filter: Some(filter),
+            ..Self::default()
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the request needs a server that supports WSH event filters
+    pub fn uses_filters(&self) -> bool {
+        self.filter.is_some() || self.fill_watchlist || self.fill_portfolio || self.fill_competitors
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Whether the request needs a server that supports a date range and
+    /// limit for WSH events
+    pub fn uses_date_range(&self) -> bool {
+        self.start_date.is_some() || self.end_date.is_some() || self.total_limit.is_some()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The filter in the form sent to TWS, empty if there is none
+    pub fn filter_param(&self) -> String {
+        self.filter
+            .as_ref()
+            .map(|filter| filter.to_string())
+            .unwrap_or_default()
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The start date in the form sent to TWS, empty if there is none
+    pub fn start_date_param(&self) -> String {
+        format_wsh_date(&self.start_date)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// The end date in the form sent to TWS, empty if there is none
+    pub fn end_date_param(&self) -> String {
+        format_wsh_date(&self.end_date)
+    }
+}
+
+impl fmt::Display for WshEventData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "con_id: {:?}, filter: {}, fill_watchlist: {}, fill_portfolio: {}, fill_competitors: {}, \
+             start_date: {:?}, end_date: {:?}, total_limit: {:?}",
+            self.con_id,
+            self.filter_param(),
+            self.fill_watchlist,
+            self.fill_portfolio,
+            self.fill_competitors,
+            self.start_date,
+            self.end_date,
+            self.total_limit
+        )
+    }
+}
+
+//==================================================================================================
+fn format_wsh_date(date: &Option<NaiveDate>) -> String {
+    date.map(|date| date.format(WSH_DATE_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+//==================================================================================================
+/// A corporate event, e.g. an earnings date or a dividend, as reported by
+/// `Wrapper::wsh_event_data`.
+///
+/// event_type - the WSH event type, e.g. `wshe_ed` for earnings dates
+/// con_id - the contract the event belongs to, if reported
+/// data - the event specific fields
+/// other - any further top level fields
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WshEvent {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub event_type: String,
+    #[cfg_attr(feature = "serde", serde(default, rename = "conid", alias = "con_id"))]
+    pub con_id: Option<i64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub data: Map<String, Value>,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub other: Map<String, Value>,
+}
+
+impl WshEvent {
+    /// Parses the JSON payload of a WSH event data message. The payload is
+    /// either a list of events or a single event.
+    pub fn parse_all(json: &str) -> Result<Vec<WshEvent>, serde_json::Error> {
+        match serde_json::from_str(json)? {
+            Value::Array(events) => events.into_iter().map(WshEvent::from_value).collect(),
+            event => Ok(vec![WshEvent::from_value(event)?]),
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// Reads an event from a JSON object. Done by hand rather than by serde,
+    /// so the events TWS sends can be read without the `serde` feature.
+    pub fn from_value(value: Value) -> Result<WshEvent, serde_json::Error> {
+        let mut other: Map<String, Value> = serde_json::from_value(value)?;
+        let mut take = |keys: &[&str]| keys.iter().find_map(|key| other.remove(*key));
+        let event_type = take(&["event_type"])
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default();
+        let con_id = take(&["conid", "con_id"])
+            .map(serde_json::from_value)
+            .transpose()?
+            .flatten();
+        let data = take(&["data"])
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default();
+        Ok(WshEvent {
+            event_type,
+            con_id,
+            data,
+            other,
+        })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A field of the event, looked up in `data` first
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.data.get(name).or_else(|| self.other.get(name))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A field of the event as text; numbers and booleans are converted
+    pub fn field_str(&self, name: &str) -> Option<String> {
+        match self.field(name)? {
+            Value::String(value) => Some(value.clone()),
+            Value::Number(value) => Some(value.to_string()),
+            Value::Bool(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    //----------------------------------------------------------------------------------------------
+    /// A date field of the event, given as `YYYYMMDD` or `YYYY-MM-DD`
+    pub fn date(&self, name: &str) -> Option<NaiveDate> {
+        let value = self.field_str(name)?;
+        NaiveDate::parse_from_str(value.trim(), WSH_DATE_FORMAT)
+            .or_else(|_| NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d"))
+            .ok()
+    }
+}
+
+impl fmt::Display for WshEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "event_type: {}, con_id: {:?}, data: {}",
+            self.event_type,
+            self.con_id,
+            Value::Object(self.data.clone())
+        )
+    }
+}
@@ -27,11 +27,11 @@
 //! use std::thread;
 //! use std::time::Duration;
 //! use twsapi::core::client::EClient;
-//! use twsapi::core::errors::IBKRApiLibError;
+//! use twsapi::core::errors::IbkrError;
 //! use twsapi::core::streamer::{Streamer, TcpStreamer};
 //! use twsapi::examples::test_helpers::TestWrapper;
 //!
-//! fn main() -> Result<(), IBKRApiLibError> {
+//! fn main() -> Result<(), IbkrError> {
 //!     let wrapper = Arc::new(Mutex::new(TestWrapper::<TcpStreamer>::new()));
 //!     let app = Arc::new(Mutex::new(EClient::new(wrapper.clone())));
 //!
@@ -1,2 +1,70 @@
 pub(crate) mod test_eclient;
 pub(crate) mod test_messages;
+pub(crate) mod test_conflation;
+pub(crate) mod test_common;
+pub(crate) mod test_smart_components;
+pub(crate) mod test_market_rules;
+pub(crate) mod test_bar_aggregator;
+pub(crate) mod test_subscription;
+pub(crate) mod test_price_averages;
+pub(crate) mod test_news;
+pub(crate) mod test_wsh;
+pub(crate) mod test_scanner;
+pub(crate) mod test_scanner_parameters;
+pub(crate) mod test_display_groups;
+pub(crate) mod test_volume_profile;
+pub(crate) mod test_quote_cache;
+pub(crate) mod test_historical;
+pub(crate) mod test_historical_downloader;
+pub(crate) mod test_bar_cache;
+pub(crate) mod test_bar_integrity;
+pub(crate) mod test_continuous_futures;
+pub(crate) mod test_arrow_export;
+pub(crate) mod test_csv_export;
+pub(crate) mod test_contract;
+pub(crate) mod test_spreads;
+pub(crate) mod test_trading_hours;
+pub(crate) mod test_contract_cache;
+pub(crate) mod test_option_chain;
+pub(crate) mod test_futures_chain;
+pub(crate) mod test_fx;
+pub(crate) mod test_fundamentals;
+pub(crate) mod test_account_summary_tags;
+pub(crate) mod test_account_updates;
+pub(crate) mod test_positions;
+pub(crate) mod test_pnl;
+pub(crate) mod test_portfolio;
+pub(crate) mod test_reconciliation;
+pub(crate) mod test_flatten;
+pub(crate) mod test_order_tracker;
+pub(crate) mod test_execution;
+pub(crate) mod test_trade_store;
+pub(crate) mod test_trade_report;
+pub(crate) mod test_fa_config;
+pub(crate) mod test_managed_accounts;
+pub(crate) mod test_model;
+pub(crate) mod test_currency;
+pub(crate) mod test_account_alerts;
+pub(crate) mod test_margin;
+pub(crate) mod test_blotter;
+pub(crate) mod test_order_condition;
+pub(crate) mod test_error_codes;
+pub(crate) mod test_audit_log;
+pub(crate) mod test_round_trip;
+pub(crate) mod test_arbitrary;
+pub(crate) mod test_msgpack;
+pub(crate) mod test_strategy;
+pub(crate) mod test_backtest;
+pub(crate) mod test_trading_mode;
+pub(crate) mod test_risk;
+pub(crate) mod test_order_throttle;
+pub(crate) mod test_order_journal;
+pub(crate) mod test_session_gate;
+pub(crate) mod test_protective_bracket;
+pub(crate) mod test_order_book;
+pub(crate) mod test_execution_quality;
+pub(crate) mod test_rebalance;
+pub(crate) mod test_fx_hedge;
+pub(crate) mod test_vol_surface;
+pub(crate) mod test_indicators;
+pub(crate) mod test_alert_hooks;
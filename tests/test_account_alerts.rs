@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::account_alerts::{AccountAlertKind, AccountAlerts, ThresholdSide};
+    use crate::twsapi::account_updates::{AccountUpdateEvent, AccountValue, AccountValueKey};
+
+    fn value(key: &str, value: &str) -> AccountUpdateEvent {
+        AccountUpdateEvent::Value {
+            account: "DU1".to_string(),
+            value: AccountValue::new(key, value, "USD"),
+        }
+    }
+
+    #[test]
+    fn test_account_alerts() {
+        let mut alerts = AccountAlerts::new()
+            .watch(
+                AccountValueKey::ExcessLiquidity,
+                "USD",
+                ThresholdSide::Below,
+                10_000.0,
+            )
+            .watch(
+                AccountValueKey::MaintMarginReq,
+                "USD",
+                ThresholdSide::Above,
+                50_000.0,
+            );
+
+        assert!(alerts.apply(&value("ExcessLiquidity", "20000")).is_empty());
+        assert!(alerts.apply(&value("NetLiquidation", "5")).is_empty());
+        assert!(alerts.apply(&value("ExcessLiquidity", "15000")).is_empty());
+
+        let fired = alerts.apply(&value("ExcessLiquidity", "9000"));
+        assert_eq!(1, fired.len());
+        assert_eq!(AccountAlertKind::Breached, fired[0].kind);
+        assert_eq!(Some(15000.0), fired[0].previous);
+        assert!(alerts.apply(&value("ExcessLiquidity", "8000")).is_empty());
+
+        let fired = alerts.apply(&value("ExcessLiquidity", "12000"));
+        assert_eq!(AccountAlertKind::Recovered, fired[0].kind);
+
+        // Already in the alert zone when first received
+        let fired = alerts.apply(&value("MaintMarginReq", "60000"));
+        assert_eq!(AccountAlertKind::Breached, fired[0].kind);
+        assert_eq!(None, fired[0].previous);
+        assert_eq!(
+            Some(60000.0),
+            alerts.value("DU1", &AccountValueKey::MaintMarginReq, "USD")
+        );
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::account_summary_tags::{
+        AccountSummary, AccountSummaryTagSet, AccountSummaryTags, AccountSummaryValue, ALL_TAGS,
+    };
+
+    #[test]
+    fn test_account_summary_tags() {
+        for tag in ALL_TAGS.iter() {
+            assert_eq!(Ok(*tag), tag.to_string().parse());
+        }
+        assert_eq!(
+            Ok(AccountSummaryTags::LedgerAll),
+            "$LEDGER:ALL".parse::<AccountSummaryTags>()
+        );
+        assert!("CashBalance".parse::<AccountSummaryTags>().is_err());
+
+        let all_tags = AccountSummaryTags::AllTags.to_string();
+        assert_eq!(ALL_TAGS.len(), all_tags.split(',').count());
+        assert!(all_tags.starts_with("AccountType,NetLiquidation,"));
+        assert!(!AccountSummaryTags::AccountType.is_numeric());
+
+        let tags = AccountSummaryTagSet::new()
+            .tag(AccountSummaryTags::BuyingPower)
+            .tags(&[
+                AccountSummaryTags::NetLiquidation,
+                AccountSummaryTags::BuyingPower,
+            ])
+            .ledger_currency("eur");
+        assert_eq!("NetLiquidation,BuyingPower,$LEDGER:EUR", tags.to_string());
+        assert_eq!(
+            "NetLiquidation,$LEDGER:ALL",
+            AccountSummaryTagSet::new()
+                .tag(AccountSummaryTags::NetLiquidation)
+                .ledger()
+                .ledger_all()
+                .to_string()
+        );
+        assert_eq!(
+            AccountSummaryTagSet::all(),
+            AccountSummaryTagSet::new().tag(AccountSummaryTags::AllTags)
+        );
+        assert!(AccountSummaryTagSet::new().is_empty());
+    }
+
+    #[test]
+    fn test_account_summary() {
+        let summary = AccountSummary::new(vec![
+            AccountSummaryValue::new("DU1", "AccountType", "INDIVIDUAL", ""),
+            AccountSummaryValue::new("DU1", "NetLiquidation", "100250.5", "USD"),
+            AccountSummaryValue::new("DU2", "NetLiquidation", "5000", "USD"),
+            AccountSummaryValue::new("DU1", "CashBalance", "-1200.25", "EUR"),
+        ]);
+
+        assert_eq!(vec!["DU1", "DU2"], summary.accounts());
+        assert_eq!(
+            Some(100250.5),
+            summary.value("DU1", AccountSummaryTags::NetLiquidation)
+        );
+        assert_eq!(
+            Some(5000.0),
+            summary.value("DU2", AccountSummaryTags::NetLiquidation)
+        );
+        let account_type = summary.get("DU1", AccountSummaryTags::AccountType).unwrap();
+        assert_eq!(None, account_type.value);
+        assert_eq!("INDIVIDUAL", account_type.text);
+        assert_eq!(
+            Some(AccountSummaryTags::AccountType),
+            account_type.summary_tag()
+        );
+        assert_eq!(None, summary.value("DU2", AccountSummaryTags::BuyingPower));
+        assert_eq!(
+            Some(-1200.25),
+            summary.ledger_value("DU1", "CashBalance", "EUR")
+        );
+        assert_eq!(None, summary.ledger_value("DU1", "CashBalance", "USD"));
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::account_updates::{
+        AccountUpdateEvent, AccountUpdates, AccountValue, AccountValueKey, PortfolioItem,
+    };
+    use crate::twsapi::contract::Contract;
+
+    fn item(con_id: i32, position: &str) -> PortfolioItem {
+        let mut contract = Contract::stock("AAPL", "SMART", "USD");
+        contract.con_id = con_id;
+        PortfolioItem::new(
+            contract,
+            BigDecimal::from_str(position).unwrap(),
+            190.0,
+            BigDecimal::from_str("19000").unwrap(),
+            BigDecimal::from_str("150.25").unwrap(),
+            3975.0,
+            0.0,
+        )
+    }
+
+    #[test]
+    fn test_account_value_key() {
+        assert_eq!(AccountValueKey::Sma, AccountValueKey::from("SMA"));
+        assert_eq!("FuturesPNL", AccountValueKey::FuturesPnL.to_string());
+        let key = AccountValueKey::from("NetLiquidation-C");
+        assert_eq!(AccountValueKey::Other("NetLiquidation-C".to_string()), key);
+        assert_eq!("NetLiquidation-C", key.to_string());
+
+        let value = AccountValue::new("NetLiquidation", "100250.50", "USD");
+        assert_eq!(Some(100250.5), value.value);
+        assert_eq!(
+            None,
+            AccountValue::new("AccountType", "INDIVIDUAL", "").value
+        );
+    }
+
+    #[test]
+    fn test_account_updates() {
+        let mut updates = AccountUpdates::new();
+        let value = |key: &str, text: &str, currency: &str| AccountUpdateEvent::Value {
+            account: "DU1".to_string(),
+            value: AccountValue::new(key, text, currency),
+        };
+
+        assert!(updates.apply(value("CashBalance", "1000", "EUR")).is_none());
+        assert!(updates.apply(value("CashBalance", "1500", "EUR")).is_none());
+        assert!(updates
+            .apply(value("NetLiquidation", "30000", "USD"))
+            .is_none());
+        assert!(updates
+            .apply(AccountUpdateEvent::Portfolio {
+                account: "DU1".to_string(),
+                item: Box::new(item(265598, "100")),
+            })
+            .is_none());
+        assert!(updates
+            .apply(AccountUpdateEvent::Time("15:30".to_string()))
+            .is_none());
+        // Not handed out before the first batch is complete
+        assert!(updates.snapshot("DU1").is_none());
+
+        let snapshot = updates
+            .apply(AccountUpdateEvent::End("DU1".to_string()))
+            .unwrap()
+            .clone();
+        assert_eq!("DU1", snapshot.account);
+        assert_eq!("15:30", snapshot.updated);
+        assert_eq!(
+            Some(1500.0),
+            snapshot.value(&AccountValueKey::CashBalance, "EUR")
+        );
+        assert_eq!(
+            Some(30000.0),
+            snapshot.value(&AccountValueKey::NetLiquidation, "USD")
+        );
+        assert_eq!(2, snapshot.values.len());
+        assert_eq!(
+            BigDecimal::from(100),
+            snapshot.position(265598).unwrap().position
+        );
+
+        // Closed positions are removed
+        updates.apply(AccountUpdateEvent::Portfolio {
+            account: "DU1".to_string(),
+            item: Box::new(item(265598, "0")),
+        });
+        assert!(updates.snapshot("DU1").unwrap().portfolio.is_empty());
+        assert!(updates.snapshot("DU2").is_none());
+    }
+}
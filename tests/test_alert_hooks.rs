@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::twsapi::account_alerts::{AccountThreshold, ThresholdSide};
+    use crate::twsapi::account_updates::{AccountValue, AccountValueKey};
+    use crate::twsapi::alert_hooks::{AlertCondition, AlertEvent, AlertHook, AlertHooks};
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::error_codes::ErrorCategory;
+    use crate::twsapi::execution::Execution;
+
+    /// Keeps the events it is called on
+    struct Pager(Arc<Mutex<Vec<AlertEvent>>>);
+
+    impl AlertHook for Pager {
+        fn alert(&mut self, event: &AlertEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_alert_conditions() {
+        let rejected = AlertEvent::OrderRejected {
+            order_id: 3,
+            code: 201,
+            message: "Order rejected".to_string(),
+        };
+        assert!(AlertCondition::OrderRejected.matches(&rejected));
+        assert!(AlertCondition::ErrorCategory(ErrorCategory::Order).matches(&rejected));
+        assert!(AlertCondition::ErrorCode(201).matches(&rejected));
+        assert!(!AlertCondition::ErrorCode(202).matches(&rejected));
+        assert!(!AlertCondition::ConnectionLost.matches(&rejected));
+
+        let lost = AlertEvent::ConnectionLost {
+            code: None,
+            message: "Socket closed or broken".to_string(),
+        };
+        assert!(AlertCondition::ConnectionLost.matches(&lost));
+        assert!(!AlertCondition::ErrorCategory(ErrorCategory::Connectivity).matches(&lost));
+        assert_eq!("connection lost: Socket closed or broken", lost.to_string());
+
+        let fill = AlertEvent::Fill {
+            contract: Box::default(),
+            execution: Box::default(),
+        };
+        assert!(AlertCondition::FillReceived.matches(&fill));
+        assert_eq!(None, fill.code());
+    }
+
+    #[test]
+    fn test_alert_hooks() {
+        let pages = Arc::new(Mutex::new(Vec::new()));
+        let fills = Arc::new(Mutex::new(0));
+        let counter = fills.clone();
+        let mut hooks = AlertHooks::new()
+            .watch(AccountThreshold::new(
+                AccountValueKey::ExcessLiquidity,
+                "USD",
+                ThresholdSide::Below,
+                10_000.0,
+            ))
+            .hook(
+                &[
+                    AlertCondition::ConnectionLost,
+                    AlertCondition::OrderRejected,
+                    AlertCondition::ErrorCategory(ErrorCategory::Pacing),
+                    AlertCondition::MarginBreached,
+                ],
+                Pager(pages.clone()),
+            )
+            .hook(&[AlertCondition::FillReceived], move |_: &AlertEvent| {
+                *counter.lock().unwrap() += 1
+            });
+        assert_eq!(2, hooks.len());
+
+        assert_eq!(
+            1,
+            hooks.apply_error(5, 201, "Order rejected - reason: margin")
+        );
+        assert_eq!(1, hooks.apply_error(-1, 1100, "Connectivity lost"));
+        assert_eq!(
+            1,
+            hooks.apply_error(6, 100, "Max rate of messages exceeded")
+        );
+        // Warnings and errors of other categories are not paged
+        assert_eq!(
+            0,
+            hooks.apply_error(-1, 2104, "Market data farm connection is OK")
+        );
+        assert_eq!(0, hooks.apply_error(7, 200, "No security definition"));
+        assert_eq!(1, hooks.apply_connection_lost("Socket closed or broken"));
+
+        let contract = Contract::default();
+        let execution = Execution::default();
+        assert_eq!(1, hooks.apply_execution(&contract, &execution));
+        assert_eq!(1, *fills.lock().unwrap());
+
+        let value = |number: &str| AccountValue::new("ExcessLiquidity", number, "USD");
+        assert_eq!(0, hooks.apply_account_value("DU1", &value("20000")));
+        assert_eq!(1, hooks.apply_account_value("DU1", &value("9000")));
+        assert_eq!(0, hooks.apply_account_value("DU1", &value("8000")));
+        // Recoveries are not breaches
+        assert_eq!(0, hooks.apply_account_value("DU1", &value("12000")));
+
+        let pages = pages.lock().unwrap();
+        assert_eq!(5, pages.len());
+        assert!(matches!(
+            pages[0],
+            AlertEvent::OrderRejected {
+                order_id: 5,
+                code: 201,
+                ..
+            }
+        ));
+        assert!(matches!(
+            pages[1],
+            AlertEvent::ConnectionLost {
+                code: Some(1100),
+                ..
+            }
+        ));
+        assert!(matches!(pages[2], AlertEvent::Error { code: 100, .. }));
+        assert!(matches!(
+            pages[3],
+            AlertEvent::ConnectionLost { code: None, .. }
+        ));
+        match &pages[4] {
+            AlertEvent::MarginThreshold(alert) => {
+                assert_eq!("DU1", alert.account);
+                assert_eq!(9000.0, alert.value);
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+}
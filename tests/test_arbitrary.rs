@@ -0,0 +1,156 @@
+#[cfg(all(test, feature = "arbitrary"))]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::twsapi::arbitrary::price;
+    use crate::twsapi::common::UNSET_DOUBLE;
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::execution::{Execution, ExecutionFilter};
+    use crate::twsapi::order::{Order, OrderState};
+    use crate::twsapi::order_condition::OrderConditionEnum;
+    use crate::twsapi::round_trip::{
+        condition_round_trip, execution_round_trip, json_round_trip, order_round_trip,
+    };
+    use crate::twsapi::server_versions::{MAX_CLIENT_VER, MIN_SERVER_VER_FRACTIONAL_POSITIONS};
+
+    fn json(value: &impl serde::Serialize) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    /// The contract fields execution details and open order messages carry
+    fn message_contract(contract: &Contract) -> Contract {
+        Contract {
+            primary_exchange: String::new(),
+            ..contract.clone()
+        }
+    }
+
+    /// A price TWS sends as 0 for unset
+    fn unset_as_zero(price: f64) -> f64 {
+        if price == UNSET_DOUBLE {
+            0.0
+        } else {
+            price
+        }
+    }
+
+    /// A price that comes back unset if it was 0
+    fn zero_as_unset(price: f64) -> f64 {
+        if price == 0.0 {
+            UNSET_DOUBLE
+        } else {
+            price
+        }
+    }
+
+    /// The order as an open order brings it back: `transmit` is not sent,
+    /// neither are the condition flags without conditions, and some unset
+    /// values come back as 0
+    fn open_order(order: &Order) -> Order {
+        let default = Order::default();
+        let mut open_order = Order {
+            transmit: default.transmit,
+            lmt_price: zero_as_unset(order.lmt_price),
+            aux_price: zero_as_unset(order.aux_price),
+            volatility_type: 0,
+            reference_price_type: 0,
+            trail_stop_price: unset_as_zero(order.trail_stop_price),
+            trigger_price: unset_as_zero(order.trigger_price),
+            lmt_price_offset: unset_as_zero(order.lmt_price_offset),
+            adjusted_stop_price: unset_as_zero(order.adjusted_stop_price),
+            adjusted_stop_limit_price: unset_as_zero(order.adjusted_stop_limit_price),
+            adjusted_trailing_amount: unset_as_zero(order.adjusted_trailing_amount),
+            cash_qty: unset_as_zero(order.cash_qty),
+            ..order.clone()
+        };
+        if order.conditions.is_empty() {
+            open_order.conditions_ignore_rth = default.conditions_ignore_rth;
+            open_order.conditions_cancel_order = default.conditions_cancel_order;
+        }
+        open_order
+    }
+
+    proptest! {
+        #[test]
+        fn test_execution_round_trip(
+            request_id in 0..i32::MAX,
+            contract in any::<Contract>(),
+            execution in any::<Execution>(),
+        ) {
+            let (decoded_id, decoded_contract, decoded) =
+                execution_round_trip(request_id, &contract, &execution, MAX_CLIENT_VER).unwrap();
+            prop_assert_eq!(request_id, decoded_id);
+            prop_assert_eq!(json(&message_contract(&contract)), json(&decoded_contract));
+            prop_assert_eq!(json(&execution), json(&decoded));
+        }
+
+        #[test]
+        fn test_execution_round_trip_whole_shares(
+            contract in any::<Contract>(),
+            execution in any::<Execution>(),
+        ) {
+            let (_, _, decoded) = execution_round_trip(
+                1,
+                &contract,
+                &execution,
+                MIN_SERVER_VER_FRACTIONAL_POSITIONS - 1,
+            )
+            .unwrap();
+            prop_assert_eq!(execution.shares.trunc(), decoded.shares);
+            prop_assert_eq!(&execution.exec_id, &decoded.exec_id);
+        }
+
+        #[test]
+        fn test_order_round_trip(
+            contract in any::<Contract>(),
+            order in any::<Order>(),
+            status in prop_oneof![Just("PreSubmitted"), Just("Submitted"), Just("Filled")],
+            commission in price(),
+        ) {
+            let order_state = OrderState {
+                status: status.to_string(),
+                commission,
+                ..Default::default()
+            };
+            let (decoded_contract, decoded, decoded_state) =
+                order_round_trip(&contract, &order, &order_state, MAX_CLIENT_VER).unwrap();
+            prop_assert_eq!(json(&message_contract(&contract)), json(&decoded_contract));
+            prop_assert_eq!(json(&open_order(&order)), json(&decoded));
+            prop_assert_eq!(&order_state.status, &decoded_state.status);
+            prop_assert_eq!(zero_as_unset(commission), decoded_state.commission);
+        }
+
+        #[test]
+        fn test_order_round_trip_whole_shares(
+            contract in any::<Contract>(),
+            order in any::<Order>(),
+        ) {
+            let (_, decoded, _) = order_round_trip(
+                &contract,
+                &order,
+                &OrderState::default(),
+                MIN_SERVER_VER_FRACTIONAL_POSITIONS - 1,
+            )
+            .unwrap();
+            prop_assert_eq!(order.total_quantity.trunc(), decoded.total_quantity);
+            prop_assert_eq!(&order.order_ref, &decoded.order_ref);
+            prop_assert_eq!(order.perm_id, decoded.perm_id);
+        }
+
+        #[test]
+        fn test_condition_round_trip(condition in any::<OrderConditionEnum>()) {
+            prop_assert_eq!(json(&condition), json(&condition_round_trip(&condition).unwrap()));
+        }
+
+        #[test]
+        fn test_json_round_trip(
+            contract in any::<Contract>(),
+            order in any::<Order>(),
+            filter in any::<ExecutionFilter>(),
+        ) {
+            prop_assert_eq!(json(&contract), json(&json_round_trip(&contract).unwrap()));
+            prop_assert_eq!(json(&order), json(&json_round_trip(&order).unwrap()));
+            prop_assert_eq!(json(&filter), json(&json_round_trip(&filter).unwrap()));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+#[cfg(all(test, feature = "arrow-export"))]
+mod tests {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Date32Type, Float64Type, TimestampSecondType};
+    use arrow_array::Array;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use crate::twsapi::arrow_export::{
+        bars_to_record_batch, historical_ticks_to_record_batch, recorded_ticks_to_record_batch,
+        vol_surface_to_record_batch, write_parquet, SCHEMA_VERSION,
+    };
+    use crate::twsapi::common::{BarData, HistoricalTick, TickType};
+    use crate::twsapi::contract::Right;
+    use crate::twsapi::historical::HistoricalTicks;
+    use crate::twsapi::tick_recorder::{RecordedTick, TickRecorder};
+    use crate::twsapi::vol_surface::{VolPoint, VolSurfaceSnapshot};
+
+    #[test]
+    fn test_bars_to_record_batch() {
+        let bars = vec![
+            BarData {
+                date: "1672531200".to_string(),
+                close: 1.5,
+                volume: 10.0,
+                ..Default::default()
+            },
+            BarData {
+                date: "20230102".to_string(),
+                close: 2.5,
+                ..Default::default()
+            },
+            BarData {
+                date: "invalid".to_string(),
+                ..Default::default()
+            },
+        ];
+        let batch = bars_to_record_batch(&bars).unwrap();
+        assert_eq!(3, batch.num_rows());
+        assert_eq!(
+            Some(&SCHEMA_VERSION.to_string()),
+            batch.schema().metadata().get("twsapi.schema_version")
+        );
+
+        let times = batch.column(0).as_primitive::<TimestampSecondType>();
+        assert_eq!(1672531200, times.value(0));
+        assert_eq!(1672617600, times.value(1));
+        assert!(times.is_null(2));
+        let closes = batch.column(5).as_primitive::<Float64Type>();
+        assert_eq!(2.5, closes.value(1));
+        let volumes = batch.column(6).as_primitive::<Float64Type>();
+        assert_eq!(10.0, volumes.value(0));
+    }
+
+    #[test]
+    fn test_ticks_to_record_batch() {
+        let ticks = HistoricalTicks::Midpoint(vec![
+            HistoricalTick::new(1, 1.0, 0.0),
+            HistoricalTick::new(2, 1.5, 0.0),
+        ]);
+        let batch = historical_ticks_to_record_batch(&ticks).unwrap();
+        assert_eq!(2, batch.num_rows());
+        assert_eq!(3, batch.num_columns());
+
+        let mut recorder = TickRecorder::new();
+        let time = Utc.timestamp_opt(1, 0).unwrap();
+        recorder.record(RecordedTick::price(time, 1, TickType::Bid, 100.0));
+        recorder.record(RecordedTick::size(time, 1, TickType::BidSize, 5.0));
+        let batch = recorded_ticks_to_record_batch(&recorder.drain()).unwrap();
+        assert!(recorder.is_empty());
+        assert_eq!(2, batch.num_rows());
+        assert!(batch.column(3).is_null(1));
+        assert!(batch.column(4).is_null(0));
+
+        let mut parquet = vec![];
+        write_parquet(&mut parquet, &batch).unwrap();
+        assert_eq!(b"PAR1", &parquet[..4]);
+    }
+
+    #[test]
+    fn test_vol_surface_to_record_batch() {
+        let time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let point = |strike: f64, delta: Option<f64>| VolPoint {
+            expiry: NaiveDate::from_ymd_opt(1970, 1, 11).unwrap(),
+            strike,
+            right: Right::Put,
+            implied_vol: 0.25,
+            delta,
+            und_price: Some(100.0),
+            time,
+        };
+        let snapshot = VolSurfaceSnapshot {
+            underlying: "SPY".to_string(),
+            time,
+            spot: None,
+            points: vec![point(95.0, Some(-0.3)), point(100.0, None)],
+        };
+        let batch = vol_surface_to_record_batch(&snapshot).unwrap();
+        assert_eq!(2, batch.num_rows());
+        assert_eq!(10, batch.num_columns());
+        assert!(batch.column(2).is_null(0));
+        assert_eq!(10, batch.column(3).as_primitive::<Date32Type>().value(0));
+        assert_eq!("P", batch.column(5).as_string::<i32>().value(1));
+        assert!(batch.column(7).is_null(1));
+
+        let mut parquet = vec![];
+        write_parquet(&mut parquet, &batch).unwrap();
+        assert_eq!(b"PAR1", &parquet[..4]);
+    }
+}
@@ -0,0 +1,63 @@
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use std::fs;
+
+    use crate::twsapi::audit_log::{
+        payload_hash, JsonLinesAuditSink, MemoryAuditSink, OrderAuditLog,
+    };
+    use crate::twsapi::errors::IbkrError;
+    use crate::twsapi::messages::OutgoingMessageIds;
+
+    #[test]
+    fn test_payload_hash() {
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            payload_hash(b"abc")
+        );
+    }
+
+    #[test]
+    fn test_record_sequence() -> Result<(), IbkrError> {
+        let sink = MemoryAuditSink::new();
+        let mut log = OrderAuditLog::resume(Box::new(sink.clone()), 41);
+        let record = log.record(OutgoingMessageIds::CancelOrder, Some(7), b"4\x002\x007\x00")?;
+        assert_eq!(42, record.sequence);
+        assert_eq!(4, record.message_id);
+        assert_eq!("CancelOrder", record.message);
+        assert_eq!(6, record.payload_len);
+        log.record(OutgoingMessageIds::ReqGlobalCancel, None, b"58\x001\x00")?;
+        assert_eq!(43, log.last_sequence());
+        assert_eq!(vec![record], sink.records()[..1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_lines_resume() -> Result<(), IbkrError> {
+        let path =
+            std::env::temp_dir().join(format!("twsapi-audit-log-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(0, JsonLinesAuditSink::last_sequence(&path)?);
+
+        let mut log = OrderAuditLog::open(&path)?;
+        log.record(OutgoingMessageIds::PlaceOrder, Some(1), b"3\x001\x00")?;
+        log.record(OutgoingMessageIds::CancelOrder, Some(1), b"4\x002\x001\x00")?;
+        drop(log);
+
+        // A new log appends to the file and continues its numbering
+        let mut log = OrderAuditLog::open(&path)?;
+        assert_eq!(2, log.last_sequence());
+        let record = log.record(OutgoingMessageIds::ReqGlobalCancel, None, b"58\x001\x00")?;
+        assert_eq!(3, record.sequence);
+
+        let contents = fs::read_to_string(&path)?;
+        assert_eq!(3, contents.lines().count());
+        assert!(contents
+            .lines()
+            .next()
+            .unwrap()
+            .contains("\"message\":\"PlaceOrder\""));
+        assert_eq!(3, JsonLinesAuditSink::last_sequence(&path)?);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use crate::twsapi::backtest::{recorded_ticks, Backtest};
+    use crate::twsapi::common::{HistoricalTickLast, RealTimeBar, TickType, WhatToShow};
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::quote_cache::Quote;
+    use crate::twsapi::strategy::{OrderEvent, Strategy, StrategyContext, StrategyRunner};
+    use crate::twsapi::tick_recorder::RecordedTick;
+
+    fn time(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 2, 15, minute, 0).unwrap()
+    }
+
+    fn bar(minute: u32, open: f64, high: f64, low: f64, close: f64) -> RealTimeBar {
+        RealTimeBar {
+            date_time: time(minute),
+            open,
+            high,
+            low,
+            close,
+            ..Default::default()
+        }
+    }
+
+    fn aapl() -> Contract {
+        Contract::stock("AAPL", "SMART", "USD")
+    }
+
+    /// Buys when a bar closes above 100, sells with a limit at 105 once
+    /// filled and stops at the first timer after the exit
+    #[derive(Default)]
+    struct Swing {
+        entry: Option<i32>,
+        exit: Option<i32>,
+        events: Vec<OrderEvent>,
+        timers: Vec<DateTime<Utc>>,
+        bars: usize,
+        stopped: bool,
+    }
+
+    impl Strategy for Swing {
+        fn on_start(&mut self, context: &mut StrategyContext) {
+            context.subscribe_bars(&aapl(), WhatToShow::Trades);
+        }
+
+        fn on_bar(&mut self, context: &mut StrategyContext, _request_id: i32, bar: &RealTimeBar) {
+            assert_eq!(bar.date_time, context.now());
+            self.bars += 1;
+            if self.entry.is_none() && bar.close > 100.0 {
+                self.entry =
+                    Some(context.place_order(&aapl(), &Order::market_order("", "BUY", 10.0)));
+            }
+        }
+
+        fn on_order_event(&mut self, context: &mut StrategyContext, event: &OrderEvent) {
+            self.events.push(event.clone());
+            if let OrderEvent::Status { order_id, status } = event {
+                if Some(*order_id) == self.entry && status == "Filled" {
+                    self.exit = Some(
+                        context.place_order(&aapl(), &Order::limit_order("", "SELL", 10.0, 105.0)),
+                    );
+                }
+            }
+        }
+
+        fn on_timer(&mut self, context: &mut StrategyContext) {
+            self.timers.push(context.now());
+            if self.exit.is_some() && self.events.len() == 4 {
+                context.stop();
+            }
+        }
+
+        fn on_stop(&mut self, _context: &mut StrategyContext) {
+            self.stopped = true;
+        }
+    }
+
+    #[test]
+    fn test_backtest_bars() {
+        let mut backtest = Backtest::new().timer_interval(Duration::minutes(2));
+        // Added out of order, played in time order
+        backtest.add_bars(
+            &aapl(),
+            &[
+                bar(2, 101.0, 103.0, 100.5, 102.0),
+                bar(0, 99.0, 100.0, 98.0, 99.5),
+                bar(1, 99.5, 101.0, 99.0, 100.5),
+                bar(3, 102.0, 106.0, 101.5, 104.0),
+                bar(4, 104.0, 104.5, 103.0, 103.5),
+                bar(5, 103.5, 104.0, 102.0, 103.0),
+                bar(6, 103.0, 103.5, 102.0, 102.5),
+            ],
+        );
+        // Data of contracts the strategy does not subscribe to is ignored
+        backtest.add_bars(
+            &Contract::stock("MSFT", "SMART", "USD"),
+            &[bar(1, 400.0, 401.0, 399.0, 400.5)],
+        );
+        assert_eq!(8, backtest.len());
+
+        let mut runner = StrategyRunner::new(Swing::default(), StrategyContext::new(1, 100));
+        let report = backtest.run(&mut runner);
+        let strategy = runner.into_strategy();
+
+        // The entry is placed on the bar at 15:01 and fills at the open of
+        // the next one, the exit fills when the bar at 15:03 reaches 105
+        assert_eq!(2, report.fills.len());
+        assert_eq!(time(2), report.fills[0].time);
+        assert_eq!("BOT", report.fills[0].execution.side);
+        assert_eq!(101.0, report.fills[0].execution.price);
+        assert_eq!(time(3), report.fills[1].time);
+        assert_eq!("SLD", report.fills[1].execution.side);
+        assert_eq!(105.0, report.fills[1].execution.price);
+        assert_eq!(0.0, report.position(&aapl()));
+        assert_eq!(40.0, report.cash_flow(&aapl()));
+        assert!(report.working_orders.is_empty());
+
+        // Stopped by the timer at 15:04, before the bar at 15:04
+        assert_eq!(vec![time(2), time(4)], strategy.timers);
+        assert_eq!(4, strategy.bars);
+        assert_eq!(5, report.events);
+        assert_eq!(Some(time(3)), report.end_time);
+        assert!(strategy.stopped);
+    }
+
+    /// Places a buy limit at 99 on the first tick and cancels orders it
+    /// cannot place
+    #[derive(Default)]
+    struct Dip {
+        quotes: Vec<Quote>,
+        events: Vec<OrderEvent>,
+    }
+
+    impl Strategy for Dip {
+        fn on_start(&mut self, context: &mut StrategyContext) {
+            context.subscribe_market_data(&aapl());
+            let mut order = Order::limit_order("", "BUY", 5.0, 99.0);
+            order.order_type = "TRAIL".to_string();
+            context.place_order(&aapl(), &order);
+        }
+
+        fn on_tick(&mut self, context: &mut StrategyContext, _ticker_id: i32, quote: &Quote) {
+            if self.quotes.is_empty() {
+                context.place_order(&aapl(), &Order::limit_order("", "BUY", 5.0, 99.0));
+            }
+            self.quotes.push(quote.clone());
+        }
+
+        fn on_order_event(&mut self, _context: &mut StrategyContext, event: &OrderEvent) {
+            self.events.push(event.clone());
+        }
+    }
+
+    #[test]
+    fn test_backtest_ticks() {
+        let mut backtest = Backtest::new();
+        backtest.add_ticks(
+            &aapl(),
+            &[
+                RecordedTick::price(time(0), 7, TickType::Ask, 100.0),
+                RecordedTick::price(time(1), 7, TickType::Bid, 98.9),
+                RecordedTick::size(time(1), 7, TickType::BidSize, 300.0),
+                RecordedTick::price(time(2), 7, TickType::Ask, 99.0),
+            ],
+        );
+        let mut runner = StrategyRunner::new(Dip::default(), StrategyContext::new(1, 1));
+        let report = backtest.run(&mut runner);
+        let strategy = runner.into_strategy();
+
+        assert!(matches!(
+            strategy.events[0],
+            OrderEvent::Rejected { order_id: 1, .. }
+        ));
+        assert_eq!(
+            OrderEvent::Status {
+                order_id: 2,
+                status: "Filled".to_string()
+            },
+            strategy.events[2]
+        );
+        assert_eq!(1, report.fills.len());
+        assert_eq!(99.0, report.fills[0].execution.price);
+        assert_eq!("20250102 15:02:00", report.fills[0].execution.time);
+        assert_eq!(5.0, report.position(&aapl()));
+
+        assert_eq!(4, strategy.quotes.len());
+        assert_eq!(Some(98.9), strategy.quotes[2].bid);
+        assert_eq!(Some(300.0), strategy.quotes[2].bid_size);
+    }
+
+    #[test]
+    fn test_recorded_ticks() {
+        let ticks = recorded_ticks(
+            3,
+            &[HistoricalTickLast {
+                time: 1735830000,
+                price: 101.5,
+                size: 200.0,
+                ..Default::default()
+            }],
+        );
+        assert_eq!(
+            vec![
+                RecordedTick::price(time(0), 3, TickType::Last, 101.5),
+                RecordedTick::size(time(0), 3, TickType::LastSize, 200.0),
+            ],
+            ticks
+        );
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::bar_aggregator::{BarAggregator, BarKind};
+    use crate::twsapi::common::TickType;
+
+    #[test]
+    fn test_time_bars() {
+        let mut aggregator = BarAggregator::new(BarKind::Time(60));
+        assert!(aggregator.update_trade(125, 10.0, 100.0).is_empty());
+        assert!(aggregator.update_trade(130, 11.0, 100.0).is_empty());
+        assert!(aggregator.update_trade(150, 9.0, 200.0).is_empty());
+
+        let bars = aggregator.update_trade(185, 12.0, 50.0);
+        assert_eq!(1, bars.len());
+        assert_eq!("120", bars[0].date);
+        assert_eq!(10.0, bars[0].open);
+        assert_eq!(11.0, bars[0].high);
+        assert_eq!(9.0, bars[0].low);
+        assert_eq!(9.0, bars[0].close);
+        assert_eq!(400.0, bars[0].volume);
+        assert_eq!(3, bars[0].bar_count);
+        assert_eq!(9.75, bars[0].average);
+
+        assert!(aggregator.update_time(239).is_empty());
+        let bars = aggregator.update_time(240);
+        assert_eq!(1, bars.len());
+        assert_eq!("180", bars[0].date);
+        assert!(aggregator.current().is_none());
+    }
+
+    #[test]
+    fn test_volume_and_tick_count_bars() {
+        let mut aggregator = BarAggregator::new(BarKind::Volume(300.0));
+        assert!(aggregator.update_trade(1, 10.0, 200.0).is_empty());
+        let bars = aggregator.update_trade(2, 10.5, 150.0);
+        assert_eq!(1, bars.len());
+        assert_eq!(350.0, bars[0].volume);
+
+        let mut aggregator = BarAggregator::new(BarKind::TickCount(2));
+        assert!(aggregator.update_trade(1, 10.0, 1.0).is_empty());
+        assert_eq!(1, aggregator.update_trade(2, 10.0, 1.0).len());
+        assert!(aggregator.update_trade(3, 10.0, 1.0).is_empty());
+        assert_eq!(1, aggregator.flush().unwrap().bar_count);
+    }
+
+    #[test]
+    fn test_l1_ticks() {
+        let mut aggregator = BarAggregator::new(BarKind::TickCount(1));
+        assert!(aggregator
+            .update_tick_size(1, TickType::LastSize, 5.0)
+            .is_empty());
+        aggregator.update_tick_price(TickType::Bid, 9.0);
+        aggregator.update_tick_price(TickType::Last, 10.0);
+        let bars = aggregator.update_tick_size(2, TickType::LastSize, 5.0);
+        assert_eq!(1, bars.len());
+        assert_eq!(10.0, bars[0].close);
+        assert_eq!(5.0, bars[0].volume);
+    }
+}
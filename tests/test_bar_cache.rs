@@ -0,0 +1,72 @@
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use std::fs;
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::twsapi::bar_cache::{BarCache, BarCacheKey};
+    use crate::twsapi::common::{BarData, BarSize, WhatToShow};
+    use crate::twsapi::contract::{Contract, SecType};
+
+    fn bar(date: &str, close: f64) -> BarData {
+        BarData {
+            date: date.to_string(),
+            close,
+            ..Default::default()
+        }
+    }
+
+    fn key() -> BarCacheKey {
+        let contract = Contract {
+            con_id: 265598,
+            ..Default::default()
+        };
+        BarCacheKey::new(&contract, &BarSize::_1Min, &WhatToShow::Trades, true)
+    }
+
+    #[test]
+    fn test_key_file_name() {
+        assert_eq!("265598_1_min_TRADES_rth.json", key().file_name());
+
+        let contract = Contract {
+            symbol: "ES".to_string(),
+            sec_type: SecType::Future,
+            last_trade_date_or_contract_month: "202312".to_string(),
+            currency: "USD".to_string(),
+            ..Default::default()
+        };
+        let key = BarCacheKey::new(&contract, &BarSize::_1Day, &WhatToShow::Midpoint, false);
+        assert_eq!("ES-FUT-202312-USD_1_day_MIDPOINT.json", key.file_name());
+    }
+
+    #[test]
+    fn test_store_merges_with_stored_bars() {
+        let dir = std::env::temp_dir().join(format!("twsapi-bar-cache-{}", std::process::id()));
+        let cache = BarCache::new(&dir).unwrap();
+        let key = key();
+        assert!(cache.load(&key).unwrap().is_empty());
+        assert_eq!(None, cache.range(&key).unwrap());
+
+        cache
+            .store(&key, vec![bar("120", 2.0), bar("60", 1.0)])
+            .unwrap();
+        // The incomplete last bar is replaced by the refreshed one
+        let merged = cache
+            .store(&key, vec![bar("120", 2.5), bar("180", 3.0)])
+            .unwrap();
+        let closes: Vec<f64> = merged.iter().map(|bar| bar.close).collect();
+        assert_eq!(vec![1.0, 2.5, 3.0], closes);
+        assert_eq!(3, cache.load(&key).unwrap().len());
+        assert_eq!(
+            Some((
+                Utc.timestamp_opt(60, 0).unwrap(),
+                Utc.timestamp_opt(180, 0).unwrap()
+            )),
+            cache.range(&key).unwrap()
+        );
+
+        cache.remove(&key).unwrap();
+        assert!(cache.load(&key).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+    use crate::twsapi::bar_integrity::{check_bars, BarIssue};
+    use crate::twsapi::common::{BarData, BarSize};
+    use crate::twsapi::historical::HistoricalSchedule;
+
+    fn time(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 1, day, hour, minute, 0).unwrap()
+    }
+
+    fn bar(time: DateTime<Utc>) -> BarData {
+        BarData {
+            date: time.timestamp().to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn schedule() -> HistoricalSchedule {
+        let sessions = [3, 4].map(|day| {
+            (
+                format!("202301{:02}-09:30:00", day),
+                format!("202301{:02}-16:00:00", day),
+                format!("202301{:02}", day),
+            )
+        });
+        HistoricalSchedule::parse(
+            "20230103-09:30:00",
+            "20230104-16:00:00",
+            "US/Eastern",
+            &sessions,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_order_checks() {
+        let bars = vec![
+            bar(time(3, 14, 30)),
+            bar(time(3, 16, 0)),
+            bar(time(3, 15, 0)),
+            bar(time(3, 16, 0)),
+            BarData {
+                date: "yesterday".to_string(),
+                ..Default::default()
+            },
+        ];
+        let report = check_bars(&bars, &BarSize::_1Hour, None);
+        assert_eq!(5, report.bars);
+        assert_eq!(
+            vec![
+                BarIssue::OutOfOrder {
+                    index: 2,
+                    time: time(3, 15, 0),
+                    previous: time(3, 16, 0),
+                },
+                BarIssue::Duplicate {
+                    index: 3,
+                    time: time(3, 16, 0),
+                },
+                BarIssue::UnparseableDate {
+                    index: 4,
+                    date: "yesterday".to_string(),
+                },
+            ],
+            report.issues
+        );
+        assert!(report.needs_merge());
+        assert!(report.missing_ranges().is_empty());
+    }
+
+    #[test]
+    fn test_schedule_checks() {
+        // The first session has hourly bars at 09:30, 10:00, ..., 15:00
+        // US/Eastern, 12:00 and 13:00 are missing. The second has none.
+        let bars: Vec<BarData> = [time(3, 14, 30)]
+            .into_iter()
+            .chain([15, 16, 19, 20].map(|hour| time(3, hour, 0)))
+            .map(bar)
+            .collect();
+        let report = check_bars(&bars, &BarSize::_1Hour, Some(&schedule()));
+        assert!(!report.needs_merge());
+        assert_eq!(
+            vec![
+                BarIssue::MissingBars {
+                    ref_date: NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(),
+                    start: time(3, 17, 0),
+                    end: time(3, 19, 0),
+                    count: 2,
+                },
+                BarIssue::MissingSession {
+                    ref_date: NaiveDate::from_ymd_opt(2023, 1, 4).unwrap(),
+                    start: time(4, 14, 30),
+                    end: time(4, 21, 0),
+                },
+            ],
+            report.issues
+        );
+        assert_eq!(
+            vec![
+                (time(3, 17, 0), time(3, 19, 0)),
+                (time(4, 14, 30), time(4, 21, 0))
+            ],
+            report.missing_ranges()
+        );
+
+        let daily = vec![BarData {
+            date: "20230104".to_string(),
+            ..Default::default()
+        }];
+        let report = check_bars(&daily, &BarSize::_1Day, Some(&schedule()));
+        assert_eq!(
+            vec![BarIssue::MissingSession {
+                ref_date: NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(),
+                start: time(3, 14, 30),
+                end: time(3, 21, 0),
+            }],
+            report.issues
+        );
+        assert!(check_bars(&daily, &BarSize::_1Week, Some(&schedule())).is_clean());
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use chrono::{Duration, Utc};
+
+    use crate::twsapi::blotter::{Blotter, BlotterEvent, BlotterFilter};
+    use crate::twsapi::common::CommissionReport;
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::order::Order;
+
+    fn execution(exec_id: &str, order_id: i32, order_ref: &str) -> Execution {
+        Execution {
+            exec_id: exec_id.to_string(),
+            order_id,
+            order_ref: order_ref.to_string(),
+            side: "BOT".to_string(),
+            shares: 100.0,
+            price: 150.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_blotter() {
+        let recorded = Arc::new(Mutex::new(vec![]));
+        let mut blotter = Blotter::new();
+        let hook_recorded = recorded.clone();
+        blotter.set_hook(move |entry| hook_recorded.lock().unwrap().push(entry.sequence));
+
+        let aapl = Contract::stock("AAPL", "SMART", "USD");
+        let msft = Contract::stock("MSFT", "SMART", "USD");
+        let mut order = Order::limit_order("", "BUY", 100.0, 150.0);
+        order.order_ref = "momentum".to_string();
+        blotter.record_order(1, &aapl, &order);
+        order.lmt_price = 151.0;
+        blotter.record_order(1, &aapl, &order);
+        blotter.record_order(2, &msft, &Order::limit_order("", "SELL", 10.0, 300.0));
+        blotter.record_cancel(2);
+
+        assert!(blotter.record_execution(&aapl, &execution("E1", 1, "momentum")));
+        assert!(!blotter.record_execution(&aapl, &execution("E1", 1, "momentum")));
+        let report = CommissionReport {
+            exec_id: "E1".to_string(),
+            commission: 1.0,
+            currency: "USD".to_string(),
+            ..Default::default()
+        };
+        assert!(blotter.record_commission_report(&report));
+        assert!(!blotter.record_commission_report(&report));
+
+        assert_eq!(6, blotter.entries().len());
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], *recorded.lock().unwrap());
+        assert!(matches!(
+            blotter.entries()[1].event,
+            BlotterEvent::Amended(_)
+        ));
+
+        // The cancel and the commission inherit the contract and strategy
+        let cancel = &blotter.entries()[3];
+        assert!(matches!(cancel.event, BlotterEvent::Cancelled));
+        assert_eq!("MSFT", cancel.contract.symbol);
+        let commission = &blotter.entries()[5];
+        assert_eq!(1, commission.order_id);
+        assert_eq!("momentum", commission.order_ref);
+
+        assert_eq!(4, blotter.query(&BlotterFilter::new().symbol("AAPL")).len());
+        assert_eq!(
+            4,
+            blotter
+                .query(&BlotterFilter::new().symbol("AAPL").strategy("momentum"))
+                .len()
+        );
+        assert_eq!(2, blotter.order_history(2).len());
+
+        let now = Utc::now();
+        assert_eq!(
+            6,
+            blotter
+                .query(
+                    &BlotterFilter::new()
+                        .between(now - Duration::hours(1), now + Duration::hours(1))
+                )
+                .len()
+        );
+        assert!(blotter
+            .query(
+                &BlotterFilter::new().between(now + Duration::hours(1), now + Duration::hours(2))
+            )
+            .is_empty());
+
+        // Restored entries are indexed but not passed to the hook again
+        let mut restored = Blotter::new();
+        restored.restore(blotter.entries().to_vec());
+        assert!(!restored.record_execution(&aapl, &execution("E1", 1, "momentum")));
+        restored.record_order(1, &aapl, &order);
+        assert!(matches!(
+            restored.entries()[6].event,
+            BlotterEvent::Amended(_)
+        ));
+        assert_eq!(6, restored.entries()[6].sequence);
+    }
+}
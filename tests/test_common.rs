@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::{
+        BarSize, DataFarmKind, DataFarmStatus, DataRequestType, HistoricalDuration, WhatToShow,
+    };
+    use crate::twsapi::server_versions::MIN_SERVER_VER_HISTORICAL_SCHEDULE;
+
+    #[test]
+    fn test_data_farm_status_from_error() {
+        assert_eq!(
+            Some(DataFarmStatus::new(
+                "usfarm".to_string(),
+                DataFarmKind::MarketData,
+                true
+            )),
+            DataFarmStatus::from_error(2104, "Market data farm connection is OK:usfarm")
+        );
+        assert_eq!(
+            Some(DataFarmStatus::new(
+                "ushmds".to_string(),
+                DataFarmKind::Hmds,
+                false
+            )),
+            DataFarmStatus::from_error(2105, "HMDS data farm connection is broken:ushmds")
+        );
+        assert_eq!(
+            Some(DataFarmStatus::new(
+                "secdefnj".to_string(),
+                DataFarmKind::SecDef,
+                true
+            )),
+            DataFarmStatus::from_error(2158, "Sec-def data farm connection is OK:secdefnj")
+        );
+        assert_eq!(
+            None,
+            DataFarmStatus::from_error(200, "No security definition has been found")
+        );
+    }
+
+    #[test]
+    fn test_what_to_show() {
+        assert_eq!("YIELD_BID", WhatToShow::YieldBid.to_string());
+        assert_eq!(Ok(WhatToShow::AdjustedLast), "adjusted_last".parse());
+        assert_eq!(Ok(WhatToShow::Schedule), "SCHEDULE".parse::<WhatToShow>());
+        assert!("VOLUME".parse::<WhatToShow>().is_err());
+
+        assert!(WhatToShow::BidAsk.is_valid_for(DataRequestType::HistoricalData));
+        assert!(WhatToShow::BidAsk.is_valid_for(DataRequestType::HistoricalTicks));
+        assert!(!WhatToShow::BidAsk.is_valid_for(DataRequestType::RealTimeBars));
+        assert!(!WhatToShow::AdjustedLast.is_valid_for(DataRequestType::HistoricalTicks));
+        assert!(!WhatToShow::Schedule.is_valid_for(DataRequestType::HeadTimestamp));
+        assert_eq!(
+            MIN_SERVER_VER_HISTORICAL_SCHEDULE,
+            WhatToShow::Schedule.min_server_version()
+        );
+        assert_eq!(0, WhatToShow::Trades.min_server_version());
+    }
+
+    #[test]
+    fn test_bar_size_and_duration() {
+        assert_eq!(Ok(BarSize::_5Mins), "5 mins".parse());
+        assert_eq!(Ok(BarSize::_1Secs), " 1  sec".parse());
+        assert!("7 mins".parse::<BarSize>().is_err());
+        for bar_size in [BarSize::_1Hour, BarSize::_1Week, BarSize::_1Month] {
+            assert_eq!(Ok(bar_size.clone()), bar_size.to_string().parse());
+        }
+
+        assert_eq!(Ok(HistoricalDuration::Seconds(3600)), "3600 S".parse());
+        assert_eq!(Ok(HistoricalDuration::Years(1)), "1 y".parse());
+        assert!("5".parse::<HistoricalDuration>().is_err());
+        assert!("0 D".parse::<HistoricalDuration>().is_err());
+        assert!("2 X".parse::<HistoricalDuration>().is_err());
+        assert_eq!("2 W", HistoricalDuration::Weeks(2).to_string());
+
+        assert!(HistoricalDuration::Seconds(1800).is_compatible(&BarSize::_1Secs));
+        assert!(!HistoricalDuration::Seconds(3600).is_compatible(&BarSize::_1Secs));
+        assert!(HistoricalDuration::Days(10).is_compatible(&BarSize::_1Min));
+        assert!(!HistoricalDuration::Years(1).is_compatible(&BarSize::_1Min));
+        assert!(HistoricalDuration::Years(1).is_compatible(&BarSize::_1Month));
+        assert!(!HistoricalDuration::Days(1).is_compatible(&BarSize::_1Week));
+        assert!(HistoricalDuration::Days(1)
+            .check_bar_size(&BarSize::_1Week)
+            .is_err());
+        assert_eq!(
+            Ok(()),
+            HistoricalDuration::Months(1).check_bar_size(&BarSize::_1Day)
+        );
+    }
+}
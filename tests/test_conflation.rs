@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::twsapi::common::{TickAttrib, TickType};
+    use crate::twsapi::conflation::{ConflatedTick, TickConflator};
+
+    fn bid(request_id: i32, price: f64) -> ConflatedTick {
+        ConflatedTick::Price {
+            request_id,
+            tick_type: TickType::Bid,
+            price,
+            attrib: TickAttrib::default(),
+        }
+    }
+
+    #[test]
+    fn test_unconflated_ticks_pass_through() {
+        let mut conflator = TickConflator::new();
+        let now = Instant::now();
+
+        assert_eq!(1, conflator.offer(bid(1, 10.0), now).len());
+        assert_eq!(1, conflator.offer(bid(1, 10.5), now).len());
+        assert!(conflator.next_due(now).is_none());
+    }
+
+    #[test]
+    fn test_burst_is_coalesced_into_latest_value() {
+        let mut conflator = TickConflator::new();
+        let interval = Duration::from_millis(100);
+        let start = Instant::now();
+        conflator.set_interval(1, Some(interval));
+
+        // The first tick after a quiet period goes out immediately.
+        assert_eq!(1, conflator.offer(bid(1, 10.0), start).len());
+
+        // Everything within the interval is held back.
+        assert!(conflator.offer(bid(1, 10.1), start).is_empty());
+        assert!(conflator.offer(bid(1, 10.2), start).is_empty());
+        assert_eq!(Some(interval), conflator.next_due(start));
+        assert!(conflator.drain_due(start).is_empty());
+
+        let ticks = conflator.drain_due(start + interval);
+        assert_eq!(1, ticks.len());
+        match &ticks[0] {
+            ConflatedTick::Price { price, .. } => assert_eq!(10.2, *price),
+            _ => panic!("expected a price tick"),
+        }
+        assert!(conflator.next_due(start + interval).is_none());
+    }
+
+    #[test]
+    fn test_turning_conflation_off_drops_pending_ticks() {
+        let mut conflator = TickConflator::new();
+        let now = Instant::now();
+        conflator.set_interval(1, Some(Duration::from_secs(1)));
+
+        conflator.offer(bid(1, 10.0), now);
+        conflator.offer(bid(1, 10.1), now);
+        conflator.set_interval(1, None);
+
+        assert!(!conflator.is_conflated(1));
+        assert!(conflator.next_due(now).is_none());
+    }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use crate::twsapi::common::BarData;
+    use crate::twsapi::continuous_futures::{
+        parse_expiry, stitch, BackAdjustment, FuturesSegment, RollRule,
+    };
+    use crate::twsapi::contract::Contract;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 3, day).unwrap()
+    }
+
+    fn bar(day: u32, close: f64, volume: f64) -> BarData {
+        BarData {
+            date: format!("202303{:02}", day),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume,
+            ..Default::default()
+        }
+    }
+
+    fn segment(local_symbol: &str, expiry: NaiveDate, bars: Vec<BarData>) -> FuturesSegment {
+        let contract = Contract {
+            local_symbol: local_symbol.to_string(),
+            ..Default::default()
+        };
+        FuturesSegment::new(contract, expiry, bars)
+    }
+
+    // The front contract ESH3 trades at 90, ESJ3 at 100. ESJ3 trades more
+    // volume than ESH3 from the 9th on unless `next_volume` is lower.
+    fn segments(next_volume: f64) -> Vec<FuturesSegment> {
+        vec![
+            segment(
+                "ESJ3",
+                day(20),
+                (5..=20)
+                    .map(|day| bar(day, 100.0, if day < 9 { 500.0 } else { next_volume }))
+                    .collect(),
+            ),
+            segment(
+                "ESH3",
+                day(10),
+                (1..=10).map(|day| bar(day, 90.0, 1000.0)).collect(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_parse_expiry() {
+        assert_eq!(Some(day(17)), parse_expiry("20230317"));
+        assert_eq!(Some(day(17)), parse_expiry("20230317 13:30 US/Central"));
+        assert_eq!(None, parse_expiry("202303"));
+    }
+
+    #[test]
+    fn test_calendar_roll_with_difference_adjustment() {
+        let series = stitch(
+            segments(2000.0),
+            &RollRule::Calendar {
+                days_before_expiry: 2,
+            },
+            &BackAdjustment::Difference,
+        );
+        assert_eq!(1, series.rolls.len());
+        let roll = &series.rolls[0];
+        assert_eq!("ESH3", roll.from.local_symbol);
+        assert_eq!("ESJ3", roll.to.local_symbol);
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 3, 8, 0, 0, 0).unwrap(),
+            roll.time
+        );
+        assert_eq!(10.0, roll.adjustment);
+
+        let dates: Vec<&str> = series.bars.iter().map(|bar| bar.date.as_str()).collect();
+        assert_eq!(20, dates.len());
+        assert_eq!("20230301", dates[0]);
+        assert_eq!("20230307", dates[6]);
+        assert_eq!("20230308", dates[7]);
+        assert_eq!("20230320", dates[19]);
+        assert!(series.bars.iter().all(|bar| bar.close == 100.0));
+    }
+
+    #[test]
+    fn test_volume_roll_with_ratio_adjustment() {
+        let series = stitch(segments(2000.0), &RollRule::Volume, &BackAdjustment::Ratio);
+        let roll = &series.rolls[0];
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 3, 10, 0, 0, 0).unwrap(),
+            roll.time
+        );
+        assert!((roll.adjustment - 100.0 / 90.0).abs() < 1e-9);
+        assert_eq!(20, series.bars.len());
+        assert!(series
+            .bars
+            .iter()
+            .all(|bar| (bar.close - 100.0).abs() < 1e-9));
+
+        // Without any day of higher volume the roll happens at expiry
+        let series = stitch(segments(10.0), &RollRule::Volume, &BackAdjustment::None);
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 3, 10, 0, 0, 0).unwrap(),
+            series.rolls[0].time
+        );
+        assert_eq!(90.0, series.bars[0].close);
+        assert_eq!(100.0, series.bars[19].close);
+    }
+}
@@ -0,0 +1,341 @@
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::twsapi::contract::{
+        unique_contract, ComboLeg, Contract, ContractDescription, ContractDetails, Exchange, Right,
+        SecIdType, SecType,
+    };
+    use crate::twsapi::errors::ResolveContractError;
+
+    #[test]
+    fn test_right() {
+        assert_eq!(Ok(Right::Call), "C".parse());
+        assert_eq!(Ok(Right::Call), "call".parse());
+        assert_eq!(Ok(Right::Put), "PUT".parse());
+        assert_eq!(Ok(Right::None), "".parse());
+        assert!("X".parse::<Right>().is_err());
+        assert_eq!("P", Right::Put.to_string());
+        assert_eq!(Right::None, Right::default());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        assert_eq!("\"C\"", serde_json::to_string(&Right::Call).unwrap());
+        assert_eq!(
+            Right::Put,
+            serde_json::from_str::<Right>("\"PUT\"").unwrap()
+        );
+        assert!(serde_json::from_str::<Right>("\"X\"").is_err());
+
+        assert_eq!("\"OPT\"", serde_json::to_string(&SecType::Option).unwrap());
+        let contract: Contract =
+            serde_json::from_str(&serde_json::to_string(&Contract::forex("EURUSD")).unwrap())
+                .unwrap();
+        assert_eq!(SecType::Forex, contract.sec_type);
+        assert_eq!("\"ARCA\"", serde_json::to_string(&Exchange::ARCA).unwrap());
+
+        let description: ContractDescription = serde_json::from_str(
+            r#"{"contract": {"symbol": "IBM"}, "derivative_sec_types": ["OPT", "WAR", "CFD"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![SecType::Option, SecType::Warrant, SecType::Cfd],
+            description.derivative_sec_types
+        );
+    }
+
+    #[test]
+    fn test_constructors() {
+        let stock = Contract::stock("AAPL", "SMART", "USD");
+        assert_eq!(
+            ("AAPL", SecType::Stock, "SMART", "USD"),
+            (
+                stock.symbol.as_str(),
+                stock.sec_type,
+                stock.exchange.as_str(),
+                stock.currency.as_str()
+            )
+        );
+
+        let forex = Contract::forex("eur.usd");
+        assert_eq!(
+            ("EUR", SecType::Forex, "IDEALPRO", "USD"),
+            (
+                forex.symbol.as_str(),
+                forex.sec_type,
+                forex.exchange.as_str(),
+                forex.currency.as_str()
+            )
+        );
+
+        let future = Contract::future("ES", "202506", "CME");
+        assert_eq!(SecType::Future, future.sec_type);
+        assert_eq!("202506", future.last_trade_date_or_contract_month);
+        assert_eq!("CME", future.exchange);
+
+        let option = Contract::option("AAPL", "20250620", 200.0, Right::Call);
+        assert_eq!(SecType::Option, option.sec_type);
+        assert_eq!(Right::Call, option.right);
+        assert_eq!(200.0, option.strike);
+        assert_eq!("100", option.multiplier);
+        assert_eq!("SMART", option.exchange);
+
+        let futures_option = Contract::futures_option("ES", "20250620", 5000.0, Right::Put, "CME");
+        assert_eq!(SecType::FuturesOption, futures_option.sec_type);
+        assert_eq!(Right::Put, futures_option.right);
+
+        let crypto = Contract::crypto("BTC");
+        assert_eq!(
+            ("BTC", SecType::Crypto, "PAXOS", "USD"),
+            (
+                crypto.symbol.as_str(),
+                crypto.sec_type,
+                crypto.exchange.as_str(),
+                crypto.currency.as_str()
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_forex_rejects_invalid_pair() {
+        Contract::forex("EURUS");
+    }
+
+    #[test]
+    fn test_sec_type() {
+        for code in [
+            "STK", "OPT", "FUT", "CONTFUT", "FOP", "CASH", "BAG", "CFD", "IND", "NEWS",
+        ] {
+            assert_eq!(code, code.parse::<SecType>().unwrap().to_string());
+        }
+        assert_eq!(SecType::Cfd, SecType::from("cfd"));
+        assert_eq!(
+            SecType::Other("FUT+CONTFUT".to_string()),
+            "FUT+CONTFUT".into()
+        );
+        assert_eq!(SecType::Other(String::new()), SecType::default());
+        assert!(SecType::FuturesOption.is_option());
+        assert!(!SecType::Stock.is_option());
+    }
+
+    #[test]
+    fn test_exchange() {
+        assert_eq!(Exchange::IDEALPRO, Exchange::from(" idealpro"));
+        assert_eq!("SMART", Exchange::SMART.to_string());
+        assert!(Exchange::from("smart").is_smart());
+
+        let stock = Contract::stock("MSFT", "SMART", "USD").smart_routed(&Exchange::NASDAQ);
+        assert_eq!(
+            ("SMART", "NASDAQ"),
+            (stock.exchange.as_str(), stock.primary_exchange.as_str())
+        );
+        assert!(!stock.is_directed());
+
+        let stock = stock.directed(&Exchange::ARCA, &Exchange::NASDAQ);
+        assert_eq!(Exchange::ARCA, stock.exchange);
+        assert_eq!(Exchange::NASDAQ, stock.primary_exchange);
+        assert!(stock.is_directed());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_smart_is_no_primary_exchange() {
+        Contract::stock("MSFT", "SMART", "USD").smart_routed(&Exchange::SMART);
+    }
+
+    #[test]
+    fn test_unique_contract() {
+        let query = Contract::stock("ABC", "SMART", "USD");
+        let details = |con_id: i32, primary_exchange: &str| ContractDetails {
+            contract: Contract {
+                con_id,
+                primary_exchange: primary_exchange.to_string(),
+                ..query.clone()
+            },
+            ..Default::default()
+        };
+
+        let contract =
+            unique_contract(&query, vec![details(1, "NYSE"), details(1, "NYSE")]).unwrap();
+        assert_eq!(1, contract.con_id);
+
+        match unique_contract(&query, vec![]) {
+            Err(ResolveContractError::NotFound(contract)) => assert_eq!("ABC", contract.symbol),
+            _ => panic!("Expected no match"),
+        }
+
+        let err =
+            unique_contract(&query, vec![details(2, "ARCA"), details(1, "NYSE")]).unwrap_err();
+        match &err {
+            ResolveContractError::Ambiguous(candidates) => {
+                assert_eq!(
+                    vec![1, 2],
+                    candidates.iter().map(|c| c.con_id).collect::<Vec<i32>>()
+                )
+            }
+            _ => panic!("Expected an ambiguous match"),
+        }
+        assert!(err.to_string().starts_with("2 contracts match"));
+        assert!(err.to_string().contains("ARCA"));
+    }
+
+    #[test]
+    fn test_sec_id() {
+        assert!(SecIdType::Isin.is_valid("US0378331005"));
+        assert!(SecIdType::Isin.is_valid("de0007164600"));
+        assert!(!SecIdType::Isin.is_valid("US0378331006"));
+        assert!(!SecIdType::Isin.is_valid("US037833100"));
+        assert!(SecIdType::Cusip.is_valid("037833100"));
+        assert!(!SecIdType::Cusip.is_valid("037833101"));
+        assert!(SecIdType::Figi.is_valid("BBG000B9XRY4"));
+        assert!(!SecIdType::Figi.is_valid("BBG000B9XRY5"));
+        assert!(SecIdType::Sedol.is_valid("2046251"));
+        assert!(!SecIdType::Sedol.is_valid("2046252"));
+        assert!(SecIdType::Ric.is_valid("AAPL.O"));
+
+        assert_eq!(Ok(SecIdType::Figi), "figi".parse());
+        assert!("WKN".parse::<SecIdType>().is_err());
+
+        let contract = Contract::isin(" us0378331005 ");
+        assert_eq!("ISIN", contract.sec_id_type);
+        assert_eq!("US0378331005", contract.sec_id);
+        assert_eq!(contract.exchange, Exchange::SMART);
+        assert_eq!("CUSIP", Contract::cusip("037833100").sec_id_type);
+        assert_eq!("FIGI", Contract::figi("BBG000B9XRY4").sec_id_type);
+    }
+
+    #[test]
+    fn test_contract_description() {
+        let description = ContractDescription::new(
+            Contract {
+                symbol: "IBM".to_string(),
+                ..Default::default()
+            },
+            vec![SecType::Option, SecType::Warrant, SecType::Cfd],
+        );
+        assert!(description.has_derivatives(&SecType::Option));
+        assert!(!description.has_derivatives(&SecType::Future));
+        assert!(description.to_string().ends_with("(OPT,WAR,CFD)"));
+    }
+
+    #[test]
+    fn test_bond_details() {
+        let mut details = ContractDetails {
+            contract: Contract {
+                sec_type: SecType::Bond,
+                ..Default::default()
+            },
+            cusip: "912828YK0".to_string(),
+            ratings: "AA+/Aaa".to_string(),
+            coupon: 1.375,
+            callable: true,
+            maturity: "20261015".to_string(),
+            issue_date: "10/15/2019".to_string(),
+            next_option_date: "".to_string(),
+            ..Default::default()
+        };
+        let bond = details.bond_details().unwrap();
+        assert_eq!(vec!["AA+", "Aaa"], bond.ratings);
+        assert_eq!(1.375, bond.coupon);
+        assert!(bond.callable && !bond.putable);
+        assert_eq!(NaiveDate::from_ymd_opt(2026, 10, 15), bond.maturity);
+        assert_eq!(NaiveDate::from_ymd_opt(2019, 10, 15), bond.issue_date);
+        assert_eq!(None, bond.next_option_date);
+        let years = bond
+            .years_to_maturity(NaiveDate::from_ymd_opt(2024, 10, 15).unwrap())
+            .unwrap();
+        assert!((years - 2.0).abs() < 0.01);
+
+        details.contract.sec_type = SecType::Stock;
+        assert!(details.bond_details().is_none());
+    }
+
+    #[test]
+    fn test_contract_identity() {
+        let query = Contract::stock(" aapl", "smart ", "usd").normalize();
+        assert_eq!("AAPL", query.symbol);
+        assert_eq!("SMART", query.exchange);
+        assert_eq!("USD", query.currency);
+
+        assert_eq!(Contract::stock("aapl", "SMART", "usd"), query);
+        assert_ne!(Contract::stock("AAPL", "SMART", "EUR"), query);
+        let other_type = Contract {
+            sec_type: SecType::Other("stk".to_string()),
+            ..query.clone()
+        };
+        assert_eq!(query, other_type);
+        assert_eq!(query.identity(), other_type.identity());
+
+        let call = |strike| Contract {
+            sec_type: SecType::Option,
+            strike,
+            right: Right::Call,
+            ..query.clone()
+        };
+        assert_eq!(call(150.0), call(150.0));
+        assert_ne!(call(150.0), call(155.0));
+        assert_ne!(call(150.0), query);
+
+        let resolved = Contract {
+            con_id: 265598,
+            ..query.clone()
+        };
+        let other_listing = Contract {
+            con_id: 265598,
+            exchange: "NASDAQ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(resolved, other_listing);
+        assert_ne!(resolved, query);
+
+        let mut positions = std::collections::HashMap::new();
+        positions.insert(resolved, 100.0);
+        positions.insert(query, 50.0);
+        assert_eq!(Some(&100.0), positions.get(&other_listing));
+        assert_eq!(
+            Some(&50.0),
+            positions.get(&Contract::stock("AAPL", "SMART", "USD"))
+        );
+
+        // Spreads without a con_id are told apart by their legs
+        let spread = |legs: [(i32, f64, &str); 2]| Contract {
+            symbol: "SPY".to_string(),
+            sec_type: SecType::Combo,
+            exchange: "SMART".to_string(),
+            currency: "USD".to_string(),
+            combo_legs: legs
+                .iter()
+                .map(|(con_id, ratio, action)| ComboLeg {
+                    con_id: *con_id,
+                    ratio: *ratio,
+                    action: action.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let bull_call = spread([(1001, 1.0, "BUY"), (1002, 1.0, "SELL")]);
+        assert_eq!(bull_call, spread([(1001, 1.0, "buy"), (1002, 1.0, "sell")]));
+        assert_ne!(bull_call, spread([(1001, 1.0, "SELL"), (1002, 1.0, "BUY")]));
+        assert_ne!(bull_call, spread([(1001, 1.0, "BUY"), (1003, 1.0, "SELL")]));
+        assert_ne!(bull_call, spread([(1001, 1.0, "BUY"), (1002, 2.0, "SELL")]));
+        assert_ne!(
+            bull_call.identity(),
+            spread([(1001, 1.0, "BUY"), (1003, 1.0, "SELL")]).identity()
+        );
+        let mut spreads = std::collections::HashSet::new();
+        spreads.insert(bull_call.clone());
+        spreads.insert(spread([(1001, 1.0, "BUY"), (1003, 1.0, "SELL")]));
+        assert_eq!(2, spreads.len());
+        assert!(spreads.contains(&spread([(1001, 1.0, "buy"), (1002, 1.0, "sell")])));
+
+        // Identity and equality agree on symbols that are not ASCII
+        let street = Contract::stock("stra\u{df}e", "SMART", "EUR");
+        let strasse = Contract::stock("STRASSE", "SMART", "EUR");
+        assert_ne!(street, strasse);
+        assert_ne!(street.identity(), strasse.identity());
+    }
+}
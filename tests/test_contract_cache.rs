@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "serde")]
+    use std::fs;
+
+    use chrono::Duration;
+
+    use crate::twsapi::contract::{Contract, ContractDetails};
+    use crate::twsapi::contract_cache::{request_key, ContractCache};
+
+    fn details(con_id: i32, symbol: &str) -> ContractDetails {
+        ContractDetails {
+            contract: Contract {
+                con_id,
+                ..Contract::stock(symbol, "SMART", "USD")
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_request_key() {
+        assert_eq!(
+            request_key(&Contract::stock("aapl ", "smart", "usd")),
+            request_key(&Contract::stock("AAPL", "SMART", "USD"))
+        );
+        assert_ne!(
+            request_key(&Contract::stock("AAPL", "SMART", "USD")),
+            request_key(&Contract::stock("AAPL", "SMART", "EUR"))
+        );
+        let contract = Contract {
+            con_id: 265598,
+            ..Contract::stock("AAPL", "SMART", "USD")
+        };
+        assert_eq!("CONID:265598", request_key(&contract));
+    }
+
+    #[test]
+    fn test_get_and_expire() {
+        let mut cache = ContractCache::new(Duration::hours(1));
+        let query = Contract::stock("AAPL", "SMART", "USD");
+        assert!(cache.get(&query).is_none());
+
+        cache.insert(&query, vec![details(265598, "AAPL")]).unwrap();
+        assert_eq!(1, cache.len());
+        assert_eq!(
+            265598,
+            cache.get(&Contract::stock("aapl", "SMART", "USD")).unwrap()[0]
+                .contract
+                .con_id
+        );
+        assert_eq!("AAPL", cache.get_by_con_id(265598).unwrap().contract.symbol);
+        let by_con_id = Contract {
+            con_id: 265598,
+            ..Default::default()
+        };
+        assert_eq!(1, cache.get(&by_con_id).unwrap().len());
+
+        let mut cache = ContractCache::new(Duration::zero());
+        cache.insert(&query, vec![details(265598, "AAPL")]).unwrap();
+        assert!(cache.get(&query).is_none());
+        assert!(cache.get_by_con_id(265598).is_none());
+        cache.remove_expired().unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_persistence() {
+        let dir =
+            std::env::temp_dir().join(format!("twsapi-contract-cache-{}", std::process::id()));
+        let path = dir.join("contracts.json");
+        {
+            let mut cache = ContractCache::open(&path, Duration::days(1)).unwrap();
+            assert!(cache.is_empty());
+            cache
+                .insert(
+                    &Contract::stock("MSFT", "SMART", "USD"),
+                    vec![details(272093, "MSFT")],
+                )
+                .unwrap();
+        }
+
+        let mut cache = ContractCache::open(&path, Duration::days(1)).unwrap();
+        assert_eq!(1, cache.len());
+        assert_eq!("MSFT", cache.get_by_con_id(272093).unwrap().contract.symbol);
+
+        cache.clear().unwrap();
+        assert!(ContractCache::open(&path, Duration::days(1))
+            .unwrap()
+            .is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
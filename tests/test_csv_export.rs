@@ -0,0 +1,126 @@
+#[cfg(all(test, feature = "csv-export"))]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::twsapi::common::{BarData, HistoricalTick, TickType};
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::csv_export::{
+        parse_execution_time, write_bars, write_executions, write_historical_ticks,
+        write_recorded_ticks, write_trade_summaries, CsvWriter,
+    };
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::historical::HistoricalTicks;
+    use crate::twsapi::tick_recorder::RecordedTick;
+    use crate::twsapi::trade_report::TradeSummary;
+
+    fn to_string(bytes: Vec<u8>) -> String {
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_quoting() {
+        let mut writer = CsvWriter::new(vec![]);
+        writer
+            .write_row(&["plain", "a,b", "say \"hi\"", "two\nlines"])
+            .unwrap();
+        assert_eq!(
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n",
+            to_string(writer.into_inner())
+        );
+    }
+
+    #[test]
+    fn test_write_bars_and_ticks() {
+        let bars = vec![BarData {
+            date: "20230103".to_string(),
+            open: 1.0,
+            high: 2.0,
+            low: 0.5,
+            close: 1.5,
+            volume: 100.0,
+            bar_count: 3,
+            average: 1.25,
+            time: None,
+        }];
+        let mut csv = vec![];
+        write_bars(&mut csv, &bars).unwrap();
+        assert_eq!(
+            "time,open,high,low,close,volume,bar_count,average\r\n\
+             2023-01-03T00:00:00Z,1,2,0.5,1.5,100,3,1.25\r\n",
+            to_string(csv)
+        );
+
+        let mut csv = vec![];
+        let ticks = HistoricalTicks::Midpoint(vec![HistoricalTick::new(1672756200, 1.5, 0.0)]);
+        write_historical_ticks(&mut csv, &ticks).unwrap();
+        assert_eq!(
+            "time,price,size\r\n2023-01-03T14:30:00Z,1.5,0\r\n",
+            to_string(csv)
+        );
+
+        let mut csv = vec![];
+        let time = Utc.timestamp_opt(1672756200, 500_000_000).unwrap();
+        let ticks = vec![RecordedTick::size(time, 7, TickType::BidSize, 300.0)];
+        write_recorded_ticks(&mut csv, &ticks).unwrap();
+        assert_eq!(
+            "time,ticker_id,tick_type,price,size\r\n2023-01-03T14:30:00.500Z,7,bidSize,,300\r\n",
+            to_string(csv)
+        );
+    }
+
+    #[test]
+    fn test_write_executions() {
+        assert_eq!(
+            Some(Utc.with_ymd_and_hms(2023, 1, 3, 14, 30, 0).unwrap()),
+            parse_execution_time("20230103 09:30:00 US/Eastern", chrono_tz::UTC)
+        );
+        assert_eq!(
+            Some(Utc.with_ymd_and_hms(2023, 7, 3, 13, 30, 0).unwrap()),
+            parse_execution_time("20230703  09:30:00", chrono_tz::America::New_York)
+        );
+
+        let contract = Contract {
+            con_id: 265598,
+            symbol: "AAPL".to_string(),
+            ..Default::default()
+        };
+        let execution = Execution {
+            time: "20230103 09:30:00 US/Eastern".to_string(),
+            side: "BOT".to_string(),
+            shares: 10.0,
+            price: 125.5,
+            ..Default::default()
+        };
+        let mut csv = vec![];
+        write_executions(&mut csv, &[(contract, execution)], chrono_tz::UTC).unwrap();
+        let csv = to_string(csv);
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.starts_with("2023-01-03T14:30:00Z,,,265598,AAPL,"));
+        assert!(row.contains(",BOT,10,125.5,"));
+    }
+
+    #[test]
+    fn test_write_trade_summaries() {
+        let summary = TradeSummary {
+            executions: 2,
+            bought: 100.0,
+            sold: 100.0,
+            notional: 30_000.0,
+            realized_pnl: 100.0,
+            commissions: 2.0,
+            ..TradeSummary::new("momentum", "USD")
+        };
+        let mut csv = vec![];
+        write_trade_summaries(&mut csv, &[summary]).unwrap();
+        let csv = to_string(csv);
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("name,currency,executions,"));
+        assert_eq!(
+            "momentum,USD,2,100,100,200,30000,100,2,98,0,,0",
+            lines.next().unwrap()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::twsapi::currency::{fx_contract, fx_pair, CurrencyConverter};
+
+    #[test]
+    fn test_fx_pair() {
+        assert_eq!(
+            ("EUR".to_string(), "USD".to_string()),
+            fx_pair("usd", "EUR")
+        );
+        assert_eq!(
+            ("USD".to_string(), "JPY".to_string()),
+            fx_pair("JPY", "USD")
+        );
+        assert_eq!(
+            ("USD".to_string(), "HKD".to_string()),
+            fx_pair("HKD", "USD")
+        );
+        let contract = fx_contract("EUR", "USD");
+        assert_eq!("EUR", contract.symbol);
+        assert_eq!("USD", contract.currency);
+    }
+
+    #[test]
+    fn test_currency_converter() {
+        let earlier = Utc.with_ymd_and_hms(2023, 1, 3, 14, 0, 0).unwrap();
+        let later = earlier + Duration::minutes(5);
+        let mut converter = CurrencyConverter::new();
+        converter.set_rate("EUR", "USD", 1.1, later);
+        converter.track(1, "USD", "JPY");
+        assert!(converter.update_from_quote(1, Some(130.0), Some(132.0), None, earlier));
+        assert!(!converter.update_from_quote(2, Some(1.0), Some(1.0), None, earlier));
+
+        let conversion = converter.convert(100.0, "EUR", "USD").unwrap();
+        assert!((conversion.amount - 110.0).abs() < 1e-9);
+        assert_eq!(later, conversion.time);
+        assert!((converter.convert(110.0, "USD", "EUR").unwrap().amount - 100.0).abs() < 1e-9);
+
+        // EUR -> USD -> JPY carries the time of the older rate
+        let cross = converter.convert(1.0, "EUR", "JPY").unwrap();
+        assert!((cross.amount - 1.1 * 131.0).abs() < 1e-9);
+        assert_eq!(earlier, cross.time);
+        assert!(converter.convert(1.0, "GBP", "USD").is_none());
+
+        let mut amounts = BTreeMap::new();
+        amounts.insert("EUR".to_string(), 100.0);
+        amounts.insert("USD".to_string(), 50.0);
+        assert!((converter.convert_sum(&amounts, "USD").unwrap().amount - 160.0).abs() < 1e-9);
+        amounts.insert("GBP".to_string(), 10.0);
+        assert_eq!(
+            vec!["GBP".to_string()],
+            converter.convert_sum(&amounts, "USD").unwrap_err()
+        );
+
+        assert_eq!(
+            vec![
+                ("EUR".to_string(), "USD".to_string()),
+                ("USD".to_string(), "JPY".to_string())
+            ],
+            CurrencyConverter::required_pairs(&["JPY", "USD", "EUR", "EUR"], "USD")
+        );
+    }
+}
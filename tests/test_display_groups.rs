@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::display_groups::{parse_display_groups, DisplayGroupContract};
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_display_group_contract() {
+        let contract = DisplayGroupContract::parse("8314@SMART").unwrap();
+        assert_eq!(DisplayGroupContract::new(8314, "SMART"), contract);
+        assert_eq!(Some(8314), contract.con_id());
+        assert_eq!("8314@SMART", contract.to_string());
+
+        assert_eq!(
+            Some(DisplayGroupContract::None),
+            DisplayGroupContract::parse("none")
+        );
+        assert_eq!(
+            Some(DisplayGroupContract::Combo),
+            DisplayGroupContract::parse("combo")
+        );
+        assert_eq!("8314", DisplayGroupContract::new(8314, "").to_string());
+        assert_eq!(None, DisplayGroupContract::parse("IBM@SMART"));
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_parse_display_groups() {
+        assert_eq!(vec![4, 1, 7], parse_display_groups("4|1|7"));
+        assert!(parse_display_groups("").is_empty());
+    }
+}
@@ -7,22 +7,60 @@ mod tests {
     use crate::twsapi::client::{ConnStatus, EClient, POISONED_MUTEX};
 
     use crate::twsapi::{
+        account_alerts::{AccountThreshold, ThresholdSide},
+        account_updates::{AccountUpdateMultiEvent, AccountUpdates, AccountValueKey},
+        alert_hooks::{AlertCondition, AlertEvent, AlertHooks},
+        audit_log::{payload_hash, AuditRecord, AuditSink, MemoryAuditSink, OrderAuditLog},
         common::{
-            BarData, CommissionReport, DepthMktDataDescription, FaDataType, FamilyCode,
-            HistogramData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast, NewsProvider,
-            PriceIncrement, RealTimeBar, SmartComponent, TickAttrib, TickAttribBidAsk,
-            TickAttribLast, TickByTickType, TickType,
+            BarData, CommissionReport, DataFarmStatus, DepthMktDataDescription, FaDataType,
+            FamilyCode, HistogramData, HistoricalTick, HistoricalTickBidAsk, HistoricalTickLast,
+            NewsProvider, PriceIncrement, RealTimeBar, TickAttrib, TickAttribBidAsk,
+            TickAttribLast, TickByTickType, TickType, WhatToShow, NO_VALID_ID, UNSET_DOUBLE,
         },
-        contract::{Contract, ContractDescription, ContractDetails, DeltaNeutralContract},
-        errors::IBKRApiLibError,
+        conflation::TickConflator,
+        contract::{
+            Contract, ContractDescription, ContractDetails, ContractDetailsEvent,
+            DeltaNeutralContract, Right, SecType,
+        },
+        decoder::{decode_size, parse_number, size_to_decimal, unescape_unicode, Decoder},
+        display_groups::DisplayGroupContract,
+        errors::{DecodeError, DecodeErrorReason, IbkrError},
         execution::{Execution, ExecutionFilter},
+        execution_quality::ArrivalQuotes,
+        historical::HistoricalSchedule,
+        managed_accounts::ManagedAccounts,
+        margin::WhatIfEvent,
+        market_rules::MarketRuleCache,
         messages::{read_fields, read_msg, OutgoingMessageIds},
+        news::{NewsArticle, NewsBulletin, NewsHeadline},
         order::OrderState,
+        pnl::PnlSingleEvent,
         order::{Order, SoftDollarTier},
-        streamer::{Streamer, TestStreamer},
+        order_tracker::{DuplicateRefPolicy, OrderTracker},
+        quote_cache::{AuctionState, QuoteCache, TradingStatus},
+        scanner::{ScanCode, ScanFilter, ScannerSubscription},
+        session_gate::{SessionGate, SessionGateMode},
+        trading_hours::Session,
+        smart_components::{SmartComponentMap, SmartComponentRegistry},
+        streamer::{RequestSender, Streamer, TestStreamer},
+        order_book::{BookMetricsConfig, OrderBook},
+        order_journal::{read_journal, JournalEntry, OrderJournal},
+        order_throttle::{OrderThrottle, ThrottleMode},
+        risk::{MaxOrderNotional, RiskAction, RiskChecks},
+        subscription::StreamRegistry,
+        trading_mode::TradingMode,
         wrapper::Wrapper,
     };
-    use std::sync::{Arc, Mutex};
+    #[cfg(feature = "wsh")]
+    use crate::twsapi::wsh::{WshEvent, WshEventData};
+    use bigdecimal::BigDecimal;
+    #[cfg(feature = "wsh")]
+    use chrono::NaiveDate;
+    use chrono::Utc;
+    use chrono_tz::Tz;
+    use std::str::FromStr;
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::Duration;
 
     pub struct DummyTestWrapper {}
 
@@ -33,10 +71,22 @@ mod tests {
     }
 
     impl Wrapper for DummyTestWrapper {
-        fn error(&mut self, _request_id: i32, _error_code: i32, _error_string: &str) {
+        fn error(
+            &mut self,
+            _request_id: i32,
+            _error_code: i32,
+            _error_string: &str,
+            _advanced_order_reject_json: &str,
+        ) {
             todo!()
         }
 
+        fn data_farm_status(&mut self, _status: &DataFarmStatus) {
+            todo!()
+        }
+
+        fn decode_error(&mut self, _error: &DecodeError) {}
+
         fn win_error(&mut self, _text: &str, _last_error: i32) {
             todo!()
         }
@@ -59,7 +109,7 @@ mod tests {
             todo!()
         }
 
-        fn tick_size(&mut self, _request_id: i32, _tick_type: TickType, _size: i32) {
+        fn tick_size(&mut self, _request_id: i32, _tick_type: TickType, _size: BigDecimal) {
             todo!()
         }
 
@@ -71,6 +121,14 @@ mod tests {
             todo!()
         }
 
+        fn trading_status(&mut self, _request_id: i32, _trading_status: TradingStatus) {
+            todo!()
+        }
+
+        fn auction_state(&mut self, _request_id: i32, _auction_state: AuctionState) {
+            todo!()
+        }
+
         fn tick_string(&mut self, _request_id: i32, _tick_type: TickType, _value: &str) {
             todo!()
         }
@@ -94,8 +152,8 @@ mod tests {
             &mut self,
             _order_id: i32,
             _status: &str,
-            _filled: f64,
-            _remaining: f64,
+            _filled: BigDecimal,
+            _remaining: BigDecimal,
             _avg_fill_price: f64,
             _perm_id: i32,
             _parent_id: i32,
@@ -104,7 +162,6 @@ mod tests {
             _why_held: &str,
             _market_cap_price: f64,
         ) {
-            todo!()
         }
 
         fn open_order(
@@ -138,7 +195,7 @@ mod tests {
         fn update_portfolio(
             &mut self,
             _contract: Contract,
-            _position: f64,
+            _position: BigDecimal,
             _market_price: f64,
             _market_value: f64,
             _average_cost: f64,
@@ -188,7 +245,7 @@ mod tests {
             _operation: i32,
             _side: i32,
             _price: f64,
-            _size: i32,
+            _size: BigDecimal,
         ) {
             todo!()
         }
@@ -201,19 +258,12 @@ mod tests {
             _operation: i32,
             _side: i32,
             _price: f64,
-            _size: i32,
+            _size: BigDecimal,
             _is_smart_depth: bool,
         ) {
-            todo!()
         }
 
-        fn update_news_bulletin(
-            &mut self,
-            _msg_id: i32,
-            _msg_type: i32,
-            _news_message: &str,
-            _origin_exch: &str,
-        ) {
+        fn update_news_bulletin(&mut self, _bulletin: NewsBulletin) {
             todo!()
         }
 
@@ -225,6 +275,10 @@ mod tests {
             todo!()
         }
 
+        fn replace_fa_end(&mut self, _request_id: i32, _text: &str) {
+            todo!()
+        }
+
         fn historical_data(&mut self, _request_id: i32, _bar: BarData) {
             todo!()
         }
@@ -282,7 +336,7 @@ mod tests {
             &mut self,
             _account: &str,
             _contract: Contract,
-            _position: f64,
+            _position: BigDecimal,
             _avg_cost: f64,
         ) {
             todo!()
@@ -323,11 +377,15 @@ mod tests {
             todo!()
         }
 
-        fn display_group_list(&mut self, _request_id: i32, _groups: &str) {
+        fn display_group_list(&mut self, _request_id: i32, _groups: Vec<i32>) {
             todo!()
         }
 
-        fn display_group_updated(&mut self, _request_id: i32, _contract_info: &str) {
+        fn display_group_updated(
+            &mut self,
+            _request_id: i32,
+            _contract_info: DisplayGroupContract,
+        ) {
             todo!()
         }
 
@@ -337,7 +395,7 @@ mod tests {
             _account: &str,
             _model_code: &str,
             _contract: Contract,
-            _pos: f64,
+            _pos: BigDecimal,
             _avg_cost: f64,
         ) {
             todo!()
@@ -367,6 +425,7 @@ mod tests {
             &mut self,
             _request_id: i32,
             _tick_type: TickType,
+            _tick_attrib: i32,
             _implied_vol: f64,
             _delta: f64,
             _opt_price: f64,
@@ -400,9 +459,7 @@ mod tests {
             todo!()
         }
 
-        fn family_codes(&mut self, _financial_advisormily_codes: Vec<FamilyCode>) {
-            todo!()
-        }
+        fn family_codes(&mut self, _financial_advisormily_codes: Vec<FamilyCode>) {}
 
         fn symbol_samples(
             &mut self,
@@ -419,21 +476,11 @@ mod tests {
             todo!()
         }
 
-        fn tick_news(
-            &mut self,
-            _ticker_id: i32,
-            _time_stamp: i32,
-            _provider_code: &str,
-            _article_id: &str,
-            _headline: &str,
-            _extra_data: &str,
-        ) {
+        fn tick_news(&mut self, _ticker_id: i32, _headline: NewsHeadline) {
             todo!()
         }
 
-        fn smart_components(&mut self, _request_id: i32, _smart_components: Vec<SmartComponent>) {
-            todo!()
-        }
+        fn smart_components(&mut self, _request_id: i32, _smart_components: SmartComponentMap) {}
 
         fn tick_request_parameters(
             &mut self,
@@ -445,22 +492,13 @@ mod tests {
             todo!()
         }
 
-        fn news_providers(&mut self, _news_providers: Vec<NewsProvider>) {
-            todo!()
-        }
+        fn news_providers(&mut self, _news_providers: Vec<NewsProvider>) {}
 
-        fn news_article(&mut self, _request_id: i32, _article_type: i32, _article_text: &str) {
+        fn news_article(&mut self, _request_id: i32, _article: NewsArticle) {
             todo!()
         }
 
-        fn historical_news(
-            &mut self,
-            _request_id: i32,
-            _time: &str,
-            _provider_code: &str,
-            _article_id: &str,
-            _headline: &str,
-        ) {
+        fn historical_news(&mut self, _request_id: i32, _headline: NewsHeadline) {
             todo!()
         }
 
@@ -472,11 +510,13 @@ mod tests {
             todo!()
         }
 
-        fn histogram_data(&mut self, _request_id: i32, _items: Vec<HistogramData>) {
+        fn histogram_data(&mut self, _request_id: i32, _items: Vec<HistogramData>) {}
+
+        fn historical_data_update(&mut self, _request_id: i32, _bar: BarData) {
             todo!()
         }
 
-        fn historical_data_update(&mut self, _request_id: i32, _bar: BarData) {
+        fn historical_schedule(&mut self, _request_id: i32, _schedule: HistoricalSchedule) {
             todo!()
         }
 
@@ -493,9 +533,7 @@ mod tests {
             todo!()
         }
 
-        fn market_rule(&mut self, _market_rule_id: i32, _price_increments: Vec<PriceIncrement>) {
-            todo!()
-        }
+        fn market_rule(&mut self, _market_rule_id: i32, _price_increments: Vec<PriceIncrement>) {}
 
         fn profit_and_loss(
             &mut self,
@@ -510,7 +548,7 @@ mod tests {
         fn profit_and_loss_single(
             &mut self,
             _request_id: i32,
-            _pos: i32,
+            _pos: BigDecimal,
             _daily_pn_l: f64,
             _unrealized_pn_l: f64,
             _realized_pn_l: f64,
@@ -547,7 +585,7 @@ mod tests {
             _tick_type: TickByTickType,
             _time: i64,
             _price: f64,
-            _size: i32,
+            _size: BigDecimal,
             _tick_attrib_last: TickAttribLast,
             _exchange: &str,
             _special_conditions: &str,
@@ -561,8 +599,8 @@ mod tests {
             _time: i64,
             _bid_price: f64,
             _ask_price: f64,
-            _bid_size: i32,
-            _ask_size: i32,
+            _bid_size: BigDecimal,
+            _ask_size: BigDecimal,
             _tick_attrib_bid_ask: TickAttribBidAsk,
         ) {
             todo!()
@@ -588,6 +626,20 @@ mod tests {
         fn completed_orders_end(&mut self) {
             todo!()
         }
+
+        #[cfg(feature = "wsh")]
+        fn wsh_meta_data(&mut self, _request_id: i32, _meta_data: serde_json::Value) {
+            todo!()
+        }
+
+        #[cfg(feature = "wsh")]
+        fn wsh_event_data(&mut self, _request_id: i32, _events: Vec<WshEvent>) {
+            todo!()
+        }
+
+        fn user_info(&mut self, _request_id: i32, _white_branding_id: &str) {
+            todo!()
+        }
     }
 
     //------------------------------------------------------------------------------------------------
@@ -606,7 +658,7 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_account_summary() -> Result<(), IBKRApiLibError> {
+    fn test_request_account_summary() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -646,7 +698,78 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_account_updates() -> Result<(), IBKRApiLibError> {
+    fn test_stream_real_time_bars() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+
+        let request_id = 100;
+        let contract = simple_future();
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        assert!(locked_app
+            .stream_real_time_bars(request_id, &contract, WhatToShow::BidAsk, false, &[])
+            .is_err());
+
+        let subscription =
+            locked_app.stream_real_time_bars(request_id, &contract, WhatToShow::Trades, true, &[])?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert_eq!(
+            OutgoingMessageIds::ReqRealTimeBars as u8,
+            fields[0].parse::<u8>().unwrap()
+        );
+        assert_eq!(request_id, fields[2].parse::<i32>().unwrap());
+        assert_eq!("5", fields[15]);
+        assert_eq!("TRADES", fields[16]);
+        assert_eq!("1", fields[17]);
+        assert_eq!(request_id, subscription.request_id());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_stream_scanner_subscription() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+
+        let request_id = 7001;
+        let subscription = ScannerSubscription::builder(ScanCode::MostActive)
+            .number_of_rows(10)
+            .filter(ScanFilter::VolumeAbove(100000))
+            .build();
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        let scans = locked_app.stream_scanner_subscription(request_id, subscription, &[])?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert_eq!(
+            OutgoingMessageIds::ReqScannerSubscription as u8,
+            fields[0].parse::<u8>().unwrap()
+        );
+        assert_eq!(request_id, fields[1].parse::<i32>().unwrap());
+        assert_eq!("10", fields[2]);
+        assert_eq!("MOST_ACTIVE", fields[5]);
+        assert_eq!("", fields[6]);
+        assert_eq!("volumeAbove=100000;", fields[23]);
+        assert_eq!(request_id, scans.request_id());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_request_account_updates() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -679,7 +802,7 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_account_updates_multi() -> Result<(), IBKRApiLibError> {
+    fn test_request_account_updates_multi() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -725,7 +848,7 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_all_open_orders() -> Result<(), IBKRApiLibError> {
+    fn test_request_all_open_orders() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -756,7 +879,7 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_auto_open_orders() -> Result<(), IBKRApiLibError> {
+    fn test_request_auto_open_orders() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -788,7 +911,7 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_completed_orders() -> Result<(), IBKRApiLibError> {
+    fn test_request_completed_orders() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -820,7 +943,49 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_contract_details() -> Result<(), IBKRApiLibError> {
+    #[cfg(feature = "wsh")]
+    fn test_request_wsh_event_data() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+
+        let request_id = 103;
+        let mut wsh_event_data = WshEventData::new(8314);
+        wsh_event_data.start_date = NaiveDate::from_ymd_opt(2023, 1, 2);
+        wsh_event_data.total_limit = Some(10);
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        assert!(locked_app
+            .request_wsh_event_data(request_id, &wsh_event_data)
+            .is_err());
+
+        locked_app.server_version = 173;
+        locked_app.request_wsh_event_data(request_id, &wsh_event_data)?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+
+        assert_eq!(
+            OutgoingMessageIds::ReqWshEventData as u8,
+            fields[0].parse::<u8>().unwrap()
+        );
+        assert_eq!(request_id, fields[1].parse::<i32>().unwrap());
+        assert_eq!("8314", fields[2]);
+        assert_eq!("", fields[3]);
+        assert_eq!("0", fields[4]);
+        assert_eq!("20230102", fields[7]);
+        assert_eq!("", fields[8]);
+        assert_eq!("10", fields[9]);
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_request_contract_details() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -854,10 +1019,10 @@ mod tests {
         assert_eq!(contract.con_id, fields[3].parse::<i32>().unwrap()); // srv v37 and above
         assert_eq!(contract.symbol, fields[4]);
 
-        assert_eq!(contract.sec_type, fields[5]);
+        assert_eq!(contract.sec_type.to_string(), fields[5]);
         assert_eq!(contract.last_trade_date_or_contract_month, fields[6]);
         assert_eq!(contract.strike, fields[7].parse::<f64>().unwrap());
-        assert_eq!(contract.right, fields[8]);
+        assert_eq!(contract.right.to_string(), fields[8]);
         assert_eq!(contract.multiplier, fields[9]); // srv v15 and above
 
         assert_eq!(contract.exchange, fields[10]);
@@ -880,7 +1045,7 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_current_time() -> Result<(), IBKRApiLibError> {
+    fn test_request_current_time() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -912,7 +1077,7 @@ mod tests {
 
     //------------------------------------------------------------------------------------------------
     #[test]
-    fn test_request_executions() -> Result<(), IBKRApiLibError> {
+    fn test_request_executions() -> Result<(), IbkrError> {
         let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
         let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
 
@@ -939,7 +1104,7 @@ mod tests {
             acct_code.to_string(),
             time.to_string(),
             symbol.to_string(),
-            sec_type.to_string(),
+            sec_type.into(),
             exchange.to_string(),
             side.to_string(),
         );
@@ -972,4 +1137,1260 @@ mod tests {
 
         Ok(())
     }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_place_delta_neutral_order() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+
+        let contract =
+            Contract::option("IBM", "20250620", 150.0, Right::Call).delta_neutral(8314, 0.5, 75.0);
+        assert_eq!(
+            Some(DeltaNeutralContract::new(8314, 0.5, 75.0)),
+            contract.delta_neutral_contract
+        );
+        let mut order = Order::volatility_order("", "BUY", 1.0, 40.0, 2);
+        order.delta_neutral_order_type = "MKT".to_string();
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.place_order(1, &contract, &order)?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert_eq!(
+            OutgoingMessageIds::PlaceOrder as u8,
+            fields[0].parse::<u8>().unwrap()
+        );
+        assert!(fields
+            .windows(4)
+            .any(|window| window == ["1", "8314", "0.5", "75"]));
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_warrant_contract_details() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let (_sender, receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            wrapper,
+            receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams.clone(),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+        let details = streams.lock().unwrap().contract_details.add(7);
+
+        let fields: Vec<String> = [
+            "10", "8", "7", "DTE", "WAR", "20251219", "20", "C", "FWB", "EUR", "DE000VU1ABC2",
+            "DTE", "DTE", "512345678", "0.001", "1", "0.1", "LMT,MKT", "FWB,SMART", "1", "12087817",
+            "DEUTSCHE TELEKOM AG CALL VONTOBEL", "FWB", "202512", "", "", "", "MET", "", "", "", "0",
+            "1", "ISIN", "DE000VU1ABC2", "1", "DTE", "STK", "", "20251219",
+        ]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+        decoder.interpret(&fields)?;
+
+        let warrant = match details.try_recv().unwrap() {
+            ContractDetailsEvent::Details(details) => details,
+            event => panic!("unexpected event {:?}", event),
+        };
+        assert_eq!(SecType::Warrant, warrant.contract.sec_type);
+        assert_eq!(20.0, warrant.contract.strike);
+        assert_eq!(Right::Call, warrant.contract.right);
+        assert_eq!("0.1", warrant.contract.multiplier);
+        assert_eq!("DE000VU1ABC2", warrant.contract.local_symbol);
+        assert_eq!(12087817, warrant.under_con_id);
+        assert_eq!("DEUTSCHE TELEKOM AG CALL VONTOBEL", warrant.long_name);
+        assert_eq!("DE000VU1ABC2", warrant.sec_id_list[0].value);
+
+        let query = Contract::warrant("DTE", "20251219", 20.0, Right::Call, "FWB", "EUR");
+        assert_eq!(SecType::Warrant, query.sec_type);
+        assert_eq!(
+            SecType::StructuredProduct,
+            Contract::structured_product("DE000VU1ABC2", "FWB", "EUR").sec_type
+        );
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_contract_details_size_rules() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let (_sender, receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            receiver,
+            173,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams.clone(),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+        let details = streams.lock().unwrap().contract_details.add(7);
+
+        // No version and no md size multiplier, but stock type and size rules
+        let fields: Vec<String> = [
+            "10", "7", "NESN", "STK", "20251219", "0", "", "SMART", "CHF", "NESN", "EBS",
+            "NESN", "38709539", "0.01", "", "LMT,MKT", "SMART,EBS", "1", "0",
+            "NESTL\\u00c9 SA-REG", "EBS", "", "Consumer", "Food", "Food", "MET", "", "", "", "",
+            "1", "ISIN", "CH0038863350", "1", "", "", "", "", "COMMON", "1", "1", "100",
+        ]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+        decoder.interpret(&fields)?;
+
+        let details = match details.try_recv().unwrap() {
+            ContractDetailsEvent::Details(details) => details,
+            event => panic!("unexpected event {:?}", event),
+        };
+        assert_eq!(38709539, details.contract.con_id);
+        assert_eq!("NESTL\u{c9} SA-REG", details.long_name);
+        assert_eq!("EBS", details.contract.primary_exchange);
+        assert_eq!("CH0038863350", details.sec_id_list[0].value);
+        assert_eq!("COMMON", details.stock_type);
+        assert_eq!(1.0, details.min_size);
+        assert_eq!(1.0, details.size_increment);
+        assert_eq!(100.0, details.suggested_size_increment);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_portfolio_value() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let (_sender, receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams.clone(),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+        let events = streams.lock().unwrap().account_updates.add(NO_VALID_ID);
+
+        let fields: Vec<String> = [
+            "7", "8", "265598", "AAPL", "STK", "", "0", "", "", "NASDAQ", "USD", "AAPL", "NMS",
+            "0.3", "190.1", "57.03", "150.123456789", "11.99", "0", "DU1",
+        ]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+        decoder.interpret(&fields)?;
+        let fields: Vec<String> = ["54", "1", "DU1"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        decoder.interpret(&fields)?;
+
+        let mut updates = AccountUpdates::new();
+        assert!(updates.apply(events.try_recv().unwrap()).is_none());
+        let snapshot = updates
+            .apply(events.try_recv().unwrap())
+            .unwrap()
+            .clone();
+        let aapl = snapshot.position(265598).unwrap();
+        assert_eq!(BigDecimal::from_str("0.3").unwrap(), aapl.position);
+        assert_eq!(
+            BigDecimal::from_str("150.123456789").unwrap(),
+            aapl.average_cost
+        );
+        assert_eq!(190.1, aapl.market_price);
+        assert_eq!("NMS", aapl.contract.trading_class);
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_stream_positions_once() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        let positions = locked_app.stream_positions()?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert_eq!(
+            OutgoingMessageIds::ReqPositions as u8,
+            fields[0].parse::<u8>().unwrap()
+        );
+
+        // A second subscription would be ended by the cancel of the first
+        assert!(locked_app.stream_positions().is_err());
+        drop(positions);
+        assert!(locked_app.stream_positions().is_ok());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_book_metrics() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let (_sender, receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams.clone(),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+        let metrics = {
+            let mut streams = streams.lock().unwrap();
+            streams
+                .order_books
+                .insert(4, (OrderBook::new(), BookMetricsConfig::default()));
+            streams.book_metrics.add(4)
+        };
+
+        for fields in [
+            ["13", "1", "4", "0", "ARCA", "0", "1", "100.0", "300", "1"],
+            ["13", "1", "4", "0", "NSDQ", "0", "0", "100.02", "100", "1"],
+            // Rows of other requests are not part of the book
+            ["13", "1", "5", "0", "NSDQ", "0", "0", "100.01", "100", "1"],
+        ] {
+            decoder.interpret(&fields)?;
+        }
+
+        assert_eq!(None, metrics.try_recv().unwrap().best_ask);
+        let last = metrics.try_recv().unwrap();
+        assert_eq!(Some(100.02), last.best_ask);
+        assert_eq!(Some(0.5), last.imbalance);
+        assert!(metrics.try_recv().is_err());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_pnl_single() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let (_sender, receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams.clone(),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+        let events = streams.lock().unwrap().pnl_single.add(9);
+
+        let fields: Vec<String> = ["95", "9", "0.5", "1.7976931348623157E308", "12.5", "0", "95.25"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        decoder.interpret(&fields)?;
+
+        let pnl = match events.try_recv().unwrap() {
+            PnlSingleEvent::Update(pnl) => pnl,
+            event => panic!("unexpected event {:?}", event),
+        };
+        assert_eq!(BigDecimal::from_str("0.5").unwrap(), pnl.position);
+        assert_eq!(None, pnl.daily);
+        assert_eq!(Some(12.5), pnl.unrealized);
+        assert_eq!(Some(95.25), pnl.value);
+
+        // Errors end the subscription
+        let fields: Vec<String> = ["4", "2", "9", "321", "Invalid account"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        decoder.interpret(&fields)?;
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            PnlSingleEvent::Error { code: 321, .. }
+        ));
+        assert!(events.try_recv().is_err());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_kill_switch() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let order = Order::limit_order("DU1", "BUY", 100.0, 150.0);
+        locked_app.place_order(7, &contract, &order)?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert_eq!(Some("PendingSubmit".to_string()), locked_app.order_status(7));
+        assert_eq!(vec![7], locked_app.working_orders());
+
+        buf.clear();
+        let report = locked_app.kill_switch(None, Duration::from_millis(20))?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert_eq!(
+            OutgoingMessageIds::ReqGlobalCancel as u8,
+            fields[0].parse::<u8>().unwrap()
+        );
+        // No order status arrives in the test
+        assert!(!report.is_confirmed());
+        assert_eq!(vec![7], report.unconfirmed);
+
+        assert!(locked_app.is_kill_switch_engaged());
+        assert!(locked_app.place_order(8, &contract, &order).is_err());
+        locked_app.rearm();
+        assert!(locked_app.place_order(8, &contract, &order).is_ok());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_audit_log() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        let sink = MemoryAuditSink::new();
+        locked_app.set_audit_log(Some(OrderAuditLog::new(Box::new(sink.clone()))));
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let order = Order::limit_order("DU1", "BUY", 100.0, 150.0);
+        locked_app.place_order(7, &contract, &order)?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let place_order_hash = payload_hash(&buf);
+        let place_order_len = buf.len();
+        locked_app.request_account_summary(8, "All", "NetLiquidation")?;
+        locked_app.cancel_order(7)?;
+        locked_app.request_global_cancel()?;
+
+        let records = sink.records();
+        assert_eq!(3, records.len());
+        assert_eq!(vec![1, 2, 3], records.iter().map(|r| r.sequence).collect::<Vec<_>>());
+        assert_eq!(
+            vec!["PlaceOrder", "CancelOrder", "ReqGlobalCancel"],
+            records.iter().map(|r| r.message.as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(OutgoingMessageIds::PlaceOrder as i32, records[0].message_id);
+        assert_eq!(Some(7), records[0].order_id);
+        assert_eq!(place_order_hash, records[0].payload_hash);
+        assert_eq!(place_order_len, records[0].payload_len);
+        assert_eq!(None, records[2].order_id);
+        assert_eq!(3, locked_app.audit_log().unwrap().last_sequence());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_audit_log_failure_blocks_message() -> Result<(), IbkrError> {
+        struct FailingSink;
+
+        impl AuditSink for FailingSink {
+            fn append(&mut self, _record: &AuditRecord) -> Result<(), IbkrError> {
+                Err(std::io::Error::other("disk full").into())
+            }
+        }
+
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.set_audit_log(Some(OrderAuditLog::new(Box::new(FailingSink))));
+        assert!(locked_app.cancel_order(7).is_err());
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        assert!(buf.is_empty());
+        assert_eq!(0, locked_app.audit_log().unwrap().last_sequence());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_default_account() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.accounts.lock().unwrap().update("DU1,DU2");
+        assert_eq!(vec!["DU1", "DU2"], locked_app.accounts());
+        assert!(locked_app.set_default_account("DU9").is_err());
+        locked_app.set_default_account("DU2")?;
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let mut order = Order::limit_order("", "BUY", 100.0, 150.0);
+        locked_app.place_order(7, &contract, &order)?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert!(fields.iter().any(|field| field == "DU2"));
+
+        order.account = "DU9".to_string();
+        assert!(locked_app.place_order(8, &contract, &order).is_err());
+        assert!(locked_app
+            .request_positions_multi(9, "DU9", "")
+            .is_err());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_risk_checks() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.set_risk_checks(Some(
+            RiskChecks::new().add(MaxOrderNotional { limit: 10_000.0 }, RiskAction::Block),
+        ));
+
+        let mut contract = Contract::stock("AAPL", "SMART", "USD");
+        contract.con_id = 265598;
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+        match locked_app.place_order(7, &contract, &order) {
+            Err(IbkrError::Rejected { request_id, .. }) => assert_eq!(7, request_id),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        // Market orders are valued at the quote of the contract
+        let order = Order::market_order("", "BUY", 100.0);
+        assert!(locked_app.place_order(8, &contract, &order).is_err());
+        {
+            let mut quotes = locked_app.quotes.lock().unwrap();
+            quotes.add_ticker(1, Some(265598));
+            quotes.update_tick_price(1, TickType::Last, 99.0);
+        }
+        locked_app.place_order(9, &contract, &order)?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert_eq!("9", fields[1]);
+
+        locked_app.set_risk_checks(None);
+        locked_app.place_order(10, &contract, &Order::limit_order("", "BUY", 100.0, 150.0))?;
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_order_throttle() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.set_order_throttle(Some(
+            OrderThrottle::new()
+                .max_per_minute(1)
+                .mode(ThrottleMode::Queue),
+        ));
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+        locked_app.place_order(7, &contract, &order)?;
+        locked_app.place_order(8, &contract, &order)?;
+        locked_app.place_order(9, &contract, &order)?;
+        assert_eq!(
+            vec![8, 9],
+            locked_app.order_throttle().unwrap().queued()
+        );
+        // A queued order is cancelled without a message
+        locked_app.cancel_order(8)?;
+        assert!(locked_app.release_throttled_orders()?.is_empty());
+
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let (_, msg, rest) = read_msg(buf.as_slice())?;
+        assert_eq!("7", read_fields(&msg)[1]);
+        assert!(rest.is_empty());
+
+        locked_app.request_global_cancel()?;
+        assert!(locked_app.order_throttle().unwrap().queued().is_empty());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_duplicate_ref_policy() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        assert_eq!(DuplicateRefPolicy::Allow, locked_app.duplicate_ref_policy());
+        locked_app.set_duplicate_ref_policy(DuplicateRefPolicy::Reject);
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let mut order = Order::limit_order("", "BUY", 100.0, 150.0);
+        order.order_ref = "entry-1".to_string();
+        locked_app.place_order(7, &contract, &order)?;
+        assert_eq!(Some(7), locked_app.working_order_by_ref("entry-1"));
+        // Placing the same order id again modifies it
+        locked_app.place_order(7, &contract, &order)?;
+        assert!(matches!(
+            locked_app.place_order(8, &contract, &order),
+            Err(IbkrError::Rejected { request_id: 8, .. })
+        ));
+
+        // A resubmission modifies the working order
+        locked_app.set_duplicate_ref_policy(DuplicateRefPolicy::Modify);
+        order.lmt_price = 151.0;
+        locked_app.place_order(9, &contract, &order)?;
+        assert_eq!(vec![7], locked_app.working_orders());
+
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let mut rest = buf;
+        for _ in 0..3 {
+            let (_, msg, next) = read_msg(&rest)?;
+            assert_eq!("7", read_fields(&msg)[1]);
+            rest = next;
+        }
+        assert!(rest.is_empty());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_order_journal() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let path = std::env::temp_dir().join(format!(
+            "twsapi-client-order-journal-{}.bin",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        assert_eq!(None, locked_app.order_journal_sequence());
+        locked_app.set_order_journal(Some(OrderJournal::open(&path)?));
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+        locked_app.place_order(7, &contract, &order)?;
+        locked_app.cancel_order(7)?;
+        // What-if orders are not journaled
+        let mut what_if = order.clone();
+        what_if.what_if = true;
+        locked_app.place_order(8, &contract, &what_if)?;
+        locked_app.request_global_cancel()?;
+        assert_eq!(Some(3), locked_app.order_journal_sequence());
+        locked_app.set_order_journal(None);
+
+        let entries: Vec<JournalEntry> = read_journal(&path)?
+            .into_iter()
+            .map(|record| record.entry)
+            .collect();
+        assert!(matches!(&entries[0], JournalEntry::Intent(intent) if intent.order_id == 7));
+        assert_eq!(JournalEntry::Cancel { order_id: 7 }, entries[1]);
+        assert_eq!(JournalEntry::GlobalCancel, entries[2]);
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_duplicate_ref_policy_queued() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.set_duplicate_ref_policy(DuplicateRefPolicy::Reject);
+        locked_app.set_order_throttle(Some(
+            OrderThrottle::new()
+                .max_per_minute(1)
+                .mode(ThrottleMode::Queue),
+        ));
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+        locked_app.place_order(7, &contract, &order)?;
+        let mut retried = order.clone();
+        retried.order_ref = "entry-1".to_string();
+        locked_app.place_order(8, &contract, &retried)?;
+        assert_eq!(None, locked_app.working_order_by_ref("entry-1"));
+        // The retry of an order held back by the throttle is not sent twice
+        assert!(matches!(
+            locked_app.place_order(9, &contract, &retried),
+            Err(IbkrError::Rejected { request_id: 9, .. })
+        ));
+
+        // Or replaces the queued order
+        locked_app.set_duplicate_ref_policy(DuplicateRefPolicy::Modify);
+        retried.lmt_price = 151.0;
+        locked_app.place_order(9, &contract, &retried)?;
+        assert_eq!(
+            vec![8],
+            locked_app.order_throttle().unwrap().queued()
+        );
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_session_gate() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.set_session_gate(Some(SessionGate::new().mode(SessionGateMode::Queue)));
+
+        // Without sessions the contract is never open
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+        assert!(matches!(
+            locked_app.place_order(7, &contract, &order),
+            Err(IbkrError::Rejected { request_id: 7, .. })
+        ));
+        // Orders before the session opens are queued until it does
+        let opens = Utc::now().with_timezone(&Tz::UTC) + chrono::Duration::hours(1);
+        locked_app.session_gate_mut().unwrap().set_sessions(
+            contract.con_id,
+            vec![Session::new(opens, opens + chrono::Duration::hours(1))],
+        );
+        locked_app.place_order(7, &contract, &order)?;
+        assert_eq!(vec![7], locked_app.session_gate().unwrap().queued());
+        assert!(locked_app.release_session_orders()?.is_empty());
+        locked_app.cancel_order(7)?;
+        assert!(locked_app.session_gate().unwrap().queued().is_empty());
+
+        let mut outside_rth = order.clone();
+        outside_rth.outside_rth = true;
+        locked_app.place_order(8, &contract, &outside_rth)?;
+        assert!(locked_app.release_session_orders()?.is_empty());
+
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let (_, msg, rest) = read_msg(buf.as_slice())?;
+        assert_eq!("8", read_fields(&msg)[1]);
+        assert!(rest.is_empty());
+
+        // Released orders that cannot be sent go back on the queue
+        locked_app.place_order(9, &contract, &order)?;
+        locked_app.place_order(10, &contract, &order)?;
+        let now = Utc::now().with_timezone(&Tz::UTC);
+        locked_app.session_gate_mut().unwrap().set_sessions(
+            contract.con_id,
+            vec![Session::new(
+                now - chrono::Duration::hours(1),
+                now + chrono::Duration::hours(1),
+            )],
+        );
+        *locked_app.conn_state.lock().unwrap() = ConnStatus::DISCONNECTED;
+        assert!(locked_app.release_session_orders().is_err());
+        assert_eq!(vec![9, 10], locked_app.session_gate().unwrap().queued());
+        *locked_app.conn_state.lock().unwrap() = ConnStatus::CONNECTED;
+        assert_eq!(vec![9, 10], locked_app.release_session_orders()?);
+        assert!(locked_app.session_gate().unwrap().queued().is_empty());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_arrival_quotes() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        locked_app.set_arrival_quotes(Some(ArrivalQuotes::new()));
+        {
+            let mut quotes = locked_app.quotes.lock().unwrap();
+            quotes.add_ticker(1, Some(265598));
+            quotes.update_tick_price(1, TickType::Bid, 99.9);
+            quotes.update_tick_price(1, TickType::Ask, 100.1);
+        }
+
+        let mut contract = Contract::stock("AAPL", "SMART", "USD");
+        contract.con_id = 265598;
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+        locked_app.place_order(7, &contract, &order)?;
+        // What-if orders and contracts without quotes have no arrival quote
+        let mut what_if = order.clone();
+        what_if.what_if = true;
+        locked_app.place_order(8, &contract, &what_if)?;
+        locked_app.place_order(9, &Contract::stock("MSFT", "SMART", "USD"), &order)?;
+
+        let arrival_quotes = locked_app.arrival_quotes().unwrap();
+        assert_eq!(1, arrival_quotes.len());
+        let arrival = arrival_quotes.get(7).unwrap();
+        assert_eq!(265598, arrival.con_id);
+        assert_eq!(Some(100.0), arrival.mid().map(|mid| (mid * 100.0).round() / 100.0));
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_trading_mode() -> Result<(), IbkrError> {
+        let wrapper = Arc::new(Mutex::new(DummyTestWrapper::new()));
+        let app = Arc::new(Mutex::new(EClient::<DummyTestWrapper>::new(wrapper)));
+        let mut buf = Vec::<u8>::new();
+
+        let mut locked_app = app.lock().expect("EClient mutex was poisoned");
+
+        locked_app.connect_test();
+        assert_eq!(TradingMode::Unknown, locked_app.trading_mode());
+        locked_app.accounts.lock().unwrap().update("DU1,DU2");
+        assert_eq!(TradingMode::Paper, locked_app.trading_mode());
+        locked_app.check_trading_mode(TradingMode::Paper)?;
+        assert!(locked_app.check_trading_mode(TradingMode::Live).is_err());
+
+        // Paper sessions ask for delayed data
+        locked_app.apply_trading_mode_defaults()?;
+        locked_app.stream.as_mut().unwrap().read_to_end(&mut buf)?;
+        let msg_data = read_msg(buf.as_slice())?;
+        let fields = read_fields(&msg_data.1);
+        assert_eq!(
+            OutgoingMessageIds::ReqMarketDataType as i32,
+            fields[0].parse::<i32>().unwrap()
+        );
+        assert_eq!("3", fields[2]);
+
+        locked_app.set_trading_mode(Some(TradingMode::Live));
+        assert_eq!(TradingMode::Live, locked_app.trading_mode());
+        locked_app.set_trading_mode(None);
+        assert_eq!(TradingMode::Paper, locked_app.trading_mode());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_account_update_multi() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let receiver = streams.lock().unwrap().account_updates_multi.add(5);
+        let (_sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams,
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+
+        let fields: Vec<String> = [
+            "73",
+            "1",
+            "5",
+            "DU1",
+            "GROWTH",
+            "NetLiquidation",
+            "1000.5",
+            "USD",
+        ]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+        decoder.interpret(&fields)?;
+        decoder.interpret(&["74".to_string(), "1".to_string(), "5".to_string()])?;
+
+        match receiver.try_recv() {
+            Ok(AccountUpdateMultiEvent::Value(value)) => {
+                assert_eq!("GROWTH", value.model_code);
+                assert_eq!(Some(1000.5), value.value.value);
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(AccountUpdateMultiEvent::End)
+        ));
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(Some(150.25), parse_number::<f64>("150.25"));
+        assert_eq!(Some(f64::MAX), parse_number::<f64>("1.7976931348623157E308"));
+        assert_eq!(Some(f64::INFINITY), parse_number::<f64>("inf"));
+        assert_eq!(Some(-3), parse_number::<i32>("-3"));
+        assert_eq!(Some(1672531200), parse_number::<i64>("1672531200"));
+        assert_eq!(None, parse_number::<i32>(""));
+        assert_eq!(None, parse_number::<i32>("1.5"));
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_size() -> Result<(), IbkrError> {
+        let fields = [
+            "100", "0.5", "", "2147483647", "9223372036854775807", "1.7976931348623157E308",
+        ];
+        let mut fields_iter = fields.iter();
+        assert_eq!(100.0, decode_size(&mut fields_iter)?);
+        assert_eq!(0.5, decode_size(&mut fields_iter)?);
+        assert_eq!(0.0, decode_size(&mut fields_iter)?);
+        for _ in 0..3 {
+            assert_eq!(UNSET_DOUBLE, decode_size(&mut fields_iter)?);
+        }
+        assert!(decode_size(&mut ["1,5"].iter()).is_err());
+
+        // The callbacks get sizes with the digits they were sent with
+        let size = decode_size(&mut ["0.1"].iter())?;
+        assert_eq!(BigDecimal::from_str("0.1").unwrap(), size_to_decimal(size));
+        assert_eq!(BigDecimal::from(100), size_to_decimal(100.0));
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_unescape_unicode() {
+        assert_eq!("Soci\u{e9}t\u{e9}", unescape_unicode("Soci\\u00e9t\\u00e9"));
+        assert_eq!("plain text", unescape_unicode("plain text"));
+        assert_eq!("\\u12", unescape_unicode("\\u12"));
+        assert_eq!("\\uzzzz", unescape_unicode("\\uzzzz"));
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_soft_dollar_tiers() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let receiver = streams.lock().unwrap().soft_dollar_tiers.add(3);
+        let (_sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams,
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+
+        let fields: Vec<String> = [
+            "77", "3", "2", "TIER1", "0.01", "Tier 1", "TIER2", "0.02", "Tier 2",
+        ]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+        decoder.interpret(&fields)?;
+
+        assert_eq!(
+            vec![
+                SoftDollarTier::new(
+                    "TIER1".to_string(),
+                    "0.01".to_string(),
+                    "Tier 1".to_string()
+                ),
+                SoftDollarTier::new(
+                    "TIER2".to_string(),
+                    "0.02".to_string(),
+                    "Tier 2".to_string()
+                ),
+            ],
+            receiver.try_recv().unwrap()
+        );
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_user_info() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let receiver = streams.lock().unwrap().user_info.add(4);
+        let (_sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            166,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams,
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+
+        decoder.interpret(&["107".to_string(), "4".to_string(), "WB123".to_string()])?;
+        assert_eq!("WB123", receiver.try_recv().unwrap());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_errors() -> Result<(), IbkrError> {
+        let (_sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            Arc::new(Mutex::new(StreamRegistry::new())),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+        let decode_error = |result: Result<(), IbkrError>| match result {
+            Err(IbkrError::Decode(err)) => err,
+            _ => panic!("expected a decode error"),
+        };
+
+        // A truncated tick price
+        let err = decode_error(decoder.interpret(&["1", "6", "3", "1"]));
+        assert_eq!(1, err.msg_type);
+        assert_eq!(4, err.field);
+        assert_eq!(DecodeErrorReason::MissingField, err.reason);
+        assert_eq!("1\u{0}6\u{0}3\u{0}1\u{0}", err.raw_frame);
+
+        let err = decode_error(decoder.interpret(&["1", "6", "x3", "1", "1.5", "100", "0"]));
+        assert_eq!(2, err.field);
+        assert_eq!(DecodeErrorReason::InvalidNumber("x3".to_string()), err.reason);
+
+        let err = decode_error(decoder.interpret(&["1", "6", "3", "9999", "1.5", "100", "0"]));
+        assert_eq!(3, err.field);
+        assert_eq!(DecodeErrorReason::UnknownValue("9999".to_string()), err.reason);
+
+        let err = decode_error(decoder.interpret(&["999", "1"]));
+        assert_eq!(999, err.msg_type);
+        assert_eq!(0, err.field);
+        assert_eq!(DecodeErrorReason::UnknownMessageId, err.reason);
+
+        let err = decode_error(decoder.interpret(&["abc"]));
+        assert_eq!(NO_VALID_ID, err.msg_type);
+        assert_eq!(DecodeErrorReason::InvalidNumber("abc".to_string()), err.reason);
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_counts() -> Result<(), IbkrError> {
+        let (_sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            Arc::new(Mutex::new(StreamRegistry::new())),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+
+        // Each entry of a list is decoded, so a short list is missing fields
+        decoder.interpret(&["78", "2", "DU1", "F1", "DU2", "F2"])?;
+        assert!(matches!(
+            decoder.interpret(&["78", "2", "DU1", "F1"]),
+            Err(IbkrError::Decode(err)) if err.reason == DecodeErrorReason::MissingField
+        ));
+        decoder.interpret(&["89", "5", "2", "150.25", "100", "150.5", "200"])?;
+        // Counts do not pre-allocate beyond the fields sent
+        assert!(decoder.interpret(&["93", "26", "2147483647", "0", "0.01"]).is_err());
+
+        // Negative counts are empty lists
+        decoder.interpret(&["78", "-1"])?;
+        decoder.interpret(&["89", "5", "-1"])?;
+        decoder.interpret(&["93", "26", "-1"])?;
+        decoder.interpret(&["82", "7", "-1"])?;
+        decoder.interpret(&["85", "-1"])?;
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_error_keeps_running() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let receiver = streams.lock().unwrap().user_info.add(4);
+        let (sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            166,
+            Arc::new(Mutex::new(ConnStatus::DISCONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams,
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+
+        sender.send(Ok("999\u{0}1\u{0}".to_string())).unwrap();
+        sender.send(Ok("1\u{0}6\u{0}".to_string())).unwrap();
+        // A message the reader could not read
+        sender
+            .send(Err(DecodeError::new(10, 3, DecodeErrorReason::InvalidUtf8, "")))
+            .unwrap();
+        sender.send(Ok("107\u{0}4\u{0}WB123\u{0}".to_string())).unwrap();
+        drop(sender);
+        decoder.run()?;
+        assert_eq!("WB123", receiver.try_recv().unwrap());
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_what_if_error() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let receiver = streams.lock().unwrap().what_if.add(7);
+        let (_sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams.clone(),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(None)),
+        );
+
+        let fields: Vec<String> = ["4", "2", "7", "201", "Order rejected"]
+            .iter()
+            .map(|field| field.to_string())
+            .collect();
+        decoder.interpret(&fields)?;
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(WhatIfEvent::Error { code: 201, .. })
+        ));
+        assert!(!streams.lock().unwrap().what_if.contains(7));
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_alert_hooks() -> Result<(), IbkrError> {
+        let streams = Arc::new(Mutex::new(StreamRegistry::new()));
+        let _rejected = streams.lock().unwrap().what_if.add(7);
+        let _unknown = streams.lock().unwrap().what_if.add(8);
+        let _values = streams.lock().unwrap().account_updates.add(NO_VALID_ID);
+        let alerted = Arc::new(Mutex::new(Vec::new()));
+        let sink = alerted.clone();
+        let alerts = AlertHooks::new()
+            .watch(AccountThreshold::new(
+                AccountValueKey::ExcessLiquidity,
+                "USD",
+                ThresholdSide::Below,
+                10_000.0,
+            ))
+            .hook(
+                &[AlertCondition::OrderRejected, AlertCondition::MarginBreached],
+                move |event: &AlertEvent| sink.lock().unwrap().push(event.to_string()),
+            );
+        let (_sender, msg_receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            msg_receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            streams,
+            Arc::new(Mutex::new(QuoteCache::new())),
+            Arc::new(Mutex::new(OrderTracker::new())),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(Some(alerts))),
+        );
+
+        // The hooks are called for errors that end a request too
+        for fields in [
+            vec!["4", "2", "7", "201", "Order rejected"],
+            vec!["4", "2", "8", "200", "No security definition"],
+            vec!["6", "2", "ExcessLiquidity", "9000", "USD", "DU1"],
+            vec!["6", "2", "ExcessLiquidity", "8000", "USD", "DU1"],
+        ] {
+            let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+            decoder.interpret(&fields)?;
+        }
+
+        let alerted = alerted.lock().unwrap();
+        assert_eq!(2, alerted.len());
+        assert_eq!("order 7 rejected (201): Order rejected", alerted[0]);
+        assert!(alerted[1].contains("Breached"));
+
+        Ok(())
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_decode_order_status() -> Result<(), IbkrError> {
+        let orders = Arc::new(Mutex::new(OrderTracker::new()));
+        let path = std::env::temp_dir().join(format!(
+            "twsapi-decoder-order-journal-{}.bin",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let journal = Arc::new(Mutex::new(Some(OrderJournal::open(&path)?)));
+        let (_sender, receiver) = mpsc::channel();
+        let mut decoder = Decoder::new(
+            Arc::new(Mutex::new(DummyTestWrapper::new())),
+            receiver,
+            151,
+            Arc::new(Mutex::new(ConnStatus::CONNECTED)),
+            Arc::new(Mutex::new(TickConflator::new())),
+            Arc::new(Mutex::new(SmartComponentRegistry::new())),
+            Arc::new(Mutex::new(MarketRuleCache::new())),
+            RequestSender::new(None),
+            Arc::new(Mutex::new(StreamRegistry::new())),
+            Arc::new(Mutex::new(QuoteCache::new())),
+            orders.clone(),
+            Arc::new(Mutex::new(ManagedAccounts::new())),
+            journal.clone(),
+            Arc::new(Mutex::new(None)),
+        );
+        orders.lock().unwrap().track(7);
+
+        let fields: Vec<String> = [
+            "3", "7", "Cancelled", "0", "100", "0", "12345", "0", "0", "1", "", "0",
+        ]
+        .iter()
+        .map(|field| field.to_string())
+        .collect();
+        decoder.interpret(&fields)?;
+
+        assert_eq!(Some("Cancelled"), orders.lock().unwrap().status(7));
+        assert!(orders.lock().unwrap().working_orders().is_empty());
+
+        // The status is journaled
+        drop(journal);
+        drop(decoder);
+        let records = read_journal(&path)?;
+        assert!(matches!(
+            &records[0].entry,
+            JournalEntry::Status { order_id: 7, status, .. } if status == "Cancelled"
+        ));
+        std::fs::remove_file(&path)?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::error_codes::{
+        is_warning, lookup, ErrorCategory, ERROR_CODES, MARKET_DATA_FARM_OK, NO_SECURITY_DEFINITION,
+    };
+    use crate::twsapi::errors::{ConnectionError, IbkrError, ServerError, TwsError};
+    use crate::twsapi::messages::make_message;
+
+    #[test]
+    fn test_catalog() {
+        assert!(ERROR_CODES
+            .windows(2)
+            .all(|codes| codes[0].code < codes[1].code));
+
+        let info = lookup(NO_SECURITY_DEFINITION).unwrap();
+        assert_eq!(200, info.code);
+        assert_eq!("NO_SECURITY_DEFINITION", info.name);
+        assert_eq!(ErrorCategory::Contract, info.category);
+        assert!(lookup(99999).is_none());
+
+        assert!(is_warning(MARKET_DATA_FARM_OK));
+        assert!(!is_warning(NO_SECURITY_DEFINITION));
+    }
+
+    #[test]
+    fn test_ibkr_error() {
+        let err = IbkrError::Server(ServerError::new(7, 200, "No security definition"));
+        assert_eq!(Some(7), err.request_id());
+        assert_eq!(Some(200), err.code());
+        if let IbkrError::Server(err) = &err {
+            assert_eq!(ErrorCategory::Contract, err.info().unwrap().category);
+        }
+
+        let err = IbkrError::Connection(ConnectionError::new(
+            -1,
+            TwsError::UpdateTws,
+            "It does not support PnL request.",
+        ));
+        assert_eq!(None, err.request_id());
+        assert_eq!(Some(503), err.code());
+        assert_eq!(
+            "Connection error: The TWS is out of date and must be upgraded. It does not support \
+             PnL request.",
+            err.to_string()
+        );
+
+        let err = IbkrError::timeout(3, "the positions");
+        assert_eq!(Some(3), err.request_id());
+        assert_eq!(None, err.code());
+        assert_eq!(
+            "Request 3 timed out waiting for the positions",
+            err.to_string()
+        );
+
+        assert!(matches!(
+            make_message("caf\u{e9}\u{0}"),
+            Err(IbkrError::Encode(_))
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, NaiveDate, TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use crate::twsapi::common::Action;
+    use crate::twsapi::contract::SecType;
+    use crate::twsapi::execution::ExecutionFilter;
+
+    #[test]
+    fn test_execution_filter_builder() {
+        let filter = ExecutionFilter::builder()
+            .client_id(3)
+            .account("DU1")
+            .symbol("ES")
+            .sec_type(SecType::Future)
+            .exchange("CME")
+            .side(Action::Sshort)
+            .since(
+                &Tz::US__Eastern
+                    .with_ymd_and_hms(2023, 7, 3, 9, 30, 0)
+                    .unwrap(),
+            )
+            .build();
+        assert_eq!(3, filter.client_id);
+        assert_eq!("DU1", filter.acct_code);
+        assert_eq!("ES", filter.symbol);
+        assert_eq!("FUT", filter.sec_type.to_string());
+        assert_eq!("CME", filter.exchange);
+        assert_eq!("SELL", filter.side);
+        assert_eq!("20230703-13:30:00", filter.time);
+
+        let filter = ExecutionFilter::builder()
+            .since_date(
+                NaiveDate::from_ymd_opt(2023, 1, 3).unwrap(),
+                &Tz::Europe__Berlin,
+            )
+            .build();
+        assert_eq!("20230102-23:00:00", filter.time);
+
+        // An empty filter matches all executions
+        let filter = ExecutionFilter::builder().build();
+        assert_eq!(0, filter.client_id);
+        assert!(filter.time.is_empty() && filter.side.is_empty());
+        assert_eq!("", filter.sec_type.to_string());
+
+        let midnight = Local
+            .from_local_datetime(&Local::now().date_naive().and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+            .unwrap()
+            .with_timezone(&Utc);
+        let filter = ExecutionFilter::builder().today().build();
+        assert_eq!(midnight.format("%Y%m%d-%H:%M:%S").to_string(), filter.time);
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, Utc};
+
+    use crate::twsapi::common::BarData;
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::execution_quality::{
+        bars_vwap, ArrivalQuote, ArrivalQuotes, ExecutionQuality,
+    };
+
+    fn arrival(order_id: i32, bid: f64, ask: f64) -> ArrivalQuote {
+        ArrivalQuote {
+            order_id,
+            con_id: 265598,
+            time: Utc::now(),
+            bid: Some(bid),
+            ask: Some(ask),
+        }
+    }
+
+    fn execution(
+        exec_id: &str,
+        order_id: i32,
+        time: &str,
+        side: &str,
+        shares: f64,
+        price: f64,
+    ) -> Execution {
+        Execution {
+            exec_id: exec_id.to_string(),
+            order_id,
+            time: time.to_string(),
+            side: side.to_string(),
+            shares,
+            price,
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(expected: f64, actual: Option<f64>) {
+        let actual = actual.expect("no value");
+        assert!(
+            (expected - actual).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_arrival_quotes() {
+        let mut arrivals = ArrivalQuotes::new();
+        arrivals.record(arrival(1, 99.9, 100.1));
+        // A modification keeps the first quote
+        arrivals.record(arrival(1, 101.9, 102.1));
+        assert_eq!(1, arrivals.len());
+        assert_close(100.0, arrivals.get(1).unwrap().mid());
+
+        let mut one_sided = arrival(2, 0.0, 50.1);
+        one_sided.bid = None;
+        assert_eq!(None, one_sided.mid());
+    }
+
+    #[test]
+    fn test_bars_vwap() {
+        let bars = vec![
+            BarData {
+                average: 100.0,
+                volume: 300.0,
+                ..Default::default()
+            },
+            BarData {
+                average: 101.0,
+                volume: 100.0,
+                ..Default::default()
+            },
+        ];
+        assert_close(100.25, bars_vwap(&bars));
+        assert_eq!(None, bars_vwap(&[BarData::default()]));
+    }
+
+    #[test]
+    fn test_execution_quality() {
+        let mut arrivals = ArrivalQuotes::new();
+        arrivals.record(arrival(1, 99.9, 100.1));
+        arrivals.record(arrival(2, 49.9, 50.1));
+        let mut quality = ExecutionQuality::new(arrivals);
+        quality.set_vwap(1, 100.05);
+
+        assert!(quality.add_execution(&execution(
+            "a",
+            1,
+            "20240105 10:30:00",
+            "BOT",
+            100.0,
+            100.05
+        )));
+        assert!(!quality.add_execution(&execution(
+            "a",
+            1,
+            "20240105 10:30:00",
+            "BOT",
+            100.0,
+            100.05
+        )));
+        quality.add_execution(&execution(
+            "b",
+            1,
+            "20240105 10:31:00",
+            "BOT",
+            100.0,
+            100.15,
+        ));
+        quality.add_execution(&execution("c", 2, "20240108 09:45:00", "SLD", 200.0, 49.95));
+        // Orders without an arrival quote have no arrival benchmark
+        quality.add_execution(&execution("d", 3, "", "BOT", 10.0, 20.0));
+
+        let fills = quality.fills();
+        assert_eq!(4, fills.len());
+        assert_close(0.05, fills[0].arrival_slippage);
+        assert_close(5.0, fills[0].arrival_slippage_bps);
+        assert_close(0.0, fills[0].vwap_slippage);
+        assert_close(0.1, fills[0].effective_spread);
+        assert_close(0.15, fills[1].arrival_slippage);
+        assert_close(0.3, fills[1].effective_spread);
+        // Selling below the mid is a cost
+        assert_close(0.05, fills[2].arrival_slippage);
+        assert_close(10.0, fills[2].arrival_slippage_bps);
+        assert_eq!(None, fills[2].vwap_slippage);
+        assert_eq!(None, fills[3].arrival_slippage);
+        assert_eq!(None, fills[3].date);
+
+        let orders = quality.orders();
+        assert_eq!(3, orders.len());
+        assert_eq!(2, orders[0].fills);
+        assert_eq!(200.0, orders[0].shares);
+        assert_close(100.1, Some(orders[0].avg_price));
+        assert_close(0.1, orders[0].arrival_slippage);
+        assert_close(10.0, orders[0].arrival_slippage_bps);
+        assert_close(0.05, orders[0].vwap_slippage);
+        assert_close(0.2, orders[0].effective_spread);
+        assert!(!orders[1].buy);
+        assert_eq!(None, orders[2].arrival_slippage_bps);
+
+        let daily = quality.daily();
+        assert_eq!(2, daily.len());
+        assert_eq!(NaiveDate::from_ymd_opt(2024, 1, 5).unwrap(), daily[0].date);
+        assert_eq!(1, daily[0].orders);
+        assert_eq!(2, daily[0].fills);
+        assert_close(20020.0, Some(daily[0].notional));
+        assert_close(20.0, Some(daily[0].arrival_cost));
+        assert_close(10.0, Some(daily[0].vwap_cost));
+        assert_close(20.0, daily[0].effective_spread_bps.map(|bps| bps.round()));
+        assert_close(10.0, Some(daily[1].arrival_cost));
+        assert_eq!(None, daily[1].vwap_slippage_bps);
+    }
+}
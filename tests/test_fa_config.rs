@@ -0,0 +1,102 @@
+#[cfg(all(test, feature = "fa-config"))]
+mod tests {
+    use crate::twsapi::fa_config::{
+        aliases_to_xml, groups_to_xml, parse_aliases, parse_groups, parse_profiles,
+        profiles_to_xml, FaAlias, FaProfileType,
+    };
+
+    const GROUPS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListOfGroups>
+  <Group>
+    <name>Equal_Quantity</name>
+    <ListOfAccts varName="list">
+      <String>DU119915</String>
+      <String>DU119916</String>
+    </ListOfAccts>
+    <defaultMethod>EqualQuantity</defaultMethod>
+  </Group>
+  <Group>
+    <name>Pct_Change</name>
+    <ListOfAccts varName="list">
+      <String>DU119915</String>
+    </ListOfAccts>
+    <defaultMethod>PctChange</defaultMethod>
+  </Group>
+</ListOfGroups>"#;
+
+    const PROFILES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListOfAllocationProfiles>
+  <AllocationProfile>
+    <name>Percent_60_40</name>
+    <type>1</type>
+    <ListOfAllocations varName="listOfAllocations">
+      <Allocation>
+        <acct>DU119915</acct>
+        <amount>60.0</amount>
+      </Allocation>
+      <Allocation>
+        <acct>DU119916</acct>
+        <amount>40.0</amount>
+      </Allocation>
+    </ListOfAllocations>
+  </AllocationProfile>
+  <AllocationProfile>
+    <name>Shares</name>
+    <type>3</type>
+    <ListOfAllocations varName="listOfAllocations">
+      <Allocation>
+        <acct>DU119915</acct>
+        <amount>100.0</amount>
+      </Allocation>
+    </ListOfAllocations>
+  </AllocationProfile>
+</ListOfAllocationProfiles>"#;
+
+    #[test]
+    fn test_fa_groups() -> Result<(), roxmltree::Error> {
+        let mut groups = parse_groups(GROUPS_XML)?;
+        assert_eq!(2, groups.len());
+        assert_eq!("Equal_Quantity", groups[0].name);
+        assert_eq!(vec!["DU119915", "DU119916"], groups[0].accounts);
+        assert_eq!("PctChange", groups[1].default_method);
+
+        assert!(groups[1].add_account("DU119917"));
+        assert!(!groups[1].add_account("DU119917"));
+        assert!(groups[0].remove_account("DU119916"));
+        assert!(!groups[0].remove_account("DU119916"));
+
+        groups[0].name = "A & B".to_string();
+        assert_eq!(groups, parse_groups(&groups_to_xml(&groups))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fa_profiles() -> Result<(), roxmltree::Error> {
+        let mut profiles = parse_profiles(PROFILES_XML)?;
+        assert_eq!(2, profiles.len());
+        assert_eq!(FaProfileType::Percentages, profiles[0].profile_type);
+        assert_eq!(FaProfileType::Shares, profiles[1].profile_type);
+        assert_eq!("DU119916", profiles[0].allocations[1].account);
+        assert_eq!(40.0, profiles[0].allocations[1].amount);
+
+        profiles[0].set_allocation("DU119916", 30.0);
+        profiles[0].set_allocation("DU119917", 10.0);
+        assert_eq!(3, profiles[0].allocations.len());
+        assert_eq!(30.0, profiles[0].allocations[1].amount);
+        assert!(profiles[1].remove_allocation("DU119915"));
+
+        assert_eq!(profiles, parse_profiles(&profiles_to_xml(&profiles))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fa_aliases() -> Result<(), roxmltree::Error> {
+        let aliases = vec![
+            FaAlias::new("DU119915", "Smith <IRA>"),
+            FaAlias::new("DU119916", "Jones"),
+        ];
+        assert_eq!(aliases, parse_aliases(&aliases_to_xml(&aliases))?);
+        assert!(parse_aliases("<ListOfAccountAliases>").is_err());
+        Ok(())
+    }
+}
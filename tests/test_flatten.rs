@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::common::TickType;
+    use crate::twsapi::contract::{Contract, Right, SecType};
+    use crate::twsapi::flatten::{
+        closing_combo_order, closing_order, closing_orders, combo_size, FlattenFilter, FlattenStyle,
+    };
+    use crate::twsapi::positions::Position;
+    use crate::twsapi::quote_cache::QuoteCache;
+    use crate::twsapi::spreads::vertical;
+
+    fn option(con_id: i32, strike: f64) -> Contract {
+        Contract {
+            con_id,
+            ..Contract::option("AAPL", "20250620", strike, Right::Call)
+        }
+    }
+
+    fn position(account: &str, contract: &Contract, size: i32) -> Position {
+        Position::new(account, contract.clone(), BigDecimal::from(size), 100.0, "")
+    }
+
+    fn quotes(prices: &[(i32, f64, f64)]) -> QuoteCache {
+        let mut quotes = QuoteCache::new();
+        for (con_id, bid, ask) in prices {
+            quotes.add_ticker(*con_id, Some(*con_id));
+            quotes.update_tick_price(*con_id, TickType::Bid, *bid);
+            quotes.update_tick_price(*con_id, TickType::Ask, *ask);
+        }
+        quotes
+    }
+
+    #[test]
+    fn test_closing_order() {
+        let mut stock = Contract::stock("AAPL", "", "USD");
+        stock.con_id = 1;
+        stock.primary_exchange = "NASDAQ".to_string();
+        let quotes = quotes(&[(1, 99.0, 101.0), (2, 2.0, 2.2)]);
+
+        let order =
+            closing_order(&position("DU1", &stock, 100), FlattenStyle::Market, &quotes).unwrap();
+        assert_eq!("SELL", order.order.action);
+        assert_eq!("MKT", order.order.order_type);
+        assert_eq!(100.0, order.order.total_quantity);
+        assert_eq!("NASDAQ", order.contract.exchange);
+
+        let limit = FlattenStyle::MarketableLimit { offset: 0.05 };
+        let order = closing_order(&position("DU1", &stock, -50), limit, &quotes).unwrap();
+        assert_eq!("BUY", order.order.action);
+        assert_eq!("LMT", order.order.order_type);
+        assert_eq!(50.0, order.order.total_quantity);
+        assert!((order.order.lmt_price - 101.05).abs() < 1e-9);
+
+        // Option quantities are in contracts, the notional includes the multiplier
+        let call = option(2, 200.0);
+        let order = closing_order(&position("DU1", &call, 3), limit, &quotes).unwrap();
+        assert_eq!(3.0, order.order.total_quantity);
+        assert!((order.order.lmt_price - 1.95).abs() < 1e-9);
+        assert_eq!(600.0, order.notional(2.0));
+
+        assert!(closing_order(&position("DU1", &option(3, 210.0), 1), limit, &quotes).is_err());
+        assert!(closing_order(&position("DU1", &stock, 0), FlattenStyle::Market, &quotes).is_err());
+    }
+
+    #[test]
+    fn test_closing_orders() {
+        let mut stock = Contract::stock("MSFT", "SMART", "USD");
+        stock.con_id = 1;
+        let positions = vec![
+            position("DU1", &stock, 10),
+            position("DU1", &option(2, 200.0), -2),
+            position("DU2", &stock, 5),
+            position("DU2", &option(3, 210.0), 0),
+        ];
+        let quotes = QuoteCache::new();
+
+        let orders = closing_orders(
+            &positions,
+            &FlattenFilter::new(),
+            FlattenStyle::Market,
+            &quotes,
+        )
+        .unwrap();
+        assert_eq!(3, orders.len());
+
+        let filter = FlattenFilter::new()
+            .account("DU1")
+            .sec_type(SecType::Option);
+        let orders = closing_orders(&positions, &filter, FlattenStyle::Market, &quotes).unwrap();
+        assert_eq!(1, orders.len());
+        assert_eq!("BUY", orders[0].order.action);
+        assert_eq!(2.0, orders[0].order.total_quantity);
+
+        let filter = FlattenFilter::new().symbol("MSFT");
+        let orders = closing_orders(&positions, &filter, FlattenStyle::Market, &quotes).unwrap();
+        assert_eq!(2, orders.len());
+    }
+
+    #[test]
+    fn test_closing_combo_order() {
+        let long = option(2, 200.0);
+        let short = option(3, 210.0);
+        let spread = vertical(&long, &short, "SMART").unwrap();
+
+        let positions = vec![
+            position("DU1", &long, 3),
+            position("DU1", &short, -2),
+            position("DU2", &long, 1),
+            position("DU2", &short, 1),
+        ];
+        assert_eq!(2.0, combo_size(&spread, "DU1", &positions).unwrap());
+        assert!(combo_size(&spread, "DU2", &positions).is_err());
+        assert_eq!(0.0, combo_size(&spread, "DU3", &positions).unwrap());
+
+        // Sells the long leg at its bid and buys the short leg back at its ask
+        let quotes = quotes(&[(2, 5.0, 5.2), (3, 2.0, 2.1)]);
+        let limit = FlattenStyle::MarketableLimit { offset: 0.05 };
+        let order = closing_combo_order(&spread, "DU1", &positions, limit, &quotes).unwrap();
+        assert_eq!("SELL", order.order.action);
+        assert_eq!(2.0, order.order.total_quantity);
+        assert!((order.order.lmt_price - 2.85).abs() < 1e-9);
+        assert_eq!(SecType::Combo, order.contract.sec_type);
+
+        let positions = vec![position("DU1", &long, -1), position("DU1", &short, 1)];
+        let order = closing_combo_order(&spread, "DU1", &positions, limit, &quotes).unwrap();
+        assert_eq!("BUY", order.order.action);
+        assert!((order.order.lmt_price - 3.25).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(all(test, feature = "fundamentals"))]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::twsapi::fundamentals::{
+        CompanySnapshot, Estimates, FinancialStatements, FinancialSummary, FundamentalReportType,
+        StatementType,
+    };
+
+    const SNAPSHOT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ReportSnapshot Major="1" Minor="0" Revision="1">
+  <CoIDs>
+    <CoID Type="RepNo">05680</CoID>
+    <CoID Type="CompanyName">Apple Inc.</CoID>
+  </CoIDs>
+  <Issues>
+    <Issue ID="1" Type="C" Desc="Common Stock" Order="1">
+      <IssueID Type="Name">Ordinary Shares</IssueID>
+      <IssueID Type="Ticker">AAPL</IssueID>
+      <Exchange Code="NASD" Country="USA">NASDAQ</Exchange>
+    </Issue>
+  </Issues>
+  <CoGeneralInfo>
+    <Employees LastUpdated="2023-09-30">161000</Employees>
+    <SharesOut Date="2023-10-20" TotalFloat="15535000000.0">15552752000.0</SharesOut>
+    <ReportingCurrency Code="USD">U.S. Dollars</ReportingCurrency>
+  </CoGeneralInfo>
+  <TextInfo>
+    <Text Type="Business Summary" lastModified="2023-11-03">Apple designs phones.</Text>
+  </TextInfo>
+  <Ratios PriceCurrency="USD" ReportingCurrency="USD" ExchangeRate="1.00000">
+    <Group ID="Price and Volume">
+      <Ratio FieldName="NPRICE" Type="N">171.10000</Ratio>
+      <Ratio FieldName="PDATE" Type="D">2023-11-10T00:00:00</Ratio>
+    </Group>
+  </Ratios>
+  <ForecastData ConsensusType="Mean" CurFiscalYear="2024">
+    <Ratio FieldName="TargetPrice" Type="N">
+      <Value PeriodType="CURR">198.50</Value>
+    </Ratio>
+  </ForecastData>
+</ReportSnapshot>"#;
+
+    const SUMMARY: &str = r#"<FinancialSummary>
+  <EPSs>
+    <EPS asofDate="2023-09-30" reportType="A" period="3M">1.46</EPS>
+    <EPS asofDate="2023-06-30" reportType="A" period="3M">1.26</EPS>
+  </EPSs>
+  <TotalRevenues currency="USD">
+    <TotalRevenue asofDate="2023-09-30" reportType="A" period="3M">89498000000.0</TotalRevenue>
+  </TotalRevenues>
+  <DividendPerShares currency="USD">
+    <DividendPerShare asofDate="2023-09-30" reportType="A" period="3M">0.24</DividendPerShare>
+  </DividendPerShares>
+</FinancialSummary>"#;
+
+    const STATEMENTS: &str = r#"<ReportFinancialStatements Major="1" Minor="0" Revision="1">
+  <CoGeneralInfo>
+    <ReportingCurrency Code="USD">U.S. Dollars</ReportingCurrency>
+  </CoGeneralInfo>
+  <FinancialStatements>
+    <COAMap>
+      <mapItem coaItem="SREV" statementType="INC" lineID="10" precision="1">Revenue</mapItem>
+      <mapItem coaItem="ATOT" statementType="BAL" lineID="10" precision="1">Total Assets</mapItem>
+    </COAMap>
+    <AnnualPeriods>
+      <FiscalPeriod Type="Annual" EndDate="2023-09-30" FiscalYear="2023">
+        <Statement Type="INC">
+          <FPHeader><PeriodLength>12</PeriodLength></FPHeader>
+          <lineItem coaCode="SREV">383285.0</lineItem>
+        </Statement>
+        <Statement Type="BAL">
+          <lineItem coaCode="ATOT">352583.0</lineItem>
+        </Statement>
+      </FiscalPeriod>
+      <FiscalPeriod Type="Annual" EndDate="2022-09-24" FiscalYear="2022">
+        <Statement Type="INC">
+          <lineItem coaCode="SREV">394328.0</lineItem>
+        </Statement>
+      </FiscalPeriod>
+    </AnnualPeriods>
+    <InterimPeriods>
+      <FiscalPeriod Type="Interim" EndDate="2023-09-30" FiscalYear="2023" FiscalPeriodNumber="4">
+        <Statement Type="INC">
+          <lineItem coaCode="SREV">89498.0</lineItem>
+        </Statement>
+      </FiscalPeriod>
+    </InterimPeriods>
+  </FinancialStatements>
+</ReportFinancialStatements>"#;
+
+    const ESTIMATES: &str = r#"<REarnEstCons Version="1">
+  <ConsEstimates>
+    <FYEstimates>
+      <FYEstimate type="EPS" unit="U">
+        <FYPeriod periodType="A" fYear="2024" endMonth="9" endCalYear="2024">
+          <ConsEstimate type="High">
+            <ConsValue dateType="CURR">7.00</ConsValue>
+            <ConsValue dateType="1WA">7.10</ConsValue>
+          </ConsEstimate>
+          <ConsEstimate type="Mean"><ConsValue dateType="CURR">6.55</ConsValue></ConsEstimate>
+          <ConsEstimate type="NumOfEst"><ConsValue dateType="CURR">40</ConsValue></ConsEstimate>
+        </FYPeriod>
+      </FYEstimate>
+    </FYEstimates>
+  </ConsEstimates>
+</REarnEstCons>"#;
+
+    #[test]
+    fn test_company_snapshot() {
+        assert_eq!("RESC", FundamentalReportType::Estimates.to_string());
+
+        let snapshot = CompanySnapshot::parse(SNAPSHOT).unwrap();
+        assert_eq!("Apple Inc.", snapshot.company_name);
+        assert_eq!("AAPL", snapshot.ticker);
+        assert_eq!("NASD", snapshot.exchange);
+        assert_eq!("USD", snapshot.reporting_currency);
+        assert_eq!(Some(161000), snapshot.employees);
+        assert_eq!(Some(15552752000.0), snapshot.shares_outstanding);
+        assert_eq!("Apple designs phones.", snapshot.business_summary);
+        assert_eq!(Some(171.1), snapshot.ratio("NPRICE"));
+        assert_eq!(None, snapshot.ratio("PDATE"));
+        assert_eq!(Some(&198.5), snapshot.forecasts.get("TargetPrice"));
+
+        assert!(CompanySnapshot::parse("<ReportSnapshot>").is_err());
+    }
+
+    #[test]
+    fn test_financial_summary() {
+        let summary = FinancialSummary::parse(SUMMARY).unwrap();
+        assert_eq!("USD", summary.currency);
+        assert_eq!(2, summary.eps.len());
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2023, 6, 30).unwrap(),
+            summary.eps[0].as_of
+        );
+        assert_eq!(1.46, summary.eps[1].value);
+        assert_eq!(89498000000.0, summary.revenues[0].value);
+        assert_eq!("3M", summary.dividends_per_share[0].period);
+    }
+
+    #[test]
+    fn test_financial_statements() {
+        let statements = FinancialStatements::parse(STATEMENTS).unwrap();
+        assert_eq!("USD", statements.currency);
+        assert_eq!(2, statements.annual.len());
+        assert_eq!(Some(4), statements.interim[0].period_number);
+
+        let income = statements.latest_annual(&StatementType::Income).unwrap();
+        assert_eq!(Some(383285.0), income.value("SREV"));
+        assert_eq!("Revenue", income.line_items[0].name);
+        let balance_sheet = statements
+            .latest_annual(&StatementType::BalanceSheet)
+            .unwrap();
+        assert_eq!("Total Assets", balance_sheet.line_items[0].name);
+        assert_eq!(
+            Some(394328.0),
+            statements.annual[1]
+                .statement(&StatementType::Income)
+                .and_then(|statement| statement.value("SREV"))
+        );
+    }
+
+    #[test]
+    fn test_estimates() {
+        let estimates = Estimates::parse(ESTIMATES).unwrap();
+        let eps = estimates.annual("EPS", 2024).unwrap();
+        assert_eq!(Some(6.55), eps.mean());
+        assert_eq!(Some(7.0), eps.high());
+        assert_eq!(None, eps.low());
+        assert_eq!(Some(40), eps.number_of_estimates());
+        assert_eq!(Some(9), eps.end_month);
+        assert!(estimates.annual("EPS", 2025).is_none());
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::twsapi::contract::{Contract, ContractDetails, SecType};
+    use crate::twsapi::futures_chain::{days_to_expiry, roll, FuturesChain, RollPrice};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn details(con_id: i32, local_symbol: &str, expiry: &str) -> ContractDetails {
+        ContractDetails {
+            contract: Contract {
+                con_id,
+                local_symbol: local_symbol.to_string(),
+                ..Contract::future("ES", expiry, "CME")
+            },
+            ..Default::default()
+        }
+    }
+
+    fn chain() -> FuturesChain {
+        FuturesChain::new(
+            Contract::future("ES", "", "CME"),
+            vec![
+                details(3, "ESZ4", "20241220"),
+                details(1, "ESM4", "20240621"),
+                details(2, "ESU4", "20240920"),
+                details(2, "ESU4", "20240920"),
+                details(4, "ESH5", "invalid"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_expiries() {
+        let chain = chain();
+        assert_eq!(
+            vec!["ESM4", "ESU4", "ESZ4"],
+            chain
+                .expiries
+                .iter()
+                .map(|expiry| expiry.contract().local_symbol.as_str())
+                .collect::<Vec<&str>>()
+        );
+
+        let today = date(2024, 6, 21);
+        assert_eq!(
+            "ESM4",
+            chain.front_month(today).unwrap().contract().local_symbol
+        );
+        assert_eq!(
+            "ESU4",
+            chain.back_month(today).unwrap().contract().local_symbol
+        );
+
+        let today = date(2024, 6, 22);
+        assert_eq!(
+            "ESU4",
+            chain.front_month(today).unwrap().contract().local_symbol
+        );
+        assert_eq!(2, chain.active(today).count());
+        assert!(chain.front_month(date(2025, 1, 1)).is_none());
+    }
+
+    #[test]
+    fn test_roll_window() {
+        assert_eq!(0, days_to_expiry(date(2024, 6, 21), date(2024, 6, 21)));
+        assert_eq!(-1, days_to_expiry(date(2024, 6, 21), date(2024, 6, 22)));
+
+        let chain = chain();
+        let front = chain.front_month(date(2024, 6, 10)).unwrap();
+        assert_eq!(11, front.days_to_expiry(date(2024, 6, 10)));
+        assert!(front.in_roll_window(date(2024, 6, 13), 8));
+        assert!(!front.in_roll_window(date(2024, 6, 12), 8));
+
+        assert!(chain.rolling(date(2024, 6, 12), 8).is_empty());
+        assert_eq!(1, chain.rolling(date(2024, 6, 13), 8).len());
+        assert_eq!(
+            "ESM4",
+            chain
+                .current(date(2024, 6, 12), 8)
+                .unwrap()
+                .contract()
+                .local_symbol
+        );
+        assert_eq!(
+            "ESU4",
+            chain
+                .current(date(2024, 6, 13), 8)
+                .unwrap()
+                .contract()
+                .local_symbol
+        );
+    }
+
+    #[test]
+    fn test_roll() {
+        let chain = chain();
+        let expiring = chain.expiries[0].contract();
+        let target = chain.expiries[1].contract();
+
+        let long = roll(expiring, target, 3.0, &RollPrice::Limit(52.25), "DU123").unwrap();
+        assert_eq!(SecType::Combo, long.combo.sec_type);
+        assert_eq!("CME", long.combo.exchange);
+        let legs: Vec<(i32, &str)> = long
+            .combo
+            .combo_legs
+            .iter()
+            .map(|leg| (leg.con_id, leg.action.as_str()))
+            .collect();
+        assert_eq!(vec![(1, "SELL"), (2, "BUY")], legs);
+        assert_eq!("BUY", long.order.action);
+        assert_eq!("LMT", long.order.order_type);
+        assert_eq!(3.0, long.order.total_quantity);
+        assert_eq!(52.25, long.order.lmt_price);
+        assert_eq!("DU123", long.order.account);
+
+        let short = roll(expiring, target, -2.0, &RollPrice::Market, "").unwrap();
+        assert_eq!("SELL", short.order.action);
+        assert_eq!("MKT", short.order.order_type);
+        assert_eq!(2.0, short.order.total_quantity);
+
+        assert!(roll(target, expiring, 1.0, &RollPrice::Market, "").is_err());
+        assert!(roll(expiring, target, 0.0, &RollPrice::Market, "").is_err());
+    }
+}
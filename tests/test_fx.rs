@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::contract::{Contract, SecType};
+    use crate::twsapi::fx::{
+        base_quantity, base_to_quote, quote_to_base, validate_cfd_order, validate_fx_order,
+    };
+    use crate::twsapi::order::Order;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(125_000.0, base_to_quote(100_000.0, 1.25));
+        assert!((quote_to_base(110_000.0, 1.1) - 100_000.0).abs() < 1e-6);
+
+        let order = Order::limit_order_with_cash_qty_order("", "BUY", 0.0, 1.1, 55_000.0);
+        assert!((base_quantity(&order, 1.1) - 50_000.0).abs() < 1e-6);
+        let order = Order::market_order("", "BUY", 30_000.0);
+        assert_eq!(30_000.0, base_quantity(&order, 1.1));
+    }
+
+    #[test]
+    fn test_validate_fx_order() {
+        let eur_usd = Contract::forex("EUR.USD");
+        let order = Order::market_order("", "BUY", 30_000.0);
+        assert!(validate_fx_order(&eur_usd, &order, 1.1, 1.1).is_ok());
+
+        // 20000 EUR are 22000 USD, an odd lot
+        let order = Order::market_order("", "BUY", 20_000.0);
+        assert!(validate_fx_order(&eur_usd, &order, 1.1, 1.1).is_err());
+
+        let order = Order::limit_order_with_cash_qty_order("", "BUY", 0.0, 1.1, 33_000.0);
+        assert!(validate_fx_order(&eur_usd, &order, 1.1, 1.1).is_ok());
+        let order = Order::limit_order_with_cash_qty_order("", "BUY", 30_000.0, 1.1, 33_000.0);
+        assert!(validate_fx_order(&eur_usd, &order, 1.1, 1.1).is_err());
+
+        let mut order = Order::market_order("", "BUY", 30_000.0);
+        order.tif = "OPG".to_string();
+        assert!(validate_fx_order(&eur_usd, &order, 1.1, 1.1).is_err());
+
+        let stock = Contract::stock("AAPL", "SMART", "USD");
+        assert!(validate_fx_order(&stock, &Order::market_order("", "BUY", 1.0), 1.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_validate_cfd_order() {
+        let cfd = Contract::cfd("IBDE40", "EUR");
+        assert_eq!(SecType::Cfd, cfd.sec_type);
+        assert_eq!("SMART", cfd.exchange);
+
+        let mut order = Order::limit_order("", "SELL", 10.0, 15_000.0);
+        order.tif = "gtc".to_string();
+        assert!(validate_cfd_order(&cfd, &order).is_ok());
+        assert!(validate_cfd_order(&cfd, &Order::market_order("", "BUY", 1.5)).is_err());
+        let order = Order::limit_order_with_cash_qty_order("", "BUY", 0.0, 15_000.0, 1_000.0);
+        assert!(validate_cfd_order(&cfd, &order).is_err());
+    }
+}
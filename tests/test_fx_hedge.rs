@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::account_updates::{AccountUpdateEvent, AccountValue};
+    use crate::twsapi::fx_hedge::FxHedgeMonitor;
+
+    fn value(key: &str, text: &str, currency: &str) -> AccountUpdateEvent {
+        AccountUpdateEvent::Value {
+            account: "DU1".to_string(),
+            value: AccountValue::new(key, text, currency),
+        }
+    }
+
+    #[test]
+    fn test_fx_hedge_monitor() {
+        let mut monitor = FxHedgeMonitor::new("USD", 50_000.0);
+        assert!(monitor
+            .apply(&value("CashBalance", "100000", "EUR"))
+            .is_none());
+        // The exposure is known once the rate is
+        let hedge = monitor.apply(&value("ExchangeRate", "1.1", "EUR")).unwrap();
+        assert!((110_000.0 - hedge.exposure.exposure).abs() < 1e-6);
+        assert_eq!("EUR", hedge.contract.symbol);
+        assert_eq!("USD", hedge.contract.currency);
+        assert_eq!("IDEALPRO", hedge.contract.exchange);
+        assert_eq!("SELL", hedge.order.action);
+        assert_eq!("MKT", hedge.order.order_type);
+        assert_eq!(100_000.0, hedge.order.total_quantity);
+        assert!(!hedge.dry_run);
+
+        // Hedged once per breach
+        assert!(monitor
+            .apply(&value("CashBalance", "90000", "EUR"))
+            .is_none());
+        assert!(monitor.apply(&value("CashBalance", "0", "EUR")).is_none());
+        let hedge = monitor
+            .apply(&value("CashBalance", "-60000", "EUR"))
+            .unwrap();
+        assert_eq!("BUY", hedge.order.action);
+        assert_eq!(60_000.0, hedge.order.total_quantity);
+
+        // Pairs with the base currency of the account first
+        monitor.apply(&value("ExchangeRate", "0.0067", "JPY"));
+        let hedge = monitor
+            .apply(&value("CashBalance", "10000000", "JPY"))
+            .unwrap();
+        assert_eq!("USD", hedge.contract.symbol);
+        assert_eq!("JPY", hedge.contract.currency);
+        assert_eq!("BUY", hedge.order.action);
+        assert_eq!(67_000.0, hedge.order.total_quantity);
+
+        // Values in the base currency are not exposures
+        assert!(monitor
+            .apply(&value("CashBalance", "1000000", "USD"))
+            .is_none());
+        assert!(monitor
+            .apply(&value("CashBalance", "1000000", "BASE"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_fx_hedge_options() {
+        let mut monitor = FxHedgeMonitor::new("EUR", 1_000.0)
+            .currency("USD")
+            .currency("GBP")
+            .dry_run(true);
+        assert!(monitor.is_dry_run());
+
+        monitor.apply(&value("ExchangeRate", "0.9", "USD"));
+        let hedge = monitor
+            .apply(&value("CashBalance", "60000", "USD"))
+            .unwrap();
+        assert_eq!("EUR", hedge.contract.symbol);
+        assert_eq!("BUY", hedge.order.action);
+        assert_eq!(54_000.0, hedge.order.total_quantity);
+        assert!(hedge.dry_run);
+
+        // Not watched
+        monitor.apply(&value("ExchangeRate", "0.0062", "JPY"));
+        assert!(monitor
+            .apply(&value("CashBalance", "10000000", "JPY"))
+            .is_none());
+
+        // Below the IDEALPRO minimum
+        monitor.apply(&value("ExchangeRate", "1.15", "GBP"));
+        assert!(monitor
+            .apply(&value("CashBalance", "5000", "GBP"))
+            .is_none());
+        monitor.rearm("DU1", "GBP");
+        assert!(monitor
+            .apply(&value("CashBalance", "50000", "GBP"))
+            .is_some());
+
+        assert!(monitor.exposure("DU1", "GBP").is_some());
+        monitor.clear();
+        assert!(monitor.exposure("DU1", "GBP").is_none());
+    }
+}
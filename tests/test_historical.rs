@@ -0,0 +1,306 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use chrono::{NaiveDate, TimeZone, Timelike, Utc};
+    use chrono_tz::Tz;
+
+    use crate::twsapi::common::{BarData, HistoricalTick, WhatToShow};
+    use crate::twsapi::historical::{
+        format_end_date_time, is_retryable_historical_error, BarTimeParser, HistoricalDataEvent,
+        HistoricalDataStream, HistoricalRetryPolicy, HistoricalSchedule, HistoricalTicks,
+        HistoricalTicksQuery,
+    };
+    use crate::twsapi::server_versions::{
+        MIN_SERVER_VER_HISTORICAL_SCHEDULE, MIN_SERVER_VER_HISTORICAL_TICKS,
+    };
+    use crate::twsapi::subscription::{StreamRegistry, Subscription};
+
+    fn stream(
+        registry: &mut StreamRegistry,
+        request_id: i32,
+        keep_up_to_date: bool,
+    ) -> (HistoricalDataStream, Arc<AtomicBool>, Arc<AtomicBool>) {
+        let receiver = registry.historical_data.add(request_id);
+        let active = Arc::new(AtomicBool::new(true));
+        let still_active = active.clone();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled_clone = cancelled.clone();
+        let subscription = Subscription::new(request_id, receiver, move || {
+            if still_active.load(Ordering::Acquire) {
+                cancelled_clone.store(true, Ordering::Release)
+            }
+        });
+        (
+            HistoricalDataStream::new(subscription, keep_up_to_date, active.clone()),
+            active,
+            cancelled,
+        )
+    }
+
+    fn bar(close: f64) -> BarData {
+        BarData {
+            close,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_historical_data_stream_completes_on_end() {
+        let mut registry = StreamRegistry::new();
+        let (mut bars, active, cancelled) = stream(&mut registry, 1, false);
+
+        for close in [1.0, 2.0] {
+            assert!(registry
+                .historical_data
+                .send(1, HistoricalDataEvent::Bar(bar(close)))
+                .is_ok());
+        }
+        let end = HistoricalDataEvent::End {
+            start: "20230103 09:30:00".to_string(),
+            end: "20230104 16:00:00".to_string(),
+        };
+        assert!(registry.historical_data.send(1, end).is_ok());
+
+        let closes: Vec<f64> = bars
+            .collect_bars()
+            .unwrap()
+            .iter()
+            .map(|bar| bar.close)
+            .collect();
+        assert_eq!(vec![1.0, 2.0], closes);
+        assert_eq!(
+            Some(("20230103 09:30:00", "20230104 16:00:00")),
+            bars.range()
+        );
+        assert!(bars.is_finished());
+        assert!(bars.next().is_none());
+        assert!(bars.next_update().is_none());
+
+        // A completed request is not cancelled
+        assert!(!active.load(Ordering::Acquire));
+        drop(bars);
+        assert!(!cancelled.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_historical_data_stream_error_and_updates() {
+        let mut registry = StreamRegistry::new();
+        let (mut bars, _, _) = stream(&mut registry, 2, false);
+        let error = HistoricalDataEvent::Error {
+            code: 162,
+            message: "Historical Market Data Service error message".to_string(),
+        };
+        assert!(registry.historical_data.send(2, error).is_ok());
+        assert!(bars.next().unwrap().is_err());
+        assert!(bars.next().is_none());
+
+        let (mut updates, _, cancelled) = stream(&mut registry, 3, true);
+        let end = HistoricalDataEvent::End {
+            start: "".to_string(),
+            end: "".to_string(),
+        };
+        assert!(registry.historical_data.send(3, end).is_ok());
+        assert!(registry
+            .historical_data
+            .send(3, HistoricalDataEvent::Update(bar(3.0)))
+            .is_ok());
+        assert!(updates.collect_bars().unwrap().is_empty());
+        assert_eq!(3.0, updates.next_update().unwrap().unwrap().close);
+        assert!(!updates.is_finished());
+
+        // A request that is kept up to date is cancelled on drop
+        drop(updates);
+        assert!(cancelled.load(Ordering::Acquire));
+    }
+
+    fn midpoint_ticks(times: &[i32]) -> HistoricalTicks {
+        HistoricalTicks::Midpoint(
+            times
+                .iter()
+                .map(|time| HistoricalTick::new(*time, 1.5, 0.0))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_historical_ticks_query_pages() {
+        let start = Utc.with_ymd_and_hms(2023, 1, 3, 14, 30, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2023, 1, 3, 14, 31, 0).unwrap();
+        let mut query = HistoricalTicksQuery::new(WhatToShow::Midpoint, start, Some(end));
+        query.number_of_ticks = 3;
+        assert_eq!("20230103-14:30:00", query.start_date_time_param());
+
+        let t = start.timestamp() as i32;
+        let mut ticks = HistoricalTicks::empty(&WhatToShow::Midpoint);
+
+        // A full page continues after its last second
+        let mut page = midpoint_ticks(&[t, t + 10, t + 20, t + 20]);
+        let next = query.next_page(&mut page).unwrap();
+        assert_eq!("20230103-14:30:21", next.start_date_time_param());
+        ticks.append(page);
+
+        // Ticks after the end of the range are dropped
+        let mut page = midpoint_ticks(&[t + 50, t + 60, t + 70]);
+        assert!(next.next_page(&mut page).is_none());
+        ticks.append(page);
+        assert_eq!(6, ticks.len());
+        assert_eq!(Some(t + 60), ticks.last_time());
+
+        // A page that is not full completes the range
+        let mut page = midpoint_ticks(&[t, t + 1]);
+        assert!(query.next_page(&mut page).is_none());
+        assert_eq!(2, page.len());
+
+        // Ticks of a different kind are not appended
+        ticks.append(HistoricalTicks::empty(&WhatToShow::Trades));
+        assert_eq!(6, ticks.len());
+        assert!(!WhatToShow::Bid.is_valid_for_historical_ticks());
+    }
+
+    #[test]
+    fn test_historical_schedule() {
+        let sessions = [
+            ("20230104-09:30:00", "20230104-16:00:00", "20230104"),
+            ("20230103-09:30:00", "20230103-16:00:00", "20230103"),
+        ]
+        .iter()
+        .map(|(start, end, ref_date)| (start.to_string(), end.to_string(), ref_date.to_string()))
+        .collect::<Vec<_>>();
+        let schedule = HistoricalSchedule::parse(
+            "20230103-09:30:00",
+            "20230104-16:00:00",
+            "US/Eastern",
+            &sessions,
+        )
+        .unwrap();
+
+        // Sorted by start and converted to UTC
+        let first = &schedule.sessions[0];
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 1, 3, 14, 30, 0).unwrap(),
+            first.start
+        );
+        assert_eq!(6.5 * 3600.0, first.duration().num_seconds() as f64);
+        assert_eq!(9, schedule.local_time(&first.start).hour());
+
+        let noon = Utc.with_ymd_and_hms(2023, 1, 4, 17, 0, 0).unwrap();
+        let ref_date = NaiveDate::from_ymd_opt(2023, 1, 4).unwrap();
+        assert_eq!(ref_date, schedule.session_at(&noon).unwrap().ref_date);
+        let night = Utc.with_ymd_and_hms(2023, 1, 3, 23, 0, 0).unwrap();
+        assert!(schedule.session_at(&night).is_none());
+        assert_eq!(ref_date, schedule.next_session(&night).unwrap().ref_date);
+        assert_eq!(1, schedule.sessions_for(ref_date).len());
+        assert_eq!(2, schedule.trading_days().len());
+
+        assert!(HistoricalSchedule::parse(
+            "20230103-09:30:00",
+            "20230104-16:00:00",
+            "Mars/Olympus",
+            &sessions
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_bar_time_parser() {
+        let eastern = Tz::US__Eastern;
+        let parser = BarTimeParser::new(1, eastern);
+        let open = eastern.with_ymd_and_hms(2023, 1, 3, 9, 30, 0).unwrap();
+        assert_eq!(Some(open), parser.parse("20230103 09:30:00"));
+        assert_eq!(Some(open), parser.parse("20230103  09:30:00"));
+        assert_eq!(Some(open), parser.parse("20230103 15:30:00 Europe/Berlin"));
+        assert_eq!(
+            Tz::Europe__Berlin,
+            parser
+                .parse("20230103 15:30:00 Europe/Berlin")
+                .unwrap()
+                .timezone()
+        );
+        assert_eq!(
+            Some(eastern.with_ymd_and_hms(2023, 1, 3, 0, 0, 0).unwrap()),
+            parser.parse("20230103")
+        );
+        assert!(parser.parse("1672756200").is_none());
+        assert!(parser.parse("20230103 09:30:00 Mars/Olympus").is_none());
+
+        let parser = BarTimeParser::new(2, eastern);
+        assert_eq!(Some(open), parser.parse("1672756200"));
+        assert_eq!(eastern, parser.parse("1672756200").unwrap().timezone());
+        assert!(parser.parse("20230103 09:30:00").is_none());
+
+        // Bars keep their time through serialization
+        #[cfg(feature = "serde")]
+        {
+            let bar = BarData {
+                date: "1672756200".to_string(),
+                time: parser.parse("1672756200"),
+                ..Default::default()
+            };
+            let bar: BarData = serde_json::from_str(&serde_json::to_string(&bar).unwrap()).unwrap();
+            assert_eq!(Some(open), bar.time);
+            assert_eq!(eastern, bar.time.unwrap().timezone());
+            let bar: BarData = serde_json::from_str(
+                r#"{"date":"20230103","open":1.0,"high":1.0,"low":1.0,"close":1.0,"volume":0,"bar_count":0,"average":1.0}"#,
+            )
+            .unwrap();
+            assert!(bar.time.is_none());
+        }
+    }
+
+    #[test]
+    fn test_format_end_date_time() {
+        let close = Tz::US__Eastern
+            .with_ymd_and_hms(2023, 1, 3, 16, 0, 0)
+            .unwrap();
+        assert_eq!(
+            "20230103 16:00:00 US/Eastern",
+            format_end_date_time(&close, MIN_SERVER_VER_HISTORICAL_SCHEDULE)
+        );
+        assert_eq!(
+            "20230103 16:00:00 US/Eastern",
+            format_end_date_time(&close, MIN_SERVER_VER_HISTORICAL_TICKS - 1)
+        );
+
+        let close = close.with_timezone(&Tz::UTC);
+        assert_eq!(
+            "20230103-21:00:00",
+            format_end_date_time(&close, MIN_SERVER_VER_HISTORICAL_TICKS)
+        );
+        assert_eq!(
+            "20230103 21:00:00 GMT",
+            format_end_date_time(&close, MIN_SERVER_VER_HISTORICAL_TICKS - 1)
+        );
+        let close = close.with_timezone(&Tz::Etc__UTC);
+        assert_eq!(
+            "20230103-21:00:00",
+            format_end_date_time(&close, MIN_SERVER_VER_HISTORICAL_TICKS)
+        );
+    }
+
+    #[test]
+    fn test_historical_retry_policy() {
+        let policy = HistoricalRetryPolicy::default();
+        assert!(policy.is_enabled());
+        assert_eq!(Duration::from_secs(15), policy.delay(0));
+        assert_eq!(Duration::from_secs(60), policy.delay(2));
+        assert_eq!(Duration::from_secs(120), policy.delay(5));
+        assert!(!HistoricalRetryPolicy::none().is_enabled());
+
+        assert!(is_retryable_historical_error(
+            162,
+            "Historical Market Data Service error message:Historical data request pacing violation"
+        ));
+        assert!(is_retryable_historical_error(
+            162,
+            "Historical Market Data Service error message:HMDS query timeout"
+        ));
+        assert!(!is_retryable_historical_error(
+            162,
+            "Historical Market Data Service error message:HMDS query returned no data"
+        ));
+        assert!(!is_retryable_historical_error(200, "pacing violation"));
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::twsapi::common::{BarData, BarSize, HistoricalDuration, WhatToShow};
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::errors::{IbkrError, ServerError};
+    use crate::twsapi::historical_downloader::{
+        is_transient_error, merge_bars, HistoricalDownloader, HistoricalPacer,
+    };
+    use crate::twsapi::server_versions::MIN_SERVER_VER_HISTORICAL_TICKS;
+
+    fn bar(date: &str, close: f64) -> BarData {
+        BarData {
+            date: date.to_string(),
+            close,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chunks_respect_bar_size_limits() {
+        let start = Utc.with_ymd_and_hms(2023, 1, 2, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2023, 1, 2, 2, 30, 0).unwrap();
+        let downloader = HistoricalDownloader::new(
+            Contract::default(),
+            BarSize::_5Secs,
+            WhatToShow::Trades,
+            start,
+            end,
+        );
+        let chunks = downloader.chunks();
+        assert_eq!(3, chunks.len());
+        assert_eq!(start, chunks[0].start);
+        assert_eq!(chunks[0].end, chunks[1].start);
+        assert_eq!(end, chunks[2].end);
+        assert_eq!(
+            HistoricalDuration::Seconds(3600),
+            chunks[0].duration(&BarSize::_5Secs)
+        );
+        assert_eq!("1800 S", chunks[2].duration(&BarSize::_5Secs).to_string());
+        // A chunk shorter than a bar requests one bar
+        assert_eq!(
+            HistoricalDuration::Days(1),
+            chunks[2].duration(&BarSize::_1Day)
+        );
+        assert_eq!(
+            "20230102-01:00:00",
+            chunks[0].end_date_time_param(MIN_SERVER_VER_HISTORICAL_TICKS)
+        );
+
+        let downloader = HistoricalDownloader::new(
+            Contract::default(),
+            BarSize::_1Min,
+            WhatToShow::Midpoint,
+            start,
+            start + chrono::Duration::days(3),
+        );
+        let chunks = downloader.chunks();
+        assert_eq!(3, chunks.len());
+        assert_eq!("1 D", chunks[0].duration(&BarSize::_1Min).to_string());
+    }
+
+    #[test]
+    fn test_pacer_limits_bursts_and_windows() {
+        let now = Instant::now();
+        let mut pacer = HistoricalPacer::new();
+        for _ in 0..4 {
+            pacer.record(now);
+        }
+        assert_eq!(Duration::ZERO, pacer.delay(now));
+        pacer.record(now);
+        assert_eq!(Duration::from_secs(2), pacer.delay(now));
+
+        let mut pacer = HistoricalPacer::new();
+        for i in 0..60 {
+            pacer.record(now + Duration::from_secs(i * 3));
+        }
+        let later = now + Duration::from_secs(200);
+        assert_eq!(Duration::from_secs(400), pacer.delay(later));
+    }
+
+    #[test]
+    fn test_merge_bars_orders_and_deduplicates() {
+        let start = Utc.timestamp_opt(1000, 0).unwrap();
+        let end = Utc.timestamp_opt(1300, 0).unwrap();
+        let bars = vec![
+            bar("1200", 3.0),
+            bar("1100", 2.0),
+            bar("1000", 1.0),
+            bar("1100", 2.0),
+            bar("1300", 4.0),
+            bar("900", 0.0),
+        ];
+        let merged = merge_bars(bars, &start, &end);
+        let closes: Vec<f64> = merged.iter().map(|bar| bar.close).collect();
+        assert_eq!(vec![1.0, 2.0, 3.0], closes);
+
+        let start = Utc.with_ymd_and_hms(2023, 1, 2, 15, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2023, 1, 5, 0, 0, 0).unwrap();
+        let bars = vec![bar("20230103", 2.0), bar("20230102", 1.0)];
+        assert_eq!(2, merge_bars(bars, &start, &end).len());
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        let pacing = IbkrError::Server(ServerError::new(
+            1,
+            162,
+            "Historical Market Data Service error message:API historical data query cancelled",
+        ));
+        assert!(is_transient_error(&pacing));
+        let unknown_contract = IbkrError::Server(ServerError::new(
+            1,
+            200,
+            "No security definition has been found for the request",
+        ));
+        assert!(!is_transient_error(&unknown_contract));
+    }
+}
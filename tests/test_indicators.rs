@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::twsapi::common::{BarData, RealTimeBar};
+    use crate::twsapi::indicators::{
+        Atr, Ema, Indicator, IndicatorPipeline, IndicatorStream, Ohlcv, Rsi, Sma,
+    };
+    use crate::twsapi::subscription::Subscription;
+
+    fn close(close: f64) -> Ohlcv {
+        Ohlcv {
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100.0,
+        }
+    }
+
+    fn values(indicator: &mut dyn Indicator, closes: &[f64]) -> Vec<Option<f64>> {
+        closes
+            .iter()
+            .map(|price| indicator.update(&close(*price)))
+            .collect()
+    }
+
+    struct Range;
+
+    impl Indicator for Range {
+        fn name(&self) -> String {
+            "Range".to_string()
+        }
+
+        fn update(&mut self, bar: &Ohlcv) -> Option<f64> {
+            Some(bar.high - bar.low)
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn test_indicators() {
+        let mut sma = Sma::new(3);
+        assert_eq!(
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0)],
+            values(&mut sma, &[1.0, 2.0, 3.0, 4.0, 5.0])
+        );
+        sma.reset();
+        assert_eq!(None, sma.update(&close(1.0)));
+
+        let mut ema = Ema::new(3);
+        assert_eq!(
+            vec![None, None, Some(2.0), Some(3.0)],
+            values(&mut ema, &[1.0, 2.0, 3.0, 4.0])
+        );
+
+        let mut rsi = Rsi::new(2);
+        assert_eq!(
+            vec![None, None, Some(100.0), Some(50.0)],
+            values(&mut rsi, &[1.0, 2.0, 3.0, 2.0])
+        );
+        assert_eq!("RSI(2)", rsi.name());
+
+        let mut atr = Atr::new(2);
+        let bar = |high: f64, low: f64, close: f64| Ohlcv {
+            high,
+            low,
+            close,
+            ..Default::default()
+        };
+        assert_eq!(None, atr.update(&bar(10.0, 8.0, 9.0)));
+        assert_eq!(Some(2.0), atr.update(&bar(11.0, 9.0, 10.0)));
+        // A gap counts into the true range
+        assert_eq!(Some(3.0), atr.update(&bar(14.0, 12.0, 13.0)));
+    }
+
+    #[test]
+    fn test_indicator_pipeline() {
+        let mut pipeline = IndicatorPipeline::new().with(Sma::new(2)).with(Range);
+        assert_eq!(vec!["SMA(2)", "Range"], pipeline.names());
+
+        let bars = vec![
+            BarData {
+                high: 11.0,
+                low: 9.0,
+                close: 10.0,
+                ..Default::default()
+            },
+            BarData {
+                high: 13.0,
+                low: 10.0,
+                close: 12.0,
+                ..Default::default()
+            },
+        ];
+        let updates = pipeline.update_bars(bars);
+        assert_eq!(None, updates[0].value("SMA(2)"));
+        assert_eq!(Some(2.0), updates[0].value("Range"));
+        assert_eq!(Some(11.0), updates[1].value("SMA(2)"));
+        assert_eq!(12.0, updates[1].bar.close);
+        assert_eq!(None, updates[1].value("EMA(2)"));
+
+        pipeline.reset();
+        let update = pipeline.update_real_time_bar(RealTimeBar {
+            close: 5.0,
+            ..Default::default()
+        });
+        assert_eq!(None, update.value("SMA(2)"));
+    }
+
+    #[test]
+    fn test_indicator_stream() {
+        let (sender, receiver) = mpsc::channel();
+        let subscription = Subscription::new(7, receiver, || {});
+        let mut stream =
+            IndicatorStream::new(subscription, IndicatorPipeline::new().with(Sma::new(2)));
+        assert_eq!(7, stream.request_id());
+        assert!(stream.try_recv().is_err());
+
+        for close in [1.0, 3.0] {
+            sender
+                .send(RealTimeBar {
+                    close,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        assert_eq!(None, stream.try_recv().unwrap().value("SMA(2)"));
+        assert_eq!(Some(2.0), stream.try_recv().unwrap().value("SMA(2)"));
+        assert_eq!(vec!["SMA(2)"], stream.into_pipeline().names());
+    }
+}
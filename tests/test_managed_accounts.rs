@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::managed_accounts::{parse_managed_accounts, ManagedAccounts};
+
+    #[test]
+    fn test_parse_managed_accounts() {
+        assert_eq!(vec!["DU1", "DU2"], parse_managed_accounts("DU1, DU2,"));
+        assert!(parse_managed_accounts("").is_empty());
+    }
+
+    #[test]
+    fn test_managed_accounts() {
+        let mut accounts = ManagedAccounts::new();
+        // Anything goes until the list arrives
+        assert!(accounts.validate("DU9").is_ok());
+        assert_eq!("", accounts.resolve("").unwrap());
+
+        accounts.update("DU1,DU2");
+        assert!(accounts.is_known());
+        assert!(accounts.validate("DU9").is_err());
+        assert!(accounts.set_default_account("DU9").is_err());
+        assert!(accounts.set_default_account("").is_err());
+        accounts.set_default_account("DU2").unwrap();
+        assert_eq!("DU2", accounts.resolve("").unwrap());
+        assert_eq!("DU1", accounts.resolve("DU1").unwrap());
+        assert!(accounts.resolve("DU9").is_err());
+
+        accounts.update("DU1");
+        assert_eq!(None, accounts.default_account());
+    }
+}
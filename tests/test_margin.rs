@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::margin::{parse_margin_value, BasketMarginProjection, MarginImpact};
+    use crate::twsapi::order::OrderState;
+
+    fn order_state(before: &str, change: &str, warning_text: &str) -> OrderState {
+        OrderState {
+            init_margin_before: before.to_string(),
+            init_margin_change: change.to_string(),
+            maint_margin_before: before.to_string(),
+            maint_margin_change: change.to_string(),
+            equity_with_loan_before: "10000".to_string(),
+            equity_with_loan_change: "-2".to_string(),
+            commission: 1.0,
+            commission_currency: "USD".to_string(),
+            warning_text: warning_text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_margin_value() {
+        assert_eq!(Some(1500.25), parse_margin_value("1500.25"));
+        assert_eq!(Some(-3.0), parse_margin_value(" -3 "));
+        assert_eq!(None, parse_margin_value(""));
+        assert_eq!(None, parse_margin_value("1.7976931348623157E308"));
+    }
+
+    #[test]
+    fn test_basket_margin_projection() {
+        let mut projection = BasketMarginProjection::new();
+        projection.add(MarginImpact::from_order_state(&order_state(
+            "2000", "500", "",
+        )));
+        projection.add(MarginImpact::from_order_state(&order_state(
+            "2000",
+            "300",
+            "Large order",
+        )));
+        let mut unset = order_state("", "", "");
+        unset.commission = 1.7976931348623157E308;
+        projection.add(MarginImpact::from_order_state(&unset));
+
+        assert_eq!(3, projection.legs.len());
+        assert_eq!(Some(2000.0), projection.init_margin_before);
+        assert_eq!(800.0, projection.init_margin_change);
+        assert_eq!(Some(2800.0), projection.init_margin_after());
+        assert_eq!(Some(2800.0), projection.maint_margin_after());
+        assert_eq!(Some(9994.0), projection.equity_with_loan_after());
+        assert_eq!(Some(7194.0), projection.excess_after());
+        assert_eq!(2.0, projection.commission);
+        assert_eq!(vec![(1, "Large order".to_string())], projection.warnings);
+        assert_eq!(None, projection.legs[2].commission);
+
+        assert_eq!(None, BasketMarginProjection::new().excess_after());
+    }
+}
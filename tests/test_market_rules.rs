@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::PriceIncrement;
+    use crate::twsapi::contract::ContractDetails;
+    use crate::twsapi::market_rules::{parse_market_rule_ids, MarketRule, MarketRuleCache};
+
+    fn sample_rule() -> MarketRule {
+        MarketRule::new(
+            26,
+            vec![
+                PriceIncrement::new(1.0, 0.05),
+                PriceIncrement::new(0.0, 0.01),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_market_rule_snapping() {
+        let rule = sample_rule();
+        assert_eq!(Some(0.01), rule.increment(0.5));
+        assert_eq!(Some(0.05), rule.increment(100.0));
+        assert_eq!(100.35, rule.snap(100.36));
+        assert_eq!(100.35, rule.snap_down(100.39));
+        assert_eq!(100.4, rule.snap_up(100.36));
+        assert_eq!(100.35, rule.snap_up(100.35));
+        assert_eq!(0.57, rule.snap(0.574));
+        assert_eq!("100.35", rule.format_price(100.35));
+        assert_eq!("0.50", rule.format_price(0.5));
+    }
+
+    #[test]
+    fn test_market_rule_cache() {
+        let mut cache = MarketRuleCache::new();
+        assert_eq!(vec![26, 635, 26], parse_market_rule_ids("26,635, 26"));
+        assert_eq!(vec![26, 635], cache.missing_rules("26,635,26"));
+        assert!(cache.missing_rules("26,635").is_empty());
+
+        cache.insert(sample_rule());
+        assert!(!cache.mark_requested(26));
+
+        let contract_details = ContractDetails {
+            valid_exchanges: "SMART,ISLAND".to_string(),
+            market_rule_ids: "26,635".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            26,
+            cache
+                .rule_for_exchange(&contract_details, "SMART")
+                .unwrap()
+                .market_rule_id
+        );
+        assert!(cache
+            .rule_for_exchange(&contract_details, "ISLAND")
+            .is_none());
+
+        cache.set_auto_fetch(false);
+        assert!(cache.missing_rules("700").is_empty());
+    }
+}
@@ -2,14 +2,27 @@
 mod tests {
 
     use crate::examples::contract_samples;
-    use crate::twsapi::common::{TickByTickType, UNSET_DOUBLE, UNSET_INTEGER};
-    use crate::twsapi::errors::IBKRApiLibError;
+    use crate::twsapi::common::{TickByTickType, UNSET_DOUBLE, UNSET_INTEGER, UNSET_LONG};
+    use crate::twsapi::errors::{DecodeErrorReason, EncodeError, IbkrError};
     use crate::twsapi::messages::{
-        make_field, make_field_handle_empty, make_message, read_fields, read_msg,
-        OutgoingMessageIds,
+        begin_message, finish_message, make_field, make_field_handle_empty, make_message,
+        read_fields, read_msg, split_fields, write_field, IncomingMessageIds, InvalidUtf8Policy,
+        MessageBuffer, MessageInfo, OutgoingMessageIds, RawField, RawFieldLog, INCOMING_MESSAGES,
+        OUTGOING_MESSAGES,
     };
+    use crate::twsapi::reader::Reader;
+    use crate::twsapi::server_versions::{
+        MAX_CLIENT_VER, MIN_SERVER_VER_COMPLETED_ORDERS, MIN_SERVER_VER_PNL,
+    };
+    use crate::twsapi::streamer::TestStreamer;
+    use bigdecimal::BigDecimal;
+    use num_traits::FromPrimitive;
+    use std::io::Write;
+    use std::str::FromStr;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{mpsc, Arc};
     #[test]
-    fn test_make_field() -> Result<(), IBKRApiLibError> {
+    fn test_make_field() -> Result<(), IbkrError> {
         assert_eq!("1\u{0}", make_field(&true)?);
         assert_eq!("\u{0}", make_field(&UNSET_DOUBLE)?);
         assert_eq!("\u{0}", make_field(&UNSET_INTEGER)?);
@@ -23,7 +36,7 @@ mod tests {
     }
 
     #[test]
-    fn test_make_field_handle_empty() -> Result<(), IBKRApiLibError> {
+    fn test_make_field_handle_empty() -> Result<(), IbkrError> {
         assert_eq!("1\u{0}", make_field_handle_empty(&true)?);
         assert_eq!("\u{0}", make_field_handle_empty(&UNSET_DOUBLE)?);
         assert_eq!("\u{0}", make_field_handle_empty(&UNSET_INTEGER)?);
@@ -39,10 +52,121 @@ mod tests {
         let fields = "here\u{0}are\u{0}some\u{0}fields\u{0}1\u{0}2.5\u{0}1000\u{0}";
         let result_fields = vec!["here", "are", "some", "fields", "1", "2.5", "1000"];
         assert_eq!(result_fields, read_fields(fields));
+        assert_eq!(result_fields, split_fields(fields));
+        assert!(split_fields("").is_empty());
+        // Empty fields are kept, a trailing unterminated one is not a field
+        assert_eq!(vec!["", "a", ""], split_fields("\u{0}a\u{0}\u{0}"));
+        assert_eq!(vec!["a"], split_fields("a\u{0}partial"));
+        assert_eq!(vec!["Zürich", "東京"], split_fields("Zürich\u{0}東京\u{0}"));
     }
 
     #[test]
-    fn test_make_msg() -> Result<(), IBKRApiLibError> {
+    fn test_message_buffer() -> Result<(), IbkrError> {
+        let mut bytes = make_message("1\u{0}first\u{0}")?;
+        bytes.extend(make_message("2\u{0}second\u{0}")?);
+
+        // Messages are taken out as soon as they are complete
+        let mut buffer = MessageBuffer::new();
+        buffer.extend(&bytes[..3]);
+        assert_eq!(None, buffer.next_message()?);
+        buffer.extend(&bytes[3..15]);
+        assert_eq!(Some("1\u{0}first\u{0}".to_string()), buffer.next_message()?);
+        assert_eq!(None, buffer.next_message()?);
+        assert_eq!(3, buffer.pending());
+        buffer.extend(&bytes[15..]);
+        assert_eq!(
+            Some("2\u{0}second\u{0}".to_string()),
+            buffer.next_message()?
+        );
+        assert_eq!(0, buffer.pending());
+
+        buffer.extend(&[0, 0, 0, 1, 0xff]);
+        assert!(buffer.next_message().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_buffer_invalid_utf8() -> Result<(), IbkrError> {
+        // A contract details message with a Latin-1 long name
+        let payload = b"10\x008\x001\x00Soci\xe9t\xe9 G\xe9n\xe9rale\x00EUR\x00";
+        let mut bytes = (payload.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(payload);
+        bytes.extend(make_message("1\u{0}next\u{0}")?);
+
+        // The message is dropped, the one after it is read
+        let mut buffer = MessageBuffer::new();
+        buffer.extend(&bytes);
+        match buffer.next_message() {
+            Err(IbkrError::Decode(err)) => {
+                assert_eq!(10, err.msg_type);
+                assert_eq!(3, err.field);
+                assert_eq!(DecodeErrorReason::InvalidUtf8, err.reason);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(Some("1\u{0}next\u{0}".to_string()), buffer.next_message()?);
+
+        let mut buffer = MessageBuffer::new();
+        let raw_fields = RawFieldLog::new();
+        buffer.set_utf8_policy(InvalidUtf8Policy::Lossy, raw_fields.clone());
+        buffer.extend(&bytes);
+        let text = buffer.next_message()?.unwrap();
+        assert_eq!(
+            vec![
+                "10",
+                "8",
+                "1",
+                "Soci\u{fffd}t\u{fffd} G\u{fffd}n\u{fffd}rale",
+                "EUR"
+            ],
+            split_fields(&text)
+        );
+        assert!(raw_fields.take().is_empty());
+
+        buffer.set_utf8_policy(InvalidUtf8Policy::LossyKeepRaw, raw_fields.clone());
+        buffer.extend(&bytes);
+        buffer.next_message()?;
+        assert_eq!(Some(text), buffer.next_message()?);
+        assert_eq!(
+            vec![RawField {
+                message_id: 10,
+                field: 3,
+                text: "Soci\u{fffd}t\u{fffd} G\u{fffd}n\u{fffd}rale".to_string(),
+                bytes: b"Soci\xe9t\xe9 G\xe9n\xe9rale".to_vec(),
+            }],
+            raw_fields.take()
+        );
+        assert!(raw_fields.take().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_invalid_utf8() -> Result<(), IbkrError> {
+        let payload = b"10\x008\x001\x00Soci\xe9t\xe9 G\xe9n\xe9rale\x00EUR\x00";
+        let mut streamer = TestStreamer::new();
+        streamer.write_all(&(payload.len() as u32).to_be_bytes())?;
+        streamer.write_all(payload)?;
+        streamer.write_all(&make_message("1\u{0}next\u{0}")?)?;
+
+        // The error of the dropped message is passed on for the decoder to
+        // report, then the message after it
+        let (sender, receiver) = mpsc::channel();
+        Reader::new(Box::new(streamer), sender, Arc::new(AtomicBool::new(false))).run();
+        match receiver.recv().unwrap() {
+            Err(err) => {
+                assert_eq!(10, err.msg_type);
+                assert_eq!(DecodeErrorReason::InvalidUtf8, err.reason);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!("1\u{0}next\u{0}", receiver.recv().unwrap().unwrap());
+        assert!(receiver.recv().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_make_msg() -> Result<(), IbkrError> {
         let mut msg = "".to_string();
         let contract = contract_samples::usstock();
         let message_id = OutgoingMessageIds::ReqTickByTickData as i32;
@@ -51,10 +175,10 @@ mod tests {
         msg.push_str(&make_field(&1009)?);
         msg.push_str(&make_field(&contract.con_id)?);
         msg.push_str(&make_field(&contract.symbol)?);
-        msg.push_str(&make_field(&contract.sec_type)?);
+        msg.push_str(&make_field(&contract.sec_type.to_string())?);
         msg.push_str(&make_field(&contract.last_trade_date_or_contract_month)?);
         msg.push_str(&make_field(&contract.strike)?);
-        msg.push_str(&make_field(&contract.right)?);
+        msg.push_str(&make_field(&contract.right.to_string())?);
         msg.push_str(&make_field(&contract.multiplier)?);
         msg.push_str(&make_field(&contract.exchange)?);
         msg.push_str(&make_field(&contract.primary_exchange)?);
@@ -80,7 +204,50 @@ mod tests {
     }
 
     #[test]
-    fn test_read_msg() -> Result<(), IBKRApiLibError> {
+    fn test_write_field() -> Result<(), IbkrError> {
+        let mut buf = vec![];
+        write_field(&mut buf, &true)?;
+        write_field(&mut buf, &UNSET_DOUBLE)?;
+        write_field(&mut buf, &UNSET_INTEGER)?;
+        write_field(&mut buf, &UNSET_LONG)?;
+        write_field(&mut buf, &100)?;
+        write_field(&mut buf, &1234567890123_i64)?;
+        write_field(&mut buf, &3_usize)?;
+        write_field(&mut buf, &2.5)?;
+        write_field(&mut buf, &"hello")?;
+        write_field(&mut buf, &"hello".to_string())?;
+        write_field(&mut buf, &Option::<String>::None)?;
+        write_field(&mut buf, &Some(7))?;
+        write_field(&mut buf, &BigDecimal::from_str("0.0001").unwrap())?;
+        assert_eq!(
+            "1\u{0}\u{0}\u{0}\u{0}100\u{0}1234567890123\u{0}3\u{0}2.5\u{0}hello\u{0}hello\u{0}\u{0}7\u{0}0.0001\u{0}",
+            String::from_utf8(buf).unwrap()
+        );
+
+        let mut buf = vec![];
+        assert_eq!(
+            Err(EncodeError::NonAscii("Müller".to_string())),
+            write_field(&mut buf, &"Müller")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_framing() -> Result<(), IbkrError> {
+        let mut buf = vec![];
+        for _ in 0..2 {
+            begin_message(&mut buf);
+            write_field(&mut buf, &(OutgoingMessageIds::ReqCurrentTime as i32))?;
+            write_field(&mut buf, &1)?;
+            assert_eq!(make_message("49\u{0}1\u{0}")?, finish_message(&mut buf));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_msg() -> Result<(), IbkrError> {
         let msg_bytes: Vec<u8> = vec![
             0, 0, 0, 50, 57, 55, 0, 49, 48, 48, 57, 0, 48, 0, 65, 77, 90, 78, 0, 83, 84, 75, 0, 0,
             48, 0, 0, 0, 73, 83, 76, 65, 78, 68, 0, 0, 85, 83, 68, 0, 0, 0, 65, 108, 108, 76, 97,
@@ -92,4 +259,174 @@ mod tests {
 
         Ok(())
     }
+
+    fn assert_table_consistent(table: &[MessageInfo], info: impl Fn(i32) -> Option<MessageInfo>) {
+        for pair in table.windows(2) {
+            assert!(pair[0].id < pair[1].id, "{:?} is out of order", pair[1]);
+        }
+        for entry in table {
+            assert_eq!(
+                Some(*entry),
+                info(entry.id),
+                "{:?} does not round-trip",
+                entry
+            );
+            assert!(entry.version >= 0, "{:?}", entry);
+            assert!(
+                (0..=MAX_CLIENT_VER).contains(&entry.min_server_version),
+                "{:?} needs a server newer than the client supports",
+                entry
+            );
+        }
+    }
+
+    #[test]
+    fn test_message_tables() {
+        assert_table_consistent(INCOMING_MESSAGES, |id| {
+            IncomingMessageIds::from_i32(id).map(IncomingMessageIds::info)
+        });
+        assert_table_consistent(OUTGOING_MESSAGES, |id| {
+            OutgoingMessageIds::from_i32(id).map(OutgoingMessageIds::info)
+        });
+        assert_eq!(None, IncomingMessageIds::from_i32(22));
+        assert_eq!(None, OutgoingMessageIds::from_i32(26));
+    }
+
+    #[test]
+    fn test_message_versions() {
+        assert_eq!(11, OutgoingMessageIds::ReqMktData.version());
+        assert_eq!(45, OutgoingMessageIds::PlaceOrder.version());
+        assert_eq!(0, OutgoingMessageIds::ReqPnl.version());
+        assert_eq!(8, IncomingMessageIds::ContractData.version());
+        assert_eq!(10, IncomingMessageIds::ExecutionData.version());
+        assert_eq!("ReqMktData", OutgoingMessageIds::ReqMktData.info().name);
+        assert_eq!(0, OutgoingMessageIds::ReqMktData.min_server_version());
+        assert_eq!(
+            MIN_SERVER_VER_PNL,
+            OutgoingMessageIds::ReqPnl.min_server_version()
+        );
+        assert_eq!(
+            MIN_SERVER_VER_COMPLETED_ORDERS,
+            IncomingMessageIds::CompletedOrder.min_server_version()
+        );
+    }
+
+    #[test]
+    fn test_request_response_min_server_versions() {
+        // A request is never known to fewer servers than its response
+        for (request, response) in [
+            (
+                OutgoingMessageIds::ReqPositions,
+                IncomingMessageIds::PositionData,
+            ),
+            (
+                OutgoingMessageIds::ReqAccountSummary,
+                IncomingMessageIds::AccountSummary,
+            ),
+            (
+                OutgoingMessageIds::ReqPositionsMulti,
+                IncomingMessageIds::PositionMulti,
+            ),
+            (
+                OutgoingMessageIds::ReqAccountUpdatesMulti,
+                IncomingMessageIds::AccountUpdateMulti,
+            ),
+            (
+                OutgoingMessageIds::VerifyRequest,
+                IncomingMessageIds::VerifyMessageApi,
+            ),
+            (
+                OutgoingMessageIds::VerifyAndAuthRequest,
+                IncomingMessageIds::VerifyAndAuthMessageApi,
+            ),
+            (
+                OutgoingMessageIds::QueryDisplayGroups,
+                IncomingMessageIds::DisplayGroupList,
+            ),
+            (
+                OutgoingMessageIds::ReqSecDefOptParams,
+                IncomingMessageIds::SecurityDefinitionOptionParameter,
+            ),
+            (
+                OutgoingMessageIds::ReqSoftDollarTiers,
+                IncomingMessageIds::SoftDollarTiers,
+            ),
+            (
+                OutgoingMessageIds::ReqFamilyCodes,
+                IncomingMessageIds::FamilyCodes,
+            ),
+            (
+                OutgoingMessageIds::ReqMatchingSymbols,
+                IncomingMessageIds::SymbolSamples,
+            ),
+            (
+                OutgoingMessageIds::ReqMktDepthExchanges,
+                IncomingMessageIds::MktDepthExchanges,
+            ),
+            (
+                OutgoingMessageIds::ReqSmartComponents,
+                IncomingMessageIds::SmartComponents,
+            ),
+            (
+                OutgoingMessageIds::ReqNewsArticle,
+                IncomingMessageIds::NewsArticle,
+            ),
+            (
+                OutgoingMessageIds::ReqNewsProviders,
+                IncomingMessageIds::NewsProviders,
+            ),
+            (
+                OutgoingMessageIds::ReqHistoricalNews,
+                IncomingMessageIds::HistoricalNews,
+            ),
+            (
+                OutgoingMessageIds::ReqHeadTimestamp,
+                IncomingMessageIds::HeadTimestamp,
+            ),
+            (
+                OutgoingMessageIds::ReqHistogramData,
+                IncomingMessageIds::HistogramData,
+            ),
+            (
+                OutgoingMessageIds::ReqMarketRule,
+                IncomingMessageIds::MarketRule,
+            ),
+            (OutgoingMessageIds::ReqPnl, IncomingMessageIds::Pnl),
+            (
+                OutgoingMessageIds::ReqPnlSingle,
+                IncomingMessageIds::PnlSingle,
+            ),
+            (
+                OutgoingMessageIds::ReqHistoricalTicks,
+                IncomingMessageIds::HistoricalTicks,
+            ),
+            (
+                OutgoingMessageIds::ReqTickByTickData,
+                IncomingMessageIds::TickByTick,
+            ),
+            (
+                OutgoingMessageIds::ReqCompletedOrders,
+                IncomingMessageIds::CompletedOrder,
+            ),
+            (
+                OutgoingMessageIds::ReqWshMetaData,
+                IncomingMessageIds::WshMetaData,
+            ),
+            (
+                OutgoingMessageIds::ReqWshEventData,
+                IncomingMessageIds::WshEventData,
+            ),
+            (
+                OutgoingMessageIds::ReqUserInfo,
+                IncomingMessageIds::UserInfo,
+            ),
+        ] {
+            assert!(
+                request.min_server_version() >= response.min_server_version(),
+                "{:?} / {:?}",
+                request,
+                response
+            );
+        }
+    }
 }
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::model::Model;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::positions::Position;
+
+    #[test]
+    fn test_model() {
+        let model = Model::new("DU1", "GROWTH");
+        let order = model.order(&Order::limit_order("", "BUY", 100.0, 150.0));
+        assert_eq!("DU1", order.account);
+        assert_eq!("GROWTH", order.model_code);
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let positions = vec![
+            Position::new(
+                "DU1",
+                contract.clone(),
+                BigDecimal::from(100),
+                150.0,
+                "GROWTH",
+            ),
+            Position::new("DU1", contract.clone(), BigDecimal::from(50), 150.0, ""),
+            Position::new("DU2", contract, BigDecimal::from(10), 150.0, "GROWTH"),
+        ];
+        let held = model.positions(&positions);
+        assert_eq!(1, held.len());
+        assert_eq!("DU1", held[0].account);
+        assert_eq!(2, Model::new("", "GROWTH").positions(&positions).len());
+
+        let execution = Execution {
+            acct_number: "DU1".to_string(),
+            model_code: "GROWTH".to_string(),
+            ..Default::default()
+        };
+        assert!(model.executed(&execution));
+        assert!(!Model::new("DU1", "VALUE").executed(&execution));
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(all(test, feature = "msgpack"))]
+mod tests {
+    use std::io::Cursor;
+
+    use chrono::{TimeZone, Utc};
+
+    use crate::twsapi::common::TickType;
+    use crate::twsapi::contract::{Contract, SecType};
+    use crate::twsapi::errors::IbkrError;
+    use crate::twsapi::msgpack::{from_msgpack, to_msgpack, MsgpackReader, MsgpackWriter};
+    use crate::twsapi::order::Order;
+    use crate::twsapi::order_condition::{MarginCondition, OrderConditionEnum};
+    use crate::twsapi::tick_recorder::RecordedTick;
+
+    fn json(value: &impl serde::Serialize) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<(), IbkrError> {
+        let contract = Contract {
+            con_id: 265598,
+            symbol: "AAPL".to_string(),
+            sec_type: SecType::Stock,
+            exchange: "SMART".to_string(),
+            currency: "USD".to_string(),
+            ..Default::default()
+        };
+        let bytes = to_msgpack(&contract)?;
+        assert_eq!(json(&contract), json(&from_msgpack::<Contract>(&bytes)?));
+
+        let order = Order {
+            action: "BUY".to_string(),
+            order_type: "LMT".to_string(),
+            total_quantity: 100.0,
+            lmt_price: 151.25,
+            conditions: vec![OrderConditionEnum::Margin(MarginCondition::new(
+                false, 30.0,
+            ))],
+            ..Default::default()
+        };
+        let bytes = to_msgpack(&order)?;
+        assert!(bytes.len() < serde_json::to_vec(&order).unwrap().len());
+        assert_eq!(json(&order), json(&from_msgpack::<Order>(&bytes)?));
+
+        assert!(from_msgpack::<Contract>(&bytes[..bytes.len() / 2]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_frames() -> Result<(), IbkrError> {
+        let time = Utc.timestamp_opt(1672531200, 0).unwrap();
+        let ticks = vec![
+            RecordedTick::price(time, 1, TickType::Bid, 100.5),
+            RecordedTick::size(time, 1, TickType::BidSize, 300.0),
+        ];
+        let mut writer = MsgpackWriter::new(vec![]);
+        for tick in &ticks {
+            writer.write(tick)?;
+        }
+        writer.flush()?;
+        let bytes = writer.into_inner();
+
+        let mut reader = MsgpackReader::new(Cursor::new(&bytes));
+        let read: Vec<RecordedTick> = reader.read_all()?;
+        assert_eq!(ticks, read);
+        assert!(reader.read::<RecordedTick>()?.is_none());
+
+        // A stream cut off inside a frame
+        let mut reader = MsgpackReader::new(Cursor::new(&bytes[..bytes.len() - 1]));
+        assert!(reader.read::<RecordedTick>()?.is_some());
+        assert!(reader.read::<RecordedTick>().is_err());
+        let mut reader = MsgpackReader::new(Cursor::new(&bytes[..2]));
+        assert!(reader.read::<RecordedTick>().is_err());
+        Ok(())
+    }
+}
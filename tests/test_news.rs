@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use crate::twsapi::news::{
+        format_historical_news_time, parse_historical_news_time, HistoricalNewsQuery, NewsArticle,
+        NewsArticleType, NewsBulletin, NewsBulletinType, NewsHeadline,
+    };
+
+    #[test]
+    fn test_news_headline_metadata() {
+        let headline = NewsHeadline::new(
+            Utc.with_ymd_and_hms(2019, 6, 27, 15, 48, 21).unwrap(),
+            "BRFG".to_string(),
+            "BRFG$0a1b2c".to_string(),
+            "{A:800015:L:en:K:0.87:C:0.95}Apple beats estimates".to_string(),
+            "".to_string(),
+        );
+        assert_eq!("Apple beats estimates", headline.text());
+        assert_eq!(Some("A:800015:L:en:K:0.87:C:0.95"), headline.metadata());
+        assert_eq!(Some("en"), headline.metadata_value("L"));
+        assert_eq!(Some("0.87"), headline.metadata_value("K"));
+        assert_eq!(None, headline.metadata_value("X"));
+    }
+
+    #[test]
+    fn test_historical_news_times_and_paging() {
+        let time = parse_historical_news_time("2019-06-27 15:48:21.0").unwrap();
+        assert_eq!(Utc.with_ymd_and_hms(2019, 6, 27, 15, 48, 21).unwrap(), time);
+        assert_eq!("2019-06-27 15:48:21.0", format_historical_news_time(&time));
+
+        let query = HistoricalNewsQuery::new(8314, &["BRFG", "DJNL"], 300);
+        assert_eq!("BRFG+DJNL", query.provider_codes_param());
+
+        let oldest = NewsHeadline::new(
+            time,
+            "BRFG".to_string(),
+            "id".to_string(),
+            "headline".to_string(),
+            "".to_string(),
+        );
+        let next_page = query.next_page(&oldest);
+        assert_eq!(
+            "2019-06-27 15:48:20.9",
+            format_historical_news_time(&next_page.end_date_time.unwrap())
+        );
+        assert_eq!(300, next_page.total_results);
+    }
+
+    #[test]
+    fn test_news_article_type() {
+        assert!(NewsArticle::new(NewsArticleType::Text, "<html></html>".to_string()).is_html());
+        assert!(!NewsArticle::new(NewsArticleType::Text, "Plain".to_string()).is_html());
+        assert!(!NewsArticle::new(NewsArticleType::Binary, "<JVBERi0".to_string()).is_html());
+    }
+
+    #[test]
+    fn test_news_bulletin() {
+        let bulletin = NewsBulletin::new(
+            1,
+            NewsBulletinType::ExchangeUnavailable,
+            "Trading halted".to_string(),
+            "NYSE".to_string(),
+        );
+        assert!(bulletin.is_exchange_status());
+        assert_eq!(
+            "msg_id: 1, msg_type: ExchangeUnavailable, message: Trading halted, origin_exchange: NYSE",
+            bulletin.to_string()
+        );
+    }
+}
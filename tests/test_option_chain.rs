@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::twsapi::contract::{Contract, Right, SecType};
+    use crate::twsapi::option_chain::{OptionChain, OptionChainFilter, OptionChainParameters};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn chain() -> OptionChain {
+        let underlying = Contract {
+            con_id: 265598,
+            ..Contract::stock("AAPL", "SMART", "USD")
+        };
+        let expirations = |expirations: &[&str]| -> Vec<String> {
+            expirations
+                .iter()
+                .map(|expiry| expiry.to_string())
+                .collect()
+        };
+        OptionChain::new(
+            underlying,
+            vec![
+                OptionChainParameters::new(
+                    "SMART".to_string(),
+                    265598,
+                    "AAPL".to_string(),
+                    "100".to_string(),
+                    &expirations(&["20240621", "20240517", "20240517", "invalid"]),
+                    &[190.0, 170.0, 180.0, 200.0],
+                ),
+                OptionChainParameters::new(
+                    "CBOE".to_string(),
+                    265598,
+                    "AAPL".to_string(),
+                    "100".to_string(),
+                    &expirations(&["20240517", "20240719"]),
+                    &[180.0, 210.0],
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_parameters() {
+        let chain = chain();
+        let smart = &chain.parameters[0];
+        assert_eq!(
+            vec![date(2024, 5, 17), date(2024, 6, 21)],
+            smart.expirations
+        );
+        assert_eq!(vec![170.0, 180.0, 190.0, 200.0], smart.strikes);
+
+        assert_eq!(vec!["CBOE", "SMART"], chain.exchanges());
+        assert_eq!(vec!["100"], chain.multipliers());
+        assert_eq!(vec!["AAPL"], chain.trading_classes());
+        assert_eq!(
+            vec![date(2024, 5, 17), date(2024, 6, 21), date(2024, 7, 19)],
+            chain.expirations()
+        );
+        assert_eq!(vec![170.0, 180.0, 190.0, 200.0, 210.0], chain.strikes());
+    }
+
+    #[test]
+    fn test_filter() {
+        let chain = chain();
+        let filtered = chain.filter(
+            &OptionChainFilter::new()
+                .exchange("smart")
+                .expiries(date(2024, 5, 1), date(2024, 5, 31))
+                .strikes_around(185.0, 0.05),
+        );
+        assert_eq!(1, filtered.parameters.len());
+        assert_eq!(vec![date(2024, 5, 17)], filtered.expirations());
+        assert_eq!(vec![180.0, 190.0], filtered.strikes());
+
+        // Exchanges left without strikes are dropped
+        let filtered = chain.filter(&OptionChainFilter::new().strikes(205.0, 215.0));
+        assert_eq!(vec!["CBOE"], filtered.exchanges());
+
+        assert_eq!(
+            chain.parameters,
+            chain.filter(&OptionChainFilter::new()).parameters
+        );
+    }
+
+    #[test]
+    fn test_contracts() {
+        let chain = chain().filter(
+            &OptionChainFilter::new()
+                .exchange("SMART")
+                .expiries(date(2024, 6, 1), date(2024, 6, 30))
+                .strikes(180.0, 190.0),
+        );
+        let contracts = chain.contracts(&[Right::Call, Right::Put]);
+        assert_eq!(4, contracts.len());
+        let contract = &contracts[1];
+        assert_eq!("AAPL", contract.symbol);
+        assert_eq!(SecType::Option, contract.sec_type);
+        assert_eq!("20240621", contract.last_trade_date_or_contract_month);
+        assert_eq!(180.0, contract.strike);
+        assert_eq!(Right::Put, contract.right);
+        assert_eq!("SMART", contract.exchange);
+        assert_eq!("USD", contract.currency);
+        assert_eq!("100", contract.multiplier);
+        assert_eq!(0, contract.con_id);
+
+        let mut chain = chain;
+        chain.underlying.sec_type = SecType::Future;
+        assert_eq!(
+            SecType::FuturesOption,
+            chain.contracts(&[Right::Call])[0].sec_type
+        );
+    }
+}
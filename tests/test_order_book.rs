@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::order_book::{BookMetricsConfig, DepthUpdate, OrderBook};
+
+    const INSERT: i32 = 0;
+    const UPDATE: i32 = 1;
+    const DELETE: i32 = 2;
+    const ASK: i32 = 0;
+    const BID: i32 = 1;
+
+    fn row(
+        position: i32,
+        market_maker: &str,
+        operation: i32,
+        side: i32,
+        price: f64,
+        size: f64,
+    ) -> DepthUpdate<'_> {
+        DepthUpdate {
+            position,
+            market_maker,
+            operation,
+            side,
+            price,
+            size,
+        }
+    }
+
+    fn book() -> OrderBook {
+        let mut book = OrderBook::new();
+        for (position, price, size) in [(0, 100.00, 300.0), (1, 99.99, 200.0), (2, 99.90, 500.0)] {
+            assert!(book.apply(&row(position, "", INSERT, BID, price, size)));
+        }
+        for (position, price, size) in [(0, 100.02, 100.0), (1, 100.03, 100.0)] {
+            assert!(book.apply(&row(position, "", INSERT, ASK, price, size)));
+        }
+        book
+    }
+
+    #[test]
+    fn test_order_book() {
+        let mut book = book();
+        assert_eq!(100.00, book.best_bid().unwrap().price);
+        assert_eq!(100.02, book.best_ask().unwrap().price);
+
+        // A better bid is inserted on top
+        assert!(book.apply(&row(0, "NSDQ", INSERT, BID, 100.01, 50.0)));
+        assert_eq!("NSDQ", book.best_bid().unwrap().market_maker);
+        assert!(book.apply(&row(0, "", UPDATE, BID, 100.01, 80.0)));
+        assert_eq!(80.0, book.best_bid().unwrap().size);
+        assert!(book.apply(&row(0, "", DELETE, BID, 100.01, 80.0)));
+        assert_eq!(3, book.bids().len());
+
+        // An update of the row after the last adds it
+        assert!(book.apply(&row(2, "", UPDATE, ASK, 100.05, 10.0)));
+        assert_eq!(3, book.asks().len());
+
+        // Rows that do not fit the book
+        assert!(!book.apply(&row(5, "", DELETE, ASK, 0.0, 0.0)));
+        assert!(!book.apply(&row(5, "", INSERT, BID, 99.0, 1.0)));
+        assert!(!book.apply(&row(0, "", 3, BID, 99.0, 1.0)));
+        assert!(!book.apply(&row(0, "", INSERT, 2, 99.0, 1.0)));
+        assert!(!book.apply(&row(-1, "", INSERT, BID, 99.0, 1.0)));
+
+        book.clear();
+        assert!(book.best_bid().is_none());
+    }
+
+    #[test]
+    fn test_book_metrics() {
+        let book = book();
+        let metrics = book.metrics(7, &BookMetricsConfig::new(2, 0.01, 2));
+        assert_eq!(7, metrics.request_id);
+        assert_eq!(Some(100.00), metrics.best_bid);
+        assert!((metrics.spread.unwrap() - 0.02).abs() < 1e-9);
+        assert!((metrics.mid.unwrap() - 100.01).abs() < 1e-9);
+        // 300 bid against 100 offered leans towards the ask
+        assert!((metrics.weighted_mid.unwrap() - 100.015).abs() < 1e-9);
+        // 500 bid and 200 offered within 2 levels
+        assert!((metrics.imbalance.unwrap() - 3.0 / 7.0).abs() < 1e-9);
+        // The bid at 99.90 is more than 2 ticks away
+        assert_eq!(500.0, metrics.bid_depth);
+        assert_eq!(200.0, metrics.ask_depth);
+        assert!((metrics.bid_slope.unwrap() - 500.0 / 0.02).abs() < 1e-6);
+        assert!((metrics.ask_slope.unwrap() - 200.0 / 0.02).abs() < 1e-6);
+
+        // A one sided book
+        let mut book = OrderBook::new();
+        book.apply(&row(0, "", INSERT, BID, 100.0, 10.0));
+        let metrics = book.metrics(7, &BookMetricsConfig::default());
+        assert_eq!(None, metrics.mid);
+        assert_eq!(None, metrics.weighted_mid);
+        assert_eq!(Some(1.0), metrics.imbalance);
+        assert_eq!(None, metrics.bid_slope);
+        assert_eq!(0.0, metrics.ask_depth);
+    }
+}
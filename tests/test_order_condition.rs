@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::errors::IbkrError;
+    use crate::twsapi::order_condition::{
+        create_condition, decode_condition, encode_condition, Condition, ConditionType,
+        OrderConditionEnum, PriceCondition, TriggerMethod,
+    };
+
+    #[test]
+    fn test_set_value_from_string() {
+        let mut price = create_condition(ConditionType::Price);
+        price.set_value_from_string("101.5".to_string()).unwrap();
+        assert_eq!("101.5", price.value_to_string());
+        assert!(price.set_value_from_string("abc".to_string()).is_err());
+
+        let mut volume = create_condition(ConditionType::Volume);
+        volume.set_value_from_string("1000".to_string()).unwrap();
+        assert_eq!("1000", volume.value_to_string());
+        assert!(volume.set_value_from_string("1000.5".to_string()).is_err());
+
+        let mut execution = create_condition(ConditionType::Execution);
+        assert!(execution.set_value_from_string("1".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_create_condition_types() {
+        for cond_type in [
+            ConditionType::Price,
+            ConditionType::Time,
+            ConditionType::Margin,
+            ConditionType::Execution,
+            ConditionType::Volume,
+            ConditionType::PercentChange,
+        ] {
+            assert_eq!(
+                cond_type as i32,
+                create_condition(cond_type).get_type() as i32
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_condition() -> Result<(), IbkrError> {
+        // Connector, is_more, value, con_id, exchange and trigger method, as
+        // TWS sends them with an open order
+        let fields = ["1", "a", "1", "150.5", "265598", "SMART", "2"];
+        match decode_condition(&mut fields.iter())? {
+            OrderConditionEnum::Price(price) => {
+                assert_eq!(150.5, price.price);
+                assert_eq!(TriggerMethod::Last as i32, price.trigger_method as i32);
+                let contract = &price.contract_condition;
+                assert_eq!(265598, contract.con_id);
+                assert_eq!("SMART", contract.exchange);
+                assert!(contract.operator_condition.is_more);
+                assert!(
+                    contract
+                        .operator_condition
+                        .order_condition
+                        .is_conjunction_connection
+                );
+            }
+            condition => panic!("unexpected condition {:?}", condition),
+        }
+
+        let fields = ["6", "o", "0", "1000", "8314", "NYSE"];
+        match decode_condition(&mut fields.iter())? {
+            OrderConditionEnum::Volume(volume) => {
+                assert_eq!(1000, volume.volume);
+                assert_eq!(8314, volume.contract_condition.con_id);
+                assert_eq!("NYSE", volume.contract_condition.exchange);
+                assert!(!volume.contract_condition.operator_condition.is_more);
+            }
+            condition => panic!("unexpected condition {:?}", condition),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_condition() -> Result<(), IbkrError> {
+        let condition = OrderConditionEnum::Price(PriceCondition::new(
+            TriggerMethod::Last,
+            265598,
+            "SMART",
+            true,
+            150.5,
+        ));
+        assert_eq!(
+            vec!["1\0", "o\0", "1\0", "150.5\0", "265598\0", "SMART\0", "2\0"],
+            encode_condition(&condition)?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    use chrono::Utc;
+
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::errors::IbkrError;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::order_journal::{
+        read_journal, JournalEntry, JournalFill, JournalReader, JournalRecord, JournalState,
+        OrderIntent, OrderJournal,
+    };
+
+    fn fill(order_id: i32, exec_id: &str, side: &str, shares: f64) -> JournalEntry {
+        JournalEntry::Fill(JournalFill {
+            order_id,
+            exec_id: exec_id.to_string(),
+            time: "20240105 10:30:00".to_string(),
+            side: side.to_string(),
+            shares,
+            price: 150.25,
+        })
+    }
+
+    #[test]
+    fn test_record_round_trip() -> Result<(), IbkrError> {
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let mut order = Order::limit_order("", "BUY", 100.0, 150.5);
+        order.order_ref = "entry".to_string();
+        let intent = OrderIntent::new(7, &contract, &order);
+        assert_eq!(Some(150.5), intent.lmt_price);
+        assert_eq!(None, intent.aux_price);
+
+        let record = JournalRecord {
+            sequence: 3,
+            time: Utc::now(),
+            entry: JournalEntry::Intent(intent),
+        };
+        let bytes = record.encode();
+        assert_eq!(
+            bytes.len() - 4,
+            u32::from_be_bytes(bytes[..4].try_into().unwrap()) as usize
+        );
+        let decoded = JournalRecord::decode(&bytes[4..])?;
+        assert_eq!(record.entry, decoded.entry);
+        assert_eq!(
+            record.time.timestamp_millis(),
+            decoded.time.timestamp_millis()
+        );
+
+        // A record cut short ends the journal
+        let mut reader = JournalReader::new(&bytes[..bytes.len() - 1]);
+        assert!(reader.next_record()?.is_none());
+        assert!(JournalRecord::decode(b"1\x000\x00UNKNOWN\x00").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_journal_replay() -> Result<(), IbkrError> {
+        let path =
+            std::env::temp_dir().join(format!("twsapi-order-journal-{}.bin", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(read_journal(&path)?.is_empty());
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let order = Order::limit_order("", "BUY", 100.0, 150.5);
+        let mut journal = OrderJournal::open(&path)?;
+        journal.append(JournalEntry::Intent(OrderIntent::new(1, &contract, &order)))?;
+        journal.append(JournalEntry::Intent(OrderIntent::new(2, &contract, &order)))?;
+        journal.append(JournalEntry::Status {
+            order_id: 1,
+            status: "Submitted".to_string(),
+            filled: 0.0,
+            remaining: 100.0,
+            avg_fill_price: 0.0,
+        })?;
+        journal.append(fill(1, "0001", "BOT", 40.0))?;
+        drop(journal);
+
+        // A crash in the middle of a record leaves it cut short
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(&[0, 0, 0, 40, b'5'])?;
+        drop(file);
+
+        let mut journal = OrderJournal::open(&path)?;
+        assert_eq!(4, journal.last_sequence());
+        // The same execution reported again counts once
+        journal.append(fill(1, "0001", "BOT", 40.0))?;
+        journal.append(JournalEntry::Cancel { order_id: 2 })?;
+        let record = journal.append(JournalEntry::Status {
+            order_id: 2,
+            status: "Cancelled".to_string(),
+            filled: 0.0,
+            remaining: 100.0,
+            avg_fill_price: 0.0,
+        })?;
+        assert_eq!(7, record.sequence);
+        journal.append(JournalEntry::GlobalCancel)?;
+        drop(journal);
+
+        let records = read_journal(&path)?;
+        assert_eq!(
+            (1..=8).collect::<Vec<u64>>(),
+            records
+                .iter()
+                .map(|record| record.sequence)
+                .collect::<Vec<_>>()
+        );
+        let state = JournalState::replay(&records);
+        assert_eq!(8, state.last_sequence());
+        assert_eq!(vec![1], state.working_orders());
+
+        let first = state.order(1).unwrap();
+        assert_eq!("Submitted", first.status);
+        assert_eq!(1, first.fills.len());
+        assert_eq!(40.0, first.filled_position());
+        assert!(first.cancel_requested);
+        assert_eq!(
+            Some("AAPL"),
+            first.intent.as_ref().map(|intent| intent.symbol.as_str())
+        );
+
+        let second = state.order(2).unwrap();
+        assert_eq!("Cancelled", second.status);
+        assert!(!second.is_working());
+
+        assert_eq!(8, JournalState::from_file(&path)?.last_sequence());
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::order_throttle::{OrderThrottle, ThrottleEvent, ThrottleMode};
+
+    fn admit(
+        throttle: &mut OrderThrottle,
+        now: Instant,
+        order_id: i32,
+    ) -> Result<(), ThrottleEvent> {
+        throttle.admit(
+            now,
+            order_id,
+            &Contract::stock("AAPL", "SMART", "USD"),
+            &Order::limit_order("", "BUY", 100.0, 150.0),
+        )
+    }
+
+    #[test]
+    fn test_reject() {
+        let start = Instant::now();
+        let mut throttle = OrderThrottle::new().max_per_second(2).max_per_minute(3);
+        assert_eq!(ThrottleMode::Reject, throttle.throttle_mode());
+        assert!(admit(&mut throttle, start, 1).is_ok());
+        assert!(admit(&mut throttle, start + Duration::from_millis(100), 2).is_ok());
+        assert_eq!(
+            Err(ThrottleEvent::Throttled {
+                order_id: 3,
+                queued: false,
+                wait: Duration::from_millis(800)
+            }),
+            admit(&mut throttle, start + Duration::from_millis(200), 3)
+        );
+        assert!(throttle.queued().is_empty());
+
+        // The per second limit allows it, the per minute limit does not
+        assert!(admit(&mut throttle, start + Duration::from_secs(1), 4).is_ok());
+        assert_eq!(
+            Duration::from_secs(58),
+            throttle.delay(start + Duration::from_secs(2))
+        );
+        assert!(admit(&mut throttle, start + Duration::from_secs(2), 5).is_err());
+        assert!(admit(&mut throttle, start + Duration::from_secs(60), 6).is_ok());
+    }
+
+    #[test]
+    fn test_queue() {
+        let start = Instant::now();
+        let events = Arc::new(Mutex::new(vec![]));
+        let hook_events = events.clone();
+        let mut throttle = OrderThrottle::new()
+            .max_per_second(1)
+            .mode(ThrottleMode::Queue);
+        throttle.set_hook(move |event| hook_events.lock().unwrap().push(event.to_string()));
+
+        assert!(admit(&mut throttle, start, 1).is_ok());
+        for order_id in 2..=4 {
+            assert!(admit(&mut throttle, start, order_id).is_err());
+        }
+        assert_eq!(vec![2, 3, 4], throttle.queued());
+        assert!(throttle.release(start).is_empty());
+
+        // One order per second goes out, in the order they were placed
+        let released = throttle.release(start + Duration::from_secs(1));
+        assert_eq!(
+            vec![2],
+            released
+                .iter()
+                .map(|queued| queued.order_id)
+                .collect::<Vec<_>>()
+        );
+        // An order that could not be sent goes back to the front and no
+        // longer counts as sent
+        throttle.requeue(released);
+        assert_eq!(vec![2, 3, 4], throttle.queued());
+        let released = throttle.release(start + Duration::from_secs(1));
+        assert_eq!(2, released[0].order_id);
+        assert!(throttle.remove(3));
+        assert!(!throttle.remove(3));
+        // Orders are queued as long as others are waiting
+        assert!(admit(&mut throttle, start + Duration::from_secs(3), 5).is_err());
+        let released = throttle.release(start + Duration::from_secs(3));
+        assert_eq!(4, released[0].order_id);
+        assert_eq!(vec![5], throttle.queued());
+        throttle.clear();
+        assert!(throttle.queued().is_empty());
+
+        assert_eq!(
+            vec![
+                "order 2 throttled, queued for 1s",
+                "order 3 throttled, queued for 1s",
+                "order 4 throttled, queued for 1s",
+                "order 2 released",
+                "order 2 released",
+                "order 3 dropped",
+                "order 5 throttled, queued for 0ns",
+                "order 4 released",
+                "order 5 dropped",
+            ],
+            *events.lock().unwrap()
+        );
+    }
+}
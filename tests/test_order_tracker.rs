@@ -0,0 +1,47 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::order_tracker::{is_final_status, OrderTracker};
+
+    #[test]
+    fn test_order_tracker() {
+        let mut orders = OrderTracker::new();
+        orders.track(1);
+        orders.track(2);
+        orders.update(3, "Submitted");
+        assert_eq!(Some("PendingSubmit"), orders.status(1));
+        assert_eq!(vec![1, 2, 3], orders.working_orders());
+
+        orders.update(1, "Filled");
+        orders.update(2, "PreSubmitted");
+        // Modifying an order keeps its status
+        orders.track(2);
+        assert_eq!(Some("PreSubmitted"), orders.status(2));
+        assert!(!orders.is_working(1));
+        assert!(orders.is_working(2));
+        assert!(!orders.is_working(4));
+        assert_eq!(vec![2, 3], orders.working_orders());
+
+        assert!(is_final_status("ApiCancelled"));
+        assert!(!is_final_status("PendingCancel"));
+    }
+
+    #[test]
+    fn test_order_refs() {
+        let mut orders = OrderTracker::new();
+        orders.track(1);
+        orders.set_order_ref(1, "rebalance-1");
+        orders.track(2);
+        orders.set_order_ref(2, "");
+        assert_eq!(Some("rebalance-1"), orders.order_ref(1));
+        assert_eq!(None, orders.order_ref(2));
+        assert_eq!(Some(1), orders.working_order_by_ref("rebalance-1"));
+        assert_eq!(None, orders.working_order_by_ref(""));
+
+        // Only working orders count
+        orders.update(1, "Cancelled");
+        assert_eq!(None, orders.working_order_by_ref("rebalance-1"));
+        orders.track(3);
+        orders.set_order_ref(3, "rebalance-1");
+        assert_eq!(Some(3), orders.working_order_by_ref("rebalance-1"));
+    }
+}
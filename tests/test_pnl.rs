@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::common::UNSET_DOUBLE;
+    use crate::twsapi::pnl::{pnl_value, ProfitAndLoss, SingleProfitAndLoss};
+
+    #[test]
+    fn test_profit_and_loss() {
+        assert_eq!(None, pnl_value(UNSET_DOUBLE));
+        assert_eq!(Some(-12.5), pnl_value(-12.5));
+
+        let pnl = ProfitAndLoss::new(UNSET_DOUBLE, 250.5, 0.0);
+        assert_eq!(None, pnl.daily);
+        assert_eq!(Some(250.5), pnl.unrealized);
+        assert_eq!(Some(0.0), pnl.realized);
+        assert_eq!("daily: -, unrealized: 250.5, realized: 0", pnl.to_string());
+
+        let pnl = SingleProfitAndLoss::new(BigDecimal::from(-3), 10.0, 20.0, 0.0, UNSET_DOUBLE);
+        assert_eq!(BigDecimal::from(-3), pnl.position);
+        assert_eq!(None, pnl.value);
+    }
+}
@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+    use chrono::Utc;
+
+    use crate::twsapi::common::{CommissionReport, TickType};
+    use crate::twsapi::contract::{Contract, Right};
+    use crate::twsapi::currency::CurrencyConverter;
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::portfolio::Portfolio;
+    use crate::twsapi::positions::Position;
+    use crate::twsapi::quote_cache::QuoteCache;
+
+    fn execution(exec_id: &str, side: &str, shares: f64, price: f64) -> Execution {
+        Execution {
+            exec_id: exec_id.to_string(),
+            acct_number: "DU1".to_string(),
+            side: side.to_string(),
+            shares,
+            price,
+            ..Default::default()
+        }
+    }
+
+    fn aapl() -> Contract {
+        let mut contract = Contract::stock("AAPL", "SMART", "USD");
+        contract.con_id = 265598;
+        contract
+    }
+
+    #[test]
+    fn test_net_executions() {
+        let mut portfolio = Portfolio::new();
+        let aapl = aapl();
+
+        assert!(portfolio.apply_execution(&aapl, &execution("1", "BOT", 100.0, 10.0)));
+        assert!(portfolio.apply_execution(&aapl, &execution("2", "BOT", 100.0, 12.0)));
+        // Executions are only applied once
+        assert!(!portfolio.apply_execution(&aapl, &execution("2", "BOT", 100.0, 12.0)));
+        let position = portfolio.position("DU1", 265598).unwrap();
+        assert_eq!(200.0, position.quantity);
+        assert_eq!(11.0, position.average_cost);
+
+        assert!(portfolio.apply_execution(&aapl, &execution("3", "SLD", 50.0, 13.0)));
+        let position = portfolio.position("DU1", 265598).unwrap();
+        assert_eq!(150.0, position.quantity);
+        assert_eq!(11.0, position.average_cost);
+        assert_eq!(100.0, position.realized_pnl);
+
+        // Selling through the position opens a short one at the fill price
+        assert!(portfolio.apply_execution(&aapl, &execution("4", "SLD", 200.0, 10.0)));
+        let position = portfolio.position("DU1", 265598).unwrap();
+        assert_eq!(-50.0, position.quantity);
+        assert_eq!(10.0, position.average_cost);
+        assert_eq!(-50.0, position.realized_pnl);
+
+        let commission = CommissionReport {
+            exec_id: "4".to_string(),
+            commission: 1.5,
+            currency: "USD".to_string(),
+            ..Default::default()
+        };
+        assert!(portfolio.apply_commission(&commission));
+        assert!(!portfolio.apply_commission(&commission));
+        assert_eq!(1.5, portfolio.position("DU1", 265598).unwrap().commissions);
+
+        portfolio.mark(265598, 9.0);
+        assert_eq!(
+            Some(50.0),
+            portfolio.position("DU1", 265598).unwrap().unrealized_pnl()
+        );
+        assert_eq!(Some(&-450.0), portfolio.exposure("DU1").get("USD"));
+        assert_eq!(Some(&-50.0), portfolio.realized_pnl("DU1").get("USD"));
+    }
+
+    #[test]
+    fn test_positions_and_quotes() {
+        let mut portfolio = Portfolio::new();
+        let mut option = Contract::option("AAPL", "20250620", 150.0, Right::Call);
+        option.con_id = 8314;
+        option.multiplier = "100".to_string();
+
+        portfolio.set_position(&Position::new(
+            "DU1",
+            option.clone(),
+            BigDecimal::from(2),
+            250.0,
+            "",
+        ));
+        portfolio.set_position(&Position::new(
+            "DU1",
+            aapl(),
+            BigDecimal::from(10),
+            100.0,
+            "",
+        ));
+        let position = portfolio.position("DU1", 8314).unwrap();
+        assert_eq!(2.5, position.average_cost);
+        assert_eq!(500.0, position.cost_basis());
+
+        let mut quotes = QuoteCache::new();
+        quotes.add_ticker(1, Some(8314));
+        quotes.update_tick_price(1, TickType::Bid, 3.0);
+        quotes.update_tick_price(1, TickType::Ask, 3.5);
+        portfolio.mark_with_quotes(&quotes);
+
+        assert_eq!(
+            Some(650.0),
+            portfolio.position("DU1", 8314).unwrap().market_value()
+        );
+        assert_eq!(
+            None,
+            portfolio.position("DU1", 265598).unwrap().market_price
+        );
+        // Unmarked positions count with their cost basis
+        assert_eq!(Some(&1650.0), portfolio.exposure("DU1").get("USD"));
+        assert_eq!(Some(&150.0), portfolio.unrealized_pnl("DU1").get("USD"));
+        assert_eq!(2, portfolio.open_positions("DU1").count());
+        assert_eq!(0, portfolio.open_positions("DU2").count());
+
+        let mut converter = CurrencyConverter::new();
+        converter.set_rate("EUR", "USD", 1.25, Utc::now());
+        assert_eq!(
+            1320.0,
+            portfolio
+                .base_exposure("DU1", &converter, "EUR")
+                .unwrap()
+                .amount
+        );
+        assert!(portfolio
+            .base_unrealized_pnl("DU1", &converter, "GBP")
+            .is_err());
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::positions::{collect_positions, Position, PositionEvent};
+    use crate::twsapi::subscription::{StreamRegistry, Subscription};
+
+    fn position(account: &str, con_id: i32, size: &str) -> PositionEvent {
+        let mut contract = Contract::stock("AAPL", "SMART", "USD");
+        contract.con_id = con_id;
+        PositionEvent::Position(Box::new(Position::new(
+            account,
+            contract,
+            BigDecimal::from_str(size).unwrap(),
+            150.25,
+            "",
+        )))
+    }
+
+    #[test]
+    fn test_collect_positions() {
+        let mut registry = StreamRegistry::new();
+        let receiver = registry.positions_multi.add(3);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled_clone = cancelled.clone();
+        let subscription = Subscription::new(3, receiver, move || {
+            cancelled_clone.store(true, Ordering::Release)
+        });
+
+        for event in [
+            position("DU1", 265598, "100"),
+            position("DU2", 265598, "-5"),
+            position("DU1", 8314, "0"),
+            position("DU1", 265598, "100.5"),
+            PositionEvent::End,
+        ] {
+            assert!(registry.positions_multi.send(3, event).is_ok());
+        }
+
+        let positions = collect_positions(&subscription, Duration::from_millis(10)).unwrap();
+        assert_eq!(2, positions.len());
+        assert_eq!("DU1", positions[0].account);
+        assert_eq!(BigDecimal::from_str("100.5").unwrap(), positions[0].size);
+        assert_eq!(BigDecimal::from(-5), positions[1].size);
+        assert!(!positions[0].is_closed());
+
+        // Times out without an end marker
+        assert!(collect_positions(&subscription, Duration::from_millis(10)).is_err());
+        let event = PositionEvent::Error {
+            code: 321,
+            message: "Invalid account".to_string(),
+        };
+        assert!(registry.positions_multi.send(3, event).is_ok());
+        assert!(collect_positions(&subscription, Duration::from_millis(10)).is_err());
+
+        drop(subscription);
+        assert!(cancelled.load(Ordering::Acquire));
+    }
+}
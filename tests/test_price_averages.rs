@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::RealTimeBar;
+    use crate::twsapi::price_averages::{RollingTwap, SessionVwap};
+
+    #[test]
+    fn test_session_vwap() {
+        let mut vwap = SessionVwap::new();
+        assert_eq!(None, vwap.vwap());
+
+        vwap.update_trade(10.0, 100.0);
+        vwap.update_trade(11.0, 300.0);
+        vwap.update_real_time_bar(&RealTimeBar {
+            high: 13.0,
+            low: 11.0,
+            close: 12.0,
+            volume: 100.0,
+            ..Default::default()
+        });
+        assert_eq!(Some(11.0), vwap.vwap());
+        assert_eq!(500.0, vwap.cumulative_volume());
+
+        vwap.reset();
+        assert_eq!(0.0, vwap.cumulative_volume());
+    }
+
+    #[test]
+    fn test_rolling_twap() {
+        let mut twap = RollingTwap::new(60);
+        assert_eq!(None, twap.twap(0));
+
+        twap.update(0, 10.0);
+        assert_eq!(Some(10.0), twap.twap(0));
+        twap.update(30, 20.0);
+        assert_eq!(Some(15.0), twap.twap(60));
+        // 10.0 has left the window, 20.0 has been in effect since 30
+        assert_eq!(Some(20.0), twap.twap(90));
+
+        twap.update(100, 30.0);
+        // 20.0 from 60 to 100, 30.0 from 100 to 120
+        assert_eq!(Some((20.0 * 40.0 + 30.0 * 20.0) / 60.0), twap.twap(120));
+    }
+}
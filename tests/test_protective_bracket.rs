@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::positions::Position;
+    use crate::twsapi::protective_bracket::{protective_bracket, BracketPrices};
+
+    fn stock() -> Contract {
+        let mut stock = Contract::stock("AAPL", "", "USD");
+        stock.con_id = 1;
+        stock.primary_exchange = "NASDAQ".to_string();
+        stock
+    }
+
+    fn position(size: i32) -> Position {
+        Position::new("U1", stock(), BigDecimal::from(size), 100.0, "")
+    }
+
+    fn working(order_id: i32, mut order: Order, oca_group: &str) -> (Contract, Order) {
+        order.order_id = order_id;
+        order.oca_group = oca_group.to_string();
+        (stock(), order)
+    }
+
+    #[test]
+    fn test_protective_bracket() {
+        let bracket =
+            protective_bracket(&position(100), &[], BracketPrices::new(110.0, 95.0), "grp")
+                .unwrap();
+        assert_eq!("NASDAQ", bracket.contract.exchange);
+        assert_eq!("grp", bracket.oca_group);
+        let take_profit = bracket.take_profit.as_ref().unwrap();
+        assert_eq!(
+            ("SELL", 100.0, "LMT", 110.0),
+            (
+                take_profit.action.as_str(),
+                take_profit.total_quantity,
+                take_profit.order_type.as_str(),
+                take_profit.lmt_price
+            )
+        );
+        let stop_loss = bracket.stop_loss.as_ref().unwrap();
+        assert_eq!(
+            ("STP", 95.0),
+            (stop_loss.order_type.as_str(), stop_loss.aux_price)
+        );
+        assert!(bracket
+            .orders()
+            .all(|order| order.oca_group == "grp" && order.tif == "GTC" && order.oca_type == 2));
+
+        // Short positions are protected by buying
+        let bracket =
+            protective_bracket(&position(-50), &[], BracketPrices::new(90.0, 105.0), "grp")
+                .unwrap();
+        assert!(bracket
+            .orders()
+            .all(|order| order.action == "BUY" && order.total_quantity == 50.0));
+
+        // Prices on the wrong sides and flat positions
+        assert!(
+            protective_bracket(&position(-50), &[], BracketPrices::new(110.0, 95.0), "grp")
+                .is_err()
+        );
+        assert!(
+            protective_bracket(&position(0), &[], BracketPrices::new(110.0, 95.0), "grp").is_err()
+        );
+    }
+
+    #[test]
+    fn test_partial_bracket() {
+        let working_orders = vec![
+            working(1, Order::limit_order("U1", "SELL", 100.0, 112.0), "old"),
+            working(2, Order::stop_order("", "SELL", 60.0, 94.0), "old"),
+            // An order left over from when the position was short
+            working(3, Order::stop_order("U1", "BUY", 100.0, 120.0), ""),
+            // Orders of other accounts and contracts do not count
+            working(4, Order::stop_order("U2", "SELL", 40.0, 94.0), ""),
+            (
+                Contract::stock("MSFT", "SMART", "USD"),
+                Order::stop_order("U1", "SELL", 40.0, 94.0),
+            ),
+        ];
+        let bracket = protective_bracket(
+            &position(100),
+            &working_orders,
+            BracketPrices::new(110.0, 95.0),
+            "grp",
+        )
+        .unwrap();
+        assert!(bracket.take_profit.is_none());
+        assert_eq!(40.0, bracket.stop_loss.as_ref().unwrap().total_quantity);
+        assert_eq!("old", bracket.oca_group);
+        assert_eq!(vec![3], bracket.stale_orders);
+        assert!(!bracket.is_complete());
+
+        let bracket = protective_bracket(
+            &position(60),
+            &working_orders,
+            BracketPrices::new(110.0, 95.0),
+            "grp",
+        )
+        .unwrap();
+        assert!(bracket.is_complete());
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::{
+        common::TickType,
+        quote_cache::{is_auction_tick, AuctionState, QuoteCache, TradingStatus},
+    };
+
+    #[test]
+    fn test_trading_status() {
+        assert_eq!(
+            TradingStatus::NotHalted,
+            TradingStatus::from_tick_value(0.0)
+        );
+        assert_eq!(TradingStatus::Halted, TradingStatus::from_tick_value(1.0));
+        assert_eq!(
+            TradingStatus::VolatilityHalt,
+            TradingStatus::from_tick_value(2.0)
+        );
+        assert_eq!(
+            TradingStatus::NotAvailable,
+            TradingStatus::from_tick_value(-1.0)
+        );
+        assert_eq!(
+            TradingStatus::NotAvailable,
+            TradingStatus::from_tick_value(7.0)
+        );
+        assert!(TradingStatus::VolatilityHalt.is_halted());
+        assert!(!TradingStatus::NotAvailable.is_halted());
+    }
+
+    #[test]
+    fn test_quote_cache() {
+        let mut cache = QuoteCache::new();
+        cache.add_ticker(1, Some(8314));
+        cache.add_ticker(2, None);
+
+        cache.update_tick_price(1, TickType::Bid, 100.0);
+        cache.update_tick_price(1, TickType::Ask, 100.5);
+        cache.update_tick_size(1, TickType::BidSize, 300.0);
+        cache.update_tick_price(2, TickType::DelayedLast, 50.0);
+
+        let quote = cache.quote(1).unwrap();
+        assert_eq!(Some(100.25), quote.midpoint());
+        assert_eq!(Some(300.0), quote.bid_size);
+        assert_eq!(TradingStatus::NotAvailable, quote.trading_status);
+        assert_eq!(Some(50.0), cache.quote(2).unwrap().last);
+
+        cache.update_trading_status(1, TradingStatus::VolatilityHalt);
+        assert!(cache.is_halted(1));
+        assert!(!cache.is_halted(2));
+        assert!(!cache.is_halted(3));
+        assert!(cache.is_contract_halted(8314));
+        assert_eq!(vec![1], cache.halted_tickers());
+
+        cache.update_trading_status(1, TradingStatus::NotHalted);
+        assert!(!cache.is_contract_halted(8314));
+
+        cache.remove_ticker(1);
+        assert!(cache.quote(1).is_none());
+    }
+
+    #[test]
+    fn test_auction_state() {
+        let mut cache = QuoteCache::new();
+        cache.add_ticker(1, Some(8314));
+        assert!(!cache.auction_state(1).unwrap().is_available());
+        assert!(cache.contract_auction_state(8314).is_none());
+
+        assert!(is_auction_tick(TickType::RegulatoryImbalance));
+        assert!(!is_auction_tick(TickType::Bid));
+        cache.update_tick_price(1, TickType::AuctionPrice, 101.5);
+        cache.update_tick_size(1, TickType::AuctionVolume, 20000.0);
+        cache.update_tick_size(1, TickType::AuctionImbalance, 5000.0);
+        cache.update_tick_size(1, TickType::RegulatoryImbalance, 4000.0);
+
+        let auction = AuctionState {
+            price: Some(101.5),
+            volume: Some(20000.0),
+            imbalance: Some(5000.0),
+            regulatory_imbalance: Some(4000.0),
+        };
+        assert_eq!(Some(&auction), cache.auction_state(1));
+        assert_eq!(Some(&auction), cache.contract_auction_state(8314));
+        assert_eq!(Some(0.25), auction.imbalance_ratio());
+        assert_eq!(None, cache.quote(1).unwrap().last);
+    }
+}
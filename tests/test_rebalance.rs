@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::common::TickType;
+    use crate::twsapi::contract::{Contract, ContractDetails};
+    use crate::twsapi::positions::Position;
+    use crate::twsapi::quote_cache::QuoteCache;
+    use crate::twsapi::rebalance::{RebalanceStyle, RebalanceTarget, Rebalancer};
+
+    fn stock(con_id: i32, symbol: &str) -> Contract {
+        Contract {
+            con_id,
+            ..Contract::stock(symbol, "SMART", "USD")
+        }
+    }
+
+    fn position(account: &str, contract: &Contract, size: i32) -> Position {
+        Position::new(account, contract.clone(), BigDecimal::from(size), 100.0, "")
+    }
+
+    fn quotes(prices: &[(i32, f64, f64)]) -> QuoteCache {
+        let mut quotes = QuoteCache::new();
+        for (con_id, bid, ask) in prices {
+            quotes.add_ticker(*con_id, Some(*con_id));
+            quotes.update_tick_price(*con_id, TickType::Bid, *bid);
+            quotes.update_tick_price(*con_id, TickType::Ask, *ask);
+        }
+        quotes
+    }
+
+    #[test]
+    fn test_target_from_details() {
+        let details = ContractDetails {
+            contract: stock(1, "AAPL"),
+            min_tick: 0.05,
+            size_increment: 100.0,
+            ..Default::default()
+        };
+        let target = RebalanceTarget::from_details(&details, 0.5);
+        assert_eq!(100.0, target.lot_size);
+        assert_eq!(0.0, target.min_size);
+        assert_eq!(0.05, target.min_tick);
+    }
+
+    #[test]
+    fn test_plan() {
+        let aapl = stock(1, "AAPL");
+        let msft = stock(2, "MSFT");
+        let ibm = stock(3, "IBM");
+        let positions = vec![
+            position("DU1", &aapl, 300),
+            position("DU1", &ibm, 10),
+            position("DU2", &msft, 1000),
+        ];
+        let quotes = quotes(&[(1, 99.95, 100.05), (2, 49.95, 50.05), (3, 9.95, 10.05)]);
+        let rebalancer = Rebalancer::new()
+            .target(RebalanceTarget::new(&aapl, 0.2))
+            .target(RebalanceTarget::new(&msft, 0.5).lot_size(10.0))
+            .style(RebalanceStyle::MarketableLimit { ticks: 2 });
+
+        let plan = rebalancer
+            .plan("DU1", 100_000.0, &positions, &quotes)
+            .unwrap();
+        // Sells come first
+        assert_eq!(2, plan.orders.len());
+        assert_eq!("SELL", plan.orders[0].order.action);
+        assert_eq!(100.0, plan.orders[0].order.total_quantity);
+        assert_eq!(200.0, plan.orders[0].target);
+        assert_eq!(99.93, plan.orders[0].order.lmt_price);
+        assert_eq!("BUY", plan.orders[1].order.action);
+        assert_eq!(1000.0, plan.orders[1].order.total_quantity);
+        assert_eq!(50.07, plan.orders[1].order.lmt_price);
+        assert_eq!(2, plan.basket().len());
+
+        // Untargeted positions can be closed
+        let plan = rebalancer
+            .clone()
+            .close_untargeted(true)
+            .style(RebalanceStyle::Market)
+            .plan("DU1", 100_000.0, &positions, &quotes)
+            .unwrap();
+        assert_eq!(3, plan.orders.len());
+        assert_eq!(3, plan.orders[1].contract.con_id);
+        assert_eq!("MKT", plan.orders[1].order.order_type);
+        assert_eq!(10.0, plan.orders[1].order.total_quantity);
+    }
+
+    #[test]
+    fn test_plan_skips_small_trades() {
+        let aapl = stock(1, "AAPL");
+        let positions = vec![position("DU1", &aapl, 195)];
+        let quotes = quotes(&[(1, 99.95, 100.05)]);
+
+        let rebalancer = Rebalancer::new().target(RebalanceTarget::new(&aapl, 0.2).lot_size(100.0));
+        let plan = rebalancer
+            .plan("DU1", 100_000.0, &positions, &quotes)
+            .unwrap();
+        assert!(plan.orders.is_empty());
+        assert_eq!(1, plan.skipped.len());
+
+        let rebalancer = Rebalancer::new()
+            .target(RebalanceTarget::new(&aapl, 0.2))
+            .min_trade_value(1000.0);
+        let plan = rebalancer
+            .plan("DU1", 100_000.0, &positions, &quotes)
+            .unwrap();
+        assert!(plan.orders.is_empty());
+        assert_eq!(1, plan.skipped.len());
+        let plan = rebalancer
+            .plan("DU1", 200_000.0, &positions, &quotes)
+            .unwrap();
+        assert_eq!(205.0, plan.orders[0].order.total_quantity);
+    }
+
+    #[test]
+    fn test_plan_errors() {
+        let aapl = stock(1, "AAPL");
+        let rebalancer = Rebalancer::new().target(RebalanceTarget::new(&aapl, 0.2));
+        assert!(rebalancer
+            .plan("DU1", 0.0, &[], &QuoteCache::new())
+            .is_err());
+        // Contracts without a quote cannot be valued
+        assert!(rebalancer
+            .plan("DU1", 100_000.0, &[], &QuoteCache::new())
+            .is_err());
+    }
+}
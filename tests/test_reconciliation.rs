@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use bigdecimal::BigDecimal;
+
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::portfolio::Portfolio;
+    use crate::twsapi::positions::Position;
+    use crate::twsapi::reconciliation::{reconcile, Discrepancy};
+
+    fn position(account: &str, con_id: i32, size: i32) -> Position {
+        let mut contract = Contract::stock("AAPL", "SMART", "USD");
+        contract.con_id = con_id;
+        Position::new(account, contract, BigDecimal::from(size), 100.0, "")
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut portfolio = Portfolio::new();
+        portfolio.set_position(&position("DU1", 1, 100));
+        portfolio.set_position(&position("DU1", 2, 50));
+        portfolio.set_position(&position("DU1", 3, 10));
+        portfolio.set_position(&position("DU1", 5, 0));
+
+        let broker = vec![
+            position("DU1", 1, 100),
+            position("DU1", 2, 40),
+            position("DU1", 4, -5),
+            position("DU1", 5, 0),
+            position("DU2", 1, 7),
+        ];
+        let report = reconcile(&portfolio, &broker);
+        assert!(!report.is_clean());
+        assert_eq!(4, report.discrepancies.len());
+
+        assert!(matches!(
+            &report.discrepancies[0],
+            Discrepancy::SizeMismatch { local_size, broker_size, .. }
+                if *local_size == 50.0 && *broker_size == 40.0
+        ));
+        assert_eq!(-10.0, report.discrepancies[0].difference());
+        assert!(matches!(
+            &report.discrepancies[1],
+            Discrepancy::Extra { contract, local_size, .. }
+                if contract.con_id == 3 && *local_size == 10.0
+        ));
+        assert!(matches!(
+            &report.discrepancies[2],
+            Discrepancy::Missing { contract, broker_size, .. }
+                if contract.con_id == 4 && *broker_size == -5.0
+        ));
+        assert_eq!("DU2", report.discrepancies[3].account());
+        assert_eq!(3, report.account_discrepancies("DU1").count());
+
+        // Positions of several models add up
+        let broker = vec![
+            position("DU1", 1, 60),
+            position("DU1", 1, 40),
+            position("DU1", 2, 50),
+            position("DU1", 3, 10),
+        ];
+        let report = reconcile(&portfolio, &broker);
+        assert!(report.is_clean());
+        assert_eq!("No discrepancies", report.to_string());
+    }
+}
@@ -0,0 +1,179 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use chrono::{Duration, Utc};
+
+    use crate::twsapi::blotter::Blotter;
+    use crate::twsapi::common::CommissionReport;
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::risk::{
+        order_price, MaxDailyLoss, MaxOrderNotional, MaxPosition, RestrictedList, RiskAction,
+        RiskCheck, RiskChecks, RiskLimits, RiskOrder,
+    };
+
+    fn fill(blotter: &Arc<Mutex<Blotter>>, exec_id: &str, side: &str, shares: f64) {
+        let execution = Execution {
+            exec_id: exec_id.to_string(),
+            side: side.to_string(),
+            shares,
+            price: 150.0,
+            ..Default::default()
+        };
+        blotter
+            .lock()
+            .unwrap()
+            .record_execution(&Contract::stock("AAPL", "SMART", "USD"), &execution);
+    }
+
+    fn commission(blotter: &Arc<Mutex<Blotter>>, exec_id: &str, realized_pnl: f64) {
+        blotter
+            .lock()
+            .unwrap()
+            .record_commission_report(&CommissionReport {
+                exec_id: exec_id.to_string(),
+                commission: 1.0,
+                currency: "USD".to_string(),
+                realized_pnl,
+                ..Default::default()
+            });
+    }
+
+    fn run_check(check: &dyn RiskCheck, contract: &Contract, order: &Order) -> Option<String> {
+        check.check(&RiskOrder {
+            order_id: 1,
+            contract,
+            order,
+            price: order_price(order),
+        })
+    }
+
+    #[test]
+    fn test_max_order_notional() {
+        let check = MaxOrderNotional { limit: 10_000.0 };
+        let stock = Contract::stock("AAPL", "SMART", "USD");
+        assert!(run_check(&check, &stock, &Order::limit_order("", "BUY", 50.0, 150.0)).is_none());
+        assert!(run_check(&check, &stock, &Order::limit_order("", "SELL", 70.0, 150.0)).is_some());
+        // Market orders without a quote cannot be valued
+        assert!(run_check(&check, &stock, &Order::market_order("", "BUY", 1.0)).is_some());
+        assert!(run_check(&check, &stock, &Order::stop_order("", "SELL", 70.0, 150.0)).is_some());
+        // The trailing amount of a trailing stop is not its price
+        let mut trail = Order::market_order("", "SELL", 500.0);
+        trail.order_type = "TRAIL".to_string();
+        trail.aux_price = 1.5;
+        assert_eq!(None, order_price(&trail));
+        assert!(run_check(&check, &stock, &trail).is_some());
+
+        // The multiplier counts
+        let mut option = stock.clone();
+        option.multiplier = "100".to_string();
+        assert!(run_check(&check, &option, &Order::limit_order("", "BUY", 1.0, 99.0)).is_none());
+        assert!(run_check(&check, &option, &Order::limit_order("", "BUY", 2.0, 99.0)).is_some());
+    }
+
+    #[test]
+    fn test_max_position() {
+        let blotter = Arc::new(Mutex::new(Blotter::new()));
+        let check = MaxPosition::new(100.0, blotter.clone()).start_position("AAPL", 20.0);
+        fill(&blotter, "1", "BOT", 50.0);
+        fill(&blotter, "2", "SLD", 10.0);
+        assert_eq!(60.0, check.position("AAPL"));
+
+        let stock = Contract::stock("AAPL", "SMART", "USD");
+        assert!(run_check(&check, &stock, &Order::limit_order("", "BUY", 40.0, 150.0)).is_none());
+        assert!(run_check(&check, &stock, &Order::limit_order("", "BUY", 41.0, 150.0)).is_some());
+        assert!(run_check(
+            &check,
+            &stock,
+            &Order::limit_order("", "SELL", 160.0, 150.0)
+        )
+        .is_none());
+        assert!(run_check(
+            &check,
+            &stock,
+            &Order::limit_order("", "SELL", 161.0, 150.0)
+        )
+        .is_some());
+
+        // Reducing a position beyond the limit passes
+        let check = MaxPosition::new(10.0, blotter).start_position("AAPL", 20.0);
+        assert!(run_check(&check, &stock, &Order::limit_order("", "SELL", 5.0, 150.0)).is_none());
+    }
+
+    #[test]
+    fn test_max_daily_loss() {
+        let blotter = Arc::new(Mutex::new(Blotter::new()));
+        let check = MaxDailyLoss::new(500.0, blotter.clone());
+        let stock = Contract::stock("AAPL", "SMART", "USD");
+        let buy = Order::limit_order("", "BUY", 10.0, 150.0);
+
+        fill(&blotter, "1", "BOT", 100.0);
+        fill(&blotter, "2", "SLD", 50.0);
+        commission(&blotter, "1", f64::MAX);
+        commission(&blotter, "2", -450.0);
+        assert_eq!(-452.0, check.realized_pnl());
+        assert!(run_check(&check, &stock, &buy).is_none());
+
+        fill(&blotter, "3", "SLD", 10.0);
+        commission(&blotter, "3", -100.0);
+        assert!(run_check(&check, &stock, &buy).is_some());
+        // Closing orders still pass
+        assert!(run_check(&check, &stock, &Order::market_order("", "SELL", 40.0)).is_none());
+
+        // Losses before the start of the day do not count
+        let check = MaxDailyLoss::new(500.0, blotter).day_start(Utc::now() + Duration::minutes(1));
+        assert_eq!(0.0, check.realized_pnl());
+    }
+
+    #[test]
+    fn test_risk_checks() {
+        let blotter = Arc::new(Mutex::new(Blotter::new()));
+        let limits = RiskLimits {
+            max_order_notional: Some(10_000.0),
+            max_position: Some(100.0),
+            restricted: vec!["gme".to_string()],
+            ..Default::default()
+        };
+        let mut checks = RiskChecks::from_limits(&limits, blotter, RiskAction::Warn)
+            .add(MaxOrderNotional { limit: 20_000.0 }, RiskAction::Block);
+        assert_eq!(4, checks.len());
+
+        let warnings = Arc::new(Mutex::new(vec![]));
+        let hook_warnings = warnings.clone();
+        checks.set_warning_hook(move |violation| {
+            hook_warnings.lock().unwrap().push(violation.check.clone())
+        });
+
+        let contract = Contract::stock("GME", "SMART", "USD");
+        let order = Order::limit_order("", "BUY", 80.0, 150.0);
+        let risk_order = RiskOrder {
+            order_id: 3,
+            contract: &contract,
+            order: &order,
+            price: Some(150.0),
+        };
+        let violations = checks.enforce(&risk_order).unwrap();
+        assert_eq!(2, violations.len());
+        assert_eq!(3, violations[0].order_id);
+        assert_eq!(
+            vec!["restricted list", "max order notional"],
+            *warnings.lock().unwrap()
+        );
+
+        let order = Order::limit_order("", "BUY", 200.0, 150.0);
+        let violation = checks
+            .enforce(&RiskOrder {
+                order: &order,
+                ..risk_order
+            })
+            .unwrap_err();
+        assert_eq!(RiskAction::Block, violation.action);
+        assert_eq!("max order notional", violation.check);
+        // Nothing is passed to the hook for a blocked order
+        assert_eq!(2, warnings.lock().unwrap().len());
+
+        assert!(RestrictedList::new(["GME"]).contains("gme"));
+    }
+}
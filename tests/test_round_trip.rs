@@ -0,0 +1,135 @@
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::twsapi::contract::{Contract, Right, SecType};
+    use crate::twsapi::errors::IbkrError;
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::order_condition::{
+        ExecutionCondition, MarginCondition, OrderConditionEnum, PercentChangeCondition,
+        PriceCondition, TimeCondition, TriggerMethod, VolumeCondition,
+    };
+    use crate::twsapi::round_trip::{condition_round_trip, execution_round_trip, json_round_trip};
+    use crate::twsapi::server_versions::{
+        MAX_CLIENT_VER, MIN_SERVER_VER_FRACTIONAL_POSITIONS, MIN_SERVER_VER_LAST_LIQUIDITY,
+    };
+
+    fn json(value: &impl serde::Serialize) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    fn execution() -> (Contract, Execution) {
+        let contract = Contract {
+            con_id: 495512551,
+            symbol: "ES".to_string(),
+            sec_type: SecType::FuturesOption,
+            last_trade_date_or_contract_month: "20251219".to_string(),
+            strike: 5800.0,
+            right: Right::Call,
+            multiplier: "50".to_string(),
+            exchange: "CME".to_string(),
+            currency: "USD".to_string(),
+            local_symbol: "ESZ5 C5800".to_string(),
+            trading_class: "ES".to_string(),
+            ..Default::default()
+        };
+        let execution = Execution {
+            exec_id: "0000e0d5.67a1b2c3.01.01".to_string(),
+            time: "20251020 15:30:01".to_string(),
+            acct_number: "DU123456".to_string(),
+            exchange: "CME".to_string(),
+            side: "BOT".to_string(),
+            shares: 2.5,
+            price: 41.25,
+            perm_id: 1803567,
+            client_id: 7,
+            order_id: 12,
+            liquidation: 0,
+            cum_qty: 2.5,
+            avg_price: 41.25,
+            order_ref: "hedge".to_string(),
+            ev_rule: "".to_string(),
+            ev_multiplier: 50.0,
+            model_code: "core".to_string(),
+            last_liquidity: 2,
+        };
+        (contract, execution)
+    }
+
+    #[test]
+    fn test_execution_round_trip() -> Result<(), IbkrError> {
+        let (contract, execution) = execution();
+        let (request_id, decoded_contract, decoded) =
+            execution_round_trip(4, &contract, &execution, MAX_CLIENT_VER)?;
+        assert_eq!(4, request_id);
+        assert_eq!(json(&contract), json(&decoded_contract));
+        assert_eq!(json(&execution), json(&decoded));
+
+        // Before last liquidity the message has a version field and no
+        // liquidity, before fractional positions the shares are whole
+        let (_, _, decoded) =
+            execution_round_trip(4, &contract, &execution, MIN_SERVER_VER_LAST_LIQUIDITY - 1)?;
+        assert_eq!(0, decoded.last_liquidity);
+        assert_eq!("core", decoded.model_code);
+        let (_, _, decoded) = execution_round_trip(
+            4,
+            &contract,
+            &execution,
+            MIN_SERVER_VER_FRACTIONAL_POSITIONS - 1,
+        )?;
+        assert_eq!(2.0, decoded.shares);
+        assert_eq!(41.25, decoded.price);
+        Ok(())
+    }
+
+    #[test]
+    fn test_condition_round_trip() -> Result<(), IbkrError> {
+        let mut time = TimeCondition::new(true, "20251020 15:30:00".to_string());
+        time.operator_condition.order_condition.and();
+        let conditions = [
+            OrderConditionEnum::Price(PriceCondition::new(
+                TriggerMethod::DoubleBidAsk,
+                265598,
+                "SMART",
+                true,
+                150.5,
+            )),
+            OrderConditionEnum::Time(time),
+            OrderConditionEnum::Margin(MarginCondition::new(false, 30.0)),
+            OrderConditionEnum::Execution(ExecutionCondition::new(
+                SecType::Stock,
+                "SMART".to_string(),
+                "AAPL".to_string(),
+            )),
+            OrderConditionEnum::Volume(VolumeCondition::new(8314, "NYSE", true, 100000)),
+            OrderConditionEnum::PercentChange(PercentChangeCondition::new(
+                8314,
+                "NYSE".to_string(),
+                false,
+                -2.5,
+            )),
+        ];
+        for condition in conditions {
+            assert_eq!(json(&condition), json(&condition_round_trip(&condition)?));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_round_trip() -> Result<(), IbkrError> {
+        let (contract, _) = execution();
+        assert_eq!(json(&contract), json(&json_round_trip(&contract)?));
+
+        let order = Order {
+            action: "BUY".to_string(),
+            order_type: "LMT".to_string(),
+            total_quantity: 2.0,
+            lmt_price: 41.25,
+            conditions: vec![OrderConditionEnum::Margin(MarginCondition::new(
+                false, 30.0,
+            ))],
+            ..Default::default()
+        };
+        assert_eq!(json(&order), json(&json_round_trip(&order)?));
+        Ok(())
+    }
+}
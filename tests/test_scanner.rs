@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::{UNSET_DOUBLE, UNSET_INTEGER};
+    use crate::twsapi::scanner::{
+        ScanCode, ScanFilter, ScanInstrument, ScanLocation, ScannerSubscription,
+        NO_ROW_NUMBER_SPECIFIED,
+    };
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_scanner_subscription_builder() {
+        let subscription = ScannerSubscription::builder(ScanCode::TopPercGain)
+            .instrument(ScanInstrument::StockEu)
+            .location(ScanLocation::StockEuIbis)
+            .filter(ScanFilter::PriceAbove(5.0))
+            .filter(ScanFilter::MarketCapAbove(1000.0))
+            .filter(ScanFilter::PriceAbove(10.0))
+            .build();
+
+        assert_eq!("TOP_PERC_GAIN", subscription.scan_code);
+        assert_eq!("STOCK.EU", subscription.instrument);
+        assert_eq!("STK.EU.IBIS", subscription.location_code);
+        assert_eq!(NO_ROW_NUMBER_SPECIFIED, subscription.number_of_rows);
+        assert_eq!(UNSET_DOUBLE, subscription.above_price);
+        assert_eq!(UNSET_INTEGER, subscription.above_volume);
+
+        let filters: Vec<String> = subscription
+            .filter_options
+            .iter()
+            .map(|option| option.to_string())
+            .collect();
+        assert_eq!(vec!["marketCapAbove1e6=1000;", "priceAbove=10;"], filters);
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_scan_codes() {
+        assert_eq!("HOT_BY_VOLUME", ScanCode::HotByVolume.to_string());
+        assert_eq!(
+            "SCAN_CUSTOM",
+            ScanCode::Other("SCAN_CUSTOM".to_string()).to_string()
+        );
+        assert_eq!(
+            "usdVolumeAbove=1000000",
+            ScanFilter::Other("usdVolumeAbove".to_string(), "1000000".to_string()).to_string()
+        );
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(all(test, feature = "scanner-params"))]
+mod tests {
+    use crate::twsapi::scanner::{
+        ScanCode, ScanFilter, ScanInstrument, ScanLocation, ScannerSubscription,
+    };
+    use crate::twsapi::scanner_parameters::{ScanValidationError, ScannerParameters};
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ScanParameterResponse>
+  <InstrumentList varName="fullInstrumentList">
+    <Instrument>
+      <name>US Stocks</name>
+      <type>STK</type>
+      <filters>PRICE,VOLUME</filters>
+    </Instrument>
+    <Instrument>
+      <name>US Futures</name>
+      <type>FUT.US</type>
+      <filters>VOLUME</filters>
+    </Instrument>
+  </InstrumentList>
+  <LocationTree varName="locationTree">
+    <Location>
+      <displayName>US Stocks</displayName>
+      <locationCode>STK.US</locationCode>
+      <instruments>STK</instruments>
+      <LocationTree>
+        <Location>
+          <displayName>Listed/NASDAQ</displayName>
+          <locationCode>STK.US.MAJOR</locationCode>
+          <instruments>STK</instruments>
+        </Location>
+      </LocationTree>
+    </Location>
+  </LocationTree>
+  <ScanTypeList varName="scanTypeList">
+    <ScanType>
+      <displayName>Top % Gainers</displayName>
+      <scanCode>TOP_PERC_GAIN</scanCode>
+      <instruments>STK,STOCK.EU</instruments>
+    </ScanType>
+    <ScanType>
+      <displayName>Most Active</displayName>
+      <scanCode>MOST_ACTIVE</scanCode>
+      <instruments>STK,FUT.US</instruments>
+    </ScanType>
+  </ScanTypeList>
+  <FilterList varName="filterList">
+    <RangeFilter>
+      <id>PRICE</id>
+      <category>PRICE_VOLUME</category>
+      <AbstractField type="scanner.filter.DoubleField">
+        <code>priceAbove</code>
+        <displayName>Price Above</displayName>
+      </AbstractField>
+      <AbstractField type="scanner.filter.DoubleField">
+        <code>priceBelow</code>
+        <displayName>Price Below</displayName>
+      </AbstractField>
+    </RangeFilter>
+    <SimpleFilter>
+      <id>VOLUME</id>
+      <category>PRICE_VOLUME</category>
+      <AbstractField type="scanner.filter.IntField">
+        <code>volumeAbove</code>
+        <displayName>Volume Above</displayName>
+      </AbstractField>
+    </SimpleFilter>
+  </FilterList>
+</ScanParameterResponse>"#;
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_parse_scanner_parameters() {
+        let parameters = ScannerParameters::parse(XML).unwrap();
+
+        assert_eq!(2, parameters.instruments.len());
+        assert_eq!(vec!["PRICE", "VOLUME"], parameters.instruments[0].filters);
+        assert_eq!(2, parameters.locations.len());
+        let major = parameters.location("STK.US.MAJOR").unwrap();
+        assert_eq!(Some("STK.US".to_string()), major.parent_code);
+        assert_eq!(None, parameters.location("STK.US").unwrap().parent_code);
+        assert_eq!(1, parameters.scan_types_for_instrument("FUT.US").len());
+
+        let price = parameters.filter_for_field("priceBelow").unwrap();
+        assert_eq!("PRICE", price.id);
+        assert!(price.is_range);
+        assert_eq!(2, price.fields.len());
+        assert!(!parameters.filter_for_field("volumeAbove").unwrap().is_range);
+
+        assert!(ScannerParameters::parse("<unclosed>").is_err());
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_validate_scanner_subscription() {
+        let parameters = ScannerParameters::parse(XML).unwrap();
+
+        let valid = ScannerSubscription::builder(ScanCode::TopPercGain)
+            .filter(ScanFilter::PriceAbove(5.0))
+            .build();
+        assert!(parameters.validate(&valid).is_empty());
+
+        let invalid = ScannerSubscription::builder(ScanCode::TopPercGain)
+            .instrument(ScanInstrument::FutureUs)
+            .location(ScanLocation::FutureUs)
+            .filter(ScanFilter::PriceAbove(5.0))
+            .filter(ScanFilter::ImpVolatAbove(0.3))
+            .build();
+        assert_eq!(
+            vec![
+                ScanValidationError::UnknownLocation("FUT.US".to_string()),
+                ScanValidationError::ScanCodeNotForInstrument(
+                    "TOP_PERC_GAIN".to_string(),
+                    "FUT.US".to_string()
+                ),
+                ScanValidationError::FilterNotForInstrument(
+                    "priceAbove".to_string(),
+                    "FUT.US".to_string()
+                ),
+                ScanValidationError::UnknownFilter("impVolatAbove".to_string()),
+            ],
+            parameters.validate(&invalid)
+        );
+    }
+}
@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use crate::twsapi::contract::{Contract, ContractDetails};
+    use crate::twsapi::order::Order;
+    use crate::twsapi::session_gate::{GateDecision, SessionGate, SessionGateMode, SessionHours};
+
+    fn details() -> ContractDetails {
+        let mut details = ContractDetails {
+            time_zone_id: "EST".to_string(),
+            trading_hours: "20240102:0400-20240102:2000;20240103:0400-20240103:2000".to_string(),
+            liquid_hours: "20240102:0930-20240102:1600;20240103:0930-20240103:1600".to_string(),
+            ..Default::default()
+        };
+        details.contract = Contract::stock("AAPL", "SMART", "USD");
+        details.contract.con_id = 265598;
+        details
+    }
+
+    #[test]
+    fn test_session_gate() {
+        let details = details();
+        let mut gate = SessionGate::new();
+        gate.add_contract_details(&details).unwrap();
+        let contract = &details.contract;
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+
+        let open = Utc.with_ymd_and_hms(2024, 1, 2, 15, 0, 0).unwrap();
+        let pre_market = Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap();
+        assert_eq!(GateDecision::Open, gate.check(&open, contract, &order));
+        assert!(matches!(
+            gate.check(&pre_market, contract, &order),
+            GateDecision::Rejected(_)
+        ));
+
+        // Orders meant for outside the regular hours pass
+        let mut outside_rth = order.clone();
+        outside_rth.outside_rth = true;
+        assert_eq!(
+            GateDecision::Open,
+            gate.check(&pre_market, contract, &outside_rth)
+        );
+
+        // Contracts without sessions
+        let unknown = Contract::stock("MSFT", "SMART", "USD");
+        assert!(matches!(
+            gate.check(&open, &unknown, &order),
+            GateDecision::Rejected(_)
+        ));
+        let gate = gate.allow_unknown(true);
+        assert_eq!(GateDecision::Open, gate.check(&open, &unknown, &order));
+
+        // The extended hours
+        let mut gate = SessionGate::new().hours(SessionHours::Trading);
+        gate.add_contract_details(&details).unwrap();
+        assert_eq!(
+            GateDecision::Open,
+            gate.check(&pre_market, contract, &order)
+        );
+    }
+
+    #[test]
+    fn test_session_gate_queue() {
+        let details = details();
+        let mut gate = SessionGate::new().mode(SessionGateMode::Queue);
+        gate.add_contract_details(&details).unwrap();
+        let contract = &details.contract;
+        let order = Order::limit_order("", "BUY", 100.0, 150.0);
+
+        let overnight = Utc.with_ymd_and_hms(2024, 1, 3, 2, 0, 0).unwrap();
+        assert_eq!(
+            GateDecision::Queued {
+                opens: Tz::US__Eastern
+                    .with_ymd_and_hms(2024, 1, 3, 9, 30, 0)
+                    .unwrap()
+            },
+            gate.admit(&overnight, 1, contract, &order)
+        );
+        gate.admit(&overnight, 2, contract, &order);
+        assert_eq!(vec![1, 2], gate.queued());
+        assert!(gate.release(&overnight).is_empty());
+
+        assert!(gate.remove(2));
+        assert!(!gate.remove(2));
+        let open = Utc.with_ymd_and_hms(2024, 1, 3, 15, 0, 0).unwrap();
+        let released = gate.release(&open);
+        assert_eq!(
+            vec![1],
+            released
+                .iter()
+                .map(|queued| queued.order_id)
+                .collect::<Vec<_>>()
+        );
+        assert!(gate.queued().is_empty());
+
+        // No session is known after the last one closes
+        let later = Utc.with_ymd_and_hms(2024, 1, 4, 2, 0, 0).unwrap();
+        assert!(matches!(
+            gate.admit(&later, 3, contract, &order),
+            GateDecision::Rejected(_)
+        ));
+        assert!(gate.queued().is_empty());
+    }
+}
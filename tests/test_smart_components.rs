@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::SmartComponent;
+    use crate::twsapi::smart_components::{SmartComponentMap, SmartComponentRegistry};
+
+    fn sample_map() -> SmartComponentMap {
+        SmartComponentMap::new(vec![
+            SmartComponent::new(1, "ISLAND".to_string(), "Q".to_string()),
+            SmartComponent::new(2, "ARCA".to_string(), "P".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_smart_component_map() {
+        let map = sample_map();
+        assert_eq!(2, map.len());
+        assert_eq!("ARCA", map.get(2).unwrap().exchange);
+        assert_eq!(Some("ISLAND"), map.exchange_for_letter("Q"));
+        assert_eq!(None, map.exchange_for_letter("Z"));
+        assert_eq!(
+            vec!["ARCA".to_string(), "ISLAND".to_string(), "Z".to_string()],
+            map.exchanges_for_letters("PQZ")
+        );
+    }
+
+    #[test]
+    fn test_smart_component_registry_resolves_bbo_exchange() {
+        let mut registry = SmartComponentRegistry::new();
+        registry.set_bbo_exchange(1001, "a6");
+        registry.add_request(7, "a6");
+        registry.complete_request(7, sample_map());
+
+        assert_eq!(Some("a6"), registry.bbo_exchange(1001));
+        assert_eq!(
+            Some("ISLAND"),
+            registry
+                .map_for_ticker(1001)
+                .and_then(|map| map.exchange_for_letter("Q"))
+        );
+
+        registry.remove_ticker(1001);
+        assert!(registry.map_for_ticker(1001).is_none());
+        assert!(registry.map("a6").is_some());
+    }
+}
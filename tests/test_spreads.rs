@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::Action;
+    use crate::twsapi::contract::{Contract, Right, SecType};
+    use crate::twsapi::spreads::{
+        butterfly, calendar, diagonal, iron_condor, straddle, strangle, vertical, ComboBuilder,
+    };
+
+    fn option(con_id: i32, expiry: &str, strike: f64, right: Right) -> Contract {
+        Contract {
+            con_id,
+            ..Contract::option("AAPL", expiry, strike, right)
+        }
+    }
+
+    fn legs(combo: &Contract) -> Vec<(i32, f64, &str)> {
+        combo
+            .combo_legs
+            .iter()
+            .map(|leg| (leg.con_id, leg.ratio, leg.action.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_two_leg_spreads() {
+        let call_200 = option(1, "20250620", 200.0, Right::Call);
+        let call_210 = option(2, "20250620", 210.0, Right::Call);
+        let put_190 = option(3, "20250620", 190.0, Right::Put);
+        let put_200 = option(4, "20250620", 200.0, Right::Put);
+        let call_200_sep = option(5, "20250919", 200.0, Right::Call);
+        let call_210_sep = option(6, "20250919", 210.0, Right::Call);
+
+        let combo = vertical(&call_200, &call_210, "SMART").unwrap();
+        assert_eq!(SecType::Combo, combo.sec_type);
+        assert_eq!("AAPL", combo.symbol);
+        assert_eq!("USD", combo.currency);
+        assert_eq!("SMART", combo.combo_legs[0].exchange);
+        assert_eq!(vec![(1, 1.0, "BUY"), (2, 1.0, "SELL")], legs(&combo));
+        assert!(vertical(&call_200, &put_190, "SMART").is_err());
+        assert!(vertical(&call_200, &call_200_sep, "SMART").is_err());
+
+        let combo = calendar(&call_200, &call_200_sep, "SMART").unwrap();
+        assert_eq!(vec![(1, 1.0, "SELL"), (5, 1.0, "BUY")], legs(&combo));
+        assert!(calendar(&call_200_sep, &call_200, "SMART").is_err());
+
+        let combo = diagonal(&call_200, &call_210_sep, "SMART").unwrap();
+        assert_eq!(vec![(1, 1.0, "SELL"), (6, 1.0, "BUY")], legs(&combo));
+        assert!(diagonal(&call_200, &call_200_sep, "SMART").is_err());
+
+        let combo = straddle(&call_200, &put_200, "SMART").unwrap();
+        assert_eq!(vec![(1, 1.0, "BUY"), (4, 1.0, "BUY")], legs(&combo));
+        assert!(straddle(&call_200, &put_190, "SMART").is_err());
+
+        let combo = strangle(&call_210, &put_190, "SMART").unwrap();
+        assert_eq!(vec![(2, 1.0, "BUY"), (3, 1.0, "BUY")], legs(&combo));
+        assert!(strangle(&call_200, &put_200, "SMART").is_err());
+    }
+
+    #[test]
+    fn test_multi_leg_spreads() {
+        let call_190 = option(1, "20250620", 190.0, Right::Call);
+        let call_200 = option(2, "20250620", 200.0, Right::Call);
+        let call_210 = option(3, "20250620", 210.0, Right::Call);
+        let call_215 = option(4, "20250620", 215.0, Right::Call);
+        let put_180 = option(5, "20250620", 180.0, Right::Put);
+        let put_190 = option(6, "20250620", 190.0, Right::Put);
+
+        let combo = butterfly(&call_190, &call_200, &call_210, "SMART").unwrap();
+        assert_eq!(
+            vec![(1, 1.0, "BUY"), (2, 2.0, "SELL"), (3, 1.0, "BUY")],
+            legs(&combo)
+        );
+        assert!(butterfly(&call_190, &call_200, &call_215, "SMART").is_err());
+        assert!(butterfly(&call_210, &call_200, &call_190, "SMART").is_err());
+
+        let combo = iron_condor(&put_180, &put_190, &call_210, &call_215, "SMART").unwrap();
+        assert_eq!(
+            vec![
+                (5, 1.0, "BUY"),
+                (6, 1.0, "SELL"),
+                (3, 1.0, "SELL"),
+                (4, 1.0, "BUY")
+            ],
+            legs(&combo)
+        );
+        assert!(iron_condor(&put_190, &put_180, &call_210, &call_215, "SMART").is_err());
+        assert!(iron_condor(&call_190, &put_190, &call_210, &call_215, "SMART").is_err());
+    }
+
+    #[test]
+    fn test_combo_builder_checks_legs() {
+        let stock = Contract {
+            con_id: 265598,
+            ..Contract::stock("AAPL", "SMART", "USD")
+        };
+        let call = option(1, "20250620", 200.0, Right::Call);
+        let combo = ComboBuilder::new("SMART")
+            .leg(&stock, Action::Buy, 100.0)
+            .leg(&call, Action::Sell, 1.0)
+            .build()
+            .unwrap();
+        assert_eq!(vec![(265598, 100.0, "BUY"), (1, 1.0, "SELL")], legs(&combo));
+
+        assert!(ComboBuilder::new("SMART").build().is_err());
+        let unresolved = Contract::option("AAPL", "20250620", 200.0, Right::Call);
+        assert!(ComboBuilder::new("SMART")
+            .leg(&unresolved, Action::Buy, 1.0)
+            .build()
+            .is_err());
+        let other = Contract {
+            con_id: 7,
+            ..Contract::option("MSFT", "20250620", 400.0, Right::Call)
+        };
+        assert!(ComboBuilder::new("SMART")
+            .leg(&call, Action::Buy, 1.0)
+            .leg(&other, Action::Sell, 1.0)
+            .build()
+            .is_err());
+        assert!(vertical(&stock, &call, "SMART").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::{RealTimeBar, WhatToShow};
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::quote_cache::Quote;
+    use crate::twsapi::strategy::{
+        OrderEvent, Strategy, StrategyCommand, StrategyContext, StrategyEvent, StrategyRunner,
+    };
+
+    /// Buys on the first bar that closes above the threshold and cancels
+    /// the order if it is not filled by the next timer
+    #[derive(Default)]
+    struct Breakout {
+        threshold: f64,
+        ticks: usize,
+        order_id: Option<i32>,
+        statuses: Vec<String>,
+        stopped: bool,
+    }
+
+    impl Strategy for Breakout {
+        fn on_start(&mut self, context: &mut StrategyContext) {
+            let contract = Contract::stock("AAPL", "SMART", "USD");
+            context.subscribe_market_data(&contract);
+            context.subscribe_bars(&contract, WhatToShow::Trades);
+        }
+
+        fn on_tick(&mut self, _context: &mut StrategyContext, _ticker_id: i32, _quote: &Quote) {
+            self.ticks += 1;
+        }
+
+        fn on_bar(&mut self, context: &mut StrategyContext, _request_id: i32, bar: &RealTimeBar) {
+            if self.order_id.is_none() && bar.close > self.threshold {
+                let contract = Contract::stock("AAPL", "SMART", "USD");
+                let order = Order::limit_order("", "BUY", 100.0, bar.close);
+                self.order_id = Some(context.place_order(&contract, &order));
+            }
+        }
+
+        fn on_order_event(&mut self, context: &mut StrategyContext, event: &OrderEvent) {
+            if let OrderEvent::Status { status, .. } = event {
+                self.statuses.push(status.clone());
+                if status == "Filled" {
+                    context.stop();
+                }
+            }
+        }
+
+        fn on_timer(&mut self, context: &mut StrategyContext) {
+            if let Some(order_id) = self.order_id {
+                context.cancel_order(order_id);
+            }
+        }
+
+        fn on_stop(&mut self, _context: &mut StrategyContext) {
+            self.stopped = true;
+        }
+    }
+
+    fn bar(close: f64) -> StrategyEvent {
+        StrategyEvent::Bar {
+            request_id: 2,
+            bar: RealTimeBar {
+                close,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_context_ids() {
+        let mut context = StrategyContext::new(100, 1);
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        assert_eq!(1, context.subscribe_market_data(&contract));
+        assert_eq!(2, context.subscribe_bars(&contract, WhatToShow::Midpoint));
+        assert_eq!(100, context.place_order(&contract, &Order::default()));
+        assert_eq!(101, context.place_order(&contract, &Order::default()));
+        context.unsubscribe(1);
+
+        let commands = context.take_commands();
+        assert_eq!(5, commands.len());
+        match &commands[3] {
+            StrategyCommand::PlaceOrder {
+                order_id, order, ..
+            } => {
+                assert_eq!(101, *order_id);
+                assert_eq!(101, order.order_id);
+            }
+            command => panic!("unexpected command {:?}", command),
+        }
+        assert!(matches!(commands[4], StrategyCommand::Unsubscribe(1)));
+        assert!(context.take_commands().is_empty());
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let strategy = Breakout {
+            threshold: 150.0,
+            ..Default::default()
+        };
+        let mut runner = StrategyRunner::new(strategy, StrategyContext::new(10, 1));
+        runner.start_strategy();
+        runner.start_strategy();
+        assert_eq!(2, runner.context().take_commands().len());
+
+        runner.dispatch(&StrategyEvent::Tick {
+            ticker_id: 1,
+            quote: Quote::default(),
+        });
+        runner.dispatch(&bar(149.0));
+        assert!(runner.context().take_commands().is_empty());
+        runner.dispatch(&bar(151.0));
+        runner.dispatch(&bar(152.0));
+        let commands = runner.context().take_commands();
+        assert_eq!(1, commands.len());
+        assert!(matches!(
+            &commands[0],
+            StrategyCommand::PlaceOrder { order_id: 10, order, .. } if order.lmt_price == 151.0
+        ));
+
+        runner.dispatch(&StrategyEvent::Timer);
+        assert!(matches!(
+            runner.context().take_commands()[..],
+            [StrategyCommand::CancelOrder(10)]
+        ));
+
+        for status in ["Submitted", "Filled"] {
+            runner.dispatch(&StrategyEvent::Order(OrderEvent::Status {
+                order_id: 10,
+                status: status.to_string(),
+            }));
+        }
+        assert!(runner.context().is_stopping());
+        let strategy = runner.into_strategy();
+        assert_eq!(1, strategy.ticks);
+        assert_eq!(vec!["Submitted", "Filled"], strategy.statuses);
+        assert!(!strategy.stopped);
+    }
+}
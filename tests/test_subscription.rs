@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use crate::twsapi::common::RealTimeBar;
+    use crate::twsapi::subscription::{StreamRegistry, Subscription};
+
+    #[test]
+    fn test_subscription_receives_and_cancels_on_drop() {
+        let mut registry = StreamRegistry::new();
+        let receiver = registry.real_time_bars.add(7);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled_clone = cancelled.clone();
+        let subscription = Subscription::new(7, receiver, move || {
+            cancelled_clone.store(true, Ordering::Release)
+        });
+
+        let bar = RealTimeBar {
+            close: 10.5,
+            ..Default::default()
+        };
+        assert!(registry.real_time_bars.send(7, bar).is_ok());
+        assert_eq!(10.5, subscription.try_recv().unwrap().close);
+
+        // Events of other requests are handed back for the wrapper
+        assert!(registry
+            .real_time_bars
+            .send(8, RealTimeBar::default())
+            .is_err());
+
+        assert_eq!(7, subscription.request_id());
+        assert!(!cancelled.load(Ordering::Acquire));
+        drop(subscription);
+        assert!(cancelled.load(Ordering::Acquire));
+
+        // The receiver is gone, so the event is swallowed and the sender
+        // removed
+        assert!(registry
+            .real_time_bars
+            .send(7, RealTimeBar::default())
+            .is_ok());
+        assert!(registry
+            .real_time_bars
+            .send(7, RealTimeBar::default())
+            .is_err());
+    }
+}
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::twsapi::common::{CommissionReport, UNSET_DOUBLE};
+    use crate::twsapi::contract::{Contract, Right};
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::trade_report::TradeReport;
+
+    fn execution(exec_id: &str, order_id: i32, side: &str, shares: f64, price: f64) -> Execution {
+        Execution {
+            exec_id: exec_id.to_string(),
+            order_id,
+            side: side.to_string(),
+            shares,
+            price,
+            order_ref: if order_id < 10 { "momentum" } else { "" }.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn commission(exec_id: &str, commission: f64, realized_pnl: f64) -> CommissionReport {
+        CommissionReport {
+            exec_id: exec_id.to_string(),
+            commission,
+            currency: "USD".to_string(),
+            realized_pnl,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trade_report() {
+        let stock = Contract::stock("AAPL", "SMART", "USD");
+        let call = Contract::option("AAPL", "20250620", 200.0, Right::Call);
+        let executions = vec![
+            (stock.clone(), execution("1", 1, "BOT", 100.0, 150.10)),
+            (stock.clone(), execution("1", 1, "BOT", 100.0, 150.10)),
+            (stock.clone(), execution("2", 2, "SLD", 100.0, 151.00)),
+            (call, execution("3", 11, "BOT", 2.0, 3.50)),
+        ];
+        let commissions = vec![
+            commission("1", 1.0, UNSET_DOUBLE),
+            commission("2", 1.0, 88.0),
+            commission("3", 1.3, UNSET_DOUBLE),
+        ];
+        let arrival_prices: HashMap<i32, f64> = [(1, 150.0), (2, 151.05)].into_iter().collect();
+
+        let report = TradeReport::new(&executions, &commissions, &arrival_prices);
+        assert_eq!(1, report.by_symbol.len());
+        let aapl = report.symbol("AAPL").next().unwrap();
+        assert_eq!(3, aapl.executions);
+        assert_eq!(102.0, aapl.bought);
+        assert_eq!(100.0, aapl.sold);
+        assert_eq!(202.0, aapl.volume());
+        // The option counts with its multiplier
+        assert!((aapl.notional - (15_010.0 + 15_100.0 + 700.0)).abs() < 1e-6);
+        assert_eq!(88.0, aapl.realized_pnl);
+        assert!((aapl.commissions - 3.3).abs() < 1e-9);
+        assert!((aapl.net_pnl() - 84.7).abs() < 1e-9);
+        // Bought 0.10 above and sold 0.05 below the arrival prices
+        assert!((aapl.slippage - 15.0).abs() < 1e-6);
+        assert!((aapl.slippage_bps().unwrap() - 15.0 / 30_105.0 * 10_000.0).abs() < 1e-9);
+
+        assert_eq!(2, report.by_strategy.len());
+        assert_eq!("", report.by_strategy[0].name);
+        assert!(report.by_strategy[0].slippage_bps().is_none());
+        let momentum = report.strategy("momentum").next().unwrap();
+        assert_eq!(2, momentum.executions);
+        assert_eq!(86.0, momentum.net_pnl());
+
+        #[cfg(feature = "serde")]
+        {
+            let json = report.to_json().unwrap();
+            let parsed: TradeReport = serde_json::from_str(&json).unwrap();
+            assert_eq!(report, parsed);
+        }
+    }
+}
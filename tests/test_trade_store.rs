@@ -0,0 +1,137 @@
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use crate::twsapi::common::CommissionReport;
+    use crate::twsapi::contract::Contract;
+    use crate::twsapi::execution::Execution;
+    use crate::twsapi::order::Order;
+    use crate::twsapi::trade_store::{OrderStatusRecord, TradeStore, SCHEMA_VERSION};
+
+    fn execution(exec_id: &str, time: &str) -> Execution {
+        Execution {
+            exec_id: exec_id.to_string(),
+            time: time.to_string(),
+            acct_number: "DU1".to_string(),
+            exchange: "ISLAND".to_string(),
+            side: "BOT".to_string(),
+            shares: 100.0,
+            price: 150.25,
+            order_id: 7,
+            order_ref: "momentum".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trade_store_executions() -> rusqlite::Result<()> {
+        let mut store = TradeStore::open_in_memory()?;
+        store.set_time_zone(Tz::US__Eastern);
+        let version: i32 = store
+            .connection()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(SCHEMA_VERSION, version);
+
+        let mut contract = Contract::stock("AAPL", "SMART", "USD");
+        contract.con_id = 265598;
+        assert!(store.record_execution(&contract, &execution("1", "20230103  09:30:00"))?);
+        assert!(!store.record_execution(&contract, &execution("1", "20230103  09:30:00"))?);
+        assert!(store.record_execution(&contract, &execution("2", "20230103 10:00:00 UTC"))?);
+        assert!(store.record_execution(&contract, &execution("3", "20230104 09:30:00"))?);
+
+        let (stored_contract, stored) = store.execution("1")?.unwrap();
+        assert_eq!(265598, stored_contract.con_id);
+        assert_eq!("momentum", stored.order_ref);
+        assert_eq!(150.25, stored.price);
+        assert!(store.execution("4")?.is_none());
+
+        let from = Utc.with_ymd_and_hms(2023, 1, 3, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2023, 1, 4, 0, 0, 0).unwrap();
+        let executions = store.executions_between(&from, &to)?;
+        // 10:00 UTC comes before 09:30 US/Eastern
+        let exec_ids: Vec<&str> = executions
+            .iter()
+            .map(|(_, execution)| execution.exec_id.as_str())
+            .collect();
+        assert_eq!(vec!["2", "1"], exec_ids);
+
+        let mut report = CommissionReport {
+            exec_id: "1".to_string(),
+            commission: 1.0,
+            currency: "USD".to_string(),
+            ..Default::default()
+        };
+        store.record_commission_report(&report)?;
+        report.commission = 1.5;
+        store.record_commission_report(&report)?;
+        assert_eq!(1.5, store.commission_report("1")?.unwrap().commission);
+        assert_eq!(1, store.commission_reports_between(&from, &to)?.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trade_store_orders() -> rusqlite::Result<()> {
+        let store = TradeStore::open_in_memory()?;
+
+        let contract = Contract::stock("AAPL", "SMART", "USD");
+        let mut order = Order::limit_order("DU1", "BUY", 100.0, 150.0);
+        order.order_id = 7;
+        store.record_order(&contract, &order)?;
+        order.lmt_price = 151.0;
+        store.record_order(&contract, &order)?;
+        let (_, stored) = store.order(7)?.unwrap();
+        assert_eq!(151.0, stored.lmt_price);
+
+        let status = |status: &str, filled: f64| {
+            OrderStatusRecord::new(
+                7,
+                status,
+                filled,
+                100.0 - filled,
+                0.0,
+                1,
+                0,
+                0.0,
+                0,
+                "",
+                0.0,
+            )
+        };
+        assert!(store.record_order_status(&status("Submitted", 0.0))?);
+        assert!(!store.record_order_status(&status("Submitted", 0.0))?);
+        assert!(store.record_order_status(&status("Submitted", 40.0))?);
+        assert!(store.record_order_status(&status("Filled", 100.0))?);
+
+        let states = store.order_states(7)?;
+        assert_eq!(3, states.len());
+        assert_eq!(40.0, states[1].filled);
+        assert_eq!("Filled", store.last_order_state(7)?.unwrap().status);
+
+        store.record_order_status(&OrderStatusRecord::new(
+            8,
+            "Cancelled",
+            0.0,
+            10.0,
+            0.0,
+            2,
+            0,
+            0.0,
+            0,
+            "",
+            0.0,
+        ))?;
+        let last: Vec<(i32, String)> = store
+            .last_order_states()?
+            .into_iter()
+            .map(|record| (record.order_id, record.status))
+            .collect();
+        assert_eq!(
+            vec![(7, "Filled".to_string()), (8, "Cancelled".to_string())],
+            last
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::Tz;
+
+    use crate::twsapi::contract::ContractDetails;
+    use crate::twsapi::trading_hours::{
+        next_session, parse_time_zone_id, parse_trading_hours, session_at, Session,
+    };
+
+    #[test]
+    fn test_parse_time_zone_id() {
+        assert_eq!(Some(Tz::US__Eastern), parse_time_zone_id("US/Eastern"));
+        assert_eq!(Some(Tz::US__Eastern), parse_time_zone_id("EST"));
+        assert_eq!(
+            Some(Tz::US__Central),
+            parse_time_zone_id("CST (Central Standard Time)")
+        );
+        assert_eq!(Some(Tz::MET), parse_time_zone_id("MET"));
+        assert_eq!(None, parse_time_zone_id("Mars/Olympus"));
+        assert_eq!(None, parse_time_zone_id(""));
+    }
+
+    #[test]
+    fn test_parse_trading_hours() {
+        let eastern = Tz::US__Eastern;
+        let sessions = parse_trading_hours(
+            "20240103:0930-20240103:1600;20240101:CLOSED;20240102:0930-20240102:1600",
+            &eastern,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                Session::new(
+                    eastern.with_ymd_and_hms(2024, 1, 2, 9, 30, 0).unwrap(),
+                    eastern.with_ymd_and_hms(2024, 1, 2, 16, 0, 0).unwrap()
+                ),
+                Session::new(
+                    eastern.with_ymd_and_hms(2024, 1, 3, 9, 30, 0).unwrap(),
+                    eastern.with_ymd_and_hms(2024, 1, 3, 16, 0, 0).unwrap()
+                ),
+            ],
+            sessions
+        );
+        assert_eq!(chrono::Duration::minutes(390), sessions[0].duration());
+        assert!(sessions[0].contains(&Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap()));
+        assert!(!sessions[0].contains(&Utc.with_ymd_and_hms(2024, 1, 2, 21, 0, 0).unwrap()));
+
+        // Futures sessions span midnight
+        let central = Tz::US__Central;
+        let sessions = parse_trading_hours("20240102:1700-20240103:1600", &central).unwrap();
+        assert_eq!(
+            central.with_ymd_and_hms(2024, 1, 3, 16, 0, 0).unwrap(),
+            sessions[0].close
+        );
+
+        // The older format without dates of the close
+        let sessions = parse_trading_hours("20090507:0700-1830,1830-0200", &central).unwrap();
+        assert_eq!(2, sessions.len());
+        assert_eq!(
+            central.with_ymd_and_hms(2009, 5, 8, 2, 0, 0).unwrap(),
+            sessions[1].close
+        );
+        let sessions = parse_trading_hours("20090507:0000-2400", &central).unwrap();
+        assert_eq!(chrono::Duration::days(1), sessions[0].duration());
+
+        assert!(parse_trading_hours("20240102:0930", &eastern).is_err());
+        assert!(parse_trading_hours("2024-01-02:0930-1600", &eastern).is_err());
+        assert_eq!(Ok(vec![]), parse_trading_hours("", &eastern));
+    }
+
+    #[test]
+    fn test_contract_details_sessions() {
+        let details = ContractDetails {
+            time_zone_id: "EST".to_string(),
+            trading_hours: "20240102:0400-20240102:2000".to_string(),
+            liquid_hours: "20240102:0930-20240102:1600".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(Some(Tz::US__Eastern), details.time_zone());
+        assert_eq!(1, details.trading_sessions().unwrap().len());
+        assert_eq!(
+            Tz::US__Eastern
+                .with_ymd_and_hms(2024, 1, 2, 9, 30, 0)
+                .unwrap(),
+            details.liquid_sessions().unwrap()[0].open
+        );
+
+        #[cfg(feature = "serde")]
+        {
+            let session = &details.liquid_sessions().unwrap()[0];
+            let json = serde_json::to_string(session).unwrap();
+            assert_eq!(*session, serde_json::from_str::<Session>(&json).unwrap());
+        }
+
+        let details = ContractDetails {
+            time_zone_id: "Nowhere".to_string(),
+            ..details
+        };
+        assert!(details.trading_sessions().is_err());
+    }
+
+    #[test]
+    fn test_market_open_queries() {
+        let details = ContractDetails {
+            time_zone_id: "US/Eastern".to_string(),
+            trading_hours: "20240105:0930-20240105:1600;20240106:CLOSED;20240107:CLOSED;\
+                            20240108:0930-20240108:1600"
+                .to_string(),
+            ..Default::default()
+        };
+        let eastern = Tz::US__Eastern;
+        let friday_noon = Utc.with_ymd_and_hms(2024, 1, 5, 17, 0, 0).unwrap();
+        let saturday = eastern.with_ymd_and_hms(2024, 1, 6, 12, 0, 0).unwrap();
+        let monday_open = eastern.with_ymd_and_hms(2024, 1, 8, 9, 30, 0).unwrap();
+
+        assert!(details.is_open_at(&friday_noon));
+        assert!(!details.is_open_at(&saturday));
+        assert!(details.is_open_at(&monday_open));
+
+        assert_eq!(Some(monday_open), details.next_open(&friday_noon));
+        assert_eq!(Some(monday_open), details.next_open(&saturday));
+        assert_eq!(
+            Some(eastern.with_ymd_and_hms(2024, 1, 5, 16, 0, 0).unwrap()),
+            details.next_close(&friday_noon)
+        );
+        assert_eq!(
+            Some(eastern.with_ymd_and_hms(2024, 1, 8, 16, 0, 0).unwrap()),
+            details.next_close(&saturday)
+        );
+
+        assert_eq!(
+            Some(chrono::Duration::hours(4)),
+            details.time_until_close(&friday_noon)
+        );
+        assert_eq!(None, details.time_until_close(&saturday));
+        assert_eq!(None, details.next_open(&monday_open));
+
+        let sessions = details.trading_sessions().unwrap();
+        assert_eq!(Some(&sessions[0]), session_at(&sessions, &friday_noon));
+        assert_eq!(Some(&sessions[1]), next_session(&sessions, &friday_noon));
+
+        let details = ContractDetails {
+            trading_hours: "invalid".to_string(),
+            ..details
+        };
+        assert!(!details.is_open_at(&friday_noon));
+        assert_eq!(None, details.next_close(&friday_noon));
+    }
+}
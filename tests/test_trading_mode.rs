@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use crate::twsapi::common::MarketDataTypeEnum;
+    use crate::twsapi::trading_mode::{
+        TradingMode, GATEWAY_LIVE_PORT, GATEWAY_PAPER_PORT, TWS_LIVE_PORT, TWS_PAPER_PORT,
+    };
+
+    fn accounts(accounts: &[&str]) -> Vec<String> {
+        accounts.iter().map(|account| account.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_port() {
+        assert_eq!(TradingMode::Paper, TradingMode::from_port(TWS_PAPER_PORT));
+        assert_eq!(
+            TradingMode::Paper,
+            TradingMode::from_port(GATEWAY_PAPER_PORT)
+        );
+        assert_eq!(TradingMode::Live, TradingMode::from_port(TWS_LIVE_PORT));
+        assert_eq!(TradingMode::Live, TradingMode::from_port(GATEWAY_LIVE_PORT));
+        assert_eq!(TradingMode::Unknown, TradingMode::from_port(4003));
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            TradingMode::Paper,
+            TradingMode::from_accounts(&accounts(&["DU1234567"]))
+        );
+        assert_eq!(
+            TradingMode::Paper,
+            TradingMode::from_accounts(&accounts(&["DF1234567", "DU1234568"]))
+        );
+        assert_eq!(
+            TradingMode::Live,
+            TradingMode::from_accounts(&accounts(&["U1234567"]))
+        );
+        assert_eq!(TradingMode::Unknown, TradingMode::from_accounts(&[]));
+
+        // The accounts win over the port
+        assert_eq!(
+            TradingMode::Live,
+            TradingMode::detect(TWS_PAPER_PORT, &accounts(&["U1234567"]))
+        );
+        assert_eq!(TradingMode::Paper, TradingMode::detect(TWS_PAPER_PORT, &[]));
+        assert_eq!(TradingMode::Unknown, TradingMode::detect(7500, &[]));
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert!(TradingMode::Paper.is_paper());
+        assert!(!TradingMode::Unknown.is_live());
+        assert_eq!(
+            MarketDataTypeEnum::Delayed as i32,
+            TradingMode::Paper.default_market_data_type() as i32
+        );
+        assert_eq!(
+            MarketDataTypeEnum::Realtime as i32,
+            TradingMode::Live.default_market_data_type() as i32
+        );
+        assert_eq!("paper", TradingMode::Paper.to_string());
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use crate::twsapi::common::TickType;
+    use crate::twsapi::contract::{Contract, Right};
+    use crate::twsapi::vol_surface::VolSurface;
+
+    fn option(expiry: &str, strike: f64, right: Right) -> Contract {
+        Contract::option("SPY", expiry, strike, right)
+    }
+
+    #[test]
+    fn test_vol_surface() {
+        let time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let mut surface = VolSurface::new("SPY");
+        assert!(surface.track(1, &option("20240119", 450.0, Right::Call)));
+        assert!(surface.track(2, &option("20240119", 440.0, Right::Put)));
+        assert!(surface.track(3, &option("20240216", 450.0, Right::Call)));
+        assert!(!surface.track(4, &Contract::stock("SPY", "SMART", "USD")));
+
+        // Only the model computations with an implied volatility count
+        assert!(!surface.update(1, TickType::BidOptionComputation, 0.2, 0.5, 450.0, time));
+        assert!(!surface.update(1, TickType::ModelOption, -2.0, 0.5, 450.0, time));
+        assert!(!surface.update(9, TickType::ModelOption, 0.2, 0.5, 450.0, time));
+        assert!(surface.update(3, TickType::ModelOption, 0.18, 0.52, 451.0, time));
+        assert!(surface.update(1, TickType::ModelOption, 0.2, 0.5, 450.5, time));
+        assert!(surface.update(2, TickType::DelayedModelOption, 0.22, f64::MAX, 450.5, time));
+        assert_eq!(3, surface.len());
+        let january = NaiveDate::from_ymd_opt(2024, 1, 19).unwrap();
+        let point = surface.point(january, 440.0, Right::Put).unwrap();
+        assert_eq!(0.22, point.implied_vol);
+        assert_eq!(None, point.delta);
+
+        let snapshot = surface.snapshot(time);
+        assert_eq!("SPY", snapshot.underlying);
+        assert_eq!(Some(450.5), snapshot.spot);
+        let strikes: Vec<f64> = snapshot.points.iter().map(|point| point.strike).collect();
+        assert_eq!(vec![440.0, 450.0, 450.0], strikes);
+        assert_eq!(2, snapshot.expiries().len());
+        assert_eq!(2, snapshot.smile(january).count());
+
+        // The spot set overrides the price of the computations
+        surface.set_spot(452.0);
+        assert_eq!(Some(452.0), surface.snapshot(time).spot);
+        surface.clear();
+        assert!(surface.is_empty());
+        assert_eq!(None, surface.snapshot(time).spot);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_vol_surface_json() -> std::io::Result<()> {
+        let time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let mut surface = VolSurface::new("SPY");
+        surface.track(1, &option("20240119", 450.0, Right::Call));
+        surface.update(1, TickType::ModelOption, 0.2, 0.5, 450.5, time);
+        let snapshot = surface.snapshot(time);
+
+        let path =
+            std::env::temp_dir().join(format!("twsapi-vol-surface-{}.json", std::process::id()));
+        snapshot.write_json(&path)?;
+        let read = crate::twsapi::vol_surface::VolSurfaceSnapshot::read_json(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(snapshot, read);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveTime};
+
+    use crate::twsapi::volume_profile::{VolumeAtPrice, VolumeProfile};
+
+    #[test]
+    fn test_volume_profile() {
+        let mut profile = VolumeProfile::new(0.25);
+        assert_eq!(None, profile.point_of_control());
+        assert_eq!(None, profile.value_area(0.7));
+
+        profile.update_trade(0, 100.0, 100.0);
+        profile.update_trade(1, 100.1, 400.0);
+        profile.update_trade(2, 100.25, 200.0);
+        profile.update_trade(3, 100.5, 300.0);
+        profile.update_trade(4, 100.75, 50.0);
+        profile.update_trade(5, 99.75, 0.0);
+
+        assert_eq!(1050.0, profile.total_volume());
+        assert_eq!(500.0, profile.volume_at(100.0));
+        assert_eq!(Some(100.0), profile.point_of_control());
+        assert_eq!(
+            VolumeAtPrice {
+                price: 100.0,
+                volume: 500.0,
+                count: 2
+            },
+            profile.levels()[0]
+        );
+        assert_eq!(4, profile.levels().len());
+        // 500 at the POC, then 200 above, then 300 above
+        assert_eq!(Some((100.0, 100.5)), profile.value_area(0.7));
+        assert_eq!(Some((100.0, 100.0)), profile.value_area(0.0));
+    }
+
+    #[test]
+    fn test_volume_profile_session_reset() {
+        // Sessions start at 09:30 New York winter time, i.e. 14:30 UTC
+        let mut profile = VolumeProfile::new(1.0).with_session_start(
+            NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+            FixedOffset::west_opt(5 * 3600).unwrap(),
+        );
+
+        // 2023-01-03 15:00 UTC and 2023-01-04 14:00 UTC are the same session
+        profile.update_trade(1672758000, 10.0, 100.0);
+        profile.update_trade(1672840800, 11.0, 100.0);
+        assert_eq!(200.0, profile.total_volume());
+
+        // 2023-01-04 14:30 UTC starts the next session
+        profile.update_trade(1672842600, 12.0, 50.0);
+        assert_eq!(50.0, profile.total_volume());
+        assert_eq!(Some(12.0), profile.point_of_control());
+    }
+}
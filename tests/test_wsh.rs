@@ -0,0 +1,51 @@
+#[cfg(all(test, feature = "wsh"))]
+mod tests {
+    use crate::twsapi::wsh::{WshEvent, WshEventData};
+    use chrono::NaiveDate;
+    use serde_json::json;
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_parse_wsh_events() {
+        let json = r#"[
+            {"event_type": "wshe_ed", "conid": 8314,
+             "data": {"earnings_date": "20230125", "time_of_day": "AMC"}},
+            {"event_type": "wshe_div", "index_date": "2023-02-10", "data": {"amount": 1.65}}
+        ]"#;
+
+        let events = WshEvent::parse_all(json).unwrap();
+        assert_eq!(2, events.len());
+        assert_eq!("wshe_ed", events[0].event_type);
+        assert_eq!(Some(8314), events[0].con_id);
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2023, 1, 25),
+            events[0].date("earnings_date")
+        );
+        assert_eq!(Some("AMC".to_string()), events[0].field_str("time_of_day"));
+        assert_eq!(None, events[1].con_id);
+        assert_eq!(Some("1.65".to_string()), events[1].field_str("amount"));
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2023, 2, 10),
+            events[1].date("index_date")
+        );
+
+        let single = WshEvent::parse_all(r#"{"event_type": "wshe_ed"}"#).unwrap();
+        assert_eq!(1, single.len());
+        assert!(WshEvent::parse_all("not json").is_err());
+    }
+
+    //------------------------------------------------------------------------------------------------
+    #[test]
+    fn test_wsh_event_data_params() {
+        let mut wsh_event_data = WshEventData::with_filter(json!({"country": "All"}));
+        assert!(wsh_event_data.uses_filters());
+        assert!(!wsh_event_data.uses_date_range());
+        assert_eq!(r#"{"country":"All"}"#, wsh_event_data.filter_param());
+
+        wsh_event_data.end_date = NaiveDate::from_ymd_opt(2023, 12, 31);
+        assert!(wsh_event_data.uses_date_range());
+        assert_eq!("", wsh_event_data.start_date_param());
+        assert_eq!("20231231", wsh_event_data.end_date_param());
+        assert!(!WshEventData::new(8314).uses_filters());
+    }
+}